@@ -0,0 +1,130 @@
+// In the EU we may only transmit on 868MHz for a share of the time,
+// 1% or 10% depending on the sub-band, averaged over an hour. The E32
+// doesn't keep track, so we estimate the time on air of everything we
+// send from the air rate and sum it over a sliding window. Over the
+// cap the observables polls are held back until we're below again.
+// Commands of the operator still go out, they count all the same.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::clock::Instant;
+
+// Over which the regulations average
+pub const WINDOW: Duration = Duration::from_secs(60 * 60);
+// Of the E32 as configured in default_parameters
+const AIR_RATE_BPS: f64 = 9600.0;
+// The forward error correction sends 5 bits for 4
+const CODE_RATE: f64 = 4.0 / 5.0;
+// Preamble and header of each packet, estimated
+const PACKET_OVERHEAD: Duration = Duration::from_millis(12);
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DutyCycleStatus {
+    // Of the window spent transmitting
+    pub usage: f64,
+    pub cap: Option<f64>,
+    // The polls are held back
+    pub throttled: bool,
+}
+
+#[derive(Debug)]
+pub struct DutyCycle {
+    // None transmits as much as we like
+    cap: Option<f64>,
+    window: Duration,
+    transmissions: VecDeque<(Instant, Duration)>,
+    on_air: Duration,
+}
+
+pub fn airtime(bytes: usize) -> Duration {
+    PACKET_OVERHEAD + Duration::from_secs_f64(bytes as f64 * 8.0 / (AIR_RATE_BPS * CODE_RATE))
+}
+
+impl DutyCycle {
+    pub fn new(cap: Option<f64>, window: Duration) -> Self {
+        Self {
+            cap,
+            window,
+            transmissions: VecDeque::new(),
+            on_air: Duration::ZERO,
+        }
+    }
+
+    pub fn set_cap(&mut self, cap: Option<f64>) {
+        self.cap = cap;
+    }
+
+    pub fn record(&mut self, now: Instant, bytes: usize) {
+        let airtime = airtime(bytes);
+        self.transmissions.push_back((now, airtime));
+        self.on_air += airtime;
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some((sent, airtime)) = self.transmissions.front() {
+            if now.duration_since(*sent) < self.window {
+                break;
+            }
+            self.on_air -= *airtime;
+            self.transmissions.pop_front();
+        }
+    }
+
+    pub fn usage(&mut self, now: Instant) -> f64 {
+        self.expire(now);
+        self.on_air.as_secs_f64() / self.window.as_secs_f64()
+    }
+
+    // Only asked for traffic that can wait
+    pub fn allows_polling(&mut self, now: Instant) -> bool {
+        match self.cap {
+            Some(cap) => self.usage(now) < cap,
+            None => true,
+        }
+    }
+
+    pub fn status(&mut self, now: Instant) -> DutyCycleStatus {
+        DutyCycleStatus {
+            usage: self.usage(now),
+            cap: self.cap,
+            throttled: !self.allows_polling(now),
+        }
+    }
+}
+
+impl Default for DutyCycle {
+    fn default() -> Self {
+        Self::new(None, WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_airtime_of_a_poll() {
+        // $LNCCMD,123,RQA,OBG,1*CS plus line end
+        let poll = airtime(26);
+        assert!(poll > Duration::from_millis(35) && poll < Duration::from_millis(45));
+    }
+
+    #[test]
+    fn test_polls_held_back_over_the_cap() {
+        let mut duty_cycle = DutyCycle::new(Some(0.1), Duration::from_secs(10));
+        let start = Instant::now();
+        assert!(duty_cycle.allows_polling(start));
+        // 30 polls are over a second on air
+        for _ in 0..30 {
+            duty_cycle.record(start, 26);
+        }
+        assert!(!duty_cycle.allows_polling(start));
+        assert!(duty_cycle.status(start).throttled);
+        let later = start + Duration::from_secs(10);
+        assert!(duty_cycle.allows_polling(later));
+        assert_eq!(duty_cycle.usage(later), 0.0);
+        duty_cycle.record(later, 26);
+        duty_cycle.set_cap(None);
+        assert!(!duty_cycle.status(later).throttled);
+    }
+}
@@ -0,0 +1,179 @@
+// Operators see and hear what the sensors don't. A mark puts the
+// moment of the key press into the journal and onto the plots, to
+// correlate it with the data afterwards. If labels are configured,
+// one can be picked to go with it, the mark keeps the time of the
+// key press anyway.
+pub const DEFAULT_LABELS: [&str; 4] = [
+    "anomaly heard",
+    "valve opened",
+    "venting seen",
+    "flame visible",
+];
+// Of a label, to fit the plots
+pub const MAX_LABEL: usize = 24;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    // Seconds into the session
+    pub elapsed: f64,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Picking {
+    elapsed: f64,
+    selected: usize,
+}
+
+#[derive(Debug)]
+pub struct Annotator {
+    labels: Vec<String>,
+    picking: Option<Picking>,
+}
+
+impl Annotation {
+    pub fn text(&self) -> String {
+        match &self.label {
+            Some(label) => format!("Mark: {}", label),
+            None => "Mark".into(),
+        }
+    }
+}
+
+impl Default for Annotator {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_LABELS
+                .iter()
+                .map(|label| label.to_string())
+                .collect(),
+        )
+    }
+}
+
+impl Annotator {
+    pub fn new(labels: Vec<String>) -> Self {
+        Self {
+            labels,
+            picking: None,
+        }
+    }
+
+    // A pending mark picks from the new labels, or is journaled right
+    // away without them
+    pub fn set_labels(&mut self, labels: Vec<String>) -> Option<Annotation> {
+        self.labels = labels;
+        if self.labels.is_empty() {
+            return self.skip();
+        }
+        if let Some(picking) = &mut self.picking {
+            picking.selected = 0;
+        }
+        None
+    }
+
+    // The annotation to journal right away, if there's no label to
+    // pick, or a previous mark is still waiting for one
+    pub fn mark(&mut self, elapsed: f64) -> Option<Annotation> {
+        let unlabeled = self.skip();
+        if self.labels.is_empty() {
+            return Some(Annotation {
+                elapsed,
+                label: None,
+            });
+        }
+        self.picking = Some(Picking {
+            elapsed,
+            selected: 0,
+        });
+        unlabeled
+    }
+
+    pub fn is_picking(&self) -> bool {
+        self.picking.is_some()
+    }
+
+    pub fn step(&mut self, steps: i64) {
+        let count = self.labels.len() as i64;
+        if let Some(picking) = &mut self.picking {
+            picking.selected = (picking.selected as i64 + steps).rem_euclid(count) as usize;
+        }
+    }
+
+    pub fn confirm(&mut self) -> Option<Annotation> {
+        self.picking.take().map(|picking| Annotation {
+            elapsed: picking.elapsed,
+            label: self.labels.get(picking.selected).cloned(),
+        })
+    }
+
+    // Still marked, just without a label
+    pub fn skip(&mut self) -> Option<Annotation> {
+        self.picking.take().map(|picking| Annotation {
+            elapsed: picking.elapsed,
+            label: None,
+        })
+    }
+
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.picking.map(|picking| picking.selected)
+    }
+
+    // Seconds into the session of the mark waiting for its label
+    pub fn pending(&self) -> Option<f64> {
+        self.picking.map(|picking| picking.elapsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_is_picked_for_the_key_press() {
+        let mut annotator = Annotator::default();
+        assert_eq!(annotator.mark(12.5), None);
+        annotator.step(-1);
+        assert_eq!(
+            annotator.confirm(),
+            Some(Annotation {
+                elapsed: 12.5,
+                label: Some("flame visible".into())
+            })
+        );
+        assert!(!annotator.is_picking());
+        // Marking again before picking keeps the first one
+        annotator.mark(13.0);
+        assert_eq!(annotator.mark(14.0).unwrap().text(), "Mark");
+        assert_eq!(annotator.pending(), Some(14.0));
+    }
+
+    #[test]
+    fn test_without_labels_marks_right_away() {
+        let mut annotator = Annotator::new(vec![]);
+        assert_eq!(annotator.mark(3.0).unwrap().elapsed, 3.0);
+        assert!(!annotator.is_picking());
+    }
+
+    #[test]
+    fn test_pending_mark_survives_new_labels() {
+        let mut annotator = Annotator::default();
+        annotator.mark(7.0);
+        annotator.step(2);
+        assert_eq!(annotator.set_labels(vec!["smoke".into()]), None);
+        assert_eq!(annotator.selected(), Some(0));
+        assert_eq!(annotator.confirm().unwrap().text(), "Mark: smoke");
+        annotator.mark(8.0);
+        assert_eq!(
+            annotator.set_labels(vec![]),
+            Some(Annotation {
+                elapsed: 8.0,
+                label: None
+            })
+        );
+    }
+}
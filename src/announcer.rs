@@ -0,0 +1,369 @@
+// Spoken countdown through the speakers of the ground station: the
+// T-minus marks up to the opening of the launch window, holds and
+// the confirmed ignition. Each kind is enabled on its own, so the
+// range announcer isn't talked over for what they announce anyway.
+// Pre-recorded samples are played if there are any for the language,
+// otherwise the text is spoken by the system's TTS. On the recovery
+// page the nodes heard of are called out as well.
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+
+use clap::ArgEnum;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use log::error;
+use serde::Deserialize;
+
+use crate::bus::{EventBus, Marked};
+use crate::i18n::Language;
+use crate::journal::Marker;
+use crate::rqprotocol::Node;
+use crate::window::WindowStatus;
+
+// Seconds before the window opens
+const T_MINUS_MARKS: [u64; 16] = [600, 300, 120, 60, 30, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0];
+
+#[cfg(target_os = "macos")]
+const PLAYER: &str = "afplay";
+#[cfg(not(target_os = "macos"))]
+const PLAYER: &str = "aplay";
+#[cfg(target_os = "macos")]
+const TTS: &str = "say";
+#[cfg(not(target_os = "macos"))]
+const TTS: &str = "espeak-ng";
+
+#[derive(Clone, Copy, Debug, ArgEnum, PartialEq, Eq, Deserialize)]
+#[clap(rename_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum AnnouncedEvent {
+    TMinus,
+    Holds,
+    Ignition,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Announcement {
+    TMinus(u64),
+    Hold(String),
+    Resume,
+    Ignition,
+    // Loud after a silence, see recovery.rs
+    Heard { node: Node, again: bool },
+}
+
+impl Announcement {
+    // The recovery page's are always said
+    fn event(&self) -> Option<AnnouncedEvent> {
+        match self {
+            Announcement::TMinus(_) => Some(AnnouncedEvent::TMinus),
+            Announcement::Hold(_) | Announcement::Resume => Some(AnnouncedEvent::Holds),
+            Announcement::Ignition => Some(AnnouncedEvent::Ignition),
+            Announcement::Heard { .. } => None,
+        }
+    }
+
+    // Ignition and open stay English, see i18n.rs
+    pub fn text(&self, language: Language) -> String {
+        match (self, language) {
+            (Announcement::TMinus(0), _) => "Window open".into(),
+            (Announcement::TMinus(seconds), _) => {
+                format!("T minus {}", spoken_duration(*seconds, language))
+            }
+            (Announcement::Hold(reason), Language::English) => format!("Hold, {}", reason),
+            (Announcement::Hold(reason), Language::German) => format!("Halt, {}", reason),
+            (Announcement::Hold(reason), Language::French) => format!("Attente, {}", reason),
+            (Announcement::Resume, Language::English) => "Hold released".into(),
+            (Announcement::Resume, Language::German) => "Halt aufgehoben".into(),
+            (Announcement::Resume, Language::French) => "Attente levée".into(),
+            (Announcement::Ignition, Language::English) => "Ignition confirmed".into(),
+            (Announcement::Ignition, Language::German) => "Ignition bestätigt".into(),
+            (Announcement::Ignition, Language::French) => "Ignition confirmée".into(),
+            (Announcement::Heard { node, again: false }, _) => node.to_string(),
+            (Announcement::Heard { node, .. }, Language::English) => {
+                format!("{} heard again", node)
+            }
+            (Announcement::Heard { node, .. }, Language::German) => {
+                format!("{} wieder empfangen", node)
+            }
+            (Announcement::Heard { node, .. }, Language::French) => {
+                format!("{} de nouveau reçu", node)
+            }
+        }
+    }
+
+    // Of the pre-recorded sample, without the hold's reason
+    pub fn sample(&self) -> String {
+        match self {
+            Announcement::TMinus(seconds) => format!("t-minus-{}", seconds),
+            Announcement::Hold(_) => "hold".into(),
+            Announcement::Resume => "resume".into(),
+            Announcement::Ignition => "ignition".into(),
+            Announcement::Heard { again: false, .. } => "heard".into(),
+            Announcement::Heard { again: true, .. } => "heard-again".into(),
+        }
+    }
+}
+
+fn spoken_duration(seconds: u64, language: Language) -> String {
+    match (seconds >= 60, language) {
+        (true, Language::English) => format!("{} minutes", seconds / 60),
+        (true, Language::German) => format!("{} Minuten", seconds / 60),
+        (true, Language::French) => format!("{} minutes", seconds / 60),
+        (false, _) => seconds.to_string(),
+    }
+}
+
+pub struct Announcer {
+    events: Vec<AnnouncedEvent>,
+    markers: Receiver<Marked>,
+    // Seconds until the window opens at the last poll
+    opens_in: Option<u64>,
+    holding: bool,
+}
+
+impl Announcer {
+    pub fn new(bus: &EventBus) -> Self {
+        Self {
+            events: vec![],
+            markers: bus.markers.subscribe(),
+            opens_in: None,
+            holding: false,
+        }
+    }
+
+    pub fn set_events(&mut self, events: Vec<AnnouncedEvent>) {
+        self.events = events;
+    }
+
+    // What's due since the last poll, the disabled ones dropped
+    pub fn poll(&mut self, window: Option<&WindowStatus>) -> Vec<Announcement> {
+        let mut announcements = vec![];
+        let opens_in = match window {
+            // Rounded up, T minus 10 is said with 10s to go
+            Some(WindowStatus::Pending { opens_in }) => {
+                Some((opens_in.as_millis() as u64 + 999) / 1000)
+            }
+            Some(WindowStatus::Open { .. } | WindowStatus::Hold { .. }) => Some(0),
+            _ => None,
+        };
+        if let (Some(last), Some(now)) = (self.opens_in, opens_in) {
+            if let Some(mark) = T_MINUS_MARKS
+                .iter()
+                .rev()
+                .find(|mark| **mark < last && **mark >= now)
+            {
+                announcements.push(Announcement::TMinus(*mark));
+            }
+        }
+        self.opens_in = opens_in;
+        match window {
+            Some(WindowStatus::Hold { reason, .. }) if !self.holding => {
+                self.holding = true;
+                announcements.push(Announcement::Hold(reason.clone()));
+            }
+            Some(WindowStatus::Open { .. }) if self.holding => {
+                self.holding = false;
+                announcements.push(Announcement::Resume);
+            }
+            _ => {}
+        }
+        for marked in self.markers.try_iter() {
+            if marked.marker == Marker::Ignition {
+                announcements.push(Announcement::Ignition);
+            }
+        }
+        announcements.retain(|announcement| {
+            announcement
+                .event()
+                .map_or(true, |event| self.events.contains(&event))
+        });
+        announcements
+    }
+}
+
+// One after the other, on a thread of its own
+pub struct Speaker {
+    sender: Sender<(Announcement, Language)>,
+}
+
+// Of the sample directory and the espeak-ng voice
+fn language_code(language: Language) -> &'static str {
+    match language {
+        Language::English => "en",
+        Language::German => "de",
+        Language::French => "fr",
+    }
+}
+
+fn sample_path(samples: &Path, announcement: &Announcement, language: Language) -> PathBuf {
+    samples
+        .join(language_code(language))
+        .join(format!("{}.wav", announcement.sample()))
+}
+
+// Still queued behind a longer announcement, a count is only worth
+// saying while it's the latest
+fn drop_outdated(queue: &mut VecDeque<(Announcement, Language)>) {
+    let is_count = |announcement: &Announcement| matches!(announcement, Announcement::TMinus(_));
+    let latest = queue
+        .iter()
+        .rposition(|(announcement, _)| is_count(announcement));
+    let mut index = 0;
+    queue.retain(|(announcement, _)| {
+        let keep = !is_count(announcement) || Some(index) == latest;
+        index += 1;
+        keep
+    });
+}
+
+fn speak(samples: Option<&Path>, announcement: &Announcement, language: Language) {
+    let sample = samples
+        .map(|samples| sample_path(samples, announcement, language))
+        .filter(|path| path.exists());
+    let status = match sample {
+        Some(path) => Command::new(PLAYER).arg(path).status(),
+        None => {
+            let mut tts = Command::new(TTS);
+            if TTS == "espeak-ng" {
+                tts.args(["-v", language_code(language)]);
+            }
+            tts.arg(announcement.text(language)).status()
+        }
+    };
+    if let Err(e) = status {
+        error!("Can't announce {:?}, {}", announcement, e);
+    }
+}
+
+impl Speaker {
+    pub fn new(samples: Option<PathBuf>) -> Self {
+        let (sender, receiver) = unbounded::<(Announcement, Language)>();
+        thread::spawn(move || {
+            let mut queue = VecDeque::new();
+            loop {
+                if queue.is_empty() {
+                    match receiver.recv() {
+                        Ok(next) => queue.push_back(next),
+                        Err(_) => break,
+                    }
+                }
+                queue.extend(receiver.try_iter());
+                drop_outdated(&mut queue);
+                if let Some((announcement, language)) = queue.pop_front() {
+                    speak(samples.as_deref(), &announcement, language);
+                }
+            }
+        });
+        Self { sender }
+    }
+
+    pub fn say(&self, announcement: Announcement, language: Language) {
+        let _ = self.sender.send((announcement, language));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_countdown_is_announced() {
+        let bus = EventBus::default();
+        let mut announcer = Announcer::new(&bus);
+        announcer.set_events(vec![AnnouncedEvent::TMinus, AnnouncedEvent::Holds]);
+        let pending = |millis| WindowStatus::Pending {
+            opens_in: Duration::from_millis(millis),
+        };
+        assert!(announcer.poll(Some(&pending(62_000))).is_empty());
+        assert!(announcer.poll(Some(&pending(61_000))).is_empty());
+        assert_eq!(
+            announcer.poll(Some(&pending(59_500))),
+            vec![Announcement::TMinus(60)]
+        );
+        assert!(announcer.poll(Some(&pending(59_000))).is_empty());
+        // A stall skips to the latest mark
+        assert_eq!(
+            announcer.poll(Some(&pending(7_200))),
+            vec![Announcement::TMinus(8)]
+        );
+        let open = WindowStatus::Open {
+            closes_in: Duration::from_secs(600),
+            closing: false,
+        };
+        assert_eq!(announcer.poll(Some(&open)), vec![Announcement::TMinus(0)]);
+        let hold = WindowStatus::Hold {
+            reason: "Aircraft".into(),
+            resumes_in: Duration::from_secs(60),
+        };
+        assert_eq!(
+            announcer.poll(Some(&hold)),
+            vec![Announcement::Hold("Aircraft".into())]
+        );
+        assert!(announcer.poll(Some(&hold)).is_empty());
+        assert_eq!(announcer.poll(Some(&open)), vec![Announcement::Resume]);
+        // Ignition isn't enabled
+        bus.markers.publish(Marked {
+            marker: Marker::Ignition,
+            elapsed: 3.0,
+            text: "Ignition acknowledged by RQB".into(),
+        });
+        assert!(announcer.poll(Some(&open)).is_empty());
+
+        assert_eq!(
+            Announcement::TMinus(120).text(Language::German),
+            "T minus 2 Minuten"
+        );
+        assert_eq!(
+            Announcement::Ignition.text(Language::German),
+            "Ignition bestätigt"
+        );
+        assert_eq!(
+            sample_path(
+                Path::new("voice"),
+                &Announcement::TMinus(10),
+                Language::German
+            ),
+            Path::new("voice/de/t-minus-10.wav")
+        );
+        assert_eq!(
+            Announcement::Hold("Aircraft".into()).text(Language::French),
+            "Attente, Aircraft"
+        );
+        let heard = |again| Announcement::Heard {
+            node: Node::RedQueen(b'B'),
+            again,
+        };
+        assert_eq!(heard(false).text(Language::German), "RQB");
+        assert_eq!(heard(true).text(Language::English), "RQB heard again");
+        assert_eq!(heard(true).sample(), "heard-again");
+    }
+
+    #[test]
+    fn test_outdated_counts_are_dropped() {
+        let mut queue: VecDeque<_> = [
+            Announcement::TMinus(10),
+            Announcement::Hold("Wind".into()),
+            Announcement::TMinus(9),
+            Announcement::TMinus(8),
+            Announcement::Resume,
+        ]
+        .into_iter()
+        .map(|announcement| (announcement, Language::French))
+        .collect();
+        drop_outdated(&mut queue);
+        let left: Vec<_> = queue
+            .into_iter()
+            .map(|(announcement, _)| announcement)
+            .collect();
+        assert_eq!(
+            left,
+            vec![
+                Announcement::Hold("Wind".into()),
+                Announcement::TMinus(8),
+                Announcement::Resume,
+            ]
+        );
+    }
+}
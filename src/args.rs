@@ -1,6 +1,41 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
 use std::str::FromStr;
+use std::time::Duration;
 
 use clap::{ArgAction, Parser};
+use serde::Deserialize;
+
+use crate::annotation::DEFAULT_LABELS;
+use crate::announcer::AnnouncedEvent;
+use crate::autorecord::AutoRecordPolicy;
+use crate::backoff::BackoffPolicy;
+use crate::config::{validate_key_entry_timeout, ConfigFile};
+use crate::crank::Crank;
+use crate::faults::{Faults, SharedFaults};
+use crate::freshness::Freshness;
+use crate::i18n::Language;
+use crate::idle::IdlePolicy;
+use crate::input::{RepeatConfig, TabNavigation};
+use crate::layout::scaling::Accessibility;
+use crate::layout::theme::Theme;
+use crate::linkcrypto::{self, Key};
+use crate::observables::Profile;
+use crate::observables::{filter::Filters, range::PlotRanges, units::Units};
+use crate::recorder::Sink;
+use crate::rqprotocol::Node;
+use crate::tcpbridge::{Latency, Transport};
+use crate::telemetry::parser::rq2::ImuRanges;
+use crate::telemetry::relay::{
+    CurveClient, RelayFormat, DEFAULT_BACKLOG, DEFAULT_BATCH, DEFAULT_REPLAY_BIND,
+    DEFAULT_REPLAY_URI,
+};
+use crate::telemetry::retention::RetentionPolicy;
+use crate::telemetry::roster::{ChannelConfig, DEFAULT_ROSTER};
+use crate::telemetry::sim::SimConfig;
+use crate::window::LaunchWindowConfig;
 
 #[derive(Clone, Parser, Debug)]
 pub enum LaunchMode {
@@ -22,23 +57,567 @@ impl FromStr for LaunchMode {
     }
 }
 
+// How launch secrets are shown, e.g. during livestreams
+#[derive(Clone, Copy, Parser, Debug, PartialEq, Deserialize)]
+pub enum Privacy {
+    Off,
+    // Only the digit currently edited is shown
+    Mask,
+    // No key row at all
+    Hide,
+}
+
+impl FromStr for Privacy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Off" => Ok(Privacy::Off),
+            "Mask" => Ok(Privacy::Mask),
+            "Hide" => Ok(Privacy::Hide),
+            _ => Err("No valid value, use Off, Mask, Hide"),
+        }
+    }
+}
+
 #[derive(Clone, Parser, Debug)]
 #[clap(version, about, long_about = None)]
 pub struct ProgramArgs {
     #[clap(short, long)]
     pub port: Option<String>,
+    // Second E32 to fail over to when the primary link degrades
+    #[clap(long)]
+    pub secondary_port: Option<String>,
+    #[clap(long, default_value_t = 3)]
+    pub failover_after: usize,
+    // serial for the E32, or tcp:HOST:PORT for a serial bridge
+    // to the UART of the RQ on the test bench
+    #[clap(long, default_value = "serial")]
+    pub transport: Transport,
+    // Emulated radio latency over the bridge, each way
+    #[clap(long, default_value_t = 0)]
+    pub bridge_latency_ms: u64,
+    #[clap(long, default_value_t = 0)]
+    pub bridge_jitter_ms: u64,
     #[clap(short, long)]
     pub start_with: LaunchMode,
+    // Test stand or rocket
+    #[clap(long, arg_enum, default_value = "rocket")]
+    pub profile: Profile,
     #[clap(short, long, action = ArgAction::SetTrue)]
     pub dont_record: bool,
+    // Second copy of the recording, a directory (e.g. a USB
+    // stick), tcp://host:port or memory://<bytes> for tests
+    #[clap(long)]
+    pub mirror_recording: Option<Sink>,
+    // pcap file getting the serial traffic of both directions,
+    // independent of the recording
+    #[clap(long)]
+    pub capture: Option<PathBuf>,
+    // Tape of the input events with their frames, to play back
+    // instead of the live input, see inputtape.rs
+    #[clap(long)]
+    pub record_input: Option<PathBuf>,
+    #[clap(long)]
+    pub play_input: Option<PathBuf>,
+    // Expected thrust curve for the timeline, RASP .eng or CSV
+    #[clap(long)]
+    pub reference_thrust: Option<PathBuf>,
+    // Pad camera taking snapshots on ignition and anomalies,
+    // a V4L2 device on the novaview
+    #[clap(long)]
+    pub camera: Option<String>,
+    // Clips of that length instead of snapshots
+    #[clap(long)]
+    pub camera_clip_s: Option<u64>,
+    // Address to serve the read-only dashboard on, e.g. 0.0.0.0:8080,
+    // needs the dashboard feature
+    #[clap(long)]
+    pub dashboard: Option<String>,
+    // Directory with the scripted sequences
+    #[clap(long)]
+    pub sequences: Option<PathBuf>,
+    // Where previous sessions are exported to, e.g. a mounted
+    // stick, not the card they are recorded on
+    #[clap(long)]
+    pub export_sessions: Option<PathBuf>,
+    #[clap(long, default_value = "Off")]
+    pub privacy: Privacy,
+    #[clap(long, default_value = "English")]
+    pub language: Language,
+    #[clap(long, default_value = "Dark")]
+    pub theme: Theme,
+    // Of thrust and pressure on the screen
+    #[clap(long, default_value = "Metric")]
+    pub units: Units,
+    // Initial window size, the layout follows resizes
+    #[clap(long, default_value_t = 1024)]
+    pub window_width: u32,
+    #[clap(long, default_value_t = 600)]
+    pub window_height: u32,
+    // On top of scaling to the window size
+    #[clap(long, default_value_t = 1.0)]
+    pub ui_scale: f32,
+    // Key repeat for digit entry
+    #[clap(long, default_value_t = 400)]
+    pub repeat_delay_ms: u64,
+    #[clap(long, default_value_t = 120)]
+    pub repeat_interval_ms: u64,
+    #[clap(long, default_value_t = 2.0)]
+    pub repeat_acceleration: f32,
+    #[clap(long, default_value_t = 4)]
+    pub repeat_max_steps: u32,
+    // Tab navigation, Left/Right skip several tabs on a fast spin
+    #[clap(long, action = ArgAction::SetTrue)]
+    pub no_tab_wrap: bool,
+    #[clap(long, default_value_t = 150)]
+    pub tab_debounce_ms: u64,
+    // Arming progress, see crank.rs
+    #[clap(long, default_value_t = 3)]
+    pub crank_increment: u8,
+    #[clap(long, default_value_t = 500)]
+    pub crank_decay_interval_ms: u64,
+    #[clap(long, default_value_t = 1)]
+    pub crank_decay_amount: u8,
+    #[clap(long, default_value_t = 100)]
+    pub crank_threshold: u8,
+    // Nodes to poll observables from in turn, e.g. RQA,FDA.
+    // Defaults to the target RedQueen only.
+    #[clap(long, value_delimiter = ',')]
+    pub poll_nodes: Vec<Node>,
+    // How long the line has to be quiet after a purge
+    #[clap(long, default_value_t = 500)]
+    pub drain_idle_ms: u64,
+    // Entered key digits are cleared after this, back to idle,
+    // well before the automatic reset
+    #[clap(long, default_value_t = 30)]
+    pub key_entry_timeout_s: u64,
+    // Observables not received for this many polls are shown
+    // as stale, but never sooner than the minimum
+    #[clap(long, default_value_t = 20)]
+    pub stale_polls: u32,
+    #[clap(long, default_value_t = 1000)]
+    pub stale_min_ms: u64,
+    // Holding off retries after NAKs and timeouts, doubling
+    // from the initial hold up to the maximum
+    #[clap(long, default_value_t = 250)]
+    pub backoff_initial_ms: u64,
+    #[clap(long, default_value_t = 8000)]
+    pub backoff_max_hold_ms: u64,
+    #[clap(long, default_value_t = 0.25)]
+    pub backoff_jitter: f32,
+    // Of the time on air over an hour, as allowed in the sub-band.
+    // Above it the observables polls are held back.
+    #[clap(long)]
+    pub duty_cycle_percent: Option<f64>,
+    // The onboard recording is started when armed or the thrust
+    // exceeds the threshold, and stopped after the burn
+    #[clap(long, action = ArgAction::SetTrue)]
+    pub no_auto_record: bool,
+    #[clap(long, default_value_t = 0.5)]
+    pub auto_record_thrust_kn: f64,
+    #[clap(long, default_value_t = 10)]
+    pub auto_record_stop_s: u64,
+    // Standby without activity for that long, 0 never. It slows the
+    // observables polls down and dims the display.
+    #[clap(long, default_value_t = 10)]
+    pub idle_standby_min: u64,
+    #[clap(long, default_value_t = 1000)]
+    pub idle_poll_ms: u64,
+    #[clap(long, default_value_t = 20)]
+    pub idle_brightness_percent: u8,
+    // Large, high-contrast state, MET and safety indicators
+    // while waiting for the fire and during the burn
+    #[clap(long, action = ArgAction::SetTrue)]
+    pub accessible: bool,
+    #[clap(long, default_value_t = 2.0)]
+    pub critical_scale: f32,
+    // To pick from when marking a moment, none marks right away
+    #[clap(long, value_delimiter = ',', default_values = &DEFAULT_LABELS)]
+    pub mark_labels: Vec<String>,
+    // Synthetic telemetry instead of NRF modules
+    #[clap(long, action = ArgAction::SetTrue)]
+    pub simulate_telemetry: bool,
+    #[clap(long, default_value_t = 50.0)]
+    pub sim_imu_rate: f32,
+    #[clap(long, default_value_t = 2.0)]
+    pub sim_state_rate: f32,
+    #[clap(long, default_value_t = 0.02)]
+    pub sim_dropout: f32,
+    // How telemetry is relayed to desktop frontends
+    #[clap(long, default_value = "Json")]
+    pub relay_format: RelayFormat,
+    #[clap(long, default_value_t = DEFAULT_BATCH)]
+    pub relay_batch: usize,
+    // Kept for subscribers joining late
+    #[clap(long, default_value_t = DEFAULT_BACKLOG.as_secs())]
+    pub relay_backlog_s: u64,
+    // Answered there, and asked for there
+    #[clap(long, default_value = DEFAULT_REPLAY_BIND)]
+    pub relay_replay_bind: String,
+    #[clap(long, default_value = DEFAULT_REPLAY_URI)]
+    pub relay_replay_uri: String,
+    // Parsed summaries on a second topic, e.g. 100 for 10 Hz
+    #[clap(long)]
+    pub relay_summary_ms: Option<u64>,
+    // Telemetry kept in memory per node
+    #[clap(long, default_value_t = 20_000)]
+    pub telemetry_max_entries: usize,
+    #[clap(long, default_value_t = 600)]
+    pub telemetry_max_age_s: u64,
+    // Directory to write what's dropped from memory to, also
+    // when nothing is recorded like on the novaview
+    #[clap(long)]
+    pub spill_telemetry: Option<PathBuf>,
+    // Fault injection for chaos testing, ignored in release builds
+    #[clap(long, default_value_t = 0)]
+    pub inject_drop_acks: usize,
+    // Probability of a corrupted checksum per response
+    #[clap(long, default_value_t = 0.0)]
+    pub inject_corrupt: f32,
+    #[clap(long, default_value_t = 0)]
+    pub inject_delay_ms: u64,
+    // Verb of the command to NAK, e.g. PING
+    #[clap(long)]
+    pub inject_nak: Option<String>,
+    // Deviations from the wire format are journaled and need to
+    // be acknowledged, for qualifying firmware releases
+    #[clap(long, action = ArgAction::SetTrue)]
+    pub strict: bool,
+    // Address of gpsd, e.g. localhost:2947, to take the wall clock
+    // time from the GPS receiver instead of the system
+    #[clap(long)]
+    pub gpsd: Option<String>,
+    // UDP port the wireless pendant sends its input events to, off
+    // without. Needs the pendant key.
+    #[clap(long)]
+    pub pendant_port: Option<u16>,
+    // CURVE keys of the relay, only from the config
+    // file so they don't show up in the process list.
+    #[clap(skip)]
+    pub relay_secret_key: Option<String>,
+    #[clap(skip)]
+    pub relay_server_key: Option<String>,
+    // Public keys of the subscribers the publisher lets in
+    #[clap(skip)]
+    pub relay_allowed_clients: Vec<String>,
+    // Of the subscriber, to be let in
+    #[clap(skip)]
+    pub relay_client_public_key: Option<String>,
+    #[clap(skip)]
+    pub relay_client_secret_key: Option<String>,
+    // Of the link encryption per node, for the same reason
+    #[clap(skip)]
+    pub link_keys: HashMap<Node, String>,
+    #[clap(skip)]
+    pub pendant_key: Option<String>,
+    // Of the plots, set on the filter page and stored in the config file
+    #[clap(skip)]
+    pub filters: Filters,
+    // Of the plots, from the config file
+    #[clap(skip)]
+    pub plot_ranges: PlotRanges,
+    // With its planned holds, from the config file
+    #[clap(skip)]
+    pub launch_window: Option<LaunchWindowConfig>,
+    // Per node, from the config file or the calibration page
+    #[clap(skip)]
+    pub imu_ranges: HashMap<Node, ImuRanges>,
+    // Of the NRF modules, from the config file
+    #[clap(skip = DEFAULT_ROSTER.to_vec())]
+    pub telemetry_roster: Vec<ChannelConfig>,
+    // Spoken through the speakers, e.g. t-minus,holds,ignition.
+    // None without.
+    #[clap(long, arg_enum, value_delimiter = ',')]
+    pub announce: Vec<AnnouncedEvent>,
+    // Pre-recorded announcements as <language>/<sample>.wav,
+    // e.g. de/t-minus-10.wav, spoken by TTS without
+    #[clap(long)]
+    pub voice_samples: Option<PathBuf>,
+    // Writes the launch control state machine as Graphviz and exits
+    #[clap(long)]
+    pub export_fsm: Option<PathBuf>,
+    // Writes the RQ protocol specification, a JSON Schema and a
+    // document, into the directory and exits
+    #[clap(long)]
+    pub export_protocol: Option<PathBuf>,
+    // JSON file with settings overriding the above,
+    // watched for changes while running
+    #[clap(long)]
+    pub config: Option<PathBuf>,
+}
+
+impl ProgramArgs {
+    pub fn repeat_config(&self) -> RepeatConfig {
+        RepeatConfig {
+            delay: Duration::from_millis(self.repeat_delay_ms),
+            interval: Duration::from_millis(self.repeat_interval_ms),
+            acceleration: self.repeat_acceleration,
+            max_steps: self.repeat_max_steps,
+        }
+    }
+
+    pub fn tab_navigation(&self) -> TabNavigation {
+        TabNavigation {
+            wrap: !self.no_tab_wrap,
+            debounce: Duration::from_millis(self.tab_debounce_ms),
+        }
+    }
+
+    pub fn crank(&self) -> Crank {
+        Crank {
+            increment: self.crank_increment,
+            decay_interval: Duration::from_millis(self.crank_decay_interval_ms),
+            decay_amount: self.crank_decay_amount,
+            threshold: self.crank_threshold,
+        }
+    }
+
+    pub fn drain_idle(&self) -> Duration {
+        Duration::from_millis(self.drain_idle_ms)
+    }
+
+    pub fn bridge_latency(&self) -> Latency {
+        Latency {
+            delay: Duration::from_millis(self.bridge_latency_ms),
+            jitter: Duration::from_millis(self.bridge_jitter_ms),
+        }
+    }
+
+    pub fn key_entry_timeout(&self) -> Duration {
+        Duration::from_secs(self.key_entry_timeout_s)
+    }
+
+    pub fn freshness(&self) -> Freshness {
+        Freshness {
+            polls: self.stale_polls,
+            minimum: Duration::from_millis(self.stale_min_ms),
+        }
+    }
+
+    pub fn relay_backlog(&self) -> Duration {
+        Duration::from_secs(self.relay_backlog_s)
+    }
+
+    pub fn relay_summary(&self) -> Option<Duration> {
+        self.relay_summary_ms.map(Duration::from_millis)
+    }
+
+    pub fn relay_client(&self) -> Option<CurveClient> {
+        Some(CurveClient {
+            server_key: self.relay_server_key.clone()?,
+            keys: self
+                .relay_client_public_key
+                .clone()
+                .zip(self.relay_client_secret_key.clone()),
+        })
+    }
+
+    pub fn backoff_policy(&self) -> BackoffPolicy {
+        BackoffPolicy {
+            initial: Duration::from_millis(self.backoff_initial_ms),
+            max_hold: Duration::from_millis(self.backoff_max_hold_ms),
+            jitter: self.backoff_jitter.clamp(0.0, 1.0),
+        }
+    }
+
+    // Validated with the config file
+    pub fn link_keys(&self) -> HashMap<Node, Key> {
+        self.link_keys
+            .iter()
+            .filter_map(|(node, key)| Some((*node, linkcrypto::decode_key(key).ok()?)))
+            .collect()
+    }
+
+    // Port and key, if both are configured
+    pub fn pendant(&self) -> Option<(u16, Key)> {
+        let key = linkcrypto::decode_key(self.pendant_key.as_deref()?).ok()?;
+        Some((self.pendant_port?, key))
+    }
+
+    pub fn accessibility(&self) -> Accessibility {
+        Accessibility {
+            enabled: self.accessible,
+            critical_scale: self.critical_scale,
+        }
+    }
+
+    pub fn auto_record_policy(&self) -> AutoRecordPolicy {
+        AutoRecordPolicy {
+            enabled: !self.no_auto_record,
+            thrust_threshold: self.auto_record_thrust_kn,
+            stop_after: Duration::from_secs(self.auto_record_stop_s),
+        }
+    }
+
+    pub fn idle_policy(&self) -> IdlePolicy {
+        IdlePolicy {
+            after: (self.idle_standby_min > 0)
+                .then(|| Duration::from_secs(self.idle_standby_min * 60)),
+            poll_interval: Duration::from_millis(self.idle_poll_ms),
+            brightness_percent: self.idle_brightness_percent,
+        }
+    }
+
+    pub fn duty_cycle_cap(&self) -> Option<f64> {
+        self.duty_cycle_percent.map(|percent| percent / 100.0)
+    }
+
+    pub fn camera_clip(&self) -> Option<Duration> {
+        self.camera_clip_s.map(Duration::from_secs)
+    }
+
+    // Applies the config file given on the command line, if any,
+    // and checks what the file doesn't
+    pub fn with_config(mut self) -> anyhow::Result<Self> {
+        if let Some(path) = self.config.clone() {
+            ConfigFile::load(&path)?.apply(&mut self);
+        }
+        self.validate()?;
+        Ok(self)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        validate_key_entry_timeout(self.key_entry_timeout_s)?;
+        self.sim_config().validate()
+    }
+
+    pub fn retention_policy(&self) -> RetentionPolicy {
+        RetentionPolicy {
+            max_entries: self.telemetry_max_entries,
+            max_age: Duration::from_secs(self.telemetry_max_age_s),
+        }
+    }
+
+    // Enabled right away when any fault is given
+    pub fn faults(&self) -> SharedFaults {
+        let mut faults = Faults {
+            enabled: false,
+            drop_acks: self.inject_drop_acks,
+            corrupt_probability: self.inject_corrupt.clamp(0.0, 1.0),
+            delay: Duration::from_millis(self.inject_delay_ms),
+            nak_verb: self.inject_nak.clone(),
+        };
+        faults.enabled = cfg!(debug_assertions) && faults.any();
+        Rc::new(RefCell::new(faults))
+    }
+
+    pub fn sim_config(&self) -> SimConfig {
+        SimConfig {
+            imu_rate: self.sim_imu_rate,
+            state_rate: self.sim_state_rate,
+            dropout: self.sim_dropout,
+        }
+    }
 }
 
 impl Default for ProgramArgs {
     fn default() -> Self {
+        let repeat = RepeatConfig::default();
+        let tabs = TabNavigation::default();
+        let crank = Crank::default();
+        let sim = SimConfig::default();
+        let retention = RetentionPolicy::default();
+        let freshness = Freshness::default();
+        let backoff = BackoffPolicy::default();
+        let auto_record = AutoRecordPolicy::default();
+        let idle = IdlePolicy::default();
+        let accessibility = Accessibility::default();
         Self {
             port: Default::default(),
+            secondary_port: Default::default(),
+            failover_after: 3,
+            transport: Transport::Serial,
+            bridge_latency_ms: 0,
+            bridge_jitter_ms: 0,
             start_with: LaunchMode::Observables,
+            profile: Profile::default(),
             dont_record: false,
+            mirror_recording: None,
+            capture: None,
+            record_input: None,
+            play_input: None,
+            reference_thrust: None,
+            camera: None,
+            camera_clip_s: None,
+            dashboard: None,
+            sequences: None,
+            export_sessions: None,
+            privacy: Privacy::Off,
+            language: Language::default(),
+            theme: Theme::default(),
+            units: Units::default(),
+            window_width: 1024,
+            window_height: 600,
+            ui_scale: 1.0,
+            repeat_delay_ms: repeat.delay.as_millis() as u64,
+            repeat_interval_ms: repeat.interval.as_millis() as u64,
+            repeat_acceleration: repeat.acceleration,
+            repeat_max_steps: repeat.max_steps,
+            no_tab_wrap: !tabs.wrap,
+            tab_debounce_ms: tabs.debounce.as_millis() as u64,
+            crank_increment: crank.increment,
+            crank_decay_interval_ms: crank.decay_interval.as_millis() as u64,
+            crank_decay_amount: crank.decay_amount,
+            crank_threshold: crank.threshold,
+            poll_nodes: vec![],
+            drain_idle_ms: 500,
+            key_entry_timeout_s: 30,
+            stale_polls: freshness.polls,
+            stale_min_ms: freshness.minimum.as_millis() as u64,
+            backoff_initial_ms: backoff.initial.as_millis() as u64,
+            backoff_max_hold_ms: backoff.max_hold.as_millis() as u64,
+            backoff_jitter: backoff.jitter,
+            duty_cycle_percent: None,
+            no_auto_record: !auto_record.enabled,
+            auto_record_thrust_kn: auto_record.thrust_threshold,
+            auto_record_stop_s: auto_record.stop_after.as_secs(),
+            idle_standby_min: idle.after.map_or(0, |after| after.as_secs() / 60),
+            idle_poll_ms: idle.poll_interval.as_millis() as u64,
+            idle_brightness_percent: idle.brightness_percent,
+            accessible: accessibility.enabled,
+            critical_scale: accessibility.critical_scale,
+            mark_labels: DEFAULT_LABELS
+                .iter()
+                .map(|label| label.to_string())
+                .collect(),
+            simulate_telemetry: false,
+            sim_imu_rate: sim.imu_rate,
+            sim_state_rate: sim.state_rate,
+            sim_dropout: sim.dropout,
+            relay_format: RelayFormat::default(),
+            relay_batch: DEFAULT_BATCH,
+            relay_backlog_s: DEFAULT_BACKLOG.as_secs(),
+            relay_replay_bind: DEFAULT_REPLAY_BIND.into(),
+            relay_replay_uri: DEFAULT_REPLAY_URI.into(),
+            relay_summary_ms: None,
+            telemetry_max_entries: retention.max_entries,
+            telemetry_max_age_s: retention.max_age.as_secs(),
+            spill_telemetry: None,
+            inject_drop_acks: 0,
+            inject_corrupt: 0.0,
+            inject_delay_ms: 0,
+            inject_nak: None,
+            strict: false,
+            gpsd: None,
+            pendant_port: None,
+            relay_secret_key: None,
+            relay_server_key: None,
+            relay_allowed_clients: vec![],
+            relay_client_public_key: None,
+            relay_client_secret_key: None,
+            link_keys: HashMap::new(),
+            pendant_key: None,
+            filters: Filters::default(),
+            plot_ranges: PlotRanges::default(),
+            launch_window: None,
+            imu_ranges: HashMap::new(),
+            telemetry_roster: DEFAULT_ROSTER.to_vec(),
+            announce: vec![],
+            voice_samples: None,
+            export_fsm: None,
+            export_protocol: None,
+            config: None,
         }
     }
 }
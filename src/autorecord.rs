@@ -0,0 +1,159 @@
+// Nobody should have to remember to start the onboard recording
+// before a burn. It's started when the launch sequence begins, ahead
+// of arming, or when the thrust exceeds the threshold, and stopped again
+// once neither has been the case for a while. The ground recording
+// runs all the time anyway. The operator starting or stopping the
+// onboard recording by hand takes over until the burn is over.
+use std::time::Duration;
+
+use crate::clock::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoRecordPolicy {
+    pub enabled: bool,
+    // In kN, of the tared thrust
+    pub thrust_threshold: f64,
+    // After the thrust decayed, or the launch was left
+    pub stop_after: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trigger {
+    Armed,
+    Thrust,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Start(Trigger),
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum State {
+    #[default]
+    Waiting,
+    Triggered {
+        // Only a recording we started is stopped
+        ours: bool,
+        last: Instant,
+    },
+    Overridden,
+}
+
+#[derive(Debug, Default)]
+pub struct AutoRecord {
+    pub policy: AutoRecordPolicy,
+    state: State,
+}
+
+impl Default for AutoRecordPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            thrust_threshold: 0.5,
+            stop_after: Duration::from_secs(10),
+        }
+    }
+}
+
+impl AutoRecord {
+    // Whether the automation currently keeps the recording going
+    pub fn is_triggered(&self) -> bool {
+        matches!(self.state, State::Triggered { .. })
+    }
+
+    // The operator started or stopped the recording
+    pub fn overridden(&mut self) {
+        if self.state != State::Waiting {
+            self.state = State::Overridden;
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        now: Instant,
+        armed: bool,
+        thrust: Option<f64>,
+        recording: bool,
+    ) -> Option<Action> {
+        if !self.policy.enabled {
+            self.state = State::Waiting;
+            return None;
+        }
+        let trigger = match thrust {
+            Some(thrust) if thrust > self.policy.thrust_threshold => Some(Trigger::Thrust),
+            _ if armed => Some(Trigger::Armed),
+            _ => None,
+        };
+        match (self.state, trigger) {
+            (State::Waiting, Some(trigger)) => {
+                self.state = State::Triggered {
+                    ours: !recording,
+                    last: now,
+                };
+                (!recording).then_some(Action::Start(trigger))
+            }
+            (State::Triggered { ours, .. }, Some(_)) => {
+                self.state = State::Triggered { ours, last: now };
+                None
+            }
+            (State::Triggered { ours, last }, None)
+                if now.duration_since(last) >= self.policy.stop_after =>
+            {
+                self.state = State::Waiting;
+                (ours && recording).then_some(Action::Stop)
+            }
+            (State::Overridden, None) => {
+                self.state = State::Waiting;
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, SimulatedClock};
+
+    #[test]
+    fn test_burn_is_recorded_until_the_thrust_decays() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut auto = AutoRecord::default();
+        assert_eq!(auto.update(clock.now(), false, Some(0.1), false), None);
+        assert_eq!(
+            auto.update(clock.now(), false, Some(2.0), false),
+            Some(Action::Start(Trigger::Thrust))
+        );
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(auto.update(clock.now(), false, Some(1.0), true), None);
+        clock.advance(Duration::from_secs(9));
+        assert_eq!(auto.update(clock.now(), false, Some(0.0), true), None);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(
+            auto.update(clock.now(), false, Some(0.0), true),
+            Some(Action::Stop)
+        );
+        assert!(!auto.is_triggered());
+    }
+
+    #[test]
+    fn test_operator_takes_over() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut auto = AutoRecord::default();
+        assert_eq!(
+            auto.update(clock.now(), true, None, false),
+            Some(Action::Start(Trigger::Armed))
+        );
+        auto.overridden();
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(auto.update(clock.now(), true, None, false), None);
+        assert_eq!(auto.update(clock.now(), false, None, true), None);
+        // A recording already running is left alone
+        assert_eq!(auto.update(clock.now(), true, None, true), None);
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(auto.update(clock.now(), false, None, true), None);
+    }
+}
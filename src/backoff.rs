@@ -0,0 +1,161 @@
+// Under heavy interference, retrying right after a NAK or timeout
+// only adds to the congestion. After every failed transaction the
+// Consort holds off the next reset cycle for twice as long as
+// before, up to a maximum hold the operator is told about. A bit of
+// jitter keeps us from falling into step with other transmitters on
+// the channel. The first answer ends the backoff.
+use std::time::Duration;
+
+use crate::clock::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackoffPolicy {
+    // After the first failure
+    pub initial: Duration,
+    pub max_hold: Duration,
+    // Fraction of the hold it's randomly shortened or lengthened by
+    pub jitter: f32,
+}
+
+// Of the link stats
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BackoffStats {
+    // Since the last answer
+    pub failures: u32,
+    pub backoffs: usize,
+    pub held: Duration,
+    pub longest: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hold {
+    pub delay: Duration,
+    // Only the first time the maximum is reached
+    pub maxed: bool,
+}
+
+#[derive(Debug)]
+pub struct Backoff {
+    policy: BackoffPolicy,
+    until: Option<Instant>,
+    maxed: bool,
+    stats: BackoffStats,
+    rng: u32,
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(250),
+            max_hold: Duration::from_secs(8),
+            jitter: 0.25,
+        }
+    }
+}
+
+impl Backoff {
+    pub fn new(policy: BackoffPolicy) -> Self {
+        Self {
+            policy,
+            until: None,
+            maxed: false,
+            stats: BackoffStats::default(),
+            rng: 0x6c078965,
+        }
+    }
+
+    // Takes effect with the next failure
+    pub fn set_policy(&mut self, policy: BackoffPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn failed(&mut self, now: Instant) -> Hold {
+        self.stats.failures += 1;
+        let doublings = (self.stats.failures - 1).min(16);
+        let nominal = self
+            .policy
+            .initial
+            .saturating_mul(1 << doublings)
+            .min(self.policy.max_hold);
+        let at_max = nominal == self.policy.max_hold;
+        let jitter = (self.random() * 2.0 - 1.0) * self.policy.jitter;
+        let delay = nominal.mul_f32(1.0 + jitter);
+        self.until = Some(now + delay);
+        self.stats.backoffs += 1;
+        self.stats.held += delay;
+        self.stats.longest = self.stats.longest.max(delay);
+        let maxed = at_max && !self.maxed;
+        self.maxed = at_max;
+        Hold { delay, maxed }
+    }
+
+    pub fn succeeded(&mut self) {
+        self.stats.failures = 0;
+        self.until = None;
+        self.maxed = false;
+    }
+
+    // What's left of the hold
+    pub fn holding(&self, now: Instant) -> Option<Duration> {
+        self.until
+            .map(|until| until.saturating_duration_since(now))
+            .filter(|left| !left.is_zero())
+    }
+
+    pub fn stats(&self) -> &BackoffStats {
+        &self.stats
+    }
+
+    // xorshift, as for the fault injection
+    fn random(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng as f32 / u32::MAX as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_doubles_up_to_the_maximum_hold() {
+        let now = Instant::now();
+        let mut backoff = Backoff::new(BackoffPolicy {
+            initial: Duration::from_millis(100),
+            max_hold: Duration::from_millis(500),
+            jitter: 0.0,
+        });
+        let holds: Vec<_> = (0..5).map(|_| backoff.failed(now)).collect();
+        let delays: Vec<_> = holds.iter().map(|hold| hold.delay.as_millis()).collect();
+        assert_eq!(delays, vec![100, 200, 400, 500, 500]);
+        let maxed: Vec<_> = holds.iter().map(|hold| hold.maxed).collect();
+        assert_eq!(maxed, vec![false, false, false, true, false]);
+        assert_eq!(
+            backoff.holding(now + Duration::from_millis(300)),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(backoff.holding(now + Duration::from_millis(500)), None);
+        assert_eq!(backoff.stats().held, Duration::from_millis(1700));
+        backoff.succeeded();
+        assert_eq!(backoff.holding(now), None);
+        assert_eq!(backoff.failed(now).delay, Duration::from_millis(100));
+        assert_eq!(backoff.stats().failures, 1);
+    }
+
+    #[test]
+    fn test_jitter_stays_within_bounds() {
+        let now = Instant::now();
+        let mut backoff = Backoff::new(BackoffPolicy {
+            initial: Duration::from_secs(1),
+            max_hold: Duration::from_secs(1),
+            jitter: 0.25,
+        });
+        let delays: Vec<_> = (0..50).map(|_| backoff.failed(now).delay).collect();
+        assert!(delays
+            .iter()
+            .all(|delay| (750..=1250).contains(&delay.as_millis())));
+        assert!(delays.iter().any(|delay| *delay != delays[0]));
+    }
+}
@@ -9,25 +9,64 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use clap::Parser;
+use control_frontend::announcer::{Announcement, Speaker};
 use control_frontend::args::ProgramArgs;
+#[cfg(feature = "novaview")]
+use control_frontend::camera::v4l2::Camera;
+use control_frontend::clock::{self, SharedClock};
+use control_frontend::config::{self, ConfigWatcher};
 use control_frontend::connection::Connection;
-use control_frontend::consort::Consort;
-use control_frontend::input::InputEvent;
+use control_frontend::consort::{Consort, IdPartition};
+#[cfg(feature = "dashboard")]
+use control_frontend::dashboard::{self, http::Dashboard};
+use control_frontend::failover::Failover;
+use control_frontend::faults::{FaultInjector, SharedFaults};
+use control_frontend::fsm;
+use control_frontend::gpstime::GpsTime;
+#[cfg(feature = "novaview")]
+use control_frontend::groundpower::ina219::Ina219;
+#[cfg(feature = "novaview")]
+use control_frontend::idle::backlight::Backlight;
+#[cfg(feature = "novaview")]
+use control_frontend::input::STEP_MAGNITUDE;
+use control_frontend::input::{Binding, InputEvent};
+#[cfg(feature = "eframe")]
+use control_frontend::input::{KeyRepeat, KEY_BINDINGS};
+use control_frontend::layout::{scaling, theme};
+use control_frontend::looptiming::Phase;
 use control_frontend::model::{Model, SharedIdGenerator};
+#[cfg(feature = "notifications")]
+use control_frontend::notifications::{self, Notifier};
 use control_frontend::observables::AdcGain;
+use control_frontend::pendant::Pendant;
+use control_frontend::ports;
 use control_frontend::render::render;
 use control_frontend::rqprotocol::Node;
-use control_frontend::telemetry::{process_raw_telemetry_data, NRFConnector, ZMQPublisher};
+use control_frontend::rqspec;
+use control_frontend::run::RunId;
+#[cfg(feature = "novaview")]
+use control_frontend::safety::gpio::GpioSafetyInput;
+#[cfg(not(feature = "novaview"))]
+use control_frontend::safety::MockSafetyInput;
+use control_frontend::safety::SafetyInput;
+use control_frontend::sequence::Sequencer;
+use control_frontend::shutdown;
+use control_frontend::tcpbridge::Transport;
+use control_frontend::telemetry::retention::{spill_path, TelemetryStore};
+use control_frontend::telemetry::{NRFConnector, ZMQPublisher};
 #[cfg(feature = "novaview")]
 use control_frontend::timestep::TimeStep;
+use crossbeam_channel::Receiver;
 
-use control_frontend::recorder::Recorder;
+use control_frontend::recorder::{Recorder, RecorderStatus};
+use control_frontend::recovery::Ping;
 
 #[cfg(feature = "e32")]
 use control_frontend::ebyte::E32Connection;
 #[cfg(not(feature = "e32"))]
 use control_frontend::ebytemock::E32Connection;
 
+#[cfg(feature = "eframe")]
 use egui::Key;
 
 #[cfg(feature = "novaview")]
@@ -40,25 +79,35 @@ use log::{error, info};
 #[cfg(feature = "novaview")]
 use sdl2::event::{Event, WindowEvent};
 
-const SCREEN_WIDTH: u32 = 1024;
-const SCREEN_HEIGHT: u32 = 600;
-
 #[cfg(not(feature = "novaview"))]
 const DEVICE: &str = "/dev/serial/by-id/usb-FTDI_FT232R_USB_UART_A50285BI-if00-port0";
 #[cfg(feature = "novaview")]
 const DEVICE: &str = "/dev/ttyAMA3";
+#[cfg(feature = "novaview")]
+const GROUND_POWER_DEVICE: &str = "/dev/i2c-1";
+#[cfg(feature = "novaview")]
+const GROUND_POWER_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+#[cfg(feature = "novaview")]
+const SAFETY_KEY_CHIP: &str = "/dev/gpiochip0";
+#[cfg(feature = "novaview")]
+const SAFETY_KEY_LINE: u32 = 26;
+#[cfg(feature = "novaview")]
+const LONG_PRESS: std::time::Duration = std::time::Duration::from_secs(1);
+#[cfg(feature = "novaview")]
+const ABORT_PRESS: std::time::Duration = std::time::Duration::from_secs(2);
 
 fn serial_port_path() -> Option<String> {
     if std::path::Path::new(DEVICE).exists() {
         return Some(DEVICE.to_string());
     }
-    serialport::available_ports().ok().and_then(|ports| {
-        if ports.len() == 1 {
-            Some(ports[0].port_name.clone())
-        } else {
-            None
-        }
-    })
+    ports::preselect(&ports::available(), ports::remembered().as_deref())
+}
+
+fn bind_pendant(args: &ProgramArgs) -> Option<Pendant> {
+    let (port, key) = args.pendant()?;
+    Pendant::bind(port, key)
+        .map_err(|e| error!("Can't listen for the pendant, {}", e))
+        .ok()
 }
 
 #[cfg(feature = "eframe")]
@@ -66,26 +115,67 @@ fn main() -> Result<(), eframe::Error> {
     simple_logger::init_with_env().unwrap();
 
     let id_generator = SharedIdGenerator::default();
-    let (me, target_red_queen) = (Node::LaunchControl, Node::RedQueen(b'B'));
+    let args = match ProgramArgs::parse().with_config() {
+        Ok(args) => args,
+        Err(e) => {
+            error!("Invalid config, {:?}", e);
+            std::process::exit(1);
+        }
+    };
+    let (me, target_red_queen) = (Node::LaunchControl, args.profile.target());
+    if let Some(path) = &args.export_fsm {
+        fsm::export(path).expect("Can't export the state machine");
+        return Ok(());
+    }
+    if let Some(directory) = &args.export_protocol {
+        rqspec::export(directory).expect("Can't export the protocol");
+        return Ok(());
+    }
     let options = eframe::NativeOptions {
-        initial_window_size: Some(egui::vec2(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32)),
+        initial_window_size: Some(egui::vec2(
+            args.window_width as f32,
+            args.window_height as f32,
+        )),
         ..Default::default()
     };
-    let args = ProgramArgs::parse();
+    let run = RunId::generate();
+    info!("Run {} ({})", run.slug(), run);
     let recorder = if args.dont_record {
-        Recorder::new(None)
+        Recorder::new_with_capture(vec![], args.capture.clone())
     } else {
-        Recorder::new_with_default_file()
+        Recorder::new_with_default_file(&run, args.mirror_recording.as_ref(), args.capture.clone())
     };
     let recorder_path = recorder.path.clone();
-    let conn = E32Connection::new(
-        id_generator.clone(),
+    let recording = recorder.status.clone();
+    let clock = clock::system();
+    let mut conn = E32Connection::new(
+        SharedIdGenerator::new(IdPartition::Keepalive),
         me.clone(),
         target_red_queen.clone(),
+        args.profile,
         recorder,
+        clock.clone(),
     )
     .unwrap();
-    let nrf_connector = control_frontend::telemetry::create();
+    if let Transport::Tcp(_) = args.transport {
+        conn.use_bridge(args.bridge_latency());
+    }
+    conn.set_drain_idle(args.drain_idle());
+    if !args.poll_nodes.is_empty() {
+        conn.set_poll_nodes(args.poll_nodes.clone());
+    }
+    let faults = args.faults();
+    let conn = FaultInjector::new(conn, faults.clone(), clock.clone());
+    let nrf_connector = if args.simulate_telemetry {
+        control_frontend::telemetry::create_simulated(args.sim_config(), clock.clone())
+    } else {
+        control_frontend::telemetry::create(
+            clock.clone(),
+            args.relay_client(),
+            &args.relay_replay_uri,
+            &args.telemetry_roster,
+        )
+    };
     eframe::run_native(
         "Launch Control",
         options,
@@ -93,10 +183,14 @@ fn main() -> Result<(), eframe::Error> {
             Box::new(LaunchControlApp::new(
                 id_generator,
                 conn,
+                faults,
                 args,
                 recorder_path,
+                recording,
+                run,
                 nrf_connector,
                 None,
+                clock,
             ))
         }),
     )
@@ -110,58 +204,365 @@ where
     model: Model<C, Id>,
     nrf_connector: Rc<RefCell<dyn NRFConnector>>,
     publisher: Option<ZMQPublisher>,
+    args: ProgramArgs,
+    config: Option<ConfigWatcher>,
+    safety_input: Option<Box<dyn SafetyInput>>,
+    pendant: Option<Pendant>,
+    pings: Receiver<Ping>,
+    #[cfg(feature = "novaview")]
+    ground_power: Option<Ina219>,
+    #[cfg(feature = "novaview")]
+    ground_power_read: Instant,
+    #[cfg(feature = "novaview")]
+    camera: Option<Camera>,
+    #[cfg(feature = "novaview")]
+    backlight: Option<Backlight>,
+    #[cfg(feature = "dashboard")]
+    dashboard: Option<Dashboard>,
+    #[cfg(feature = "notifications")]
+    notifier: Notifier,
+    speaker: Speaker,
+    #[cfg(feature = "eframe")]
+    left_repeat: KeyRepeat,
+    #[cfg(feature = "eframe")]
+    right_repeat: KeyRepeat,
 }
 
 impl<C: Connection, Id: Iterator<Item = usize>> LaunchControlApp<C, Id> {
     fn new(
         id_generator: Id,
         conn: C,
+        faults: SharedFaults,
         args: ProgramArgs,
         recorder_path: Option<PathBuf>,
+        recording: RecorderStatus,
+        run: RunId,
         nrf_connector: Rc<RefCell<dyn NRFConnector>>,
         publisher: Option<ZMQPublisher>,
+        clock: SharedClock,
     ) -> Self {
-        let (me, target_red_queen) = (Node::LaunchControl, Node::RedQueen(b'B'));
-        let start_time = Instant::now();
-
+        let (me, target_red_queen) = (Node::LaunchControl, args.profile.target());
         let consort =
-            Consort::new_with_id_generator(me, target_red_queen, start_time, id_generator);
-        let port_path = args
-            .port
-            .or_else(|| serial_port_path())
-            .expect("No serial port found");
-        info!("Opening E32 {}", port_path);
-        let model = Model::new(
+            Consort::new_with_id_generator(me, target_red_queen, clock.clone(), id_generator);
+        #[cfg(feature = "eframe")]
+        let repeat_config = args.repeat_config();
+        let config = args.config.as_deref().map(ConfigWatcher::new);
+        let port_path = match &args.transport {
+            Transport::Tcp(address) => Some(address.clone()),
+            Transport::Serial => args.port.clone().or_else(|| serial_port_path()),
+        };
+        match &port_path {
+            Some(port) => info!("Opening E32 {}", port),
+            None => info!("No serial port to assume, picking one"),
+        }
+        let failover = Failover::new(
+            port_path.as_deref().unwrap_or_default(),
+            args.secondary_port.as_deref(),
+            args.failover_after,
+        );
+        #[cfg(feature = "novaview")]
+        let safety_input = GpioSafetyInput::new(SAFETY_KEY_CHIP, SAFETY_KEY_LINE)
+            .map(|input| Box::new(input) as Box<dyn SafetyInput>)
+            .map_err(|e| error!("Can't open safety key GPIO, {}", e))
+            .ok();
+        #[cfg(not(feature = "novaview"))]
+        let safety_input: Option<Box<dyn SafetyInput>> = Some(Box::new(MockSafetyInput::default()));
+        let pendant = bind_pendant(&args);
+        let telemetry_spill = args
+            .spill_telemetry
+            .as_deref()
+            .map(|directory| spill_path(directory, &run));
+        let mut model = Model::new(
             consort,
             conn,
-            start_time,
-            &port_path,
+            clock,
+            failover,
             &AdcGain::Gain32,
-            args.start_with,
+            args.start_with.clone(),
+            args.privacy,
             recorder_path,
+            recording,
+            run,
             nrf_connector.clone(),
         );
+        model.sequencer = Sequencer::new(args.sequences.clone());
+        model.export_directory = args.export_sessions.clone();
+        model.language = args.language;
+        model.set_profile(args.profile);
+        model.filters = args.filters.clone();
+        model.plot_ranges = args.plot_ranges.clone();
+        model.units = args.units;
+        model.calibration.set_configured(args.imu_ranges.clone());
+        model.set_roster(args.telemetry_roster.clone());
+        model.gps_time = args.gpsd.as_deref().map(GpsTime::start);
+        model.tab_navigation = args.tab_navigation();
+        model.freshness = args.freshness();
+        model.accessibility = args.accessibility();
+        model.consort.set_backoff_policy(args.backoff_policy());
+        model.consort.set_link_keys(args.link_keys());
+        model.conformance.set_strict(args.strict);
+        model.set_key_entry_timeout(args.key_entry_timeout());
+        model.set_launch_window(args.launch_window.clone());
+        model.postmortem.set_config(&args);
+        if let Some(path) = &args.record_input {
+            model.record_input(path);
+        }
+        if let Some(path) = &args.play_input {
+            model.play_input(path);
+        }
+        model.auto_record.policy = args.auto_record_policy();
+        model.idle.set_policy(args.idle_policy());
+        model.announcer.set_events(args.announce.clone());
+        model.set_poll_nodes(&args.poll_nodes);
+        model.annotator.set_labels(args.mark_labels.clone());
+        model.faults = faults;
+        if port_path.is_none() {
+            model.toggle_port_picker(ports::available());
+        }
+        model.telemetry = TelemetryStore::new(
+            args.retention_policy(),
+            telemetry_spill.as_deref(),
+            Some(run),
+        );
+        if let Some(path) = &args.reference_thrust {
+            model.load_reference_thrust(path);
+        }
+        #[cfg(feature = "novaview")]
+        let camera = args.camera.as_deref().and_then(|device| {
+            Camera::new(device, args.camera_clip())
+                .map_err(|e| error!("Can't open camera, {}", e))
+                .ok()
+        });
+        #[cfg(feature = "novaview")]
+        if camera.is_some() {
+            model.enable_snapshots();
+        }
 
+        let pings = model.bus.pings.subscribe();
+        #[cfg(feature = "notifications")]
+        let notifier = Notifier::new(&model.bus);
+        let speaker = Speaker::new(args.voice_samples.clone());
         Self {
             model,
             nrf_connector,
             publisher,
+            args,
+            config,
+            safety_input,
+            pendant,
+            pings,
+            #[cfg(feature = "novaview")]
+            ground_power: Ina219::new(GROUND_POWER_DEVICE)
+                .map_err(|e| error!("Can't open INA219, {}", e))
+                .ok(),
+            #[cfg(feature = "novaview")]
+            ground_power_read: Instant::now(),
+            #[cfg(feature = "novaview")]
+            camera,
+            #[cfg(feature = "novaview")]
+            backlight: Backlight::find()
+                .map_err(|e| error!("Can't find the backlight, {}", e))
+                .ok(),
+            #[cfg(feature = "dashboard")]
+            dashboard: args.dashboard.as_deref().and_then(|address| {
+                Dashboard::start(address)
+                    .map_err(|e| error!("Can't serve dashboard, {}", e))
+                    .ok()
+            }),
+            #[cfg(feature = "notifications")]
+            notifier,
+            speaker,
+            #[cfg(feature = "eframe")]
+            left_repeat: KeyRepeat::new(repeat_config.clone()),
+            #[cfg(feature = "eframe")]
+            right_repeat: KeyRepeat::new(repeat_config),
+        }
+    }
+
+    fn watch_config(&mut self) {
+        let result = match &mut self.config {
+            Some(config) => config.poll(Instant::now()),
+            None => None,
+        };
+        match result {
+            Some(Ok(config)) => {
+                let pendant = self.args.pendant();
+                let reload = config.apply(&mut self.args);
+                info!("Config reloaded, {:?}", reload);
+                if self.args.pendant() != pendant {
+                    // Frees the port first, should only the key change
+                    self.pendant = None;
+                    self.pendant = bind_pendant(&self.args);
+                }
+                #[cfg(feature = "eframe")]
+                {
+                    self.left_repeat = KeyRepeat::new(self.args.repeat_config());
+                    self.right_repeat = KeyRepeat::new(self.args.repeat_config());
+                }
+                self.model.reconfigure(&self.args, reload);
+            }
+            Some(Err(e)) => {
+                error!("Config rejected, {}", e);
+                self.model.reject_config(e.to_string());
+            }
+            None => {}
+        }
+    }
+
+    // Back into the config file, so they survive a restart. The
+    // watcher then sees nothing new, the args already have them.
+    fn store_settings(&mut self) {
+        // Per machine, not in the config file
+        if let Some(port) = self.model.port_picker.take_chosen() {
+            self.args.port = Some(port.clone());
+            if let Err(e) = ports::remember(&port) {
+                error!("Remembering the serial port failed, {}", e);
+            }
+        }
+        if let Some(filters) = self.model.take_changed_filters() {
+            self.args.filters = filters.clone();
+            if let Some(path) = &self.args.config {
+                if let Err(e) = config::store_filters(path, &filters) {
+                    error!("Storing plot filters failed, {}", e);
+                }
+            }
+        }
+        if let Some(ranges) = self.model.take_changed_imu_ranges() {
+            self.args.imu_ranges = ranges.clone();
+            if let Some(path) = &self.args.config {
+                if let Err(e) = config::store_imu_ranges(path, &ranges) {
+                    error!("Storing IMU ranges failed, {}", e);
+                }
+            }
+        }
+    }
+
+    // Through the speaker, like the countdown
+    fn ping_operator(&mut self) {
+        for ping in self.pings.try_iter() {
+            let heard = Announcement::Heard {
+                node: ping.node,
+                again: ping.resumed,
+            };
+            self.speaker.say(heard, self.model.language);
+        }
+    }
+
+    #[cfg(feature = "dashboard")]
+    fn publish_dashboard(&mut self) {
+        let now = Instant::now();
+        if let Some(server) = &mut self.dashboard {
+            if server.due(now) {
+                server.publish(dashboard::publish(&self.model), now);
+            }
+        }
+    }
+
+    #[cfg(feature = "notifications")]
+    fn notify_desktop(&mut self) {
+        for notification in self.notifier.poll() {
+            if let Err(e) = notifications::show(&notification) {
+                error!("Showing notification failed, {}", e);
+            }
+        }
+    }
+
+    fn announce(&mut self) {
+        for announcement in self.model.take_announcements() {
+            self.speaker.say(announcement, self.model.language);
+        }
+    }
+
+    // Merged after the keys of this frame
+    fn poll_pendant(&mut self, bindings: &mut Vec<Binding>) {
+        if let Some(pendant) = &mut self.pendant {
+            bindings.extend(pendant.poll().into_iter().map(Binding::Event));
+        }
+    }
+
+    // Without a readable input the key counts as absent
+    fn read_safety_key(&mut self) {
+        let reading = match &mut self.safety_input {
+            Some(input) => input.key_present(),
+            None => Err(anyhow::anyhow!("no safety key input")),
+        };
+        self.model.safety_key.feed(reading);
+    }
+
+    #[cfg(feature = "novaview")]
+    fn read_ground_power(&mut self) {
+        let now = Instant::now();
+        if now - self.ground_power_read < GROUND_POWER_INTERVAL {
+            return;
+        }
+        self.ground_power_read = now;
+        if let Some(ina219) = &mut self.ground_power {
+            match ina219.read() {
+                Ok(reading) => self.model.ground_power.feed(reading),
+                Err(e) => error!("Reading INA219 failed, {}", e),
+            }
+        }
+    }
+
+    // Dimmed in standby
+    #[cfg(feature = "novaview")]
+    fn dim_backlight(&mut self) {
+        if let Some(backlight) = &mut self.backlight {
+            if let Err(e) = backlight.set(self.model.idle.brightness_percent()) {
+                error!("Setting the backlight failed, {}", e);
+            }
+        }
+    }
+
+    #[cfg(feature = "novaview")]
+    fn take_snapshots(&mut self) {
+        if let (Some(camera), Some(snapshots)) = (&mut self.camera, &mut self.model.snapshots) {
+            snapshots.update();
+            // The others stay queued while the camera is busy
+            while !camera.is_busy() {
+                let Some(snapshot) = snapshots.pop() else {
+                    break;
+                };
+                match camera.take(&snapshot) {
+                    Ok(path) => self.model.journal.record(format!(
+                        "Snapshot of {} saved to {:?}",
+                        snapshot.marker.name(),
+                        path
+                    )),
+                    Err(e) => error!("Taking snapshot failed, {}", e),
+                }
+            }
         }
     }
 
     #[cfg(feature = "novaview")]
-    fn update(&mut self, input_events: &Vec<InputEvent>, ctx: &egui::Context) {
-        use control_frontend::telemetry::process_raw_telemetry_data;
+    fn update(&mut self, bindings: &Vec<Binding>, ctx: &egui::Context) {
+        let mut bindings = bindings.clone();
+        self.poll_pendant(&mut bindings);
 
         let telemetry_data = self.nrf_connector.borrow_mut().drive();
         if let Some(ref mut publisher) = self.publisher {
             publisher.publish_telemetry_data(&telemetry_data);
         }
-        self.model
-            .process_telemetry_data(process_raw_telemetry_data(&telemetry_data));
-        self.model.drive(Instant::now()).unwrap();
+        self.read_ground_power();
+        self.read_safety_key();
+        self.watch_config();
+        self.store_settings();
+        // Starts the frame budget the telemetry is processed in
+        self.model.drive().unwrap();
+        self.model.process_raw_telemetry(&telemetry_data);
+        self.ping_operator();
+        #[cfg(feature = "dashboard")]
+        self.publish_dashboard();
+        self.announce();
+        self.take_snapshots();
+        self.dim_backlight();
+        scaling::apply(ctx, self.args.ui_scale);
+        theme::apply(ctx, self.args.theme);
         // Get the egui context and begin drawing the frame
-        // Draw an egui window
+        // Draw an egui window, tessellating and painting it
+        // happens after and isn't part of the render timing
+        let rendering = Instant::now();
         egui::Area::new("launch_control")
             .fixed_pos([0.0, 0.0])
             .constrain(true)
@@ -169,43 +570,83 @@ impl<C: Connection, Id: Iterator<Item = usize>> LaunchControlApp<C, Id> {
             .show(&ctx, |ui| {
                 render(ui, &self.model);
             });
-        self.model.process_input_events(&input_events);
+        self.model
+            .loop_timing
+            .record(Phase::Render, rendering.elapsed());
+        self.model.process_bindings(&bindings);
+    }
+}
+
+// Quitting or a signal, anything else leaves the journal without its end
+impl<C: Connection, Id: Iterator<Item = usize>> Drop for LaunchControlApp<C, Id> {
+    fn drop(&mut self) {
+        // Another panic in the shutdown would abort, the
+        // workers are left to their own Drop impls
+        if std::thread::panicking() {
+            error!("Exiting on a panic, skipping the shutdown");
+            return;
+        }
+        let report = self.model.shutdown(shutdown::JOIN_TIMEOUT);
+        if !report.is_clean() {
+            error!("Exiting without {}", report.stuck.join(", "));
+        }
     }
 }
 
 #[cfg(feature = "eframe")]
 impl<C: Connection, Id: Iterator<Item = usize>> eframe::App for LaunchControlApp<C, Id> {
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        let mut input_events = vec![];
+        let mut bindings = vec![];
+        let now = Instant::now();
         ctx.input(|i| {
-            if i.key_pressed(Key::ArrowRight) {
-                input_events.push(InputEvent::Right(10));
-            }
-            if i.key_pressed(Key::ArrowLeft) {
-                input_events.push(InputEvent::Left(10));
-            }
-            if i.key_pressed(Key::Enter) {
-                input_events.push(InputEvent::Enter);
-            }
-            if i.key_pressed(Key::Space) {
-                input_events.push(InputEvent::Enter);
-            }
-            if i.key_pressed(Key::Backspace) {
-                input_events.push(InputEvent::Back);
-            }
-            if i.key_pressed(Key::Escape) {
-                frame.close();
+            for (key, binding) in KEY_BINDINGS.iter() {
+                let key = egui_key(*key);
+                // Arrows repeat while held
+                match binding {
+                    Binding::Event(InputEvent::Left(_)) => {
+                        if let Some(magnitude) = self.left_repeat.update(i.key_down(key), now) {
+                            bindings.push(Binding::Event(InputEvent::Left(magnitude)));
+                        }
+                    }
+                    Binding::Event(InputEvent::Right(_)) => {
+                        if let Some(magnitude) = self.right_repeat.update(i.key_down(key), now) {
+                            bindings.push(Binding::Event(InputEvent::Right(magnitude)));
+                        }
+                    }
+                    _ => {
+                        if i.key_pressed(key) {
+                            bindings.push(*binding);
+                        }
+                    }
+                }
             }
         });
+        if bindings.contains(&Binding::Quit) {
+            frame.close();
+        }
+        self.poll_pendant(&mut bindings);
         let telemetry_data = self.nrf_connector.borrow_mut().drive();
         if let Some(ref mut publisher) = self.publisher {
             publisher.publish_telemetry_data(&telemetry_data);
         }
-        self.model
-            .process_telemetry_data(&process_raw_telemetry_data(&telemetry_data));
-        self.model.drive(Instant::now()).unwrap();
+        self.read_safety_key();
+        self.watch_config();
+        self.store_settings();
+        // Starts the frame budget the telemetry is processed in
+        self.model.drive().unwrap();
+        self.model.process_raw_telemetry(&telemetry_data);
+        self.ping_operator();
+        #[cfg(feature = "dashboard")]
+        self.publish_dashboard();
+        #[cfg(feature = "notifications")]
+        self.notify_desktop();
+        self.announce();
+        scaling::apply(ctx, self.args.ui_scale);
+        theme::apply(ctx, self.args.theme);
         // Get the egui context and begin drawing the frame
-        // Draw an egui window
+        // Draw an egui window, tessellating and painting it
+        // happens after and isn't part of the render timing
+        let rendering = Instant::now();
         egui::Area::new("launch_control")
             .fixed_pos([0.0, 0.0])
             .constrain(true)
@@ -213,7 +654,82 @@ impl<C: Connection, Id: Iterator<Item = usize>> eframe::App for LaunchControlApp
             .show(&ctx, |ui| {
                 render(ui, &self.model);
             });
-        self.model.process_input_events(&input_events);
+        self.model
+            .loop_timing
+            .record(Phase::Render, rendering.elapsed());
+        self.model.process_bindings(&bindings);
+    }
+}
+
+#[cfg(feature = "eframe")]
+fn egui_key(key: control_frontend::input::Key) -> Key {
+    use control_frontend::input::Key as K;
+    match key {
+        K::ArrowLeft => Key::ArrowLeft,
+        K::ArrowRight => Key::ArrowRight,
+        K::Enter => Key::Enter,
+        K::Space => Key::Space,
+        K::Backspace => Key::Backspace,
+        K::Escape => Key::Escape,
+        K::S => Key::S,
+        K::T => Key::T,
+        K::M => Key::M,
+        K::H => Key::H,
+        K::D => Key::D,
+        K::P => Key::P,
+        K::K => Key::K,
+        K::F => Key::F,
+        K::G => Key::G,
+        K::L => Key::L,
+        K::A => Key::A,
+        K::R => Key::R,
+        K::N => Key::N,
+        K::B => Key::B,
+        K::O => Key::O,
+        K::I => Key::I,
+        K::C => Key::C,
+        K::U => Key::U,
+        K::X => Key::X,
+        K::J => Key::J,
+        K::Z => Key::Z,
+        K::V => Key::V,
+    }
+}
+
+#[cfg(feature = "novaview")]
+fn input_key(keycode: sdl2::keyboard::Keycode) -> Option<control_frontend::input::Key> {
+    use control_frontend::input::Key as K;
+    use sdl2::keyboard::Keycode;
+    match keycode {
+        Keycode::Left => Some(K::ArrowLeft),
+        Keycode::Right => Some(K::ArrowRight),
+        Keycode::Return => Some(K::Enter),
+        Keycode::Space => Some(K::Space),
+        Keycode::Backspace => Some(K::Backspace),
+        Keycode::Escape => Some(K::Escape),
+        Keycode::S => Some(K::S),
+        Keycode::T => Some(K::T),
+        Keycode::M => Some(K::M),
+        Keycode::H => Some(K::H),
+        Keycode::D => Some(K::D),
+        Keycode::P => Some(K::P),
+        Keycode::K => Some(K::K),
+        Keycode::F => Some(K::F),
+        Keycode::G => Some(K::G),
+        Keycode::L => Some(K::L),
+        Keycode::A => Some(K::A),
+        Keycode::R => Some(K::R),
+        Keycode::N => Some(K::N),
+        Keycode::B => Some(K::B),
+        Keycode::O => Some(K::O),
+        Keycode::I => Some(K::I),
+        Keycode::C => Some(K::C),
+        Keycode::U => Some(K::U),
+        Keycode::X => Some(K::X),
+        Keycode::J => Some(K::J),
+        Keycode::Z => Some(K::Z),
+        Keycode::V => Some(K::V),
+        _ => None,
     }
 }
 
@@ -268,9 +784,8 @@ fn get_input_events(
     video: &mut sdl2::VideoSubsystem,
     window: &sdl2::video::Window,
     joystick: &mut Option<JoystickProcessor>,
-) -> (bool, Vec<InputEvent>) {
-    let mut input_events = vec![];
-    let mut quit = false;
+) -> Vec<Binding> {
+    let mut bindings = vec![];
     // Handle sdl events
     for event in event_pump.poll_iter() {
         // Handle sdl events
@@ -282,35 +797,16 @@ fn get_input_events(
             } => {
                 if window_id == window.id() {
                     if let WindowEvent::Close = win_event {
-                        quit = true;
+                        bindings.push(Binding::Quit);
                     }
                 }
             }
             Event::KeyDown {
-                keycode: Some(sdl2::keyboard::Keycode::Escape),
+                keycode: Some(keycode),
                 ..
-            } => quit = true,
-            Event::KeyDown { keycode, .. } => {
-                if let Some(keycode) = keycode {
-                    match keycode {
-                        sdl2::keyboard::Keycode::Space => {
-                            input_events.push(InputEvent::Enter);
-                        }
-                        sdl2::keyboard::Keycode::Return => {
-                            input_events.push(InputEvent::Enter);
-                        }
-                        sdl2::keyboard::Keycode::Backspace => {
-                            input_events.push(InputEvent::Back);
-                        }
-                        sdl2::keyboard::Keycode::Left => {
-                            input_events.push(InputEvent::Left(10));
-                        }
-                        sdl2::keyboard::Keycode::Right => {
-                            input_events.push(InputEvent::Right(10));
-                        }
-                        sdl2::keyboard::Keycode::S => input_events.push(InputEvent::Send),
-                        _ => {}
-                    }
+            } => {
+                if let Some(binding) = input_key(keycode).and_then(Binding::for_key) {
+                    bindings.push(binding);
                 }
             }
             _ => {}
@@ -319,10 +815,10 @@ fn get_input_events(
         platform.handle_event(&event, sdl, video);
     }
     if let Some(joystick) = joystick {
-        joystick.produce_events(&mut input_events);
+        joystick.produce_events(&mut bindings);
     }
 
-    (quit, input_events)
+    bindings
 }
 
 #[cfg(feature = "novaview")]
@@ -330,8 +826,14 @@ struct JoystickProcessor {
     joystick: Joystick,
     position: i64,
     trigger: i64,
-    right_pressed: bool,
-    left_pressed: bool,
+    // Enter is only sent on release, holding marks the moment
+    right_pressed_since: Option<Instant>,
+    mark_press: bool,
+    // Back is only sent on release, holding opens the help
+    // and holding on aborts
+    left_pressed_since: Option<Instant>,
+    long_press: bool,
+    abort_press: bool,
 }
 
 #[cfg(feature = "novaview")]
@@ -341,12 +843,15 @@ impl JoystickProcessor {
             joystick,
             position: 0,
             trigger: 0,
-            right_pressed: false,
-            left_pressed: false,
+            right_pressed_since: None,
+            mark_press: false,
+            left_pressed_since: None,
+            long_press: false,
+            abort_press: false,
         }
     }
 
-    pub fn produce_events(&mut self, input_events: &mut Vec<InputEvent>) {
+    pub fn produce_events(&mut self, bindings: &mut Vec<Binding>) {
         let axis0_value = self.joystick.axis(0).unwrap();
         // deadzone
         if axis0_value.abs() > 10 {
@@ -355,22 +860,53 @@ impl JoystickProcessor {
         if (self.trigger - self.position).abs() > 1000_000 / 40 {
             let diff = self.trigger - self.position;
             if diff > 0 {
-                input_events.push(InputEvent::Right(10));
+                bindings.push(Binding::Event(InputEvent::Right(STEP_MAGNITUDE)));
             } else {
-                input_events.push(InputEvent::Left(10));
+                bindings.push(Binding::Event(InputEvent::Left(STEP_MAGNITUDE)));
             }
             self.trigger = self.position;
         }
         let lbp = self.joystick.button(1).unwrap();
         let rbp = self.joystick.button(0).unwrap();
-        if !self.left_pressed && lbp {
-            input_events.push(InputEvent::Back);
+        match (self.left_pressed_since, lbp) {
+            (None, true) => self.left_pressed_since = Some(Instant::now()),
+            (Some(since), true) => {
+                if !self.long_press && since.elapsed() > LONG_PRESS {
+                    self.long_press = true;
+                    bindings.push(Binding::Help);
+                }
+                if !self.abort_press && since.elapsed() > ABORT_PRESS {
+                    self.abort_press = true;
+                    bindings.push(Binding::Abort);
+                }
+            }
+            (Some(_), false) => {
+                if !self.long_press {
+                    bindings.push(Binding::Event(InputEvent::Back));
+                }
+                self.left_pressed_since = None;
+                self.long_press = false;
+                self.abort_press = false;
+            }
+            (None, false) => {}
         }
-        self.left_pressed = lbp;
-        if !self.right_pressed && rbp {
-            input_events.push(InputEvent::Enter);
+        match (self.right_pressed_since, rbp) {
+            (None, true) => self.right_pressed_since = Some(Instant::now()),
+            (Some(since), true) => {
+                if !self.mark_press && since.elapsed() > LONG_PRESS {
+                    self.mark_press = true;
+                    bindings.push(Binding::Mark);
+                }
+            }
+            (Some(_), false) => {
+                if !self.mark_press {
+                    bindings.push(Binding::Event(InputEvent::Enter));
+                }
+                self.right_pressed_since = None;
+                self.mark_press = false;
+            }
+            (None, false) => {}
         }
-        self.right_pressed = rbp;
     }
 }
 
@@ -382,25 +918,77 @@ fn run() -> anyhow::Result<()> {
 
     simple_logger::init_with_env().unwrap();
     let id_generator = SharedIdGenerator::default();
-    let (me, target_red_queen) = (Node::LaunchControl, Node::RedQueen(b'B'));
-    let args = ProgramArgs::parse();
-    let recorder = Recorder::new(None);
-    let conn = E32Connection::new(
-        id_generator.clone(),
+    let args = ProgramArgs::parse().with_config()?;
+    let (me, target_red_queen) = (Node::LaunchControl, args.profile.target());
+    if let Some(path) = &args.export_fsm {
+        return Ok(fsm::export(path)?);
+    }
+    if let Some(directory) = &args.export_protocol {
+        return rqspec::export(directory);
+    }
+    let (window_width, window_height) = (args.window_width, args.window_height);
+    let run = RunId::generate();
+    info!("Run {} ({})", run.slug(), run);
+    let recorder = Recorder::new_with_capture(vec![], args.capture.clone());
+    let recording = recorder.status.clone();
+    let clock = clock::system();
+    let mut conn = E32Connection::new(
+        SharedIdGenerator::new(IdPartition::Keepalive),
         me.clone(),
         target_red_queen.clone(),
+        args.profile,
         recorder,
+        clock.clone(),
     )
     .unwrap();
-    let nrf_connector = control_frontend::telemetry::create();
-    let mut publisher = ZMQPublisher::new("tcp://0.0.0.0:2424")?;
+    if let Transport::Tcp(_) = args.transport {
+        conn.use_bridge(args.bridge_latency());
+    }
+    conn.set_drain_idle(args.drain_idle());
+    if !args.poll_nodes.is_empty() {
+        conn.set_poll_nodes(args.poll_nodes.clone());
+    }
+    let faults = args.faults();
+    let conn = FaultInjector::new(conn, faults.clone(), clock.clone());
+    let nrf_connector = if args.simulate_telemetry {
+        control_frontend::telemetry::create_simulated(args.sim_config(), clock.clone())
+    } else {
+        control_frontend::telemetry::create(
+            clock.clone(),
+            args.relay_client(),
+            &args.relay_replay_uri,
+            &args.telemetry_roster,
+        )
+    };
+    let mut publisher = ZMQPublisher::new(
+        "tcp://0.0.0.0:2424",
+        args.relay_format,
+        args.relay_batch,
+        args.relay_secret_key.as_deref(),
+        &args.relay_allowed_clients,
+    )?;
+    // Subscribers then just don't get a backfill
+    if let Err(e) = publisher.serve_replay(&args.relay_replay_bind, args.relay_backlog()) {
+        error!(
+            "Can't serve telemetry replays on {}, {}",
+            args.relay_replay_bind, e
+        );
+    }
+    if let Some(interval) = args.relay_summary() {
+        publisher.publish_summaries(interval, args.imu_ranges.clone());
+    }
+    publisher.set_run(run);
     let mut app = LaunchControlApp::new(
         id_generator,
         conn,
+        faults,
         args,
         None,
+        recording,
+        run,
         nrf_connector.clone(),
         Some(publisher),
+        clock,
     );
 
     // Initialize sdl
@@ -414,8 +1002,9 @@ fn run() -> anyhow::Result<()> {
         .map_err(|e| anyhow::anyhow!("Failed to initialize sdl video subsystem: {}", e))?;
     // Create the sdl window
     let window = video
-        .window("Window", SCREEN_WIDTH, SCREEN_HEIGHT)
+        .window("Window", window_width, window_height)
         .opengl()
+        .resizable()
         .position_centered()
         .build()?;
     // Get the sdl event pump
@@ -450,7 +1039,7 @@ fn run() -> anyhow::Result<()> {
     'main: while !(sig_term.load(Ordering::Relaxed) || sig_int.load(Ordering::Relaxed)) {
         sd_notify::notify(true, &[NotifyState::Watchdog])?;
 
-        let (quit, input_events) = get_input_events(
+        let bindings = get_input_events(
             &mut event_pump,
             &mut platform,
             &sdl,
@@ -458,14 +1047,14 @@ fn run() -> anyhow::Result<()> {
             &window,
             &mut joystick,
         );
-        if quit {
+        if bindings.contains(&Binding::Quit) {
             break 'main;
         }
 
         platform.update_time(start_time.elapsed().as_secs_f64());
         let ctx = platform.context();
         mouse.show_cursor(false);
-        app.update(&input_events, &ctx);
+        app.update(&bindings, &ctx);
 
         // Stop drawing the egui frame and get the full output
         let full_output = platform.end_frame(&mut video)?;
@@ -479,6 +1068,7 @@ fn run() -> anyhow::Result<()> {
         timestep.run_this(|_| {});
     }
     info!("Shutdown due to signal");
+    drop(app);
     std::process::exit(0);
 }
 
@@ -1,19 +1,31 @@
 use std::time::Instant;
 
 #[cfg(feature = "novaview")]
-use control_frontend::{rqprotocol::Node, telemetry::create};
+use control_frontend::{clock, rqprotocol::Node, telemetry::create};
 use log::info;
 
 #[cfg(feature = "novaview")]
 fn main() -> anyhow::Result<()> {
     use std::time::Duration;
 
-    use control_frontend::telemetry::ZMQPublisher;
+    use control_frontend::telemetry::{relay, roster::DEFAULT_ROSTER, ZMQPublisher};
 
     simple_logger::init_with_env().unwrap();
     info!("NRF TEST");
-    let mut publisher = ZMQPublisher::new("tcp://0.0.0.0:2424")?;
-    let telemetry = create();
+    let mut publisher = ZMQPublisher::new(
+        "tcp://0.0.0.0:2424",
+        relay::RelayFormat::Binary,
+        relay::DEFAULT_BATCH,
+        // Bench tool without a config, never run it on the range
+        None,
+        &[],
+    )?;
+    let telemetry = create(
+        clock::system(),
+        None,
+        relay::DEFAULT_REPLAY_URI,
+        &DEFAULT_ROSTER,
+    );
     loop {
         publisher.publish_telemetry_data(&telemetry.borrow_mut().drive());
         for node in telemetry.borrow().registered_nodes() {
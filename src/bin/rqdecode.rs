@@ -0,0 +1,33 @@
+use std::path::PathBuf;
+
+use clap::{ArgAction, Parser};
+use control_frontend::observables::Profile;
+use control_frontend::transcript::Transcript;
+
+// Decodes a recording of the launch control into sentences
+// and transactions, for auditing without the GUI.
+#[derive(Parser, Debug)]
+#[clap(version, about, long_about = None)]
+struct DecodeArgs {
+    recording: PathBuf,
+    // One JSON object per line instead of the readable transcript
+    #[clap(long, action = ArgAction::SetTrue)]
+    json: bool,
+    #[clap(long, arg_enum, default_value = "rocket")]
+    profile: Profile,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = DecodeArgs::parse();
+    let data = std::fs::read(&args.recording)?;
+    let transcript = Transcript::decode(&data, args.profile);
+    if args.json {
+        for entry in transcript.entries.iter() {
+            println!("{}", serde_json::to_string(entry)?);
+        }
+        println!("{}", serde_json::to_string(&transcript.summary())?);
+    } else {
+        transcript.write_text(&mut std::io::stdout(), &args.recording)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,329 @@
+// Sends a safe command to each of the polled nodes in turn, e.g. to
+// reset them all before a multi-node test instead of one by one. The
+// command link has one transaction at a time, so the next node gets
+// the command once the last one ACKed, NAKed or timed out. Only
+// commands without any effect on the pyros can be broadcast.
+use crate::observables::AdcGain;
+use crate::rqprotocol::{Command, Node, Response};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BroadcastCommand {
+    Reset,
+    Ping,
+}
+
+pub const COMMANDS: [BroadcastCommand; 2] = [BroadcastCommand::Reset, BroadcastCommand::Ping];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeResult {
+    Pending,
+    Sent,
+    Acked,
+    Naked,
+    TimedOut,
+    // Couldn't go out to the node, e.g. in radio silence
+    NotSent,
+    // The run was cancelled before
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BroadcastRun {
+    pub command: BroadcastCommand,
+    // In the order they are sent to
+    pub results: Vec<(Node, NodeResult)>,
+    reported: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Broadcast {
+    active: bool,
+    nodes: Vec<Node>,
+    pub selected: usize,
+    pub run: Option<BroadcastRun>,
+}
+
+impl BroadcastCommand {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BroadcastCommand::Reset => "Reset",
+            BroadcastCommand::Ping => "Ping",
+        }
+    }
+
+    pub fn command(&self, gain: AdcGain) -> Command {
+        match self {
+            BroadcastCommand::Reset => Command::Reset(gain),
+            BroadcastCommand::Ping => Command::Ping,
+        }
+    }
+
+    fn acknowledged_by(&self, response: &Response) -> bool {
+        matches!(
+            (self, response),
+            (BroadcastCommand::Reset, Response::ResetAck)
+                | (BroadcastCommand::Ping, Response::PingAck)
+        )
+    }
+}
+
+impl NodeResult {
+    pub fn is_done(&self) -> bool {
+        !matches!(self, NodeResult::Pending | NodeResult::Sent)
+    }
+}
+
+impl BroadcastRun {
+    pub fn is_finished(&self) -> bool {
+        self.results.iter().all(|(_, result)| result.is_done())
+    }
+
+    pub fn in_flight(&self) -> Option<Node> {
+        self.results
+            .iter()
+            .find(|(_, result)| *result == NodeResult::Sent)
+            .map(|(node, _)| *node)
+    }
+
+    fn settle(&mut self, result: NodeResult) -> bool {
+        match self
+            .results
+            .iter_mut()
+            .find(|(_, result)| *result == NodeResult::Sent)
+        {
+            Some((_, sent)) => {
+                *sent = result;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn count(&self, wanted: NodeResult) -> usize {
+        self.results
+            .iter()
+            .filter(|(_, result)| *result == wanted)
+            .count()
+    }
+
+    // Once finished, for the journal
+    pub fn summary(&self) -> String {
+        let mut text = format!(
+            "Broadcast {}: {} ACK, {} NAK, {} timed out",
+            self.command.name(),
+            self.count(NodeResult::Acked),
+            self.count(NodeResult::Naked),
+            self.count(NodeResult::TimedOut),
+        );
+        let not_sent = self.count(NodeResult::NotSent);
+        if not_sent > 0 {
+            text.push_str(&format!(", {} not sent", not_sent));
+        }
+        let skipped = self.count(NodeResult::Skipped);
+        if skipped > 0 {
+            text.push_str(&format!(", {} skipped", skipped));
+        }
+        text
+    }
+}
+
+impl Broadcast {
+    pub fn start(&mut self) {
+        self.active = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn set_nodes(&mut self, nodes: Vec<Node>) {
+        self.nodes = nodes;
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn selected(&self) -> BroadcastCommand {
+        COMMANDS[self.selected]
+    }
+
+    pub fn select(&mut self, steps: i64) {
+        if !self.is_running() {
+            self.selected =
+                (self.selected as i64 + steps).rem_euclid(COMMANDS.len() as i64) as usize;
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.run.as_ref().map_or(false, |run| !run.is_finished())
+    }
+
+    // Of the selected command to all nodes
+    pub fn run_selected(&mut self) -> bool {
+        if self.is_running() || self.nodes.is_empty() {
+            return false;
+        }
+        self.run = Some(BroadcastRun {
+            command: self.selected(),
+            results: self
+                .nodes
+                .iter()
+                .map(|node| (*node, NodeResult::Pending))
+                .collect(),
+            reported: false,
+        });
+        true
+    }
+
+    // Nodes still pending won't get the command, the
+    // one in flight has its answer or timeout noted
+    pub fn cancel(&mut self) {
+        if let Some(run) = &mut self.run {
+            for (_, result) in run.results.iter_mut() {
+                if *result == NodeResult::Pending {
+                    *result = NodeResult::Skipped;
+                }
+            }
+        }
+    }
+
+    // Which node to send to next, it's then in flight
+    pub fn next_node(&mut self) -> Option<(Node, BroadcastCommand)> {
+        let run = self.run.as_mut()?;
+        if run.in_flight().is_some() {
+            return None;
+        }
+        let (node, result) = run
+            .results
+            .iter_mut()
+            .find(|(_, result)| *result == NodeResult::Pending)?;
+        *result = NodeResult::Sent;
+        Some((*node, run.command))
+    }
+
+    pub fn in_flight(&self) -> Option<Node> {
+        self.run.as_ref()?.in_flight()
+    }
+
+    // False if nothing was in flight
+    pub fn process_response(&mut self, response: &Response) -> bool {
+        match &mut self.run {
+            Some(run) => {
+                let result = match run.command.acknowledged_by(response) {
+                    true => NodeResult::Acked,
+                    false => NodeResult::Naked,
+                };
+                run.settle(result)
+            }
+            None => false,
+        }
+    }
+
+    pub fn refused(&mut self) -> bool {
+        self.run
+            .as_mut()
+            .map_or(false, |run| run.settle(NodeResult::Naked))
+    }
+
+    pub fn not_sent(&mut self) -> bool {
+        self.run
+            .as_mut()
+            .map_or(false, |run| run.settle(NodeResult::NotSent))
+    }
+
+    pub fn timed_out(&mut self) -> bool {
+        self.run
+            .as_mut()
+            .map_or(false, |run| run.settle(NodeResult::TimedOut))
+    }
+
+    // The summary, only the first time it's asked for
+    pub fn report(&mut self) -> Option<String> {
+        let run = self.run.as_mut()?;
+        if run.reported || !run.is_finished() {
+            return None;
+        }
+        run.reported = true;
+        Some(run.summary())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_broadcast_tracks_each_node() {
+        let nodes = vec![
+            Node::RedQueen(b'A'),
+            Node::RedQueen(b'B'),
+            Node::Farduino(b'C'),
+        ];
+        let mut broadcast = Broadcast::default();
+        assert!(!broadcast.run_selected());
+        broadcast.set_nodes(nodes.clone());
+        assert!(broadcast.run_selected());
+        assert!(!broadcast.run_selected());
+        assert_eq!(
+            broadcast.next_node(),
+            Some((nodes[0], BroadcastCommand::Reset))
+        );
+        // One at a time
+        assert_eq!(broadcast.next_node(), None);
+        assert!(broadcast.process_response(&Response::ResetAck));
+        assert_eq!(
+            broadcast.next_node(),
+            Some((nodes[1], BroadcastCommand::Reset))
+        );
+        assert!(broadcast.refused());
+        assert_eq!(broadcast.in_flight(), None);
+        broadcast.next_node();
+        assert_eq!(broadcast.report(), None);
+        assert!(broadcast.timed_out());
+        assert!(!broadcast.timed_out());
+        assert!(!broadcast.is_running());
+        assert_eq!(
+            broadcast.run.as_ref().unwrap().results,
+            vec![
+                (nodes[0], NodeResult::Acked),
+                (nodes[1], NodeResult::Naked),
+                (nodes[2], NodeResult::TimedOut),
+            ]
+        );
+        assert_eq!(
+            broadcast.report(),
+            Some("Broadcast Reset: 1 ACK, 1 NAK, 1 timed out".into())
+        );
+        assert_eq!(broadcast.report(), None);
+    }
+
+    #[test]
+    fn test_cancel_skips_pending_nodes() {
+        let mut broadcast = Broadcast::default();
+        broadcast.set_nodes(vec![
+            Node::RedQueen(b'A'),
+            Node::RedQueen(b'B'),
+            Node::RedQueen(b'C'),
+        ]);
+        broadcast.select(1);
+        assert_eq!(broadcast.selected(), BroadcastCommand::Ping);
+        broadcast.run_selected();
+        broadcast.next_node();
+        assert!(broadcast.not_sent());
+        broadcast.next_node();
+        broadcast.cancel();
+        assert!(broadcast.is_running());
+        // Other answers than the ACK count as refused
+        assert!(broadcast.process_response(&Response::ResetAck));
+        assert_eq!(broadcast.next_node(), None);
+        assert_eq!(
+            broadcast.report(),
+            Some("Broadcast Ping: 0 ACK, 1 NAK, 0 timed out, 1 not sent, 1 skipped".into())
+        );
+    }
+}
@@ -0,0 +1,79 @@
+// Caps the work the model does per frame, so a burst of telemetry
+// doesn't stall the UI on the Pi. What doesn't fit is left for the
+// next frame, input is always processed right away. Only when work
+// is left over frame after frame do we call it an overload.
+use std::time::Duration;
+
+use crate::clock::Instant;
+
+// About half a frame at 60fps, the rest is for rendering
+pub const FRAME_BUDGET: Duration = Duration::from_millis(8);
+// Half a second at 60fps
+const OVERLOAD_FRAMES: usize = 30;
+
+#[derive(Debug)]
+pub struct FrameBudget {
+    budget: Duration,
+    started: Option<Instant>,
+    deferring_frames: usize,
+}
+
+impl Default for FrameBudget {
+    fn default() -> Self {
+        Self::new(FRAME_BUDGET)
+    }
+}
+
+impl FrameBudget {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            started: None,
+            deferring_frames: 0,
+        }
+    }
+
+    // Deferred means work was left over from the last frame
+    pub fn start(&mut self, now: Instant, deferred: bool) {
+        self.started = Some(now);
+        self.deferring_frames = match deferred {
+            true => self.deferring_frames + 1,
+            false => 0,
+        };
+    }
+
+    pub fn exhausted(&self, now: Instant) -> bool {
+        self.started
+            .map_or(false, |started| now.duration_since(started) > self.budget)
+    }
+
+    pub fn overloaded(&self) -> bool {
+        self.deferring_frames >= OVERLOAD_FRAMES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, SimulatedClock};
+
+    #[test]
+    fn test_overload_needs_consistent_deferring() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut budget = FrameBudget::default();
+        assert!(!budget.exhausted(clock.now()));
+        budget.start(clock.now(), false);
+        clock.advance(FRAME_BUDGET);
+        assert!(!budget.exhausted(clock.now()));
+        clock.advance(Duration::from_millis(1));
+        assert!(budget.exhausted(clock.now()));
+        for _ in 0..OVERLOAD_FRAMES - 1 {
+            budget.start(clock.now(), true);
+        }
+        assert!(!budget.overloaded());
+        budget.start(clock.now(), true);
+        assert!(budget.overloaded());
+        budget.start(clock.now(), false);
+        assert!(!budget.overloaded());
+    }
+}
@@ -0,0 +1,107 @@
+// What happens in the model, for the subsystems that follow it
+// (snapshots, exports, alarms, ...) without the model calling
+// each of them. Every kind of event has its own topic, and every
+// subscriber its own queue, drained whenever it gets around to it.
+// Topics are cheap to clone, so workers on other threads can
+// publish too.
+use std::sync::{Arc, Mutex};
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+use crate::{
+    clock::Instant,
+    journal::Marker,
+    model::Mode,
+    recovery::Ping,
+    rqprotocol::{Node, Response},
+};
+
+pub struct Topic<T> {
+    subscribers: Arc<Mutex<Vec<Sender<T>>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModeChange {
+    pub at: Instant,
+    pub from: Mode,
+    pub to: Mode,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marked {
+    pub marker: Marker,
+    // Seconds into the session
+    pub elapsed: f64,
+    pub text: String,
+}
+
+#[derive(Clone, Default)]
+pub struct EventBus {
+    pub modes: Topic<ModeChange>,
+    // Of the journal
+    pub markers: Topic<Marked>,
+    // Of the target, as processed by the model
+    pub responses: Topic<Response>,
+    // For the operator, while looking for the rocket
+    pub pings: Topic<Ping>,
+    // Of the nodes NAKing a command
+    pub refusals: Topic<Node>,
+}
+
+impl<T> Default for Topic<T> {
+    fn default() -> Self {
+        Self {
+            subscribers: Default::default(),
+        }
+    }
+}
+
+impl<T> Clone for Topic<T> {
+    fn clone(&self) -> Self {
+        Self {
+            subscribers: self.subscribers.clone(),
+        }
+    }
+}
+
+impl<T: Clone> Topic<T> {
+    // Only what is published from now on
+    pub fn subscribe(&self) -> Receiver<T> {
+        let (sender, receiver) = unbounded();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    // Subscribers that went away are forgotten
+    pub fn publish(&self, event: T) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_subscriber_gets_its_own_copy() {
+        let bus = EventBus::default();
+        bus.responses.publish(Response::PingAck);
+        let first = bus.responses.subscribe();
+        let second = bus.clone().responses.subscribe();
+        bus.responses.publish(Response::ResetAck);
+        assert_eq!(
+            first.try_iter().collect::<Vec<_>>(),
+            vec![Response::ResetAck]
+        );
+        drop(first);
+        bus.responses.publish(Response::IgnitionAck);
+        assert_eq!(
+            second.try_iter().collect::<Vec<_>>(),
+            vec![Response::ResetAck, Response::IgnitionAck]
+        );
+        assert_eq!(bus.responses.subscribers.lock().unwrap().len(), 1);
+    }
+}
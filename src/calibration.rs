@@ -0,0 +1,205 @@
+// The ranges the IMUs of the nodes are set to, so their raw values are
+// scaled right instead of assuming 24g/2000dps. The nodes report their
+// ranges in a config packet on boot, the calibration page shows them
+// next to the configured ones and flags a mismatch. The ranges are set
+// per node on the page, or taken over from what a node reported, and
+// stored in the config file.
+use std::collections::HashMap;
+
+use crate::rqprotocol::Node;
+use crate::telemetry::parser::rq2::ImuRanges;
+
+pub const PARAMETERS: [&str; 2] = ["Accelerometer", "Gyroscope"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verification {
+    // No config packet heard yet
+    Unreported,
+    Verified,
+    Mismatch(ImuRanges),
+}
+
+// The calibration page
+#[derive(Debug, Default)]
+pub struct ImuCalibration {
+    active: bool,
+    nodes: Vec<Node>,
+    pub selected_node: usize,
+    pub selected: usize,
+    configured: HashMap<Node, ImuRanges>,
+    reported: HashMap<Node, ImuRanges>,
+    changed: bool,
+}
+
+impl ImuCalibration {
+    pub fn start(&mut self) {
+        self.active = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn set_nodes(&mut self, nodes: Vec<Node>) {
+        self.selected_node = self.selected_node.min(nodes.len().saturating_sub(1));
+        self.nodes = nodes;
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn set_configured(&mut self, configured: HashMap<Node, ImuRanges>) {
+        self.configured = configured;
+    }
+
+    pub fn configured(&self) -> &HashMap<Node, ImuRanges> {
+        &self.configured
+    }
+
+    pub fn ranges(&self, node: &Node) -> ImuRanges {
+        self.configured.get(node).copied().unwrap_or_default()
+    }
+
+    pub fn verification(&self, node: &Node) -> Verification {
+        match self.reported.get(node) {
+            None => Verification::Unreported,
+            Some(reported) if *reported == self.ranges(node) => Verification::Verified,
+            Some(reported) => Verification::Mismatch(*reported),
+        }
+    }
+
+    // Of the nodes reporting other ranges than configured
+    pub fn mismatched(&self) -> Vec<Node> {
+        let mut nodes: Vec<Node> = self
+            .reported
+            .keys()
+            .filter(|node| matches!(self.verification(node), Verification::Mismatch(_)))
+            .copied()
+            .collect();
+        nodes.sort_by_key(|node| node.to_string());
+        nodes
+    }
+
+    // For the journal, when a node reports different ranges than before
+    pub fn report(&mut self, node: Node, ranges: ImuRanges) -> Option<String> {
+        if self.reported.insert(node, ranges) == Some(ranges) {
+            return None;
+        }
+        Some(self.verification_text(&node))
+    }
+
+    fn verification_text(&self, node: &Node) -> String {
+        let configured = self.ranges(node);
+        match self.verification(node) {
+            Verification::Mismatch(reported) => format!(
+                "{} reports IMU ranges {}, configured are {}",
+                node,
+                reported.text(),
+                configured.text()
+            ),
+            _ => format!("{} IMU ranges {} verified", node, configured.text()),
+        }
+    }
+
+    pub fn selected_node(&self) -> Option<Node> {
+        self.nodes.get(self.selected_node).copied()
+    }
+
+    // Through the parameters, then on to the next node
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % PARAMETERS.len();
+        if self.selected == 0 && !self.nodes.is_empty() {
+            self.selected_node = (self.selected_node + 1) % self.nodes.len();
+        }
+    }
+
+    pub fn adjust(&mut self, steps: i64) {
+        if let Some(node) = self.selected_node() {
+            let mut ranges = self.ranges(&node);
+            match self.selected {
+                0 => ranges.acc = ranges.acc.step(steps),
+                _ => ranges.gyr = ranges.gyr.step(steps),
+            }
+            self.configure(node, ranges);
+        }
+    }
+
+    // What the selected node reported becomes its configuration
+    pub fn adopt_reported(&mut self) -> Option<(Node, ImuRanges)> {
+        let node = self.selected_node()?;
+        let reported = *self.reported.get(&node)?;
+        self.configure(node, reported);
+        Some((node, reported))
+    }
+
+    fn configure(&mut self, node: Node, ranges: ImuRanges) {
+        if self.ranges(&node) != ranges {
+            self.configured.insert(node, ranges);
+            self.changed = true;
+        }
+    }
+
+    // Once the page is left with changes, to be stored
+    pub fn take_changed(&mut self) -> Option<HashMap<Node, ImuRanges>> {
+        if self.active {
+            return None;
+        }
+        std::mem::take(&mut self.changed).then(|| self.configured.clone())
+    }
+
+    pub fn has_changes(&self) -> bool {
+        self.changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::parser::rq2::{BMI088AccRange, BMI088GyrRange};
+
+    #[test]
+    fn test_ranges_are_verified_and_configured() {
+        let rqa = Node::RedQueen(b'A');
+        let rqb = Node::RedQueen(b'B');
+        let narrow = ImuRanges {
+            acc: BMI088AccRange::AccRange6g,
+            gyr: BMI088GyrRange::GyrRange500s,
+        };
+        let mut calibration = ImuCalibration::default();
+        calibration.set_nodes(vec![rqa, rqb]);
+        assert_eq!(calibration.verification(&rqa), Verification::Unreported);
+        assert_eq!(
+            calibration.report(rqa, ImuRanges::default()),
+            Some("RQA IMU ranges 24g/2000dps verified".into())
+        );
+        assert_eq!(calibration.report(rqa, ImuRanges::default()), None);
+        assert_eq!(
+            calibration.report(rqb, narrow),
+            Some("RQB reports IMU ranges 6g/500dps, configured are 24g/2000dps".into())
+        );
+        assert_eq!(calibration.mismatched(), vec![rqb]);
+
+        calibration.start();
+        calibration.next();
+        calibration.next();
+        assert_eq!(calibration.selected_node(), Some(rqb));
+        assert_eq!(calibration.adopt_reported(), Some((rqb, narrow)));
+        assert_eq!(calibration.verification(&rqb), Verification::Verified);
+        assert!(calibration.mismatched().is_empty());
+        calibration.next();
+        calibration.adjust(-1);
+        assert_eq!(calibration.ranges(&rqb).gyr, BMI088GyrRange::GyrRange1000s);
+        // Stored once the page is left
+        assert_eq!(calibration.take_changed(), None);
+        calibration.stop();
+        let stored = calibration.take_changed().unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[&rqb].acc, BMI088AccRange::AccRange6g);
+        assert_eq!(calibration.take_changed(), None);
+    }
+}
@@ -0,0 +1,186 @@
+// Snapshots of the pad by the USB camera of the ground station,
+// taken when ignition is acknowledged and on anomalies. They are
+// stored next to the recording and named after the seconds into
+// the session of their marker, so they line up with the journal
+// and the timeline. The journal's markers on the event bus decide
+// when, the novaview build has the camera taking them.
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use crossbeam_channel::Receiver;
+
+use crate::{bus::Marked, journal::Marker};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub marker: Marker,
+    pub elapsed: f64,
+    // Without extension, a clip is stored differently
+    pub path: PathBuf,
+}
+
+pub struct Snapshots {
+    recording: PathBuf,
+    markers: Receiver<Marked>,
+    pending: VecDeque<Snapshot>,
+}
+
+fn worth_a_snapshot(marker: Marker) -> bool {
+    matches!(marker, Marker::Ignition | Marker::Anomaly)
+}
+
+impl Snapshots {
+    pub fn new(recording: &Path, markers: Receiver<Marked>) -> Self {
+        Self {
+            recording: recording.to_path_buf(),
+            markers,
+            pending: VecDeque::new(),
+        }
+    }
+
+    // Queues the markers published since the last call,
+    // and returns how many there were
+    pub fn update(&mut self) -> usize {
+        let before = self.pending.len();
+        while let Ok(Marked {
+            marker, elapsed, ..
+        }) = self.markers.try_recv()
+        {
+            if worth_a_snapshot(marker) {
+                let path = self.path(marker, elapsed);
+                self.pending.push_back(Snapshot {
+                    marker,
+                    elapsed,
+                    path,
+                });
+            }
+        }
+        self.pending.len() - before
+    }
+
+    pub fn pop(&mut self) -> Option<Snapshot> {
+        self.pending.pop_front()
+    }
+
+    fn path(&self, marker: Marker, elapsed: f64) -> PathBuf {
+        let stem = self
+            .recording
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let name = format!(
+            "{}-{}-{:07.1}s",
+            stem,
+            marker.name().to_lowercase().replace(' ', "-"),
+            elapsed
+        );
+        self.recording.with_file_name(name)
+    }
+}
+
+#[cfg(feature = "novaview")]
+pub mod v4l2 {
+    use std::path::PathBuf;
+    use std::process::{Child, Command, Stdio};
+    use std::time::Duration;
+
+    use anyhow::bail;
+
+    use super::Snapshot;
+
+    // ffmpeg does the grabbing, so we don't need to
+    // deal with the formats of the different cameras
+    pub struct Camera {
+        device: String,
+        // Clips of that length instead of single pictures
+        clip: Option<Duration>,
+        running: Option<Child>,
+    }
+
+    impl Camera {
+        pub fn new(device: &str, clip: Option<Duration>) -> anyhow::Result<Self> {
+            if !std::path::Path::new(device).exists() {
+                bail!("no camera at {}", device);
+            }
+            Ok(Self {
+                device: device.into(),
+                clip,
+                running: None,
+            })
+        }
+
+        // Reaps ffmpeg once it's done, so call it every frame
+        pub fn is_busy(&mut self) -> bool {
+            let done = match &mut self.running {
+                Some(child) => !matches!(child.try_wait(), Ok(None)),
+                None => return false,
+            };
+            if done {
+                self.running = None;
+            }
+            !done
+        }
+
+        // One at a time, the device can't be opened twice
+        pub fn take(&mut self, snapshot: &Snapshot) -> anyhow::Result<PathBuf> {
+            if self.is_busy() {
+                bail!("camera still busy");
+            }
+            let mut path = snapshot.path.clone().into_os_string();
+            let mut command = Command::new("ffmpeg");
+            command
+                .args(["-loglevel", "error", "-y", "-f", "v4l2", "-i"])
+                .arg(&self.device);
+            match self.clip {
+                Some(clip) => {
+                    command.args(["-t", &format!("{:.1}", clip.as_secs_f32())]);
+                    path.push(".mkv");
+                }
+                None => {
+                    command.args(["-frames:v", "1"]);
+                    path.push(".jpg");
+                }
+            }
+            command
+                .arg(&path)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null());
+            self.running = Some(command.spawn()?);
+            Ok(path.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bus::EventBus, journal::Journal};
+
+    #[test]
+    fn test_ignition_and_anomalies_are_snapped_once() {
+        let bus = EventBus::default();
+        let mut journal = Journal::default();
+        journal.publish_markers(bus.markers.clone());
+        let mut snapshots = Snapshots::new(
+            Path::new("/data/lnc-20240601-1200.bin"),
+            bus.markers.subscribe(),
+        );
+        journal.mark(Marker::Reset, 1.0, "Reset acknowledged by RQB");
+        journal.mark(Marker::Ignition, 12.34, "Ignition acknowledged by RQB");
+        assert_eq!(snapshots.update(), 1);
+        journal.record("Snapshot taken");
+        journal.mark(Marker::Anomaly, 13.0, "2 anomalies reported by RQA");
+        assert_eq!(snapshots.update(), 1);
+        assert_eq!(snapshots.update(), 0);
+        let paths: Vec<_> = std::iter::from_fn(|| snapshots.pop())
+            .map(|snapshot| snapshot.path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/data/lnc-20240601-1200-ignition-00012.3s"),
+                PathBuf::from("/data/lnc-20240601-1200-anomaly-00013.0s"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,151 @@
+// Captures the serial traffic of the E32 in the classic pcap format,
+// so the RQ protocol can be looked at with Wireshark & co. There's no
+// standard link type for it, we use the first one reserved for private
+// use (DLT_USER0, 147). Each packet starts with one byte telling the
+// direction, followed by the bytes as they went over the line:
+//
+// 0x00 <bytes received from the node>
+// 0x01 <bytes sent by us>
+//
+// Received bytes are collected into one packet up to the end of the
+// line, sent ones are a packet per write.
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const LINKTYPE_USER0: u32 = 147;
+const MAGIC: u32 = 0xa1b2c3d4;
+const SNAPLEN: u32 = 65535;
+// Noise without line ends doesn't pile up forever
+const MAX_RECEIVED: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Received = 0,
+    Sent = 1,
+}
+
+pub struct Capture<W: Write> {
+    writer: W,
+    received: Vec<u8>,
+    // Of the first received byte
+    received_at: SystemTime,
+}
+
+impl Capture<BufWriter<File>> {
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> Capture<W> {
+    pub fn new(mut writer: W) -> std::io::Result<Self> {
+        writer.write_all(&MAGIC.to_le_bytes())?;
+        writer.write_all(&2u16.to_le_bytes())?;
+        writer.write_all(&4u16.to_le_bytes())?;
+        // Timezone offset and accuracy, both unused
+        writer.write_all(&0i32.to_le_bytes())?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        writer.write_all(&SNAPLEN.to_le_bytes())?;
+        writer.write_all(&LINKTYPE_USER0.to_le_bytes())?;
+        Ok(Self {
+            writer,
+            received: vec![],
+            received_at: UNIX_EPOCH,
+        })
+    }
+
+    pub fn received(&mut self, byte: u8, at: SystemTime) -> std::io::Result<()> {
+        if self.received.is_empty() {
+            self.received_at = at;
+        }
+        self.received.push(byte);
+        if byte == b'\n' || self.received.len() >= MAX_RECEIVED {
+            self.flush_received()?;
+        }
+        Ok(())
+    }
+
+    pub fn sent(&mut self, data: &[u8], at: SystemTime) -> std::io::Result<()> {
+        self.flush_received()?;
+        self.packet(Direction::Sent, at, data)
+    }
+
+    // Also writes out a partially received line
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.flush_received()?;
+        self.writer.flush()
+    }
+
+    fn flush_received(&mut self) -> std::io::Result<()> {
+        if self.received.is_empty() {
+            return Ok(());
+        }
+        let received = std::mem::take(&mut self.received);
+        self.packet(Direction::Received, self.received_at, &received)
+    }
+
+    fn packet(&mut self, direction: Direction, at: SystemTime, data: &[u8]) -> std::io::Result<()> {
+        let since_epoch = at.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let length = data.len() as u32 + 1;
+        self.writer
+            .write_all(&(since_epoch.as_secs() as u32).to_le_bytes())?;
+        self.writer
+            .write_all(&since_epoch.subsec_micros().to_le_bytes())?;
+        self.writer.write_all(&length.to_le_bytes())?;
+        self.writer.write_all(&length.to_le_bytes())?;
+        self.writer.write_all(&[direction as u8])?;
+        self.writer.write_all(data)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_packets_carry_direction_and_time() {
+        let at = UNIX_EPOCH + Duration::from_micros(1_700_000_000_250_000);
+        let mut capture = Capture::new(vec![]).unwrap();
+        capture.sent(b"$LNCCMD,001,RQB,PING*00\r\n", at).unwrap();
+        for byte in b"$RQBACK,001,LNC*00\r\n$RQB" {
+            capture.received(*byte, at).unwrap();
+        }
+        capture.flush().unwrap();
+        let bytes = capture.into_inner();
+        assert_eq!(&bytes[0..4], &[0xd4, 0xc3, 0xb2, 0xa1]);
+        assert_eq!(&bytes[20..24], &LINKTYPE_USER0.to_le_bytes());
+        let mut packets = vec![];
+        let mut rest = &bytes[24..];
+        while !rest.is_empty() {
+            let field = |i: usize| u32::from_le_bytes(rest[i..i + 4].try_into().unwrap());
+            let (seconds, micros, length) = (field(0), field(4), field(8) as usize);
+            packets.push((seconds, micros, rest[16], rest[17..16 + length].to_vec()));
+            rest = &rest[16 + length..];
+        }
+        assert_eq!(
+            packets,
+            vec![
+                (
+                    1_700_000_000,
+                    250_000,
+                    1,
+                    b"$LNCCMD,001,RQB,PING*00\r\n".to_vec()
+                ),
+                (
+                    1_700_000_000,
+                    250_000,
+                    0,
+                    b"$RQBACK,001,LNC*00\r\n".to_vec()
+                ),
+                (1_700_000_000, 250_000, 0, b"$RQB".to_vec()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+// The source of time for the model, the consort, the E32 worker and
+// the telemetry endpoints. Everything shares one clock, so tests can
+// step a SimulatedClock and see timeouts fire without sleeping.
+// Durations are taken on the monotonic time only, the wall time is
+// for stamping what's written, see timesource.rs.
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+#[cfg(test)]
+pub use mock_instant::Instant;
+#[cfg(not(test))]
+pub use std::time::Instant;
+
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> Instant;
+    fn wall(&self) -> DateTime<Utc>;
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+// Only moves when told to. Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct SimulatedClock {
+    now: Arc<Mutex<Instant>>,
+    wall: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wall(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+
+    fn wall(&self) -> DateTime<Utc> {
+        *self.wall.lock().unwrap()
+    }
+}
+
+impl SimulatedClock {
+    // The wall time starts at the real one
+    pub fn new(start: Instant) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(start)),
+            wall: Arc::new(Mutex::new(Utc::now())),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+        *self.wall.lock().unwrap() += chrono::Duration::from_std(duration).unwrap();
+    }
+
+    // Only the wall time, as NTP does
+    pub fn step_wall(&self, step: chrono::Duration) {
+        *self.wall.lock().unwrap() += step;
+    }
+
+    pub fn shared(&self) -> SharedClock {
+        Arc::new(self.clone())
+    }
+}
+
+pub fn system() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulated_clock_is_shared() {
+        let start = Instant::now();
+        let clock = SimulatedClock::new(start);
+        let shared = clock.shared();
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(shared.now(), start + Duration::from_secs(3));
+        assert_eq!(shared.now().duration_since(start), Duration::from_secs(3));
+        let wall = shared.wall();
+        clock.step_wall(chrono::Duration::seconds(-60));
+        assert_eq!(shared.wall(), wall - chrono::Duration::seconds(60));
+        assert_eq!(shared.now(), start + Duration::from_secs(3));
+    }
+}
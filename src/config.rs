@@ -0,0 +1,780 @@
+// Settings from a JSON file that is watched while running, so they
+// can be changed mid-campaign without a restart. Display and polling
+// settings are applied right away. A new radio port is reconnected to
+// right away, or on the next reconnect during the launch sequence, and
+// flagged until then. What is only set up on start is flagged as
+// needing a restart. A file that doesn't parse or validate is
+// rejected as a whole.
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    annotation,
+    announcer::AnnouncedEvent,
+    args::{Privacy, ProgramArgs},
+    crank,
+    i18n::Language,
+    layout::theme::Theme,
+    linkcrypto,
+    model::AUTO_RESET_TIMEOUT,
+    observables::{filter::Filters, range::PlotRanges, units::Units, Profile},
+    recorder::Sink,
+    rqprotocol::Node,
+    telemetry::{
+        parser::rq2::ImuRanges,
+        relay,
+        roster::{self, ChannelConfig},
+    },
+    window::LaunchWindowConfig,
+};
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+// The worker gives up purging after 10s anyway
+const MAX_DRAIN_IDLE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub ui_scale: Option<f32>,
+    pub privacy: Option<Privacy>,
+    pub language: Option<Language>,
+    pub theme: Option<Theme>,
+    pub units: Option<Units>,
+    pub accessible: Option<bool>,
+    pub critical_scale: Option<f32>,
+    pub repeat_delay_ms: Option<u64>,
+    pub repeat_interval_ms: Option<u64>,
+    pub repeat_acceleration: Option<f32>,
+    pub repeat_max_steps: Option<u32>,
+    pub no_tab_wrap: Option<bool>,
+    pub tab_debounce_ms: Option<u64>,
+    pub crank_increment: Option<u8>,
+    pub crank_decay_interval_ms: Option<u64>,
+    pub crank_decay_amount: Option<u8>,
+    pub crank_threshold: Option<u8>,
+    pub poll_nodes: Option<Vec<Node>>,
+    pub drain_idle_ms: Option<u64>,
+    pub key_entry_timeout_s: Option<u64>,
+    pub stale_polls: Option<u32>,
+    pub stale_min_ms: Option<u64>,
+    pub backoff_initial_ms: Option<u64>,
+    pub backoff_max_hold_ms: Option<u64>,
+    pub backoff_jitter: Option<f32>,
+    pub duty_cycle_percent: Option<f64>,
+    pub no_auto_record: Option<bool>,
+    pub auto_record_thrust_kn: Option<f64>,
+    pub auto_record_stop_s: Option<u64>,
+    pub idle_standby_min: Option<u64>,
+    pub idle_poll_ms: Option<u64>,
+    pub idle_brightness_percent: Option<u8>,
+    pub mark_labels: Option<Vec<String>>,
+    pub telemetry_max_entries: Option<usize>,
+    pub telemetry_max_age_s: Option<u64>,
+    pub announce: Option<Vec<AnnouncedEvent>>,
+    // Written back from the filter page
+    pub filters: Option<Filters>,
+    pub plot_ranges: Option<PlotRanges>,
+    pub launch_window: Option<LaunchWindowConfig>,
+    // Written back from the calibration page
+    pub imu_ranges: Option<HashMap<Node, ImuRanges>>,
+    // The receiver sets its modules up again
+    pub telemetry_roster: Option<Vec<ChannelConfig>>,
+    // Reconnected to unless the launch sequence is on
+    pub port: Option<String>,
+    pub secondary_port: Option<String>,
+    // The relay is only set up on start
+    pub relay_secret_key: Option<String>,
+    pub relay_server_key: Option<String>,
+    pub relay_allowed_clients: Option<Vec<String>>,
+    pub relay_client_public_key: Option<String>,
+    pub relay_client_secret_key: Option<String>,
+    // As are the mirror of the recording and gpsd
+    pub mirror_recording: Option<Sink>,
+    pub gpsd: Option<String>,
+    // Of the link encryption, in hex
+    pub link_keys: Option<HashMap<Node, String>>,
+    // The pendant is bound again
+    pub pendant_port: Option<u16>,
+    pub pendant_key: Option<String>,
+    // Only set up on start too
+    pub profile: Option<Profile>,
+}
+
+// Names of the settings that changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Reload {
+    pub applied: Vec<&'static str>,
+    pub needs_reconnect: Vec<&'static str>,
+    // Only set up on start
+    pub needs_restart: Vec<&'static str>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReloadOutcome {
+    Applied(Reload),
+    Rejected(String),
+}
+
+pub struct ConfigWatcher {
+    path: PathBuf,
+    contents: Option<Vec<u8>>,
+    last_check: Option<Instant>,
+}
+
+fn update<T: PartialEq + Clone>(
+    name: &'static str,
+    value: &Option<T>,
+    target: &mut T,
+    changed: &mut Vec<&'static str>,
+) {
+    if let Some(value) = value {
+        if value != target {
+            *target = value.clone();
+            changed.push(name);
+        }
+    }
+}
+
+impl ConfigFile {
+    pub fn parse(contents: &[u8]) -> anyhow::Result<Self> {
+        let config: Self = serde_json::from_slice(contents)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if let Some(ui_scale) = self.ui_scale {
+            if !(0.25..=4.0).contains(&ui_scale) {
+                return Err(anyhow!("ui_scale {} out of 0.25..4.0", ui_scale));
+            }
+        }
+        if let Some(critical_scale) = self.critical_scale {
+            if !(1.0..=4.0).contains(&critical_scale) {
+                return Err(anyhow!("critical_scale {} out of 1.0..4.0", critical_scale));
+            }
+        }
+        if let Some(poll_nodes) = &self.poll_nodes {
+            if poll_nodes.is_empty() {
+                return Err(anyhow!("poll_nodes must not be empty"));
+            }
+        }
+        if let Some(drain_idle_ms) = self.drain_idle_ms {
+            if Duration::from_millis(drain_idle_ms) > MAX_DRAIN_IDLE {
+                return Err(anyhow!("drain_idle_ms {} too long", drain_idle_ms));
+            }
+        }
+        if let Some(key_entry_timeout_s) = self.key_entry_timeout_s {
+            validate_key_entry_timeout(key_entry_timeout_s)?;
+        }
+        if self.repeat_interval_ms == Some(0) || self.repeat_max_steps == Some(0) {
+            return Err(anyhow!("key repeat interval and steps must be positive"));
+        }
+        if self.crank_increment == Some(0) || self.crank_decay_interval_ms == Some(0) {
+            return Err(anyhow!(
+                "crank increment and decay interval must be positive"
+            ));
+        }
+        if let Some(threshold) = self.crank_threshold {
+            if !(1..=crank::FULL).contains(&threshold) {
+                return Err(anyhow!("crank_threshold {} out of 1..100", threshold));
+            }
+        }
+        if self.stale_polls == Some(0) {
+            return Err(anyhow!("stale_polls must be positive"));
+        }
+        if self.backoff_initial_ms == Some(0) {
+            return Err(anyhow!("backoff_initial_ms must be positive"));
+        }
+        if let (Some(initial), Some(max_hold)) = (self.backoff_initial_ms, self.backoff_max_hold_ms)
+        {
+            if max_hold < initial {
+                return Err(anyhow!(
+                    "backoff_max_hold_ms {} below the initial",
+                    max_hold
+                ));
+            }
+        }
+        if let Some(jitter) = self.backoff_jitter {
+            if !(0.0..=1.0).contains(&jitter) {
+                return Err(anyhow!("backoff_jitter {} out of 0..1", jitter));
+            }
+        }
+        if let Some(percent) = self.duty_cycle_percent {
+            if !(percent > 0.0 && percent <= 100.0) {
+                return Err(anyhow!("duty_cycle_percent {} out of 0..100", percent));
+            }
+        }
+        if let Some(threshold) = self.auto_record_thrust_kn {
+            if threshold <= 0.0 {
+                return Err(anyhow!("auto_record_thrust_kn must be positive"));
+            }
+        }
+        if let Some(percent) = self.idle_brightness_percent {
+            if percent == 0 || percent > 100 {
+                return Err(anyhow!("idle_brightness_percent {} out of 1..100", percent));
+            }
+        }
+        for label in self.mark_labels.iter().flatten() {
+            if label.is_empty() || label.chars().count() > annotation::MAX_LABEL {
+                return Err(anyhow!(
+                    "mark label '{}' must have 1 to {} characters",
+                    label,
+                    annotation::MAX_LABEL
+                ));
+            }
+        }
+        if self.telemetry_max_entries == Some(0) {
+            return Err(anyhow!("telemetry_max_entries must be positive"));
+        }
+        if let Some(filters) = &self.filters {
+            filters.validate()?;
+        }
+        if let Some(plot_ranges) = &self.plot_ranges {
+            plot_ranges.validate()?;
+        }
+        if let Some(launch_window) = &self.launch_window {
+            launch_window.validate()?;
+        }
+        if let Some(telemetry_roster) = &self.telemetry_roster {
+            roster::validate(telemetry_roster)?;
+        }
+        for key in [
+            &self.relay_secret_key,
+            &self.relay_server_key,
+            &self.relay_client_public_key,
+            &self.relay_client_secret_key,
+        ]
+        .into_iter()
+        .flatten()
+        .chain(self.relay_allowed_clients.iter().flatten())
+        {
+            relay::decode_key(key)?;
+        }
+        // Otherwise nobody could subscribe
+        if self.relay_secret_key.is_some()
+            && self
+                .relay_allowed_clients
+                .as_ref()
+                .map_or(true, Vec::is_empty)
+        {
+            return Err(anyhow!("relay_secret_key needs relay_allowed_clients"));
+        }
+        if self.relay_client_public_key.is_some() != self.relay_client_secret_key.is_some() {
+            return Err(anyhow!(
+                "relay_client_public_key and relay_client_secret_key go together"
+            ));
+        }
+        for key in self.link_keys.iter().flat_map(HashMap::values) {
+            linkcrypto::decode_key(key)?;
+        }
+        if let Some(key) = &self.pendant_key {
+            linkcrypto::decode_key(key)?;
+        }
+        if self.pendant_port.is_some() && self.pendant_key.is_none() {
+            return Err(anyhow!("pendant_port needs a pendant_key"));
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, args: &mut ProgramArgs) -> Reload {
+        let mut reload = Reload::default();
+        let applied = &mut reload.applied;
+        update("ui_scale", &self.ui_scale, &mut args.ui_scale, applied);
+        update("privacy", &self.privacy, &mut args.privacy, applied);
+        update("language", &self.language, &mut args.language, applied);
+        update("theme", &self.theme, &mut args.theme, applied);
+        update("units", &self.units, &mut args.units, applied);
+        update(
+            "accessible",
+            &self.accessible,
+            &mut args.accessible,
+            applied,
+        );
+        update(
+            "critical_scale",
+            &self.critical_scale,
+            &mut args.critical_scale,
+            applied,
+        );
+        update(
+            "repeat_delay_ms",
+            &self.repeat_delay_ms,
+            &mut args.repeat_delay_ms,
+            applied,
+        );
+        update(
+            "repeat_interval_ms",
+            &self.repeat_interval_ms,
+            &mut args.repeat_interval_ms,
+            applied,
+        );
+        update(
+            "repeat_acceleration",
+            &self.repeat_acceleration,
+            &mut args.repeat_acceleration,
+            applied,
+        );
+        update(
+            "repeat_max_steps",
+            &self.repeat_max_steps,
+            &mut args.repeat_max_steps,
+            applied,
+        );
+        update(
+            "no_tab_wrap",
+            &self.no_tab_wrap,
+            &mut args.no_tab_wrap,
+            applied,
+        );
+        update(
+            "tab_debounce_ms",
+            &self.tab_debounce_ms,
+            &mut args.tab_debounce_ms,
+            applied,
+        );
+        update(
+            "crank_increment",
+            &self.crank_increment,
+            &mut args.crank_increment,
+            applied,
+        );
+        update(
+            "crank_decay_interval_ms",
+            &self.crank_decay_interval_ms,
+            &mut args.crank_decay_interval_ms,
+            applied,
+        );
+        update(
+            "crank_decay_amount",
+            &self.crank_decay_amount,
+            &mut args.crank_decay_amount,
+            applied,
+        );
+        update(
+            "crank_threshold",
+            &self.crank_threshold,
+            &mut args.crank_threshold,
+            applied,
+        );
+        update(
+            "poll_nodes",
+            &self.poll_nodes,
+            &mut args.poll_nodes,
+            applied,
+        );
+        update(
+            "drain_idle_ms",
+            &self.drain_idle_ms,
+            &mut args.drain_idle_ms,
+            applied,
+        );
+        update(
+            "key_entry_timeout_s",
+            &self.key_entry_timeout_s,
+            &mut args.key_entry_timeout_s,
+            applied,
+        );
+        update(
+            "stale_polls",
+            &self.stale_polls,
+            &mut args.stale_polls,
+            applied,
+        );
+        update(
+            "stale_min_ms",
+            &self.stale_min_ms,
+            &mut args.stale_min_ms,
+            applied,
+        );
+        update(
+            "backoff_initial_ms",
+            &self.backoff_initial_ms,
+            &mut args.backoff_initial_ms,
+            applied,
+        );
+        update(
+            "backoff_max_hold_ms",
+            &self.backoff_max_hold_ms,
+            &mut args.backoff_max_hold_ms,
+            applied,
+        );
+        update(
+            "backoff_jitter",
+            &self.backoff_jitter,
+            &mut args.backoff_jitter,
+            applied,
+        );
+        update(
+            "duty_cycle_percent",
+            &self.duty_cycle_percent.map(Some),
+            &mut args.duty_cycle_percent,
+            applied,
+        );
+        update(
+            "no_auto_record",
+            &self.no_auto_record,
+            &mut args.no_auto_record,
+            applied,
+        );
+        update(
+            "auto_record_thrust_kn",
+            &self.auto_record_thrust_kn,
+            &mut args.auto_record_thrust_kn,
+            applied,
+        );
+        update(
+            "auto_record_stop_s",
+            &self.auto_record_stop_s,
+            &mut args.auto_record_stop_s,
+            applied,
+        );
+        update(
+            "idle_standby_min",
+            &self.idle_standby_min,
+            &mut args.idle_standby_min,
+            applied,
+        );
+        update(
+            "idle_poll_ms",
+            &self.idle_poll_ms,
+            &mut args.idle_poll_ms,
+            applied,
+        );
+        update(
+            "idle_brightness_percent",
+            &self.idle_brightness_percent,
+            &mut args.idle_brightness_percent,
+            applied,
+        );
+        update(
+            "mark_labels",
+            &self.mark_labels,
+            &mut args.mark_labels,
+            applied,
+        );
+        update(
+            "telemetry_max_entries",
+            &self.telemetry_max_entries,
+            &mut args.telemetry_max_entries,
+            applied,
+        );
+        update(
+            "telemetry_max_age_s",
+            &self.telemetry_max_age_s,
+            &mut args.telemetry_max_age_s,
+            applied,
+        );
+        update("announce", &self.announce, &mut args.announce, applied);
+        update("filters", &self.filters, &mut args.filters, applied);
+        update(
+            "plot_ranges",
+            &self.plot_ranges,
+            &mut args.plot_ranges,
+            applied,
+        );
+        update(
+            "launch_window",
+            &self.launch_window.clone().map(Some),
+            &mut args.launch_window,
+            applied,
+        );
+        update(
+            "imu_ranges",
+            &self.imu_ranges,
+            &mut args.imu_ranges,
+            applied,
+        );
+        update(
+            "telemetry_roster",
+            &self.telemetry_roster,
+            &mut args.telemetry_roster,
+            applied,
+        );
+        let needs_reconnect = &mut reload.needs_reconnect;
+        update(
+            "port",
+            &self.port.clone().map(Some),
+            &mut args.port,
+            needs_reconnect,
+        );
+        update(
+            "secondary_port",
+            &self.secondary_port.clone().map(Some),
+            &mut args.secondary_port,
+            needs_reconnect,
+        );
+        let needs_restart = &mut reload.needs_restart;
+        update(
+            "relay_secret_key",
+            &self.relay_secret_key.clone().map(Some),
+            &mut args.relay_secret_key,
+            needs_restart,
+        );
+        update(
+            "relay_server_key",
+            &self.relay_server_key.clone().map(Some),
+            &mut args.relay_server_key,
+            needs_restart,
+        );
+        update(
+            "relay_allowed_clients",
+            &self.relay_allowed_clients,
+            &mut args.relay_allowed_clients,
+            needs_restart,
+        );
+        update(
+            "relay_client_public_key",
+            &self.relay_client_public_key.clone().map(Some),
+            &mut args.relay_client_public_key,
+            needs_restart,
+        );
+        update(
+            "relay_client_secret_key",
+            &self.relay_client_secret_key.clone().map(Some),
+            &mut args.relay_client_secret_key,
+            needs_restart,
+        );
+        update(
+            "mirror_recording",
+            &self.mirror_recording.clone().map(Some),
+            &mut args.mirror_recording,
+            needs_restart,
+        );
+        update(
+            "gpsd",
+            &self.gpsd.clone().map(Some),
+            &mut args.gpsd,
+            needs_restart,
+        );
+        update("profile", &self.profile, &mut args.profile, needs_restart);
+        let applied = &mut reload.applied;
+        update("link_keys", &self.link_keys, &mut args.link_keys, applied);
+        update(
+            "pendant_port",
+            &self.pendant_port.map(Some),
+            &mut args.pendant_port,
+            applied,
+        );
+        update(
+            "pendant_key",
+            &self.pendant_key.clone().map(Some),
+            &mut args.pendant_key,
+            applied,
+        );
+        reload
+    }
+}
+
+// Also of the command line, 0 would clear entered digits right away
+pub fn validate_key_entry_timeout(key_entry_timeout_s: u64) -> anyhow::Result<()> {
+    if key_entry_timeout_s == 0 || Duration::from_secs(key_entry_timeout_s) >= AUTO_RESET_TIMEOUT {
+        return Err(anyhow!(
+            "key_entry_timeout_s {} out of 1..{}",
+            key_entry_timeout_s,
+            AUTO_RESET_TIMEOUT.as_secs()
+        ));
+    }
+    Ok(())
+}
+
+pub fn store_filters(path: &Path, filters: &Filters) -> anyhow::Result<()> {
+    store_setting(path, "filters", serde_json::to_value(filters)?)
+}
+
+pub fn store_imu_ranges(path: &Path, ranges: &HashMap<Node, ImuRanges>) -> anyhow::Result<()> {
+    store_setting(path, "imu_ranges", serde_json::to_value(ranges)?)
+}
+
+// Keeps the other settings of the file as they are
+fn store_setting(path: &Path, name: &str, value: Value) -> anyhow::Result<()> {
+    let mut config = match std::fs::read(path) {
+        Ok(contents) => serde_json::from_slice(&contents)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => json!({}),
+        Err(e) => return Err(e.into()),
+    };
+    match &mut config {
+        Value::Object(settings) => {
+            settings.insert(name.into(), value);
+        }
+        _ => return Err(anyhow!("config file isn't a JSON object")),
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&config)? + "\n")?;
+    Ok(())
+}
+
+impl ConfigWatcher {
+    // The initial contents count as seen, they
+    // are applied when parsing the arguments.
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.into(),
+            contents: std::fs::read(path).ok(),
+            last_check: None,
+        }
+    }
+
+    // Returns the new config if the file changed since the last call
+    pub fn poll(&mut self, now: Instant) -> Option<anyhow::Result<ConfigFile>> {
+        if let Some(last_check) = self.last_check {
+            if now - last_check < WATCH_INTERVAL {
+                return None;
+            }
+        }
+        self.last_check = Some(now);
+        // A missing file is most likely an editor
+        // replacing it, so we wait for it to return.
+        let contents = std::fs::read(&self.path).ok()?;
+        if self.contents.as_ref() == Some(&contents) {
+            return None;
+        }
+        let config = ConfigFile::parse(&contents);
+        self.contents = Some(contents);
+        Some(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observables::filter::FilterKind;
+
+    #[test]
+    fn test_apply_reports_changes() {
+        let mut args = ProgramArgs::default();
+        let config = ConfigFile::parse(
+            br#"{"ui_scale": 1.0, "privacy": "Mask", "poll_nodes": ["RQB", "FDB"], "port": "/dev/ttyUSB1"}"#,
+        )
+        .unwrap();
+        let reload = config.apply(&mut args);
+        assert_eq!(reload.applied, vec!["privacy", "poll_nodes"]);
+        assert_eq!(reload.needs_reconnect, vec!["port"]);
+        assert_eq!(args.privacy, Privacy::Mask);
+        assert_eq!(args.port, Some("/dev/ttyUSB1".into()));
+        let config = ConfigFile::parse(br#"{"profile": "test-stand"}"#).unwrap();
+        assert_eq!(config.apply(&mut args).needs_restart, vec!["profile"]);
+        assert_eq!(args.profile, Profile::TestStand);
+        assert_eq!(config.apply(&mut args), Reload::default());
+        let config =
+            ConfigFile::parse(br#"{"mirror_recording": "tcp://logger.local:2525"}"#).unwrap();
+        assert_eq!(
+            config.apply(&mut args).needs_restart,
+            vec!["mirror_recording"]
+        );
+        assert_eq!(
+            args.mirror_recording,
+            Some(Sink::Tcp("logger.local:2525".into()))
+        );
+        assert!(ConfigFile::parse(br#"{"mirror_recording": "tcp://"}"#).is_err());
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(ConfigFile::parse(br#"{"ui_scale": 10.0}"#).is_err());
+        assert!(ConfigFile::parse(br#"{"poll_nodes": []}"#).is_err());
+        assert!(ConfigFile::parse(br#"{"drain_idle_ms": 20000}"#).is_err());
+        assert!(ConfigFile::parse(br#"{"key_entry_timeout_s": 120}"#).is_err());
+        let args = ProgramArgs {
+            key_entry_timeout_s: 0,
+            ..Default::default()
+        };
+        assert!(args.with_config().is_err());
+        assert!(ConfigFile::parse(br#"{"theme": "Dusk"}"#).is_err());
+        assert!(ConfigFile::parse(br#"{"colors": "dark"}"#).is_err());
+        assert!(
+            ConfigFile::parse(br#"{"backoff_initial_ms": 500, "backoff_max_hold_ms": 100}"#)
+                .is_err()
+        );
+        assert!(ConfigFile::parse(br#"{"backoff_jitter": 1.5}"#).is_err());
+        assert!(ConfigFile::parse(br#"{"duty_cycle_percent": 0}"#).is_err());
+        assert!(ConfigFile::parse(br#"{"critical_scale": 8.0}"#).is_err());
+        assert!(ConfigFile::parse(b"{").is_err());
+        assert!(ConfigFile::parse(br#"{"relay_server_key": "abcde"}"#).is_err());
+        let key = "0".repeat(40);
+        assert!(
+            ConfigFile::parse(format!(r#"{{"relay_secret_key": "{}"}}"#, key).as_bytes()).is_err()
+        );
+        assert!(ConfigFile::parse(
+            format!(
+                r#"{{"relay_secret_key": "{}", "relay_allowed_clients": ["{}"]}}"#,
+                key, key
+            )
+            .as_bytes()
+        )
+        .is_ok());
+        assert!(ConfigFile::parse(
+            format!(r#"{{"relay_client_public_key": "{}"}}"#, key).as_bytes()
+        )
+        .is_err());
+        assert!(ConfigFile::parse(br#"{"link_keys": {"RQA": "00ff"}}"#).is_err());
+        assert!(ConfigFile::parse(br#"{"pendant_port": 4210}"#).is_err());
+        assert!(ConfigFile::parse(
+            br#"{"launch_window": {"open": "2026-10-17T11:00:00Z", "close": "2026-10-17T09:00:00Z"}}"#
+        )
+        .is_err());
+        assert!(ConfigFile::parse(br#"{"mark_labels": ["valve opened", ""]}"#).is_err());
+        assert!(ConfigFile::parse(
+            br#"{"telemetry_roster": [{"node": "FDB", "channel": 3}, {"node": "FDB", "channel": 4}]}"#
+        )
+        .is_err());
+        assert!(ConfigFile::parse(
+            br#"{"plot_ranges": {"thrust": {"fixed": {"min": 5, "max": 0}}}}"#
+        )
+        .is_err());
+        assert!(ConfigFile::parse(
+            br#"{"plot_ranges": {"thrust": {"fixed": {"min": 0, "max": 5}}, "pressure": "auto"}}"#
+        )
+        .is_ok());
+        assert_eq!(ConfigFile::parse(b"{}").unwrap(), ConfigFile::default());
+    }
+
+    #[test]
+    fn test_watcher_picks_up_changes() {
+        let path = std::env::temp_dir().join(format!("lnc-config-{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"ui_scale": 1.5}"#).unwrap();
+        let mut watcher = ConfigWatcher::new(&path);
+        let start = Instant::now();
+        assert!(watcher.poll(start).is_none());
+        std::fs::write(&path, r#"{"ui_scale": 2.0}"#).unwrap();
+        assert!(watcher.poll(start + Duration::from_millis(100)).is_none());
+        let config = watcher.poll(start + WATCH_INTERVAL).unwrap().unwrap();
+        assert_eq!(config.ui_scale, Some(2.0));
+        std::fs::write(&path, r#"{"ui_scale": "#).unwrap();
+        assert!(watcher.poll(start + 2 * WATCH_INTERVAL).unwrap().is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_stored_filters_keep_the_other_settings() {
+        let path = std::env::temp_dir().join(format!("lnc-filters-{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"ui_scale": 1.5, "filters": {"thrust": "raw"}}"#).unwrap();
+        let filters = Filters {
+            thrust: FilterKind::Median,
+            ..Default::default()
+        };
+        store_filters(&path, &filters).unwrap();
+        let config = ConfigFile::load(&path).unwrap();
+        assert_eq!(config.ui_scale, Some(1.5));
+        assert_eq!(config.filters, Some(filters.clone()));
+        let mut args = ProgramArgs::default();
+        assert_eq!(config.apply(&mut args).applied, vec!["ui_scale", "filters"]);
+        assert_eq!(args.filters, filters);
+        let ranges = HashMap::from([(Node::RedQueen(b'B'), ImuRanges::default())]);
+        store_imu_ranges(&path, &ranges).unwrap();
+        let config = ConfigFile::load(&path).unwrap();
+        assert_eq!(config.filters, Some(filters));
+        assert_eq!(config.apply(&mut args).applied, vec!["imu_ranges"]);
+        assert_eq!(args.imu_ranges, ranges);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,114 @@
+// Deviations from the wire format the Consort recovers from, like an
+// answer arriving after we gave up on it. Normally they pass as link
+// errors, but when qualifying a RedQueen firmware release against the
+// frontend (--strict) each one is journaled with its sentence and
+// shown in a banner the operator has to acknowledge.
+use crate::rqprotocol::Node;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Deviation {
+    TrailingCharacters,
+    // To a command timed out or superseded by an urgent one
+    LateAnswer { id: usize },
+    IdMismatch { expected: usize, got: usize },
+    // Without any command in flight
+    Unsolicited,
+}
+
+#[derive(Debug, Default)]
+pub struct Conformance {
+    strict: bool,
+    // With the offending sentence, oldest first
+    unacknowledged: Vec<(Deviation, String)>,
+    total: usize,
+}
+
+impl Deviation {
+    pub fn describe(&self) -> String {
+        match self {
+            Deviation::TrailingCharacters => "trailing characters".into(),
+            Deviation::LateAnswer { id } => format!("late answer to #{}", id),
+            Deviation::IdMismatch { expected, got } => {
+                format!("answer to #{} while waiting for #{}", got, expected)
+            }
+            Deviation::Unsolicited => "unsolicited sentence".into(),
+        }
+    }
+}
+
+impl Conformance {
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+        if !strict {
+            self.unacknowledged.clear();
+        }
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    // The text to journal, only in strict mode
+    pub fn record(&mut self, node: Node, deviation: Deviation, sentence: &[u8]) -> Option<String> {
+        if !self.strict {
+            return None;
+        }
+        let sentence = String::from_utf8_lossy(sentence).trim_end().to_string();
+        let text = format!(
+            "Protocol deviation of {}: {}, {}",
+            node,
+            deviation.describe(),
+            sentence
+        );
+        self.total += 1;
+        self.unacknowledged.push((deviation, sentence));
+        Some(text)
+    }
+
+    pub fn unacknowledged(&self) -> &[(Deviation, String)] {
+        &self.unacknowledged
+    }
+
+    pub fn acknowledge(&mut self) {
+        self.unacknowledged.clear();
+    }
+
+    // Since start, acknowledged or not
+    pub fn total(&self) -> usize {
+        self.total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deviations_are_kept_until_acknowledged_only_when_strict() {
+        let rqa = Node::RedQueen(b'A');
+        let mut conformance = Conformance::default();
+        assert_eq!(
+            conformance.record(rqa, Deviation::Unsolicited, b"$RQAACK,001,LNC*7B\r\n"),
+            None
+        );
+        conformance.set_strict(true);
+        assert_eq!(
+            conformance.record(
+                rqa,
+                Deviation::IdMismatch {
+                    expected: 2,
+                    got: 1
+                },
+                b"$RQAACK,001,LNC*7B\r\n"
+            ),
+            Some(
+                "Protocol deviation of RQA: answer to #1 while waiting for #2, $RQAACK,001,LNC*7B"
+                    .into()
+            )
+        );
+        assert_eq!(conformance.unacknowledged().len(), 1);
+        conformance.acknowledge();
+        assert!(conformance.unacknowledged().is_empty());
+        assert_eq!(conformance.total(), 1);
+    }
+}
@@ -1,19 +1,56 @@
-#[cfg(feature = "test-stand")]
-use crate::observables::rqa as rqobs;
+use std::time::Duration;
 
-#[cfg(feature = "rocket")]
-use crate::observables::rqb as rqobs;
+use crate::airtime::DutyCycleStatus;
+use crate::diagnostics::FailureCause;
+use crate::observables::groups::RawObservablesGroup;
+use crate::rqprotocol::{Capabilities, Node};
+use crate::shutdown::Stoppable;
 
-use rqobs::RawObservablesGroup;
+// Of the observables polls of the target, every so many ask for OBG2
+pub const OBG2_EVERY: usize = 5;
+// Of a read on the line, the worker counts them to time out answers
+pub const ANSWER_TIMEOUT: Duration = Duration::from_millis(100);
+
+// Of the channels to and from the worker thread
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct QueueDepths {
+    pub commands: usize,
+    pub urgent: usize,
+    pub answers: usize,
+    pub observables: usize,
+    // Since the start, the oldest go when full
+    pub dropped_observables: usize,
+}
+
+// Traffic the connection generates on its own, as
+// declared by the active mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkActivity {
+    // Neither keepalive nor observables
+    Silent,
+    // Observables polling, with this pause in between
+    Polling(Duration),
+}
 
 #[derive(Debug, PartialEq)]
 pub enum Answers {
     Received(Vec<u8>),
-    Observables(RawObservablesGroup),
+    Observables(Node, RawObservablesGroup),
     Timeout,
+    // A polled node other than the target didn't answer
+    PollTimeout(Node),
     ConnectionOpen,
-    ConnectionError,
+    ConnectionError(FailureCause),
+    // Progress of purging the line in percent
+    Draining(u8),
     Drained,
+    // A command the operator waits for came first
+    DrainCancelled,
+    // Any sentence heard while monitoring
+    Monitored(Vec<u8>),
+    // The module had lost its parameters, these
+    // are the ones it had when we noticed.
+    ModuleReconfigured(String),
 }
 
 pub trait Connection: std::io::Write {
@@ -22,5 +59,62 @@ pub trait Connection: std::io::Write {
     fn open(&mut self, port: &str);
     fn reset(&mut self);
     fn resume(&mut self);
-    fn radio_silence(&mut self, radio_silence: bool);
+    fn link_activity(&mut self, activity: LinkActivity);
+    fn capabilities(&mut self, capabilities: Capabilities);
+    fn monitor(&mut self, monitor: bool);
+    // Both can change while running
+    fn set_drain_idle(&mut self, drain_idle: Duration);
+    fn set_poll_nodes(&mut self, nodes: Vec<Node>);
+    // Fetch OBG2 from the target with the next poll
+    fn query_continuity(&mut self);
+    // Of the time on air, None doesn't hold anything back
+    fn set_duty_cycle_cap(&mut self, cap: Option<f64>);
+    // Connections not on the regulated band don't track it
+    fn duty_cycle(&self) -> Option<DutyCycleStatus> {
+        None
+    }
+    // Connections without a worker have none
+    fn queue_depths(&self) -> Option<QueueDepths> {
+        None
+    }
+    // Written out now rather than once the worker quits
+    fn flush_recording(&mut self) {}
+    // On exit, unlike reset it can't wait on a stuck worker
+    fn stop_polling(&mut self) {
+        self.reset();
+    }
+    // Joined on exit, see shutdown.rs
+    fn worker(&mut self) -> Option<&mut dyn Stoppable> {
+        None
+    }
+    // Goes out before anything queued, by default like any write
+    fn send_urgent(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.write_all(data)
+    }
+}
+
+// What the worker of a connection talks through, the E32
+// module or a bridge to the UART of the node
+pub trait Line {
+    fn write_buffer(&mut self, data: &[u8]) -> anyhow::Result<()>;
+    // A byte, or TimedOut after waiting a while for one
+    fn read(&mut self) -> nb::Result<u8, std::io::Error>;
+    // Of lines with parameters that can drift, what they found
+    fn audit(&mut self) -> Option<Answers> {
+        None
+    }
+}
+
+// Writes through it jump the queue of the connection
+pub struct Urgent<'a, C>(pub &'a mut C);
+
+impl<C: Connection> std::io::Write for Urgent<'_, C> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.send_urgent(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
 }
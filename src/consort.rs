@@ -1,29 +1,71 @@
 use log::error;
-#[cfg(test)]
-use mock_instant::Instant;
-#[cfg(not(test))]
-use std::time::Instant;
 
+use std::collections::HashMap;
 use std::io::Write;
+use std::ops::Range;
+use std::time::Duration;
 
 use ringbuffer::{AllocRingBuffer, RingBuffer};
 
 use crate::{
-    rqparser::{NMEAFormatError, SentenceParser},
-    rqprotocol::{Command, Node, Response, Transaction, TransactionState},
+    backoff::{Backoff, BackoffPolicy, BackoffStats, Hold},
+    clock::SharedClock,
+    conformance::Deviation,
+    invariants::Invariants,
+    linkcrypto::{self, Key, LinkCipher},
+    observables::Profile,
+    rqparser::{
+        answer_header_parser, node_parser, verify_nmea_format, NMEAFormatError, NMEAFormatter,
+        SentenceParser,
+    },
+    rqprotocol::{Capabilities, Command, Node, Response, Transaction, TransactionState},
 };
 
+use crate::linkcrypto::Error as LinkError;
 use crate::rqparser::Error as ParserError;
 use crate::rqprotocol::Error as ProtocolError;
+use crate::rqprotocol::FormatErrorDetail;
 
 #[derive(Debug, PartialEq)]
 pub enum Error {
     ActiveTransaction,
     NMEAFormatError,
     ProtocolError,
+    Nak,
     IOError,
     SpuriousSentence,
     ParserError,
+    LinkCrypto(LinkError),
+    // The recipient expects radio silence, see silence.rs
+    Silenced,
+}
+
+// Locking the pyros again is always safe, whatever the node expects
+fn silence_exempt(command: &Command) -> bool {
+    matches!(command, Command::Abort)
+}
+
+// Of the errors a transaction recovers from
+fn deviation(err: &ProtocolError, abandoned: Option<usize>) -> Option<Deviation> {
+    match err {
+        ProtocolError::InvalidAssociation(_, _, got, _) if abandoned == Some(*got) => {
+            Some(Deviation::LateAnswer { id: *got })
+        }
+        ProtocolError::InvalidAssociation(_, _, got, expected) => Some(Deviation::IdMismatch {
+            expected: *expected,
+            got: *got,
+        }),
+        ProtocolError::FormatError(FormatErrorDetail::TrailingCharacters) => {
+            Some(Deviation::TrailingCharacters)
+        }
+        _ => None,
+    }
+}
+
+// Also of a garbled sentence, as long as it starts readable
+fn sender(sentence: &[u8]) -> Option<Node> {
+    let (_, node) = node_parser(sentence.strip_prefix(b"$")?).ok()?;
+    Some(node)
 }
 
 // Liaison to the RedQueen2
@@ -33,8 +75,23 @@ pub struct Consort<Id> {
     dest: Node,
     sentence_parser: SentenceParser,
     transaction: Option<Transaction>,
+    // Id of the transaction an urgent command took the
+    // place of, its answer may still be on the way
+    superseded: Option<usize>,
+    // Id of the last transaction given up on, for the same reason
+    abandoned: Option<usize>,
+    // Recovered from, with their sender and sentence, see conformance.rs
+    deviations: Vec<(Node, Deviation, Vec<u8>)>,
+    invariants: Invariants,
+    // Of the reset cycles after failed transactions
+    backoff: Backoff,
+    cipher: LinkCipher,
     command_id_generator: Id,
-    now: Instant,
+    // Nothing goes out to them
+    silenced: Vec<Node>,
+    // The answers to OBG2 differ between them
+    profile: Profile,
+    clock: SharedClock,
 }
 
 impl From<NMEAFormatError<'_>> for Error {
@@ -46,7 +103,17 @@ impl From<NMEAFormatError<'_>> for Error {
 impl From<ProtocolError> for Error {
     fn from(value: ProtocolError) -> Self {
         error!("ProtocolError: {:?}", value);
-        Error::ProtocolError
+        match value {
+            ProtocolError::Nak => Error::Nak,
+            _ => Error::ProtocolError,
+        }
+    }
+}
+
+impl From<LinkError> for Error {
+    fn from(value: LinkError) -> Self {
+        error!("LinkError: {:?}", value);
+        Error::LinkCrypto(value)
     }
 }
 
@@ -90,6 +157,54 @@ impl Iterator for SimpleIdGenerator {
     }
 }
 
+// Transaction ids have three digits. The UI Consort and the
+// E32Worker keepalive both issue them, so each gets its own part
+// of the id space. That way an answer arriving late after the ids
+// wrapped can't be taken for one to the other's transaction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IdPartition {
+    Consort,
+    Keepalive,
+}
+
+#[derive(Debug)]
+pub struct PartitionedIdGenerator {
+    partition: IdPartition,
+    id: Option<usize>,
+}
+
+impl IdPartition {
+    pub fn range(&self) -> Range<usize> {
+        match self {
+            IdPartition::Consort => 0..500,
+            IdPartition::Keepalive => 500..1000,
+        }
+    }
+}
+
+impl PartitionedIdGenerator {
+    pub fn new(partition: IdPartition) -> Self {
+        Self {
+            partition,
+            id: None,
+        }
+    }
+}
+
+impl Iterator for PartitionedIdGenerator {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let range = self.partition.range();
+        let id = match self.id {
+            Some(id) if id + 1 < range.end => id + 1,
+            _ => range.start,
+        };
+        self.id = Some(id);
+        Some(id)
+    }
+}
+
 impl<Id> Consort<Id>
 where
     Id: Iterator<Item = usize>,
@@ -97,7 +212,7 @@ where
     pub fn new_with_id_generator(
         me: Node,
         dest: Node,
-        now: Instant,
+        clock: SharedClock,
         command_id_generator: Id,
     ) -> Self {
         let sentence_parser = SentenceParser::new();
@@ -106,8 +221,16 @@ where
             dest,
             sentence_parser,
             transaction: None,
+            superseded: None,
+            abandoned: None,
+            deviations: vec![],
+            invariants: Invariants::new(me),
+            backoff: Backoff::new(BackoffPolicy::default()),
+            cipher: LinkCipher::default(),
             command_id_generator,
-            now,
+            silenced: vec![],
+            profile: Profile::default(),
+            clock,
         }
     }
 
@@ -116,25 +239,156 @@ where
         command: Command,
         writer: &mut W,
     ) -> Result<(), Error> {
+        self.send_command_to(self.dest, command, writer)
+    }
+
+    // To another node than the target, e.g. resetting all of them
+    pub fn send_command_to<W: Write>(
+        &mut self,
+        recipient: Node,
+        command: Command,
+        writer: &mut W,
+    ) -> Result<(), Error> {
+        if self.is_silenced(recipient) && !silence_exempt(&command) {
+            return Err(Error::Silenced);
+        }
         match self.transaction {
             Some(_) => Err(Error::ActiveTransaction),
             None => {
+                let sealed = linkcrypto::is_sealed(&command) && self.cipher.seals(recipient);
                 let transaction =
-                    Transaction::new(self.me.clone(), self.dest.clone(), self.next_id(), command);
+                    Transaction::new(self.me.clone(), recipient, self.next_id(), command)
+                        .with_profile(self.profile);
                 let mut dest: [u8; 82] = [0; 82];
-                writer.write(transaction.commandeer(&mut dest)?)?;
+                let sentence = transaction.commandeer(&mut dest)?;
+                if sealed {
+                    let contents = self.cipher.seal(recipient, verify_nmea_format(sentence)?)?;
+                    let mut formatter = NMEAFormatter::default();
+                    formatter.format_sentence(&contents)?;
+                    writer.write(formatter.buffer()?)?;
+                } else {
+                    writer.write(sentence)?;
+                }
+                self.invariants.sent(transaction.id, transaction.recipient);
                 self.transaction = Some(transaction);
                 Ok(())
             }
         }
     }
 
+    // Doesn't wait for a transaction in flight, that one is dropped
+    // together with a late answer to it. The writer is expected to
+    // put the command ahead of anything queued.
+    pub fn send_superseding<W: Write>(
+        &mut self,
+        command: Command,
+        writer: &mut W,
+    ) -> Result<(), Error> {
+        self.send_superseding_to(self.dest, command, writer)
+    }
+
+    // E.g. locking the pyros of all nodes on exit
+    pub fn send_superseding_to<W: Write>(
+        &mut self,
+        recipient: Node,
+        command: Command,
+        writer: &mut W,
+    ) -> Result<(), Error> {
+        // The transaction in flight stays
+        if self.is_silenced(recipient) && !silence_exempt(&command) {
+            return Err(Error::Silenced);
+        }
+        self.superseded = self
+            .transaction
+            .take()
+            .map(|transaction| transaction.id)
+            .or(self.superseded);
+        self.send_command_to(recipient, command, writer)
+    }
+
+    // True if a timeout is the superseded transaction's
+    pub fn absorb_superseded(&mut self) -> bool {
+        self.superseded.take().is_some()
+    }
+
+    pub fn dest(&self) -> Node {
+        self.dest
+    }
+
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.profile = profile;
+    }
+
     pub fn busy(&self) -> bool {
         self.transaction.is_some()
     }
 
+    // The command of the transaction in flight
+    pub fn in_flight(&self) -> Option<Command> {
+        self.transaction
+            .as_ref()
+            .map(|transaction| transaction.command.clone())
+    }
+
     pub fn reset(&mut self) {
-        self.transaction = None
+        self.abandoned = self
+            .transaction
+            .take()
+            .map(|transaction| transaction.id)
+            .or(self.superseded.take())
+            .or(self.abandoned);
+        self.invariants.abandon();
+    }
+
+    pub fn invariants(&self) -> &Invariants {
+        &self.invariants
+    }
+
+    pub fn set_backoff_policy(&mut self, policy: BackoffPolicy) {
+        self.backoff.set_policy(policy);
+    }
+
+    // After a NAK or timeout, the next reset waits for the hold
+    pub fn back_off(&mut self) -> Hold {
+        let now = self.clock.now();
+        self.backoff.failed(now)
+    }
+
+    pub fn holding(&self) -> Option<Duration> {
+        self.backoff.holding(self.clock.now())
+    }
+
+    pub fn backoff_stats(&self) -> &BackoffStats {
+        self.backoff.stats()
+    }
+
+    pub fn set_link_keys(&mut self, keys: HashMap<Node, Key>) {
+        self.cipher.set_keys(keys);
+    }
+
+    pub fn set_silenced(&mut self, nodes: Vec<Node>) {
+        self.silenced = nodes;
+    }
+
+    pub fn is_silenced(&self, node: Node) -> bool {
+        self.silenced.contains(&node)
+    }
+
+    pub fn has_link_key(&self) -> bool {
+        self.cipher.has_key(self.dest)
+    }
+
+    // From the VERSION answer, whether the node reports the
+    // encryption, if that changed. Keys decide the sealing.
+    pub fn negotiate_encryption(&mut self, capabilities: Capabilities) -> Option<bool> {
+        self.cipher.negotiate(self.dest, capabilities)
+    }
+
+    // Launch control state changes, by name, are checked along with
+    // the protocol
+    pub fn check_transition(&mut self, from: String, to: String) {
+        let now = self.clock.now();
+        self.invariants.transition(now, from, to);
     }
 
     pub fn feed(
@@ -159,9 +413,36 @@ where
         }
         // if we extracted a sentence, process it
         if let Some(sentence) = extracted_sentence {
+            let (sentence, sealed) = self.open(sentence)?;
+            self.invariants.received(self.clock.now(), &sentence);
+            let sender = sender(&sentence).unwrap_or(self.dest);
             match &mut self.transaction {
                 Some(transaction) => {
-                    let result = Ok(Some(transaction.process_response(sentence.as_slice())?));
+                    if !sealed
+                        && linkcrypto::is_sealed(&transaction.command)
+                        && self.cipher.seals(transaction.recipient)
+                    {
+                        return Err(Error::LinkCrypto(LinkError::Unsealed));
+                    }
+                    let response = match transaction.process_response(sentence.as_slice()) {
+                        Err(ProtocolError::InvalidAssociation(_, _, id, _))
+                            if self.superseded == Some(id) =>
+                        {
+                            self.superseded = None;
+                            self.deviations
+                                .push((sender, Deviation::LateAnswer { id }, sentence));
+                            return Ok(None);
+                        }
+                        Err(err) => {
+                            if let Some(deviation) = deviation(&err, self.abandoned) {
+                                self.deviations.push((sender, deviation, sentence));
+                            }
+                            return Err(err.into());
+                        }
+                        Ok(response) => response,
+                    };
+                    self.backoff.succeeded();
+                    let result = Ok(Some(response));
                     if transaction.state() == TransactionState::Dead {
                         self.transaction = None;
                     }
@@ -169,6 +450,15 @@ where
                 }
                 // We don't expect data
                 None => {
+                    let id = verify_nmea_format(&sentence)
+                        .ok()
+                        .and_then(|contents| answer_header_parser(contents).ok())
+                        .map(|(_, (_, _, id, _))| id);
+                    let deviation = match id {
+                        Some(id) if self.abandoned == Some(id) => Deviation::LateAnswer { id },
+                        _ => Deviation::Unsolicited,
+                    };
+                    self.deviations.push((sender, deviation, sentence));
                     return Err(Error::SpuriousSentence);
                 }
             }
@@ -176,8 +466,26 @@ where
         Ok(None)
     }
 
-    pub fn update_time(&mut self, now: Instant) {
-        self.now = now;
+    // Since asked last
+    pub fn take_deviations(&mut self) -> Vec<(Node, Deviation, Vec<u8>)> {
+        std::mem::take(&mut self.deviations)
+    }
+
+    // Sealed answers continue as if they came in the clear, with
+    // whether they were sealed
+    fn open(&mut self, sentence: Vec<u8>) -> Result<(Vec<u8>, bool), Error> {
+        // Malformed ones are for the transaction to report
+        let Ok(contents) = verify_nmea_format(&sentence) else {
+            return Ok((sentence, false));
+        };
+        match self.cipher.open(contents)? {
+            Some(opened) => {
+                let mut formatter = NMEAFormatter::default();
+                formatter.format_sentence(&opened)?;
+                Ok((formatter.buffer()?.into(), true))
+            }
+            None => Ok((sentence, false)),
+        }
     }
 
     fn next_id(&mut self) -> usize {
@@ -196,7 +504,10 @@ mod tests {
         rc::Rc,
     };
 
-    use crate::observables::AdcGain;
+    use crate::{
+        clock,
+        observables::{groups::RawObservablesGroup, AdcGain},
+    };
 
     use super::*;
 
@@ -239,12 +550,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_partitioned_ids_wrap_within_partition() {
+        let mut ids = PartitionedIdGenerator::new(IdPartition::Keepalive);
+        let first: Vec<_> = ids.by_ref().take(500).collect();
+        assert_eq!(first, (500..1000).collect::<Vec<_>>());
+        assert_eq!(ids.next(), Some(500));
+    }
+
+    #[test]
+    fn test_partitioned_ids_never_collide() {
+        let mut consort = PartitionedIdGenerator::new(IdPartition::Consort);
+        let mut keepalive = PartitionedIdGenerator::new(IdPartition::Keepalive);
+        // Several wraparounds of both, with the keepalive
+        // issuing more ids than the consort
+        for _ in 0..2000 {
+            let a = consort.next().unwrap();
+            let b = keepalive.next().unwrap();
+            let c = keepalive.next().unwrap();
+            assert!(IdPartition::Consort.range().contains(&a));
+            assert!(IdPartition::Keepalive.range().contains(&b));
+            assert!(a != b && a != c);
+        }
+    }
+
     #[test]
     fn test_instantiation() {
         let _consort = Consort::new_with_id_generator(
             Node::LaunchControl,
             Node::RedQueen(b'A'),
-            Instant::now(),
+            clock::system(),
             SimpleIdGenerator::default(),
         );
     }
@@ -254,7 +589,7 @@ mod tests {
         let mut consort = Consort::new_with_id_generator(
             Node::LaunchControl,
             Node::RedQueen(b'A'),
-            Instant::now(),
+            clock::system(),
             SimpleIdGenerator::default(),
         );
         let mut mock_port = MockPort::default();
@@ -274,12 +609,35 @@ mod tests {
         assert!(inputbuffer.is_empty());
     }
 
+    #[test]
+    fn test_answers_parse_with_the_profile() {
+        let mut consort = Consort::new_with_id_generator(
+            Node::LaunchControl,
+            Node::RedQueen(b'A'),
+            clock::system(),
+            SimpleIdGenerator::default(),
+        );
+        consort.set_profile(Profile::TestStand);
+        let mut mock_port = MockPort::default();
+        consort
+            .send_command(Command::ObservableGroup(2), &mut mock_port)
+            .unwrap();
+        let mut inputbuffer = ringbuffer::AllocRingBuffer::new(256);
+        for c in b"$RQAOBG,001,LNC,2,R,FOOBAR.TXT,000000FF,12345678,ABCD,22*59\r\n" {
+            inputbuffer.push(*c);
+        }
+        assert_matches!(
+            consort.feed(&mut inputbuffer),
+            Ok(Some(Response::ObservableGroup(RawObservablesGroup::OG2(_))))
+        );
+    }
+
     #[test]
     fn test_sending_command_and_receiving_partial_answer() {
         let mut consort = Consort::new_with_id_generator(
             Node::LaunchControl,
             Node::RedQueen(b'A'),
-            Instant::now(),
+            clock::system(),
             SimpleIdGenerator::default(),
         );
         let mut mock_port = MockPort::default();
@@ -298,12 +656,191 @@ mod tests {
         assert_matches!(consort.transaction, Some(_));
     }
 
+    #[test]
+    fn test_sending_command_to_another_node() {
+        let mut consort = Consort::new_with_id_generator(
+            Node::LaunchControl,
+            Node::RedQueen(b'A'),
+            clock::system(),
+            SimpleIdGenerator::default(),
+        );
+        let mut mock_port = MockPort::default();
+        consort
+            .send_command_to(Node::RedQueen(b'B'), Command::Ping, &mut mock_port)
+            .unwrap();
+        assert_eq!(
+            mock_port.sent_messages.borrow_mut().pop(),
+            Some(b"$LNCCMD,001,RQB,PING*47\r\n".as_slice().into())
+        );
+        let mut inputbuffer = ringbuffer::AllocRingBuffer::new(256);
+        for c in b"$RQBACK,001,LNC*78\r\n" {
+            inputbuffer.push(*c);
+        }
+        assert_matches!(consort.feed(&mut inputbuffer), Ok(Some(Response::PingAck)));
+        assert_eq!(consort.dest(), Node::RedQueen(b'A'));
+    }
+
+    #[test]
+    fn test_nothing_goes_out_to_silenced_nodes() {
+        let mut consort = Consort::new_with_id_generator(
+            Node::LaunchControl,
+            Node::RedQueen(b'A'),
+            clock::system(),
+            SimpleIdGenerator::default(),
+        );
+        let mut mock_port = MockPort::default();
+        consort.set_silenced(vec![Node::RedQueen(b'A')]);
+        assert_matches!(
+            consort.send_command(Command::Ping, &mut mock_port),
+            Err(Error::Silenced)
+        );
+        assert!(mock_port.sent_messages.borrow().is_empty());
+        assert!(!consort.busy());
+        consort
+            .send_command_to(Node::RedQueen(b'B'), Command::Ping, &mut mock_port)
+            .unwrap();
+        assert_eq!(mock_port.sent_messages.borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_aborts_go_out_to_silenced_nodes() {
+        let mut consort = Consort::new_with_id_generator(
+            Node::LaunchControl,
+            Node::RedQueen(b'A'),
+            clock::system(),
+            SimpleIdGenerator::default(),
+        );
+        let mut mock_port = MockPort::default();
+        consort.set_silenced(vec![Node::RedQueen(b'A')]);
+        consort
+            .send_superseding(Command::Abort, &mut mock_port)
+            .unwrap();
+        assert_eq!(mock_port.sent_messages.borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_superseding_drops_the_late_answer() {
+        let mut consort = Consort::new_with_id_generator(
+            Node::LaunchControl,
+            Node::RedQueen(b'A'),
+            clock::system(),
+            SimpleIdGenerator::default(),
+        );
+        let mut mock_port = MockPort::default();
+        consort.send_command(Command::Ping, &mut mock_port).unwrap();
+        assert_eq!(
+            consort.send_command(Command::Abort, &mut mock_port),
+            Err(Error::ActiveTransaction)
+        );
+        consort
+            .send_superseding(Command::Abort, &mut mock_port)
+            .unwrap();
+        assert_eq!(
+            mock_port.sent_messages.borrow_mut().pop(),
+            Some(b"$LNCCMD,002,RQA,ABORT*1D\r\n".as_slice().into())
+        );
+        let mut inputbuffer = ringbuffer::AllocRingBuffer::new(256);
+        for c in b"$RQAACK,001,LNC*7B\r\n$RQAACK,002,LNC*78\r\n" {
+            inputbuffer.push(*c);
+        }
+        assert_matches!(consort.feed(&mut inputbuffer), Ok(None));
+        assert_matches!(consort.feed(&mut inputbuffer), Ok(Some(Response::AbortAck)));
+        assert!(!consort.absorb_superseded());
+        assert_eq!(consort.invariants().count(), 0);
+        assert_eq!(
+            consort.take_deviations(),
+            vec![(
+                Node::RedQueen(b'A'),
+                Deviation::LateAnswer { id: 1 },
+                b"$RQAACK,001,LNC*7B\r\n".to_vec()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_sealed_commands_once_negotiated() {
+        let rqa = Node::RedQueen(b'A');
+        let mut consort = Consort::new_with_id_generator(
+            Node::LaunchControl,
+            rqa,
+            clock::system(),
+            SimpleIdGenerator::default(),
+        );
+        let key = core::array::from_fn(|i| 0x80 + i as u8);
+        consort.set_link_keys(HashMap::from([(rqa, key)]));
+        let mut mock_port = MockPort::default();
+        consort
+            .send_command(Command::Version, &mut mock_port)
+            .unwrap();
+        let mut inputbuffer = ringbuffer::AllocRingBuffer::new(256);
+        for c in b"$RQAACK,001,LNC,1,0000003F*3F\r\n" {
+            inputbuffer.push(*c);
+        }
+        let Ok(Some(Response::VersionAck(version))) = consort.feed(&mut inputbuffer) else {
+            panic!("no version");
+        };
+        assert_eq!(
+            consort.negotiate_encryption(version.capabilities),
+            Some(true)
+        );
+
+        consort
+            .send_command(Command::Reset(AdcGain::Gain1), &mut mock_port)
+            .unwrap();
+        let sent = mock_port.sent_messages.borrow_mut().pop().unwrap();
+        assert!(sent.starts_with(b"$LNCCMD,002,RQA,ENC,"));
+        for c in b"$RQAACK,002,LNC,ENC,AAAAAAAAACo,o29YEB7ik02XUuAyFJSZW4wU*78\r\n" {
+            inputbuffer.push(*c);
+        }
+        assert_matches!(consort.feed(&mut inputbuffer), Ok(Some(Response::ResetAck)));
+        // A valid answer, but with a counter already seen
+        consort
+            .send_command(Command::Reset(AdcGain::Gain1), &mut mock_port)
+            .unwrap();
+        for c in b"$RQAACK,003,LNC,ENC,AAAAAAAAACo,o2/L+s1kiSqroVyPFp6D/B2r*0C\r\n" {
+            inputbuffer.push(*c);
+        }
+        assert_eq!(
+            consort.feed(&mut inputbuffer),
+            Err(Error::LinkCrypto(LinkError::Replayed))
+        );
+    }
+
+    #[test]
+    fn test_keyed_nodes_need_sealed_answers() {
+        let rqa = Node::RedQueen(b'A');
+        let mut consort = Consort::new_with_id_generator(
+            Node::LaunchControl,
+            rqa,
+            clock::system(),
+            SimpleIdGenerator::default(),
+        );
+        let key = core::array::from_fn(|i| 0x80 + i as u8);
+        consort.set_link_keys(HashMap::from([(rqa, key)]));
+        // A VERSION answer without the capability changes nothing
+        consort.negotiate_encryption(Capabilities::legacy());
+        let mut mock_port = MockPort::default();
+        consort
+            .send_command(Command::Reset(AdcGain::Gain1), &mut mock_port)
+            .unwrap();
+        let sent = mock_port.sent_messages.borrow_mut().pop().unwrap();
+        assert!(sent.starts_with(b"$LNCCMD,001,RQA,ENC,"));
+        let mut inputbuffer = ringbuffer::AllocRingBuffer::new(256);
+        for c in b"$RQAACK,001,LNC*7B\r\n" {
+            inputbuffer.push(*c);
+        }
+        assert_eq!(
+            consort.feed(&mut inputbuffer),
+            Err(Error::LinkCrypto(LinkError::Unsealed))
+        );
+    }
+
     #[test]
     fn test_sending_spurious_command() {
         let mut consort = Consort::new_with_id_generator(
             Node::LaunchControl,
             Node::RedQueen(b'A'),
-            Instant::now(),
+            clock::system(),
             SimpleIdGenerator::default(),
         );
 
@@ -312,5 +849,18 @@ mod tests {
             inputbuffer.push(*c);
         }
         assert_matches!(consort.feed(&mut inputbuffer), Err(Error::SpuriousSentence));
+        assert_matches!(
+            consort.take_deviations().as_slice(),
+            [(Node::RedQueen(b'A'), Deviation::Unsolicited, _)]
+        );
+        // Attributed to the node it came from, not the one talked to
+        for c in b"$RQBACK,123456.001,LNC,001*4C\r\n" {
+            inputbuffer.push(*c);
+        }
+        assert_matches!(consort.feed(&mut inputbuffer), Err(Error::SpuriousSentence));
+        assert_matches!(
+            consort.take_deviations().as_slice(),
+            [(Node::RedQueen(b'B'), Deviation::Unsolicited, _)]
+        );
     }
 }
@@ -0,0 +1,213 @@
+// The progress bars unlocking the pyros, preparing ignition and
+// leaving RF silence are cranked up with Right and decay when
+// left alone. How that feels depends on the input, the keyboard
+// repeats differently than the encoder on the pad, so the numbers
+// can be configured and, in dev builds, tuned live.
+use std::time::Duration;
+
+use crate::clock::Instant;
+
+pub const FULL: u8 = 100;
+// The decay amount is per tick, a frame at 60fps, so the decay
+// doesn't depend on the frame rate
+const DECAY_TICK: Duration = Duration::from_micros(16_667);
+// Of a stalled frame no more than that counts. The input queued
+// during the stall is processed right after, a hitch so can't
+// dump the progress.
+const MAX_DECAY_STEP: Duration = Duration::from_millis(100);
+
+// A drive of the progress timers, on the clock of the model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tick {
+    pub now: Instant,
+    // Since the drive before
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crank {
+    // Per Right event
+    pub increment: u8,
+    // How long after the last Right the progress starts to decay
+    pub decay_interval: Duration,
+    // Per tick once decaying
+    pub decay_amount: u8,
+    // Progress counted as complete
+    pub threshold: u8,
+}
+
+impl Default for Crank {
+    fn default() -> Self {
+        Self {
+            increment: 3,
+            decay_interval: Duration::from_millis(500),
+            decay_amount: 1,
+            threshold: FULL,
+        }
+    }
+}
+
+impl Crank {
+    // Snaps to full once past the threshold
+    pub fn advance(&self, progress: u8) -> u8 {
+        match progress.saturating_add(self.increment) {
+            p if p >= self.threshold => FULL,
+            p => p,
+        }
+    }
+
+    // Whole ticks since decaying up to this drive, minus those up to
+    // the drive before, so no fraction of a tick gets lost
+    pub fn decay(&self, progress: u8, last_update: Instant, tick: Tick) -> u8 {
+        let decaying_since = last_update + self.decay_interval;
+        if progress >= FULL || tick.now <= decaying_since {
+            return progress;
+        }
+        let ticks = |decaying: Duration| (decaying.as_nanos() / DECAY_TICK.as_nanos()) as u64;
+        let decaying = tick.now.duration_since(decaying_since);
+        let previous = decaying.saturating_sub(tick.elapsed.min(MAX_DECAY_STEP));
+        let amount = (ticks(decaying) - ticks(previous)) * self.decay_amount as u64;
+        progress.saturating_sub(amount.min(FULL as u64) as u8)
+    }
+}
+
+const PARAMETERS: [&str; 4] = ["Increment", "Decay interval", "Decay amount", "Threshold"];
+
+// The tuning page, only offered in dev builds
+#[derive(Debug, Default)]
+pub struct CrankTuner {
+    active: bool,
+    pub selected: usize,
+}
+
+impl CrankTuner {
+    pub fn start(&mut self) {
+        self.active = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % PARAMETERS.len();
+    }
+
+    pub fn adjust(&self, crank: &mut Crank, steps: i64) {
+        let step = |value: u8, min: u8| (value as i64 + steps).clamp(min as i64, FULL as i64) as u8;
+        match self.selected {
+            0 => crank.increment = step(crank.increment, 1),
+            1 => {
+                let millis = crank.decay_interval.as_millis() as i64 + steps * 50;
+                crank.decay_interval = Duration::from_millis(millis.clamp(50, 5000) as u64);
+            }
+            2 => crank.decay_amount = step(crank.decay_amount, 0),
+            _ => crank.threshold = step(crank.threshold, 1),
+        }
+    }
+
+    pub fn parameters(crank: &Crank) -> [(&'static str, String); 4] {
+        [
+            (PARAMETERS[0], format!("{} per click", crank.increment)),
+            (
+                PARAMETERS[1],
+                format!("{}ms", crank.decay_interval.as_millis()),
+            ),
+            (
+                PARAMETERS[2],
+                format!("{} per frame at 60fps", crank.decay_amount),
+            ),
+            (PARAMETERS[3], format!("{}%", crank.threshold)),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, SimulatedClock};
+
+    #[test]
+    fn test_threshold_and_decay() {
+        let clock = SimulatedClock::new(Instant::now());
+        let crank = Crank {
+            increment: 30,
+            threshold: 80,
+            decay_amount: 5,
+            ..Default::default()
+        };
+        assert_eq!(crank.advance(0), 30);
+        assert_eq!(crank.advance(30), 60);
+        assert_eq!(crank.advance(60), FULL);
+        let last_update = clock.now();
+        let frame = |elapsed| Tick {
+            now: clock.now(),
+            elapsed,
+        };
+        clock.advance(crank.decay_interval);
+        assert_eq!(crank.decay(60, last_update, frame(DECAY_TICK)), 60);
+        clock.advance(DECAY_TICK);
+        assert_eq!(crank.decay(60, last_update, frame(DECAY_TICK)), 55);
+        assert_eq!(crank.decay(3, last_update, frame(DECAY_TICK)), 0);
+        assert_eq!(crank.decay(FULL, last_update, frame(DECAY_TICK)), FULL);
+    }
+
+    // The same per second at any frame rate
+    fn decay_over_a_second(crank: &Crank, frame: Duration) -> u8 {
+        let clock = SimulatedClock::new(Instant::now());
+        let last_update = clock.now();
+        clock.advance(crank.decay_interval);
+        let mut progress = 90;
+        let end = clock.now() + Duration::from_secs(1);
+        while clock.now() < end {
+            clock.advance(frame);
+            let tick = Tick {
+                now: clock.now(),
+                elapsed: frame,
+            };
+            progress = crank.decay(progress, last_update, tick);
+        }
+        90 - progress
+    }
+
+    #[test]
+    fn test_decay_is_bounded_and_frame_rate_independent() {
+        let crank = Crank::default();
+        assert_eq!(decay_over_a_second(&crank, DECAY_TICK), 60);
+        assert_eq!(
+            decay_over_a_second(&crank, Duration::from_micros(33_334)),
+            60
+        );
+        assert_eq!(decay_over_a_second(&crank, Duration::from_millis(5)), 59);
+        // A hitch of two seconds costs what a tenth of a second does
+        let clock = SimulatedClock::new(Instant::now());
+        let last_update = clock.now();
+        clock.advance(Duration::from_secs(2));
+        let hitch = Tick {
+            now: clock.now(),
+            elapsed: Duration::from_secs(2),
+        };
+        assert_eq!(crank.decay(90, last_update, hitch), 84);
+    }
+
+    #[test]
+    fn test_tuning_stays_in_range() {
+        let mut crank = Crank::default();
+        let mut tuner = CrankTuner::default();
+        tuner.adjust(&mut crank, -10);
+        assert_eq!(crank.increment, 1);
+        tuner.next();
+        tuner.adjust(&mut crank, 2);
+        assert_eq!(crank.decay_interval, Duration::from_millis(600));
+        tuner.next();
+        tuner.next();
+        tuner.adjust(&mut crank, 5);
+        assert_eq!(crank.threshold, FULL);
+        tuner.next();
+        assert_eq!(tuner.selected, 0);
+    }
+}
@@ -0,0 +1,328 @@
+// A read-only view of the ground station for the safety officer's
+// phone, served over the field network. The page is self contained
+// and polls the state as JSON, the plots are SVG, so nothing needs
+// to be installed or fetched from elsewhere. Only the UI thread looks
+// at the model: the bin publishes a snapshot every so often and the
+// server thread hands out the latest one. Anything but GET is refused.
+use std::time::Duration;
+
+use serde::Serialize;
+use uom::si::{force::kilonewton, pressure::bar};
+
+use crate::{
+    connection::Connection, fsm::mode_name, model::Model, telemetry::ModuleStatus, timeline::Series,
+};
+
+pub const PUBLISH_INTERVAL: Duration = Duration::from_secs(1);
+// Of the plots, the phone doesn't need the whole session
+const PLOT_SPAN: f64 = 60.0;
+const PLOT_WIDTH: f64 = 400.0;
+const PLOT_HEIGHT: f64 = 150.0;
+const JOURNAL_ENTRIES: usize = 8;
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Launch Control</title>
+<style>
+body { background: #111; color: #eee; font-family: monospace; margin: 8px; }
+td { padding: 2px 8px; }
+img { width: 100%; max-width: 400px; display: block; }
+.stale { color: #f55; }
+</style>
+</head>
+<body>
+<h2 id="mode">Waiting for the ground station</h2>
+<table id="state"></table>
+<img id="thrust" alt="Thrust">
+<img id="pressure" alt="Pressure">
+<h3>Nodes</h3>
+<table id="nodes"></table>
+<h3>Journal</h3>
+<div id="journal"></div>
+<script>
+// Built as text, nothing from the ground station is taken for markup
+function row(cells, className) {
+  const tr = document.createElement("tr");
+  if (className) tr.className = className;
+  for (const cell of cells) {
+    const td = document.createElement("td");
+    td.textContent = cell === null ? "-" : String(cell);
+    tr.appendChild(td);
+  }
+  return tr;
+}
+function fixed(value, digits, unit) {
+  return value === null ? null : value.toFixed(digits) + unit;
+}
+async function refresh() {
+  try {
+    const state = await (await fetch("/state.json")).json();
+    document.getElementById("mode").textContent = state.mode;
+    document.getElementById("state").replaceChildren(
+      row(["MET", fixed(state.met_s, 1, "s")]),
+      row(["Session", fixed(state.elapsed_s, 0, "s")]),
+      row(["Connected", state.connected ? "yes" : "no"]),
+      row(["Thrust", fixed(state.thrust_kn, 3, "kN")]),
+      row(["Pressure", fixed(state.pressure_bar, 2, "bar")]),
+      row(["VBB", fixed(state.vbb_voltage, 2, "V")]),
+      row(["Anomalies", state.anomalies]));
+    document.getElementById("nodes").replaceChildren(...state.nodes.map(n =>
+      row([n.node, n.heard_s.toFixed(1) + "s ago", n.status || ""],
+        n.heard_s > 5 ? "stale" : null)));
+    document.getElementById("journal").replaceChildren(...state.journal.map(text => {
+      const div = document.createElement("div");
+      div.textContent = text;
+      return div;
+    }));
+    const now = Date.now();
+    document.getElementById("thrust").src = "/thrust.svg?" + now;
+    document.getElementById("pressure").src = "/pressure.svg?" + now;
+  } catch (e) {
+    document.getElementById("mode").textContent = "Ground station unreachable";
+  }
+}
+refresh();
+setInterval(refresh, 1000);
+</script>
+</body>
+</html>
+"#;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeHealth {
+    pub node: String,
+    pub heard_s: f64,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DashboardState {
+    pub mode: String,
+    pub elapsed_s: f64,
+    // Mission elapsed time, since T-0
+    pub met_s: Option<f64>,
+    pub connected: bool,
+    pub thrust_kn: Option<f64>,
+    pub pressure_bar: Option<f64>,
+    pub vbb_voltage: Option<f32>,
+    pub anomalies: Option<u32>,
+    pub nodes: Vec<NodeHealth>,
+    // The latest last
+    pub journal: Vec<String>,
+}
+
+// What the server hands out until the next publish
+#[derive(Debug, Clone, Default)]
+pub struct Published {
+    pub state: String,
+    pub thrust: String,
+    pub pressure: String,
+}
+
+pub fn snapshot<C: Connection, Id: Iterator<Item = usize>>(model: &Model<C, Id>) -> DashboardState {
+    let elapsed_s = model.elapsed().as_secs_f64();
+    let obg1 = model.target_obg1().and_then(|history| history.last());
+    let obg2 = model.target_obg2();
+    let nodes = model
+        .registered_nodes()
+        .iter()
+        .map(|node| NodeHealth {
+            node: format!("{:?}", node),
+            heard_s: model.heard_from_since(node).as_secs_f64(),
+            status: model.module_status(node).map(|status| match status {
+                ModuleStatus::Receiving => "receiving".into(),
+                ModuleStatus::NoModule => "no module".into(),
+            }),
+        })
+        .collect();
+    let entries = model.journal.entries();
+    DashboardState {
+        mode: mode_name(model.mode()),
+        elapsed_s,
+        met_s: model.t0().map(|t0| elapsed_s - t0),
+        connected: model.connected(),
+        thrust_kn: obg1.map(|obg1| obg1.thrust.get::<kilonewton>()),
+        pressure_bar: obg1.map(|obg1| obg1.pressure.get::<bar>()),
+        vbb_voltage: obg2.map(|obg2| obg2.vbb_voltage),
+        anomalies: obg2.and_then(|obg2| obg2.anomalies),
+        nodes,
+        journal: entries[entries.len().saturating_sub(JOURNAL_ENTRIES)..]
+            .iter()
+            .map(|entry| entry.text.clone())
+            .collect(),
+    }
+}
+
+// The plots are filtered like on screen
+pub fn publish<C: Connection, Id: Iterator<Item = usize>>(model: &Model<C, Id>) -> Published {
+    let plot = |series: Series, kind| {
+        let points = model
+            .filters
+            .apply(kind, model.timeline.points(series), None);
+        svg_plot(series.name(), &points)
+    };
+    Published {
+        state: serde_json::to_string(&snapshot(model)).unwrap(),
+        thrust: plot(Series::Thrust, model.filters.thrust),
+        pressure: plot(Series::Pressure, model.filters.pressure),
+    }
+}
+
+// The last PLOT_SPAN seconds as a polyline, labelled with the
+// range shown so the phone doesn't need axes
+pub fn svg_plot(name: &str, points: &[[f64; 2]]) -> String {
+    let start = points.last().map_or(0.0, |last| last[0] - PLOT_SPAN);
+    let shown: Vec<_> = points.iter().filter(|p| p[0] >= start).collect();
+    let (min, max) = shown.iter().fold((f64::MAX, f64::MIN), |(min, max), p| {
+        (min.min(p[1]), max.max(p[1]))
+    });
+    let range = if max > min { max - min } else { 1.0 };
+    let polyline: Vec<String> = shown
+        .iter()
+        .map(|[time, value]| {
+            format!(
+                "{:.1},{:.1}",
+                (time - start) / PLOT_SPAN * PLOT_WIDTH,
+                PLOT_HEIGHT - (value - min) / range * PLOT_HEIGHT
+            )
+        })
+        .collect();
+    let label = match shown.is_empty() {
+        true => format!("{}: no data", name),
+        false => format!("{}: {:.3} .. {:.3}", name, min, max),
+    };
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {w} {h}\">\
+         <rect width=\"{w}\" height=\"{h}\" fill=\"#222\"/>\
+         <polyline points=\"{points}\" fill=\"none\" stroke=\"#69f\" stroke-width=\"1.5\"/>\
+         <text x=\"4\" y=\"14\" fill=\"#eee\" font-family=\"monospace\" font-size=\"12\">{label}</text>\
+         </svg>",
+        w = PLOT_WIDTH,
+        h = PLOT_HEIGHT,
+        points = polyline.join(" "),
+        label = label
+    )
+}
+
+// Content type and body for the path, None for unknown paths.
+// A query is ignored, the page uses one to defeat caches.
+pub fn respond(published: &Published, path: &str) -> Option<(&'static str, String)> {
+    let path = path.split('?').next().unwrap_or_default();
+    match path {
+        "/" | "/index.html" => Some(("text/html; charset=utf-8", PAGE.into())),
+        "/state.json" => Some(("application/json", published.state.clone())),
+        "/thrust.svg" => Some(("image/svg+xml", published.thrust.clone())),
+        "/pressure.svg" => Some(("image/svg+xml", published.pressure.clone())),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "dashboard")]
+pub mod http {
+    use std::io::Cursor;
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    use anyhow::anyhow;
+    use log::{error, info};
+    use tiny_http::{Header, Method, Request, Response};
+
+    use super::{respond, Published, PUBLISH_INTERVAL};
+
+    pub struct Dashboard {
+        published: Arc<Mutex<Published>>,
+        last_publish: Option<Instant>,
+    }
+
+    impl Dashboard {
+        // Serves on its own thread until the process ends
+        pub fn start(address: &str) -> anyhow::Result<Self> {
+            let server = tiny_http::Server::http(address).map_err(|e| anyhow!("{}", e))?;
+            info!("Dashboard served on {}", address);
+            let published = Arc::new(Mutex::new(Published::default()));
+            let shared = published.clone();
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    serve(request, &shared);
+                }
+            });
+            Ok(Self {
+                published,
+                last_publish: None,
+            })
+        }
+
+        pub fn due(&self, now: Instant) -> bool {
+            self.last_publish
+                .map_or(true, |last| now - last >= PUBLISH_INTERVAL)
+        }
+
+        pub fn publish(&mut self, published: Published, now: Instant) {
+            *self.published.lock().unwrap() = published;
+            self.last_publish = Some(now);
+        }
+    }
+
+    fn serve(request: Request, published: &Mutex<Published>) {
+        let response = if *request.method() != Method::Get {
+            text_response(405, "Read-only")
+        } else {
+            match respond(&published.lock().unwrap(), request.url()) {
+                Some((content_type, body)) => Response::from_string(body).with_header(
+                    Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes()).unwrap(),
+                ),
+                None => text_response(404, "Not found"),
+            }
+        };
+        if let Err(e) = request.respond(response) {
+            error!("Dashboard response failed, {}", e);
+        }
+    }
+
+    fn text_response(status: u16, text: &str) -> Response<Cursor<Vec<u8>>> {
+        Response::from_string(text).with_status_code(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plot_shows_the_last_minute() {
+        let points: Vec<[f64; 2]> = (0..=120).map(|i| [i as f64, i as f64]).collect();
+        let svg = svg_plot("Thrust [kN]", &points);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("Thrust [kN]: 60.000 .. 120.000"));
+        assert!(svg.contains("points=\"0.0,150.0 "));
+        assert!(svg.contains(" 400.0,0.0\""));
+        assert!(svg_plot("Pressure [bar]", &[]).contains("Pressure [bar]: no data"));
+    }
+
+    #[test]
+    fn test_only_known_paths_are_served() {
+        let published = Published {
+            state: "{}".into(),
+            thrust: "<svg/>".into(),
+            pressure: "<svg></svg>".into(),
+        };
+        assert_eq!(
+            respond(&published, "/").unwrap().0,
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(
+            respond(&published, "/thrust.svg?1718000000"),
+            Some(("image/svg+xml", "<svg/>".into()))
+        );
+        assert_eq!(
+            respond(&published, "/state.json"),
+            Some(("application/json", "{}".into()))
+        );
+        assert_eq!(respond(&published, "/../config.json"), None);
+        let state = serde_json::to_value(DashboardState::default()).unwrap();
+        assert_eq!(state["met_s"], serde_json::Value::Null);
+    }
+}
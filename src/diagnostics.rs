@@ -0,0 +1,191 @@
+use std::collections::VecDeque;
+
+use crate::consort::Error as ConsortError;
+use crate::rqparser::MAX_BUFFER_SIZE;
+
+const RAW_SENTENCE_HISTORY: usize = 5;
+const CHECKSUM_ERROR_THRESHOLD: usize = 3;
+
+// Why the connection to the RQ failed, as far as
+// we can tell from our side.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailureCause {
+    PortUnavailable(String),
+    ModuleConfiguration(String),
+    NoConnection,
+    SendFailed,
+    ChecksumErrors(usize),
+    Nak,
+    ProtocolError,
+    Unauthenticated,
+}
+
+impl FailureCause {
+    pub fn description(&self) -> String {
+        match self {
+            FailureCause::PortUnavailable(reason) => format!("Serial port unavailable: {}", reason),
+            FailureCause::ModuleConfiguration(reason) => {
+                format!("E32 module configuration failed: {}", reason)
+            }
+            FailureCause::NoConnection => "No open E32 connection".into(),
+            FailureCause::SendFailed => "Sending data to the E32 module failed".into(),
+            FailureCause::ChecksumErrors(count) => {
+                format!("{} consecutive malformed sentences", count)
+            }
+            FailureCause::Nak => "The RQ rejected our command (NAK)".into(),
+            FailureCause::ProtocolError => "Unexpected answer from the RQ".into(),
+            FailureCause::Unauthenticated => "An encrypted answer failed authentication".into(),
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self {
+            FailureCause::PortUnavailable(_) => "Check the USB cable and the --port argument.",
+            FailureCause::ModuleConfiguration(_) => "Power-cycle the E32 module and check M0/M1.",
+            FailureCause::NoConnection => "Retry to re-open the serial port.",
+            FailureCause::SendFailed => "Check the USB cable and the E32 module power.",
+            FailureCause::ChecksumErrors(_) => "Check antennas, distance and interference.",
+            FailureCause::Nak => "Check the RQ state and the entered secrets.",
+            FailureCause::ProtocolError => "Check RQ and frontend firmware versions match.",
+            FailureCause::Unauthenticated => "Check the link keys, someone may be replaying.",
+        }
+    }
+
+    // Failures while opening the port aren't retried
+    // automatically, as this would just spin.
+    pub fn is_open_failure(&self) -> bool {
+        match self {
+            FailureCause::PortUnavailable(_) | FailureCause::ModuleConfiguration(_) => true,
+            _ => false,
+        }
+    }
+}
+
+pub struct Diagnostics {
+    pub cause: Option<FailureCause>,
+    raw_sentences: VecDeque<Vec<u8>>,
+    checksum_errors: usize,
+    // Times the E32 had to be reconfigured, kept across resets
+    pub reconfigurations: usize,
+    // Received at once beyond a single sentence, and bytes not fed
+    // to the parser after an error, kept across resets too
+    pub bursts: usize,
+    pub discarded_bytes: usize,
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self {
+            cause: None,
+            raw_sentences: VecDeque::with_capacity(RAW_SENTENCE_HISTORY),
+            checksum_errors: 0,
+            reconfigurations: 0,
+            bursts: 0,
+            discarded_bytes: 0,
+        }
+    }
+}
+
+impl Diagnostics {
+    pub fn record_sentence(&mut self, sentence: &[u8]) {
+        if sentence.len() > MAX_BUFFER_SIZE {
+            self.bursts += 1;
+        }
+        if self.raw_sentences.len() == RAW_SENTENCE_HISTORY {
+            self.raw_sentences.pop_front();
+        }
+        self.raw_sentences.push_back(sentence.into());
+    }
+
+    // Without a port there's no connection either, the
+    // reason the port is missing says more
+    pub fn record_failure(&mut self, cause: FailureCause) {
+        if cause == FailureCause::NoConnection
+            && self
+                .cause
+                .as_ref()
+                .map_or(false, FailureCause::is_open_failure)
+        {
+            return;
+        }
+        self.cause = Some(cause);
+    }
+
+    pub fn record_consort_error(&mut self, error: &ConsortError) {
+        match error {
+            ConsortError::NMEAFormatError => {
+                self.checksum_errors += 1;
+                if self.checksum_errors >= CHECKSUM_ERROR_THRESHOLD {
+                    self.cause = Some(FailureCause::ChecksumErrors(self.checksum_errors));
+                }
+            }
+            ConsortError::Nak => self.cause = Some(FailureCause::Nak),
+            ConsortError::ProtocolError => self.cause = Some(FailureCause::ProtocolError),
+            ConsortError::LinkCrypto(_) => self.cause = Some(FailureCause::Unauthenticated),
+            _ => {}
+        }
+    }
+
+    pub fn record_discarded(&mut self, bytes: usize) {
+        self.discarded_bytes += bytes;
+    }
+
+    pub fn record_reconfiguration(&mut self) {
+        self.reconfigurations += 1;
+    }
+
+    pub fn record_success(&mut self) {
+        self.checksum_errors = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.cause = None;
+        self.checksum_errors = 0;
+    }
+
+    pub fn raw_sentences(&self) -> impl Iterator<Item = String> + '_ {
+        self.raw_sentences
+            .iter()
+            .map(|s| String::from_utf8_lossy(s).trim_end().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum_errors_need_to_repeat() {
+        let mut diagnostics = Diagnostics::default();
+        diagnostics.record_consort_error(&ConsortError::NMEAFormatError);
+        diagnostics.record_consort_error(&ConsortError::NMEAFormatError);
+        assert_eq!(diagnostics.cause, None);
+        diagnostics.record_success();
+        diagnostics.record_consort_error(&ConsortError::NMEAFormatError);
+        diagnostics.record_consort_error(&ConsortError::NMEAFormatError);
+        diagnostics.record_consort_error(&ConsortError::NMEAFormatError);
+        assert_eq!(diagnostics.cause, Some(FailureCause::ChecksumErrors(3)));
+    }
+
+    #[test]
+    fn test_port_missing_outlasts_no_connection() {
+        let mut diagnostics = Diagnostics::default();
+        let missing = FailureCause::PortUnavailable("No such file or directory".into());
+        diagnostics.record_failure(missing.clone());
+        diagnostics.record_failure(FailureCause::NoConnection);
+        assert_eq!(diagnostics.cause, Some(missing));
+        diagnostics.record_failure(FailureCause::SendFailed);
+        assert_eq!(diagnostics.cause, Some(FailureCause::SendFailed));
+    }
+
+    #[test]
+    fn test_only_last_sentences_are_kept() {
+        let mut diagnostics = Diagnostics::default();
+        for i in 0..10 {
+            diagnostics.record_sentence(format!("$RQBACK,{:03},LNC\r\n", i).as_bytes());
+        }
+        let sentences: Vec<String> = diagnostics.raw_sentences().collect();
+        assert_eq!(sentences.len(), RAW_SENTENCE_HISTORY);
+        assert_eq!(sentences[0], "$RQBACK,005,LNC");
+    }
+}
@@ -0,0 +1,131 @@
+// Entering a number digit by digit with the encoder: Left/Right
+// change the current digit, Enter moves on to the next and Back
+// returns to the previous one. Used for the launch secrets, but
+// the number of digits and their base are up to the caller.
+use crate::input::InputEvent;
+
+pub const MAX_DIGITS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DigitEntry {
+    // Most significant first, only the first count are used
+    digits: [u8; MAX_DIGITS],
+    count: usize,
+    base: u8,
+    position: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DigitOutcome {
+    Editing(DigitEntry),
+    // Enter on the last digit
+    Entered(u32),
+    // Back on the first digit
+    Cancelled,
+}
+
+impl DigitEntry {
+    pub fn new(count: usize, base: u8) -> Self {
+        assert!(
+            (1..=MAX_DIGITS).contains(&count),
+            "1..={} digits",
+            MAX_DIGITS
+        );
+        assert!((2..=16).contains(&base), "base 2..=16");
+        Self {
+            digits: [0; MAX_DIGITS],
+            count,
+            base,
+            position: 0,
+        }
+    }
+
+    // All digits entered, for showing a finished value
+    pub fn with_value(count: usize, base: u8, mut value: u32) -> Self {
+        let mut entry = Self::new(count, base);
+        for digit in entry.digits[..count].iter_mut().rev() {
+            *digit = (value % base as u32) as u8;
+            value /= base as u32;
+        }
+        entry.position = count - 1;
+        entry
+    }
+
+    pub fn digits(&self) -> &[u8] {
+        &self.digits[..self.count]
+    }
+
+    // Of the digit being edited
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn value(&self) -> u32 {
+        self.digits()
+            .iter()
+            .fold(0, |value, digit| value * self.base as u32 + *digit as u32)
+    }
+
+    // Going back clears the digit left, as if it was never reached
+    pub fn process_event(&self, event: &InputEvent) -> DigitOutcome {
+        let mut entry = *self;
+        match event {
+            InputEvent::Enter if self.position + 1 == self.count => {
+                return DigitOutcome::Entered(self.value())
+            }
+            InputEvent::Enter => entry.position += 1,
+            InputEvent::Back if self.position == 0 => return DigitOutcome::Cancelled,
+            InputEvent::Back => {
+                entry.digits[self.position] = 0;
+                entry.position -= 1;
+            }
+            InputEvent::Left(_) | InputEvent::Right(_) => {
+                let digit = &mut entry.digits[self.position];
+                *digit = event.step_digit(*digit, self.base);
+            }
+            InputEvent::Send => {}
+        }
+        DigitOutcome::Editing(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::STEP_MAGNITUDE;
+
+    fn editing(outcome: DigitOutcome) -> DigitEntry {
+        match outcome {
+            DigitOutcome::Editing(entry) => entry,
+            outcome => panic!("still editing expected, got {:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn test_entering_a_hex_byte() {
+        let entry = DigitEntry::new(2, 16);
+        let entry = editing(entry.process_event(&InputEvent::Left(STEP_MAGNITUDE)));
+        assert_eq!(entry.digits(), &[15, 0]);
+        let entry = editing(entry.process_event(&InputEvent::Enter));
+        let entry = editing(entry.process_event(&InputEvent::Right(3 * STEP_MAGNITUDE)));
+        assert_eq!((entry.position(), entry.value()), (1, 0xf3));
+        assert_eq!(
+            entry.process_event(&InputEvent::Enter),
+            DigitOutcome::Entered(0xf3)
+        );
+        let back = editing(entry.process_event(&InputEvent::Back));
+        assert_eq!((back.position(), back.digits()), (0, [15, 0].as_slice()));
+        assert_eq!(
+            back.process_event(&InputEvent::Back),
+            DigitOutcome::Cancelled
+        );
+    }
+
+    #[test]
+    fn test_digits_of_a_value() {
+        let entry = DigitEntry::with_value(4, 10, 1234);
+        assert_eq!(entry.digits(), &[1, 2, 3, 4]);
+        assert_eq!(entry.value(), 1234);
+        assert_eq!(DigitEntry::with_value(2, 16, 0xa5).digits(), &[10, 5]);
+    }
+}
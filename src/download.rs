@@ -0,0 +1,516 @@
+// Pulls small files the node recorded onboard back over the radio.
+// The node answers one command at a time, so the file is opened,
+// read chunk by chunk in order and closed again. The reassembled
+// data is checked against the CRC32 the node reports on opening.
+// Between downloads the node's storage can be listed entry by entry
+// and files deleted, to clear the SD card from the ground.
+use std::path::{Path, PathBuf};
+
+use crate::rqprotocol::{Command, FileEntry, Response};
+
+// Hex encoded this still fits an NMEA sentence with the ack header
+pub const CHUNK_SIZE: usize = 24;
+// Per command, a lost answer just means asking again
+const MAX_ATTEMPTS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Saved(PathBuf),
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadState {
+    Opening,
+    Reading { size: usize, checksum: u32 },
+    // The file is closed on the node whatever the outcome
+    Closing(Outcome),
+    Finished(Outcome),
+}
+
+#[derive(Debug)]
+pub struct Download {
+    pub name: String,
+    pub state: DownloadState,
+    destination: PathBuf,
+    data: Vec<u8>,
+    attempts: usize,
+}
+
+// On the node's storage, one at a time and never during a download
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileOperation {
+    Listing { index: usize, attempts: usize },
+    Deleting { name: String, attempts: usize },
+}
+
+#[derive(Debug, Default)]
+pub struct Downloader {
+    active: bool,
+    pub download: Option<Download>,
+    pub files: Vec<FileEntry>,
+    pub selected: Option<usize>,
+    pub operation: Option<FileOperation>,
+    // The selected file is deleted on confirmation
+    pub confirm_delete: bool,
+    // Of the last listing or deletion
+    pub failure: Option<String>,
+}
+
+// CRC-32 as used by zlib and most SD card firmware
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFFFFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                crc >> 1 ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+impl Download {
+    // Only the file name is used, the node
+    // must not write outside of directory.
+    pub fn new(name: &str, directory: &Path) -> Self {
+        let file_name = Path::new(name)
+            .file_name()
+            .map_or("download".into(), |n| n.to_owned());
+        Self {
+            name: name.into(),
+            state: DownloadState::Opening,
+            destination: directory.join(file_name),
+            data: vec![],
+            attempts: 0,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, DownloadState::Finished(_))
+    }
+
+    pub fn progress(&self) -> f32 {
+        match self.state {
+            DownloadState::Opening => 0.0,
+            DownloadState::Reading { size: 0, .. } => 1.0,
+            DownloadState::Reading { size, .. } => self.data.len() as f32 / size as f32,
+            DownloadState::Closing(_) | DownloadState::Finished(_) => 1.0,
+        }
+    }
+
+    pub fn received(&self) -> usize {
+        self.data.len()
+    }
+
+    // The command to send next, repeated until its answer arrives
+    pub fn next_command(&mut self) -> Option<Command> {
+        let command = match &self.state {
+            DownloadState::Opening => Command::FileOpen(self.name.clone()),
+            DownloadState::Reading { size, .. } => {
+                let offset = self.data.len();
+                Command::FileRead(offset, CHUNK_SIZE.min(size - offset))
+            }
+            DownloadState::Closing(_) => Command::FileClose,
+            DownloadState::Finished(_) => return None,
+        };
+        self.attempts += 1;
+        if self.attempts > MAX_ATTEMPTS {
+            let outcome = Outcome::Failed(format!("no answer to {:?}", command));
+            self.state = match self.state {
+                // The node closes stale files on its own eventually
+                DownloadState::Closing(_) => DownloadState::Finished(outcome),
+                _ => DownloadState::Closing(outcome),
+            };
+            self.attempts = 0;
+            return self.next_command();
+        }
+        Some(command)
+    }
+
+    pub fn process_response(&mut self, response: &Response) {
+        self.attempts = 0;
+        self.state = match (&self.state, response) {
+            (DownloadState::Opening, Response::FileOpenAck { size, checksum }) => {
+                DownloadState::Reading {
+                    size: *size,
+                    checksum: *checksum,
+                }
+            }
+            (DownloadState::Reading { size, checksum }, Response::FileReadAck { offset, data }) => {
+                if *offset != self.data.len() || data.is_empty() || offset + data.len() > *size {
+                    DownloadState::Closing(Outcome::Failed(format!(
+                        "bad chunk at offset {}",
+                        offset
+                    )))
+                } else {
+                    self.data.extend(data);
+                    if self.data.len() == *size {
+                        DownloadState::Closing(self.verify(*checksum))
+                    } else {
+                        self.state.clone()
+                    }
+                }
+            }
+            (DownloadState::Closing(outcome), Response::FileCloseAck) => {
+                DownloadState::Finished(outcome.clone())
+            }
+            _ => return,
+        };
+        if let DownloadState::Reading { size: 0, checksum } = self.state {
+            self.state = DownloadState::Closing(self.verify(checksum));
+        }
+    }
+
+    fn verify(&self, checksum: u32) -> Outcome {
+        let actual = crc32(&self.data);
+        if actual != checksum {
+            return Outcome::Failed(format!(
+                "checksum {:08X} instead of {:08X}",
+                actual, checksum
+            ));
+        }
+        match std::fs::write(&self.destination, &self.data) {
+            Ok(()) => Outcome::Saved(self.destination.clone()),
+            Err(e) => Outcome::Failed(format!("can't write {:?}, {}", self.destination, e)),
+        }
+    }
+
+    pub fn cancel(&mut self) {
+        self.state = match self.state {
+            DownloadState::Opening => DownloadState::Finished(Outcome::Failed("cancelled".into())),
+            DownloadState::Reading { .. } => {
+                DownloadState::Closing(Outcome::Failed("cancelled".into()))
+            }
+            _ => return,
+        };
+    }
+}
+
+impl Downloader {
+    pub fn start(&mut self) {
+        self.active = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    // A running download has to finish or be cancelled first
+    pub fn request(&mut self, name: &str, directory: &Path) -> bool {
+        if self.is_running() {
+            return false;
+        }
+        self.download = Some(Download::new(name, directory));
+        true
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.download.as_ref().map_or(false, |d| !d.is_finished())
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.is_running() || self.operation.is_some()
+    }
+
+    // From the first entry on
+    pub fn refresh(&mut self) -> bool {
+        if self.is_busy() {
+            return false;
+        }
+        self.operation = Some(FileOperation::Listing {
+            index: 0,
+            attempts: 0,
+        });
+        self.confirm_delete = false;
+        self.failure = None;
+        true
+    }
+
+    pub fn select(&mut self, steps: i64) {
+        if self.files.is_empty() || self.confirm_delete {
+            return;
+        }
+        let selected = match self.selected {
+            Some(selected) => (selected as i64 + steps).rem_euclid(self.files.len() as i64),
+            None => 0,
+        };
+        self.selected = Some(selected as usize);
+    }
+
+    pub fn selected_file(&self) -> Option<&FileEntry> {
+        self.files.get(self.selected?)
+    }
+
+    // Of the selected file, once confirmed
+    pub fn ask_delete(&mut self) -> bool {
+        self.confirm_delete = !self.is_busy() && self.selected_file().is_some();
+        self.confirm_delete
+    }
+
+    // The name of the file being deleted
+    pub fn delete_selected(&mut self) -> Option<String> {
+        if !self.confirm_delete || self.is_busy() {
+            return None;
+        }
+        self.confirm_delete = false;
+        let name = self.selected_file()?.name.clone();
+        self.failure = None;
+        self.operation = Some(FileOperation::Deleting {
+            name: name.clone(),
+            attempts: 0,
+        });
+        Some(name)
+    }
+
+    // The download goes first
+    pub fn next_command(&mut self) -> Option<Command> {
+        if self.is_running() {
+            return self.download.as_mut()?.next_command();
+        }
+        let (command, attempts) = match self.operation.as_mut()? {
+            FileOperation::Listing { index, attempts } => (Command::FileList(*index), attempts),
+            FileOperation::Deleting { name, attempts } => {
+                (Command::FileDelete(name.clone()), attempts)
+            }
+        };
+        *attempts += 1;
+        if *attempts > MAX_ATTEMPTS {
+            self.fail(format!("no answer to {:?}", command));
+            return None;
+        }
+        Some(command)
+    }
+
+    pub fn process_response(&mut self, response: &Response) {
+        match (self.operation.clone(), response) {
+            (
+                Some(FileOperation::Listing { index, .. }),
+                Response::FileListAck {
+                    index: listed,
+                    count,
+                    entry,
+                },
+            ) if index == *listed => {
+                if index == 0 {
+                    self.files.clear();
+                }
+                match entry {
+                    Some(entry) if index + 1 < *count => {
+                        self.files.push(entry.clone());
+                        self.operation = Some(FileOperation::Listing {
+                            index: index + 1,
+                            attempts: 0,
+                        });
+                    }
+                    _ => {
+                        self.files.extend(entry.clone());
+                        self.operation = None;
+                        self.selected = match self.files.len() {
+                            0 => None,
+                            len => Some(self.selected.unwrap_or(0).min(len - 1)),
+                        };
+                    }
+                }
+            }
+            (Some(FileOperation::Deleting { .. }), Response::FileDeleteAck) => {
+                self.operation = None;
+                self.refresh();
+            }
+            (_, Response::FileListAck { .. } | Response::FileDeleteAck) => {}
+            _ => {
+                if let Some(download) = &mut self.download {
+                    download.process_response(response);
+                }
+            }
+        }
+    }
+
+    // False if no file operation was in flight
+    pub fn refused(&mut self) -> bool {
+        if self.is_running() || self.operation.is_none() {
+            return false;
+        }
+        self.fail("refused by the node".into());
+        true
+    }
+
+    fn fail(&mut self, reason: String) {
+        self.failure = match self.operation.take() {
+            Some(FileOperation::Listing { .. }) => Some(format!("Listing failed, {}", reason)),
+            Some(FileOperation::Deleting { name, .. }) => {
+                Some(format!("Deleting {} failed, {}", name, reason))
+            }
+            None => return,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn test_download_reassembles_and_verifies() {
+        let directory = std::env::temp_dir();
+        let contents: Vec<u8> = (0..60).collect();
+        let name = format!("lnc-download-{}.dat", std::process::id());
+        let mut download = Download::new(&format!("/../{}", name), &directory);
+        assert_eq!(
+            download.next_command(),
+            Some(Command::FileOpen(format!("/../{}", name)))
+        );
+        download.process_response(&Response::FileOpenAck {
+            size: contents.len(),
+            checksum: crc32(&contents),
+        });
+        while let Some(Command::FileRead(offset, length)) = download.next_command() {
+            // A lost answer is asked for again
+            if offset == CHUNK_SIZE && download.attempts == 1 {
+                continue;
+            }
+            download.process_response(&Response::FileReadAck {
+                offset,
+                data: contents[offset..offset + length].to_vec(),
+            });
+        }
+        let path = directory.join(&name);
+        assert_eq!(
+            download.state,
+            DownloadState::Closing(Outcome::Saved(path.clone()))
+        );
+        download.process_response(&Response::FileCloseAck);
+        assert!(download.is_finished());
+        assert_eq!(std::fs::read(&path).unwrap(), contents);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_download_fails_on_checksum_and_silence() {
+        let mut download = Download::new("TEST.DAT", &std::env::temp_dir());
+        download.next_command();
+        download.process_response(&Response::FileOpenAck {
+            size: 2,
+            checksum: 0,
+        });
+        download.process_response(&Response::FileReadAck {
+            offset: 0,
+            data: vec![1, 2],
+        });
+        assert!(matches!(
+            download.state,
+            DownloadState::Closing(Outcome::Failed(_))
+        ));
+        for _ in 0..MAX_ATTEMPTS {
+            assert_eq!(download.next_command(), Some(Command::FileClose));
+        }
+        assert_eq!(download.next_command(), None);
+        assert!(download.is_finished());
+    }
+
+    fn entry(name: &str, size: usize) -> FileEntry {
+        FileEntry {
+            name: name.into(),
+            size,
+        }
+    }
+
+    #[test]
+    fn test_listing_and_deleting_files() {
+        let mut downloader = Downloader::default();
+        assert!(downloader.refresh());
+        assert!(!downloader.refresh());
+        assert_eq!(downloader.next_command(), Some(Command::FileList(0)));
+        downloader.process_response(&Response::FileListAck {
+            index: 0,
+            count: 2,
+            entry: Some(entry("REC001.DAT", 100)),
+        });
+        // Stray acks of earlier entries are ignored
+        downloader.process_response(&Response::FileListAck {
+            index: 0,
+            count: 2,
+            entry: Some(entry("REC001.DAT", 100)),
+        });
+        assert_eq!(downloader.next_command(), Some(Command::FileList(1)));
+        downloader.process_response(&Response::FileListAck {
+            index: 1,
+            count: 2,
+            entry: Some(entry("REC002.DAT", 200)),
+        });
+        assert_eq!(downloader.next_command(), None);
+        assert_eq!(
+            downloader.files,
+            vec![entry("REC001.DAT", 100), entry("REC002.DAT", 200)]
+        );
+        assert_eq!(downloader.selected, Some(0));
+        downloader.select(-1);
+        assert_eq!(downloader.selected_file(), Some(&entry("REC002.DAT", 200)));
+        assert_eq!(downloader.delete_selected(), None);
+        assert!(downloader.ask_delete());
+        // No changing the file while asked
+        downloader.select(1);
+        assert_eq!(downloader.delete_selected(), Some("REC002.DAT".into()));
+        assert_eq!(
+            downloader.next_command(),
+            Some(Command::FileDelete("REC002.DAT".into()))
+        );
+        downloader.process_response(&Response::FileDeleteAck);
+        // Deleting lists the files again
+        assert_eq!(downloader.next_command(), Some(Command::FileList(0)));
+        downloader.process_response(&Response::FileListAck {
+            index: 0,
+            count: 1,
+            entry: Some(entry("REC001.DAT", 100)),
+        });
+        assert_eq!(downloader.files, vec![entry("REC001.DAT", 100)]);
+        assert_eq!(downloader.selected, Some(0));
+    }
+
+    #[test]
+    fn test_file_operations_fail() {
+        let mut downloader = Downloader::default();
+        downloader.files = vec![entry("REC001.DAT", 100)];
+        downloader.selected = Some(0);
+        downloader.ask_delete();
+        downloader.delete_selected();
+        assert!(downloader.refused());
+        assert!(!downloader.refused());
+        assert_eq!(
+            downloader.failure,
+            Some("Deleting REC001.DAT failed, refused by the node".into())
+        );
+        downloader.refresh();
+        for _ in 0..MAX_ATTEMPTS {
+            assert_eq!(downloader.next_command(), Some(Command::FileList(0)));
+        }
+        assert_eq!(downloader.next_command(), None);
+        assert_eq!(
+            downloader.failure,
+            Some("Listing failed, no answer to FileList(0)".into())
+        );
+        // An empty storage
+        downloader.refresh();
+        downloader.next_command();
+        downloader.process_response(&Response::FileListAck {
+            index: 0,
+            count: 0,
+            entry: None,
+        });
+        assert!(downloader.files.is_empty());
+        assert_eq!(downloader.selected, None);
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::anyhow;
-use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender, TryRecvError, TrySendError};
 use ebyte_e32::{mode::Normal, Ebyte, Parameters};
 
 #[cfg(not(feature = "novaview"))]
@@ -10,27 +10,58 @@ use log::{debug, error, info, warn};
 use nb::block;
 
 use serial_core::{BaudRate, CharSize, FlowControl, Parity, PortSettings, SerialPort, StopBits};
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
 
 #[cfg(feature = "novaview")]
 use crate::e32linux::CtsAux;
 
 use std::{
     thread::{self, JoinHandle},
-    time::{Duration, Instant},
+    time::{Duration, SystemTime},
 };
 
 use crate::{
-    connection::{Answers, Connection},
+    airtime::{DutyCycle, DutyCycleStatus},
+    clock::{Instant, SharedClock},
+    connection::{
+        Answers, Connection, Line, LinkActivity, QueueDepths, ANSWER_TIMEOUT, OBG2_EVERY,
+    },
+    diagnostics::FailureCause,
+    observables::Profile,
     recorder::Recorder,
     rqparser::{SentenceParser, MAX_BUFFER_SIZE},
-    rqprotocol::{Command, Node, Response, Transaction},
+    rqprotocol::{Capabilities, Command, Error as ProtocolError, Node, Response, Transaction},
+    shutdown::{self, Stoppable},
+    tcpbridge::{self, Latency, TcpConnection},
 };
 
 #[cfg(feature = "novaview")]
 use crate::e32linux::{M0Dtr, M1Rts, Serial, StandardDelay};
 
-const ANSWER_TIMEOUT: Duration = Duration::from_millis(100);
+pub const DEFAULT_DRAIN_IDLE: Duration = Duration::from_millis(500);
+// A line that never goes quiet still ends the purge
+const MAX_DRAIN_DURATION: Duration = Duration::from_secs(10);
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const DEFAULT_LINK_ACTIVITY: LinkActivity = LinkActivity::Polling(DEFAULT_POLL_INTERVAL);
+// After a brownout the module can come back with factory
+// parameters and silently stop hearing the RedQueen.
+const AUDIT_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const AUDIT_AFTER_TIMEOUTS: usize = 3;
+// Full queues mean a stalled UI or a blocked worker. Commands and
+// answers to them are never dropped, the sender waits instead.
+// Observables are polled again anyway, the oldest are dropped.
+const COMMAND_QUEUE: usize = 64;
+const URGENT_QUEUE: usize = 16;
+const ANSWER_QUEUE: usize = 64;
+const OBSERVABLES_QUEUE: usize = 16;
 
 pub type E32Module = Ebyte<Serial, CtsAux, M0Dtr, M1Rts, StandardDelay, Normal>;
 
@@ -38,29 +69,87 @@ pub type E32Module = Ebyte<Serial, CtsAux, M0Dtr, M1Rts, StandardDelay, Normal>;
 enum Commands {
     Open(String),
     Send(Vec<u8>),
-    Drain,
+    // Data is waiting in the urgent channel
+    Urgent,
+    Drain(Duration),
     Quit,
     Reset,
     Resume,
-    RadioSilence,
-    NoRadioSilence,
+    LinkActivity(LinkActivity),
+    Capabilities(Capabilities),
+    PollNodes(Vec<Node>),
+    QueryContinuity,
+    Monitor(bool),
+    DutyCycleCap(Option<f64>),
+    FlushRecording,
+    Bridge(Latency),
+}
+
+// Never blocks, the oldest is dropped when full
+struct DropOldest<T> {
+    sender: Sender<T>,
+    // To drop from
+    receiver: Receiver<T>,
+    dropped: Arc<AtomicUsize>,
 }
 
 struct E32Worker<Id> {
     command_receiver: Receiver<Commands>,
+    // Sent before anything in the command queue
+    urgent_receiver: Receiver<Vec<u8>>,
+    // Taken out of the queue past superseded sends, still to do
+    deferred: VecDeque<Commands>,
     response_sender: Sender<Answers>,
+    observables_sender: DropOldest<Answers>,
     command_id_generator: Id,
     me: Node,
     target_red_queen: Node,
+    profile: Profile,
+    // Nodes polled for observables in turn
+    poll_nodes: Vec<Node>,
+    poll_index: usize,
+    // OBG2 requested out of turn
+    continuity_requested: bool,
     recorder: Recorder,
+    clock: SharedClock,
+    audit: Audit,
+    duty_cycle: DutyCycle,
+    // Shown in the status bar
+    duty_cycle_status: Arc<Mutex<DutyCycleStatus>>,
+    // Ports are opened as TCP bridges with this latency, not the E32
+    bridge: Option<Latency>,
 }
 
 pub struct E32Connection {
     worker: Option<JoinHandle<()>>,
     command_sender: Sender<Commands>,
+    urgent_sender: Sender<Vec<u8>>,
     response_receiver: Receiver<Answers>,
+    observables_receiver: Receiver<Answers>,
+    dropped_observables: Arc<AtomicUsize>,
     busy: bool,
-    is_radio_silence: bool,
+    link_activity: LinkActivity,
+    is_monitor: bool,
+    drain_idle: Duration,
+    duty_cycle: Arc<Mutex<DutyCycleStatus>>,
+    // Asked to stop by the shutdown, the quit maybe still queued
+    stopping: bool,
+    quit_sent: bool,
+}
+
+// Eats incoming bytes until the line has been quiet
+// for the idle window.
+struct Purge {
+    started: Instant,
+    last_byte: Instant,
+    idle: Duration,
+    reported: Option<u8>,
+}
+
+// When to read the parameters back from the module
+struct Audit {
+    last: Instant,
+    timeouts: usize,
 }
 
 impl E32Connection {
@@ -68,44 +157,144 @@ impl E32Connection {
         command_id_generator: Id,
         me: Node,
         target_red_queen: Node,
+        profile: Profile,
         recorder: Recorder,
+        clock: SharedClock,
     ) -> anyhow::Result<E32Connection> {
-        let (command_sender, command_receiver) = unbounded::<Commands>();
-        let (response_sender, response_receiver) = unbounded::<Answers>();
+        let (command_sender, command_receiver) = bounded::<Commands>(COMMAND_QUEUE);
+        let (urgent_sender, urgent_receiver) = bounded::<Vec<u8>>(URGENT_QUEUE);
+        let (response_sender, response_receiver) = bounded::<Answers>(ANSWER_QUEUE);
+        let observables_sender = DropOldest::new(OBSERVABLES_QUEUE);
+        let observables_receiver = observables_sender.receiver.clone();
+        let dropped_observables = observables_sender.dropped.clone();
+        let duty_cycle = Arc::new(Mutex::new(DutyCycleStatus::default()));
+        let duty_cycle_status = duty_cycle.clone();
         let handle = thread::spawn(move || {
             let mut worker = E32Worker {
                 command_receiver,
+                urgent_receiver,
+                deferred: VecDeque::new(),
                 response_sender,
+                observables_sender,
                 command_id_generator,
                 me,
                 target_red_queen,
+                profile,
+                poll_nodes: vec![target_red_queen],
+                poll_index: 0,
+                continuity_requested: false,
                 recorder,
+                audit: Audit::new(clock.now()),
+                clock,
+                duty_cycle: DutyCycle::default(),
+                duty_cycle_status,
+                bridge: None,
             };
             worker.work();
         });
         Ok(E32Connection {
             worker: Some(handle),
             command_sender,
+            urgent_sender,
             response_receiver,
+            observables_receiver,
+            dropped_observables,
             busy: false,
-            is_radio_silence: false,
+            link_activity: DEFAULT_LINK_ACTIVITY,
+            is_monitor: false,
+            drain_idle: DEFAULT_DRAIN_IDLE,
+            duty_cycle,
+            stopping: false,
+            quit_sent: false,
         })
     }
 
+    // Before the first open
+    pub fn use_bridge(&mut self, latency: Latency) {
+        self.command_sender.send(Commands::Bridge(latency)).unwrap();
+    }
+
     pub fn raw_module(port: &str) -> anyhow::Result<E32Module> {
         Ok(create(&port, default_parameters())?)
     }
 
     fn quit(&mut self) {
-        self.command_sender.send(Commands::Quit).expect("crossbeam");
-        // See https://stackoverflow.com/questions/57670145/how-to-store-joinhandle-of-a-thread-to-close-it-later
-        self.worker.take().map(JoinHandle::join);
+        self.stop();
+        while !self.try_join() {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    // A full queue means the worker is stuck, it's named
+    // by the shutdown instead of holding up the exit
+    fn send_on_exit(&self, command: Commands) {
+        if let Err(err) = self.command_sender.try_send(command) {
+            warn!("Not sent on exit, {}", err);
+        }
+    }
+
+    // Answers are thrown away meanwhile, a worker waiting to
+    // send one would never see the quit otherwise
+    fn send_quit(&mut self) {
+        self.response_receiver.try_iter().for_each(drop);
+        if !self.quit_sent {
+            self.quit_sent = !matches!(
+                self.command_sender.try_send(Commands::Quit),
+                Err(TrySendError::Full(_))
+            );
+        }
+    }
+}
+
+impl Stoppable for E32Connection {
+    fn name(&self) -> &'static str {
+        "E32 worker"
+    }
+
+    fn stop(&mut self) {
+        self.stopping = true;
+        self.send_quit();
+    }
+
+    fn try_join(&mut self) -> bool {
+        self.send_quit();
+        self.quit_sent && shutdown::try_join(&mut self.worker)
+    }
+}
+
+impl<T> DropOldest<T> {
+    fn new(capacity: usize) -> Self {
+        let (sender, receiver) = bounded(capacity);
+        Self {
+            sender,
+            receiver,
+            dropped: Default::default(),
+        }
+    }
+
+    fn send(&self, mut item: T) {
+        loop {
+            match self.sender.try_send(item) {
+                Ok(()) | Err(TrySendError::Disconnected(_)) => return,
+                Err(TrySendError::Full(back)) => {
+                    if self.receiver.try_recv().is_ok() {
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                    item = back;
+                }
+            }
+        }
     }
 }
 
 impl Connection for E32Connection {
+    // Answers to commands first
     fn recv(&mut self, callback: impl FnOnce(Answers)) {
-        match self.response_receiver.try_recv() {
+        let answer = match self.response_receiver.try_recv() {
+            Err(TryRecvError::Empty) => self.observables_receiver.try_recv(),
+            answer => answer,
+        };
+        match answer {
             Ok(answer) => {
                 self.busy = false;
                 callback(answer);
@@ -118,7 +307,9 @@ impl Connection for E32Connection {
     }
 
     fn drain(&mut self) {
-        self.command_sender.send(Commands::Drain).unwrap();
+        self.command_sender
+            .send(Commands::Drain(self.drain_idle))
+            .unwrap();
     }
 
     fn open(&mut self, port: &str) {
@@ -135,23 +326,183 @@ impl Connection for E32Connection {
         self.command_sender.send(Commands::Resume).unwrap();
     }
 
-    fn radio_silence(&mut self, radio_silence: bool) {
-        if radio_silence != self.is_radio_silence {
-            self.is_radio_silence = radio_silence;
+    fn link_activity(&mut self, activity: LinkActivity) {
+        if activity != self.link_activity {
+            self.link_activity = activity;
             self.command_sender
-                .send(match radio_silence {
-                    true => Commands::RadioSilence,
-                    false => Commands::NoRadioSilence,
-                })
+                .send(Commands::LinkActivity(activity))
                 .unwrap();
         }
     }
+
+    fn capabilities(&mut self, capabilities: Capabilities) {
+        self.command_sender
+            .send(Commands::Capabilities(capabilities))
+            .unwrap();
+    }
+
+    fn monitor(&mut self, monitor: bool) {
+        if monitor != self.is_monitor {
+            self.is_monitor = monitor;
+            self.command_sender
+                .send(Commands::Monitor(monitor))
+                .unwrap();
+        }
+    }
+
+    fn set_drain_idle(&mut self, drain_idle: Duration) {
+        self.drain_idle = drain_idle;
+    }
+
+    fn set_poll_nodes(&mut self, nodes: Vec<Node>) {
+        self.command_sender
+            .send(Commands::PollNodes(nodes))
+            .unwrap();
+    }
+
+    fn query_continuity(&mut self) {
+        self.command_sender.send(Commands::QueryContinuity).unwrap();
+    }
+
+    fn set_duty_cycle_cap(&mut self, cap: Option<f64>) {
+        self.command_sender
+            .send(Commands::DutyCycleCap(cap))
+            .unwrap();
+    }
+
+    fn duty_cycle(&self) -> Option<DutyCycleStatus> {
+        Some(*self.duty_cycle.lock().unwrap())
+    }
+
+    fn flush_recording(&mut self) {
+        self.send_on_exit(Commands::FlushRecording);
+    }
+
+    fn stop_polling(&mut self) {
+        self.send_on_exit(Commands::Reset);
+    }
+
+    fn worker(&mut self) -> Option<&mut dyn Stoppable> {
+        Some(self)
+    }
+
+    fn queue_depths(&self) -> Option<QueueDepths> {
+        Some(QueueDepths {
+            commands: self.command_sender.len(),
+            urgent: self.urgent_sender.len(),
+            answers: self.response_receiver.len(),
+            observables: self.observables_receiver.len(),
+            dropped_observables: self.dropped_observables.load(Ordering::Relaxed),
+        })
+    }
+
+    fn send_urgent(&mut self, data: &[u8]) -> std::io::Result<()> {
+        debug!("urgent: {}", std::str::from_utf8(data).unwrap());
+        self.urgent_sender
+            .send(data.into())
+            .expect("crossbeam always works");
+        self.command_sender
+            .send(Commands::Urgent)
+            .expect("crossbeam always works");
+        self.busy = true;
+        Ok(())
+    }
 }
 
 impl Drop for E32Connection {
     fn drop(&mut self) {
         info!("dropping E32Connection");
-        self.quit();
+        // Left behind by the shutdown otherwise
+        if !self.stopping {
+            self.quit();
+        }
+    }
+}
+
+// The sends queued ahead of urgent data are superseded by it, their
+// answers would be taken for its own. The marker of the urgent data
+// ends them, the other commands stay in order.
+fn drop_superseded(receiver: &Receiver<Commands>, deferred: &mut VecDeque<Commands>) {
+    while let Ok(command) = receiver.try_recv() {
+        match command {
+            Commands::Urgent => break,
+            Commands::Send(data) => warn!(
+                "Dropping superseded {}",
+                String::from_utf8_lossy(&data).trim_end()
+            ),
+            command => deferred.push_back(command),
+        }
+    }
+}
+
+impl Commands {
+    // Commands the operator is waiting for end a purge
+    fn cancels_purge(&self) -> bool {
+        match self {
+            Commands::Open(_) | Commands::Send(_) | Commands::Urgent | Commands::Quit => true,
+            _ => false,
+        }
+    }
+}
+
+// Answered like a finished one, nobody is left waiting for the purge
+fn cancel_purge(purge: &mut Option<Purge>, answers: &Sender<Answers>, by: &str) {
+    if purge.take().is_some() {
+        warn!("Draining cancelled by {}", by);
+        answers.send(Answers::DrainCancelled).unwrap();
+    }
+}
+
+impl Purge {
+    fn new(idle: Duration, now: Instant) -> Self {
+        Self {
+            started: now,
+            last_byte: now,
+            idle,
+            reported: None,
+        }
+    }
+
+    fn progress(&self, now: Instant) -> u8 {
+        let quiet = (now - self.last_byte).as_secs_f32() / self.idle.as_secs_f32();
+        let total = (now - self.started).as_secs_f32() / MAX_DRAIN_DURATION.as_secs_f32();
+        (quiet.max(total).min(1.0) * 100.0) as u8
+    }
+
+    // Returns the progress if it changed since last asked
+    fn report(&mut self, now: Instant) -> Option<u8> {
+        let progress = self.progress(now);
+        if self.reported == Some(progress) {
+            return None;
+        }
+        self.reported = Some(progress);
+        Some(progress)
+    }
+}
+
+impl Audit {
+    fn new(now: Instant) -> Self {
+        Self {
+            last: now,
+            timeouts: 0,
+        }
+    }
+
+    // A module that stopped hearing anything is looked at right away
+    fn due(&self, now: Instant) -> bool {
+        now - self.last >= AUDIT_INTERVAL || self.timeouts >= AUDIT_AFTER_TIMEOUTS
+    }
+
+    fn timeout(&mut self) {
+        self.timeouts += 1;
+    }
+
+    fn answered(&mut self) {
+        self.timeouts = 0;
+    }
+
+    fn done(&mut self, now: Instant) {
+        *self = Self::new(now);
     }
 }
 
@@ -160,75 +511,112 @@ where
     Id: Iterator<Item = usize>,
 {
     fn work(&mut self) {
-        let mut module = None;
+        let mut module: Option<Box<dyn Line>> = None;
         let mut fetch_observables = false;
-        let mut is_radio_silence = false;
+        let mut link_activity = DEFAULT_LINK_ACTIVITY;
+        let mut capabilities = Capabilities::legacy();
+        let mut purge: Option<Purge> = None;
+        // Only set while monitoring the channel
+        let mut monitor: Option<SentenceParser> = None;
         loop {
-            match self
-                .command_receiver
-                .recv_timeout(Duration::from_millis(100))
-            {
-                Ok(m) => match m {
-                    Commands::RadioSilence => is_radio_silence = true,
-                    Commands::NoRadioSilence => is_radio_silence = false,
-                    Commands::Reset => fetch_observables = false,
-                    Commands::Resume => fetch_observables = true,
-                    Commands::Quit => {
-                        break;
+            // While purging or monitoring we only peek for commands
+            let command_timeout = match link_activity {
+                _ if purge.is_some() || monitor.is_some() => Duration::ZERO,
+                LinkActivity::Polling(interval) => interval,
+                LinkActivity::Silent => DEFAULT_POLL_INTERVAL,
+            };
+            while let Ok(data) = self.urgent_receiver.try_recv() {
+                cancel_purge(&mut purge, &self.response_sender, "urgent data");
+                drop_superseded(&self.command_receiver, &mut self.deferred);
+                self.transmit(&mut module, &data);
+            }
+            let command = match self.deferred.pop_front() {
+                Some(command) => Ok(command),
+                None => self.command_receiver.recv_timeout(command_timeout),
+            };
+            match command {
+                Ok(m) => {
+                    if m.cancels_purge() {
+                        cancel_purge(&mut purge, &self.response_sender, &format!("{:?}", m));
                     }
-                    Commands::Open(port) => match create(&port, default_parameters()) {
-                        Ok(m) => {
-                            module = Some(m);
-                            self.response_sender.send(Answers::ConnectionOpen).unwrap();
+                    match m {
+                        Commands::LinkActivity(activity) => link_activity = activity,
+                        Commands::Capabilities(c) => capabilities = c,
+                        Commands::Monitor(true) => monitor = Some(SentenceParser::new()),
+                        Commands::Monitor(false) => monitor = None,
+                        Commands::PollNodes(nodes) => {
+                            if !nodes.is_empty() {
+                                self.poll_nodes = nodes;
+                                self.poll_index = 0;
+                            }
                         }
-                        Err(e) => {
-                            error!("Can't open port {}, reason: {}", port, e);
+                        Commands::QueryContinuity => self.continuity_requested = true,
+                        Commands::FlushRecording => self.recorder.flush(),
+                        Commands::Bridge(latency) => self.bridge = Some(latency),
+                        Commands::DutyCycleCap(cap) => self.duty_cycle.set_cap(cap),
+                        Commands::Reset => fetch_observables = false,
+                        Commands::Resume => fetch_observables = true,
+                        Commands::Quit => {
+                            break;
                         }
-                    },
-                    Commands::Send(data) => match &mut module {
-                        Some(module) => {
-                            debug!("sending {}", std::str::from_utf8(&data).unwrap());
-                            match module.write_buffer(&data) {
-                                Ok(_) => {
-                                    if Self::receive_sentence_or_timeout(
-                                        module,
-                                        |sentence| {
-                                            self.response_sender
-                                                .send(Answers::Received(sentence.clone()))
-                                                .expect("can't ack data");
-                                        },
-                                        &mut self.recorder,
-                                    ) {
-                                        self.send_timeout();
-                                    }
-                                }
-                                Err(err) => {
-                                    error!("Sending data to module failed {:?}, sending Answers::ConnectionError", err);
-                                    self.response_sender
-                                        .send(Answers::ConnectionError)
-                                        .expect("cc works!");
-                                }
+                        Commands::Open(port) => match self.open(&port) {
+                            Ok(m) => {
+                                module = Some(m);
+                                self.audit.done(self.clock.now());
+                                self.response_sender.send(Answers::ConnectionOpen).unwrap();
+                            }
+                            Err(e) => {
+                                error!("Can't open port {}, reason: {}", port, e);
+                                let cause = match self.bridged(&port) {
+                                    // As good as a missing port
+                                    true => FailureCause::PortUnavailable(e.to_string()),
+                                    false => open_failure_cause(&e),
+                                };
+                                self.response_sender
+                                    .send(Answers::ConnectionError(cause))
+                                    .expect("cc works");
+                            }
+                        },
+                        Commands::Send(data) => self.transmit(&mut module, &data),
+                        // Only wakes us up, the data came ahead of it
+                        Commands::Urgent => {}
+                        Commands::Drain(idle) => {
+                            if module.is_some() {
+                                warn!("Draining");
+                                purge = Some(Purge::new(idle, self.clock.now()));
+                            } else {
+                                // Nothing to purge without a port
+                                self.response_sender.send(Answers::Drained).unwrap();
                             }
                         }
-                        None => {
-                            error!("No open E32 connection");
-                            // To prevent spinning and log spam, wait a bit
-                            std::thread::sleep(Duration::from_millis(500));
-                            self.response_sender
-                                .send(Answers::ConnectionError)
-                                .expect("cc works");
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Some(p) = &mut purge {
+                        let finished = match &mut module {
+                            Some(module) => self.drain(module.as_mut(), p),
+                            None => true,
+                        };
+                        if finished {
+                            warn!("Drained");
+                            purge = None;
+                            self.response_sender.send(Answers::Drained).unwrap();
                         }
-                    },
-                    Commands::Drain => {
+                    } else if let Some(parser) = &mut monitor {
+                        // We stay silent and just listen
                         if let Some(module) = &mut module {
-                            self.drain(module);
+                            self.listen(module.as_mut(), parser);
                         }
-                    }
-                },
-                Err(RecvTimeoutError::Timeout) => {
-                    if fetch_observables && !is_radio_silence {
+                    } else if module.is_some() && self.audit_due() {
                         if let Some(module) = &mut module {
-                            self.fetch_observables(module);
+                            self.audit_parameters(module.as_mut());
+                        }
+                    } else if fetch_observables
+                        && link_activity != LinkActivity::Silent
+                        && self.duty_cycle.allows_polling(self.clock.now())
+                    {
+                        if let Some(module) = &mut module {
+                            self.fetch_observables(module.as_mut(), &capabilities);
                         }
                     }
                 }
@@ -236,55 +624,170 @@ where
                     panic!("Crossbeam is angry");
                 }
             }
+            *self.duty_cycle_status.lock().unwrap() = self.duty_cycle.status(self.clock.now());
+        }
+    }
+
+    // A serial port, e.g. of the failover or picked, is still
+    // the E32 when the primary is a bridge
+    fn bridged(&self, port: &str) -> bool {
+        self.bridge.is_some() && tcpbridge::is_address(port)
+    }
+
+    fn open(&self, port: &str) -> anyhow::Result<Box<dyn Line>> {
+        Ok(match self.bridge {
+            Some(latency) if self.bridged(port) => Box::new(TcpConnection::connect(port, latency)?),
+            _ => Box::new(create(port, default_parameters())?),
+        })
+    }
+
+    fn transmit(&mut self, module: &mut Option<Box<dyn Line>>, data: &[u8]) {
+        match module {
+            Some(module) => {
+                debug!("sending {}", std::str::from_utf8(data).unwrap());
+                self.recorder.store_sent(data, SystemTime::now());
+                match module.write_buffer(data) {
+                    Ok(_) => {
+                        self.duty_cycle.record(self.clock.now(), data.len());
+                        if Self::receive_sentence_or_timeout(
+                            module.as_mut(),
+                            |sentence| {
+                                self.response_sender
+                                    .send(Answers::Received(sentence.clone()))
+                                    .expect("can't ack data");
+                            },
+                            &mut self.recorder,
+                        ) {
+                            self.send_timeout();
+                        } else {
+                            self.audit.answered();
+                        }
+                    }
+                    Err(err) => {
+                        error!(
+                            "Sending data to module failed {:?}, sending Answers::ConnectionError",
+                            err
+                        );
+                        self.response_sender
+                            .send(Answers::ConnectionError(FailureCause::SendFailed))
+                            .expect("cc works!");
+                    }
+                }
+            }
+            None => {
+                error!("No open E32 connection");
+                // To prevent spinning and log spam, wait a bit
+                std::thread::sleep(Duration::from_millis(500));
+                self.response_sender
+                    .send(Answers::ConnectionError(FailureCause::NoConnection))
+                    .expect("cc works");
+            }
+        }
+    }
+
+    // Windows builds never configure the module
+    fn audit_due(&self) -> bool {
+        cfg!(not(target_os = "windows")) && self.audit.due(self.clock.now())
+    }
+
+    fn audit_parameters(&mut self, module: &mut dyn Line) {
+        self.audit.done(self.clock.now());
+        if let Some(answer) = module.audit() {
+            self.response_sender.send(answer).unwrap();
+        }
+    }
+
+    // Reads at most one byte, so commands are still
+    // looked at. Returns if the line is quiet.
+    fn drain(&mut self, module: &mut dyn Line, purge: &mut Purge) -> bool {
+        if let Ok(c) = block!(module.read()) {
+            self.recorder.store(c, SystemTime::now());
+            purge.last_byte = self.clock.now();
         }
+        let now = self.clock.now();
+        if let Some(progress) = purge.report(now) {
+            self.response_sender
+                .send(Answers::Draining(progress))
+                .unwrap();
+        }
+        purge.progress(now) == 100
     }
 
-    // We just eat incoming bytes for 5 secs
-    // before we go back to resetting.
-    fn drain(&mut self, module: &mut E32Module) {
-        warn!("Draining");
-        let until = Instant::now() + Duration::from_secs(5);
-        while Instant::now() < until {
-            let _ = block!(module.read()).map(|c| self.recorder.store(c));
+    // Relays all sentences until the line is quiet. A busy
+    // channel still gets us back to looking at commands.
+    fn listen(&mut self, module: &mut dyn Line, parser: &mut SentenceParser) {
+        for _ in 0..MAX_BUFFER_SIZE * 4 {
+            let b = match block!(module.read()) {
+                Ok(b) => b,
+                Err(_) => break,
+            };
+            self.recorder.store(b, SystemTime::now());
+            let mut sentence: Option<Vec<u8>> = None;
+            if parser
+                .feed(&[b], |sentence_| sentence = Some(sentence_.to_vec()))
+                .is_err()
+            {
+                *parser = SentenceParser::new();
+            }
+            if let Some(sentence) = sentence {
+                self.response_sender
+                    .send(Answers::Monitored(sentence))
+                    .unwrap();
+            }
         }
-        warn!("Drained");
-        self.response_sender.send(Answers::Drained).unwrap();
     }
 
-    fn fetch_observables(&mut self, module: &mut E32Module) {
+    fn fetch_observables(&mut self, module: &mut dyn Line, capabilities: &Capabilities) {
+        let node = self.poll_nodes[self.poll_index % self.poll_nodes.len()];
+        self.poll_index += 1;
         let id = self.command_id_generator.next().unwrap();
-        let obg = if id % 5 == 0 { 2 } else { 1 };
-        let mut t = Transaction::new(
-            self.me,
-            self.target_red_queen,
-            id,
-            Command::ObservableGroup(obg),
-        );
-        debug!("Send obg{} {}", obg, id);
+        // Capabilities are only known for the target. A Farduino
+        // is on the rail and only asked for the breakwire.
+        let obg = if let Node::Farduino(_) = node {
+            3
+        } else if (id % OBG2_EVERY == 0 || self.continuity_requested)
+            && node == self.target_red_queen
+            && capabilities.supports(Capabilities::OBG2)
+        {
+            self.continuity_requested = false;
+            2
+        } else {
+            1
+        };
+        let mut t = Transaction::new(self.me, node, id, Command::ObservableGroup(obg))
+            .with_profile(self.profile);
+        debug!("Send obg{} {} to {:?}", obg, id, node);
         let mut dest: [u8; MAX_BUFFER_SIZE] = [0; MAX_BUFFER_SIZE];
         let result = t.commandeer(&mut dest).unwrap();
+        self.recorder.store_sent(result, SystemTime::now());
         module.write_buffer(result).expect("can't send data");
+        self.duty_cycle.record(self.clock.now(), result.len());
         // First come the observables, so we relay them
         if Self::receive_sentence_or_timeout(
             module,
             |sentence| match t.process_response(sentence) {
                 Ok(response) => {
                     if let Response::ObservableGroup(observables) = response {
-                        self.response_sender
-                            .send(Answers::Observables(observables))
-                            .unwrap();
+                        self.observables_sender
+                            .send(Answers::Observables(node, observables));
                     }
                 }
-                Err(_) => {
-                    self.response_sender.send(Answers::ConnectionError).unwrap();
+                Err(err) => {
+                    self.response_sender
+                        .send(Answers::ConnectionError(match err {
+                            ProtocolError::Nak => FailureCause::Nak,
+                            _ => FailureCause::ProtocolError,
+                        }))
+                        .unwrap();
                     return;
                 }
             },
             &mut self.recorder,
         ) {
             debug!("timeout getting OBG{} data", obg);
-            self.send_timeout();
+            self.send_poll_timeout(node);
         } else {
+            self.audit.answered();
             // now the ack is supposed to happen
             if Self::receive_sentence_or_timeout(
                 module,
@@ -294,20 +797,33 @@ where
                 &mut self.recorder,
             ) {
                 debug!("timeout getting OBG{} ack", obg);
-                self.send_timeout();
+                self.send_poll_timeout(node);
             }
         }
         debug!("finished obg{} keepalive", obg);
     }
 
     fn send_timeout(&mut self) {
+        self.audit.timeout();
         self.response_sender
             .send(Answers::Timeout)
             .expect("can't ack data");
     }
 
+    // Only the target's silence says something about the link
+    fn send_poll_timeout(&mut self, node: Node) {
+        if node == self.target_red_queen {
+            self.send_timeout();
+            return;
+        }
+        self.audit.timeout();
+        self.response_sender
+            .send(Answers::PollTimeout(node))
+            .expect("can't ack data");
+    }
+
     fn receive_sentence_or_timeout(
-        module: &mut E32Module,
+        module: &mut dyn Line,
         callback: impl FnOnce(&Vec<u8>),
         recorder: &mut Recorder,
     ) -> bool {
@@ -316,7 +832,7 @@ where
         loop {
             match block!(module.read()) {
                 Ok(b) => {
-                    recorder.store(b);
+                    recorder.store(b, SystemTime::now());
                     let mut sentence: Option<Vec<u8>> = None;
                     sentence_parser
                         .feed(&[b], |sentence_| sentence = Some(sentence_.to_vec()))
@@ -360,6 +876,50 @@ impl std::io::Write for E32Connection {
     }
 }
 
+// Errors from the serial layer mean we couldn't even open
+// the port, everything else happened talking to the module.
+fn open_failure_cause(err: &anyhow::Error) -> FailureCause {
+    match err.downcast_ref::<serial_core::Error>() {
+        Some(err) => FailureCause::PortUnavailable(err.to_string()),
+        None => FailureCause::ModuleConfiguration(err.to_string()),
+    }
+}
+
+impl Line for E32Module {
+    fn write_buffer(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        Ok(Ebyte::write_buffer(self, data)?)
+    }
+
+    fn read(&mut self) -> nb::Result<u8, std::io::Error> {
+        Read::read(self)
+    }
+
+    // Reading the parameters doesn't transmit, so
+    // this is fine during radio silence as well.
+    fn audit(&mut self) -> Option<Answers> {
+        let wanted = default_parameters();
+        match self.parameters() {
+            Ok(active) if active == wanted => {
+                debug!("E32 parameters unchanged");
+                None
+            }
+            Ok(active) => {
+                warn!("E32 parameters drifted to {:?}", active);
+                Some(match configure(self, &wanted) {
+                    Ok(()) => Answers::ModuleReconfigured(format!("{:?}", active)),
+                    Err(e) => {
+                        Answers::ConnectionError(FailureCause::ModuleConfiguration(e.to_string()))
+                    }
+                })
+            }
+            Err(e) => {
+                error!("Can't read E32 parameters: {:?}", e);
+                None
+            }
+        }
+    }
+}
+
 fn default_parameters() -> Parameters {
     Parameters {
         address: 0x524F,
@@ -474,3 +1034,87 @@ fn configure(module: &mut E32Module, parameters: &Parameters) -> anyhow::Result<
     }
     Err(anyhow!("Can't configure module!"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_purge_progress() {
+        let start = Instant::now();
+        let mut purge = Purge::new(Duration::from_millis(500), start);
+        assert_eq!(purge.report(start), Some(0));
+        assert_eq!(purge.report(start), None);
+        assert_eq!(purge.progress(start + Duration::from_millis(250)), 50);
+        // Incoming bytes restart the idle window
+        purge.last_byte = start + Duration::from_millis(400);
+        assert_eq!(purge.progress(start + Duration::from_millis(650)), 50);
+        assert_eq!(purge.progress(start + Duration::from_millis(900)), 100);
+        // A busy line is given up on eventually
+        purge.last_byte = start + MAX_DRAIN_DURATION;
+        assert_eq!(purge.progress(start + MAX_DRAIN_DURATION), 100);
+    }
+
+    #[test]
+    fn test_cancelled_purges_are_answered() {
+        let (sender, receiver) = bounded(1);
+        let mut purge = None;
+        cancel_purge(&mut purge, &sender, "Quit");
+        assert!(receiver.try_recv().is_err());
+        purge = Some(Purge::new(Duration::from_millis(500), Instant::now()));
+        cancel_purge(&mut purge, &sender, "urgent data");
+        assert!(purge.is_none());
+        assert!(matches!(receiver.try_recv(), Ok(Answers::DrainCancelled)));
+    }
+
+    #[test]
+    fn test_observables_drop_the_oldest() {
+        let channel = DropOldest::new(2);
+        for item in 0..5 {
+            channel.send(item);
+        }
+        assert_eq!(channel.receiver.try_iter().collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(channel.dropped.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_urgent_data_drops_the_sends_queued_ahead() {
+        let (sender, receiver) = bounded(8);
+        for command in [
+            Commands::Send(b"$RQAIGN,001*00\r\n".to_vec()),
+            Commands::Resume,
+            Commands::Urgent,
+            Commands::Send(b"$RQAPNG,002*00\r\n".to_vec()),
+        ] {
+            sender.send(command).unwrap();
+        }
+        let mut deferred = VecDeque::new();
+        drop_superseded(&receiver, &mut deferred);
+        assert_eq!(deferred, vec![Commands::Resume]);
+        // Queued after the urgent data
+        assert_eq!(
+            receiver.try_recv(),
+            Ok(Commands::Send(b"$RQAPNG,002*00\r\n".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_audit_schedule() {
+        let start = Instant::now();
+        let mut audit = Audit::new(start);
+        assert!(!audit.due(start));
+        for _ in 0..AUDIT_AFTER_TIMEOUTS - 1 {
+            audit.timeout();
+        }
+        audit.answered();
+        audit.timeout();
+        assert!(!audit.due(start));
+        for _ in 0..AUDIT_AFTER_TIMEOUTS - 1 {
+            audit.timeout();
+        }
+        assert!(audit.due(start));
+        audit.done(start);
+        assert!(!audit.due(start + AUDIT_INTERVAL - Duration::from_secs(1)));
+        assert!(audit.due(start + AUDIT_INTERVAL));
+    }
+}
@@ -8,6 +8,7 @@ use crossbeam_channel::{unbounded, Receiver, Sender, TryRecvError};
 use crate::{
     connection::{Answers, Connection},
     rqparser::{command_parser, MAX_BUFFER_SIZE},
+    shutdown::{self, Stoppable},
 };
 
 enum Command {
@@ -19,6 +20,8 @@ pub struct E32Connection {
     command_sender: Sender<Command>,
     response_receiver: Receiver<Answers>,
     worker: Option<JoinHandle<()>>,
+    // Asked to stop by the shutdown
+    stopping: bool,
 }
 
 impl Connection for E32Connection {
@@ -33,6 +36,10 @@ impl Connection for E32Connection {
             }
         }
     }
+
+    fn worker(&mut self) -> Option<&mut dyn Stoppable> {
+        Some(self)
+    }
 }
 
 impl std::io::Write for E32Connection {
@@ -70,6 +77,7 @@ impl E32Connection {
             command_sender,
             response_receiver,
             worker: Some(handle),
+            stopping: false,
         })
     }
 
@@ -80,9 +88,27 @@ impl E32Connection {
     }
 }
 
+impl Stoppable for E32Connection {
+    fn name(&self) -> &'static str {
+        "E32 worker"
+    }
+
+    fn stop(&mut self) {
+        self.stopping = true;
+        self.command_sender.send(Command::Quit).expect("crossbeam");
+    }
+
+    fn try_join(&mut self) -> bool {
+        shutdown::try_join(&mut self.worker)
+    }
+}
+
 impl Drop for E32Connection {
     fn drop(&mut self) {
-        self.quit();
+        // Left behind by the shutdown otherwise
+        if !self.stopping {
+            self.quit();
+        }
     }
 }
 
@@ -0,0 +1,115 @@
+// The errors of the modules, wrapped up so they can be reported
+// from wherever they happen. Reports end up in the session journal
+// with their origin, instead of only scrolling by on stderr. The
+// module errors stay as they are for matching on them locally.
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use log::error;
+
+use crate::consort;
+use crate::diagnostics::FailureCause;
+use crate::rqparser;
+use crate::rqprotocol::{self, Node};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Consort(#[from] consort::Error),
+    #[error("{0}")]
+    Protocol(#[from] rqprotocol::Error),
+    #[error("{0}")]
+    Parser(#[from] rqparser::Error),
+    #[error("{}", .0.description())]
+    Connection(FailureCause),
+    #[error("{node}: {reason}")]
+    Telemetry { node: Node, reason: String },
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{context}: {source}")]
+    Context {
+        context: String,
+        #[source]
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    // The module the error stems from
+    pub fn origin(&self) -> &'static str {
+        match self {
+            Error::Consort(_) => "Consort",
+            Error::Protocol(_) => "Protocol",
+            Error::Parser(_) => "Parser",
+            Error::Connection(_) => "Connection",
+            Error::Telemetry { .. } => "Telemetry",
+            Error::Io(_) => "I/O",
+            Error::Context { source, .. } => source.origin(),
+        }
+    }
+
+    // What we were doing when it happened
+    pub fn context(self, context: impl Into<String>) -> Self {
+        Error::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+}
+
+// Not an error of its own, but what the connection reports
+impl From<FailureCause> for Error {
+    fn from(cause: FailureCause) -> Self {
+        Error::Connection(cause)
+    }
+}
+
+pub trait Context<T> {
+    fn context(self, context: impl Into<String>) -> Result<T, Error>;
+}
+
+impl<T, E: Into<Error>> Context<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> Result<T, Error> {
+        self.map_err(|err| err.into().context(context))
+    }
+}
+
+// Cheap to clone and can be handed to other threads
+#[derive(Clone)]
+pub struct ErrorReporter {
+    sender: Sender<Error>,
+}
+
+impl ErrorReporter {
+    pub fn report(&self, err: impl Into<Error>) {
+        let err = err.into();
+        error!("{}: {}", err.origin(), err);
+        // Nobody listening anymore when shutting down
+        let _ = self.sender.send(err);
+    }
+}
+
+pub fn error_channel() -> (ErrorReporter, Receiver<Error>) {
+    let (sender, receiver) = unbounded();
+    (ErrorReporter { sender }, receiver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_keeps_the_origin() {
+        let result: Result<(), consort::Error> = Err(consort::Error::Nak);
+        let err = result.context("Sending PING").unwrap_err();
+        assert_eq!(err.origin(), "Consort");
+        assert_eq!(err.to_string(), "Sending PING: Nak");
+        assert!(std::error::Error::source(&err).is_some());
+        let (reporter, errors) = error_channel();
+        let other = reporter.clone();
+        std::thread::spawn(move || other.report(FailureCause::NoConnection))
+            .join()
+            .unwrap();
+        reporter.report(err);
+        let origins: Vec<_> = errors.try_iter().map(|err| err.origin()).collect();
+        assert_eq!(origins, vec!["Connection", "Consort"]);
+    }
+}
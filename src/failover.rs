@@ -0,0 +1,139 @@
+// Switching between the primary E32 and a spare one on a
+// second serial port once the primary link degrades.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Radio {
+    Primary,
+    Secondary,
+}
+
+pub struct Failover {
+    primary: String,
+    secondary: Option<String>,
+    active: Radio,
+    failures: usize,
+    threshold: usize,
+}
+
+impl Radio {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Radio::Primary => "primary",
+            Radio::Secondary => "secondary",
+        }
+    }
+}
+
+impl Failover {
+    pub fn new(primary: &str, secondary: Option<&str>, threshold: usize) -> Self {
+        Self {
+            primary: primary.into(),
+            secondary: secondary.map(|s| s.into()),
+            active: Radio::Primary,
+            failures: 0,
+            threshold,
+        }
+    }
+
+    pub fn port(&self) -> &str {
+        match (self.active, &self.secondary) {
+            (Radio::Secondary, Some(secondary)) => secondary,
+            _ => &self.primary,
+        }
+    }
+
+    // Picked by the operator, back on it
+    pub fn set_primary(&mut self, port: &str) {
+        self.primary = port.into();
+        self.active = Radio::Primary;
+        self.failures = 0;
+    }
+
+    pub fn primary(&self) -> &str {
+        &self.primary
+    }
+
+    // From the config file, true if the active port changed
+    pub fn set_ports(&mut self, primary: &str, secondary: Option<&str>) -> bool {
+        let before = self.port().to_string();
+        self.primary = primary.into();
+        self.secondary = secondary.map(Into::into);
+        if self.secondary.is_none() {
+            self.active = Radio::Primary;
+        }
+        self.port() != before
+    }
+
+    pub fn active(&self) -> Radio {
+        self.active
+    }
+
+    pub fn has_secondary(&self) -> bool {
+        self.secondary.is_some()
+    }
+
+    // Only failures on the primary lead to a switch, we
+    // don't bounce back automatically.
+    pub fn record_failure(&mut self) -> Option<Radio> {
+        self.failures += 1;
+        if self.active == Radio::Primary && self.failures >= self.threshold {
+            return self.switch();
+        }
+        None
+    }
+
+    pub fn record_success(&mut self) {
+        self.failures = 0;
+    }
+
+    // Manual override, toggles between the radios
+    pub fn switch(&mut self) -> Option<Radio> {
+        if !self.has_secondary() {
+            return None;
+        }
+        self.failures = 0;
+        self.active = match self.active {
+            Radio::Primary => Radio::Secondary,
+            Radio::Secondary => Radio::Primary,
+        };
+        Some(self.active)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_switch_after_consecutive_failures() {
+        let mut failover = Failover::new("/dev/ttyUSB0", Some("/dev/ttyUSB1"), 3);
+        assert_eq!(failover.record_failure(), None);
+        assert_eq!(failover.record_failure(), None);
+        failover.record_success();
+        assert_eq!(failover.record_failure(), None);
+        assert_eq!(failover.record_failure(), None);
+        assert_eq!(failover.record_failure(), Some(Radio::Secondary));
+        assert_eq!(failover.port(), "/dev/ttyUSB1");
+        for _ in 0..5 {
+            assert_eq!(failover.record_failure(), None);
+        }
+        assert_eq!(failover.switch(), Some(Radio::Primary));
+        assert_eq!(failover.port(), "/dev/ttyUSB0");
+        failover.switch();
+        failover.set_primary("COM4");
+        assert_eq!(failover.active(), Radio::Primary);
+        assert_eq!(failover.port(), "COM4");
+        assert!(!failover.set_ports("COM4", Some("COM5")));
+        failover.switch();
+        assert!(failover.set_ports("COM4", Some("COM6")));
+        assert!(failover.set_ports("COM4", None));
+        assert_eq!(failover.active(), Radio::Primary);
+    }
+
+    #[test]
+    fn test_no_secondary_no_switch() {
+        let mut failover = Failover::new("/dev/ttyUSB0", None, 1);
+        assert_eq!(failover.record_failure(), None);
+        assert_eq!(failover.switch(), None);
+        assert_eq!(failover.port(), "/dev/ttyUSB0");
+    }
+}
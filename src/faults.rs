@@ -0,0 +1,245 @@
+// Chaos testing for the state machines, dev builds only. Wraps the
+// real connection and tampers with what the node sends back: ACKs
+// are swallowed (reported as the timeout the worker would produce
+// for a lost sentence), checksums corrupted, responses held back,
+// and the ACK for one command turned into a NAK. The backends know
+// nothing about it.
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+use std::rc::Rc;
+use std::time::Duration;
+
+use log::warn;
+
+use crate::airtime::DutyCycleStatus;
+use crate::clock::{Instant, SharedClock};
+use crate::connection::{Answers, Connection, LinkActivity, QueueDepths};
+use crate::rqparser::{ack_parser, verify_nmea_format, NMEAFormatter};
+use crate::rqprotocol::{AckHeader, Acknowledgement, Capabilities, Node};
+use crate::shutdown::Stoppable;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Faults {
+    pub enabled: bool,
+    // Counted down with each swallowed ACK
+    pub drop_acks: usize,
+    // Per response, from 0.0 to 1.0
+    pub corrupt_probability: f32,
+    pub delay: Duration,
+    // Verb of the command to NAK, e.g. PING
+    pub nak_verb: Option<String>,
+}
+
+pub type SharedFaults = Rc<RefCell<Faults>>;
+
+impl Faults {
+    pub fn any(&self) -> bool {
+        self.drop_acks > 0
+            || self.corrupt_probability > 0.0
+            || self.delay > Duration::ZERO
+            || self.nak_verb.is_some()
+    }
+}
+
+pub struct FaultInjector<C> {
+    inner: C,
+    faults: SharedFaults,
+    clock: SharedClock,
+    pending: VecDeque<(Instant, Answers)>,
+    // Commands whose ACK becomes a NAK
+    nak_ids: HashSet<usize>,
+    rng: u32,
+}
+
+impl<C: Connection> FaultInjector<C> {
+    pub fn new(inner: C, faults: SharedFaults, clock: SharedClock) -> Self {
+        Self {
+            inner,
+            faults,
+            clock,
+            pending: VecDeque::new(),
+            nak_ids: HashSet::new(),
+            rng: 0x2545f491,
+        }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    // xorshift, as for the simulated telemetry
+    fn random(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng as f32 / u32::MAX as f32
+    }
+
+    fn tamper(&mut self, answer: Answers) -> Answers {
+        let mut sentence = match answer {
+            Answers::Received(sentence) => sentence,
+            answer => return answer,
+        };
+        if let Some(header) = ack_header(&sentence) {
+            let mut faults = self.faults.borrow_mut();
+            if faults.drop_acks > 0 {
+                faults.drop_acks -= 1;
+                warn!("Fault injection: dropped ACK {}", header.id);
+                return Answers::Timeout;
+            }
+            if self.nak_ids.remove(&header.id) {
+                warn!("Fault injection: NAK for {}", header.id);
+                sentence = nak(header.source, header.id, header.recipient);
+            }
+        }
+        let corrupt_probability = self.faults.borrow().corrupt_probability;
+        if self.random() < corrupt_probability && sentence.len() >= 5 {
+            warn!("Fault injection: corrupted checksum");
+            let digit = sentence.len() - 3;
+            sentence[digit] = if sentence[digit] == b'0' { b'1' } else { b'0' };
+        }
+        Answers::Received(sentence)
+    }
+
+    fn note_nak(&mut self, buf: &[u8]) {
+        let faults = self.faults.borrow();
+        if let (true, Some(nak_verb)) = (faults.enabled, &faults.nak_verb) {
+            match command_id_and_verb(buf) {
+                Some((id, verb)) if verb == *nak_verb => {
+                    self.nak_ids.insert(id);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn ack_header(sentence: &[u8]) -> Option<AckHeader> {
+    match ack_parser(verify_nmea_format(sentence).ok()?) {
+        Ok((_, Acknowledgement::Ack(header))) => Some(header),
+        _ => None,
+    }
+}
+
+fn nak(source: Node, id: usize, recipient: Node) -> Vec<u8> {
+    let mut formatter = NMEAFormatter::default();
+    let contents = format!("{}NAK,{:03},{}", source, id, recipient);
+    formatter.format_sentence(contents.as_bytes()).unwrap();
+    formatter.buffer().unwrap().to_vec()
+}
+
+// The id and verb of an outgoing command, e.g. LNCCMD,012,RQB,PING
+fn command_id_and_verb(buf: &[u8]) -> Option<(usize, String)> {
+    let contents = std::str::from_utf8(verify_nmea_format(buf).ok()?).ok()?;
+    let mut fields = contents.split(',');
+    let _ = fields.next()?;
+    let id = fields.next()?.parse().ok()?;
+    let _ = fields.next()?;
+    Some((id, fields.next()?.to_string()))
+}
+
+impl<C: Connection> Connection for FaultInjector<C> {
+    fn recv(&mut self, callback: impl FnOnce(Answers)) {
+        let faults = self.faults.borrow().clone();
+        if !faults.enabled && self.pending.is_empty() {
+            return self.inner.recv(callback);
+        }
+        let now = self.clock.now();
+        let mut answer = None;
+        self.inner.recv(|a| answer = Some(a));
+        if let Some(answer) = answer {
+            let answer = match faults.enabled {
+                true => self.tamper(answer),
+                false => answer,
+            };
+            let due = match answer {
+                Answers::Received(_) if faults.enabled => now + faults.delay,
+                _ => now,
+            };
+            self.pending.push_back((due, answer));
+        }
+        if self.pending.front().map_or(false, |(due, _)| *due <= now) {
+            callback(self.pending.pop_front().unwrap().1);
+        }
+    }
+
+    fn drain(&mut self) {
+        self.inner.drain();
+    }
+
+    fn open(&mut self, port: &str) {
+        self.inner.open(port);
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    fn resume(&mut self) {
+        self.inner.resume();
+    }
+
+    fn link_activity(&mut self, activity: LinkActivity) {
+        self.inner.link_activity(activity);
+    }
+
+    fn capabilities(&mut self, capabilities: Capabilities) {
+        self.inner.capabilities(capabilities);
+    }
+
+    fn monitor(&mut self, monitor: bool) {
+        self.inner.monitor(monitor);
+    }
+
+    fn set_drain_idle(&mut self, drain_idle: Duration) {
+        self.inner.set_drain_idle(drain_idle);
+    }
+
+    fn set_poll_nodes(&mut self, nodes: Vec<Node>) {
+        self.inner.set_poll_nodes(nodes);
+    }
+
+    fn set_duty_cycle_cap(&mut self, cap: Option<f64>) {
+        self.inner.set_duty_cycle_cap(cap);
+    }
+
+    fn duty_cycle(&self) -> Option<DutyCycleStatus> {
+        self.inner.duty_cycle()
+    }
+
+    fn queue_depths(&self) -> Option<QueueDepths> {
+        self.inner.queue_depths()
+    }
+
+    fn query_continuity(&mut self) {
+        self.inner.query_continuity();
+    }
+
+    fn flush_recording(&mut self) {
+        self.inner.flush_recording();
+    }
+
+    fn stop_polling(&mut self) {
+        self.inner.stop_polling();
+    }
+
+    fn worker(&mut self) -> Option<&mut dyn Stoppable> {
+        self.inner.worker()
+    }
+
+    fn send_urgent(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.note_nak(data);
+        self.inner.send_urgent(data)
+    }
+}
+
+impl<C: Connection> std::io::Write for FaultInjector<C> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.note_nak(buf);
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,145 @@
+// When the link dies the last observables would stay on screen as if
+// nothing happened. Each group is timestamped per node when received,
+// and the renderers show values older than the freshness threshold as
+// stale. How old is too old follows the polling of the current mode,
+// OBG2 is polled less often than OBG1 and so gets more slack.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::clock::Instant;
+use crate::connection::{LinkActivity, OBG2_EVERY};
+use crate::rqprotocol::Node;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Group {
+    Obg1,
+    Obg2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Freshness {
+    // Missed polls before a value counts as stale
+    pub polls: u32,
+    // Never stale sooner than this
+    pub minimum: Duration,
+}
+
+// Of the values shown for a node
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Stale {
+    pub obg1: bool,
+    pub obg2: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Received {
+    at: HashMap<(Node, Group), Instant>,
+}
+
+impl Default for Freshness {
+    fn default() -> Self {
+        Self {
+            polls: 20,
+            minimum: Duration::from_secs(1),
+        }
+    }
+}
+
+impl Freshness {
+    // Nothing arrives while silent, values just age from the minimum on
+    pub fn threshold(&self, group: Group, activity: LinkActivity) -> Duration {
+        let interval = match activity {
+            LinkActivity::Polling(interval) => interval,
+            LinkActivity::Silent => return self.minimum,
+        };
+        let polls = match group {
+            Group::Obg1 => self.polls,
+            Group::Obg2 => self.polls * OBG2_EVERY as u32,
+        };
+        self.minimum.max(interval * polls)
+    }
+}
+
+impl Received {
+    pub fn record(&mut self, node: Node, group: Group, now: Instant) {
+        self.at.insert((node, group), now);
+    }
+
+    pub fn age(&self, node: Node, group: Group, now: Instant) -> Option<Duration> {
+        self.at
+            .get(&(node, group))
+            .map(|at| now.saturating_duration_since(*at))
+    }
+
+    // Never received isn't stale, there is nothing to show
+    pub fn stale(
+        &self,
+        node: Node,
+        freshness: &Freshness,
+        activity: LinkActivity,
+        now: Instant,
+    ) -> Stale {
+        let stale = |group| {
+            self.age(node, group, now)
+                .map_or(false, |age| age > freshness.threshold(group, activity))
+        };
+        Stale {
+            obg1: stale(Group::Obg1),
+            obg2: stale(Group::Obg2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threshold_follows_the_polling() {
+        let freshness = Freshness::default();
+        let polling = LinkActivity::Polling(Duration::from_millis(100));
+        assert_eq!(
+            freshness.threshold(Group::Obg1, polling),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            freshness.threshold(Group::Obg2, polling),
+            Duration::from_secs(10)
+        );
+        let fast = LinkActivity::Polling(Duration::from_millis(10));
+        assert_eq!(freshness.threshold(Group::Obg1, fast), freshness.minimum);
+        assert_eq!(
+            freshness.threshold(Group::Obg2, LinkActivity::Silent),
+            freshness.minimum
+        );
+    }
+
+    #[test]
+    fn test_values_go_stale() {
+        let freshness = Freshness::default();
+        let polling = LinkActivity::Polling(Duration::from_millis(100));
+        let node = Node::RedQueen(b'B');
+        let start = Instant::now();
+        let mut received = Received::default();
+        assert_eq!(
+            received.stale(node, &freshness, polling, start),
+            Stale::default()
+        );
+        received.record(node, Group::Obg1, start);
+        received.record(node, Group::Obg2, start);
+        let later = start + Duration::from_secs(3);
+        assert_eq!(
+            received.stale(node, &freshness, polling, later),
+            Stale {
+                obg1: true,
+                obg2: false
+            }
+        );
+        received.record(node, Group::Obg1, later);
+        assert!(!received.stale(node, &freshness, polling, later).obg1);
+        assert_eq!(
+            received.age(node, Group::Obg2, later),
+            Some(Duration::from_secs(3))
+        );
+    }
+}
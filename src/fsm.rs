@@ -0,0 +1,294 @@
+// The launch control state machine as a graph, for reviews with the
+// safety officer. Instead of maintaining a second description that
+// drifts from the code, the graph is explored: starting from the
+// first state, each input, response and the passing of time are
+// applied to one representative of every state until it is left.
+// States are told apart by name, the digits and progress they carry
+// are not. What the model does around the state machine (resets,
+// timeouts, failed sends, aborts, radio silence, auto-holds) is added
+// on top. In dev builds the last transitions can also be watched live.
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::clock::Instant;
+use crate::crank::{Crank, Tick};
+use crate::input::{InputEvent, STEP_MAGNITUDE};
+use crate::model::{CoreConnection, LaunchControlMode, Mode, StateProcessing};
+use crate::rqprotocol::{ProtocolVersion, Response};
+
+// Presses or seconds tried before an input counts as not leaving a state
+const MAX_REPEAT: usize = 256;
+const TRANSITION_LOG_CAPACITY: usize = 8;
+
+const EVENTS: [(InputEvent, &str); 5] = [
+    (InputEvent::Enter, "Enter"),
+    (InputEvent::Back, "Back"),
+    (InputEvent::Left(STEP_MAGNITUDE), "Left"),
+    (InputEvent::Right(STEP_MAGNITUDE), "Right"),
+    (InputEvent::Send, "Send"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Transition {
+    pub from: String,
+    pub to: String,
+    pub trigger: String,
+}
+
+pub struct Graph {
+    // With the command sent when entering them
+    pub states: BTreeMap<String, Option<String>>,
+    pub transitions: Vec<Transition>,
+}
+
+// Any unexpected answer is represented by the first
+fn responses() -> Vec<(Response, &'static str)> {
+    vec![
+        (Response::PingAck, "other response"),
+        (Response::ResetAck, "ResetAck"),
+        (
+            Response::VersionAck(ProtocolVersion::legacy()),
+            "VersionAck",
+        ),
+        (Response::LaunchSecretPartialAck, "LaunchSecretPartialAck"),
+        (Response::UnlockPyrosAck, "UnlockPyrosAck"),
+        (Response::LaunchSecretFullAck, "LaunchSecretFullAck"),
+        (Response::IgnitionAck, "IgnitionAck"),
+        (Response::AbortAck, "AbortAck"),
+    ]
+}
+
+pub fn state_name(state: &LaunchControlMode) -> String {
+    match state.name() {
+        "" => format!("{:?}", state),
+        name => name.to_string(),
+    }
+}
+
+// Commands are named without the secrets they carry
+fn command_name(state: &LaunchControlMode) -> Option<String> {
+    state.process_mode_change().map(|command| {
+        let command = format!("{:?}", command);
+        command
+            .split(|c| c == '(' || c == ' ')
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    })
+}
+
+pub fn launch_control_graph() -> Graph {
+    let now = Instant::now();
+    let crank = Crank::default();
+    let mut transitions = BTreeSet::new();
+    let mut states = BTreeMap::new();
+    let mut pending = VecDeque::from([LaunchControlMode::default()]);
+    let mut add = |from: &LaunchControlMode, to: &LaunchControlMode, trigger: &str| {
+        transitions.insert(Transition {
+            from: state_name(from),
+            to: state_name(to),
+            trigger: trigger.into(),
+        });
+    };
+    let reset = LaunchControlMode::Core(CoreConnection::Reset);
+    while let Some(state) = pending.pop_front() {
+        if states.contains_key(&state_name(&state)) {
+            continue;
+        }
+        states.insert(state_name(&state), command_name(&state));
+        let mut reached = vec![];
+        // Repeated until the state is left, e.g. cranking up progress
+        let follow = |step: &dyn Fn(LaunchControlMode, usize) -> LaunchControlMode| {
+            let mut next = state;
+            for i in 1..=MAX_REPEAT {
+                next = step(next, i);
+                if state_name(&next) != state_name(&state) {
+                    return Some(next);
+                }
+            }
+            None
+        };
+        for (event, trigger) in EVENTS {
+            if let Some(next) = follow(&|s, _| s.process_event(&event, now, &crank).0) {
+                reached.push((next, trigger.to_string()));
+            }
+        }
+        let mut unexpected = None;
+        for (index, (response, trigger)) in responses().into_iter().enumerate() {
+            let next = follow(&|s, _| s.process_response(response.clone(), now));
+            let name = next.as_ref().map(state_name);
+            match next {
+                _ if index == 0 => unexpected = name,
+                // Reacting like to any other answer
+                Some(_) if name == unexpected => continue,
+                _ => {}
+            }
+            if let Some(next) = next {
+                reached.push((next, trigger.to_string()));
+            }
+        }
+        let later = |i: usize| Tick {
+            now: now + Duration::from_secs(i as u64),
+            elapsed: Duration::from_secs(1),
+        };
+        if let Some(next) = follow(&|s, i| s.drive(later(i), &crank)) {
+            reached.push((next, "time passes".into()));
+        }
+        // Done by the model
+        if state == LaunchControlMode::Core(CoreConnection::Start) {
+            reached.push((reset, "reset".into()));
+        }
+        if state.affected_by_timeout() {
+            reached.push((reset, "timeout".into()));
+        }
+        if state.process_mode_change().is_some() {
+            reached.push((reset, "send failed".into()));
+        }
+        if state == reset {
+            reached.push((state.failure_mode(), "send failed".into()));
+        }
+        if state.abortable() {
+            reached.push((LaunchControlMode::SafeAbort, "abort".into()));
+        }
+        if let Some(held) = state.auto_hold(now) {
+            reached.push((held, "auto-hold".into()));
+        }
+        for (next, trigger) in reached {
+            add(&state, &next, &trigger);
+            pending.push_back(next);
+        }
+    }
+    // Commands to a node expecting radio silence are refused, the
+    // state is left for where it was entered from. Aborts go out.
+    let refused: Vec<Transition> = transitions
+        .iter()
+        .filter(|transition| {
+            states.get(&transition.to).map_or(false, |command| {
+                command.is_some() && command.as_deref() != Some("Abort")
+            })
+        })
+        .map(|transition| Transition {
+            from: transition.to.clone(),
+            to: transition.from.clone(),
+            trigger: "radio silence".into(),
+        })
+        .collect();
+    transitions.extend(refused);
+    Graph {
+        states,
+        transitions: transitions.into_iter().collect(),
+    }
+}
+
+// Graphviz, with the command sent when entering a state
+pub fn to_dot(graph: &Graph) -> String {
+    let mut dot = String::from("digraph launch_control {\n    rankdir=TB;\n");
+    let initial = state_name(&LaunchControlMode::default());
+    for (state, command) in &graph.states {
+        let label = match command {
+            Some(command) => format!("{}\\nsends {}", state, command),
+            None => state.clone(),
+        };
+        let shape = if *state == initial {
+            "doublecircle"
+        } else {
+            "box"
+        };
+        dot += &format!(
+            "    \"{}\" [label=\"{}\", shape={}];\n",
+            state, label, shape
+        );
+    }
+    for transition in &graph.transitions {
+        dot += &format!(
+            "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+            transition.from, transition.to, transition.trigger
+        );
+    }
+    dot + "}\n"
+}
+
+pub fn export(path: &Path) -> std::io::Result<()> {
+    std::fs::write(path, to_dot(&launch_control_graph()))
+}
+
+pub struct LoggedTransition {
+    pub at: Instant,
+    pub from: String,
+    pub to: String,
+}
+
+// The last transitions of the current mode, for the overlay
+#[derive(Default)]
+pub struct TransitionLog {
+    pub visible: bool,
+    entries: VecDeque<LoggedTransition>,
+}
+
+impl TransitionLog {
+    pub fn record(&mut self, at: Instant, from: &Mode, to: &Mode) {
+        if self.entries.len() == TRANSITION_LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LoggedTransition {
+            at,
+            from: mode_name(from),
+            to: mode_name(to),
+        });
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &LoggedTransition> {
+        self.entries.iter().rev()
+    }
+}
+
+pub fn mode_name(mode: &Mode) -> String {
+    match mode {
+        Mode::Observables(state) => format!("Observables: {}", state.name()),
+        Mode::LaunchControl(state) => format!("Launch Control: {}", state_name(state)),
+        Mode::RFSilence(state) => format!("RF Silence: {}", state.name()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graph_reaches_ignition_only_through_the_secrets() {
+        let graph = launch_control_graph();
+        let has = |from: &str, to: &str, trigger: &str| {
+            graph
+                .transitions
+                .iter()
+                .any(|t| t.from == from && t.to == to && t.trigger == trigger)
+        };
+        assert!(has("Start", "Reset", "reset"));
+        assert!(has("Reset", "Discover", "ResetAck"));
+        assert!(has("Idle", "Pre-Arm Checklist", "Enter"));
+        assert!(has("Prepare Unlock Pyros", "Unlocking Pyros", "Right"));
+        assert!(has("Wait for Fire", "Fire!", "Enter"));
+        assert!(has("Fire!", "Pyros ignited", "IgnitionAck"));
+        assert!(has("Pyros ignited", "SwitchToObservables", "time passes"));
+        assert!(has("Unlocking Pyros", "Safe Abort", "abort"));
+        assert!(has("Wait for Fire", "Safe Abort", "abort"));
+        assert!(has("Safe Abort", "Aborted", "AbortAck"));
+        assert!(has("Aborted", "Start", "Enter"));
+        assert!(!has("Idle", "Safe Abort", "abort"));
+        assert!(has("Fire!", "Wait for Fire", "radio silence"));
+        assert!(!graph
+            .transitions
+            .iter()
+            .any(|t| t.from == "Safe Abort" && t.to != "Aborted" && t.trigger != "send failed"));
+        let into_fire: Vec<_> = graph
+            .transitions
+            .iter()
+            .filter(|t| t.to == "Fire!")
+            .collect();
+        assert_eq!(into_fire.len(), 1);
+        let dot = to_dot(&graph);
+        assert!(dot.starts_with("digraph launch_control {"));
+        assert!(dot.contains("\"Fire!\" [label=\"Fire!\\nsends Ignition\", shape=box];"));
+    }
+}
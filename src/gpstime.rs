@@ -0,0 +1,95 @@
+// Wall clock time from the GPS receiver through gpsd, for a launch
+// window the field box's clock may be minutes off for, without
+// network. We keep the offset of the GPS time to the system clock
+// with each fix. gpsd reports a fix a little after the second it is
+// of, good enough for windows and holds counted in minutes.
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use log::{error, info};
+
+const WATCH: &[u8] = b"?WATCH={\"enable\":true,\"json\":true};\n";
+// Without a fix for longer, we fall back to the system clock
+const STALE: Duration = Duration::from_secs(10);
+const RECONNECT: Duration = Duration::from_secs(2);
+// gpsd modes, 2 and 3 are a 2D and 3D fix
+const MIN_MODE: u64 = 2;
+
+// Of the GPS to the system clock, and when we got it
+type Offset = Option<(chrono::Duration, Instant)>;
+
+pub struct GpsTime {
+    offset: Arc<Mutex<Offset>>,
+}
+
+// The time of a TPV report with a fix
+fn tpv_time(line: &str) -> Option<DateTime<Utc>> {
+    let report: serde_json::Value = serde_json::from_str(line).ok()?;
+    if report["class"] != "TPV" || report["mode"].as_u64()? < MIN_MODE {
+        return None;
+    }
+    DateTime::parse_from_rfc3339(report["time"].as_str()?)
+        .ok()
+        .map(|time| time.into())
+}
+
+fn watch(address: &str, offset: &Mutex<Offset>) -> std::io::Result<()> {
+    let mut stream = TcpStream::connect(address)?;
+    stream.write_all(WATCH)?;
+    for line in BufReader::new(stream).lines() {
+        if let Some(time) = tpv_time(&line?) {
+            *offset.lock().unwrap() = Some((time - Utc::now(), Instant::now()));
+        }
+    }
+    Ok(())
+}
+
+impl GpsTime {
+    // E.g. localhost:2947
+    pub fn start(address: &str) -> Self {
+        let offset = Arc::new(Mutex::new(None));
+        let address = address.to_string();
+        let shared = offset.clone();
+        info!("Taking the time from gpsd at {}", address);
+        thread::spawn(move || loop {
+            if let Err(e) = watch(&address, &shared) {
+                error!("Reading gpsd failed, {}", e);
+            }
+            thread::sleep(RECONNECT);
+        });
+        Self { offset }
+    }
+
+    // None without a recent fix
+    pub fn now(&self) -> Option<DateTime<Utc>> {
+        match *self.offset.lock().unwrap() {
+            Some((offset, at)) if at.elapsed() < STALE => Some(Utc::now() + offset),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tpv_time() {
+        let tpv = r#"{"class":"TPV","device":"/dev/ttyACM0","mode":3,"time":"2026-10-17T09:00:01.000Z","lat":53.1}"#;
+        assert_eq!(
+            tpv_time(tpv),
+            Some(
+                DateTime::parse_from_rfc3339("2026-10-17T09:00:01Z")
+                    .unwrap()
+                    .into()
+            )
+        );
+        assert_eq!(tpv_time(&tpv.replace("\"mode\":3", "\"mode\":1")), None);
+        assert_eq!(tpv_time(r#"{"class":"SKY","satellites":[]}"#), None);
+        assert_eq!(tpv_time("garbage"), None);
+    }
+}
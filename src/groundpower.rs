@@ -0,0 +1,197 @@
+// Power of the ground station itself. The novaview box runs off a
+// 4S LiFePO4 pack with an INA219 measuring bus voltage and current.
+// LiFePO4 discharge curves are very flat, so the state of charge
+// from voltage alone is coarse, but good enough to warn before the
+// box browns out.
+use std::time::Duration;
+
+const CELLS: f32 = 4.0;
+const CAPACITY_MAH: f32 = 6000.0;
+// Per cell, we warn a good margin above the BMS cutoff
+const WARNING_CELL_VOLTAGE: f32 = 3.1;
+const WARNING_PERCENTAGE: f32 = 15.0;
+// Exponential smoothing of the readings, the load of the
+// display and the E32 is quite spiky.
+const SMOOTHING: f32 = 0.1;
+// Below it, e.g. on a charger, there's no runtime to tell. The
+// smoothed current only approaches zero, dividing by it overflows.
+const MIN_CURRENT_MA: f32 = 1.0;
+
+// (cell voltage, percentage) under light load
+const DISCHARGE_CURVE: [(f32, f32); 10] = [
+    (2.50, 0.0),
+    (3.00, 9.0),
+    (3.20, 14.0),
+    (3.22, 17.0),
+    (3.25, 20.0),
+    (3.26, 30.0),
+    (3.27, 40.0),
+    (3.30, 70.0),
+    (3.32, 90.0),
+    (3.40, 100.0),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerReading {
+    // Volts and milliamperes
+    pub voltage: f32,
+    pub current: f32,
+}
+
+#[derive(Default)]
+pub struct GroundPower {
+    smoothed: Option<PowerReading>,
+}
+
+fn percentage_for_cell_voltage(voltage: f32) -> f32 {
+    let (first, last) = (
+        DISCHARGE_CURVE[0],
+        DISCHARGE_CURVE[DISCHARGE_CURVE.len() - 1],
+    );
+    if voltage <= first.0 {
+        return first.1;
+    }
+    if voltage >= last.0 {
+        return last.1;
+    }
+    for pair in DISCHARGE_CURVE.windows(2) {
+        let ((v0, p0), (v1, p1)) = (pair[0], pair[1]);
+        if voltage <= v1 {
+            return p0 + (voltage - v0) / (v1 - v0) * (p1 - p0);
+        }
+    }
+    last.1
+}
+
+impl GroundPower {
+    pub fn feed(&mut self, reading: PowerReading) {
+        self.smoothed = Some(match self.smoothed {
+            Some(s) => PowerReading {
+                voltage: s.voltage + (reading.voltage - s.voltage) * SMOOTHING,
+                current: s.current + (reading.current - s.current) * SMOOTHING,
+            },
+            None => reading,
+        });
+    }
+
+    pub fn reading(&self) -> Option<PowerReading> {
+        self.smoothed
+    }
+
+    pub fn percentage(&self) -> Option<f32> {
+        self.smoothed
+            .map(|r| percentage_for_cell_voltage(r.voltage / CELLS))
+    }
+
+    pub fn runtime(&self) -> Option<Duration> {
+        let reading = self.smoothed?;
+        if reading.current < MIN_CURRENT_MA {
+            return None;
+        }
+        let remaining = CAPACITY_MAH * self.percentage()? / 100.0;
+        Duration::try_from_secs_f32(remaining / reading.current * 3600.0).ok()
+    }
+
+    pub fn low(&self) -> bool {
+        match self.smoothed {
+            Some(r) => {
+                r.voltage / CELLS < WARNING_CELL_VOLTAGE
+                    || self.percentage().unwrap_or_default() < WARNING_PERCENTAGE
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(feature = "novaview")]
+pub mod ina219 {
+    use embedded_hal::blocking::i2c::WriteRead;
+    use linux_embedded_hal::I2cdev;
+
+    use super::PowerReading;
+
+    const ADDRESS: u8 = 0x40;
+    const SHUNT_VOLTAGE_REGISTER: u8 = 0x01;
+    const BUS_VOLTAGE_REGISTER: u8 = 0x02;
+    // The shunt on the novaview power board
+    const SHUNT_OHMS: f32 = 0.1;
+
+    pub struct Ina219 {
+        i2c: I2cdev,
+    }
+
+    impl Ina219 {
+        pub fn new(device: &str) -> anyhow::Result<Self> {
+            Ok(Self {
+                i2c: I2cdev::new(device)?,
+            })
+        }
+
+        fn register(&mut self, register: u8) -> anyhow::Result<u16> {
+            let mut buffer = [0; 2];
+            self.i2c.write_read(ADDRESS, &[register], &mut buffer)?;
+            Ok(u16::from_be_bytes(buffer))
+        }
+
+        pub fn read(&mut self) -> anyhow::Result<PowerReading> {
+            // Bus voltage sits in the upper 13 bits, 4mV per LSB
+            let bus = (self.register(BUS_VOLTAGE_REGISTER)? >> 3) as f32 * 0.004;
+            // Shunt voltage is signed with 10uV per LSB
+            let shunt = self.register(SHUNT_VOLTAGE_REGISTER)? as i16 as f32 * 0.00001;
+            Ok(PowerReading {
+                voltage: bus,
+                current: shunt / SHUNT_OHMS * 1000.0,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentage_interpolates() {
+        assert_eq!(percentage_for_cell_voltage(3.5), 100.0);
+        assert_eq!(percentage_for_cell_voltage(2.0), 0.0);
+        assert!((percentage_for_cell_voltage(3.1) - 11.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_runtime_and_warning() {
+        let mut power = GroundPower::default();
+        assert!(!power.low());
+        power.feed(PowerReading {
+            voltage: 13.6,
+            current: 1500.0,
+        });
+        assert_eq!(power.percentage(), Some(100.0));
+        assert_eq!(power.runtime(), Some(Duration::from_secs(4 * 3600)));
+        assert!(!power.low());
+        for _ in 0..100 {
+            power.feed(PowerReading {
+                voltage: 12.0,
+                current: 1500.0,
+            });
+        }
+        assert!(power.low());
+    }
+
+    #[test]
+    fn test_no_runtime_without_current() {
+        let mut power = GroundPower::default();
+        power.feed(PowerReading {
+            voltage: 13.6,
+            current: 1500.0,
+        });
+        for _ in 0..200 {
+            power.feed(PowerReading {
+                voltage: 13.6,
+                current: 0.0,
+            });
+            power.runtime();
+        }
+        assert!(power.reading().unwrap().current > 0.0);
+        assert_eq!(power.runtime(), None);
+    }
+}
@@ -0,0 +1,358 @@
+// On-screen labels in the crew's language. The English text is the
+// key, anything without a translation stays English. Words we act on
+// during a launch (arm, fire, ignition, pyro, open) are deliberately
+// never translated, so they read the same whichever language the
+// person next to you has set.
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+    French,
+}
+
+impl FromStr for Language {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "English" => Ok(Language::English),
+            "German" => Ok(Language::German),
+            "French" => Ok(Language::French),
+            _ => Err("No valid value, use English, German, French"),
+        }
+    }
+}
+
+const GERMAN: &[(&str, &str)] = &[
+    ("Observables", "Messwerte"),
+    ("Launch Control", "Startkontrolle"),
+    ("RF Silence", "Funkstille"),
+    ("Start", "Start"),
+    ("Failure", "Fehler"),
+    ("Reset", "Reset"),
+    ("Discover", "Suche"),
+    ("Idle", "Ruhezustand"),
+    ("Enter Hi A", "Eingabe Hi A"),
+    ("Enter Lo A", "Eingabe Lo A"),
+    ("Enter Hi B", "Eingabe Hi B"),
+    ("Enter Lo B", "Eingabe Lo B"),
+    ("Transmitting Key A", "Sende Schlüssel A"),
+    ("Transmitting Key AB", "Sende Schlüssel AB"),
+    ("Activate RF silence", "Funkstille aktivieren"),
+    ("RF silence active", "Funkstille aktiv"),
+    ("Press Enter to enter RF Silence!", "Enter für Funkstille!"),
+    ("Enter Key A", "Schlüssel A eingeben"),
+    ("Enter Key B", "Schlüssel B eingeben"),
+    ("continuity", "Durchgang"),
+    ("unknown", "unbekannt"),
+    ("waiting", "warte"),
+    (
+        "Enter to acknowledge, Back to abort",
+        "Enter bestätigt, Back bricht ab",
+    ),
+    ("Querying continuity...", "Frage Durchgang ab..."),
+    ("Connection failure", "Verbindungsfehler"),
+    (
+        "Cause unknown, waiting for automatic reset.",
+        "Ursache unbekannt, warte auf automatischen Reset.",
+    ),
+    ("Last received sentences:", "Zuletzt empfangene Sätze:"),
+    (
+        "Press Enter to retry now",
+        "Enter für sofortigen Neuversuch",
+    ),
+    (
+        "Writing the diagnostic bundle...",
+        "Schreibe das Diagnosepaket...",
+    ),
+    (
+        "Diagnostic bundle written to",
+        "Diagnosepaket geschrieben nach",
+    ),
+    ("Diagnostic bundle failed", "Diagnosepaket fehlgeschlagen"),
+    (
+        "Press Z to write a diagnostic bundle",
+        "Z schreibt ein Diagnosepaket",
+    ),
+    ("Help", "Hilfe"),
+    ("Enter or Back to close", "Enter oder Back zum Schließen"),
+    ("Not recording to file", "Keine Aufzeichnung"),
+    ("Recording", "Aufzeichnung"),
+    ("Mirror", "Spiegel"),
+    ("Connected", "Verbunden"),
+    ("Gain", "Verstärkung"),
+    ("Automatic reset in", "Automatischer Reset in"),
+    ("Key entry expires in", "Schlüsseleingabe verfällt in"),
+    ("Self-test", "Selbsttest"),
+    ("pending", "ausstehend"),
+    ("running", "läuft"),
+    (
+        "Running checks, Back to restart",
+        "Prüfungen laufen, Back startet neu",
+    ),
+    (
+        "All checks passed, press Enter to continue",
+        "Alle Prüfungen bestanden, Enter zum Fortfahren",
+    ),
+    (
+        "Launch Control locked. Enter to continue, Back to re-run",
+        "Startkontrolle gesperrt. Enter fährt fort, Back wiederholt",
+    ),
+    ("Sequences", "Sequenzen"),
+    ("Broadcast", "Rundruf"),
+    ("skipped", "übersprungen"),
+    ("not sent", "nicht gesendet"),
+    (
+        "Back to skip the remaining nodes",
+        "Back überspringt die übrigen Knoten",
+    ),
+    (
+        "Enter to send to all nodes, Back to leave",
+        "Enter sendet an alle Knoten, Back verlässt",
+    ),
+    ("Completed", "Abgeschlossen"),
+    ("Aborted", "Abgebrochen"),
+    ("steps", "Schritte"),
+    ("Last run", "Letzter Lauf"),
+    (
+        "No sequence directory given",
+        "Kein Sequenzverzeichnis angegeben",
+    ),
+    (
+        "Enter to confirm prompts, Back to abort",
+        "Enter bestätigt, Back bricht ab",
+    ),
+    (
+        "Enter to run, Back to leave",
+        "Enter startet, Back verlässt",
+    ),
+    (
+        "Start onboard recording? Enter to confirm, Back to cancel",
+        "Bordaufzeichnung starten? Enter bestätigt, Back bricht ab",
+    ),
+    (
+        "Stop onboard recording? Enter to confirm, Back to cancel",
+        "Bordaufzeichnung beenden? Enter bestätigt, Back bricht ab",
+    ),
+    (
+        "Waiting for the node to report its recording state...",
+        "Warte auf den Aufzeichnungsstatus des Knotens...",
+    ),
+    ("ADC readings", "ADC-Werte"),
+    ("Hex", "Hex"),
+    ("Raw", "Roh"),
+    ("Value", "Wert"),
+    ("Min", "Min"),
+    ("Max", "Max"),
+    ("Mean", "Mittel"),
+    ("Thrust", "Schub"),
+    ("Pressure", "Druck"),
+    ("Tared readings of the target", "Tarierte Werte des Ziels"),
+    ("window", "Fenster"),
+    (
+        "Left/Right: window, Enter: tare, Back: leave",
+        "Links/Rechts: Fenster, Enter: tarieren, Back: verlassen",
+    ),
+];
+
+const FRENCH: &[(&str, &str)] = &[
+    ("Observables", "Mesures"),
+    ("Launch Control", "Contrôle de lancement"),
+    ("RF Silence", "Silence radio"),
+    ("Start", "Démarrage"),
+    ("Failure", "Échec"),
+    ("Reset", "Reset"),
+    ("Discover", "Recherche"),
+    ("Idle", "Au repos"),
+    ("Enter Hi A", "Saisie Hi A"),
+    ("Enter Lo A", "Saisie Lo A"),
+    ("Enter Hi B", "Saisie Hi B"),
+    ("Enter Lo B", "Saisie Lo B"),
+    ("Transmitting Key A", "Envoi de la clé A"),
+    ("Transmitting Key AB", "Envoi de la clé AB"),
+    ("Activate RF silence", "Activer le silence radio"),
+    ("RF silence active", "Silence radio actif"),
+    (
+        "Press Enter to enter RF Silence!",
+        "Enter pour le silence radio !",
+    ),
+    ("Enter Key A", "Saisir la clé A"),
+    ("Enter Key B", "Saisir la clé B"),
+    ("continuity", "continuité"),
+    ("unknown", "inconnu"),
+    ("waiting", "attente"),
+    (
+        "Enter to acknowledge, Back to abort",
+        "Enter confirme, Back interrompt",
+    ),
+    (
+        "Querying continuity...",
+        "Interrogation de la continuité...",
+    ),
+    ("Connection failure", "Échec de connexion"),
+    (
+        "Cause unknown, waiting for automatic reset.",
+        "Cause inconnue, attente du reset automatique.",
+    ),
+    ("Last received sentences:", "Dernières phrases reçues :"),
+    (
+        "Press Enter to retry now",
+        "Enter pour réessayer maintenant",
+    ),
+    (
+        "Writing the diagnostic bundle...",
+        "Écriture du paquet de diagnostic...",
+    ),
+    (
+        "Diagnostic bundle written to",
+        "Paquet de diagnostic écrit dans",
+    ),
+    ("Diagnostic bundle failed", "Échec du paquet de diagnostic"),
+    (
+        "Press Z to write a diagnostic bundle",
+        "Z pour écrire un paquet de diagnostic",
+    ),
+    ("Help", "Aide"),
+    ("Enter or Back to close", "Enter ou Back pour fermer"),
+    ("Not recording to file", "Pas d'enregistrement"),
+    ("Recording", "Enregistrement"),
+    ("Mirror", "Miroir"),
+    ("Connected", "Connecté"),
+    ("Gain", "Gain"),
+    ("Automatic reset in", "Reset automatique dans"),
+    ("Key entry expires in", "La saisie des clés expire dans"),
+    ("Self-test", "Autotest"),
+    ("pending", "en attente"),
+    ("running", "en cours"),
+    (
+        "Running checks, Back to restart",
+        "Vérifications en cours, Back recommence",
+    ),
+    (
+        "All checks passed, press Enter to continue",
+        "Toutes les vérifications réussies, Enter pour continuer",
+    ),
+    (
+        "Launch Control locked. Enter to continue, Back to re-run",
+        "Contrôle de lancement verrouillé. Enter continue, Back relance",
+    ),
+    ("Sequences", "Séquences"),
+    ("Broadcast", "Diffusion"),
+    ("skipped", "ignoré"),
+    ("not sent", "non envoyé"),
+    (
+        "Back to skip the remaining nodes",
+        "Back ignore les nœuds restants",
+    ),
+    (
+        "Enter to send to all nodes, Back to leave",
+        "Enter envoie à tous les nœuds, Back quitte",
+    ),
+    ("Completed", "Terminé"),
+    ("Aborted", "Interrompu"),
+    ("steps", "étapes"),
+    ("Last run", "Dernière exécution"),
+    (
+        "No sequence directory given",
+        "Aucun répertoire de séquences indiqué",
+    ),
+    (
+        "Enter to confirm prompts, Back to abort",
+        "Enter confirme, Back interrompt",
+    ),
+    ("Enter to run, Back to leave", "Enter exécute, Back quitte"),
+    (
+        "Start onboard recording? Enter to confirm, Back to cancel",
+        "Démarrer l'enregistrement à bord ? Enter confirme, Back annule",
+    ),
+    (
+        "Stop onboard recording? Enter to confirm, Back to cancel",
+        "Arrêter l'enregistrement à bord ? Enter confirme, Back annule",
+    ),
+    (
+        "Waiting for the node to report its recording state...",
+        "Attente de l'état d'enregistrement du nœud...",
+    ),
+    ("ADC readings", "Valeurs ADC"),
+    ("Hex", "Hex"),
+    ("Raw", "Brut"),
+    ("Value", "Valeur"),
+    ("Min", "Min"),
+    ("Max", "Max"),
+    ("Mean", "Moyenne"),
+    ("Thrust", "Poussée"),
+    ("Pressure", "Pression"),
+    ("Tared readings of the target", "Valeurs tarées de la cible"),
+    ("window", "fenêtre"),
+    (
+        "Left/Right: window, Enter: tare, Back: leave",
+        "Gauche/Droite : fenêtre, Enter : tarer, Back : quitter",
+    ),
+];
+
+fn table(language: Language) -> &'static [(&'static str, &'static str)] {
+    match language {
+        Language::English => &[],
+        Language::German => GERMAN,
+        Language::French => FRENCH,
+    }
+}
+
+pub fn tr(language: Language, text: &str) -> &str {
+    table(language)
+        .iter()
+        .find(|(english, _)| *english == text)
+        .map_or(text, |(_, translated)| translated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAFETY_WORDS: &[&str] = &["arm", "fire", "ignit", "pyro", "open", "safety"];
+    // A safe system mustn't read as ready to go
+    const READY_WORDS: &[&str] = &["ready", "bereit", "prêt"];
+
+    #[test]
+    fn test_lookup_falls_back_to_english() {
+        assert_eq!(tr(Language::German, "Connected"), "Verbunden");
+        assert_eq!(tr(Language::English, "Connected"), "Connected");
+        assert_eq!(tr(Language::German, "Fire!"), "Fire!");
+        assert_eq!(tr(Language::French, "Connected"), "Connecté");
+    }
+
+    #[test]
+    fn test_languages_cover_the_same_labels() {
+        let labels = |table: &[(&str, &str)]| -> Vec<String> {
+            table
+                .iter()
+                .map(|(english, _)| english.to_string())
+                .collect()
+        };
+        assert_eq!(labels(GERMAN), labels(FRENCH));
+    }
+
+    #[test]
+    fn test_safety_words_stay_english() {
+        for (english, translated) in GERMAN.iter().chain(FRENCH) {
+            let english = english.to_lowercase();
+            assert!(
+                !SAFETY_WORDS.iter().any(|word| english.contains(word)),
+                "{} must not be translated",
+                english
+            );
+            assert!(!translated.is_empty());
+            let translated = translated.to_lowercase();
+            assert!(
+                !READY_WORDS.iter().any(|word| translated.contains(word)),
+                "{} reads as ready",
+                translated
+            );
+        }
+    }
+}
@@ -0,0 +1,175 @@
+// Pad waits routinely run hours on battery. Without input or a change
+// of state for a while the frontend goes into standby: the observables
+// are polled less often and the display is dimmed. Any input or
+// telemetry anomaly restores full activity at once.
+use std::time::Duration;
+
+use crate::clock::Instant;
+use crate::connection::LinkActivity;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdlePolicy {
+    // Without activity until standby, never without
+    pub after: Option<Duration>,
+    // Polling isn't faster than that in standby
+    pub poll_interval: Duration,
+    pub brightness_percent: u8,
+}
+
+impl Default for IdlePolicy {
+    fn default() -> Self {
+        Self {
+            after: Some(Duration::from_secs(10 * 60)),
+            poll_interval: Duration::from_secs(1),
+            brightness_percent: 20,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct IdleDetector {
+    policy: IdlePolicy,
+    last_activity: Instant,
+    standby: bool,
+}
+
+impl IdleDetector {
+    pub fn new(policy: IdlePolicy, now: Instant) -> Self {
+        Self {
+            policy,
+            last_activity: now,
+            standby: false,
+        }
+    }
+
+    pub fn set_policy(&mut self, policy: IdlePolicy) {
+        self.policy = policy;
+    }
+
+    // True when this left the standby
+    pub fn wake(&mut self, now: Instant) -> bool {
+        self.last_activity = now;
+        std::mem::take(&mut self.standby)
+    }
+
+    // True when this entered the standby
+    pub fn update(&mut self, now: Instant) -> bool {
+        let idle = match self.policy.after {
+            Some(after) => now.duration_since(self.last_activity) >= after,
+            None => false,
+        };
+        let entered = idle && !self.standby;
+        self.standby = idle;
+        entered
+    }
+
+    pub fn is_standby(&self) -> bool {
+        self.standby
+    }
+
+    // What the mode asks for, slowed down in standby
+    pub fn link_activity(&self, activity: LinkActivity) -> LinkActivity {
+        match activity {
+            LinkActivity::Polling(interval) if self.standby => {
+                LinkActivity::Polling(interval.max(self.policy.poll_interval))
+            }
+            activity => activity,
+        }
+    }
+
+    pub fn brightness_percent(&self) -> u8 {
+        match self.standby {
+            true => self.policy.brightness_percent,
+            false => 100,
+        }
+    }
+}
+
+#[cfg(feature = "novaview")]
+pub mod backlight {
+    use std::fs;
+    use std::path::PathBuf;
+
+    const BACKLIGHTS: &str = "/sys/class/backlight";
+
+    // The display backlight, through sysfs
+    pub struct Backlight {
+        path: PathBuf,
+        max: u32,
+        percent: Option<u8>,
+    }
+
+    impl Backlight {
+        // The first one there is
+        pub fn find() -> anyhow::Result<Self> {
+            let path = fs::read_dir(BACKLIGHTS)?
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("no backlight in {}", BACKLIGHTS))??
+                .path();
+            let max = fs::read_to_string(path.join("max_brightness"))?
+                .trim()
+                .parse()?;
+            Ok(Self {
+                path,
+                max,
+                percent: None,
+            })
+        }
+
+        // Only written on a change
+        pub fn set(&mut self, percent: u8) -> anyhow::Result<()> {
+            if self.percent == Some(percent) {
+                return Ok(());
+            }
+            let brightness = (self.max * percent.min(100) as u32 / 100).max(1);
+            fs::write(self.path.join("brightness"), brightness.to_string())?;
+            self.percent = Some(percent);
+            Ok(())
+        }
+    }
+
+    // Not left dimmed behind
+    impl Drop for Backlight {
+        fn drop(&mut self) {
+            let _ = self.set(100);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standby_after_inactivity() {
+        let start = Instant::now();
+        let minute = Duration::from_secs(60);
+        let mut idle = IdleDetector::new(IdlePolicy::default(), start);
+        let fast = LinkActivity::Polling(Duration::from_millis(50));
+        assert!(!idle.update(start + minute * 9));
+        assert_eq!(idle.link_activity(fast), fast);
+        assert_eq!(idle.brightness_percent(), 100);
+        assert!(idle.update(start + minute * 10));
+        assert!(!idle.update(start + minute * 11));
+        assert!(idle.is_standby());
+        assert_eq!(
+            idle.link_activity(fast),
+            LinkActivity::Polling(Duration::from_secs(1))
+        );
+        assert_eq!(
+            idle.link_activity(LinkActivity::Silent),
+            LinkActivity::Silent
+        );
+        assert_eq!(idle.brightness_percent(), 20);
+        // Activity restores it right away
+        assert!(idle.wake(start + minute * 12));
+        assert!(!idle.wake(start + minute * 12));
+        assert_eq!(idle.link_activity(fast), fast);
+        assert!(!idle.update(start + minute * 21));
+        idle.set_policy(IdlePolicy {
+            after: None,
+            ..Default::default()
+        });
+        assert!(!idle.update(start + minute * 60));
+    }
+}
@@ -1,4 +1,12 @@
-#[derive(Debug, Copy, Clone)]
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+// Magnitudes of Left/Right are in tenths of a step,
+// so a single key press or click is one step.
+pub const STEP_MAGNITUDE: u32 = 10;
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum InputEvent {
     Enter,
     Back,
@@ -6,3 +14,381 @@ pub enum InputEvent {
     Right(u32),
     Send,
 }
+
+// Keys we listen to, independent of the windowing backend
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Key {
+    ArrowLeft,
+    ArrowRight,
+    Enter,
+    Space,
+    Backspace,
+    Escape,
+    S,
+    T,
+    M,
+    H,
+    D,
+    P,
+    K,
+    F,
+    G,
+    L,
+    A,
+    R,
+    N,
+    B,
+    O,
+    I,
+    C,
+    U,
+    X,
+    J,
+    Z,
+    V,
+}
+
+// What a key or encoder input does. Besides the input events
+// handled by the current mode, there are a few global actions.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Binding {
+    Event(InputEvent),
+    SelfTest,
+    Monitor,
+    Downloads,
+    Broadcast,
+    Sequences,
+    Tuning,
+    Faults,
+    Transitions,
+    Timing,
+    Filters,
+    Calibration,
+    Adc,
+    Roster,
+    Ports,
+    Recovery,
+    SilenceOverride,
+    Sessions,
+    PostMortem,
+    Mark,
+    Abort,
+    Help,
+    Quit,
+}
+
+pub const KEY_BINDINGS: [(Key, Binding); 28] = [
+    (
+        Key::ArrowLeft,
+        Binding::Event(InputEvent::Left(STEP_MAGNITUDE)),
+    ),
+    (
+        Key::ArrowRight,
+        Binding::Event(InputEvent::Right(STEP_MAGNITUDE)),
+    ),
+    (Key::Enter, Binding::Event(InputEvent::Enter)),
+    (Key::Space, Binding::Event(InputEvent::Enter)),
+    (Key::Backspace, Binding::Event(InputEvent::Back)),
+    (Key::S, Binding::Event(InputEvent::Send)),
+    (Key::T, Binding::SelfTest),
+    (Key::M, Binding::Monitor),
+    (Key::D, Binding::Downloads),
+    (Key::B, Binding::Broadcast),
+    (Key::P, Binding::Sequences),
+    (Key::K, Binding::Tuning),
+    (Key::F, Binding::Faults),
+    (Key::G, Binding::Transitions),
+    (Key::O, Binding::Timing),
+    (Key::L, Binding::Filters),
+    (Key::I, Binding::Calibration),
+    (Key::V, Binding::Adc),
+    (Key::C, Binding::Roster),
+    (Key::U, Binding::Ports),
+    (Key::R, Binding::Recovery),
+    (Key::X, Binding::SilenceOverride),
+    (Key::J, Binding::Sessions),
+    (Key::Z, Binding::PostMortem),
+    (Key::N, Binding::Mark),
+    (Key::A, Binding::Abort),
+    (Key::H, Binding::Help),
+    (Key::Escape, Binding::Quit),
+];
+
+// The encoder and its two buttons on the novaview
+pub const ENCODER_BINDINGS: [(&str, Binding); 7] = [
+    (
+        "Turn left",
+        Binding::Event(InputEvent::Left(STEP_MAGNITUDE)),
+    ),
+    (
+        "Turn right",
+        Binding::Event(InputEvent::Right(STEP_MAGNITUDE)),
+    ),
+    ("Left button", Binding::Event(InputEvent::Back)),
+    ("Right button", Binding::Event(InputEvent::Enter)),
+    ("Hold left button", Binding::Help),
+    ("Hold left button 2s", Binding::Abort),
+    ("Hold right button", Binding::Mark),
+];
+
+#[derive(Debug, Clone)]
+pub struct RepeatConfig {
+    // How long a key needs to be held before repeating
+    pub delay: Duration,
+    pub interval: Duration,
+    // Additional steps per repeat for each second held
+    pub acceleration: f32,
+    pub max_steps: u32,
+}
+
+// How Left/Right move between the tabs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TabNavigation {
+    // From the last tab back to the first and vice versa
+    pub wrap: bool,
+    // Further events after a tab change are ignored for this long,
+    // so a fast spin of the encoder doesn't overshoot
+    pub debounce: Duration,
+}
+
+// Turns a held key into repeated Left/Right magnitudes,
+// growing the longer the key is held.
+pub struct KeyRepeat {
+    config: RepeatConfig,
+    held_since: Option<Instant>,
+    last_repeat: Option<Instant>,
+}
+
+impl InputEvent {
+    // Steps a digit by the magnitude of a Left/Right event
+    pub fn step_digit(&self, digit: u8, base: u8) -> u8 {
+        (digit as i64 + self.signed_steps()).rem_euclid(base as i64) as u8
+    }
+
+    // Negative to the left, zero for anything but Left/Right
+    pub fn signed_steps(&self) -> i64 {
+        match self {
+            InputEvent::Right(magnitude) => steps(*magnitude) as i64,
+            InputEvent::Left(magnitude) => -(steps(*magnitude) as i64),
+            _ => 0,
+        }
+    }
+}
+
+impl Key {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Key::ArrowLeft => "Left",
+            Key::ArrowRight => "Right",
+            Key::Enter => "Enter",
+            Key::Space => "Space",
+            Key::Backspace => "Backspace",
+            Key::Escape => "Escape",
+            Key::S => "S",
+            Key::T => "T",
+            Key::M => "M",
+            Key::H => "H",
+            Key::D => "D",
+            Key::P => "P",
+            Key::K => "K",
+            Key::F => "F",
+            Key::G => "G",
+            Key::L => "L",
+            Key::A => "A",
+            Key::R => "R",
+            Key::N => "N",
+            Key::B => "B",
+            Key::O => "O",
+            Key::I => "I",
+            Key::C => "C",
+            Key::U => "U",
+            Key::X => "X",
+            Key::J => "J",
+            Key::Z => "Z",
+            Key::V => "V",
+        }
+    }
+}
+
+impl Binding {
+    pub fn for_key(key: Key) -> Option<Binding> {
+        KEY_BINDINGS
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, binding)| *binding)
+    }
+
+    // Events match regardless of their magnitude
+    pub fn triggers(&self, event: &InputEvent) -> bool {
+        match self {
+            Binding::Event(e) => std::mem::discriminant(e) == std::mem::discriminant(event),
+            _ => false,
+        }
+    }
+
+    // Only for the global actions, events depend on the mode
+    pub fn description(&self) -> Option<&'static str> {
+        match self {
+            Binding::Event(_) => None,
+            Binding::SelfTest => Some("Run the self-test"),
+            Binding::Monitor => Some("Toggle the channel monitor"),
+            Binding::Downloads => Some("Toggle onboard file downloads"),
+            Binding::Broadcast => Some("Toggle the command broadcast to all nodes"),
+            Binding::Sequences => Some("Toggle scripted sequences"),
+            Binding::Tuning => cfg!(debug_assertions).then_some("Toggle progress tuning"),
+            Binding::Faults => cfg!(debug_assertions).then_some("Toggle fault injection"),
+            Binding::Transitions => {
+                cfg!(debug_assertions).then_some("Toggle the state machine overlay")
+            }
+            Binding::Timing => Some("Toggle the frame timing overlay"),
+            Binding::Filters => Some("Toggle the plot filters"),
+            Binding::Calibration => Some("Toggle the IMU range calibration"),
+            Binding::Adc => Some("Toggle the raw ADC readings, for calibrating"),
+            Binding::Roster => Some("Toggle the telemetry roster"),
+            Binding::Ports => Some("Pick the serial port of the radio"),
+            Binding::Recovery => Some("Toggle the recovery page"),
+            Binding::SilenceOverride => Some("Override the radio silence a node expects"),
+            Binding::Sessions => Some("Browse the previous sessions"),
+            Binding::PostMortem => Some("Write a diagnostic bundle for the developers"),
+            Binding::Mark => Some("Mark this moment in the journal and plots"),
+            Binding::Abort => Some("Abort the launch, re-locks the pyros"),
+            Binding::Help => Some("Toggle this help"),
+            Binding::Quit => Some("Quit"),
+        }
+    }
+}
+
+// All inputs bound to the given binding
+pub fn inputs_for(binding: &Binding) -> Vec<&'static str> {
+    let matches = |b: &Binding| match binding {
+        Binding::Event(event) => b.triggers(event),
+        _ => b == binding,
+    };
+    KEY_BINDINGS
+        .iter()
+        .filter(|(_, b)| matches(b))
+        .map(|(key, _)| key.name())
+        .chain(
+            ENCODER_BINDINGS
+                .iter()
+                .filter(|(_, b)| matches(b))
+                .map(|(name, _)| *name),
+        )
+        .collect()
+}
+
+fn steps(magnitude: u32) -> u32 {
+    std::cmp::max(1, magnitude / STEP_MAGNITUDE)
+}
+
+impl Default for RepeatConfig {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_millis(400),
+            interval: Duration::from_millis(120),
+            acceleration: 2.0,
+            max_steps: 4,
+        }
+    }
+}
+
+impl Default for TabNavigation {
+    fn default() -> Self {
+        Self {
+            wrap: true,
+            debounce: Duration::from_millis(150),
+        }
+    }
+}
+
+impl KeyRepeat {
+    pub fn new(config: RepeatConfig) -> Self {
+        Self {
+            config,
+            held_since: None,
+            last_repeat: None,
+        }
+    }
+
+    // Feed with the key state once per frame. Returns the
+    // magnitude of the event to emit, if any.
+    pub fn update(&mut self, held: bool, now: Instant) -> Option<u32> {
+        if !held {
+            self.held_since = None;
+            self.last_repeat = None;
+            return None;
+        }
+        let held_since = match self.held_since {
+            Some(held_since) => held_since,
+            None => {
+                self.held_since = Some(now);
+                self.last_repeat = Some(now);
+                return Some(STEP_MAGNITUDE);
+            }
+        };
+        let held_for = now - held_since;
+        let since_last = now - self.last_repeat.unwrap_or(held_since);
+        if held_for < self.config.delay || since_last < self.config.interval {
+            return None;
+        }
+        self.last_repeat = Some(now);
+        let accelerated = (held_for - self.config.delay).as_secs_f32() * self.config.acceleration;
+        let steps = std::cmp::min(1 + accelerated as u32, self.config.max_steps);
+        Some(steps * STEP_MAGNITUDE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_digit() {
+        assert_eq!(InputEvent::Right(STEP_MAGNITUDE).step_digit(0, 16), 1);
+        assert_eq!(InputEvent::Left(STEP_MAGNITUDE).step_digit(0, 16), 15);
+        assert_eq!(InputEvent::Right(4 * STEP_MAGNITUDE).step_digit(14, 16), 2);
+        assert_eq!(InputEvent::Left(1).step_digit(3, 16), 2);
+    }
+
+    #[test]
+    fn test_inputs_for_binding() {
+        assert_eq!(
+            inputs_for(&Binding::Event(InputEvent::Enter)),
+            vec!["Enter", "Space", "Right button"]
+        );
+        assert_eq!(
+            inputs_for(&Binding::Event(InputEvent::Left(1))),
+            vec!["Left", "Turn left"]
+        );
+        assert_eq!(inputs_for(&Binding::Help), vec!["H", "Hold left button"]);
+        assert_eq!(inputs_for(&Binding::Mark), vec!["N", "Hold right button"]);
+        assert_eq!(Binding::for_key(Key::T), Some(Binding::SelfTest));
+    }
+
+    #[test]
+    fn test_key_repeat_accelerates() {
+        let mut repeat = KeyRepeat::new(RepeatConfig::default());
+        let start = Instant::now();
+        assert_eq!(repeat.update(true, start), Some(STEP_MAGNITUDE));
+        assert_eq!(
+            repeat.update(true, start + Duration::from_millis(100)),
+            None
+        );
+        assert_eq!(
+            repeat.update(true, start + Duration::from_millis(400)),
+            Some(STEP_MAGNITUDE)
+        );
+        assert_eq!(
+            repeat.update(true, start + Duration::from_millis(2400)),
+            Some(4 * STEP_MAGNITUDE)
+        );
+        assert_eq!(
+            repeat.update(false, start + Duration::from_millis(2500)),
+            None
+        );
+        assert_eq!(
+            repeat.update(true, start + Duration::from_millis(2600)),
+            Some(STEP_MAGNITUDE)
+        );
+    }
+}
@@ -0,0 +1,123 @@
+// The key bindings with the frame of the Model they were processed in,
+// taped as JSON lines. Played back into the Model frame by frame they
+// make UI regression tests of sequences like a full arming flow
+// reproducible, independent of the radio backends. Live bindings are
+// ignored meanwhile. Besides the input events of the modes, the
+// global ones like Abort or the page toggles are taped.
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::input::Binding;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Taped {
+    // Of the Model, counted from its start
+    pub frame: u64,
+    pub binding: Binding,
+}
+
+pub struct TapeRecorder {
+    out: BufWriter<File>,
+}
+
+#[derive(Debug, Default)]
+pub struct Playback {
+    tape: VecDeque<Taped>,
+}
+
+impl TapeRecorder {
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        Ok(Self {
+            out: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    // Flushed with each, the tape of a crash is the interesting one
+    pub fn record(&mut self, taped: &Taped) -> anyhow::Result<()> {
+        serde_json::to_writer(&mut self.out, taped)?;
+        writeln!(self.out)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+impl Playback {
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let tape = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|err| anyhow!("Line {}: {}", index + 1, err))
+            })
+            .collect::<anyhow::Result<VecDeque<Taped>>>()?;
+        if tape
+            .iter()
+            .zip(tape.iter().skip(1))
+            .any(|(a, b)| a.frame > b.frame)
+        {
+            return Err(anyhow!("The frames of the tape aren't in order"));
+        }
+        Ok(Self { tape })
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    // Of the frame, and earlier ones not taken
+    pub fn take(&mut self, frame: u64) -> Vec<Binding> {
+        let mut bindings = vec![];
+        while let Some(taped) = self.tape.front().filter(|taped| taped.frame <= frame) {
+            bindings.push(taped.binding);
+            self.tape.pop_front();
+        }
+        bindings
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.tape.is_empty()
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.tape.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InputEvent;
+
+    #[test]
+    fn test_taped_bindings_come_back_in_their_frames() {
+        let path = std::env::temp_dir().join(format!("lnc-input-{}.tape", std::process::id()));
+        let mut recorder = TapeRecorder::create(&path).unwrap();
+        for (frame, binding) in [
+            (3, Binding::Event(InputEvent::Right(10))),
+            (3, Binding::Event(InputEvent::Enter)),
+            (7, Binding::Abort),
+        ] {
+            recorder.record(&Taped { frame, binding }).unwrap();
+        }
+        let mut playback = Playback::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(playback.take(2), vec![]);
+        assert_eq!(
+            playback.take(3),
+            vec![
+                Binding::Event(InputEvent::Right(10)),
+                Binding::Event(InputEvent::Enter)
+            ]
+        );
+        assert_eq!(playback.remaining(), 1);
+        assert_eq!(playback.take(8), vec![Binding::Abort]);
+        assert!(playback.is_finished());
+        assert!(Playback::parse("{\"frame\":1}").is_err());
+    }
+}
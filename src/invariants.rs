@@ -0,0 +1,239 @@
+// Runtime verification of what the protocol promises, alongside the
+// Consort. The Consort acts on the answers and is lenient where it
+// can be, this only watches: answers must belong to the transaction
+// in flight and come from the node it addresses, observables must
+// not trail the ACK that ended their transaction, and the launch
+// control must only take transitions of its graph. Violations don't
+// change what happens, they are logged and kept for the monitor
+// page so firmware regressions are caught early.
+use std::collections::{BTreeSet, VecDeque};
+
+use log::warn;
+
+use crate::{
+    clock::Instant,
+    fsm::launch_control_graph,
+    rqparser::{answer_header_parser, verify_nmea_format},
+    rqprotocol::Node,
+};
+
+const VIOLATION_HISTORY: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    // No transaction with the id is in flight
+    UnknownId(usize),
+    // The answer isn't from the node addressed, or not to us
+    WrongNode { expected: Node, got: Node },
+    // Observables after the final ACK of their transaction
+    ObservablesAfterAck(usize),
+    IllegalTransition { from: String, to: String },
+}
+
+#[derive(Debug)]
+pub struct LoggedViolation {
+    pub at: Instant,
+    pub violation: Violation,
+}
+
+#[derive(Debug)]
+pub struct Invariants {
+    me: Node,
+    // Id and recipient
+    in_flight: Option<(usize, Node)>,
+    // Dropped by a reset, timeout or abort. Their late
+    // answers are expected on a radio link.
+    abandoned: Option<usize>,
+    acknowledged: Option<usize>,
+    // Pairs of state names, explored when first needed
+    legal: Option<BTreeSet<(String, String)>>,
+    count: usize,
+    violations: VecDeque<LoggedViolation>,
+}
+
+impl Violation {
+    pub fn description(&self) -> String {
+        match self {
+            Violation::UnknownId(id) => format!("Answer to {:03}, not in flight", id),
+            Violation::WrongNode { expected, got } => {
+                format!("Answer by/to {}, expected {}", got, expected)
+            }
+            Violation::ObservablesAfterAck(id) => {
+                format!("Observables for {:03} after its ACK", id)
+            }
+            Violation::IllegalTransition { from, to } => {
+                format!("Illegal transition {} -> {}", from, to)
+            }
+        }
+    }
+}
+
+impl Invariants {
+    pub fn new(me: Node) -> Self {
+        Self {
+            me,
+            in_flight: None,
+            abandoned: None,
+            acknowledged: None,
+            legal: None,
+            count: 0,
+            violations: VecDeque::with_capacity(VIOLATION_HISTORY),
+        }
+    }
+
+    // A transaction still in flight is abandoned for it
+    pub fn sent(&mut self, id: usize, recipient: Node) {
+        self.abandon();
+        self.in_flight = Some((id, recipient));
+    }
+
+    pub fn abandon(&mut self) {
+        if let Some((id, _)) = self.in_flight.take() {
+            self.abandoned = Some(id);
+        }
+    }
+
+    // Sentences that don't parse are the Consort's to complain about
+    pub fn received(&mut self, now: Instant, sentence: &[u8]) {
+        let (source, verb, id, recipient) =
+            match verify_nmea_format(sentence).map(answer_header_parser) {
+                Ok(Ok((_rest, header))) => header,
+                _ => return,
+            };
+        let observables = verb == b"OBG";
+        if self.abandoned == Some(id) {
+            return;
+        }
+        if observables && self.acknowledged == Some(id) {
+            self.violated(now, Violation::ObservablesAfterAck(id));
+            return;
+        }
+        let expected = match self.in_flight {
+            Some((in_flight, expected)) if in_flight == id => expected,
+            _ => {
+                self.violated(now, Violation::UnknownId(id));
+                return;
+            }
+        };
+        if source != expected {
+            self.violated(
+                now,
+                Violation::WrongNode {
+                    expected,
+                    got: source,
+                },
+            );
+        } else if recipient != self.me {
+            self.violated(
+                now,
+                Violation::WrongNode {
+                    expected: self.me,
+                    got: recipient,
+                },
+            );
+        }
+        if !observables {
+            self.in_flight = None;
+            self.acknowledged = Some(id);
+        }
+    }
+
+    // Of the launch control states, named as in fsm.rs
+    pub fn transition(&mut self, now: Instant, from: String, to: String) {
+        let legal = self.legal.get_or_insert_with(|| {
+            launch_control_graph()
+                .transitions
+                .into_iter()
+                .map(|transition| (transition.from, transition.to))
+                .collect()
+        });
+        if from != to && !legal.contains(&(from.clone(), to.clone())) {
+            self.violated(now, Violation::IllegalTransition { from, to });
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    // The latest first
+    pub fn violations(&self) -> impl Iterator<Item = &LoggedViolation> {
+        self.violations.iter().rev()
+    }
+
+    fn violated(&mut self, at: Instant, violation: Violation) {
+        warn!("Protocol violation: {}", violation.description());
+        self.count += 1;
+        if self.violations.len() == VIOLATION_HISTORY {
+            self.violations.pop_front();
+        }
+        self.violations.push_back(LoggedViolation { at, violation });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fsm::state_name;
+    use crate::model::{CoreConnection, LaunchControlMode};
+
+    fn latest(invariants: &Invariants) -> Option<Violation> {
+        invariants
+            .violations()
+            .next()
+            .map(|logged| logged.violation.clone())
+    }
+
+    #[test]
+    fn test_answers_must_match_the_transaction() {
+        let now = Instant::now();
+        let mut invariants = Invariants::new(Node::LaunchControl);
+        invariants.sent(6, Node::RedQueen(b'A'));
+        invariants.received(
+            now,
+            b"$RQAOBG,006,LNC,1,0BEBC200,000000003440E810,00069B00,FFFFFA7B*16\r\n",
+        );
+        invariants.received(now, b"$RQAACK,006,LNC,1*61\r\n");
+        assert_eq!(invariants.count(), 0);
+        invariants.received(
+            now,
+            b"$RQAOBG,006,LNC,1,0BEBC200,000000003440E810,00069B00,FFFFFA7B*16\r\n",
+        );
+        assert_eq!(latest(&invariants), Some(Violation::ObservablesAfterAck(6)));
+        invariants.sent(7, Node::RedQueen(b'A'));
+        invariants.received(now, b"$RQBACK,007,LNC*7E\r\n");
+        assert_eq!(
+            latest(&invariants),
+            Some(Violation::WrongNode {
+                expected: Node::RedQueen(b'A'),
+                got: Node::RedQueen(b'B')
+            })
+        );
+        invariants.received(now, b"$RQAACK,123,LNC*7A\r\n");
+        assert_eq!(latest(&invariants), Some(Violation::UnknownId(123)));
+        // A late answer after giving up on it is fine
+        invariants.sent(8, Node::RedQueen(b'A'));
+        invariants.sent(9, Node::RedQueen(b'A'));
+        invariants.received(now, b"$RQAACK,008,LNC*72\r\n");
+        assert_eq!(invariants.count(), 3);
+    }
+
+    #[test]
+    fn test_launch_control_follows_its_graph() {
+        let now = Instant::now();
+        let mut invariants = Invariants::new(Node::LaunchControl);
+        let reset = LaunchControlMode::Core(CoreConnection::Reset);
+        let wait = LaunchControlMode::WaitForPyroTimeout(now);
+        let fire = LaunchControlMode::Fire;
+        invariants.transition(now, state_name(&fire), state_name(&wait));
+        assert_eq!(invariants.count(), 0);
+        invariants.transition(now, state_name(&reset), state_name(&fire));
+        assert_eq!(
+            latest(&invariants),
+            Some(Violation::IllegalTransition {
+                from: state_name(&reset),
+                to: state_name(&fire)
+            })
+        );
+    }
+}
@@ -0,0 +1,148 @@
+// The session journal documents noteworthy events of a session
+// in human readable form, next to the recording if there is one.
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use log::{error, info};
+
+use crate::bus::{Marked, Topic};
+use crate::clock::SharedClock;
+
+// Events worth a marker on the plots
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Marker {
+    Reset,
+    PyrosUnlocked,
+    Ignition,
+    Anomaly,
+    Abort,
+    RailExit,
+    // Set by the operator
+    Mark,
+}
+
+pub struct Entry {
+    pub at: DateTime<Utc>,
+    pub text: String,
+    // With the seconds into the session, the time axis of the plots
+    pub marker: Option<(Marker, f64)>,
+}
+
+impl Marker {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Marker::Reset => "Reset",
+            Marker::PyrosUnlocked => "Pyros unlocked",
+            Marker::Ignition => "Ignition",
+            Marker::Anomaly => "Anomaly",
+            Marker::Abort => "Abort",
+            Marker::RailExit => "Rail exit",
+            Marker::Mark => "Mark",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Journal {
+    entries: Vec<Entry>,
+    path: Option<PathBuf>,
+    markers: Option<Topic<Marked>>,
+    // Of the wall time, the system's without
+    clock: Option<SharedClock>,
+}
+
+impl Journal {
+    pub fn new(recording: Option<&Path>) -> Self {
+        Self {
+            entries: vec![],
+            path: recording.map(|p| p.with_extension("journal")),
+            markers: None,
+            clock: None,
+        }
+    }
+
+    pub fn stamp_with(&mut self, clock: SharedClock) {
+        self.clock = Some(clock);
+    }
+
+    pub fn publish_markers(&mut self, topic: Topic<Marked>) {
+        self.markers = Some(topic);
+    }
+
+    pub fn record(&mut self, text: impl Into<String>) {
+        self.push(text.into(), None);
+    }
+
+    pub fn mark(&mut self, marker: Marker, elapsed: f64, text: impl Into<String>) {
+        self.push(text.into(), Some((marker, elapsed)));
+    }
+
+    fn push(&mut self, text: String, marker: Option<(Marker, f64)>) {
+        let entry = Entry {
+            at: self
+                .clock
+                .as_ref()
+                .map_or_else(Utc::now, |clock| clock.wall()),
+            text,
+            marker,
+        };
+        info!("Journal: {}", entry.text);
+        if let (Some(topic), Some((marker, elapsed))) = (&self.markers, entry.marker) {
+            topic.publish(Marked {
+                marker,
+                elapsed,
+                text: entry.text.clone(),
+            });
+        }
+        if let Some(path) = &self.path {
+            if let Err(err) = append(path, &entry) {
+                error!("Can't write journal {:?}: {:?}", path, err);
+            }
+        }
+        self.entries.push(entry);
+    }
+
+    pub fn entries(&self) -> &Vec<Entry> {
+        &self.entries
+    }
+
+    pub fn markers(&self) -> impl Iterator<Item = (Marker, f64, &str)> {
+        self.entries
+            .iter()
+            .filter_map(|e| e.marker.map(|(marker, at)| (marker, at, e.text.as_str())))
+    }
+
+    // The marker closest to the given time, if within tolerance
+    pub fn marker_near(&self, elapsed: f64, tolerance: f64) -> Option<(Marker, f64, &str)> {
+        self.markers()
+            .filter(|(_, at, _)| (at - elapsed).abs() <= tolerance)
+            .min_by(|a, b| (a.1 - elapsed).abs().total_cmp(&(b.1 - elapsed).abs()))
+    }
+}
+
+fn append(path: &Path, entry: &Entry) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{} {}", entry.at.to_rfc3339(), entry.text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marker_near() {
+        let mut journal = Journal::new(None);
+        journal.record("Config reloaded");
+        journal.mark(Marker::Reset, 1.0, "Reset acknowledged by RQB");
+        journal.mark(Marker::Ignition, 2.0, "Ignition acknowledged by RQB");
+        assert_eq!(journal.markers().count(), 2);
+        assert_eq!(
+            journal.marker_near(1.8, 0.5),
+            Some((Marker::Ignition, 2.0, "Ignition acknowledged by RQB"))
+        );
+        assert_eq!(journal.marker_near(1.4, 0.5).unwrap().0, Marker::Reset);
+        assert_eq!(journal.marker_near(5.0, 0.5), None);
+    }
+}
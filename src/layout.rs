@@ -10,6 +10,244 @@ pub mod header {
     pub const MARGIN: f32 = 0.1;
 }
 
+// Everything was laid out for the 1024x600 novaview screen, so
+// sizes are given for that and scaled to the actual window.
+pub mod scaling {
+    use egui::{Context, FontFamily, FontId, Id, Ui, Vec2};
+
+    pub const REFERENCE_WIDTH: f32 = 1024.0;
+    pub const REFERENCE_HEIGHT: f32 = 600.0;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Scale(f32);
+
+    // Waiting for the fire and the burn shown large and in high
+    // contrast, readable from a distance
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Accessibility {
+        pub enabled: bool,
+        // Of the critical state texts, on top of the UI scale
+        pub critical_scale: f32,
+    }
+
+    impl Default for Accessibility {
+        fn default() -> Self {
+            Self {
+                enabled: false,
+                critical_scale: 2.0,
+            }
+        }
+    }
+
+    fn scale_id() -> Id {
+        Id::new("layout scale")
+    }
+
+    // The smaller ratio wins so nothing gets cut off
+    pub fn factor_for(size: Vec2, user_scale: f32) -> f32 {
+        let factor = (size.x / REFERENCE_WIDTH).min(size.y / REFERENCE_HEIGHT);
+        factor.max(0.1) * user_scale
+    }
+
+    // Call once per frame before rendering, this follows
+    // window resizes.
+    pub fn apply(ctx: &Context, user_scale: f32) {
+        let factor = factor_for(ctx.screen_rect().size(), user_scale);
+        if ctx.data_mut(|d| d.get_temp::<Scale>(scale_id())) == Some(Scale(factor)) {
+            return;
+        }
+        ctx.data_mut(|d| d.insert_temp(scale_id(), Scale(factor)));
+        let mut style = (*ctx.style()).clone();
+        for (text_style, font) in style.text_styles.iter_mut() {
+            if let Some(reference) = egui::Style::default().text_styles.get(text_style) {
+                font.size = reference.size * factor;
+            }
+        }
+        ctx.set_style(style);
+    }
+
+    pub fn factor(ui: &Ui) -> f32 {
+        ui.ctx()
+            .data_mut(|d| d.get_temp::<Scale>(scale_id()))
+            .map_or(1.0, |scale| scale.0)
+    }
+
+    // A size given for the reference screen
+    pub fn scaled(ui: &Ui, size: f32) -> f32 {
+        size * factor(ui)
+    }
+
+    pub fn monospace(ui: &Ui, size: f32) -> FontId {
+        FontId::new(scaled(ui, size), FontFamily::Monospace)
+    }
+}
+
+// Light for daylight on the range, dark otherwise. Only the egui
+// visuals follow, the colors of the modes stay as they are.
+pub mod theme {
+    use std::str::FromStr;
+
+    use egui::{Context, Visuals};
+    use serde::Deserialize;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+    pub enum Theme {
+        #[default]
+        Dark,
+        Light,
+    }
+
+    impl FromStr for Theme {
+        type Err = &'static str;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "Dark" => Ok(Theme::Dark),
+                "Light" => Ok(Theme::Light),
+                _ => Err("No valid value, use Dark, Light"),
+            }
+        }
+    }
+
+    // Call once per frame before rendering, like the scaling
+    pub fn apply(ctx: &Context, theme: Theme) {
+        let dark = theme == Theme::Dark;
+        if ctx.style().visuals.dark_mode != dark {
+            ctx.set_visuals(if dark {
+                Visuals::dark()
+            } else {
+                Visuals::light()
+            });
+        }
+    }
+}
+
+// Large numbers like thrust or the MET: the value with its unit and
+// where it's heading, sized to fill the space they get. Stale values
+// are greyed and struck through, like the labels elsewhere.
+pub mod numeric {
+    use egui::{Color32, FontId, Pos2, Sense, Stroke, Ui, Vec2};
+
+    use super::scaling::scaled;
+
+    // Text is measured at this size, then scaled to fit
+    const MEASURE_SIZE: f32 = 100.0;
+    // Of the unit and trend arrow, relative to the value
+    const UNIT_RATIO: f32 = 0.4;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Trend {
+        Rising,
+        Falling,
+        Steady,
+    }
+
+    pub struct NumericDisplay {
+        value: String,
+        unit: String,
+        trend: Option<Trend>,
+        stale: bool,
+        color: Color32,
+    }
+
+    impl Trend {
+        // Changes within the deadband are steady
+        pub fn of(previous: f64, current: f64, deadband: f64) -> Self {
+            match current - previous {
+                delta if delta > deadband => Trend::Rising,
+                delta if delta < -deadband => Trend::Falling,
+                _ => Trend::Steady,
+            }
+        }
+
+        fn arrow(&self) -> &'static str {
+            match self {
+                Trend::Rising => "▲",
+                Trend::Falling => "▼",
+                Trend::Steady => "▶",
+            }
+        }
+    }
+
+    // The font size of text measured at MEASURE_SIZE to fill the space
+    pub fn fit(measured: Vec2, available: Vec2) -> f32 {
+        if measured.x <= 0.0 || measured.y <= 0.0 {
+            return MEASURE_SIZE;
+        }
+        MEASURE_SIZE * (available.x / measured.x).min(available.y / measured.y)
+    }
+
+    impl NumericDisplay {
+        pub fn new(value: impl Into<String>, unit: impl Into<String>) -> Self {
+            Self {
+                value: value.into(),
+                unit: unit.into(),
+                trend: None,
+                stale: false,
+                color: Color32::WHITE,
+            }
+        }
+
+        pub fn trend(mut self, trend: Option<Trend>) -> Self {
+            self.trend = trend;
+            self
+        }
+
+        pub fn stale(mut self, stale: bool) -> Self {
+            self.stale = stale;
+            self
+        }
+
+        pub fn color(mut self, color: Color32) -> Self {
+            self.color = color;
+            self
+        }
+
+        // Across the available width, at most as high as given
+        // for the reference screen, aligned like the ui
+        pub fn show(self, ui: &mut Ui, max_height: f32) {
+            let align = ui.layout().horizontal_align().to_factor();
+            let size = Vec2::new(ui.available_width(), scaled(ui, max_height));
+            let (response, painter) = ui.allocate_painter(size, Sense::hover());
+            let color = match self.stale {
+                true => Color32::GRAY,
+                false => self.color,
+            };
+            let suffix = match self.trend {
+                Some(trend) => format!(" {} {}", self.unit, trend.arrow()),
+                None => format!(" {}", self.unit),
+            };
+            let layout = |size: f32| {
+                (
+                    painter.layout_no_wrap(self.value.clone(), FontId::monospace(size), color),
+                    painter.layout_no_wrap(
+                        suffix.clone(),
+                        FontId::monospace(size * UNIT_RATIO),
+                        color,
+                    ),
+                )
+            };
+            let (value, unit) = layout(MEASURE_SIZE);
+            let measured = Vec2::new(value.size().x + unit.size().x, value.size().y);
+            let (value, unit) = layout(fit(measured, response.rect.size()));
+            let spare = response.rect.width() - value.size().x - unit.size().x;
+            let origin = response.rect.left_top() + Vec2::new(spare.max(0.0) * align, 0.0);
+            // The unit sits on the baseline of the value
+            let unit_origin = origin + Vec2::new(value.size().x, value.size().y - unit.size().y);
+            let strike = origin.y + value.size().y / 2.0;
+            let strike = [
+                Pos2::new(origin.x, strike),
+                Pos2::new(origin.x + value.size().x, strike),
+            ];
+            painter.galley(origin, value);
+            painter.galley(unit_origin, unit);
+            if self.stale {
+                painter.line_segment(strike, Stroke::new(scaled(ui, 2.0), color));
+            }
+        }
+    }
+}
+
 pub mod colors {
     use memoize::memoize;
 
@@ -137,6 +375,25 @@ mod tests {
     use super::*;
     use palette::LinSrgb;
 
+    #[test]
+    fn test_scale_factor() {
+        let reference = egui::vec2(scaling::REFERENCE_WIDTH, scaling::REFERENCE_HEIGHT);
+        assert_eq!(scaling::factor_for(reference, 1.0), 1.0);
+        assert_eq!(scaling::factor_for(reference * 2.0, 1.0), 2.0);
+        assert_eq!(scaling::factor_for(egui::vec2(2048.0, 600.0), 1.5), 1.5);
+        assert_eq!(scaling::factor_for(egui::vec2(512.0, 600.0), 1.0), 0.5);
+    }
+
+    #[test]
+    fn test_numbers_fill_the_available_space() {
+        use egui::vec2;
+        use numeric::{fit, Trend};
+        assert_eq!(fit(vec2(200.0, 100.0), vec2(400.0, 100.0)), 100.0);
+        assert_eq!(fit(vec2(200.0, 100.0), vec2(100.0, 300.0)), 50.0);
+        assert_eq!(Trend::of(1.0, 1.05, 0.1), Trend::Steady);
+        assert_eq!(Trend::of(1.0, 0.5, 0.1), Trend::Falling);
+    }
+
     #[test]
     fn test_color_from_hex_string() {
         let input = b"#0000ff";
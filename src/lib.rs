@@ -1,22 +1,76 @@
 #![feature(assert_matches, slice_pattern, exclusive_range_pattern)]
+pub mod airtime;
+pub mod annotation;
+pub mod announcer;
 pub mod args;
+pub mod autorecord;
+pub mod backoff;
+pub mod broadcast;
+pub mod budget;
+pub mod bus;
+pub mod calibration;
+pub mod camera;
+pub mod capture;
+pub mod clock;
 pub mod common;
+pub mod config;
+pub mod conformance;
 pub mod connection;
 pub mod consort;
+pub mod crank;
+pub mod dashboard;
+pub mod diagnostics;
+pub mod digits;
+pub mod download;
 #[cfg(feature = "novaview")]
 pub mod e32linux;
 #[cfg(feature = "e32")]
 pub mod ebyte;
 #[cfg(not(feature = "e32"))]
 pub mod ebytemock;
+pub mod error;
+pub mod failover;
+pub mod faults;
+pub mod freshness;
+pub mod fsm;
+pub mod gpstime;
+pub mod groundpower;
+pub mod i18n;
+pub mod idle;
 pub mod input;
+pub mod inputtape;
+pub mod invariants;
+pub mod journal;
 pub mod layout;
+pub mod linkcrypto;
+pub mod looptiming;
 pub mod model;
+pub mod monitor;
+pub mod notifications;
 pub mod observables;
+pub mod onboard;
+pub mod pendant;
+pub mod ports;
+pub mod postmortem;
 pub mod recorder;
+pub mod recovery;
 pub mod render;
 pub mod rqparser;
 pub mod rqprotocol;
+pub mod rqspec;
+pub mod run;
+pub mod safety;
+pub mod selftest;
+pub mod sequence;
+pub mod session;
+pub mod sessions;
+pub mod shutdown;
+pub mod silence;
+pub mod tcpbridge;
 pub mod telemetry;
+pub mod timeline;
+pub mod timesource;
 pub mod timestep;
+pub mod transcript;
 pub mod visualisation;
+pub mod window;
@@ -0,0 +1,85 @@
+// ChaCha20-Poly1305 as of RFC 8439, from the RustCrypto crate. Only
+// wrapped for the fixed size keys, nonces and tags of the link.
+use chacha20poly1305::aead::AeadInPlace;
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit};
+
+pub const KEY_SIZE: usize = 32;
+pub const NONCE_SIZE: usize = 12;
+pub const TAG_SIZE: usize = 16;
+
+// Encrypts in place
+pub fn seal(
+    key: &[u8; KEY_SIZE],
+    nonce: &[u8; NONCE_SIZE],
+    aad: &[u8],
+    data: &mut [u8],
+) -> [u8; TAG_SIZE] {
+    ChaCha20Poly1305::new(key.into())
+        .encrypt_in_place_detached(nonce.into(), aad, data)
+        // Only for more than 256 GiB
+        .expect("data too long to seal")
+        .into()
+}
+
+// Decrypts in place, but only if the tag matches
+pub fn open(
+    key: &[u8; KEY_SIZE],
+    nonce: &[u8; NONCE_SIZE],
+    aad: &[u8],
+    data: &mut [u8],
+    tag: &[u8; TAG_SIZE],
+) -> bool {
+    ChaCha20Poly1305::new(key.into())
+        .decrypt_in_place_detached(nonce.into(), aad, data, tag.into())
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // RFC 8439, section 2.8.2
+    #[test]
+    fn test_rfc_8439_aead_vector() {
+        let key: [u8; KEY_SIZE] = core::array::from_fn(|i| 0x80 + i as u8);
+        let nonce: [u8; NONCE_SIZE] = unhex("070000004041424344454647").try_into().unwrap();
+        let aad = unhex("50515253c0c1c2c3c4c5c6c7");
+        let plaintext = b"Ladies and Gentlemen of the class of '99: \
+If I could offer you only one tip for the future, sunscreen would be it.";
+        let mut data = plaintext.to_vec();
+        let tag = seal(&key, &nonce, &aad, &mut data);
+        assert_eq!(
+            data,
+            unhex(
+                "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d6\
+                 3dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b36\
+                 92ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc\
+                 3ff4def08e4b7a9de576d26586cec64b6116"
+            )
+        );
+        assert_eq!(tag.to_vec(), unhex("1ae10b594f09e26a7e902ecbd0600691"));
+        assert!(open(&key, &nonce, &aad, &mut data, &tag));
+        assert_eq!(data, plaintext);
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let key = [7; KEY_SIZE];
+        let nonce = [1; NONCE_SIZE];
+        let mut data = b"IGNITION".to_vec();
+        let tag = seal(&key, &nonce, b"LNCCMD", &mut data);
+        let mut flipped = data.clone();
+        flipped[0] ^= 1;
+        assert!(!open(&key, &nonce, b"LNCCMD", &mut flipped, &tag));
+        assert!(!open(&key, &nonce, b"LNCACK", &mut data.clone(), &tag));
+        assert!(open(&key, &nonce, b"LNCCMD", &mut data, &tag));
+        assert_eq!(data, b"IGNITION");
+    }
+}
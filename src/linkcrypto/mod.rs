@@ -0,0 +1,369 @@
+// Optional authenticated encryption of the commands, so nobody else
+// on the channel can arm or fire a node, or replay what we sent. It's
+// used with nodes a key is configured for. Their VERSION answer is
+// only journaled, a forged one mustn't talk us into the clear.
+//
+// For the firmware, the scheme is:
+//
+// - ChaCha20-Poly1305 (RFC 8439), with a pre-shared 256 bit key per
+//   node, used in both directions.
+// - Of `$LNCCMD,123,RQA,SECRET_AB,3F,AB*69` the verb and parameters
+//   are sealed, the header stays readable:
+//   `$LNCCMD,123,RQA,ENC,<counter>,<ciphertext and tag>*CS`
+// - The counter is a u64, 8 bytes big endian. Every sender increases
+//   it with each sealed sentence, and receivers drop sentences with a
+//   counter not above the last one of the sender. We keep the last one
+//   sent in the settings directory and start above it, or at the time
+//   in µs if that's further, so it keeps increasing across restarts.
+// - The nonce is the sender id (3 ASCII chars), a zero byte and the
+//   8 counter bytes.
+// - The associated data is the sentence up to the last comma.
+// - Counter, ciphertext and tag are base64 without padding.
+//
+// Only commands changing the state of a node are sealed. Queries like
+// the keepalive OBG polls and the file transfer wouldn't fit, and the
+// VERSION command negotiates the encryption in the first place.
+// Answers to queries may come sealed or in the clear, those to sealed
+// commands have to be sealed as well. The simulated node holds
+// no keys, so don't configure any when simulating.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use log::warn;
+
+use crate::{
+    rqparser::MAX_BUFFER_SIZE,
+    rqprotocol::{Capabilities, Command, Node},
+};
+
+pub mod aead;
+
+pub type Key = [u8; aead::KEY_SIZE];
+
+const ENCRYPTED: &[u8] = b"ENC";
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+// Between `$` and `*`
+const MAX_CONTENTS: usize = MAX_BUFFER_SIZE - 6;
+const COUNTER_FILE: &str = "link-counter";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Error {
+    SentenceTooLong,
+    Malformed,
+    NoKey,
+    Forged,
+    Replayed,
+    // In the clear where it had to be sealed
+    Unsealed,
+}
+
+#[derive(Debug)]
+pub struct LinkCipher {
+    keys: HashMap<Node, Key>,
+    // Whether the node reported the capability
+    negotiated: HashMap<Node, bool>,
+    counter: u64,
+    counter_path: Option<PathBuf>,
+    // Last counter per sender
+    seen: HashMap<Node, u64>,
+}
+
+pub fn decode_key(hex: &str) -> anyhow::Result<Key> {
+    if hex.len() != 2 * aead::KEY_SIZE || !hex.is_ascii() {
+        return Err(anyhow!("link key needs {} hex digits", 2 * aead::KEY_SIZE));
+    }
+    let mut key = [0; aead::KEY_SIZE];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16)
+            .map_err(|_| anyhow!("link key isn't hex"))?;
+    }
+    Ok(key)
+}
+
+// Once negotiated with the node
+pub fn is_sealed(command: &Command) -> bool {
+    !matches!(
+        command,
+        Command::Ping
+            | Command::ObservableGroup(_)
+            | Command::Version
+            | Command::FileOpen(_)
+            | Command::FileRead(_, _)
+            | Command::FileClose
+            | Command::FileList(_)
+    )
+}
+
+fn encode(data: &[u8]) -> Vec<u8> {
+    let mut text = Vec::with_capacity((data.len() * 4).div_ceil(3));
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| {
+            bits | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..=chunk.len() {
+            text.push(BASE64[(bits >> (18 - 6 * i)) as usize & 63]);
+        }
+    }
+    text
+}
+
+fn decode(text: &[u8]) -> Result<Vec<u8>, Error> {
+    if text.len() % 4 == 1 {
+        return Err(Error::Malformed);
+    }
+    let mut data = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.chunks(4) {
+        let mut bits = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = BASE64
+                .iter()
+                .position(|digit| digit == c)
+                .ok_or(Error::Malformed)?;
+            bits |= (value as u32) << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            data.push((bits >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(data)
+}
+
+fn read_counter(path: &Path) -> Option<u64> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn write_counter(path: &Path, counter: u64) -> std::io::Result<()> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    std::fs::write(path, counter.to_string())
+}
+
+fn nonce(sender: &[u8], counter: &[u8; 8]) -> [u8; aead::NONCE_SIZE] {
+    let mut nonce = [0; aead::NONCE_SIZE];
+    nonce[0..3].copy_from_slice(&sender[0..3]);
+    nonce[4..].copy_from_slice(counter);
+    nonce
+}
+
+// Talker and type, id and recipient
+fn header_length(contents: &[u8]) -> Option<usize> {
+    contents
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| **c == b',')
+        .map(|(i, _)| i)
+        .nth(2)
+}
+
+impl Default for LinkCipher {
+    fn default() -> Self {
+        Self::new(HashMap::new())
+    }
+}
+
+impl LinkCipher {
+    pub fn new(keys: HashMap<Node, Key>) -> Self {
+        let counter_path = crate::ports::settings_path(COUNTER_FILE);
+        if counter_path.is_none() {
+            warn!("No settings directory, the link counter isn't kept");
+        }
+        Self::with_counter_path(keys, counter_path)
+    }
+
+    fn with_counter_path(keys: HashMap<Node, Key>, counter_path: Option<PathBuf>) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_micros() as u64);
+        // Even if the clock went back
+        let counter = counter_path
+            .as_deref()
+            .and_then(read_counter)
+            .map_or(0, |last| last.saturating_add(1))
+            .max(now);
+        Self {
+            keys,
+            negotiated: HashMap::new(),
+            counter,
+            counter_path,
+            seen: HashMap::new(),
+        }
+    }
+
+    pub fn set_keys(&mut self, keys: HashMap<Node, Key>) {
+        self.keys = keys;
+    }
+
+    pub fn has_key(&self, node: Node) -> bool {
+        self.keys.contains_key(&node)
+    }
+
+    // Whether the node reports the capability, if that changed
+    pub fn negotiate(&mut self, node: Node, capabilities: Capabilities) -> Option<bool> {
+        let supported = capabilities.supports(Capabilities::ENCRYPTION);
+        let changed = self.negotiated.insert(node, supported) != Some(supported);
+        changed.then_some(supported)
+    }
+
+    // Whatever the node reported
+    pub fn seals(&self, node: Node) -> bool {
+        self.has_key(node)
+    }
+
+    // Of the sentence contents between `$` and `*`
+    pub fn seal(&mut self, recipient: Node, contents: &[u8]) -> Result<Vec<u8>, Error> {
+        let key = self.keys.get(&recipient).ok_or(Error::NoKey)?;
+        let header = header_length(contents).ok_or(Error::Malformed)?;
+        self.counter += 1;
+        // Before the sentence can go out
+        if let Some(path) = &self.counter_path {
+            if let Err(e) = write_counter(path, self.counter) {
+                warn!("Can't keep the link counter, {}", e);
+            }
+        }
+        let counter = self.counter.to_be_bytes();
+        let mut sealed = contents[..header].to_vec();
+        sealed.push(b',');
+        sealed.extend_from_slice(ENCRYPTED);
+        sealed.push(b',');
+        sealed.extend(encode(&counter));
+        let mut data = contents[header + 1..].to_vec();
+        let tag = aead::seal(key, &nonce(contents, &counter), &sealed, &mut data);
+        data.extend_from_slice(&tag);
+        sealed.push(b',');
+        sealed.extend(encode(&data));
+        if sealed.len() > MAX_CONTENTS {
+            return Err(Error::SentenceTooLong);
+        }
+        Ok(sealed)
+    }
+
+    // The contents as if sent in the clear, None if they weren't sealed
+    pub fn open(&mut self, contents: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        let fields: Vec<&[u8]> = contents.split(|c| *c == b',').collect();
+        if fields.get(3) != Some(&ENCRYPTED) {
+            return Ok(None);
+        }
+        if fields.len() != 6 || fields[0].len() != 6 {
+            return Err(Error::Malformed);
+        }
+        let sender: Node = std::str::from_utf8(&fields[0][0..3])
+            .ok()
+            .and_then(|id| id.parse().ok())
+            .ok_or(Error::Malformed)?;
+        let key = self.keys.get(&sender).ok_or(Error::NoKey)?;
+        let counter: [u8; 8] = decode(fields[4])?
+            .try_into()
+            .map_err(|_| Error::Malformed)?;
+        let mut data = decode(fields[5])?;
+        if data.len() < aead::TAG_SIZE {
+            return Err(Error::Malformed);
+        }
+        let tag: [u8; aead::TAG_SIZE] = data
+            .split_off(data.len() - aead::TAG_SIZE)
+            .try_into()
+            .unwrap();
+        let aad = &contents[..contents.len() - fields[5].len() - 1];
+        if !aead::open(key, &nonce(fields[0], &counter), aad, &mut data, &tag) {
+            return Err(Error::Forged);
+        }
+        let counter = u64::from_be_bytes(counter);
+        if self
+            .seen
+            .get(&sender)
+            .map_or(false, |last| counter <= *last)
+        {
+            return Err(Error::Replayed);
+        }
+        self.seen.insert(sender, counter);
+        let header = fields[0].len() + fields[1].len() + fields[2].len() + 2;
+        let mut opened = contents[..header].to_vec();
+        if !data.is_empty() {
+            opened.push(b',');
+            opened.extend(data);
+        }
+        Ok(Some(opened))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher_keeping(node: Node, counter_path: Option<PathBuf>) -> LinkCipher {
+        let key = core::array::from_fn(|i| 0x80 + i as u8);
+        let mut cipher = LinkCipher::with_counter_path(HashMap::from([(node, key)]), counter_path);
+        cipher.negotiate(node, Capabilities::all());
+        cipher
+    }
+
+    fn cipher(node: Node) -> LinkCipher {
+        cipher_keeping(node, None)
+    }
+
+    // For the firmware, key 808182...9f
+    #[test]
+    fn test_sentence_vector() {
+        let rqa = Node::RedQueen(b'A');
+        let mut cipher = cipher(rqa);
+        cipher.counter = 0x0001020304050606;
+        assert_eq!(
+            cipher.seal(rqa, b"LNCCMD,123,RQA,SECRET_AB,3F,AB"),
+            Ok(
+                b"LNCCMD,123,RQA,ENC,AAECAwQFBgc,hqISV/z+vGq+xPl9eS2fdcNNDLb+zyRNMLGGsyYXwg"
+                    .to_vec()
+            )
+        );
+        assert_eq!(
+            cipher.open(b"RQAACK,123,LNC,ENC,AAAAAAAAACo,oBi64yK7zBmrpsVLmgtrltQooAAw"),
+            Ok(Some(b"RQAACK,123,LNC,3F,AB".to_vec()))
+        );
+        assert_eq!(
+            cipher.open(b"RQAACK,123,LNC,ENC,AAAAAAAAACo,oBi64yK7zBmrpsVLmgtrltQooAAw"),
+            Err(Error::Replayed)
+        );
+        assert_eq!(
+            cipher.open(b"RQAACK,123,LNC,ENC,AAAAAAAAACs,oBi64yK7zBmrpsVLmgtrltQooAAw"),
+            Err(Error::Forged)
+        );
+        assert_eq!(cipher.open(b"RQAACK,123,LNC,3F,AB"), Ok(None));
+    }
+
+    #[test]
+    fn test_counter_increases_across_restarts() {
+        let path = std::env::temp_dir().join(format!("lnc-link-counter-{}", std::process::id()));
+        let rqa = Node::RedQueen(b'A');
+        // Far ahead of the clock
+        write_counter(&path, u64::MAX / 2).unwrap();
+        let mut cipher = cipher_keeping(rqa, Some(path.clone()));
+        cipher.seal(rqa, b"LNCCMD,001,RQA,IGNITION").unwrap();
+        let sent = read_counter(&path).unwrap();
+        assert!(sent > u64::MAX / 2);
+        let restarted = cipher_keeping(rqa, Some(path.clone()));
+        assert!(restarted.counter > sent);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_negotiation() {
+        let rqa = Node::RedQueen(b'A');
+        let rqb = Node::RedQueen(b'B');
+        let mut cipher = cipher(rqa);
+        assert!(cipher.seals(rqa));
+        assert_eq!(cipher.negotiate(rqa, Capabilities::all()), None);
+        assert_eq!(cipher.negotiate(rqb, Capabilities::all()), Some(true));
+        assert!(!cipher.seals(rqb));
+        // Not even a forged VERSION answer turns it off
+        assert_eq!(cipher.negotiate(rqa, Capabilities::legacy()), Some(false));
+        assert!(cipher.seals(rqa));
+        assert_eq!(
+            cipher.seal(rqb, b"LNCCMD,001,RQB,IGNITION"),
+            Err(Error::NoKey)
+        );
+        assert!(is_sealed(&Command::Ignition));
+        assert!(!is_sealed(&Command::Version));
+        assert_eq!(decode(&encode(b"hello")), Ok(b"hello".to_vec()));
+    }
+}
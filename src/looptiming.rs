@@ -0,0 +1,147 @@
+// Where the time of a frame goes, to chase the stutters on the Pi
+// when telemetry bursts arrive. The main loop reports the phases of
+// each frame, the overlay shows them over the last couple of seconds
+// next to the depths of the queues between the threads.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::clock::Instant;
+
+// About two seconds at 60fps
+const WINDOW: usize = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Drive,
+    Telemetry,
+    Render,
+}
+
+pub const PHASES: [Phase; 3] = [Phase::Drive, Phase::Telemetry, Phase::Render];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Sample {
+    // Since the frame before
+    interval: Duration,
+    phases: [Duration; PHASES.len()],
+    packets: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhaseStats {
+    pub mean: Duration,
+    pub max: Duration,
+}
+
+#[derive(Debug, Default)]
+pub struct LoopTiming {
+    pub visible: bool,
+    last_frame: Option<Instant>,
+    current: Sample,
+    samples: VecDeque<Sample>,
+    // Name and depth, as of the last frame
+    pub queues: Vec<(&'static str, usize)>,
+}
+
+impl Phase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Drive => "Drive",
+            Phase::Telemetry => "Telemetry",
+            Phase::Render => "Render",
+        }
+    }
+}
+
+impl LoopTiming {
+    // Closes the frame before
+    pub fn begin_frame(&mut self, now: Instant) {
+        if let Some(last) = self.last_frame {
+            self.current.interval = now.duration_since(last);
+            if self.samples.len() == WINDOW {
+                self.samples.pop_front();
+            }
+            self.samples.push_back(self.current);
+        }
+        self.current = Sample::default();
+        self.last_frame = Some(now);
+    }
+
+    pub fn record(&mut self, phase: Phase, duration: Duration) {
+        self.current.phases[phase as usize] += duration;
+    }
+
+    pub fn count_packets(&mut self, packets: usize) {
+        self.current.packets += packets;
+    }
+
+    pub fn fps(&self) -> Option<f32> {
+        let total: Duration = self.samples.iter().map(|sample| sample.interval).sum();
+        (!total.is_zero()).then(|| self.samples.len() as f32 / total.as_secs_f32())
+    }
+
+    // The longest gap between two frames, what's felt as a stutter
+    pub fn worst_frame(&self) -> Option<Duration> {
+        self.samples.iter().map(|sample| sample.interval).max()
+    }
+
+    pub fn phase(&self, phase: Phase) -> Option<PhaseStats> {
+        let durations = self
+            .samples
+            .iter()
+            .map(|sample| sample.phases[phase as usize]);
+        let max = durations.clone().max()?;
+        Some(PhaseStats {
+            mean: durations.sum::<Duration>() / self.samples.len() as u32,
+            max,
+        })
+    }
+
+    // Of telemetry packets in a single frame
+    pub fn burst(&self) -> usize {
+        self.samples
+            .iter()
+            .map(|sample| sample.packets)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_are_timed() {
+        let start = Instant::now();
+        let mut timing = LoopTiming::default();
+        assert_eq!(timing.fps(), None);
+        assert_eq!(timing.phase(Phase::Drive), None);
+        for frame in 0..4u32 {
+            timing.begin_frame(start + Duration::from_millis(20) * frame);
+            timing.record(Phase::Drive, Duration::from_millis(frame as u64 * 2));
+            timing.record(Phase::Render, Duration::from_millis(5));
+            timing.count_packets(frame as usize * 10);
+        }
+        // A stutter closes the last frame
+        timing.begin_frame(start + Duration::from_millis(160));
+        assert_eq!(timing.fps(), Some(25.0));
+        assert_eq!(timing.worst_frame(), Some(Duration::from_millis(100)));
+        assert_eq!(
+            timing.phase(Phase::Drive),
+            Some(PhaseStats {
+                mean: Duration::from_millis(3),
+                max: Duration::from_millis(6),
+            })
+        );
+        assert_eq!(timing.phase(Phase::Telemetry).unwrap().max, Duration::ZERO);
+        assert_eq!(timing.burst(), 30);
+        for frame in 0..=WINDOW as u32 {
+            timing.begin_frame(
+                start + Duration::from_millis(200) + Duration::from_millis(10) * frame,
+            );
+        }
+        assert_eq!(timing.worst_frame(), Some(Duration::from_millis(10)));
+        assert_eq!(timing.burst(), 0);
+    }
+}
@@ -1,45 +1,115 @@
+use chrono::{DateTime, Utc};
+use crossbeam_channel::Receiver;
 use log::{debug, error};
-#[cfg(test)]
-use mock_instant::Instant;
 use ringbuffer::{AllocRingBuffer, RingBuffer};
-use std::collections::HashMap;
-
-#[cfg(not(test))]
-use std::time::Instant;
+use std::collections::{HashMap, VecDeque};
 
 use std::{cell::RefCell, rc::Rc};
 use std::{
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
     time::Duration,
 };
-
-use crate::args::LaunchMode;
-#[cfg(feature = "test-stand")]
-use crate::observables::rqa as rqobs;
-
-#[cfg(feature = "rocket")]
-use crate::observables::rqb as rqobs;
+use uom::si::{force::kilonewton, pressure::bar};
+
+use crate::airtime::DutyCycleStatus;
+use crate::announcer::{Announcement, Announcer};
+use crate::args::{LaunchMode, Privacy, ProgramArgs};
+use crate::autorecord::{Action as AutoAction, AutoRecord, Trigger};
+use crate::conformance::Conformance;
+use crate::crank::{Crank, CrankTuner, Tick};
+use crate::faults::{Faults, SharedFaults};
+use crate::fsm::{state_name, TransitionLog};
+use crate::i18n::Language;
+use crate::idle::{IdleDetector, IdlePolicy};
+use crate::looptiming::{LoopTiming, Phase};
+use crate::observables::filter::{FilterTuner, Filters};
+use crate::observables::groups::{
+    Breakwire, LaunchDetect, ObservablesGroup2, RawObservablesGroup, RecordingState,
+    SystemDefinition,
+};
+use crate::observables::range::PlotRanges;
+use crate::observables::units::Units;
+use crate::observables::uptime::NodeClock;
+use crate::observables::Profile;
+use crate::onboard::{OnboardRecording, Outcome as OnboardOutcome};
+use crate::ports::{self, PortInfo, PortPicker};
 use crate::rqprotocol::Node;
-use crate::telemetry::parser::rq2::{TelemetryData, TelemetryPacket};
-
-use rqobs::{ObservablesGroup1, ObservablesGroup2, RawObservablesGroup, SystemDefinition};
+use crate::run::RunId;
+use crate::telemetry::parser::rq2::{ImuRanges, TelemetryData, TelemetryPacket};
+use crate::timesource::StepDetector;
 
 use crate::{
-    connection::{Answers, Connection},
-    consort::{Consort, SimpleIdGenerator},
-    input::InputEvent,
-    observables::AdcGain,
+    annotation::{Annotation, Annotator},
+    broadcast::Broadcast,
+    budget::FrameBudget,
+    bus::{EventBus, ModeChange},
+    calibration::ImuCalibration,
+    camera::Snapshots,
+    clock::{Instant, SharedClock},
+    config::{Reload, ReloadOutcome},
+    connection::{Answers, Connection, LinkActivity, Urgent},
+    consort::{Consort, Error as ConsortError, IdPartition, PartitionedIdGenerator},
+    diagnostics::{Diagnostics, FailureCause},
+    digits::{DigitEntry, DigitOutcome},
+    download::Downloader,
+    error::{error_channel, Context, Error, ErrorReporter},
+    failover::{Failover, Radio},
+    freshness::{Freshness, Group, Received, Stale},
+    gpstime::GpsTime,
+    groundpower::GroundPower,
+    input::{Binding, InputEvent, TabNavigation},
+    inputtape::{Playback, TapeRecorder, Taped},
+    journal::{Journal, Marker},
+    layout::scaling::Accessibility,
+    monitor::Monitor,
+    observables::{
+        adc::AdcMonitor,
+        history::Obg1History,
+        reference::{Deviation, ReferenceCurve},
+        tare,
+        tare::Tare,
+        AdcGain,
+    },
+    postmortem::{Bundle, PostMortem, BUNDLE_DIRECTORY, JOURNAL_TAIL},
+    recorder::{RecorderStatus, FILE_TIME_FORMAT},
+    recovery::Recovery,
     rqparser::MAX_BUFFER_SIZE,
-    rqprotocol::{Command, Response},
-    telemetry::NRFConnector,
+    rqprotocol::{Capabilities, Command, ProtocolVersion, Response},
+    safety::SafetyKey,
+    selftest::{check_recorder, Check, SelfTest, Verdict},
+    sequence::{Outcome as SequenceOutcome, Quantity, Sequencer},
+    session::{self, LastSession},
+    sessions::SessionBrowser,
+    shutdown::{self, ShutdownReport, Stage},
+    silence::SilenceCompliance,
+    telemetry::{
+        process_raw_telemetry_data,
+        retention::{Stored, TelemetryStore},
+        roster::{self, ChannelConfig, RosterPage},
+        ModuleStatus, NRFConnector, RawTelemetryPacket, SubscriptionHealth,
+    },
+    timeline::{Series, Timeline},
+    window::{LaunchWindow, LaunchWindowConfig, WindowStatus},
 };
 
-const AUTO_RESET_TIMEOUT: Duration = Duration::from_secs(120);
+pub const AUTO_RESET_TIMEOUT: Duration = Duration::from_secs(120);
+const KEY_ENTRY_TIMEOUT: Duration = Duration::from_secs(30);
+const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(20);
+const TIMELINE_ALIGNMENT_STEP: f64 = 0.01;
+// Pauses between observables polls, watching
+// data warrants a busier channel.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const OBSERVABLES_POLL_INTERVAL: Duration = Duration::from_millis(50);
+// The same error again within this is journaled only once
+const ERROR_REPEAT_INTERVAL: Duration = Duration::from_secs(5);
+// The launch secrets are a hex byte each
+const KEY_DIGITS: usize = 2;
+const KEY_BASE: u8 = 16;
 
 #[derive(Clone)]
 pub struct SharedIdGenerator {
-    command_id_generator: Arc<Mutex<SimpleIdGenerator>>,
+    command_id_generator: Arc<Mutex<PartitionedIdGenerator>>,
 }
 
 impl Iterator for SharedIdGenerator {
@@ -50,19 +120,26 @@ impl Iterator for SharedIdGenerator {
     }
 }
 
-impl Default for SharedIdGenerator {
-    fn default() -> Self {
+impl SharedIdGenerator {
+    pub fn new(partition: IdPartition) -> Self {
         Self {
-            command_id_generator: Default::default(),
+            command_id_generator: Arc::new(Mutex::new(PartitionedIdGenerator::new(partition))),
         }
     }
 }
 
+impl Default for SharedIdGenerator {
+    fn default() -> Self {
+        Self::new(IdPartition::Consort)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CoreConnection {
     Start,
     Failure,
     Reset,
+    Discover,
     Idle,
 }
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -73,64 +150,64 @@ pub enum RFSilenceMode {
     LeaveRadioSilence { progress: u8, last_update: Instant },
 }
 
+// Items the operator has to acknowledge before
+// entering the launch secrets, in this order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreArmCheck {
+    Battery,
+    Pyro12,
+    Pyro34,
+}
+
+pub const PRE_ARM_CHECKS: [PreArmCheck; 3] = [
+    PreArmCheck::Battery,
+    PreArmCheck::Pyro12,
+    PreArmCheck::Pyro34,
+];
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LaunchControlMode {
     Core(CoreConnection),
-    EnterDigitHiA {
-        hi_a: u8,
-    },
-    EnterDigitLoA {
-        hi_a: u8,
-        lo_a: u8,
+    // Number of acknowledged PRE_ARM_CHECKS
+    PreArm {
+        acknowledged: usize,
     },
+    EnterKeyA(DigitEntry),
     TransmitKeyA {
-        hi_a: u8,
-        lo_a: u8,
+        key_a: u8,
     },
     PrepareUnlockPyros {
-        hi_a: u8,
-        lo_a: u8,
+        key_a: u8,
         progress: u8,
         last_update: Instant,
     },
     UnlockPyros {
-        hi_a: u8,
-        lo_a: u8,
+        key_a: u8,
     },
-    EnterDigitHiB {
-        hi_a: u8,
-        lo_a: u8,
-        hi_b: u8,
-    },
-    EnterDigitLoB {
-        hi_a: u8,
-        lo_a: u8,
-        hi_b: u8,
-        lo_b: u8,
+    EnterKeyB {
+        key_a: u8,
+        entry: DigitEntry,
     },
     TransmitKeyAB {
-        hi_a: u8,
-        lo_a: u8,
-        hi_b: u8,
-        lo_b: u8,
+        key_a: u8,
+        key_b: u8,
     },
     PrepareIgnition {
-        hi_a: u8,
-        lo_a: u8,
-        hi_b: u8,
-        lo_b: u8,
+        key_a: u8,
+        key_b: u8,
         progress: u8,
         last_update: Instant,
     },
     WaitForFire {
-        hi_a: u8,
-        lo_a: u8,
-        hi_b: u8,
-        lo_b: u8,
+        key_a: u8,
+        key_b: u8,
     },
     Fire,
     WaitForPyroTimeout(Instant),
     SwitchToObservables,
+    // Waiting for the target to confirm the pyros are locked again
+    SafeAbort,
+    Aborted,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -158,19 +235,140 @@ where
 {
     pub mode: Mode,
     pub control: ControlArea,
+    pub privacy: Privacy,
+    pub accessibility: Accessibility,
+    pub language: Language,
     pub consort: Consort<Id>,
     module: C,
+    clock: SharedClock,
     start: Instant,
     now: Instant,
-    port: String,
+    pub failover: Failover,
     last_state_change: Option<Instant>,
-    pub obg1: Vec<ObservablesGroup1>,
-    pub obg2: Option<ObservablesGroup2>,
+    // Observables per polled node
+    pub obg1: HashMap<Node, Obg1History>,
+    // Of the OBG1 uptime, per node
+    node_clocks: HashMap<Node, NodeClock>,
+    pub obg2: HashMap<Node, ObservablesGroup2>,
+    // Of the Farduinos on the rail
+    pub launch_detect: HashMap<Node, LaunchDetect>,
+    // Into the session, a reset or abort puts a rail exit before it behind
+    launch_dismissed_at: Option<f64>,
+    // When each of them arrived, to tell stale values
+    received: Received,
+    pub freshness: Freshness,
+    // Test stand or rocket, for showing the observables
+    pub profile: Profile,
     pub established_connection_at: Option<Instant>,
     pub adc_gain: AdcGain,
     pub recorder_path: Option<PathBuf>,
+    pub recording: RecorderStatus,
+    // Stamped on everything this run produces
+    pub run: RunId,
+    // Of the telemetry relay we subscribe to
+    relay_run: Option<RunId>,
     nrf_connector: Rc<RefCell<dyn NRFConnector>>,
-    telemetry_data: HashMap<Node, Vec<TelemetryData>>,
+    pub telemetry: TelemetryStore,
+    module_status: HashMap<Node, ModuleStatus>,
+    // Of the frames that couldn't be reassembled, per node
+    malformed_frames: HashMap<Node, usize>,
+    pub diagnostics: Diagnostics,
+    // Set while the connection purges the line
+    pub drain_progress: Option<u8>,
+    // The reset cycle waits for the backoff of the Consort
+    reset_held: bool,
+    protocol_versions: HashMap<Node, ProtocolVersion>,
+    pub tare: Tare,
+    pub self_test: SelfTest,
+    self_test_started: Instant,
+    pub timeline: Timeline,
+    // Expected thrust curve, with the deviation from it
+    // for the ignition it was computed for
+    pub reference: Option<ReferenceCurve>,
+    pub thrust_deviation: Option<(f64, Deviation)>,
+    // Only with a camera, next to the recording if there is one
+    pub snapshots: Option<Snapshots>,
+    pub journal: Journal,
+    // For the subsystems following what happens here
+    pub bus: EventBus,
+    error_reporter: ErrorReporter,
+    errors: Receiver<Error>,
+    last_error: Option<(String, Instant)>,
+    pub monitor: Monitor,
+    // The last known telemetry, for finding the rocket
+    pub recovery: Recovery,
+    pub annotator: Annotator,
+    pub last_session: LastSession,
+    // Of the target this session, for the journal
+    max_thrust_kn: Option<f64>,
+    pub crank: Crank,
+    pub tab_navigation: TabNavigation,
+    last_tab_change: Option<Instant>,
+    pub tuner: CrankTuner,
+    // Of the plotted observables, the history stays raw
+    pub filters: Filters,
+    pub plot_ranges: PlotRanges,
+    // Of the numbers shown
+    pub units: Units,
+    pub filter_tuner: FilterTuner,
+    filters_changed: bool,
+    // Of the IMUs, what their telemetry is scaled with
+    pub calibration: ImuCalibration,
+    // Of the target's load cell and pressure sensor
+    pub adc: AdcMonitor,
+    // Of the telemetry receiver
+    pub roster: Vec<ChannelConfig>,
+    pub roster_page: RosterPage,
+    // Of the E32
+    pub port_picker: PortPicker,
+    pub transitions: TransitionLog,
+    // Shared with the fault injecting connection, if any
+    pub faults: SharedFaults,
+    pub downloader: Downloader,
+    pub onboard_recording: OnboardRecording,
+    pub auto_record: AutoRecord,
+    pub sequencer: Sequencer,
+    pub broadcast: Broadcast,
+    pub ground_power: GroundPower,
+    pub safety_key: SafetyKey,
+    // Key AB and the ignition only go out while it's open
+    launch_window: Option<LaunchWindow>,
+    // Disciplines the wall clock, if gpsd is configured
+    pub gps_time: Option<GpsTime>,
+    // Outcome of the last config file change
+    pub reload: Option<ReloadOutcome>,
+    pub help: bool,
+    budget: FrameBudget,
+    // Telemetry that didn't fit the budget of an earlier frame, with
+    // the elapsed time it was received at. Input isn't queued behind
+    // it, an abort mustn't wait.
+    backlog: VecDeque<(Duration, TelemetryPacket)>,
+    pub loop_timing: LoopTiming,
+    // Standby while nothing happens, to spare the battery
+    pub idle: IdleDetector,
+    pub announcer: Announcer,
+    // Until the frontend speaks them
+    announcements: Vec<Announcement>,
+    clock_steps: StepDetector,
+    // Of the nodes reporting radio silence in their telemetry
+    pub silence: SilenceCompliance,
+    // Of the journals next to the recording
+    pub sessions: SessionBrowser,
+    // Where they are exported to, e.g. a stick
+    pub export_directory: Option<PathBuf>,
+    // Of the wire format, surfaced under --strict
+    pub conformance: Conformance,
+    key_entry_timeout: Duration,
+    // Of the key being entered
+    key_entry_since: Option<Instant>,
+    // For the diagnostic bundle of a failure
+    pub postmortem: PostMortem,
+    // Of the bundles without a recording
+    pub bundle_directory: PathBuf,
+    // Driven since the start, the input events are taped with it
+    frame: u64,
+    tape: Option<TapeRecorder>,
+    pub playback: Option<Playback>,
 }
 
 impl CoreConnection {
@@ -192,6 +390,14 @@ impl CoreConnection {
         match self {
             CoreConnection::Start => true,
             CoreConnection::Reset => true,
+            CoreConnection::Discover => true,
+            _ => false,
+        }
+    }
+
+    fn is_discover(&self) -> bool {
+        match self {
+            CoreConnection::Discover => true,
             _ => false,
         }
     }
@@ -201,6 +407,7 @@ impl CoreConnection {
             Self::Start => "Start",
             Self::Failure => "Failure",
             Self::Reset => "Reset",
+            Self::Discover => "Discover",
             Self::Idle => "Idle",
         }
     }
@@ -208,7 +415,14 @@ impl CoreConnection {
         match self {
             Self::Reset => match response {
                 Response::ResetAck => {
-                    debug!("Acknowledged Reset, go to Idle");
+                    debug!("Acknowledged Reset, go to Discover");
+                    Self::Discover
+                }
+                _ => Self::Start,
+            },
+            Self::Discover => match response {
+                Response::VersionAck(version) => {
+                    debug!("Node reported {:?}, go to Idle", version);
                     Self::Idle
                 }
                 _ => Self::Start,
@@ -232,7 +446,12 @@ pub trait StateProcessing {
 
     fn core_mode(&self) -> CoreConnection;
 
-    fn process_event(&self, event: &InputEvent) -> (Self::State, ControlArea);
+    fn process_event(
+        &self,
+        event: &InputEvent,
+        now: Instant,
+        crank: &Crank,
+    ) -> (Self::State, ControlArea);
 
     // Invoked when the mode has changed
     // to send a command to the RQ
@@ -240,11 +459,11 @@ pub trait StateProcessing {
 
     // Invoked with the response to a sent command
     // to progress the state machine.
-    fn process_response(&self, response: Response) -> Self::State;
+    fn process_response(&self, response: Response, now: Instant) -> Self::State;
 
     // Invoked unconditionally and allows state changes
     // dependent on time
-    fn drive(&self) -> Self::State;
+    fn drive(&self, tick: Tick, crank: &Crank) -> Self::State;
 
     fn affected_by_timeout(&self) -> bool;
 
@@ -254,52 +473,49 @@ pub trait StateProcessing {
 
     fn reset_ongoing(&self) -> bool;
 
-    fn is_radio_silence(&self) -> bool;
+    // What the connection may send on its own
+    fn link_activity(&self) -> LinkActivity;
 }
 
 impl StateProcessing for LaunchControlMode {
     type State = LaunchControlMode;
 
-    fn process_response(&self, response: Response) -> Self::State {
+    fn process_response(&self, response: Response, now: Instant) -> Self::State {
         match self {
             Self::Core(core_mode) => Self::Core(core_mode.process_response(response)),
-            Self::TransmitKeyA { hi_a, lo_a } => match response {
+            Self::TransmitKeyA { key_a } => match response {
                 Response::LaunchSecretPartialAck => Self::PrepareUnlockPyros {
-                    hi_a: *hi_a,
-                    lo_a: *lo_a,
+                    key_a: *key_a,
                     progress: 0,
-                    last_update: Instant::now(),
+                    last_update: now,
                 },
                 _ => Self::State::Core(CoreConnection::Start),
             },
-            Self::UnlockPyros { hi_a, lo_a, .. } => match response {
-                Response::UnlockPyrosAck => Self::State::EnterDigitHiB {
-                    hi_a: *hi_a,
-                    lo_a: *lo_a,
-                    hi_b: 0,
+            Self::UnlockPyros { key_a } => match response {
+                Response::UnlockPyrosAck => Self::State::EnterKeyB {
+                    key_a: *key_a,
+                    entry: DigitEntry::new(KEY_DIGITS, KEY_BASE),
                 },
                 _ => Self::Core(CoreConnection::Start),
             },
-            Self::TransmitKeyAB {
-                hi_a,
-                lo_a,
-                hi_b,
-                lo_b,
-            } => match response {
+            Self::TransmitKeyAB { key_a, key_b } => match response {
                 Response::LaunchSecretFullAck => Self::State::PrepareIgnition {
-                    hi_a: *hi_a,
-                    lo_a: *lo_a,
-                    hi_b: *hi_b,
-                    lo_b: *lo_b,
+                    key_a: *key_a,
+                    key_b: *key_b,
                     progress: 0,
-                    last_update: Instant::now(),
+                    last_update: now,
                 },
                 _ => Self::Core(CoreConnection::Start),
             },
             Self::State::Fire => match response {
-                Response::IgnitionAck => Self::State::WaitForPyroTimeout(Instant::now()),
+                Response::IgnitionAck => Self::State::WaitForPyroTimeout(now),
                 _ => Self::Core(CoreConnection::Start),
             },
+            // Sent again until acknowledged, see Model::resend_abort
+            Self::State::SafeAbort => match response {
+                Response::AbortAck => Self::State::Aborted,
+                _ => *self,
+            },
             _ => *self,
         }
     }
@@ -307,68 +523,66 @@ impl StateProcessing for LaunchControlMode {
     fn name(&self) -> &str {
         match self {
             Self::State::Core(core) => core.name(),
-            Self::State::EnterDigitHiA { .. } => "Enter Hi A",
-            Self::State::EnterDigitLoA { .. } => "Enter Lo A",
+            Self::State::PreArm { .. } => "Pre-Arm Checklist",
+            Self::State::EnterKeyA(entry) => match entry.position() {
+                0 => "Enter Hi A",
+                _ => "Enter Lo A",
+            },
             Self::State::PrepareUnlockPyros { .. } => "Prepare Unlock Pyros",
             Self::State::UnlockPyros { .. } => "Unlocking Pyros",
             Self::State::TransmitKeyA { .. } => "Transmitting Key A",
-            Self::State::EnterDigitHiB { .. } => "Enter Hi B",
-            Self::State::EnterDigitLoB { .. } => "Enter Lo B",
+            Self::State::EnterKeyB { entry, .. } => match entry.position() {
+                0 => "Enter Hi B",
+                _ => "Enter Lo B",
+            },
             Self::State::TransmitKeyAB { .. } => "Transmitting Key AB",
             Self::State::PrepareIgnition { .. } => "Prepare Ignition",
             Self::State::WaitForFire { .. } => "Wait for Fire",
             Self::State::Fire => "Fire!",
             Self::State::WaitForPyroTimeout { .. } => "Pyros ignited",
             Self::State::SwitchToObservables => "",
+            Self::State::SafeAbort => "Safe Abort",
+            Self::State::Aborted => "Aborted",
         }
     }
 
-    fn process_event(&self, event: &InputEvent) -> (Self::State, ControlArea) {
+    fn process_event(
+        &self,
+        event: &InputEvent,
+        now: Instant,
+        crank: &Crank,
+    ) -> (Self::State, ControlArea) {
         match self {
             LaunchControlMode::Core(CoreConnection::Idle) => self.process_event_idle(event),
-            LaunchControlMode::EnterDigitHiA { hi_a } => {
-                self.process_event_enter_higit_hi_a(event, *hi_a)
+            LaunchControlMode::PreArm { acknowledged } => {
+                self.process_event_pre_arm(event, *acknowledged)
             }
-            LaunchControlMode::EnterDigitLoA { hi_a, lo_a } => {
-                self.process_event_enter_higit_lo_a(event, *hi_a, *lo_a)
+            LaunchControlMode::EnterKeyA(entry) => self.process_enter_key_a(event, entry),
+            LaunchControlMode::EnterKeyB { key_a, entry } => {
+                self.process_enter_key_b(event, *key_a, entry)
             }
-            LaunchControlMode::EnterDigitHiB { hi_a, lo_a, hi_b } => {
-                self.process_event_enter_higit_hi_b(event, *hi_a, *lo_a, *hi_b)
-            }
-            LaunchControlMode::EnterDigitLoB {
-                hi_a,
-                lo_a,
-                hi_b,
-                lo_b,
-            } => self.process_event_enter_higit_lo_b(event, *hi_a, *lo_a, *hi_b, *lo_b),
             LaunchControlMode::PrepareIgnition {
-                hi_a,
-                lo_a,
-                hi_b,
-                lo_b,
+                key_a,
+                key_b,
                 progress,
                 last_update,
             } => self.process_prepare_ignition(
                 event,
-                *hi_a,
-                *lo_a,
-                *hi_b,
-                *lo_b,
+                *key_a,
+                *key_b,
                 *progress,
                 *last_update,
+                now,
+                crank,
             ),
             LaunchControlMode::PrepareUnlockPyros {
-                hi_a,
-                lo_a,
+                key_a,
                 progress,
                 last_update,
-            } => self.process_unlock_pyros(event, *hi_a, *lo_a, *progress, *last_update),
-            LaunchControlMode::WaitForFire {
-                hi_a,
-                lo_a,
-                hi_b,
-                lo_b,
-            } => self.process_fire(event, *hi_a, *lo_a, *hi_b, *lo_b),
+            } => self.process_unlock_pyros(event, *key_a, *progress, *last_update, now, crank),
+            LaunchControlMode::WaitForFire { key_a, key_b } => {
+                self.process_fire(event, *key_a, *key_b)
+            }
             // only left through a response
             LaunchControlMode::TransmitKeyA { .. } => (*self, ControlArea::Details),
             // only left through a response
@@ -377,76 +591,56 @@ impl StateProcessing for LaunchControlMode {
             LaunchControlMode::Fire => (*self, ControlArea::Details),
             // only left through a response
             LaunchControlMode::UnlockPyros { .. } => (*self, ControlArea::Details),
+            // only left through a response
+            LaunchControlMode::SafeAbort => (*self, ControlArea::Details),
+            LaunchControlMode::Aborted => match event {
+                InputEvent::Enter | InputEvent::Back => (
+                    LaunchControlMode::Core(CoreConnection::Start),
+                    ControlArea::Tabs,
+                ),
+                _ => (*self, ControlArea::Details),
+            },
             _ => self.process_event_nop(event),
         }
     }
 
     fn process_mode_change(&self) -> Option<Command> {
         match self {
-            LaunchControlMode::TransmitKeyA { hi_a, lo_a } => {
-                Some(Command::LaunchSecretPartial(hi_a << 4 | lo_a))
-            }
-            LaunchControlMode::TransmitKeyAB {
-                hi_a,
-                lo_a,
-                hi_b,
-                lo_b,
-            } => Some(Command::LaunchSecretFull(
-                hi_a << 4 | lo_a,
-                hi_b << 4 | lo_b,
-            )),
+            LaunchControlMode::TransmitKeyA { key_a } => Some(Command::LaunchSecretPartial(*key_a)),
+            LaunchControlMode::TransmitKeyAB { key_a, key_b } => {
+                Some(Command::LaunchSecretFull(*key_a, *key_b))
+            }
             LaunchControlMode::Fire => Some(Command::Ignition),
             LaunchControlMode::UnlockPyros { .. } => Some(Command::UnlockPyros),
+            LaunchControlMode::SafeAbort => Some(Command::Abort),
             _ => None,
         }
     }
 
-    fn drive(&self) -> Self {
+    fn drive(&self, tick: Tick, crank: &Crank) -> Self {
         match self {
             LaunchControlMode::PrepareIgnition {
-                hi_a,
-                lo_a,
-                hi_b,
-                lo_b,
+                key_a,
+                key_b,
                 progress,
                 last_update,
             } => LaunchControlMode::PrepareIgnition {
-                hi_a: *hi_a,
-                lo_a: *lo_a,
-                hi_b: *hi_b,
-                lo_b: *lo_b,
-                progress: if *progress < 100 {
-                    if last_update.elapsed() > Duration::from_millis(500) {
-                        std::cmp::max(*progress, 1) - 1
-                    } else {
-                        *progress
-                    }
-                } else {
-                    100
-                },
+                key_a: *key_a,
+                key_b: *key_b,
+                progress: crank.decay(*progress, *last_update, tick),
                 last_update: *last_update,
             },
             LaunchControlMode::PrepareUnlockPyros {
-                hi_a,
-                lo_a,
+                key_a,
                 progress,
                 last_update,
             } => LaunchControlMode::PrepareUnlockPyros {
-                hi_a: *hi_a,
-                lo_a: *lo_a,
-                progress: if *progress < 100 {
-                    if last_update.elapsed() > Duration::from_millis(500) {
-                        std::cmp::max(*progress, 1) - 1
-                    } else {
-                        *progress
-                    }
-                } else {
-                    100
-                },
+                key_a: *key_a,
+                progress: crank.decay(*progress, *last_update, tick),
                 last_update: *last_update,
             },
             LaunchControlMode::WaitForPyroTimeout(timeout) => {
-                if timeout.elapsed() > Duration::from_secs(3) {
+                if tick.now.duration_since(*timeout) > Duration::from_secs(3) {
                     LaunchControlMode::SwitchToObservables
                 } else {
                     *self
@@ -462,6 +656,8 @@ impl StateProcessing for LaunchControlMode {
                 CoreConnection::Idle => true,
                 _ => false,
             },
+            // Never given up on
+            LaunchControlMode::SafeAbort => true,
             _ => false,
         };
         !self.reset_ongoing() && !idle
@@ -486,8 +682,8 @@ impl StateProcessing for LaunchControlMode {
         self.core_mode().reset_ongoing()
     }
 
-    fn is_radio_silence(&self) -> bool {
-        false
+    fn link_activity(&self) -> LinkActivity {
+        LinkActivity::Polling(POLL_INTERVAL)
     }
 }
 
@@ -501,7 +697,7 @@ impl StateProcessing for ObservablesMode {
         }
     }
 
-    fn process_response(&self, response: Response) -> Self::State {
+    fn process_response(&self, response: Response, _now: Instant) -> Self::State {
         match self {
             ObservablesMode::Core(core) => ObservablesMode::Core(core.process_response(response)),
             _ => *self,
@@ -515,7 +711,12 @@ impl StateProcessing for ObservablesMode {
         }
     }
 
-    fn process_event(&self, event: &InputEvent) -> (Self::State, ControlArea) {
+    fn process_event(
+        &self,
+        event: &InputEvent,
+        _now: Instant,
+        _crank: &Crank,
+    ) -> (Self::State, ControlArea) {
         match event {
             InputEvent::Back => (*self, ControlArea::Tabs),
             _ => (*self, ControlArea::Details),
@@ -526,7 +727,7 @@ impl StateProcessing for ObservablesMode {
         None
     }
 
-    fn drive(&self) -> Self {
+    fn drive(&self, _tick: Tick, _crank: &Crank) -> Self {
         *self
     }
 
@@ -546,8 +747,8 @@ impl StateProcessing for ObservablesMode {
         self.core_mode().reset_ongoing()
     }
 
-    fn is_radio_silence(&self) -> bool {
-        false
+    fn link_activity(&self) -> LinkActivity {
+        LinkActivity::Polling(OBSERVABLES_POLL_INTERVAL)
     }
 }
 
@@ -563,7 +764,12 @@ impl StateProcessing for RFSilenceMode {
         }
     }
 
-    fn process_event(&self, event: &InputEvent) -> (Self::State, ControlArea) {
+    fn process_event(
+        &self,
+        event: &InputEvent,
+        now: Instant,
+        crank: &Crank,
+    ) -> (Self::State, ControlArea) {
         match self {
             RFSilenceMode::Core(_) => match event {
                 InputEvent::Back => (Self::Core(CoreConnection::Start), ControlArea::Tabs),
@@ -578,8 +784,8 @@ impl StateProcessing for RFSilenceMode {
             RFSilenceMode::LeaveRadioSilence { progress, .. } => match event {
                 InputEvent::Right(_) => (
                     RFSilenceMode::LeaveRadioSilence {
-                        progress: std::cmp::min(progress + 3, 100),
-                        last_update: Instant::now(),
+                        progress: crank.advance(*progress),
+                        last_update: now,
                     },
                     ControlArea::Details,
                 ),
@@ -596,13 +802,13 @@ impl StateProcessing for RFSilenceMode {
         }
     }
 
-    fn process_response(&self, response: Response) -> Self::State {
+    fn process_response(&self, response: Response, now: Instant) -> Self::State {
         match self {
             Self::Core(core) => Self::Core(core.process_response(response)),
             RFSilenceMode::SendRFSilenceCommand => match response {
                 Response::RFSilenceAck => Self::LeaveRadioSilence {
                     progress: 0,
-                    last_update: Instant::now(),
+                    last_update: now,
                 },
                 _ => Self::State::Core(CoreConnection::Start),
             },
@@ -610,7 +816,7 @@ impl StateProcessing for RFSilenceMode {
         }
     }
 
-    fn drive(&self) -> Self::State {
+    fn drive(&self, tick: Tick, crank: &Crank) -> Self::State {
         match self {
             RFSilenceMode::LeaveRadioSilence {
                 progress,
@@ -619,11 +825,7 @@ impl StateProcessing for RFSilenceMode {
                 100 => RFSilenceMode::Core(CoreConnection::Start),
                 _ => RFSilenceMode::LeaveRadioSilence {
                     last_update: *last_update,
-                    progress: if last_update.elapsed() > Duration::from_millis(500) {
-                        std::cmp::max(*progress, 1) - 1
-                    } else {
-                        *progress
-                    },
+                    progress: crank.decay(*progress, *last_update, tick),
                 },
             },
             _ => *self,
@@ -653,10 +855,10 @@ impl StateProcessing for RFSilenceMode {
         self.core_mode().reset_ongoing()
     }
 
-    fn is_radio_silence(&self) -> bool {
+    fn link_activity(&self) -> LinkActivity {
         match self {
-            RFSilenceMode::LeaveRadioSilence { .. } => true,
-            _ => false,
+            RFSilenceMode::LeaveRadioSilence { .. } => LinkActivity::Silent,
+            _ => LinkActivity::Polling(POLL_INTERVAL),
         }
     }
 }
@@ -682,11 +884,13 @@ impl Default for RFSilenceMode {
 impl StateProcessing for Mode {
     type State = Mode;
 
-    fn process_response(&self, response: Response) -> Self::State {
+    fn process_response(&self, response: Response, now: Instant) -> Self::State {
         match self {
-            Mode::Observables(state) => Mode::Observables(state.process_response(response)),
-            Mode::LaunchControl(state) => Mode::LaunchControl(state.process_response(response)),
-            Mode::RFSilence(state) => Mode::RFSilence(state.process_response(response)),
+            Mode::Observables(state) => Mode::Observables(state.process_response(response, now)),
+            Mode::LaunchControl(state) => {
+                Mode::LaunchControl(state.process_response(response, now))
+            }
+            Mode::RFSilence(state) => Mode::RFSilence(state.process_response(response, now)),
         }
     }
 
@@ -698,24 +902,33 @@ impl StateProcessing for Mode {
         }
     }
 
-    fn process_event(&self, event: &InputEvent) -> (Self::State, ControlArea) {
+    fn process_event(
+        &self,
+        event: &InputEvent,
+        now: Instant,
+        crank: &Crank,
+    ) -> (Self::State, ControlArea) {
         match self {
             Mode::Observables(state) => {
-                let (state, ca) = state.process_event(event);
+                let (state, ca) = state.process_event(event, now, crank);
                 (Mode::Observables(state), ca)
             }
             Mode::LaunchControl(state) => {
-                let (state, ca) = state.process_event(event);
+                let (state, ca) = state.process_event(event, now, crank);
                 (Mode::LaunchControl(state), ca)
             }
             Mode::RFSilence(state) => {
-                let (state, ca) = state.process_event(event);
+                let (state, ca) = state.process_event(event, now, crank);
                 (Mode::RFSilence(state), ca)
             }
         }
     }
 
     fn process_mode_change(&self) -> Option<Command> {
+        // Right after a reset, ask the node what it supports
+        if self.core_mode().is_discover() {
+            return Some(Command::Version);
+        }
         match self {
             Mode::LaunchControl(state) => state.process_mode_change(),
             Mode::Observables(state) => state.process_mode_change(),
@@ -723,11 +936,11 @@ impl StateProcessing for Mode {
         }
     }
 
-    fn drive(&self) -> Self {
+    fn drive(&self, tick: Tick, crank: &Crank) -> Self {
         let mut mode = match self {
-            Mode::LaunchControl(state) => Mode::LaunchControl(state.drive()),
-            Mode::Observables(state) => Mode::Observables(state.drive()),
-            Mode::RFSilence(state) => Mode::RFSilence(state.drive()),
+            Mode::LaunchControl(state) => Mode::LaunchControl(state.drive(tick, crank)),
+            Mode::Observables(state) => Mode::Observables(state.drive(tick, crank)),
+            Mode::RFSilence(state) => Mode::RFSilence(state.drive(tick, crank)),
         };
         if let Mode::LaunchControl(LaunchControlMode::SwitchToObservables) = mode {
             mode = Mode::Observables(ObservablesMode::Core(CoreConnection::Start))
@@ -771,11 +984,11 @@ impl StateProcessing for Mode {
         self.core_mode().reset_ongoing()
     }
 
-    fn is_radio_silence(&self) -> bool {
+    fn link_activity(&self) -> LinkActivity {
         match self {
-            Mode::Observables(state) => state.is_radio_silence(),
-            Mode::LaunchControl(state) => state.is_radio_silence(),
-            Mode::RFSilence(state) => state.is_radio_silence(),
+            Mode::Observables(state) => state.link_activity(),
+            Mode::LaunchControl(state) => state.link_activity(),
+            Mode::RFSilence(state) => state.link_activity(),
         }
     }
 }
@@ -796,53 +1009,62 @@ impl RFSilenceMode {
     }
 }
 impl LaunchControlMode {
-    pub fn digits(&self) -> (u8, u8, u8, u8) {
+    // Of the key being entered
+    pub fn key_in_entry(&self) -> Option<char> {
         match self {
-            LaunchControlMode::Core(_) => (0, 0, 0, 0),
-            LaunchControlMode::EnterDigitHiA { hi_a } => (*hi_a, 0, 0, 0),
-            LaunchControlMode::EnterDigitLoA { hi_a, lo_a } => (*hi_a, *lo_a, 0, 0),
-            LaunchControlMode::PrepareUnlockPyros { hi_a, lo_a, .. } => (*hi_a, *lo_a, 0, 0),
-            LaunchControlMode::UnlockPyros { hi_a, lo_a, .. } => (*hi_a, *lo_a, 0, 0),
-            LaunchControlMode::TransmitKeyA { hi_a, lo_a } => (*hi_a, *lo_a, 0, 0),
-            LaunchControlMode::EnterDigitHiB { hi_a, lo_a, hi_b } => (*hi_a, *lo_a, *hi_b, 0),
-            LaunchControlMode::EnterDigitLoB {
-                hi_a,
-                lo_a,
-                hi_b,
-                lo_b,
-            } => (*hi_a, *lo_a, *hi_b, *lo_b),
-            LaunchControlMode::TransmitKeyAB {
-                hi_a,
-                lo_a,
-                hi_b,
-                lo_b,
-            } => (*hi_a, *lo_a, *hi_b, *lo_b),
-            LaunchControlMode::PrepareIgnition {
-                hi_a,
-                lo_a,
-                hi_b,
-                lo_b,
-                ..
-            } => (*hi_a, *lo_a, *hi_b, *lo_b),
-            LaunchControlMode::WaitForFire {
-                hi_a,
-                lo_a,
-                hi_b,
-                lo_b,
-            } => (*hi_a, *lo_a, *hi_b, *lo_b),
-            LaunchControlMode::Fire => (0, 0, 0, 0),
-            LaunchControlMode::WaitForPyroTimeout(_) => (0, 0, 0, 0),
-            LaunchControlMode::SwitchToObservables => (0, 0, 0, 0),
-        }
-    }
-
-    pub fn highlights(&self) -> (bool, bool, bool, bool) {
+            LaunchControlMode::EnterKeyA(_) => Some('A'),
+            LaunchControlMode::EnterKeyB { .. } => Some('B'),
+            _ => None,
+        }
+    }
+
+    // Key A and B as far as entered, and if being entered
+    pub fn key_entries(&self) -> [(DigitEntry, bool); 2] {
+        let entered = |key: u8| {
+            (
+                DigitEntry::with_value(KEY_DIGITS, KEY_BASE, key as u32),
+                false,
+            )
+        };
+        let blank = (DigitEntry::new(KEY_DIGITS, KEY_BASE), false);
+        match self {
+            LaunchControlMode::EnterKeyA(entry) => [(*entry, true), blank],
+            LaunchControlMode::TransmitKeyA { key_a }
+            | LaunchControlMode::PrepareUnlockPyros { key_a, .. }
+            | LaunchControlMode::UnlockPyros { key_a } => [entered(*key_a), blank],
+            LaunchControlMode::EnterKeyB { key_a, entry } => [entered(*key_a), (*entry, true)],
+            LaunchControlMode::TransmitKeyAB { key_a, key_b }
+            | LaunchControlMode::PrepareIgnition { key_a, key_b, .. }
+            | LaunchControlMode::WaitForFire { key_a, key_b } => [entered(*key_a), entered(*key_b)],
+            _ => [blank, blank],
+        }
+    }
+
+    // Everything past the idle screen, until the abort is underway
+    pub fn abortable(&self) -> bool {
+        match self {
+            LaunchControlMode::Core(_)
+            | LaunchControlMode::SwitchToObservables
+            | LaunchControlMode::SafeAbort
+            | LaunchControlMode::Aborted => false,
+            _ => true,
+        }
+    }
+
+    // When the launch window closes or a hold begins during the
+    // ignition count, it's counted up again once the window allows
+    pub fn auto_hold(&self, now: Instant) -> Option<LaunchControlMode> {
         match self {
-            LaunchControlMode::EnterDigitHiA { .. } => (true, false, false, false),
-            LaunchControlMode::EnterDigitLoA { .. } => (false, true, false, false),
-            LaunchControlMode::EnterDigitHiB { .. } => (false, false, true, false),
-            LaunchControlMode::EnterDigitLoB { .. } => (false, false, false, true),
-            _ => (false, false, false, false),
+            LaunchControlMode::PrepareIgnition { key_a, key_b, .. }
+            | LaunchControlMode::WaitForFire { key_a, key_b } => {
+                Some(LaunchControlMode::PrepareIgnition {
+                    key_a: *key_a,
+                    key_b: *key_b,
+                    progress: 0,
+                    last_update: now,
+                })
+            }
+            _ => None,
         }
     }
 
@@ -858,10 +1080,11 @@ impl LaunchControlMode {
     pub fn unlock_pyros_progress(&self) -> f32 {
         let p = match self {
             LaunchControlMode::Core(_) => 0,
-            LaunchControlMode::EnterDigitHiA { .. } => 0,
-            LaunchControlMode::EnterDigitLoA { .. } => 0,
+            LaunchControlMode::PreArm { .. } => 0,
+            LaunchControlMode::EnterKeyA(_) => 0,
             LaunchControlMode::TransmitKeyA { .. } => 0,
             LaunchControlMode::PrepareUnlockPyros { progress, .. } => *progress,
+            LaunchControlMode::SafeAbort | LaunchControlMode::Aborted => 0,
             _ => 100,
         };
         p as f32 / 100.0
@@ -875,19 +1098,26 @@ impl LaunchControlMode {
     fn process_event_idle(&self, event: &InputEvent) -> (Self, ControlArea) {
         match event {
             InputEvent::Enter => (
-                LaunchControlMode::EnterDigitHiA { hi_a: 0 },
+                LaunchControlMode::PreArm { acknowledged: 0 },
                 ControlArea::Details,
             ),
             _ => self.process_event_nop(event),
         }
     }
 
-    fn process_event_enter_higit_hi_a(&self, event: &InputEvent, digit: u8) -> (Self, ControlArea) {
+    fn process_event_pre_arm(
+        &self,
+        event: &InputEvent,
+        acknowledged: usize,
+    ) -> (Self, ControlArea) {
         match event {
+            InputEvent::Enter if acknowledged + 1 >= PRE_ARM_CHECKS.len() => (
+                LaunchControlMode::EnterKeyA(DigitEntry::new(KEY_DIGITS, KEY_BASE)),
+                ControlArea::Details,
+            ),
             InputEvent::Enter => (
-                LaunchControlMode::EnterDigitLoA {
-                    hi_a: digit,
-                    lo_a: 0,
+                LaunchControlMode::PreArm {
+                    acknowledged: acknowledged + 1,
                 },
                 ControlArea::Details,
             ),
@@ -895,161 +1125,74 @@ impl LaunchControlMode {
                 LaunchControlMode::Core(CoreConnection::Start),
                 ControlArea::Tabs,
             ),
-            InputEvent::Right(_) => (
-                LaunchControlMode::EnterDigitHiA {
-                    hi_a: (digit + 1) % 16,
-                },
-                ControlArea::Details,
-            ),
-            InputEvent::Left(_) => (
-                LaunchControlMode::EnterDigitHiA {
-                    hi_a: (16 + digit - 1) % 16,
-                },
-                ControlArea::Details,
-            ),
-            InputEvent::Send => self.process_event_nop(event),
+            _ => (*self, ControlArea::Details),
         }
     }
 
-    fn process_event_enter_higit_lo_a(
-        &self,
-        event: &InputEvent,
-        hi_a: u8,
-        lo_a: u8,
-    ) -> (Self, ControlArea) {
-        match event {
-            InputEvent::Enter => (
-                LaunchControlMode::TransmitKeyA { hi_a, lo_a },
-                ControlArea::Details,
-            ),
-            // Back to high digit!
-            InputEvent::Back => (
-                LaunchControlMode::EnterDigitHiA { hi_a },
-                ControlArea::Details,
-            ),
-            InputEvent::Right(_) => (
-                LaunchControlMode::EnterDigitLoA {
-                    hi_a,
-                    lo_a: (lo_a + 1) % 16,
-                },
-                ControlArea::Details,
-            ),
-            InputEvent::Left(_) => (
-                LaunchControlMode::EnterDigitLoA {
-                    hi_a,
-                    lo_a: (16 + lo_a - 1) % 16,
-                },
-                ControlArea::Details,
-            ),
-            InputEvent::Send => self.process_event_nop(event),
+    // The check currently awaiting acknowledgement
+    pub fn pre_arm_check(&self) -> Option<PreArmCheck> {
+        match self {
+            LaunchControlMode::PreArm { acknowledged } => {
+                PRE_ARM_CHECKS.get(*acknowledged).copied()
+            }
+            _ => None,
         }
     }
 
-    fn process_event_enter_higit_hi_b(
-        &self,
-        event: &InputEvent,
-        hi_a: u8,
-        lo_a: u8,
-        hi_b: u8,
-    ) -> (Self, ControlArea) {
-        match event {
-            InputEvent::Enter => (
-                LaunchControlMode::EnterDigitLoB {
-                    hi_a,
-                    lo_a,
-                    hi_b,
-                    lo_b: 0,
-                },
+    fn process_enter_key_a(&self, event: &InputEvent, entry: &DigitEntry) -> (Self, ControlArea) {
+        match entry.process_event(event) {
+            DigitOutcome::Editing(entry) => {
+                (LaunchControlMode::EnterKeyA(entry), ControlArea::Details)
+            }
+            DigitOutcome::Entered(key_a) => (
+                LaunchControlMode::TransmitKeyA { key_a: key_a as u8 },
                 ControlArea::Details,
             ),
-            InputEvent::Back => (
+            DigitOutcome::Cancelled => (
                 LaunchControlMode::Core(CoreConnection::Start),
                 ControlArea::Tabs,
             ),
-            InputEvent::Right(_) => (
-                LaunchControlMode::EnterDigitHiB {
-                    hi_a,
-                    lo_a,
-                    hi_b: (hi_b + 1) % 16,
-                },
-                ControlArea::Details,
-            ),
-            InputEvent::Left(_) => (
-                LaunchControlMode::EnterDigitHiB {
-                    hi_a,
-                    lo_a,
-                    hi_b: (16 + hi_b - 1) % 16,
-                },
-                ControlArea::Details,
-            ),
-            InputEvent::Send => self.process_event_nop(event),
         }
     }
 
-    fn process_event_enter_higit_lo_b(
+    fn process_enter_key_b(
         &self,
         event: &InputEvent,
-        hi_a: u8,
-        lo_a: u8,
-        hi_b: u8,
-        lo_b: u8,
+        key_a: u8,
+        entry: &DigitEntry,
     ) -> (Self, ControlArea) {
-        match event {
-            InputEvent::Enter => (
-                LaunchControlMode::TransmitKeyAB {
-                    hi_a,
-                    lo_a,
-                    hi_b,
-                    lo_b,
-                },
-                ControlArea::Details,
-            ),
-            // Back to the high digit!
-            InputEvent::Back => (
-                LaunchControlMode::EnterDigitHiB { hi_a, lo_a, hi_b },
+        match entry.process_event(event) {
+            DigitOutcome::Editing(entry) => (
+                LaunchControlMode::EnterKeyB { key_a, entry },
                 ControlArea::Details,
             ),
-            InputEvent::Right(_) => (
-                LaunchControlMode::EnterDigitLoB {
-                    hi_a,
-                    lo_a,
-                    hi_b,
-                    lo_b: (lo_b + 1) % 16,
+            DigitOutcome::Entered(key_b) => (
+                LaunchControlMode::TransmitKeyAB {
+                    key_a,
+                    key_b: key_b as u8,
                 },
                 ControlArea::Details,
             ),
-            InputEvent::Left(_) => (
-                LaunchControlMode::EnterDigitLoB {
-                    hi_a,
-                    lo_a,
-                    hi_b,
-                    lo_b: (16 + lo_b - 1) % 16,
-                },
-                ControlArea::Details,
+            DigitOutcome::Cancelled => (
+                LaunchControlMode::Core(CoreConnection::Start),
+                ControlArea::Tabs,
             ),
-            InputEvent::Send => self.process_event_nop(event),
         }
     }
 
     fn process_prepare_ignition(
         &self,
         event: &InputEvent,
-        hi_a: u8,
-        lo_a: u8,
-        hi_b: u8,
-        lo_b: u8,
+        key_a: u8,
+        key_b: u8,
         progress: u8,
         last_update: Instant,
+        now: Instant,
+        crank: &Crank,
     ) -> (Self, ControlArea) {
-        let now = Instant::now();
         if progress == 100 {
             (
-                LaunchControlMode::WaitForFire {
-                    hi_a,
-                    lo_a,
-                    hi_b,
-                    lo_b,
-                },
+                LaunchControlMode::WaitForFire { key_a, key_b },
                 ControlArea::Details,
             )
         } else {
@@ -1060,21 +1203,17 @@ impl LaunchControlMode {
                 ),
                 InputEvent::Right(_) => (
                     LaunchControlMode::PrepareIgnition {
-                        hi_a,
-                        lo_a,
-                        hi_b,
-                        lo_b,
-                        progress: std::cmp::min(progress + 3, 100),
+                        key_a,
+                        key_b,
+                        progress: crank.advance(progress),
                         last_update: now,
                     },
                     ControlArea::Details,
                 ),
                 _ => (
                     LaunchControlMode::PrepareIgnition {
-                        hi_a,
-                        lo_a,
-                        hi_b,
-                        lo_b,
+                        key_a,
+                        key_b,
                         progress,
                         last_update,
                     },
@@ -1087,15 +1226,15 @@ impl LaunchControlMode {
     fn process_unlock_pyros(
         &self,
         event: &InputEvent,
-        hi_a: u8,
-        lo_a: u8,
+        key_a: u8,
         progress: u8,
         last_update: Instant,
+        now: Instant,
+        crank: &Crank,
     ) -> (Self, ControlArea) {
-        let now = Instant::now();
         if progress == 100 {
             (
-                LaunchControlMode::UnlockPyros { hi_a, lo_a },
+                LaunchControlMode::UnlockPyros { key_a },
                 ControlArea::Details,
             )
         } else {
@@ -1106,17 +1245,15 @@ impl LaunchControlMode {
                 ),
                 InputEvent::Right(_) => (
                     LaunchControlMode::PrepareUnlockPyros {
-                        hi_a,
-                        lo_a,
-                        progress: std::cmp::min(progress + 3, 100),
+                        key_a,
+                        progress: crank.advance(progress),
                         last_update: now,
                     },
                     ControlArea::Details,
                 ),
                 _ => (
                     LaunchControlMode::PrepareUnlockPyros {
-                        hi_a,
-                        lo_a,
+                        key_a,
                         progress,
                         last_update,
                     },
@@ -1126,14 +1263,7 @@ impl LaunchControlMode {
         }
     }
 
-    fn process_fire(
-        &self,
-        event: &InputEvent,
-        hi_a: u8,
-        lo_a: u8,
-        hi_b: u8,
-        lo_b: u8,
-    ) -> (Self, ControlArea) {
+    fn process_fire(&self, event: &InputEvent, key_a: u8, key_b: u8) -> (Self, ControlArea) {
         match event {
             InputEvent::Back => (
                 LaunchControlMode::Core(CoreConnection::Start),
@@ -1141,12 +1271,7 @@ impl LaunchControlMode {
             ),
             InputEvent::Enter => (LaunchControlMode::Fire, ControlArea::Details),
             _ => (
-                LaunchControlMode::WaitForFire {
-                    hi_a,
-                    lo_a,
-                    hi_b,
-                    lo_b,
-                },
+                LaunchControlMode::WaitForFire { key_a, key_b },
                 ControlArea::Details,
             ),
         }
@@ -1165,34 +1290,142 @@ where
     pub fn new(
         consort: Consort<Id>,
         module: C,
-        now: Instant,
-        port: &str,
+        clock: SharedClock,
+        failover: Failover,
         gain: &AdcGain,
         start_with: LaunchMode,
+        privacy: Privacy,
         recorder_path: Option<PathBuf>,
+        recording: RecorderStatus,
+        run: RunId,
         nrf_connector: Rc<RefCell<dyn NRFConnector>>,
     ) -> Self {
-        Self {
+        let bus = EventBus::default();
+        let mut journal = Journal::new(recorder_path.as_deref());
+        journal.publish_markers(bus.markers.clone());
+        journal.stamp_with(clock.clone());
+        let announcer = Announcer::new(&bus);
+        // Before we journal anything ourselves
+        let last_session = LastSession::new(recorder_path.as_deref().and_then(|path| {
+            session::previous_session(
+                session::directory(path),
+                Some(&path.with_extension("journal")),
+                consort.dest(),
+            )
+        }));
+        journal.record(session::started_text(recorder_path.as_deref()));
+        journal.record(session::run_text(&run));
+        let (error_reporter, errors) = error_channel();
+        let now = clock.now();
+        let mut model = Self {
             mode: match start_with {
                 LaunchMode::Observables => Mode::Observables(ObservablesMode::default()),
                 LaunchMode::LaunchControl => Mode::LaunchControl(LaunchControlMode::default()),
                 LaunchMode::RFSilence => Mode::RFSilence(RFSilenceMode::default()),
             },
             control: Default::default(),
+            privacy,
+            accessibility: Accessibility::default(),
+            language: Language::default(),
             consort,
+            clock,
             start: now,
             now,
             module,
-            port: port.into(),
+            failover,
             last_state_change: None,
-            obg1: vec![],
-            obg2: None,
+            obg1: HashMap::new(),
+            node_clocks: HashMap::new(),
+            obg2: HashMap::new(),
+            launch_detect: HashMap::new(),
+            launch_dismissed_at: None,
+            received: Received::default(),
+            freshness: Freshness::default(),
+            profile: Profile::default(),
             established_connection_at: None,
             adc_gain: gain.clone(),
             recorder_path,
+            recording,
+            run,
+            relay_run: None,
             nrf_connector,
-            telemetry_data: HashMap::new(),
-        }
+            telemetry: TelemetryStore::default(),
+            module_status: HashMap::new(),
+            malformed_frames: HashMap::new(),
+            diagnostics: Diagnostics::default(),
+            drain_progress: None,
+            reset_held: false,
+            protocol_versions: HashMap::new(),
+            tare: Tare::default(),
+            self_test: Default::default(),
+            self_test_started: now,
+            timeline: Timeline::default(),
+            reference: None,
+            thrust_deviation: None,
+            snapshots: None,
+            bus,
+            journal,
+            error_reporter,
+            errors,
+            last_error: None,
+            monitor: Monitor::default(),
+            recovery: Recovery::default(),
+            annotator: Annotator::default(),
+            last_session,
+            max_thrust_kn: None,
+            crank: Crank::default(),
+            tab_navigation: TabNavigation::default(),
+            last_tab_change: None,
+            tuner: CrankTuner::default(),
+            filters: Filters::default(),
+            plot_ranges: PlotRanges::default(),
+            units: Units::default(),
+            filter_tuner: FilterTuner::default(),
+            filters_changed: false,
+            calibration: ImuCalibration::default(),
+            adc: AdcMonitor::default(),
+            roster: vec![],
+            roster_page: RosterPage::default(),
+            port_picker: PortPicker::default(),
+            transitions: TransitionLog::default(),
+            faults: Rc::new(RefCell::new(Faults::default())),
+            downloader: Downloader::default(),
+            onboard_recording: OnboardRecording::default(),
+            auto_record: AutoRecord::default(),
+            sequencer: Sequencer::default(),
+            broadcast: Broadcast::default(),
+            ground_power: GroundPower::default(),
+            safety_key: SafetyKey::default(),
+            launch_window: None,
+            gps_time: None,
+            reload: None,
+            help: false,
+            budget: FrameBudget::default(),
+            backlog: VecDeque::new(),
+            loop_timing: LoopTiming::default(),
+            idle: IdleDetector::new(IdlePolicy::default(), now),
+            announcer,
+            announcements: vec![],
+            clock_steps: StepDetector::default(),
+            silence: SilenceCompliance::default(),
+            sessions: SessionBrowser::default(),
+            export_directory: None,
+            conformance: Conformance::default(),
+            key_entry_timeout: KEY_ENTRY_TIMEOUT,
+            key_entry_since: None,
+            postmortem: PostMortem::default(),
+            bundle_directory: PathBuf::from(BUNDLE_DIRECTORY),
+            frame: 0,
+            tape: None,
+            playback: None,
+        };
+        model.start_self_test();
+        model
+    }
+
+    // As of the last drive
+    pub fn now(&self) -> Instant {
+        self.now
     }
 
     pub fn elapsed(&self) -> Duration {
@@ -1203,18 +1436,109 @@ where
         &self.mode
     }
 
-    pub fn process_telemetry_data(&mut self, telemetry_data: &Vec<TelemetryPacket>) {
-        for tp in telemetry_data {
-            if !self.telemetry_data.contains_key(&tp.node) {
-                self.telemetry_data.insert(tp.node, vec![]);
+    // For reporting errors into the journal from elsewhere,
+    // other threads included
+    pub fn error_reporter(&self) -> ErrorReporter {
+        self.error_reporter.clone()
+    }
+
+    // The latest packet of a node decides its module status
+    pub fn process_raw_telemetry(&mut self, raw: &Vec<RawTelemetryPacket>) {
+        let started = self.clock.now();
+        self.loop_timing.count_packets(raw.len());
+        for packet in raw {
+            let (node, status) = match packet {
+                RawTelemetryPacket::Frame(node, _) | RawTelemetryPacket::Backfilled(node, ..) => {
+                    (node, ModuleStatus::Receiving)
+                }
+                RawTelemetryPacket::NoModule(node) => (node, ModuleStatus::NoModule),
+                RawTelemetryPacket::Malformed(node, _) => {
+                    *self.malformed_frames.entry(*node).or_default() += 1;
+                    self.wake();
+                    (node, ModuleStatus::Receiving)
+                }
+            };
+            self.module_status.insert(*node, status);
+        }
+        let relay_run = self
+            .telemetry_subscription()
+            .and_then(|health| health.relay_run);
+        if relay_run.is_some() && relay_run != self.relay_run {
+            self.relay_run = relay_run;
+            if let Some(run) = &relay_run {
+                self.journal
+                    .record(format!("Telemetry relayed by run {} ({})", run.slug(), run));
+            }
+        }
+        self.process_telemetry_data(process_raw_telemetry_data(
+            raw,
+            self.calibration.configured(),
+            &self.error_reporter,
+        ));
+        self.loop_timing
+            .record(Phase::Telemetry, self.clock.now().duration_since(started));
+    }
+
+    // Kept with the time they were received at, the
+    // backlog may only be worked off frames later
+    fn process_telemetry_data(&mut self, telemetry_data: Vec<(TelemetryPacket, Duration)>) {
+        let elapsed = self.elapsed();
+        self.backlog.extend(
+            telemetry_data
+                .into_iter()
+                .map(|(tp, age)| (elapsed.saturating_sub(age), tp)),
+        );
+        self.work_off_backlog();
+    }
+
+    fn process_telemetry_packet(&mut self, received: Duration, tp: TelemetryPacket) {
+        if let TelemetryData::State(state) = &tp.data {
+            if let Some(text) = self.silence.record(tp.node, &state.ignition) {
+                self.journal.record(text);
+                self.consort.set_silenced(self.silence.inhibited());
+            }
+        }
+        if let TelemetryData::Config(ranges) = &tp.data {
+            if let Some(text) = self.calibration.report(tp.node, *ranges) {
+                self.journal.record(text);
+                if self.calibration.mismatched().contains(&tp.node) {
+                    self.wake();
+                }
+            }
+        }
+        if let TelemetryData::IMU(imu) = &tp.data {
+            self.timeline.push_onboard(
+                tp.node,
+                tp.preamble.timestamp,
+                received.as_secs_f64(),
+                imu.imu.acc_magnitude() as f64,
+                imu.pressure as f64,
+            );
+        }
+        if let Some(ping) = self
+            .recovery
+            .record(tp.node, received, tp.preamble.timestamp, &tp.data)
+        {
+            self.bus.pings.publish(ping);
+        }
+        self.telemetry.push(tp.node, received, tp.data);
+    }
+
+    // Always does some work, so the backlog can't starve
+    fn work_off_backlog(&mut self) {
+        while let Some((received, tp)) = self.backlog.pop_front() {
+            self.process_telemetry_packet(received, tp);
+            if self.budget.exhausted(self.clock.now()) {
+                break;
             }
-            self.telemetry_data
-                .get_mut(&tp.node)
-                .unwrap()
-                .push(tp.data.clone());
         }
     }
 
+    // Work was left over frame after frame
+    pub fn overloaded(&self) -> bool {
+        self.budget.overloaded()
+    }
+
     pub fn registered_nodes(&self) -> Vec<Node> {
         self.nrf_connector.borrow().registered_nodes().clone()
     }
@@ -1223,88 +1547,321 @@ where
         self.nrf_connector.borrow().heard_from_since(node)
     }
 
-    pub fn telemetry_data_for_node(&self, node: &Node) -> Option<&Vec<TelemetryData>> {
-        self.telemetry_data.get(node)
+    pub fn telemetry_subscription(&self) -> Option<SubscriptionHealth> {
+        self.nrf_connector.borrow().subscription()
+    }
+
+    pub fn duty_cycle(&self) -> Option<DutyCycleStatus> {
+        self.module.duty_cycle()
+    }
+
+    pub fn module_status(&self, node: &Node) -> Option<ModuleStatus> {
+        self.module_status.get(node).copied()
+    }
+
+    pub fn malformed_frames(&self) -> usize {
+        self.malformed_frames.values().sum()
+    }
+
+    pub fn telemetry_data_for_node(&self, node: &Node) -> Option<&VecDeque<Stored>> {
+        self.telemetry.for_node(node)
+    }
+
+    // Starts the frame, telemetry and input are processed after
+    pub fn drive(&mut self) -> anyhow::Result<()> {
+        self.frame += 1;
+        let started = self.clock.now();
+        self.loop_timing.begin_frame(started);
+        let result = self.drive_frame();
+        self.loop_timing
+            .record(Phase::Drive, self.clock.now().duration_since(started));
+        self.loop_timing.queues = self.queue_depths();
+        result
+    }
+
+    // Of the work left for the next frames and the channels
+    // from the threads, for the timing overlay
+    fn queue_depths(&self) -> Vec<(&'static str, usize)> {
+        let mut queues = vec![
+            ("Telemetry", self.backlog.len()),
+            ("Errors", self.errors.len()),
+        ];
+        if let Some(depths) = self.module.queue_depths() {
+            queues.extend([
+                ("E32 commands", depths.commands),
+                ("E32 urgent", depths.urgent),
+                ("E32 answers", depths.answers),
+                ("E32 observables", depths.observables),
+                ("E32 observables dropped", depths.dropped_observables),
+            ]);
+        }
+        queues
+    }
+
+    pub fn toggle_loop_timing(&mut self) {
+        self.loop_timing.visible = !self.loop_timing.visible;
     }
 
-    pub fn drive(&mut self, now: Instant) -> anyhow::Result<()> {
-        self.now = now;
-        self.consort.update_time(now);
+    fn drive_frame(&mut self) -> anyhow::Result<()> {
+        let previous = self.now;
+        self.now = self.clock.now();
+        if let Some(step) = self.clock_steps.check(self.now, self.clock.wall()) {
+            self.journal.record(step.text());
+        }
+        if self.idle.update(self.now) {
+            self.journal
+                .record("Standby, observables polled less often and display dimmed");
+        }
+        self.update_launch_window();
+        if let Some(outcome) = self.sessions.poll() {
+            self.journal.record(match outcome {
+                Ok(exported) => format!("Session exported to {}", exported.display()),
+                Err(err) => format!("Session export failed: {}", err),
+            });
+        }
+        if let Some(outcome) = self.postmortem.poll() {
+            self.journal.record(match outcome {
+                Ok(path) => format!("Diagnostic bundle written to {}", path.display()),
+                Err(err) => format!("Diagnostic bundle failed: {}", err),
+            });
+        }
+        self.budget.start(self.now, !self.backlog.is_empty());
+        self.work_off_backlog();
+        self.drive_self_test();
         // When we are in start state, start a reset cycle
         if self.mode.core_mode().is_start() || self.effect_timeout() {
             self.reset();
             self.control = Default::default();
             return Ok(());
         }
+        if self.reset_held && !self.reset_blocked() {
+            self.send_reset();
+        }
+        self.expire_key_entry();
 
-        let mut ringbuffer = AllocRingBuffer::new(MAX_BUFFER_SIZE);
         let mut timeout = false;
-        let mut error = false;
+        let mut unanswered = None;
+        let mut error = None;
         let mut reset = false;
         let mut observables = None;
-        self.module.recv(|answer| match answer {
-            Answers::Received(sentence) => {
-                for c in sentence {
-                    ringbuffer.push(c);
+        let mut received = None;
+        let mut monitored = None;
+        let mut reconfigured = None;
+        let mut drain_progress = self.drain_progress;
+        self.module.recv(|answer| {
+            // Any other answer means the purge is over
+            drain_progress = match answer {
+                Answers::Draining(progress) => Some(progress),
+                _ => None,
+            };
+            match answer {
+                Answers::Received(sentence) => {
+                    received = Some(sentence);
+                }
+                Answers::Timeout => {
+                    timeout = true;
+                }
+                Answers::PollTimeout(node) => {
+                    unanswered = Some(node);
+                }
+                Answers::ConnectionError(cause) => {
+                    error = Some(cause);
+                }
+                Answers::Observables(node, o) => {
+                    observables = Some((node, o));
+                }
+                Answers::Draining(_) => {}
+                Answers::Monitored(sentence) => {
+                    monitored = Some(sentence);
+                }
+                Answers::ModuleReconfigured(drifted) => {
+                    reconfigured = Some(drifted);
+                }
+                Answers::Drained => {
+                    reset = true;
+                }
+                // Whatever cancelled it is answered next
+                Answers::DrainCancelled => {}
+                Answers::ConnectionOpen => {
+                    // Go through a reset cycle on a new connection
+                    self.self_test.record(Check::SerialPort, Verdict::Passed);
+                    self.self_test.record(Check::E32Parameters, Verdict::Passed);
+                    reset = true;
                 }
-            }
-            Answers::Timeout => {
-                timeout = true;
-            }
-            Answers::ConnectionError => {
-                error = true;
-            }
-            Answers::Observables(o) => {
-                observables = Some(o);
-            }
-            Answers::Drained => {
-                reset = true;
-            }
-            Answers::ConnectionOpen => {
-                // Go through a reset cycle on a new connection
-                reset = true;
             }
         });
-        if let Some(o) = observables {
-            self.process_observables(&o);
+        self.drain_progress = drain_progress;
+        // Sized for a burst of sentences, a fixed one drops its start
+        let mut ringbuffer =
+            AllocRingBuffer::new(received.as_ref().map_or(0, Vec::len).max(MAX_BUFFER_SIZE));
+        if let Some(sentence) = received {
+            for c in sentence.iter() {
+                ringbuffer.push(*c);
+            }
+            self.diagnostics.record_sentence(&sentence);
+            self.postmortem.record_sentence(&sentence);
+            self.monitor.record(&sentence, self.profile);
+        }
+        if let Some(sentence) = monitored {
+            self.monitor.record(&sentence, self.profile);
         }
-        if timeout {
-            self.module.drain();
+        if let Some(drifted) = reconfigured {
+            self.diagnostics.record_reconfiguration();
+            self.journal
+                .record(format!("E32 reconfigured, had drifted to {}", drifted));
+        }
+        if let Some((node, o)) = observables {
+            self.process_observables(node, &o);
+        }
+        // The link is fine, only that node's values are gone
+        if let Some(node) = unanswered {
+            debug!("{} didn't answer the poll", node);
+            self.obg1.remove(&node);
+            self.obg2.remove(&node);
+        }
+        if timeout && self.consort.absorb_superseded() {
+            // The command an abort took the place of
+            debug!("Superseded command timed out");
+        } else if timeout && self.aborting() {
+            self.resend_abort();
+        } else if timeout && self.broadcast.timed_out() {
+            // A node not answering is no reason to purge the line
+            self.consort.reset();
+            self.module.resume();
+        } else if timeout && self.mode.core_mode().is_discover() {
+            self.assume_legacy_node();
+        } else if timeout {
+            self.back_off();
+            if !self.fail_over() {
+                self.module.drain();
+            }
             self.obg1.clear();
-            self.obg2 = None;
+            self.obg2.clear();
         } else if reset {
+            self.diagnostics.clear();
             self.reset();
-        } else if error {
-            self.mode = self.mode.failure_mode();
-            self.module.open(&self.port);
+        } else if let Some(cause) = error {
+            self.set_mode(self.mode.failure_mode());
+            // Not having opened the port yet isn't the link's fault
+            let switched = match cause {
+                FailureCause::NoConnection => false,
+                _ => self.fail_over(),
+            };
+            if !switched && !cause.is_open_failure() {
+                self.open_port();
+            }
+            self.record_open_failure(&cause);
+            self.diagnostics.record_failure(cause);
         } else {
             while !ringbuffer.is_empty() {
                 match self.consort.feed(&mut ringbuffer) {
                     Ok(response) => {
                         if let Some(response) = response {
                             debug!("process_response: {:?}", response);
+                            self.diagnostics.record_success();
+                            self.failover.record_success();
                             self.process_response(response);
                         }
                         self.module.resume();
                     }
+                    Err(ConsortError::Nak) if self.broadcast.refused() => {
+                        self.consort.reset();
+                        self.module.resume();
+                        break;
+                    }
+                    Err(ConsortError::Nak) if self.downloader.refused() => {
+                        self.consort.reset();
+                        self.module.resume();
+                        break;
+                    }
+                    Err(ConsortError::Nak) if self.onboard_recording_refused() => {
+                        self.consort.reset();
+                        self.module.resume();
+                        break;
+                    }
+                    Err(ConsortError::Nak) if self.aborting() => {
+                        self.resend_abort();
+                        break;
+                    }
+                    Err(ConsortError::Nak) if self.mode.core_mode().is_discover() => {
+                        self.assume_legacy_node();
+                        break;
+                    }
+                    // The node heard us and refused, that's no
+                    // reason to purge the line or give up on the radio
+                    Err(ConsortError::Nak) => {
+                        self.diagnostics.record_consort_error(&ConsortError::Nak);
+                        self.error_reporter
+                            .report(Error::from(ConsortError::Nak).context("Command refused"));
+                        self.bus.refusals.publish(self.consort.dest());
+                        self.failover.record_success();
+                        self.back_off();
+                        self.reset();
+                        break;
+                    }
+                    // Garbled, forged or unsealed, the abort isn't given up on
+                    Err(err) if self.aborting() => {
+                        self.diagnostics.record_consort_error(&err);
+                        self.resend_abort();
+                        break;
+                    }
                     Err(err) => {
-                        error!("Feeding consort error: {:?}", err);
+                        self.diagnostics.record_consort_error(&err);
+                        self.error_reporter
+                            .report(Error::from(err).context("Feeding consort"));
                         self.module.reset();
-                        self.module.drain();
+                        if !self.fail_over() {
+                            self.module.drain();
+                        }
                         break;
                     }
                 }
             }
+            // What's left after an error is lost
+            self.diagnostics.record_discarded(ringbuffer.len());
         }
-        self.set_mode(self.mode.drive());
-        self.module.radio_silence(self.mode.is_radio_silence());
+        self.record_deviations();
+        let tick = Tick {
+            now: self.now,
+            elapsed: self.now.duration_since(previous),
+        };
+        self.set_mode(self.mode.drive(tick, &self.crank));
+        self.drive_download();
+        self.drive_onboard_recording();
+        self.drive_reference();
+        self.drive_sequence();
+        self.drive_broadcast();
+        self.module.link_activity(self.link_activity());
+        self.module.monitor(self.monitor.is_active());
+        self.journal_errors();
         Ok(())
     }
 
+    fn record_deviations(&mut self) {
+        for (node, deviation, sentence) in self.consort.take_deviations() {
+            if let Some(text) = self.conformance.record(node, deviation, &sentence) {
+                self.journal.record(text);
+            }
+        }
+    }
+
+    fn journal_errors(&mut self) {
+        while let Ok(err) = self.errors.try_recv() {
+            let text = format!("{} error: {}", err.origin(), err);
+            if let Some((last, at)) = &self.last_error {
+                if *last == text && self.now.duration_since(*at) < ERROR_REPEAT_INTERVAL {
+                    continue;
+                }
+            }
+            self.journal.record(text.clone());
+            self.last_error = Some((text, self.now));
+        }
+    }
+
     fn effect_timeout(&self) -> bool {
         if let Some(last_state_change) = self.last_state_change {
             if self.mode.affected_by_timeout()
-                && Instant::now().duration_since(last_state_change) > AUTO_RESET_TIMEOUT
+                && self.now.duration_since(last_state_change) > AUTO_RESET_TIMEOUT
             {
                 error!("TIMEOUT!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!");
                 return true;
@@ -1313,62 +1870,537 @@ where
         return false;
     }
 
-    fn reset(&mut self) {
-        self.mode = self.mode.reset_mode();
-        self.established_connection_at = None;
-        self.consort.reset();
+    // Entered digits don't linger until the automatic reset
+    fn expire_key_entry(&mut self) {
+        let Some(expires_in) = self.key_entry_expires_in() else {
+            return;
+        };
+        if !expires_in.is_zero() {
+            return;
+        }
+        let Mode::LaunchControl(state) = self.mode else {
+            return;
+        };
+        let next = match state {
+            LaunchControlMode::EnterKeyA(_) => LaunchControlMode::Core(CoreConnection::Idle),
+            // The pyros are unlocked by then, they are locked again first
+            LaunchControlMode::EnterKeyB { .. } => LaunchControlMode::SafeAbort,
+            _ => return,
+        };
+        self.journal.record(format!(
+            "Key {} entry expired after {}s",
+            state.key_in_entry().unwrap_or('?'),
+            self.key_entry_timeout.as_secs()
+        ));
+        self.control = Default::default();
+        self.set_mode(Mode::LaunchControl(next));
+    }
+
+    pub fn set_key_entry_timeout(&mut self, timeout: Duration) {
+        self.key_entry_timeout = timeout;
+    }
+
+    // For the countdown while a key is being entered
+    pub fn key_entry_expires_in(&self) -> Option<Duration> {
+        let since = self.key_entry_since?;
+        Some(
+            self.key_entry_timeout
+                .saturating_sub(self.now.duration_since(since)),
+        )
+    }
+
+    fn reset(&mut self) {
+        // A Ping in flight is lost with the reset
+        if *self.self_test.verdict(Check::Ping) == Verdict::Running {
+            self.self_test.record(Check::Ping, Verdict::Pending);
+        }
+        self.set_mode(self.mode.reset_mode());
+        self.dismiss_launch();
+        self.established_connection_at = None;
+        self.consort.reset();
         self.module.reset();
+        self.reset_held = self.reset_blocked();
+        if !self.reset_held {
+            self.send_reset();
+        }
+    }
+
+    // By the backoff, or a target expecting radio silence
+    fn reset_blocked(&self) -> bool {
+        self.consort.holding().is_some() || self.consort.is_silenced(self.consort.dest())
+    }
+
+    fn send_reset(&mut self) {
+        self.reset_held = false;
         match self
             .consort
             .send_command(Command::Reset(self.adc_gain.clone()), &mut self.module)
+            .context("Sending reset")
         {
             Ok(_) => {}
-            Err(_) => {
-                self.mode = self.mode.failure_mode();
+            Err(err) => {
+                self.error_reporter.report(err);
+                self.set_mode(self.mode.failure_mode());
             }
         }
     }
 
+    // The operator learns when retrying has slowed down all the way
+    fn back_off(&mut self) {
+        let hold = self.consort.back_off();
+        if hold.maxed {
+            self.journal.record(format!(
+                "Command link backing off, {:.1}s between retries",
+                hold.delay.as_secs_f32()
+            ));
+        }
+    }
+
     fn process_response(&mut self, response: Response) {
+        self.bus.responses.publish(response.clone());
+        if self.broadcast.process_response(&response) {
+            return;
+        }
+        if let Response::FileOpenAck { .. }
+        | Response::FileReadAck { .. }
+        | Response::FileCloseAck
+        | Response::FileListAck { .. }
+        | Response::FileDeleteAck = response
+        {
+            self.downloader.process_response(&response);
+            return;
+        }
+        if let Response::RecordStartAck | Response::RecordStopAck = response {
+            self.journal.record(format!(
+                "{:?} by {}, waiting for OBG2",
+                response,
+                self.consort.dest()
+            ));
+            return;
+        }
+        if let Some(run) = &mut self.sequencer.run {
+            run.process_response(&response, self.now);
+        }
         if let Response::ObservableGroup(raw_observables) = response {
-            self.process_observables(&raw_observables)
+            self.process_observables(self.consort.dest(), &raw_observables)
         } else {
-            self.set_mode(self.mode.process_response(response));
+            if let Response::PingAck = response {
+                self.self_test.record(Check::Ping, Verdict::Passed);
+            }
+            if let Response::VersionAck(version) = response {
+                self.protocol_versions.insert(self.consort.dest(), version);
+                self.module.capabilities(version.capabilities);
+                self.negotiate_encryption(version.capabilities);
+            }
+            match response {
+                Response::ResetAck => self.mark(Marker::Reset),
+                Response::UnlockPyrosAck => self.mark(Marker::PyrosUnlocked),
+                Response::IgnitionAck => self.mark(Marker::Ignition),
+                Response::AbortAck => self.mark(Marker::Abort),
+                _ => {}
+            }
+            let acknowledged = response == Response::AbortAck;
+            self.set_mode(self.mode.process_response(response, self.now));
+            if self.aborting() && !acknowledged {
+                self.resend_abort();
+            }
+        }
+    }
+
+    fn aborting(&self) -> bool {
+        self.mode == Mode::LaunchControl(LaunchControlMode::SafeAbort)
+    }
+
+    // One lost packet mustn't leave the pyros unlocked
+    fn resend_abort(&mut self) {
+        self.journal
+            .record("Abort not acknowledged, sending it again");
+        self.consort.reset();
+        let sent = self
+            .consort
+            .send_superseding(Command::Abort, &mut Urgent(&mut self.module));
+        if let Err(err) = sent {
+            error!("Can't resend the abort, {:?}", err);
+        }
+    }
+
+    fn mark(&mut self, marker: Marker) {
+        self.journal.mark(
+            marker,
+            self.elapsed().as_secs_f64(),
+            format!("{} acknowledged by {}", marker.name(), self.consort.dest()),
+        );
+    }
+
+    fn negotiate_encryption(&mut self, capabilities: Capabilities) {
+        let dest = self.consort.dest();
+        match self.consort.negotiate_encryption(capabilities) {
+            Some(true) => self
+                .journal
+                .record(format!("Commands to {} are encrypted", dest)),
+            Some(false) if self.consort.has_link_key() => self.journal.record(format!(
+                "{} doesn't report link encryption, its commands stay encrypted",
+                dest
+            )),
+            _ => {}
+        }
+    }
+
+    // Nodes predating the VERSION command either NAK
+    // or ignore it, we then assume the legacy feature set.
+    fn assume_legacy_node(&mut self) {
+        debug!("Node doesn't support VERSION, assuming legacy");
+        self.consort.reset();
+        self.process_response(Response::VersionAck(ProtocolVersion::legacy()));
+        self.module.resume();
+    }
+
+    pub fn protocol_version(&self) -> Option<&ProtocolVersion> {
+        self.protocol_versions.get(&self.consort.dest())
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.protocol_version()
+            .map(|v| v.capabilities)
+            .unwrap_or_else(Capabilities::legacy)
+    }
+
+    // Observables of the target node, which is what
+    // tare, timeline and launch control work with.
+    pub fn target_obg1(&self) -> Option<&Obg1History> {
+        self.obg1.get(&self.consort.dest())
+    }
+
+    pub fn target_obg2(&self) -> Option<&ObservablesGroup2> {
+        self.obg2.get(&self.consort.dest())
+    }
+
+    pub fn stale(&self, node: Node) -> Stale {
+        self.received.stale(
+            node,
+            &self.freshness,
+            self.link_activity(),
+            self.clock.now(),
+        )
+    }
+
+    // The mode asks for it, the standby slows it down. A target
+    // expecting radio silence isn't polled either.
+    fn link_activity(&self) -> LinkActivity {
+        if self.consort.is_silenced(self.consort.dest()) {
+            return LinkActivity::Silent;
         }
+        self.idle.link_activity(self.mode.link_activity())
+    }
+
+    // Input or a telemetry anomaly
+    pub fn wake(&mut self) {
+        if self.idle.wake(self.clock.now()) {
+            self.journal.record("Standby left");
+        }
+    }
+
+    pub fn target_stale(&self) -> Stale {
+        self.stale(self.consort.dest())
     }
 
-    fn process_observables(&mut self, raw: &RawObservablesGroup) {
+    fn process_observables(&mut self, node: Node, raw: &RawObservablesGroup) {
         let sys_def = SystemDefinition::default();
+        let is_target = node == self.consort.dest();
         match raw {
             RawObservablesGroup::OG1(obg1) => {
-                self.obg1.push(sys_def.transform_og1(obg1));
+                let tare = if is_target {
+                    self.tare.feed(obg1);
+                    self.tare.offset.as_ref()
+                } else {
+                    None
+                };
+                let reading = obg1;
+                let mut obg1 = sys_def.transform_og1(obg1, tare);
+                let clock = self.node_clocks.entry(node).or_default();
+                let rebooted = clock.is_reboot(obg1.uptime);
+                obg1.time = clock.time(obg1.uptime, self.now - self.start);
+                if rebooted {
+                    self.journal
+                        .record(format!("{} rebooted, its uptime restarted", node));
+                }
+                if is_target {
+                    self.adc.feed(reading, &obg1);
+                    let thrust = obg1.thrust.get::<kilonewton>();
+                    self.max_thrust_kn =
+                        Some(self.max_thrust_kn.map_or(thrust, |max| max.max(thrust)));
+                    self.timeline.push_ground(
+                        obg1.time.as_secs_f64(),
+                        self.elapsed().as_secs_f64(),
+                        obg1.thrust.get::<kilonewton>(),
+                        obg1.pressure.get::<bar>(),
+                    );
+                }
+                self.obg1.entry(node).or_default().push(obg1);
+                self.received.record(node, Group::Obg1, self.now);
             }
             RawObservablesGroup::OG2(obg2) => {
-                self.obg2 = Some(sys_def.transform_og2(obg2));
+                let obg2 = sys_def.transform_og2(obg2);
+                // Only the test stand counts them
+                let previous = self.obg2.get(&node).and_then(|o| o.anomalies);
+                if let (Some(anomalies), Some(previous)) = (obg2.anomalies, previous) {
+                    if anomalies > previous {
+                        self.wake();
+                        self.journal.mark(
+                            Marker::Anomaly,
+                            self.elapsed().as_secs_f64(),
+                            format!("{} anomalies reported by {}", anomalies, node),
+                        );
+                    }
+                }
+                self.obg2.insert(node, obg2);
+                self.received.record(node, Group::Obg2, self.now);
+            }
+            RawObservablesGroup::OG3(obg3) => {
+                let detect = sys_def.transform_og3(obg3);
+                // A wire already broken when first heard of is no launch
+                let was_intact = matches!(
+                    self.launch_detect.get(&node).map(|d| d.breakwire),
+                    Some(Breakwire::Intact)
+                );
+                if let (Breakwire::Broken(ago), true) = (detect.breakwire, was_intact) {
+                    self.wake();
+                    self.journal.mark(
+                        Marker::RailExit,
+                        self.elapsed().as_secs_f64() - ago.as_secs_f64(),
+                        format!("Breakwire of {} broken", node),
+                    );
+                }
+                self.launch_detect.insert(node, detect);
+            }
+        }
+    }
+
+    // Of this frame, live ones are ignored while playing back
+    pub fn process_bindings(&mut self, bindings: &[Binding]) {
+        let bindings = match &mut self.playback {
+            Some(playback) => {
+                let bindings = playback.take(self.frame);
+                if playback.is_finished() {
+                    self.playback = None;
+                    self.journal.record("Input playback finished");
+                }
+                bindings
+            }
+            None => bindings.to_vec(),
+        };
+        if !bindings.is_empty() {
+            self.wake();
+        }
+        self.tape_input(&bindings);
+        // Never deferred, whatever telemetry is waiting
+        for binding in bindings {
+            self.process_binding(binding);
+        }
+    }
+
+    fn process_binding(&mut self, binding: Binding) {
+        match binding {
+            Binding::Event(event) => self.process_input_event(&event),
+            Binding::SelfTest => self.start_self_test(),
+            Binding::Monitor => self.toggle_monitor(),
+            Binding::Downloads => self.toggle_downloads(),
+            Binding::Broadcast => self.toggle_broadcast(),
+            Binding::Sequences => self.toggle_sequences(),
+            Binding::Tuning => self.toggle_tuning(),
+            Binding::Faults => self.toggle_faults(),
+            Binding::Transitions => self.toggle_transitions(),
+            Binding::Timing => self.toggle_loop_timing(),
+            Binding::Filters => self.toggle_filters(),
+            Binding::Calibration => self.toggle_calibration(),
+            Binding::Adc => self.toggle_adc(),
+            Binding::Roster => self.toggle_roster(),
+            Binding::Ports => self.toggle_port_picker(ports::available()),
+            Binding::Recovery => self.toggle_recovery(),
+            Binding::SilenceOverride => self.toggle_silence_override(),
+            Binding::Sessions => self.toggle_sessions(),
+            Binding::PostMortem => self.write_postmortem(),
+            Binding::Mark => self.mark_moment(),
+            Binding::Abort => self.abort(),
+            Binding::Help => self.toggle_help(),
+            // Up to the application
+            Binding::Quit => {}
+        }
+    }
+
+    fn tape_input(&mut self, bindings: &[Binding]) {
+        let Some(tape) = &mut self.tape else {
+            return;
+        };
+        for binding in bindings {
+            let taped = Taped {
+                frame: self.frame,
+                binding: *binding,
+            };
+            if let Err(err) = tape.record(&taped) {
+                self.tape = None;
+                self.journal
+                    .record(format!("Input recording stopped: {}", err));
+                return;
+            }
+        }
+    }
+
+    pub fn record_input(&mut self, path: &Path) {
+        match TapeRecorder::create(path) {
+            Ok(tape) => {
+                self.tape = Some(tape);
+                self.journal
+                    .record(format!("Recording input to {}", path.display()));
+            }
+            Err(err) => {
+                self.journal
+                    .record(format!("Can't record input to {}: {}", path.display(), err))
             }
         }
     }
 
-    pub fn process_input_events(&mut self, events: &Vec<InputEvent>) {
-        for event in events {
-            self.process_input_event(event);
+    pub fn play_input(&mut self, path: &Path) {
+        match Playback::load(path) {
+            Ok(playback) => {
+                self.journal.record(format!(
+                    "Playing back {} input events from {}",
+                    playback.remaining(),
+                    path.display()
+                ));
+                self.playback = Some(playback);
+            }
+            Err(err) => self.journal.record(format!(
+                "Can't play back input from {}: {}",
+                path.display(),
+                err
+            )),
         }
     }
 
     fn process_input_event(&mut self, event: &InputEvent) {
+        self.wake();
+        if self.help {
+            if let InputEvent::Back | InputEvent::Enter = event {
+                self.help = false;
+            }
+            return;
+        }
+        // Modal, nothing goes on unnoticed
+        if !self.conformance.unacknowledged().is_empty() {
+            if let InputEvent::Enter = event {
+                self.conformance.acknowledge();
+                self.journal.record("Protocol deviations acknowledged");
+            }
+            return;
+        }
+        if self.annotator.is_picking() {
+            self.process_annotation_event(event);
+            return;
+        }
+        if self.port_picker.is_active() {
+            self.process_port_picker_event(event);
+            return;
+        }
+        if self.last_session.is_active() {
+            match event {
+                InputEvent::Enter => self.last_session.toggle_full_journal(),
+                InputEvent::Back => self.last_session.dismiss(),
+                _ => {}
+            }
+            return;
+        }
+        if self.self_test.is_active() {
+            self.process_self_test_event(event);
+            return;
+        }
+        if self.monitor.is_active() {
+            self.process_monitor_event(event);
+            return;
+        }
+        if self.recovery.is_active() {
+            if let InputEvent::Back = event {
+                self.recovery.stop();
+            }
+            return;
+        }
+        if self.roster_page.is_active() {
+            if let InputEvent::Back = event {
+                self.roster_page.stop();
+            }
+            return;
+        }
+        if self.sessions.is_active() {
+            self.process_sessions_event(event);
+            return;
+        }
+        if self.downloader.is_active() {
+            self.process_download_event(event);
+            return;
+        }
+        if self.broadcast.is_active() {
+            self.process_broadcast_event(event);
+            return;
+        }
+        if self.sequencer.is_active() {
+            self.process_sequence_event(event);
+            return;
+        }
+        if self.tuner.is_active() {
+            self.process_tuning_event(event);
+            return;
+        }
+        if self.filter_tuner.is_active() {
+            self.process_filter_event(event);
+            return;
+        }
+        if self.calibration.is_active() {
+            self.process_calibration_event(event);
+            return;
+        }
+        if self.adc.is_active() {
+            self.process_adc_event(event);
+            return;
+        }
+        // Manual radio override, but not in the middle of a sequence
+        if let InputEvent::Send = event {
+            if self.mode.core_mode().is_failure() || self.control == ControlArea::Tabs {
+                self.switch_radio();
+                return;
+            }
+        }
+        if self.mode.core_mode().is_failure() {
+            if let InputEvent::Enter = event {
+                self.retry_now();
+                return;
+            }
+        }
+        let previous = self.mode;
         self.control = match self.control {
             ControlArea::Tabs => self.process_tabs_event(event),
             ControlArea::Details => self.process_details_event(event),
         };
+        if let Mode::LaunchControl(LaunchControlMode::PreArm { acknowledged: 0 }) = self.mode {
+            if previous != self.mode {
+                self.query_continuity();
+            }
+        }
+    }
+
+    // Results shown in the checklist must not predate it
+    fn query_continuity(&mut self) {
+        self.obg2.remove(&self.consort.dest());
+        self.module.query_continuity();
     }
 
     fn process_tabs_event(&mut self, event: &InputEvent) -> ControlArea {
         match event {
-            InputEvent::Left(..) => self.toggle_tab(true),
-            InputEvent::Right(..) => self.toggle_tab(false),
+            InputEvent::Left(..) | InputEvent::Right(..) => self.toggle_tab(event.signed_steps()),
             InputEvent::Enter => {
-                let (mode, control) = self.mode.process_event(event);
-                self.mode = mode;
+                let (mode, control) = self
+                    .mode
+                    .process_event(event, self.clock.now(), &self.crank);
+                self.set_mode(mode);
                 control
             }
             _ => self.control,
@@ -1377,7 +2409,69 @@ where
 
     fn process_details_event(&mut self, event: &InputEvent) -> ControlArea {
         debug!("process_detail_event: {:?}", event);
-        let (mode, control_area) = self.mode.process_event(event);
+        // Nothing to acknowledge before the continuity is in
+        if let (Mode::LaunchControl(LaunchControlMode::PreArm { .. }), InputEvent::Enter) =
+            (self.mode, event)
+        {
+            if self.target_obg2().is_none() {
+                return self.control;
+            }
+        }
+        if let Mode::Observables(_) = self.mode {
+            // The confirmation takes all input until answered
+            if let OnboardRecording::Confirm(_) = self.onboard_recording {
+                match event {
+                    InputEvent::Enter => {
+                        self.onboard_recording.confirm();
+                        self.auto_record.overridden();
+                    }
+                    InputEvent::Back => self.onboard_recording.cancel(),
+                    _ => {}
+                }
+                return self.control;
+            }
+            match event {
+                InputEvent::Enter => self.tare_observables(),
+                InputEvent::Left(_) => self.timeline.align(-TIMELINE_ALIGNMENT_STEP),
+                InputEvent::Right(_) => self.timeline.align(TIMELINE_ALIGNMENT_STEP),
+                InputEvent::Send => self.request_onboard_recording(),
+                _ => {}
+            }
+        }
+        let (mode, control_area) = self
+            .mode
+            .process_event(event, self.clock.now(), &self.crank);
+        // Key AB and the ignition need the range safety key
+        if let Mode::LaunchControl(
+            LaunchControlMode::TransmitKeyAB { .. } | LaunchControlMode::Fire,
+        ) = mode
+        {
+            if self.safety_key.inhibits() {
+                return self.control;
+            }
+            if let Some(status) = self.launch_window_holding() {
+                let held = match mode {
+                    Mode::LaunchControl(LaunchControlMode::Fire) => "Ignition",
+                    _ => "Key AB",
+                };
+                self.journal.record(format!("{} held: {}", held, status));
+                return self.control;
+            }
+        }
+        // Nor is the ignition count completed
+        if let Mode::LaunchControl(LaunchControlMode::WaitForFire { .. }) = mode {
+            if self.launch_window_holding().is_some() {
+                return self.control;
+            }
+        }
+        // The ignition can't go out while another command is in flight
+        if let Mode::LaunchControl(LaunchControlMode::Fire) = mode {
+            if self.consort.busy() {
+                self.journal
+                    .record("Ignition refused, a command is in flight, press again");
+                return self.control;
+            }
+        }
         self.set_mode(mode);
         control_area
     }
@@ -1385,20 +2479,61 @@ where
     fn set_mode(&mut self, mode: Mode) {
         if self.mode != mode {
             debug!("old mode: {:?}, new mode: {:?}", self.mode, mode);
+            self.transitions.record(self.now, &self.mode, &mode);
+            // Only within the launch control, the other modes aren't safety critical
+            if let (Mode::LaunchControl(from), Mode::LaunchControl(to)) = (&self.mode, &mode) {
+                self.consort
+                    .check_transition(state_name(from), state_name(to));
+            }
+            self.bus.modes.publish(ModeChange {
+                at: self.now,
+                from: self.mode,
+                to: mode,
+            });
+            // Not the reset cycles of a lost link
+            if !self.mode.reset_ongoing() && !mode.reset_ongoing() {
+                self.wake();
+            }
+            let key_in_entry = |mode: &Mode| match mode {
+                Mode::LaunchControl(state) => state.key_in_entry(),
+                _ => None,
+            };
+            if key_in_entry(&mode) != key_in_entry(&self.mode) {
+                self.key_entry_since = key_in_entry(&mode).map(|_| self.now);
+            }
+            let failed = mode.core_mode().is_failure() && !self.mode.core_mode().is_failure();
+            let previous = self.mode;
             self.mode = mode;
-            self.process_mode_change();
-            self.last_state_change = Some(Instant::now());
+            if failed {
+                self.write_postmortem();
+            }
+            self.process_mode_change(previous);
+            self.last_state_change = Some(self.clock.now());
         }
     }
 
-    fn process_mode_change(&mut self) {
+    fn process_mode_change(&mut self, previous: Mode) {
         if let Some(command) = self.mode.process_mode_change() {
-            if self
-                .consort
-                .send_command(command, &mut self.module)
-                .is_err()
-            {
-                self.reset();
+            let verb = command.verb();
+            let sent = match command {
+                // Doesn't wait for whatever is in flight
+                Command::Abort => self
+                    .consort
+                    .send_superseding(command, &mut Urgent(&mut self.module)),
+                command => self.consort.send_command(command, &mut self.module),
+            };
+            match sent {
+                Ok(()) => {}
+                // Nothing went wrong, the operator may override the silence
+                Err(ConsortError::Silenced) => {
+                    self.journal.record(format!(
+                        "{} to {} refused, it expects radio silence",
+                        String::from_utf8_lossy(verb),
+                        self.consort.dest()
+                    ));
+                    self.set_mode(previous);
+                }
+                Err(_) => self.reset(),
             }
         }
         match self.established_connection_at {
@@ -1409,146 +2544,3005 @@ where
             }
             None => {
                 if self.connected() {
-                    self.established_connection_at = Some(Instant::now());
+                    self.established_connection_at = Some(self.clock.now());
                 }
             }
         }
     }
 
-    pub fn uptime(&self) -> Option<Duration> {
-        self.established_connection_at
-            .and_then(|timepoint| Some(Instant::now() - timepoint))
-    }
-
-    pub fn auto_reset_in(&self) -> Option<Duration> {
-        if self.mode.affected_by_timeout() {
-            if let Some(last_state_change) = self.last_state_change {
-                return Some(AUTO_RESET_TIMEOUT - Instant::now().duration_since(last_state_change));
+    pub fn load_reference_thrust(&mut self, path: &Path) {
+        match ReferenceCurve::load(path) {
+            Ok(reference) => {
+                self.journal
+                    .record(format!("Reference thrust curve {}", reference.name));
+                self.reference = Some(reference);
+            }
+            Err(err) => {
+                error!("Can't load reference thrust curve {:?}: {}", path, err);
+                self.journal
+                    .record(format!("Can't load reference thrust curve: {}", err));
             }
         }
-        None
     }
 
-    fn toggle_tab(&mut self, go_left: bool) -> ControlArea {
-        if !self.mode.reset_ongoing() {
-            if go_left {
-                self.mode = match self.mode {
-                    Mode::LaunchControl(_) => {
-                        Mode::Observables(ObservablesMode::Core(CoreConnection::Start))
-                    }
-                    Mode::Observables(_) => {
-                        Mode::RFSilence(RFSilenceMode::Core(CoreConnection::Start))
-                    }
-                    Mode::RFSilence(_) => {
-                        Mode::LaunchControl(LaunchControlMode::Core(CoreConnection::Start))
-                    }
-                }
-            } else {
-                self.mode = match self.mode {
-                    Mode::LaunchControl(_) => {
-                        Mode::RFSilence(RFSilenceMode::Core(CoreConnection::Start))
-                    }
-                    Mode::Observables(_) => {
-                        Mode::LaunchControl(LaunchControlMode::Core(CoreConnection::Start))
-                    }
-                    Mode::RFSilence(_) => {
-                        Mode::Observables(ObservablesMode::Core(CoreConnection::Start))
-                    }
+    // The latest ignition aligns the reference curve
+    pub fn ignition_at(&self) -> Option<f64> {
+        self.journal
+            .markers()
+            .filter(|(marker, _, _)| *marker == Marker::Ignition)
+            .last()
+            .map(|(_, at, _)| at)
+    }
+
+    // The latest ignition, or the rail exit if the rocket left without
+    // one. An ignition only accounts for the next rail exit.
+    fn anchor(&self) -> Option<(Marker, f64)> {
+        let mut commanded = false;
+        let mut anchor = None;
+        for (marker, at, _) in self.journal.markers() {
+            match marker {
+                Marker::Ignition => {
+                    anchor = Some((marker, at));
+                    commanded = true;
                 }
+                Marker::RailExit if !commanded => anchor = Some((marker, at)),
+                Marker::RailExit => commanded = false,
+                _ => {}
             }
         }
-        ControlArea::Tabs
+        anchor
     }
 
-    pub fn connected(&self) -> bool {
-        self.mode.core_mode().connected()
+    // Into the session, of the mission elapsed time
+    pub fn t0(&self) -> Option<f64> {
+        self.anchor().map(|(_, at)| at)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::consort::SimpleIdGenerator;
-    use crate::rqparser::command_parser;
-    use crate::rqprotocol::Node;
-    use std::assert_matches::assert_matches;
+    // The breakwire triggered without our ignition command, until
+    // a reset or abort
+    pub fn uncommanded_launch(&self) -> bool {
+        match self.anchor() {
+            Some((Marker::RailExit, at)) => self
+                .launch_dismissed_at
+                .map_or(true, |dismissed| at > dismissed),
+            _ => false,
+        }
+    }
 
-    use super::*;
+    fn dismiss_launch(&mut self) {
+        self.launch_dismissed_at = Some(self.elapsed().as_secs_f64());
+    }
 
-    struct MockConnection {
-        responses: Vec<Vec<u8>>,
+    // Summarized once the reference burn is over
+    fn drive_reference(&mut self) {
+        let (reference, ignition) = match (&self.reference, self.ignition_at()) {
+            (Some(reference), Some(ignition)) => (reference, ignition),
+            _ => return,
+        };
+        let summarized = self
+            .thrust_deviation
+            .map_or(false, |(at, _)| at == ignition);
+        if summarized || self.elapsed().as_secs_f64() < ignition + reference.burn_time() {
+            return;
+        }
+        if let Some(deviation) =
+            reference.deviation(&self.timeline.points(Series::Thrust), ignition)
+        {
+            let text = format!("Thrust vs. {}: {}", reference.name, deviation.summary());
+            self.journal.record(text);
+            self.thrust_deviation = Some((ignition, deviation));
+        }
     }
 
-    impl Connection for MockConnection {
-        fn recv(&mut self, callback: impl FnOnce(Answers)) {
-            if self.responses.len() > 0 {
-                let response = self.responses.pop().unwrap();
-                callback(Answers::Received(response));
+    pub fn enable_snapshots(&mut self) {
+        let recording = self.recorder_path.clone().unwrap_or_else(|| {
+            PathBuf::from(format!(
+                "{}-{}-pad",
+                self.clock.wall().format(FILE_TIME_FORMAT),
+                self.run.slug()
+            ))
+        });
+        self.snapshots = Some(Snapshots::new(&recording, self.bus.markers.subscribe()));
+    }
+
+    // Zero thrust and pressure on the currently averaged
+    // readings. Already downsampled data isn't re-computed.
+    pub fn tare_observables(&mut self) {
+        if let Some(offset) = self.tare.tare(self.clock.wall()) {
+            debug!("Tared observables: {:?}", offset);
+            if let Some(path) = &self.recorder_path {
+                if let Err(err) = tare::annotate(path, offset).context("Annotating tare") {
+                    self.error_reporter.report(err);
+                }
             }
         }
+    }
 
-        fn drain(&mut self) {
-            todo!()
+    pub fn start_self_test(&mut self) {
+        self.self_test.restart();
+        self.self_test_started = self.now;
+        self.self_test.record(
+            Check::Recorder,
+            check_recorder(self.recorder_path.as_deref()),
+        );
+    }
+
+    fn process_self_test_event(&mut self, event: &InputEvent) {
+        match event {
+            InputEvent::Enter => {
+                if self.self_test.acknowledge() && !self.self_test.passed() {
+                    // Launch Control is only accessible after a passed self-test
+                    if let Mode::LaunchControl(_) = self.mode {
+                        self.set_mode(Mode::Observables(ObservablesMode::Core(
+                            CoreConnection::Start,
+                        )));
+                    }
+                }
+            }
+            InputEvent::Back => {
+                self.start_self_test();
+                self.retry_now();
+            }
+            _ => {}
         }
+    }
 
-        fn open(&mut self, _port: &str) {
-            todo!()
+    // Takes over the settings that can change while running
+    pub fn reconfigure(&mut self, args: &ProgramArgs, mut reload: Reload) {
+        self.privacy = args.privacy;
+        self.accessibility = args.accessibility();
+        self.language = args.language;
+        self.telemetry.set_policy(args.retention_policy());
+        self.crank = args.crank();
+        self.freshness = args.freshness();
+        self.consort.set_backoff_policy(args.backoff_policy());
+        self.conformance.set_strict(args.strict);
+        self.module.set_duty_cycle_cap(args.duty_cycle_cap());
+        self.auto_record.policy = args.auto_record_policy();
+        self.idle.set_policy(args.idle_policy());
+        self.announcer.set_events(args.announce.clone());
+        if let Some(annotation) = self.annotator.set_labels(args.mark_labels.clone()) {
+            self.annotate(annotation);
+        }
+        self.filters = args.filters.clone();
+        self.plot_ranges = args.plot_ranges.clone();
+        self.units = args.units;
+        self.calibration.set_configured(args.imu_ranges.clone());
+        self.set_roster(args.telemetry_roster.clone());
+        self.set_launch_window(args.launch_window.clone());
+        self.tab_navigation = args.tab_navigation();
+        self.module.set_drain_idle(args.drain_idle());
+        self.set_key_entry_timeout(args.key_entry_timeout());
+        self.postmortem.set_config(args);
+        self.set_poll_nodes(&args.poll_nodes);
+        self.consort.set_link_keys(args.link_keys());
+        if !reload.needs_reconnect.is_empty() {
+            self.reconnect_to(args, &mut reload);
         }
+        if reload != Reload::default() {
+            self.journal.record(format!(
+                "Config reloaded, applied: {:?}, needs reconnect: {:?}, needs restart: {:?}",
+                reload.applied, reload.needs_reconnect, reload.needs_restart
+            ));
+        }
+        self.reload = Some(ReloadOutcome::Applied(reload));
+    }
 
-        fn reset(&mut self) {
-            todo!()
+    // To the ports of the config, unless that changes the radio during
+    // the launch sequence. They are then used on the next reconnect.
+    fn reconnect_to(&mut self, args: &ProgramArgs, reload: &mut Reload) {
+        let primary = args
+            .port
+            .clone()
+            .unwrap_or_else(|| self.failover.primary().into());
+        if !self
+            .failover
+            .set_ports(&primary, args.secondary_port.as_deref())
+        {
+            reload.needs_reconnect.clear();
+        } else if self.port_change_allowed() {
+            self.journal.record(format!(
+                "Radio port {} from the config, reconnecting",
+                self.failover.port()
+            ));
+            self.diagnostics.clear();
+            self.open_port();
+            reload.needs_reconnect.clear();
+        } else {
+            self.journal.record(format!(
+                "Radio port {} from the config, used on the next reconnect",
+                self.failover.port()
+            ));
         }
+    }
 
-        fn resume(&mut self) {
-            todo!()
+    // The nodes of the roster are the ones to calibrate
+    pub fn set_roster(&mut self, roster: Vec<ChannelConfig>) {
+        if roster != self.roster {
+            self.nrf_connector.borrow_mut().set_roster(&roster);
+            self.calibration.set_nodes(roster::nodes(&roster));
+            self.roster = roster;
         }
     }
 
-    impl std::io::Write for MockConnection {
-        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-            match command_parser(&buf[1..buf.len() - 4]) {
-                Ok((.., transaction)) => {
-                    let mut buffer = [0; MAX_BUFFER_SIZE];
-                    let response = transaction.acknowledge(&mut buffer).unwrap();
-                    self.responses.push(response.into());
-                    Ok(buf.len())
+    // Keeps what was journaled if it didn't change
+    pub fn set_launch_window(&mut self, config: Option<LaunchWindowConfig>) {
+        if self.launch_window.as_ref().map(LaunchWindow::config) != config.as_ref() {
+            self.launch_window = config.map(LaunchWindow::new);
+        }
+    }
+
+    pub fn launch_window(&self) -> Option<&WindowStatus> {
+        self.launch_window.as_ref()?.status()
+    }
+
+    // What holds key AB and the ignition, if a window is configured
+    fn launch_window_holding(&self) -> Option<String> {
+        let window = self.launch_window.as_ref()?;
+        match window.status() {
+            _ if window.allows_launch() => None,
+            Some(status) => Some(status.text()),
+            None => Some("Launch window not checked yet".into()),
+        }
+    }
+
+    // None without gpsd, else if we have a fix
+    pub fn gps_fix(&self) -> Option<bool> {
+        self.gps_time.as_ref().map(|gps| gps.now().is_some())
+    }
+
+    // Of the GPS while we have a fix
+    pub fn wall_time(&self) -> DateTime<Utc> {
+        self.gps_time
+            .as_ref()
+            .and_then(GpsTime::now)
+            .unwrap_or_else(|| self.clock.wall())
+    }
+
+    fn update_launch_window(&mut self) {
+        let now = self.wall_time();
+        let mut closed = false;
+        if let Some(window) = &mut self.launch_window {
+            let allowed = window.allows_launch();
+            for event in window.update(now) {
+                self.journal.record(event);
+            }
+            closed = allowed && !window.allows_launch();
+        }
+        if let Mode::LaunchControl(state) = self.mode {
+            match state.auto_hold(self.now) {
+                Some(held) if closed => {
+                    self.wake();
+                    self.journal.record(format!(
+                        "Auto-hold in {}, the ignition count starts over",
+                        state.name()
+                    ));
+                    self.set_mode(Mode::LaunchControl(held));
                 }
-                Err(_) => unreachable!("We should never receive wrong commands"),
+                _ => {}
             }
         }
+        let window = self.launch_window.as_ref().and_then(LaunchWindow::status);
+        self.announcements.extend(self.announcer.poll(window));
+    }
 
-        fn flush(&mut self) -> std::io::Result<()> {
-            Ok(())
+    pub fn take_announcements(&mut self) -> Vec<Announcement> {
+        std::mem::take(&mut self.announcements)
+    }
+
+    // Without any, only the target is polled and broadcast to
+    // Journaled, so replays decode the recording alike
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.profile = profile;
+        self.consort.set_profile(profile);
+        self.journal.record(session::profile_text(profile));
+    }
+
+    pub fn set_poll_nodes(&mut self, nodes: &[Node]) {
+        let nodes = match nodes {
+            [] => vec![self.consort.dest()],
+            nodes => nodes.to_vec(),
+        };
+        self.broadcast.set_nodes(nodes.clone());
+        self.module.set_poll_nodes(nodes);
+    }
+
+    pub fn reject_config(&mut self, reason: String) {
+        self.journal.record(format!("Config rejected: {}", reason));
+        self.reload = Some(ReloadOutcome::Rejected(reason));
+    }
+
+    // From any launch control page, whatever is shown on top
+    pub fn abort(&mut self) {
+        if let Mode::LaunchControl(mode) = self.mode {
+            if mode.abortable() {
+                self.help = false;
+                self.journal.record("Abort commanded");
+                self.dismiss_launch();
+                self.set_mode(Mode::LaunchControl(LaunchControlMode::SafeAbort));
+                self.control = ControlArea::Details;
+            }
         }
     }
 
-    //// #[test]
-    //// fn test_full_fsm_progression() {
-    ////     let connection = MockConnection { responses: vec![] };
-    ////     let now = Instant::now();
-    ////     let consort = Consort::new_with_id_generator(
-    ////         Node::LaunchControl,
-    ////         Node::RedQueen(b'A'),
-    ////         now,
-    ////         SimpleIdGenerator::default(),
-    ////     );
-    ////     let mut model = Model::new(
-    ////         consort,
-    ////         connection,
-    ////         now,
-    ////         "comport",
-    ////         &AdcGain::Gain64,
-    ////         true,
-    ////         None,
-    ////     );
-    ////     assert_matches!(model.mode(), Mode::LaunchControl(_));
-    ////     assert_eq!(model.control, ControlArea::Tabs);
-    ////     // Put us into reset
-    ////     model.drive(Instant::now()).unwrap();
-    ////     // progress to idle
-    ////     model.drive(Instant::now()).unwrap();
-    ////     assert_matches!(model.mode(), Mode::LaunchControl(LaunchControlState::Idle));
-    ////     model.process_input_event(&InputEvent::Enter);
-    ////     assert_eq!(model.control, ControlArea::Details);
-    ////     assert_matches!(model.mode(), Mode::LaunchControl(_));
-    //// }
+    pub fn toggle_help(&mut self) {
+        self.help = !self.help;
+    }
+
+    // What the input events do right now, beneath the help overlay
+    pub fn input_effects(&self) -> Vec<(InputEvent, &'static str)> {
+        use InputEvent::{Back, Enter, Left, Right, Send};
+        let step = crate::input::STEP_MAGNITUDE;
+        if !self.conformance.unacknowledged().is_empty() {
+            return vec![(Enter, "Acknowledge the protocol deviations")];
+        }
+        if self.annotator.is_picking() {
+            return vec![
+                (Left(step), "Previous label"),
+                (Right(step), "Next label"),
+                (Enter, "Mark with the label"),
+                (Back, "Mark without a label"),
+            ];
+        }
+        if self.port_picker.is_active() {
+            return vec![
+                (Left(step), "Previous port"),
+                (Right(step), "Next port"),
+                (Enter, "Use the port"),
+                (Back, "Keep the port"),
+            ];
+        }
+        if self.last_session.is_active() {
+            return match self.last_session.showing_full_journal() {
+                true => vec![(Enter, "Back to the summary"), (Back, "Dismiss")],
+                false => vec![(Enter, "Open the full journal"), (Back, "Dismiss")],
+            };
+        }
+        if self.self_test.is_active() {
+            return vec![(Enter, "Acknowledge result"), (Back, "Restart self-test")];
+        }
+        if self.monitor.is_active() {
+            return vec![
+                (Left(step), "Previous talker filter"),
+                (Right(step), "Next talker filter"),
+                (Enter, "Next sentence type filter"),
+                (Back, "Leave monitor"),
+            ];
+        }
+        if self.recovery.is_active() {
+            return vec![(Back, "Leave recovery")];
+        }
+        if self.roster_page.is_active() {
+            return vec![(Back, "Leave the telemetry roster")];
+        }
+        if self.sessions.is_active() {
+            return match self.sessions.replay() {
+                Some(_) => vec![
+                    (Left(step), "Previous sentence"),
+                    (Right(step), "Next sentence"),
+                    (Back, "Leave the replay"),
+                ],
+                None => vec![
+                    (Left(step), "Previous session"),
+                    (Right(step), "Next session"),
+                    (Enter, "Replay the session"),
+                    (Send, "Export the session"),
+                    (Back, "Leave the sessions"),
+                ],
+            };
+        }
+        if self.downloader.is_active() {
+            if self.downloader.confirm_delete {
+                return vec![(Enter, "Delete the file"), (Back, "Keep the file")];
+            }
+            return match self.downloader.is_running() {
+                true => vec![(Back, "Cancel download")],
+                false => vec![
+                    (Left(step), "Previous file"),
+                    (Right(step), "Next file"),
+                    (Enter, "Download file"),
+                    (Send, "Delete file"),
+                    (Back, "Leave downloads"),
+                ],
+            };
+        }
+        if self.broadcast.is_active() {
+            return match self.broadcast.is_running() {
+                true => vec![(Back, "Skip the remaining nodes")],
+                false => vec![
+                    (Left(step), "Previous command"),
+                    (Right(step), "Next command"),
+                    (Enter, "Send to all nodes"),
+                    (Back, "Leave broadcast"),
+                ],
+            };
+        }
+        if self.sequencer.is_active() {
+            return match self.sequencer.is_running() {
+                true => vec![(Enter, "Confirm prompt"), (Back, "Abort sequence")],
+                false => vec![
+                    (Left(step), "Previous sequence"),
+                    (Right(step), "Next sequence"),
+                    (Enter, "Run sequence"),
+                    (Back, "Leave sequences"),
+                ],
+            };
+        }
+        if self.tuner.is_active() {
+            return vec![
+                (Left(step), "Decrease parameter"),
+                (Right(step), "Increase parameter"),
+                (Enter, "Next parameter"),
+                (Back, "Leave tuning"),
+            ];
+        }
+        if self.filter_tuner.is_active() {
+            return vec![
+                (Left(step), "Previous setting"),
+                (Right(step), "Next setting"),
+                (Enter, "Next filter parameter"),
+                (Back, "Leave filters"),
+            ];
+        }
+        if self.calibration.is_active() {
+            return vec![
+                (Left(step), "Smaller range"),
+                (Right(step), "Larger range"),
+                (Enter, "Next range"),
+                (Send, "Take the reported ranges"),
+                (Back, "Leave calibration"),
+            ];
+        }
+        if self.adc.is_active() {
+            return vec![
+                (Left(step), "Shorter window"),
+                (Right(step), "Longer window"),
+                (Enter, "Tare"),
+                (Back, "Leave ADC readings"),
+            ];
+        }
+        if self.mode.core_mode().is_failure() {
+            return vec![(Enter, "Retry now"), (Send, "Switch radio")];
+        }
+        if self.control == ControlArea::Tabs {
+            return vec![
+                (Left(step), "Previous tab"),
+                (Right(step), "Next tab"),
+                (Enter, "Open tab"),
+                (Send, "Switch radio"),
+            ];
+        }
+        match self.mode {
+            Mode::Observables(_) => match self.onboard_recording {
+                OnboardRecording::Confirm(true) => {
+                    vec![(Enter, "Start onboard recording"), (Back, "Cancel")]
+                }
+                OnboardRecording::Confirm(false) => {
+                    vec![(Enter, "Stop onboard recording"), (Back, "Cancel")]
+                }
+                _ => vec![
+                    (Enter, "Tare thrust and pressure"),
+                    (Left(step), "Shift onboard data earlier"),
+                    (Right(step), "Shift onboard data later"),
+                    (Send, "Start or stop onboard recording"),
+                    (Back, "Back to tabs"),
+                ],
+            },
+            Mode::LaunchControl(state) => match state {
+                LaunchControlMode::PreArm { .. } => {
+                    vec![(Enter, "Acknowledge check"), (Back, "Abort")]
+                }
+                LaunchControlMode::EnterKeyA(entry)
+                | LaunchControlMode::EnterKeyB { entry, .. } => {
+                    let last = entry.position() + 1 == entry.digits().len();
+                    vec![
+                        (Left(step), "Decrease digit"),
+                        (Right(step), "Increase digit"),
+                        (Enter, if last { "Transmit key" } else { "Next digit" }),
+                        (
+                            Back,
+                            if entry.position() == 0 {
+                                "Abort"
+                            } else {
+                                "Previous digit"
+                            },
+                        ),
+                    ]
+                }
+                LaunchControlMode::PrepareUnlockPyros { .. } => {
+                    vec![(Right(step), "Keep turning to unlock"), (Back, "Abort")]
+                }
+                LaunchControlMode::PrepareIgnition { .. } => {
+                    vec![(Right(step), "Keep turning to arm"), (Back, "Abort")]
+                }
+                LaunchControlMode::WaitForFire { .. } => vec![(Enter, "Fire!"), (Back, "Abort")],
+                LaunchControlMode::Aborted => vec![(Enter, "Back to idle"), (Back, "Back to idle")],
+                _ => vec![],
+            },
+            Mode::RFSilence(state) => match state {
+                RFSilenceMode::WaitForEnter => {
+                    vec![(Enter, "Activate RF silence"), (Back, "Abort")]
+                }
+                RFSilenceMode::LeaveRadioSilence { .. } => {
+                    vec![(Right(step), "Keep turning to leave RF silence")]
+                }
+                _ => vec![],
+            },
+        }
+    }
+
+    pub fn toggle_monitor(&mut self) {
+        if self.monitor.is_active() {
+            self.monitor.stop();
+        } else {
+            self.monitor.start();
+        }
+    }
+
+    // At the key press, the label may follow
+    pub fn mark_moment(&mut self) {
+        let elapsed = self.elapsed().as_secs_f64();
+        if let Some(annotation) = self.annotator.mark(elapsed) {
+            self.annotate(annotation);
+        }
+    }
+
+    // Journaled when quitting, a journal without it is of a
+    // session that ended abnormally
+    pub fn end_session(&mut self) {
+        self.journal.record(session::ended_text(self.max_thrust_kn));
+    }
+
+    // On exit, in the order of shutdown.rs. The pyros of all nodes
+    // broadcast to are locked, whatever state they are in, an ACK
+    // isn't waited for.
+    pub fn shutdown(&mut self, timeout: Duration) -> ShutdownReport {
+        let mut report = ShutdownReport::default();
+        report.enter(Stage::StopPollers);
+        self.module.stop_polling();
+        report.enter(Stage::FlushRecording);
+        self.module.flush_recording();
+        report.enter(Stage::SafeState);
+        let nodes = match self.broadcast.nodes() {
+            [] => vec![self.consort.dest()],
+            nodes => nodes.to_vec(),
+        };
+        for node in nodes {
+            let sent = self.consort.send_superseding_to(
+                node,
+                Command::Abort,
+                &mut Urgent(&mut self.module),
+            );
+            match sent {
+                Ok(()) => self
+                    .journal
+                    .record(format!("Abort to {} on exit sent", node)),
+                Err(err) => {
+                    error!("Can't lock the pyros of {} on exit, {:?}", node, err);
+                    self.journal
+                        .record(format!("Abort to {} on exit not sent, {}", node, err));
+                }
+            }
+        }
+        report.enter(Stage::FlushJournal);
+        self.end_session();
+        report.enter(Stage::JoinWorkers);
+        let mut telemetry = self.nrf_connector.borrow_mut();
+        let workers = [self.module.worker(), telemetry.worker()]
+            .into_iter()
+            .flatten()
+            .collect();
+        report.stuck = shutdown::join_all(workers, timeout);
+        report
+    }
+
+    fn process_annotation_event(&mut self, event: &InputEvent) {
+        let annotation = match event {
+            InputEvent::Enter => self.annotator.confirm(),
+            InputEvent::Back => self.annotator.skip(),
+            _ => {
+                self.annotator.step(event.signed_steps());
+                None
+            }
+        };
+        if let Some(annotation) = annotation {
+            self.annotate(annotation);
+        }
+    }
+
+    fn annotate(&mut self, annotation: Annotation) {
+        self.journal
+            .mark(Marker::Mark, annotation.elapsed, annotation.text());
+    }
+
+    // For commanding a node out of the radio silence
+    pub fn toggle_silence_override(&mut self) {
+        let text = match self.silence.toggle_override() {
+            Some(true) => "Radio silence overridden, commands go out",
+            Some(false) => "Radio silence override withdrawn",
+            None => return,
+        };
+        self.journal.record(text);
+        self.consort.set_silenced(self.silence.inhibited());
+    }
+
+    fn link_statistics(&self) -> Vec<String> {
+        let backoff = self.consort.backoff_stats();
+        vec![
+            format!(
+                "Cause: {}",
+                self.diagnostics
+                    .cause
+                    .as_ref()
+                    .map_or("unknown".into(), FailureCause::description)
+            ),
+            format!(
+                "Radio: {} on {}",
+                self.failover.active().name(),
+                self.failover.port()
+            ),
+            format!("Mode: {:?}", self.mode),
+            format!(
+                "E32 reconfigurations: {}",
+                self.diagnostics.reconfigurations
+            ),
+            format!("Bursts: {}", self.diagnostics.bursts),
+            format!("Discarded bytes: {}", self.diagnostics.discarded_bytes),
+            format!("Malformed frames: {}", self.malformed_frames()),
+            format!("Protocol deviations: {}", self.conformance.total()),
+            format!("Backoff: {:?}", backoff),
+            format!("Duty cycle: {:?}", self.duty_cycle()),
+        ]
+    }
+
+    // Next to the recording, or in the bundle directory without one
+    pub fn write_postmortem(&mut self) {
+        if self.postmortem.is_writing() {
+            self.journal
+                .record("Diagnostic bundle still being written, not asked again");
+            return;
+        }
+        let target = match &self.recorder_path {
+            Some(path) => session::directory(path).join(BUNDLE_DIRECTORY),
+            None => self.bundle_directory.clone(),
+        };
+        let entries = self.journal.entries();
+        let bundle = Bundle {
+            sentences: self.postmortem.sentences(),
+            journal: entries[entries.len().saturating_sub(JOURNAL_TAIL)..]
+                .iter()
+                .map(|entry| format!("{} {}", entry.at.to_rfc3339(), entry.text))
+                .collect(),
+            config: self.postmortem.config().into(),
+            link: self.link_statistics(),
+            // The other threads can't be captured from here
+            backtrace: std::backtrace::Backtrace::force_capture(),
+        };
+        let at = self.wall_time();
+        self.postmortem.start(bundle, target, at);
+    }
+
+    // Without a recording there's no journal either
+    pub fn toggle_sessions(&mut self) {
+        if self.sessions.is_active() {
+            self.sessions.stop();
+            return;
+        }
+        let sessions = match &self.recorder_path {
+            Some(path) => session::sessions(
+                session::directory(path),
+                Some(&path.with_extension("journal")),
+                self.consort.dest(),
+            ),
+            None => vec![],
+        };
+        self.sessions.start(sessions);
+    }
+
+    fn process_sessions_event(&mut self, event: &InputEvent) {
+        if self.sessions.replay().is_some() {
+            match event {
+                InputEvent::Left(_) | InputEvent::Right(_) => {
+                    self.sessions.step(event.signed_steps())
+                }
+                InputEvent::Back => self.sessions.close(),
+                _ => {}
+            }
+            return;
+        }
+        match event {
+            InputEvent::Left(_) => self.sessions.select(-1),
+            InputEvent::Right(_) => self.sessions.select(1),
+            InputEvent::Enter => {
+                self.sessions.open(self.profile);
+            }
+            InputEvent::Send => match &self.export_directory {
+                Some(target) => {
+                    self.sessions.export_selected(self.profile, target.clone());
+                }
+                None => self.sessions.exported = Some(Err("no export directory configured".into())),
+            },
+            InputEvent::Back => self.sessions.stop(),
+        }
+    }
+
+    pub fn toggle_roster(&mut self) {
+        if self.roster_page.is_active() {
+            self.roster_page.stop();
+        } else {
+            self.roster_page.start();
+        }
+    }
+
+    // With the ports there are now
+    pub fn toggle_port_picker(&mut self, ports: Vec<PortInfo>) {
+        if self.port_picker.is_active() {
+            self.port_picker.stop();
+        } else {
+            self.port_picker.start(ports, self.failover.port());
+        }
+    }
+
+    pub fn toggle_recovery(&mut self) {
+        if self.recovery.is_active() {
+            self.recovery.stop();
+        } else {
+            self.recovery.start();
+        }
+    }
+
+    fn process_monitor_event(&mut self, event: &InputEvent) {
+        match event {
+            InputEvent::Left(_) => self.monitor.cycle_talker(false),
+            InputEvent::Right(_) => self.monitor.cycle_talker(true),
+            InputEvent::Enter => self.monitor.cycle_kind(),
+            InputEvent::Back => self.monitor.stop(),
+            InputEvent::Send => {}
+        }
+    }
+
+    pub fn toggle_downloads(&mut self) {
+        if self.downloader.is_active() {
+            self.downloader.stop();
+        } else {
+            self.downloader.start();
+            if self.capabilities().supports(Capabilities::FILE_MANAGE) {
+                self.downloader.refresh();
+            }
+        }
+    }
+
+    fn process_download_event(&mut self, event: &InputEvent) {
+        if self.downloader.confirm_delete {
+            match event {
+                InputEvent::Enter => self.delete_selected_file(),
+                InputEvent::Back => self.downloader.confirm_delete = false,
+                _ => {}
+            }
+            return;
+        }
+        match event {
+            InputEvent::Left(_) => self.downloader.select(-1),
+            InputEvent::Right(_) => self.downloader.select(1),
+            InputEvent::Enter => self.request_download(),
+            InputEvent::Send if self.capabilities().supports(Capabilities::FILE_MANAGE) => {
+                self.downloader.ask_delete();
+            }
+            InputEvent::Back => match &mut self.downloader.download {
+                Some(download) if !download.is_finished() => download.cancel(),
+                _ => self.downloader.stop(),
+            },
+            _ => {}
+        }
+    }
+
+    // The file still being recorded to stays
+    fn delete_selected_file(&mut self) {
+        let recording = self.onboard_file();
+        let selected = self
+            .downloader
+            .selected_file()
+            .map(|file| file.name.clone());
+        if let Some(name) = selected.filter(|name| Some(name) == recording.as_ref()) {
+            self.downloader.confirm_delete = false;
+            self.downloader.failure = Some(format!("{} is being recorded", name));
+            return;
+        }
+        if let Some(name) = self.downloader.delete_selected() {
+            self.journal
+                .record(format!("Deleting {} on {}", name, self.consort.dest()));
+        }
+    }
+
+    // Downloaded files go next to the recording. Without
+    // a listing only the onboard recording is known.
+    fn request_download(&mut self) {
+        if !self.capabilities().supports(Capabilities::FILE_READ) {
+            return;
+        }
+        let selected = self
+            .downloader
+            .selected_file()
+            .map(|file| file.name.clone());
+        let name = match selected.or_else(|| self.onboard_file()) {
+            Some(name) => name,
+            None => return,
+        };
+        let directory = self
+            .recorder_path
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new("."))
+            .to_path_buf();
+        if self.downloader.request(&name, &directory) {
+            self.journal
+                .record(format!("Downloading {} from {}", name, self.consort.dest()));
+        }
+    }
+
+    // The file the target reports to be recording to
+    pub fn onboard_file(&self) -> Option<String> {
+        match &self.target_obg2()?.recording_state {
+            RecordingState::Recording(name) => Some(name.clone()),
+            _ => None,
+        }
+    }
+
+    // Starting or stopping asks for confirmation first
+    fn request_onboard_recording(&mut self) {
+        if self.capabilities().supports(Capabilities::RECORDING) {
+            let recording = self.onboard_file().is_some();
+            self.onboard_recording.request(recording);
+        }
+    }
+
+    // Like downloads while watching observables, and ahead of arming,
+    // so a refusal can't get in the way of the launch sequence
+    fn onboard_commands_allowed(&self) -> bool {
+        match self.mode {
+            Mode::Observables(ObservablesMode::Core(CoreConnection::Idle))
+            | Mode::LaunchControl(
+                LaunchControlMode::PreArm { .. } | LaunchControlMode::EnterKeyA(_),
+            ) => !self.consort.busy(),
+            _ => false,
+        }
+    }
+
+    // False if no onboard recording command was in flight
+    fn onboard_recording_refused(&mut self) -> bool {
+        let in_flight = matches!(
+            self.consort.in_flight(),
+            Some(Command::RecordStart | Command::RecordStop)
+        );
+        if !in_flight || !self.onboard_recording.refused() {
+            return false;
+        }
+        self.journal.record("Onboard recording command refused");
+        true
+    }
+
+    fn drive_auto_record(&mut self) {
+        if !self.capabilities().supports(Capabilities::RECORDING) {
+            return;
+        }
+        // From the checklist on, the command can't go out once armed
+        let armed = match self.mode {
+            Mode::LaunchControl(state) => state.abortable(),
+            _ => false,
+        };
+        let thrust = self
+            .target_obg1()
+            .and_then(|history| history.last())
+            .map(|obg1| obg1.thrust.get::<kilonewton>());
+        let recording = self.onboard_file().is_some();
+        let text = match self.auto_record.update(self.now, armed, thrust, recording) {
+            Some(AutoAction::Start(trigger)) if self.onboard_recording.automatic(true) => {
+                match trigger {
+                    Trigger::Armed => {
+                        "Onboard recording started automatically, launch sequence".into()
+                    }
+                    Trigger::Thrust => format!(
+                        "Onboard recording started automatically, thrust above {:.1}kN",
+                        self.auto_record.policy.thrust_threshold
+                    ),
+                }
+            }
+            Some(AutoAction::Stop) if self.onboard_recording.automatic(false) => {
+                match self.max_thrust_kn {
+                    Some(thrust) => format!(
+                        "Onboard recording stopped automatically, {}",
+                        session::max_thrust_text(thrust)
+                    ),
+                    None => "Onboard recording stopped automatically".into(),
+                }
+            }
+            _ => return,
+        };
+        self.journal.record(text);
+    }
+
+    fn drive_onboard_recording(&mut self) {
+        self.drive_auto_record();
+        if let Some(command) = self.onboard_recording.command() {
+            if self.onboard_commands_allowed() {
+                match self.consort.send_command(command, &mut self.module) {
+                    Ok(_) => self.onboard_recording.sent(self.now),
+                    Err(_) => self.reset(),
+                }
+            }
+        }
+        let recording = self.onboard_file().is_some();
+        let text = match self.onboard_recording.observe(recording, self.now) {
+            Some(OnboardOutcome::Started) => "Onboard recording started",
+            Some(OnboardOutcome::Stopped) => "Onboard recording stopped",
+            Some(OnboardOutcome::Unconfirmed) => "Onboard recording change not reported by OBG2",
+            None => return,
+        };
+        self.journal.record(text);
+    }
+
+    // Downloads share the consort with the modes, so they only run
+    // while watching observables, which never sends commands itself.
+    fn drive_download(&mut self) {
+        if self.mode != Mode::Observables(ObservablesMode::Core(CoreConnection::Idle))
+            || self.consort.busy()
+        {
+            return;
+        }
+        if let Some(command) = self.downloader.next_command() {
+            if self
+                .consort
+                .send_command(command, &mut self.module)
+                .is_err()
+            {
+                self.reset();
+            }
+        }
+    }
+
+    pub fn toggle_broadcast(&mut self) {
+        if self.broadcast.is_active() {
+            self.broadcast.stop();
+        } else {
+            self.broadcast.start();
+        }
+    }
+
+    fn process_broadcast_event(&mut self, event: &InputEvent) {
+        let running = self.broadcast.is_running();
+        match event {
+            InputEvent::Left(_) => self.broadcast.select(-1),
+            InputEvent::Right(_) => self.broadcast.select(1),
+            InputEvent::Enter if !running => {
+                if self.broadcast.run_selected() {
+                    self.journal.record(format!(
+                        "Broadcast {} to {} nodes started",
+                        self.broadcast.selected().name(),
+                        self.broadcast.nodes().len()
+                    ));
+                }
+            }
+            InputEvent::Back if running => self.broadcast.cancel(),
+            InputEvent::Back => self.broadcast.stop(),
+            _ => {}
+        }
+    }
+
+    // Like downloads only while watching observables. The
+    // answers go to the broadcast instead of the modes.
+    fn drive_broadcast(&mut self) {
+        if self.mode == Mode::Observables(ObservablesMode::Core(CoreConnection::Idle))
+            && !self.consort.busy()
+        {
+            if let Some((node, broadcast)) = self.broadcast.next_node() {
+                let command = broadcast.command(self.adc_gain.clone());
+                // Only this node misses out, the others still get it
+                if let Err(err) = self
+                    .consort
+                    .send_command_to(node, command, &mut self.module)
+                {
+                    self.broadcast.not_sent();
+                    self.journal.record(format!(
+                        "Broadcast {} to {} not sent, {}",
+                        broadcast.name(),
+                        node,
+                        err
+                    ));
+                }
+            }
+        }
+        if let Some(summary) = self.broadcast.report() {
+            self.journal.record(summary);
+        }
+    }
+
+    pub fn toggle_sequences(&mut self) {
+        if self.sequencer.is_active() {
+            self.sequencer.stop();
+        } else {
+            self.sequencer.start();
+        }
+    }
+
+    // Only in dev builds, the pad uses the configured values
+    pub fn toggle_tuning(&mut self) {
+        if self.tuner.is_active() {
+            self.tuner.stop();
+        } else if cfg!(debug_assertions) {
+            self.tuner.start();
+        }
+    }
+
+    pub fn toggle_filters(&mut self) {
+        if self.filter_tuner.is_active() {
+            self.leave_filters();
+        } else {
+            self.filter_tuner.start();
+        }
+    }
+
+    // Once the page is left with changes, to be stored
+    pub fn take_changed_filters(&mut self) -> Option<Filters> {
+        if self.filter_tuner.is_active() {
+            return None;
+        }
+        std::mem::take(&mut self.filters_changed).then(|| self.filters.clone())
+    }
+
+    fn leave_filters(&mut self) {
+        self.filter_tuner.stop();
+        if self.filters_changed {
+            self.journal.record(format!(
+                "Plot filters set to thrust: {}, pressure: {}, window: {}, cutoff: {:.1}Hz",
+                self.filters.thrust.name(),
+                self.filters.pressure.name(),
+                self.filters.window,
+                self.filters.cutoff_hz
+            ));
+        }
+    }
+
+    pub fn toggle_calibration(&mut self) {
+        if self.calibration.is_active() {
+            self.leave_calibration();
+        } else {
+            self.calibration.start();
+        }
+    }
+
+    pub fn toggle_adc(&mut self) {
+        if self.adc.is_active() {
+            self.adc.stop();
+        } else {
+            self.adc.start();
+        }
+    }
+
+    // Once the page is left with changes, to be stored
+    pub fn take_changed_imu_ranges(&mut self) -> Option<HashMap<Node, ImuRanges>> {
+        self.calibration.take_changed()
+    }
+
+    fn leave_calibration(&mut self) {
+        self.calibration.stop();
+        if self.calibration.has_changes() {
+            let mut ranges: Vec<String> = self
+                .calibration
+                .configured()
+                .iter()
+                .map(|(node, ranges)| format!("{}: {}", node, ranges.text()))
+                .collect();
+            ranges.sort();
+            self.journal
+                .record(format!("IMU ranges set to {}", ranges.join(", ")));
+        }
+    }
+
+    // Only in dev builds, like the graph export
+    pub fn toggle_transitions(&mut self) {
+        self.transitions.visible = !self.transitions.visible && cfg!(debug_assertions);
+    }
+
+    // Only in dev builds, with the faults from the command line
+    pub fn toggle_faults(&mut self) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        let enabled = {
+            let mut faults = self.faults.borrow_mut();
+            faults.enabled = !faults.enabled;
+            faults.enabled
+        };
+        self.journal.record(format!(
+            "Fault injection {}",
+            if enabled { "on" } else { "off" }
+        ));
+    }
+
+    fn process_tuning_event(&mut self, event: &InputEvent) {
+        match event {
+            InputEvent::Left(_) | InputEvent::Right(_) => {
+                self.tuner.adjust(&mut self.crank, event.signed_steps())
+            }
+            InputEvent::Enter => self.tuner.next(),
+            InputEvent::Back => {
+                self.tuner.stop();
+                self.journal
+                    .record(format!("Progress tuned to {:?}", self.crank));
+            }
+            InputEvent::Send => {}
+        }
+    }
+
+    fn process_filter_event(&mut self, event: &InputEvent) {
+        match event {
+            InputEvent::Left(_) | InputEvent::Right(_) => {
+                self.filter_tuner
+                    .adjust(&mut self.filters, event.signed_steps());
+                self.filters_changed = true;
+            }
+            InputEvent::Enter => self.filter_tuner.next(),
+            InputEvent::Back => self.leave_filters(),
+            InputEvent::Send => {}
+        }
+    }
+
+    fn process_calibration_event(&mut self, event: &InputEvent) {
+        match event {
+            InputEvent::Left(_) | InputEvent::Right(_) => {
+                self.calibration.adjust(event.signed_steps())
+            }
+            InputEvent::Enter => self.calibration.next(),
+            InputEvent::Send => {
+                if let Some((node, ranges)) = self.calibration.adopt_reported() {
+                    self.journal.record(format!(
+                        "{} IMU ranges taken as reported, {}",
+                        node,
+                        ranges.text()
+                    ));
+                }
+            }
+            InputEvent::Back => self.leave_calibration(),
+        }
+    }
+
+    fn process_adc_event(&mut self, event: &InputEvent) {
+        match event {
+            InputEvent::Left(_) | InputEvent::Right(_) => self.adc.select(event.signed_steps()),
+            // The offset, on the readings averaged for it, never while
+            // the launch control is up. The statistics start over tared.
+            InputEvent::Enter => {
+                if let Mode::Observables(_) = self.mode {
+                    self.tare_observables();
+                    self.adc.clear();
+                }
+            }
+            InputEvent::Send => {}
+            InputEvent::Back => self.adc.stop(),
+        }
+    }
+
+    fn process_port_picker_event(&mut self, event: &InputEvent) {
+        match event {
+            InputEvent::Left(_) | InputEvent::Right(_) => {
+                self.port_picker.select(event.signed_steps())
+            }
+            InputEvent::Enter if !self.port_change_allowed() => {
+                self.port_picker.stop();
+                self.journal
+                    .record("Radio port not changed, the launch sequence is on");
+            }
+            InputEvent::Enter => {
+                if let Some(port) = self.port_picker.choose() {
+                    self.use_port(&port);
+                }
+            }
+            InputEvent::Back => self.port_picker.stop(),
+            InputEvent::Send => {}
+        }
+    }
+
+    // Not while the radio is needed for the launch
+    fn port_change_allowed(&self) -> bool {
+        matches!(
+            self.mode,
+            Mode::Observables(_)
+                | Mode::RFSilence(_)
+                | Mode::LaunchControl(LaunchControlMode::Core(_))
+        )
+    }
+
+    // Without a port to open, the operator picks one
+    fn open_port(&mut self) {
+        match self.failover.port() {
+            "" if !self.port_picker.is_active() => {
+                self.port_picker.start(ports::available(), "");
+            }
+            "" => {}
+            port => self.module.open(port),
+        }
+    }
+
+    // Re-opening leads to a reset
+    fn use_port(&mut self, port: &str) {
+        self.failover.set_primary(port);
+        self.journal.record(format!("Radio port {} picked", port));
+        self.diagnostics.clear();
+        self.module.open(port);
+    }
+
+    fn process_sequence_event(&mut self, event: &InputEvent) {
+        let running = self.sequencer.is_running();
+        match event {
+            InputEvent::Left(_) => self.sequencer.select(-1),
+            InputEvent::Right(_) => self.sequencer.select(1),
+            InputEvent::Enter if running => {
+                let run = self.sequencer.run.as_mut().unwrap();
+                if let Some(prompt) = run.acknowledge(self.now) {
+                    self.journal.record(format!("Confirmed: {}", prompt));
+                }
+            }
+            InputEvent::Enter => {
+                if let Some(name) = self.sequencer.run_selected(self.now) {
+                    self.journal.record(format!("Sequence {} started", name));
+                }
+            }
+            InputEvent::Back if running => self.sequencer.run.as_mut().unwrap().abort(),
+            InputEvent::Back => self.sequencer.stop(),
+            InputEvent::Send => {}
+        }
+    }
+
+    // The latest reading of the target, for waiting on conditions
+    fn quantity(&self, quantity: Quantity) -> Option<f64> {
+        match quantity {
+            Quantity::Vbb => self.target_obg2().map(|obg2| obg2.vbb_voltage as f64),
+            Quantity::Thrust => self
+                .target_obg1()?
+                .last()
+                .map(|obg1| obg1.thrust.get::<kilonewton>()),
+            Quantity::Pressure => self
+                .target_obg1()?
+                .last()
+                .map(|obg1| obg1.pressure.get::<bar>()),
+        }
+    }
+
+    // Like downloads, commands are only sent while watching observables
+    fn drive_sequence(&mut self) {
+        let mut run = match self.sequencer.run.take() {
+            Some(run) => run,
+            None => return,
+        };
+        if !run.is_finished() {
+            run.drive(self.now, |quantity| self.quantity(quantity));
+        }
+        if self.mode == Mode::Observables(ObservablesMode::Core(CoreConnection::Idle))
+            && !self.consort.busy()
+        {
+            if let Some(command) = run.next_command() {
+                if self
+                    .consort
+                    .send_command(command, &mut self.module)
+                    .is_err()
+                {
+                    self.reset();
+                }
+            }
+        }
+        if let Some(outcome) = run.report() {
+            let text = match outcome {
+                SequenceOutcome::Completed => "completed".to_string(),
+                SequenceOutcome::Aborted => "aborted".to_string(),
+                SequenceOutcome::Failed(reason) => format!("failed, {}", reason),
+            };
+            self.journal
+                .record(format!("Sequence {} {}", run.sequence.name, text));
+        }
+        self.sequencer.run = Some(run);
+    }
+
+    fn drive_self_test(&mut self) {
+        if !self.self_test.is_active() || self.self_test.finished() {
+            return;
+        }
+        if self.now.duration_since(self.self_test_started) > SELF_TEST_TIMEOUT {
+            if *self.self_test.verdict(Check::NRFModules) != Verdict::Passed {
+                self.self_test.record(
+                    Check::NRFModules,
+                    Verdict::Failed("No NRF modules found".into()),
+                );
+            }
+            self.self_test.expire();
+            return;
+        }
+        if !self.registered_nodes().is_empty() {
+            self.self_test.record(Check::NRFModules, Verdict::Passed);
+        }
+        if *self.self_test.verdict(Check::Ping) == Verdict::Pending
+            && self.connected()
+            && !self.consort.busy()
+        {
+            if self
+                .consort
+                .send_command(Command::Ping, &mut self.module)
+                .is_ok()
+            {
+                self.self_test.record(Check::Ping, Verdict::Running);
+            }
+        }
+    }
+
+    fn record_open_failure(&mut self, cause: &FailureCause) {
+        match cause {
+            FailureCause::PortUnavailable(reason) => {
+                self.self_test
+                    .record(Check::SerialPort, Verdict::Failed(reason.clone()));
+            }
+            FailureCause::ModuleConfiguration(reason) => {
+                self.self_test.record(Check::SerialPort, Verdict::Passed);
+                self.self_test
+                    .record(Check::E32Parameters, Verdict::Failed(reason.clone()));
+            }
+            _ => {}
+        }
+    }
+
+    // Don't wait for the automatic reset cycle, but
+    // re-open the port which leads to a reset.
+    pub fn retry_now(&mut self) {
+        debug!("Retrying connection on user request");
+        self.diagnostics.clear();
+        self.open_port();
+    }
+
+    // Counts a link failure and switches radios after too
+    // many in a row. Returns if we switched.
+    fn fail_over(&mut self) -> bool {
+        match self.failover.record_failure() {
+            Some(radio) => {
+                self.switch_to(radio, "link degraded");
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn switch_radio(&mut self) {
+        if let Some(radio) = self.failover.switch() {
+            self.switch_to(radio, "manual override");
+        }
+    }
+
+    fn switch_to(&mut self, radio: Radio, reason: &str) {
+        self.journal.record(format!(
+            "Switched to {} radio on {}, {}",
+            radio.name(),
+            self.failover.port(),
+            reason
+        ));
+        self.diagnostics.clear();
+        self.module.open(self.failover.port());
+    }
+
+    pub fn uptime(&self) -> Option<Duration> {
+        self.established_connection_at
+            .and_then(|timepoint| Some(self.clock.now() - timepoint))
+    }
+
+    pub fn auto_reset_in(&self) -> Option<Duration> {
+        if self.mode.affected_by_timeout() {
+            if let Some(last_state_change) = self.last_state_change {
+                return Some(
+                    AUTO_RESET_TIMEOUT - self.clock.now().duration_since(last_state_change),
+                );
+            }
+        }
+        None
+    }
+
+    // Negative steps go left
+    fn toggle_tab(&mut self, steps: i64) -> ControlArea {
+        let now = self.clock.now();
+        let debouncing = self.last_tab_change.map_or(false, |last| {
+            now.duration_since(last) < self.tab_navigation.debounce
+        });
+        if self.mode.reset_ongoing() || debouncing {
+            return ControlArea::Tabs;
+        }
+        let go_left = steps < 0;
+        let previous = self.mode;
+        // Only the tab landed on is logged
+        let mut tab = self.mode;
+        for _ in 0..steps.unsigned_abs() {
+            let mut mode = Self::next_tab(&tab, go_left);
+            while !self.tab_available(&mode) {
+                mode = Self::next_tab(&mode, go_left);
+            }
+            let wrapped = match go_left {
+                true => Self::tab_index(&mode) > Self::tab_index(&tab),
+                false => Self::tab_index(&mode) < Self::tab_index(&tab),
+            };
+            if wrapped && !self.tab_navigation.wrap {
+                break;
+            }
+            tab = mode;
+        }
+        self.set_mode(tab);
+        if self.mode != previous {
+            self.last_tab_change = Some(now);
+        }
+        ControlArea::Tabs
+    }
+
+    // Left to right
+    fn tab_index(mode: &Mode) -> usize {
+        match mode {
+            Mode::Observables(_) => 0,
+            Mode::LaunchControl(_) => 1,
+            Mode::RFSilence(_) => 2,
+        }
+    }
+
+    // Radio silence needs support by the node, Launch
+    // Control a passed self-test. Observables are always there.
+    fn tab_available(&self, mode: &Mode) -> bool {
+        match mode {
+            Mode::Observables(_) => true,
+            Mode::LaunchControl(_) => self.self_test.passed(),
+            Mode::RFSilence(_) => self.capabilities().supports(Capabilities::RF_SILENCE),
+        }
+    }
+
+    fn next_tab(mode: &Mode, go_left: bool) -> Mode {
+        if go_left {
+            match mode {
+                Mode::LaunchControl(_) => {
+                    Mode::Observables(ObservablesMode::Core(CoreConnection::Start))
+                }
+                Mode::Observables(_) => Mode::RFSilence(RFSilenceMode::Core(CoreConnection::Start)),
+                Mode::RFSilence(_) => {
+                    Mode::LaunchControl(LaunchControlMode::Core(CoreConnection::Start))
+                }
+            }
+        } else {
+            match mode {
+                Mode::LaunchControl(_) => {
+                    Mode::RFSilence(RFSilenceMode::Core(CoreConnection::Start))
+                }
+                Mode::Observables(_) => {
+                    Mode::LaunchControl(LaunchControlMode::Core(CoreConnection::Start))
+                }
+                Mode::RFSilence(_) => {
+                    Mode::Observables(ObservablesMode::Core(CoreConnection::Start))
+                }
+            }
+        }
+    }
+
+    pub fn connected(&self) -> bool {
+        self.mode.core_mode().connected()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::announcer::AnnouncedEvent;
+    use crate::backoff::BackoffPolicy;
+    use crate::clock::{Clock, SimulatedClock};
+    use crate::consort::SimpleIdGenerator;
+    use crate::faults::FaultInjector;
+    use crate::observables::groups::RawObservablesGroup1;
+    use crate::observables::groups::{RawObservablesGroup2, RawObservablesGroup3};
+    use crate::observables::{Ads1256Reading, ClkFreq, Timestamp};
+    use crate::rqparser::command_parser;
+    use crate::rqprotocol::Node;
+    use crate::shutdown::JOIN_TIMEOUT;
+    use crate::telemetry::sim::{SimConfig, SimulatedNRFConnector};
+    use std::assert_matches::assert_matches;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MockConnection {
+        responses: Vec<Vec<u8>>,
+        link_activity: Vec<LinkActivity>,
+        draining: bool,
+        // Of the next commands, unanswered
+        lost: usize,
+        timed_out: bool,
+        poll_timeout: Option<Node>,
+        sent: usize,
+    }
+
+    impl Connection for MockConnection {
+        fn recv(&mut self, callback: impl FnOnce(Answers)) {
+            if self.timed_out {
+                self.timed_out = false;
+                callback(Answers::Timeout);
+            } else if let Some(node) = self.poll_timeout.take() {
+                callback(Answers::PollTimeout(node));
+            } else if self.draining {
+                self.draining = false;
+                callback(Answers::Drained);
+            } else if self.responses.len() > 0 {
+                let response = self.responses.pop().unwrap();
+                callback(Answers::Received(response));
+            }
+        }
+
+        fn drain(&mut self) {
+            self.responses.clear();
+            self.draining = true;
+        }
+
+        fn open(&mut self, _port: &str) {}
+
+        fn reset(&mut self) {}
+
+        fn resume(&mut self) {}
+
+        fn link_activity(&mut self, activity: LinkActivity) {
+            self.link_activity.push(activity);
+        }
+
+        fn capabilities(&mut self, _capabilities: Capabilities) {}
+
+        fn monitor(&mut self, _monitor: bool) {}
+
+        fn set_drain_idle(&mut self, _drain_idle: Duration) {}
+
+        fn set_poll_nodes(&mut self, _nodes: Vec<Node>) {}
+
+        fn query_continuity(&mut self) {}
+
+        fn set_duty_cycle_cap(&mut self, _cap: Option<f64>) {}
+    }
+
+    impl std::io::Write for MockConnection {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.sent += 1;
+            if self.lost > 0 {
+                self.lost -= 1;
+                self.timed_out = true;
+                return Ok(buf.len());
+            }
+            match command_parser(&buf[1..buf.len() - 4]) {
+                Ok((.., transaction)) => {
+                    let mut buffer = [0; MAX_BUFFER_SIZE];
+                    let response = transaction.acknowledge(&mut buffer).unwrap();
+                    self.responses.push(response.into());
+                    Ok(buf.len())
+                }
+                Err(_) => unreachable!("We should never receive wrong commands"),
+            }
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_pre_arm_checklist_gates_key_entry() {
+        let now = Instant::now();
+        let crank = Crank::default();
+        let idle = LaunchControlMode::Core(CoreConnection::Idle);
+        let (mut mode, _) = idle.process_event(&InputEvent::Enter, now, &crank);
+        assert_eq!(mode.pre_arm_check(), Some(PreArmCheck::Battery));
+        assert_eq!(
+            mode.process_event(&InputEvent::Right(1), now, &crank).0,
+            LaunchControlMode::PreArm { acknowledged: 0 }
+        );
+        for check in [PreArmCheck::Pyro12, PreArmCheck::Pyro34] {
+            mode = mode.process_event(&InputEvent::Enter, now, &crank).0;
+            assert_eq!(mode.pre_arm_check(), Some(check));
+        }
+        assert_eq!(
+            mode.process_event(&InputEvent::Enter, now, &crank),
+            (
+                LaunchControlMode::EnterKeyA(DigitEntry::new(KEY_DIGITS, KEY_BASE)),
+                ControlArea::Details
+            )
+        );
+        assert_eq!(
+            mode.process_event(&InputEvent::Back, now, &crank),
+            (
+                LaunchControlMode::Core(CoreConnection::Start),
+                ControlArea::Tabs
+            )
+        );
+    }
+
+    #[test]
+    fn test_mode_timeouts_follow_the_clock() {
+        let clock = SimulatedClock::new(Instant::now());
+        let crank = Crank::default();
+        let tick = |elapsed| Tick {
+            now: clock.now(),
+            elapsed,
+        };
+        let mode = LaunchControlMode::Fire.process_response(Response::IgnitionAck, clock.now());
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(mode.drive(tick(Duration::from_secs(2)), &crank), mode);
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(
+            mode.drive(tick(Duration::from_secs(2)), &crank),
+            LaunchControlMode::SwitchToObservables
+        );
+        let mode = LaunchControlMode::PrepareUnlockPyros {
+            key_a: 0x12,
+            progress: 50,
+            last_update: clock.now(),
+        };
+        assert_eq!(mode.drive(tick(Duration::ZERO), &crank), mode);
+        // Stalled, only the last 100ms of it count, five whole ticks
+        clock.advance(Duration::from_millis(600));
+        assert_matches!(
+            mode.drive(tick(Duration::from_millis(600)), &crank),
+            LaunchControlMode::PrepareUnlockPyros { progress: 45, .. }
+        );
+    }
+
+    fn model(clock: &SimulatedClock) -> Model<MockConnection, SimpleIdGenerator> {
+        model_with(clock, MockConnection::default())
+    }
+
+    fn model_with<C: Connection>(
+        clock: &SimulatedClock,
+        connection: C,
+    ) -> Model<C, SimpleIdGenerator> {
+        let consort = Consort::new_with_id_generator(
+            Node::LaunchControl,
+            Node::RedQueen(b'B'),
+            clock.shared(),
+            SimpleIdGenerator::default(),
+        );
+        let mut model = Model::new(
+            consort,
+            connection,
+            clock.shared(),
+            Failover::new("comport", None, 3),
+            &AdcGain::Gain32,
+            LaunchMode::RFSilence,
+            Privacy::Off,
+            None,
+            RecorderStatus::default(),
+            RunId::from_bytes([0; 16]),
+            crate::telemetry::create_simulated(Default::default(), clock.shared()),
+        );
+        // Failures write one, not into the working directory
+        model.bundle_directory =
+            std::env::temp_dir().join(format!("lnc-bundles-{}", std::process::id()));
+        model
+    }
+
+    #[test]
+    fn test_standby_slows_polling_until_input() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.self_test.expire();
+        model.self_test.acknowledge();
+        model.mode = Mode::Observables(ObservablesMode::Core(CoreConnection::Idle));
+        model.drive().unwrap();
+        assert_eq!(
+            model.module.link_activity.last(),
+            Some(&LinkActivity::Polling(OBSERVABLES_POLL_INTERVAL))
+        );
+        clock.advance(Duration::from_secs(10 * 60));
+        model.drive().unwrap();
+        assert!(model.idle.is_standby());
+        assert_eq!(
+            model.module.link_activity.last(),
+            Some(&LinkActivity::Polling(Duration::from_secs(1)))
+        );
+        model.process_input_event(&InputEvent::Enter);
+        model.drive().unwrap();
+        assert!(!model.idle.is_standby());
+        assert_eq!(
+            model.module.link_activity.last(),
+            Some(&LinkActivity::Polling(OBSERVABLES_POLL_INTERVAL))
+        );
+    }
+
+    #[test]
+    fn test_wall_clock_steps_are_journaled() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.drive().unwrap();
+        clock.advance(Duration::from_millis(100));
+        clock.step_wall(chrono::Duration::seconds(90));
+        model.drive().unwrap();
+        let entry = model
+            .journal
+            .entries()
+            .iter()
+            .find(|entry| entry.text.starts_with("Wall clock stepped by +90.0s"))
+            .unwrap();
+        assert_eq!(entry.at, clock.wall());
+    }
+
+    #[test]
+    fn test_shutdown_locks_all_pyros_before_the_journal_ends() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        let (rqa, rqc) = (Node::RedQueen(b'A'), Node::RedQueen(b'C'));
+        model.set_poll_nodes(&[rqa, rqc]);
+        // Even if not connected
+        let report = model.shutdown(JOIN_TIMEOUT);
+        assert_eq!(
+            report.stages,
+            vec![
+                Stage::StopPollers,
+                Stage::FlushRecording,
+                Stage::SafeState,
+                Stage::FlushJournal,
+                Stage::JoinWorkers
+            ]
+        );
+        assert!(report.is_clean());
+        let texts: Vec<_> = model
+            .journal
+            .entries()
+            .iter()
+            .rev()
+            .take(3)
+            .map(|entry| entry.text.clone())
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                session::ended_text(model.max_thrust_kn),
+                "Abort to RQC on exit sent".to_string(),
+                "Abort to RQA on exit sent".to_string(),
+            ]
+        );
+        // The aborts, acknowledged right away by the mock
+        assert_eq!(model.module.responses.len(), 2);
+        assert!(model.consort.busy());
+    }
+
+    #[test]
+    fn test_modes_declare_link_activity() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.mode = Mode::RFSilence(RFSilenceMode::LeaveRadioSilence {
+            progress: 0,
+            last_update: clock.now(),
+        });
+        model.drive().unwrap();
+        assert_eq!(
+            model.module.link_activity.last(),
+            Some(&LinkActivity::Silent)
+        );
+        model.mode = Mode::RFSilence(RFSilenceMode::LeaveRadioSilence {
+            progress: 100,
+            last_update: clock.now(),
+        });
+        model.drive().unwrap();
+        assert_eq!(
+            model.module.link_activity.last(),
+            Some(&LinkActivity::Polling(POLL_INTERVAL))
+        );
+        model.mode = Mode::Observables(ObservablesMode::Core(CoreConnection::Idle));
+        model.drive().unwrap();
+        assert_eq!(
+            model.module.link_activity.last(),
+            Some(&LinkActivity::Polling(OBSERVABLES_POLL_INTERVAL))
+        );
+    }
+
+    #[test]
+    fn test_safety_key_inhibits_key_ab() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.self_test.expire();
+        model.self_test.acknowledge();
+        let entered = Mode::LaunchControl(LaunchControlMode::EnterKeyB {
+            key_a: 0x12,
+            entry: DigitEntry::with_value(KEY_DIGITS, KEY_BASE, 0x34),
+        });
+        model.mode = entered;
+        model.control = ControlArea::Details;
+        model.process_input_event(&InputEvent::Enter);
+        assert_eq!(model.mode, entered);
+        for _ in 0..3 {
+            model.safety_key.feed(Ok(true));
+        }
+        model.process_input_event(&InputEvent::Enter);
+        assert_matches!(
+            model.mode,
+            Mode::LaunchControl(LaunchControlMode::TransmitKeyAB { .. })
+        );
+    }
+
+    #[test]
+    fn test_key_entry_expires_before_the_auto_reset() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.mode = Mode::LaunchControl(LaunchControlMode::Core(CoreConnection::Idle));
+        model.set_mode(Mode::LaunchControl(LaunchControlMode::EnterKeyA(
+            DigitEntry::with_value(KEY_DIGITS, KEY_BASE, 0x12),
+        )));
+        clock.advance(Duration::from_secs(20));
+        model.drive().unwrap();
+        assert_eq!(model.key_entry_expires_in(), Some(Duration::from_secs(10)));
+        clock.advance(Duration::from_secs(10));
+        model.drive().unwrap();
+        assert_eq!(
+            model.mode,
+            Mode::LaunchControl(LaunchControlMode::Core(CoreConnection::Idle))
+        );
+        assert_eq!(model.key_entry_expires_in(), None);
+        assert!(model
+            .journal
+            .entries()
+            .iter()
+            .any(|entry| entry.text == "Key A entry expired after 30s"));
+        // With the pyros unlocked, they are locked again, the abort
+        // acknowledged right away by the mock
+        model.set_mode(Mode::LaunchControl(LaunchControlMode::EnterKeyB {
+            key_a: 0x12,
+            entry: DigitEntry::new(KEY_DIGITS, KEY_BASE),
+        }));
+        clock.advance(Duration::from_secs(30));
+        model.drive().unwrap();
+        assert_eq!(model.mode, Mode::LaunchControl(LaunchControlMode::Aborted));
+    }
+
+    #[test]
+    fn test_taped_input_plays_back_frame_by_frame() {
+        let path = std::env::temp_dir().join(format!("lnc-arming-{}.tape", std::process::id()));
+        let armed = |clock: &SimulatedClock| {
+            let mut model = model(clock);
+            model.self_test.expire();
+            model.self_test.acknowledge();
+            for _ in 0..3 {
+                model.safety_key.feed(Ok(true));
+            }
+            model.mode = Mode::LaunchControl(LaunchControlMode::EnterKeyB {
+                key_a: 0x12,
+                entry: DigitEntry::with_value(KEY_DIGITS, KEY_BASE, 0x34),
+            });
+            model.control = ControlArea::Details;
+            model
+        };
+        let run = |model: &mut Model<_, _>, clock: &SimulatedClock, live: fn(u64) -> Vec<_>| {
+            (1..=12)
+                .map(|frame| {
+                    clock.advance(Duration::from_millis(16));
+                    model.drive().unwrap();
+                    model.process_bindings(&live(frame));
+                    model.mode
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = armed(&clock);
+        model.record_input(&path);
+        let recorded = run(&mut model, &clock, |frame| match frame {
+            2 => vec![Binding::Event(InputEvent::Enter)],
+            5..=9 => vec![Binding::Event(InputEvent::Right(
+                crate::input::STEP_MAGNITUDE,
+            ))],
+            11 => vec![Binding::Abort],
+            _ => vec![],
+        });
+        assert_eq!(
+            recorded.last(),
+            Some(&Mode::LaunchControl(LaunchControlMode::Aborted))
+        );
+        assert_ne!(recorded.first(), recorded.last());
+
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = armed(&clock);
+        model.play_input(&path);
+        std::fs::remove_file(&path).unwrap();
+        // Ignored until the tape ran out
+        let played = run(&mut model, &clock, |frame| match frame {
+            1..=9 => vec![Binding::Event(InputEvent::Back), Binding::Abort],
+            _ => vec![],
+        });
+        assert_eq!(played, recorded);
+        assert!(model.playback.is_none());
+        assert!(model
+            .journal
+            .entries()
+            .iter()
+            .any(|entry| entry.text == "Input playback finished"));
+    }
+
+    #[test]
+    fn test_launch_window_holds_key_ab() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.self_test.expire();
+        model.self_test.acknowledge();
+        for _ in 0..3 {
+            model.safety_key.feed(Ok(true));
+        }
+        let entered = Mode::LaunchControl(LaunchControlMode::EnterKeyB {
+            key_a: 0x12,
+            entry: DigitEntry::with_value(KEY_DIGITS, KEY_BASE, 0x34),
+        });
+        let window = |open: i64, close: i64| LaunchWindowConfig {
+            open: Utc::now() + chrono::Duration::minutes(open),
+            close: Utc::now() + chrono::Duration::minutes(close),
+            holds: vec![],
+            warn_before_close_s: 300,
+        };
+        model.mode = entered;
+        model.control = ControlArea::Details;
+        model.set_launch_window(Some(window(-60, -1)));
+        model.update_launch_window();
+        assert_eq!(model.launch_window(), Some(&WindowStatus::Closed));
+        model.process_input_event(&InputEvent::Enter);
+        assert_eq!(model.mode, entered);
+        model.set_launch_window(Some(window(-1, 1)));
+        model.update_launch_window();
+        model.process_input_event(&InputEvent::Enter);
+        assert_matches!(
+            model.mode,
+            Mode::LaunchControl(LaunchControlMode::TransmitKeyAB { .. })
+        );
+        model.mode = Mode::LaunchControl(LaunchControlMode::WaitForFire {
+            key_a: 0x12,
+            key_b: 0x34,
+        });
+        // Closing while waiting for the fire
+        clock.advance(Duration::from_secs(120));
+        model.update_launch_window();
+        assert_eq!(
+            model.journal.entries().last().unwrap().text,
+            "Auto-hold in Wait for Fire, the ignition count starts over"
+        );
+        assert_matches!(
+            model.mode,
+            Mode::LaunchControl(LaunchControlMode::PrepareIgnition { progress: 0, .. })
+        );
+        model.mode = Mode::LaunchControl(LaunchControlMode::WaitForFire {
+            key_a: 0x12,
+            key_b: 0x34,
+        });
+        model.process_input_event(&InputEvent::Enter);
+        assert_eq!(
+            model.journal.entries().last().unwrap().text,
+            "Ignition held: Window closed, HOLD"
+        );
+    }
+
+    #[test]
+    fn test_countdown_is_announced_on_the_wall_clock() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        let mut args = ProgramArgs::default();
+        args.announce = vec![AnnouncedEvent::TMinus];
+        args.launch_window = Some(LaunchWindowConfig {
+            open: clock.wall() + chrono::Duration::seconds(11),
+            close: clock.wall() + chrono::Duration::minutes(60),
+            holds: vec![],
+            warn_before_close_s: 300,
+        });
+        model.reconfigure(&args, Reload::default());
+        model.update_launch_window();
+        assert!(model.take_announcements().is_empty());
+        clock.advance(Duration::from_millis(1500));
+        model.update_launch_window();
+        assert_eq!(model.take_announcements(), vec![Announcement::TMinus(10)]);
+        assert!(model.take_announcements().is_empty());
+    }
+
+    #[test]
+    fn test_abort_locks_the_pyros() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.self_test.expire();
+        model.self_test.acknowledge();
+        model.mode = Mode::LaunchControl(LaunchControlMode::Core(CoreConnection::Idle));
+        // Nothing to abort yet
+        model.abort();
+        assert_eq!(
+            model.mode,
+            Mode::LaunchControl(LaunchControlMode::Core(CoreConnection::Idle))
+        );
+        model.mode = Mode::LaunchControl(LaunchControlMode::WaitForFire {
+            key_a: 0x12,
+            key_b: 0x34,
+        });
+        model.help = true;
+        model.abort();
+        assert!(!model.help);
+        assert_eq!(
+            model.mode,
+            Mode::LaunchControl(LaunchControlMode::SafeAbort)
+        );
+        // Only the acknowledgement leaves it
+        model.process_input_event(&InputEvent::Back);
+        assert_eq!(
+            model.mode,
+            Mode::LaunchControl(LaunchControlMode::SafeAbort)
+        );
+        model.drive().unwrap();
+        assert_eq!(model.mode, Mode::LaunchControl(LaunchControlMode::Aborted));
+        assert_eq!(
+            model.journal.entries().last().unwrap().marker.map(|m| m.0),
+            Some(Marker::Abort)
+        );
+        model.process_input_event(&InputEvent::Enter);
+        assert_eq!(model.mode.core_mode(), CoreConnection::Start);
+        assert_eq!(model.consort.invariants().count(), 0);
+    }
+
+    #[test]
+    fn test_lost_aborts_are_sent_again() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.self_test.expire();
+        model.self_test.acknowledge();
+        model.mode = Mode::LaunchControl(LaunchControlMode::WaitForFire {
+            key_a: 0x12,
+            key_b: 0x34,
+        });
+        model.module.lost = 2;
+        model.abort();
+        for _ in 0..2 {
+            model.drive().unwrap();
+            assert_eq!(
+                model.mode,
+                Mode::LaunchControl(LaunchControlMode::SafeAbort)
+            );
+        }
+        // Not even the automatic reset gives up on it
+        clock.advance(AUTO_RESET_TIMEOUT * 2);
+        model.drive().unwrap();
+        assert_eq!(model.mode, Mode::LaunchControl(LaunchControlMode::Aborted));
+        assert_eq!(model.module.sent, 3);
+    }
+
+    #[test]
+    fn test_full_fsm_progression() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        assert_matches!(model.mode, Mode::RFSilence(_));
+        assert_eq!(model.control, ControlArea::Tabs);
+        // Put us into reset
+        model.drive().unwrap();
+        assert_eq!(model.mode.core_mode(), CoreConnection::Reset);
+        // Reset acknowledged, discover the node's version, then idle
+        model.drive().unwrap();
+        assert_eq!(model.mode.core_mode(), CoreConnection::Discover);
+        model.drive().unwrap();
+        assert_eq!(
+            model.mode,
+            Mode::RFSilence(RFSilenceMode::Core(CoreConnection::Idle))
+        );
+        // Out of the self-test overlay
+        model.self_test.expire();
+        model.self_test.acknowledge();
+        model.process_input_event(&InputEvent::Enter);
+        assert_eq!(model.control, ControlArea::Details);
+        assert_matches!(model.mode, Mode::RFSilence(_));
+        assert_eq!(model.consort.invariants().count(), 0);
+    }
+
+    #[test]
+    fn test_mixed_module_sets() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        let mut sim = SimulatedNRFConnector::new(
+            SimConfig {
+                dropout: 0.0,
+                ..Default::default()
+            },
+            clock.shared(),
+        );
+        clock.advance(Duration::from_millis(100));
+        let (rqb, rqt, fdt) = (
+            Node::RedQueen(b'B'),
+            Node::RedQueen(b'T'),
+            Node::Farduino(b'T'),
+        );
+        let frames = sim.drive();
+        let mut raw: Vec<RawTelemetryPacket> = frames
+            .iter()
+            .filter(|p| matches!(p, RawTelemetryPacket::Frame(node, _) if *node == rqb))
+            .cloned()
+            .collect();
+        raw.extend([
+            RawTelemetryPacket::NoModule(rqt),
+            RawTelemetryPacket::NoModule(fdt),
+        ]);
+        model.process_raw_telemetry(&raw);
+        assert_eq!(model.module_status(&rqb), Some(ModuleStatus::Receiving));
+        assert_eq!(model.module_status(&rqt), Some(ModuleStatus::NoModule));
+        assert_eq!(model.module_status(&Node::Farduino(b'B')), None);
+        assert!(model.telemetry_data_for_node(&rqb).is_some());
+        assert!(model.telemetry_data_for_node(&rqt).is_none());
+        // A module showing up late is picked up
+        let late: Vec<RawTelemetryPacket> = frames
+            .into_iter()
+            .filter(|p| matches!(p, RawTelemetryPacket::Frame(node, _) if *node == rqt))
+            .collect();
+        model.process_raw_telemetry(&late);
+        assert_eq!(model.module_status(&rqt), Some(ModuleStatus::Receiving));
+        assert_eq!(model.module_status(&fdt), Some(ModuleStatus::NoModule));
+        // A module sending pieces is still receiving
+        model.process_raw_telemetry(&vec![RawTelemetryPacket::Malformed(
+            fdt,
+            "incomplete frame of 5 bytes dropped".into(),
+        )]);
+        assert_eq!(model.module_status(&fdt), Some(ModuleStatus::Receiving));
+        assert_eq!(model.malformed_frames(), 1);
+    }
+
+    #[test]
+    fn test_reported_imu_ranges_are_verified() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.self_test.expire();
+        model.self_test.acknowledge();
+        let rqb = Node::RedQueen(b'B');
+        model.calibration.set_nodes(vec![rqb]);
+        // 6g and 250dps
+        let config = RawTelemetryPacket::Frame(rqb, vec![7, 3, 0, 0, 0, 0, 1, 3]);
+        model.process_raw_telemetry(&vec![config.clone()]);
+        assert_eq!(model.calibration.mismatched(), vec![rqb]);
+        assert_eq!(
+            model.journal.entries().last().unwrap().text,
+            "RQB reports IMU ranges 6g/250dps, configured are 24g/2000dps"
+        );
+        model.toggle_calibration();
+        model.process_input_event(&InputEvent::Send);
+        assert_eq!(model.take_changed_imu_ranges(), None);
+        model.process_input_event(&InputEvent::Back);
+        assert!(!model.calibration.is_active());
+        assert_eq!(
+            model.journal.entries().last().unwrap().text,
+            "IMU ranges set to RQB: 6g/250dps"
+        );
+        let stored = model.take_changed_imu_ranges().unwrap();
+        assert_eq!(stored[&rqb].text(), "6g/250dps");
+        // Heard again after a reboot, nothing new
+        let entries = model.journal.entries().len();
+        model.process_raw_telemetry(&vec![config]);
+        assert_eq!(model.journal.entries().len(), entries);
+        assert!(model.calibration.mismatched().is_empty());
+    }
+
+    #[test]
+    fn test_input_never_waits_for_telemetry() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.self_test.expire();
+        model.self_test.acknowledge();
+        model.mode = Mode::LaunchControl(LaunchControlMode::Core(CoreConnection::Idle));
+        model.control = ControlArea::Details;
+        let mut frame = b"A\x00~\xdcvV".to_vec();
+        frame.extend([0, 0, 0x2c, 0x10, 3]);
+        frame.resize(32, 0);
+        let packets = vec![RawTelemetryPacket::Frame(Node::RedQueen(b'B'), frame); 5];
+        model.budget = FrameBudget::new(Duration::ZERO);
+        model.budget.start(clock.now(), false);
+        clock.advance(Duration::from_millis(1));
+        model.process_raw_telemetry(&packets);
+        assert_eq!(model.backlog.len(), 4);
+        model.process_bindings(&[Binding::Event(InputEvent::Enter)]);
+        assert_eq!(
+            model.mode,
+            Mode::LaunchControl(LaunchControlMode::PreArm { acknowledged: 0 })
+        );
+        assert_eq!(model.backlog.len(), 4);
+    }
+
+    #[test]
+    fn test_backfilled_telemetry_keeps_its_time() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        let rqb = Node::RedQueen(b'B');
+        let mut frame = b"A\x00~\xdcvV".to_vec();
+        frame.extend([0, 0, 0x2c, 0x10, 3]);
+        frame.resize(32, 0);
+        clock.advance(Duration::from_secs(10));
+        model.drive().unwrap();
+        model.process_raw_telemetry(&vec![
+            RawTelemetryPacket::Backfilled(rqb, frame.clone(), Duration::from_secs(4)),
+            RawTelemetryPacket::Frame(rqb, frame),
+        ]);
+        let received: Vec<_> = model
+            .telemetry_data_for_node(&rqb)
+            .unwrap()
+            .iter()
+            .map(|stored| stored.received)
+            .collect();
+        assert_eq!(
+            received,
+            vec![Duration::from_secs(6), Duration::from_secs(10)]
+        );
+    }
+
+    #[test]
+    fn test_radio_silence_reported_by_telemetry_holds_commands() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.mode = Mode::Observables(ObservablesMode::Core(CoreConnection::Idle));
+        let rqb = Node::RedQueen(b'B');
+        let state = |ignition: u8| {
+            let mut frame = b"A\x00~\xdcvV".to_vec();
+            frame.extend([ignition, 0, 0x2c, 0x10, 3]);
+            frame.resize(32, 0);
+            RawTelemetryPacket::Frame(rqb, frame)
+        };
+        model.process_raw_telemetry(&vec![state(5)]);
+        assert_eq!(
+            model.journal.entries().last().unwrap().text,
+            "RQB expects radio silence, commands to it held back"
+        );
+        assert_eq!(model.link_activity(), LinkActivity::Silent);
+        model.reset();
+        assert!(model.reset_held);
+        assert!(model.module.responses.is_empty());
+        // Commanded out of it by the operator
+        model.toggle_silence_override();
+        assert!(!model.consort.is_silenced(rqb));
+        model.reset();
+        assert_eq!(model.module.responses.len(), 1);
+        model.module.responses.clear();
+        model.consort.reset();
+        model.toggle_silence_override();
+        // Ignition is refused without losing the armed state
+        let armed = Mode::LaunchControl(LaunchControlMode::WaitForFire {
+            key_a: 0x12,
+            key_b: 0x34,
+        });
+        model.mode = armed;
+        model.set_mode(Mode::LaunchControl(LaunchControlMode::Fire));
+        assert_eq!(model.mode, armed);
+        assert_eq!(
+            model.journal.entries().last().unwrap().text,
+            "IGNITION to RQB refused, it expects radio silence"
+        );
+        // An abort always goes out
+        model.abort();
+        assert_eq!(model.module.responses.len(), 1);
+        model.toggle_silence_override();
+        model.process_raw_telemetry(&vec![state(0)]);
+        assert_eq!(
+            model.journal.entries().last().unwrap().text,
+            "RQB left radio silence"
+        );
+        assert!(!model.silence.is_overridden());
+    }
+
+    #[test]
+    fn test_strict_mode_stops_at_protocol_deviations() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.mode = Mode::Observables(ObservablesMode::Core(CoreConnection::Idle));
+        model.conformance.set_strict(true);
+        // Through the reset cycle, nothing in flight after
+        model.drive().unwrap();
+        assert!(!model.consort.busy());
+        let dest = model.consort.dest();
+        let mut formatter = crate::rqparser::NMEAFormatter::default();
+        formatter
+            .format_sentence(format!("{}ACK,001,LNC", dest).as_bytes())
+            .unwrap();
+        model
+            .module
+            .responses
+            .push(formatter.buffer().unwrap().to_vec());
+        model.drive().unwrap();
+        assert!(model.journal.entries().iter().any(|entry| entry.text
+            == format!(
+                "Protocol deviation of {}: unsolicited sentence, {}",
+                dest,
+                String::from_utf8_lossy(formatter.buffer().unwrap()).trim_end()
+            )));
+        assert_eq!(
+            model.input_effects(),
+            vec![(InputEvent::Enter, "Acknowledge the protocol deviations")]
+        );
+        model.process_input_event(&InputEvent::Enter);
+        assert!(model.conformance.unacknowledged().is_empty());
+        assert_eq!(model.conformance.total(), 1);
+    }
+
+    #[test]
+    fn test_port_is_picked() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        let ports = ["COM3", "comport", "COM7"]
+            .into_iter()
+            .map(|name| PortInfo {
+                name: name.into(),
+                description: "FTDI".into(),
+            })
+            .collect();
+        model.toggle_port_picker(ports);
+        assert_eq!(model.port_picker.selected, 1);
+        model.process_input_event(&InputEvent::Right(crate::input::STEP_MAGNITUDE));
+        model.process_input_event(&InputEvent::Enter);
+        assert!(!model.port_picker.is_active());
+        assert_eq!(model.failover.port(), "COM7");
+        assert_eq!(model.port_picker.take_chosen(), Some("COM7".into()));
+        assert_eq!(
+            model.journal.entries().last().unwrap().text,
+            "Radio port COM7 picked"
+        );
+    }
+
+    #[test]
+    fn test_port_stays_during_the_launch_sequence() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = faulty_model(&clock, Failover::new("", None, 3));
+        // Nothing to open, the operator is asked instead
+        model.retry_now();
+        assert!(model.port_picker.is_active());
+        model.port_picker.stop();
+        model.mode = Mode::LaunchControl(LaunchControlMode::PreArm { acknowledged: 0 });
+        let ports = vec![PortInfo {
+            name: "COM3".into(),
+            description: "FTDI".into(),
+        }];
+        model.toggle_port_picker(ports);
+        model.process_input_event(&InputEvent::Enter);
+        assert!(!model.port_picker.is_active());
+        assert_eq!(model.failover.port(), "");
+        assert_eq!(model.port_picker.take_chosen(), None);
+        assert_eq!(
+            model.journal.entries().last().unwrap().text,
+            "Radio port not changed, the launch sequence is on"
+        );
+    }
+
+    #[test]
+    fn test_config_ports_are_reconnected_to() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        let mut args = ProgramArgs::default();
+        args.port = Some("COM4".into());
+        let reload = Reload {
+            needs_reconnect: vec!["port"],
+            ..Default::default()
+        };
+        model.reconfigure(&args, reload.clone());
+        assert_eq!(model.failover.port(), "COM4");
+        assert_eq!(
+            model.reload,
+            Some(ReloadOutcome::Applied(Reload::default()))
+        );
+        // Held during the launch sequence, and flagged until then
+        model.mode = Mode::LaunchControl(LaunchControlMode::PreArm { acknowledged: 0 });
+        args.port = Some("COM5".into());
+        model.reconfigure(&args, reload.clone());
+        assert_eq!(model.failover.port(), "COM5");
+        assert_eq!(model.reload, Some(ReloadOutcome::Applied(reload)));
+        assert_eq!(
+            model.journal.entries().last().unwrap().text,
+            "Config reloaded, applied: [], needs reconnect: [\"port\"], needs restart: []"
+        );
+    }
+
+    #[test]
+    fn test_work_over_budget_is_deferred() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        let mut sim = SimulatedNRFConnector::new(
+            SimConfig {
+                dropout: 0.0,
+                ..Default::default()
+            },
+            clock.shared(),
+        );
+        let raw = sim.drive();
+        let stored = |model: &Model<MockConnection, SimpleIdGenerator>| {
+            model
+                .registered_nodes()
+                .iter()
+                .filter_map(|node| model.telemetry_data_for_node(node))
+                .map(VecDeque::len)
+                .sum::<usize>()
+        };
+        model.drive().unwrap();
+        clock.advance(crate::budget::FRAME_BUDGET * 2);
+        model.process_raw_telemetry(&raw);
+        assert_eq!(stored(&model), 1);
+        model.drive().unwrap();
+        assert_eq!(stored(&model), raw.len());
+        assert!(!model.overloaded());
+    }
+
+    fn faulty_model(
+        clock: &SimulatedClock,
+        failover: Failover,
+    ) -> Model<FaultInjector<MockConnection>, SimpleIdGenerator> {
+        let faults: SharedFaults = Default::default();
+        let connection =
+            FaultInjector::new(MockConnection::default(), faults.clone(), clock.shared());
+        let mut model = model_with(clock, connection);
+        model.failover = failover;
+        model.faults = faults;
+        model
+    }
+
+    #[test]
+    fn test_failures_write_a_bundle_without_a_recording() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        let target = std::env::temp_dir().join(format!("lnc-failure-{}", std::process::id()));
+        model.bundle_directory = target.clone();
+        model.set_mode(model.mode.failure_mode());
+        assert!(model.postmortem.is_writing());
+        // Only once at a time
+        model.write_postmortem();
+        while model.postmortem.is_writing() {
+            std::thread::sleep(Duration::from_millis(10));
+            model.drive().unwrap();
+        }
+        let Some(Ok(directory)) = &model.postmortem.written else {
+            panic!("no bundle");
+        };
+        assert!(directory.starts_with(&target));
+        assert!(directory.join("backtrace.txt").exists());
+        let texts: Vec<_> = model
+            .journal
+            .entries()
+            .iter()
+            .map(|entry| entry.text.clone())
+            .filter(|text| text.starts_with("Diagnostic bundle"))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Diagnostic bundle still being written, not asked again".to_string(),
+                format!("Diagnostic bundle written to {}", directory.display()),
+            ]
+        );
+        std::fs::remove_dir_all(&target).unwrap();
+    }
+
+    #[test]
+    fn test_bursts_are_fed_whole() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.drive().unwrap();
+        assert_eq!(model.mode.core_mode(), CoreConnection::Reset);
+        // The ACK of the reset, with line noise trailing in the same read
+        let noise = b"\x00\xff".repeat(MAX_BUFFER_SIZE);
+        model.module.responses[0].extend_from_slice(&noise);
+        model.drive().unwrap();
+        assert_eq!(model.mode.core_mode(), CoreConnection::Discover);
+        assert_eq!(model.diagnostics.bursts, 1);
+        assert_eq!(model.diagnostics.discarded_bytes, 0);
+        model.drive().unwrap();
+        assert_eq!(model.mode.core_mode(), CoreConnection::Idle);
+    }
+
+    fn drive_frames<C: Connection>(
+        clock: &SimulatedClock,
+        model: &mut Model<C, SimpleIdGenerator>,
+    ) {
+        for _ in 0..10 {
+            model.drive().unwrap();
+            clock.advance(Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn test_recovers_from_injected_faults() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = faulty_model(&clock, Failover::new("comport", None, 3));
+        for faults in [
+            Faults {
+                drop_acks: 2,
+                ..Default::default()
+            },
+            Faults {
+                corrupt_probability: 0.5,
+                ..Default::default()
+            },
+            Faults {
+                delay: Duration::from_millis(250),
+                ..Default::default()
+            },
+            Faults {
+                nak_verb: Some("RESET".into()),
+                ..Default::default()
+            },
+        ] {
+            *model.faults.borrow_mut() = Faults {
+                enabled: true,
+                ..faults
+            };
+            model.reset();
+            drive_frames(&clock, &mut model);
+            model.toggle_faults();
+            // Waiting out the backoff the faults caused
+            for _ in 0..4 {
+                drive_frames(&clock, &mut model);
+            }
+            assert_eq!(
+                model.mode,
+                Mode::RFSilence(RFSilenceMode::Core(CoreConnection::Idle))
+            );
+            assert!(!model.consort.busy());
+        }
+        assert_eq!(model.module.inner().responses.len(), 0);
+    }
+
+    #[test]
+    fn test_nak_keeps_the_radio() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = faulty_model(&clock, Failover::new("comport", Some("backup"), 1));
+        *model.faults.borrow_mut() = Faults {
+            enabled: true,
+            nak_verb: Some("PING".into()),
+            ..Default::default()
+        };
+        drive_frames(&clock, &mut model);
+        assert_eq!(model.failover.active(), Radio::Primary);
+        assert_ne!(*model.self_test.verdict(Check::Ping), Verdict::Passed);
+        model.toggle_faults();
+        drive_frames(&clock, &mut model);
+        assert_eq!(*model.self_test.verdict(Check::Ping), Verdict::Passed);
+    }
+
+    #[test]
+    fn test_nak_holds_off_the_next_reset() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = faulty_model(&clock, Failover::new("comport", None, 3));
+        model.consort.set_backoff_policy(BackoffPolicy {
+            initial: Duration::from_millis(1000),
+            max_hold: Duration::from_millis(1000),
+            jitter: 0.0,
+        });
+        *model.faults.borrow_mut() = Faults {
+            enabled: true,
+            nak_verb: Some("RESET".into()),
+            ..Default::default()
+        };
+        model.reset();
+        while model.consort.backoff_stats().failures == 0 {
+            model.drive().unwrap();
+        }
+        assert!(!model.consort.busy());
+        clock.advance(Duration::from_millis(900));
+        model.drive().unwrap();
+        assert_eq!(model.consort.backoff_stats().backoffs, 1);
+        // Only now the reset goes out again, and is refused again
+        clock.advance(Duration::from_millis(100));
+        model.drive().unwrap();
+        assert_eq!(model.consort.backoff_stats().backoffs, 2);
+    }
+
+    #[test]
+    fn test_errors_are_journaled_with_their_origin() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = faulty_model(&clock, Failover::new("comport", None, 3));
+        *model.faults.borrow_mut() = Faults {
+            enabled: true,
+            nak_verb: Some("PING".into()),
+            ..Default::default()
+        };
+        let reporter = model.error_reporter();
+        std::thread::spawn(move || {
+            for _ in 0..3 {
+                reporter.report(Error::Telemetry {
+                    node: Node::RedQueen(b'B'),
+                    reason: "short frame".into(),
+                });
+            }
+        })
+        .join()
+        .unwrap();
+        drive_frames(&clock, &mut model);
+        let errors: Vec<_> = model
+            .journal
+            .entries()
+            .iter()
+            .map(|entry| entry.text.as_str())
+            .filter(|text| text.contains(" error: "))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "Telemetry error: RQB: short frame",
+                "Consort error: Command refused: Nak",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_thrust_is_compared_after_the_burn() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        let path = std::env::temp_dir().join(format!("lnc-reference-{}.eng", std::process::id()));
+        std::fs::write(&path, "X1000 75 500 0 1.0 2.0 ARR\n0.5 2000.0\n1.0 0.0\n").unwrap();
+        model.load_reference_thrust(&path);
+        std::fs::remove_file(&path).unwrap();
+        model.journal.mark(Marker::Ignition, 1.0, "Ignition");
+        for i in 0..=30 {
+            let time = i as f64 / 10.0;
+            let thrust = if (1.0..2.0).contains(&time) { 2.0 } else { 0.0 };
+            model.timeline.push_ground(time, time, thrust, 1.0);
+        }
+        model.drive().unwrap();
+        assert_eq!(model.thrust_deviation, None);
+        clock.advance(Duration::from_secs(3));
+        model.drive().unwrap();
+        let (ignition, deviation) = model.thrust_deviation.unwrap();
+        assert_eq!(ignition, 1.0);
+        assert_eq!(deviation.peak, (2.0, 2.0));
+        let text = &model.journal.entries().last().unwrap().text;
+        assert!(text.starts_with("Thrust vs. X1000: Peak 2.00kN"));
+    }
+
+    #[test]
+    fn test_anomalies_of_the_test_stand_are_marked() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        let rqa = Profile::TestStand.target();
+        for (source, anomalies) in [(rqa, Some(3)), (rqa, Some(5)), (rqa, Some(5)), (rqa, None)] {
+            let obg2 = RawObservablesGroup2 {
+                recording: None,
+                anomalies,
+                vbb_voltage: 0x2EE0,
+                pyro_status: 0x22,
+            };
+            model.process_observables(source, &RawObservablesGroup::OG2(obg2));
+        }
+        let markers: Vec<_> = model
+            .journal
+            .markers()
+            .map(|(marker, _, text)| (marker, text))
+            .collect();
+        assert_eq!(
+            markers,
+            vec![(Marker::Anomaly, "5 anomalies reported by RQA")]
+        );
+    }
+
+    #[test]
+    fn test_breakwire_anchors_t0_without_ignition() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        let fdb = Node::Farduino(b'B');
+        // A millisecond clock, polled a minute after power-up
+        let breakwire = |state, rail_exit| {
+            RawObservablesGroup::OG3(RawObservablesGroup3 {
+                clkfreq: ClkFreq(1000),
+                uptime: Timestamp(60_000),
+                breakwire: state,
+                rail_exit: Timestamp(rail_exit),
+            })
+        };
+        model.now = model.now + Duration::from_secs(10);
+        // Broken before we heard of the wire, the rocket didn't just leave
+        model.process_observables(fdb, &breakwire(b'B', 1_000));
+        assert_eq!(model.t0(), None);
+        model.process_observables(fdb, &breakwire(b'I', 0));
+        assert_eq!(model.t0(), None);
+        model.process_observables(fdb, &breakwire(b'B', 58_500));
+        model.process_observables(fdb, &breakwire(b'B', 58_500));
+        assert_eq!(model.t0(), Some(8.5));
+        assert!(model.uncommanded_launch());
+        // Put behind by a reset, t0 stays
+        model.reset();
+        assert!(!model.uncommanded_launch());
+        assert_eq!(model.t0(), Some(8.5));
+        // Our ignition accounts for the next rail exit
+        model.journal.mark(Marker::Ignition, 20.0, "Ignition");
+        model.process_observables(fdb, &breakwire(b'I', 0));
+        model.process_observables(fdb, &breakwire(b'B', 60_000));
+        assert_eq!(model.t0(), Some(20.0));
+        assert!(!model.uncommanded_launch());
+        let rail_exits = model
+            .journal
+            .markers()
+            .filter(|(marker, _, _)| *marker == Marker::RailExit)
+            .count();
+        assert_eq!(rail_exits, 2);
+    }
+
+    #[test]
+    fn test_tab_navigation_follows_the_magnitude() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        for check in [
+            Check::SerialPort,
+            Check::E32Parameters,
+            Check::Ping,
+            Check::NRFModules,
+            Check::Recorder,
+        ] {
+            model.self_test.record(check, Verdict::Passed);
+        }
+        model.mode = Mode::Observables(ObservablesMode::Core(CoreConnection::Idle));
+        // As if the reset after switching was over already
+        let settle = |model: &mut Model<MockConnection, SimpleIdGenerator>| {
+            model.mode = match model.mode {
+                Mode::Observables(_) => {
+                    Mode::Observables(ObservablesMode::Core(CoreConnection::Idle))
+                }
+                Mode::LaunchControl(_) => {
+                    Mode::LaunchControl(LaunchControlMode::Core(CoreConnection::Idle))
+                }
+                Mode::RFSilence(_) => Mode::RFSilence(RFSilenceMode::Core(CoreConnection::Idle)),
+            }
+        };
+        model.toggle_tab(2);
+        assert_matches!(model.mode, Mode::RFSilence(_));
+        // Logged once, without the tab passed over
+        let logged = model.transitions.entries().next().unwrap();
+        assert_eq!(logged.from, "Observables: Idle");
+        assert!(logged.to.starts_with("RF Silence"));
+        assert_eq!(model.transitions.entries().count(), 1);
+        settle(&mut model);
+        // Spinning on right away is ignored
+        model.toggle_tab(1);
+        assert_matches!(model.mode, Mode::RFSilence(_));
+        clock.advance(model.tab_navigation.debounce);
+        model.toggle_tab(1);
+        assert_matches!(model.mode, Mode::Observables(_));
+        settle(&mut model);
+        model.tab_navigation.wrap = false;
+        clock.advance(model.tab_navigation.debounce);
+        model.toggle_tab(-1);
+        assert_matches!(model.mode, Mode::Observables(_));
+        model.toggle_tab(5);
+        assert_matches!(model.mode, Mode::RFSilence(_));
+    }
+
+    #[test]
+    fn test_onboard_recording_needs_confirmation() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.self_test.expire();
+        model.self_test.acknowledge();
+        model.mode = Mode::Observables(ObservablesMode::Core(CoreConnection::Start));
+        drive_frames(&clock, &mut model);
+        model.control = ControlArea::Details;
+        model.process_input_event(&InputEvent::Send);
+        assert_eq!(model.onboard_recording, OnboardRecording::Confirm(true));
+        // Enter confirms instead of taring
+        model.process_input_event(&InputEvent::Enter);
+        assert_eq!(model.onboard_recording, OnboardRecording::Requested(true));
+        model.drive().unwrap();
+        assert_matches!(
+            model.onboard_recording,
+            OnboardRecording::Sent { start: true, .. }
+        );
+        model.drive().unwrap();
+        assert!(!model.consort.busy());
+        // Without OBG2 reporting it, the change is given up on
+        clock.advance(Duration::from_secs(11));
+        model.drive().unwrap();
+        assert!(model.onboard_recording.is_idle());
+        let texts: Vec<&str> = model
+            .journal
+            .entries()
+            .iter()
+            .map(|e| e.text.as_str())
+            .collect();
+        assert!(texts.contains(&"RecordStartAck by RQB, waiting for OBG2"));
+        assert!(texts.contains(&"Onboard recording change not reported by OBG2"));
+    }
+
+    #[test]
+    fn test_poll_timeouts_stay_with_their_node() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.self_test.expire();
+        model.self_test.acknowledge();
+        model.mode = Mode::Observables(ObservablesMode::Core(CoreConnection::Start));
+        drive_frames(&clock, &mut model);
+        let idle = Mode::Observables(ObservablesMode::Core(CoreConnection::Idle));
+        assert_eq!(model.mode, idle);
+        let logger = Node::Farduino(b'C');
+        for node in [logger, model.consort.dest()] {
+            model.obg1.insert(node, Default::default());
+        }
+        model.module.poll_timeout = Some(logger);
+        model.drive().unwrap();
+        assert_eq!(model.mode, idle);
+        assert!(!model.module.draining);
+        assert!(!model.obg1.contains_key(&logger));
+        assert!(model.obg1.contains_key(&model.consort.dest()));
+    }
+
+    #[test]
+    fn test_broadcast_skips_nodes_it_cant_send_to() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.self_test.expire();
+        model.self_test.acknowledge();
+        model.mode = Mode::Observables(ObservablesMode::Core(CoreConnection::Start));
+        drive_frames(&clock, &mut model);
+        let idle = Mode::Observables(ObservablesMode::Core(CoreConnection::Idle));
+        assert_eq!(model.mode, idle);
+        let (silent, other) = (Node::RedQueen(b'A'), Node::RedQueen(b'C'));
+        model.set_poll_nodes(&[silent, other]);
+        model.consort.set_silenced(vec![silent]);
+        model.broadcast.select(1);
+        assert!(model.broadcast.run_selected());
+        model.drive().unwrap();
+        assert_eq!(
+            model.journal.entries().last().unwrap().text,
+            "Broadcast Ping to RQA not sent, Silenced"
+        );
+        model.drive().unwrap();
+        assert_eq!(model.mode, idle);
+        assert_eq!(model.broadcast.in_flight(), Some(other));
+    }
+
+    #[test]
+    fn test_adc_page_tares_only_the_observables() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.self_test.expire();
+        model.self_test.acknowledge();
+        model.tare.feed(&RawObservablesGroup1 {
+            clkfreq: ClkFreq(1),
+            uptime: Timestamp(1),
+            thrust: Ads1256Reading(100),
+            pressure: Ads1256Reading(200),
+        });
+        model.mode = Mode::LaunchControl(LaunchControlMode::PreArm { acknowledged: 0 });
+        model.toggle_adc();
+        model.process_input_event(&InputEvent::Enter);
+        assert!(model.tare.offset.is_none());
+        model.mode = Mode::Observables(ObservablesMode::Core(CoreConnection::Idle));
+        model.process_input_event(&InputEvent::Enter);
+        assert!(model.tare.offset.is_some());
+        assert_eq!(model.adc.count(), 0);
+    }
+
+    #[test]
+    fn test_onboard_recording_starts_ahead_of_arming() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        model.self_test.expire();
+        model.self_test.acknowledge();
+        model.mode = Mode::Observables(ObservablesMode::Core(CoreConnection::Start));
+        drive_frames(&clock, &mut model);
+        assert!(model.onboard_recording.is_idle());
+        model.mode = Mode::LaunchControl(LaunchControlMode::PreArm { acknowledged: 0 });
+        model.drive().unwrap();
+        assert_matches!(
+            model.onboard_recording,
+            OnboardRecording::Sent { start: true, .. }
+        );
+        assert!(model.auto_record.is_triggered());
+        assert_eq!(
+            model.journal.entries().last().unwrap().text,
+            "Onboard recording started automatically, launch sequence"
+        );
+    }
+
+    #[test]
+    fn test_onboard_recording_stays_out_of_the_ignition() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = faulty_model(&clock, Failover::new("comport", None, 3));
+        model.self_test.expire();
+        model.self_test.acknowledge();
+        model.mode = Mode::Observables(ObservablesMode::Core(CoreConnection::Start));
+        drive_frames(&clock, &mut model);
+        model.auto_record.policy.enabled = false;
+        let waiting = Mode::LaunchControl(LaunchControlMode::WaitForFire { key_a: 1, key_b: 2 });
+        model.mode = waiting;
+        model.onboard_recording.automatic(true);
+        model.drive().unwrap();
+        assert_eq!(model.onboard_recording, OnboardRecording::Requested(true));
+        assert!(!model.consort.busy());
+        // Refused before arming, the launch sequence carries on
+        *model.faults.borrow_mut() = Faults {
+            enabled: true,
+            nak_verb: Some("RECORD_START".into()),
+            ..Default::default()
+        };
+        let checklist = Mode::LaunchControl(LaunchControlMode::PreArm { acknowledged: 0 });
+        model.mode = checklist;
+        model.drive().unwrap();
+        model.drive().unwrap();
+        assert!(model.onboard_recording.is_idle());
+        assert_eq!(model.mode, checklist);
+        assert_eq!(
+            model.journal.entries().last().unwrap().text,
+            "Onboard recording command refused"
+        );
+        // A fire pressed while busy is reported, not swallowed
+        for _ in 0..3 {
+            model.safety_key.feed(Ok(true));
+        }
+        model.mode = waiting;
+        model
+            .consort
+            .send_command(Command::Ping, &mut model.module)
+            .unwrap();
+        model.control = ControlArea::Details;
+        model.process_input_event(&InputEvent::Enter);
+        assert_eq!(model.mode, waiting);
+        assert_eq!(
+            model.journal.entries().last().unwrap().text,
+            "Ignition refused, a command is in flight, press again"
+        );
+    }
+
+    #[test]
+    fn test_mark_keeps_the_time_of_the_key_press() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut model = model(&clock);
+        clock.advance(Duration::from_secs(5));
+        model.drive().unwrap();
+        model.mark_moment();
+        clock.advance(Duration::from_secs(3));
+        model.drive().unwrap();
+        model.process_bindings(&[
+            Binding::Event(InputEvent::Right(crate::input::STEP_MAGNITUDE)),
+            Binding::Event(InputEvent::Enter),
+        ]);
+        assert!(!model.annotator.is_picking());
+        let (marker, at, text) = model.journal.markers().last().unwrap();
+        assert_eq!(marker, Marker::Mark);
+        assert_eq!(at, 5.0);
+        assert_eq!(text, "Mark: valve opened");
+    }
+
+    // The E32 worker on its own thread, talking to a bridge that answers
+    // every command with a garbled sentence. The purge that follows only
+    // ends as the shared clock is stepped, however long the test waits.
+    #[cfg(feature = "e32")]
+    #[test]
+    fn test_model_and_e32_worker_step_the_same_clock() {
+        use crate::ebyte::{E32Connection, DEFAULT_DRAIN_IDLE};
+        use crate::recorder::Recorder;
+        use crate::tcpbridge::Latency;
+        use std::io::{Read, Write};
+
+        let clock = SimulatedClock::new(Instant::now());
+        let bridge = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = bridge.local_addr().unwrap().to_string();
+        let (commands, received) = crossbeam_channel::unbounded();
+        std::thread::spawn(move || {
+            let (mut stream, _) = bridge.accept().unwrap();
+            let mut buffer = [0; 256];
+            while let Ok(count @ 1..) = stream.read(&mut buffer) {
+                let _ = commands.send(buffer[..count].to_vec());
+                let _ = stream.write_all(b"$RQBACK,999,LNC,0*00\r\n");
+            }
+        });
+        let mut conn = E32Connection::new(
+            SimpleIdGenerator::default(),
+            Node::LaunchControl,
+            Node::RedQueen(b'B'),
+            Profile::default(),
+            Recorder::new(vec![]),
+            clock.shared(),
+        )
+        .unwrap();
+        conn.use_bridge(Latency::default());
+        let mut model = model_with(&clock, conn);
+        model.use_port(&address);
+        let drive_until = |model: &mut Model<_, _>, done: &dyn Fn(&Model<_, _>) -> bool| {
+            for _ in 0..500 {
+                model.drive().unwrap();
+                if done(model) {
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            panic!("the worker didn't get there");
+        };
+        drive_until(&mut model, &|model| model.drain_progress.is_some());
+        received.try_iter().for_each(drop);
+        // Real time passing doesn't end the purge
+        std::thread::sleep(DEFAULT_DRAIN_IDLE * 2);
+        model.drive().unwrap();
+        assert_eq!(model.drain_progress, Some(0));
+        assert!(received.is_empty());
+        // The reset after it is the next thing on the line
+        clock.advance(DEFAULT_DRAIN_IDLE);
+        drive_until(&mut model, &|_| !received.is_empty());
+    }
 }
@@ -0,0 +1,224 @@
+// Promiscuous monitoring of the channel. Every valid sentence is
+// kept, no matter which nodes talk to each other, so we can see
+// what other teams on the same frequency are doing. Sentences of
+// our types our parsers don't understand say why, to spot firmware
+// format mismatches.
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    observables::Profile,
+    rqparser::{diagnose, verify_nmea_format, ParseFailure},
+    rqprotocol::Node,
+};
+
+const MONITOR_CAPACITY: usize = 200;
+
+#[derive(Debug, Clone)]
+pub struct Traffic {
+    pub at: DateTime<Utc>,
+    pub talker: Node,
+    // The sentence type following the talker, e.g. CMD or ACK
+    pub kind: String,
+    pub sentence: String,
+    pub failure: Option<ParseFailure>,
+}
+
+pub struct Monitor {
+    traffic: VecDeque<Traffic>,
+    active: bool,
+    invalid: usize,
+    pub talker: Option<Node>,
+    pub kind: Option<String>,
+}
+
+impl Default for Monitor {
+    fn default() -> Self {
+        Self {
+            traffic: VecDeque::with_capacity(MONITOR_CAPACITY),
+            active: false,
+            invalid: 0,
+            talker: None,
+            kind: None,
+        }
+    }
+}
+
+fn split_address(payload: &str) -> Option<(Node, String)> {
+    let address = payload.split(',').next()?;
+    if address.len() < 4 || !address.is_ascii() {
+        return None;
+    }
+    let talker = address[0..3].parse().ok()?;
+    Some((talker, address[3..].into()))
+}
+
+// Steps through None and the given options
+fn cycle<T: Clone + PartialEq>(options: &[T], current: &Option<T>, forward: bool) -> Option<T> {
+    let position = current
+        .as_ref()
+        .and_then(|c| options.iter().position(|o| o == c));
+    let index = match (position, forward) {
+        (None, true) => 0,
+        (None, false) => options.len().checked_sub(1)?,
+        (Some(p), true) => p + 1,
+        (Some(p), false) => p.checked_sub(1)?,
+    };
+    options.get(index).cloned()
+}
+
+impl Monitor {
+    pub fn start(&mut self) {
+        self.active = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    // Takes a full sentence including delimiters. Returns
+    // if it was valid and thus recorded.
+    pub fn record(&mut self, sentence: &[u8], profile: Profile) -> bool {
+        let parsed = verify_nmea_format(sentence).ok().and_then(|payload| {
+            let text = std::str::from_utf8(payload).ok()?;
+            let (talker, kind) = split_address(text)?;
+            Some((talker, kind, text.to_string(), diagnose(profile, payload)))
+        });
+        match parsed {
+            Some((talker, kind, sentence, failure)) => {
+                if self.traffic.len() == MONITOR_CAPACITY {
+                    self.traffic.pop_front();
+                }
+                self.traffic.push_back(Traffic {
+                    at: Utc::now(),
+                    talker,
+                    kind,
+                    sentence,
+                    failure,
+                });
+                true
+            }
+            None => {
+                self.invalid += 1;
+                false
+            }
+        }
+    }
+
+    pub fn invalid(&self) -> usize {
+        self.invalid
+    }
+
+    pub fn talkers(&self) -> Vec<Node> {
+        let mut talkers: Vec<Node> = vec![];
+        for traffic in self.traffic.iter() {
+            if !talkers.contains(&traffic.talker) {
+                talkers.push(traffic.talker);
+            }
+        }
+        talkers.sort_by_key(|t| t.to_string());
+        talkers
+    }
+
+    pub fn kinds(&self) -> Vec<String> {
+        let mut kinds: Vec<String> = self.traffic.iter().map(|t| t.kind.clone()).collect();
+        kinds.sort();
+        kinds.dedup();
+        kinds
+    }
+
+    pub fn cycle_talker(&mut self, forward: bool) {
+        self.talker = cycle(&self.talkers(), &self.talker, forward);
+    }
+
+    pub fn cycle_kind(&mut self) {
+        self.kind = cycle(&self.kinds(), &self.kind, true);
+    }
+
+    pub fn filtered(&self) -> impl Iterator<Item = &Traffic> {
+        self.traffic.iter().filter(|t| {
+            self.talker.map_or(true, |talker| talker == t.talker)
+                && self.kind.as_ref().map_or(true, |kind| *kind == t.kind)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rqparser::NMEAFormatter;
+
+    fn sentence(payload: &[u8]) -> Vec<u8> {
+        let mut formatter = NMEAFormatter::default();
+        formatter.format_sentence(payload).unwrap();
+        formatter.buffer().unwrap().to_vec()
+    }
+
+    #[test]
+    fn test_records_any_node_pair() {
+        let mut monitor = Monitor::default();
+        assert!(monitor.record(&sentence(b"LNCCMD,001,RQA,PING"), Profile::TestStand));
+        assert!(monitor.record(&sentence(b"RQAACK,001,LNC"), Profile::TestStand));
+        assert!(monitor.record(&sentence(b"FDXCMD,042,RQX,RESET,2"), Profile::TestStand));
+        assert!(!monitor.record(b"$RQAACK,001,LNC*00\r\n", Profile::TestStand));
+        assert_eq!(monitor.invalid(), 1);
+        assert_eq!(
+            monitor.talkers(),
+            vec![
+                Node::Farduino(b'X'),
+                Node::LaunchControl,
+                Node::RedQueen(b'A')
+            ]
+        );
+        assert_eq!(monitor.kinds(), vec!["ACK".to_string(), "CMD".to_string()]);
+    }
+
+    #[test]
+    fn test_explains_what_we_dont_understand() {
+        let mut monitor = Monitor::default();
+        monitor.record(
+            &sentence(b"RQAOBG,001,LNC,1,0BEBC200,00000000AA894CC8,000669E2,00000001"),
+            Profile::TestStand,
+        );
+        monitor.record(
+            &sentence(b"RQAOBG,002,LNC,1,0BEBC200,0000AA894CC8,000669E2,00000001"),
+            Profile::TestStand,
+        );
+        monitor.record(
+            &sentence(b"RQASTATE,013940.4184,DROGUE_OPEN"),
+            Profile::TestStand,
+        );
+        let failures: Vec<_> = monitor.filtered().map(|t| t.failure.clone()).collect();
+        assert_eq!(failures[0], None);
+        let failure = failures[1].clone().unwrap();
+        assert_eq!(
+            (failure.offset, failure.found.as_str()),
+            (26, "0000AA894CC8")
+        );
+        assert_eq!(failures[2], None);
+    }
+
+    #[test]
+    fn test_filters() {
+        let mut monitor = Monitor::default();
+        monitor.record(&sentence(b"LNCCMD,001,RQA,PING"), Profile::TestStand);
+        monitor.record(&sentence(b"RQAACK,001,LNC"), Profile::TestStand);
+        monitor.record(&sentence(b"FDXCMD,042,RQX,PING"), Profile::TestStand);
+        monitor.cycle_kind();
+        assert_eq!(monitor.kind, Some("ACK".into()));
+        assert_eq!(monitor.filtered().count(), 1);
+        monitor.cycle_kind();
+        monitor.cycle_talker(false);
+        assert_eq!(monitor.talker, Some(Node::RedQueen(b'A')));
+        assert_eq!(monitor.filtered().count(), 0);
+        monitor.cycle_talker(true);
+        monitor.cycle_kind();
+        assert_eq!((monitor.talker, monitor.kind.clone()), (None, None));
+        assert_eq!(monitor.filtered().count(), 3);
+    }
+}
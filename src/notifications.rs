@@ -0,0 +1,148 @@
+// Notifications of the desktop for what happens on the link, so the
+// window can stay in the background during long integration tests.
+// The notifier follows the bus, only the desktop build with the
+// notifications feature shows them.
+use crossbeam_channel::Receiver;
+
+use crate::bus::{EventBus, Marked, ModeChange};
+use crate::journal::Marker;
+use crate::model::StateProcessing;
+use crate::rqprotocol::Node;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub summary: &'static str,
+    pub body: String,
+}
+
+pub struct Notifier {
+    modes: Receiver<ModeChange>,
+    markers: Receiver<Marked>,
+    refusals: Receiver<Node>,
+    connected: bool,
+    // Since the link was up
+    lost: bool,
+}
+
+impl Notifier {
+    pub fn new(bus: &EventBus) -> Self {
+        Self {
+            modes: bus.modes.subscribe(),
+            markers: bus.markers.subscribe(),
+            refusals: bus.refusals.subscribe(),
+            connected: false,
+            lost: false,
+        }
+    }
+
+    // What happened since the last poll, refusals summed up
+    pub fn poll(&mut self) -> Vec<Notification> {
+        let mut notifications = vec![];
+        for change in self.modes.try_iter() {
+            let core = change.to.core_mode();
+            if core.is_failure() && self.connected {
+                self.connected = false;
+                self.lost = true;
+                notifications.push(Notification {
+                    summary: "Connection lost",
+                    body: format!("Link failed while in {}", change.from.name()),
+                });
+            } else if !core.is_failure() && !change.to.reset_ongoing() && !self.connected {
+                self.connected = true;
+                if std::mem::take(&mut self.lost) {
+                    notifications.push(Notification {
+                        summary: "Connection restored",
+                        body: format!("Link back up in {}", change.to.name()),
+                    });
+                }
+            }
+        }
+        for marked in self.markers.try_iter() {
+            if marked.marker == Marker::Anomaly {
+                notifications.push(Notification {
+                    summary: "Anomaly",
+                    body: marked.text,
+                });
+            }
+        }
+        let refusals: Vec<Node> = self.refusals.try_iter().collect();
+        if let Some(node) = refusals.last() {
+            notifications.push(Notification {
+                summary: "Command refused",
+                body: match refusals.len() {
+                    1 => format!("NAK from {}", node),
+                    count => format!("{} NAKs, the last from {}", count, node),
+                },
+            });
+        }
+        notifications
+    }
+}
+
+#[cfg(feature = "notifications")]
+pub fn show(notification: &Notification) -> anyhow::Result<()> {
+    notify_rust::Notification::new()
+        .appname("Launch Control")
+        .summary(notification.summary)
+        .body(&notification.body)
+        .show()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Instant;
+    use crate::model::{CoreConnection, Mode, ObservablesMode};
+
+    #[test]
+    fn test_link_events_are_notified() {
+        let bus = EventBus::default();
+        let mut notifier = Notifier::new(&bus);
+        let mode = |core| Mode::Observables(ObservablesMode::Core(core));
+        let change = |from, to| ModeChange {
+            at: Instant::now(),
+            from: mode(from),
+            to: mode(to),
+        };
+        // Failing before ever connecting isn't a loss
+        bus.modes
+            .publish(change(CoreConnection::Start, CoreConnection::Failure));
+        bus.modes
+            .publish(change(CoreConnection::Discover, CoreConnection::Idle));
+        assert!(notifier.poll().is_empty());
+        bus.modes
+            .publish(change(CoreConnection::Idle, CoreConnection::Failure));
+        bus.modes
+            .publish(change(CoreConnection::Failure, CoreConnection::Reset));
+        bus.modes
+            .publish(change(CoreConnection::Reset, CoreConnection::Failure));
+        let summaries = |notifications: Vec<Notification>| {
+            notifications
+                .iter()
+                .map(|notification| notification.summary)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(summaries(notifier.poll()), vec!["Connection lost"]);
+        bus.modes
+            .publish(change(CoreConnection::Discover, CoreConnection::Idle));
+        bus.markers.publish(Marked {
+            marker: Marker::Anomaly,
+            elapsed: 12.0,
+            text: "3 anomalies reported by RQB".into(),
+        });
+        bus.markers.publish(Marked {
+            marker: Marker::Mark,
+            elapsed: 13.0,
+            text: "Mark".into(),
+        });
+        bus.refusals.publish(Node::RedQueen(b'B'));
+        bus.refusals.publish(Node::RedQueen(b'A'));
+        let notifications = notifier.poll();
+        assert_eq!(
+            summaries(notifications.clone()),
+            vec!["Connection restored", "Anomaly", "Command refused"]
+        );
+        assert_eq!(notifications[2].body, "2 NAKs, the last from RQA");
+    }
+}
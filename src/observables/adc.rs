@@ -0,0 +1,248 @@
+// The raw ADC readings of the load cell and the pressure sensor next
+// to what they convert to, for calibration sessions. Their min, max and
+// mean over a selectable window let gain and offset be worked out on
+// the spot, without exporting the data first. Readings of the target
+// are kept for the longest window, also while the page isn't shown.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use uom::si::{force::kilonewton, pressure::hectopascal};
+
+use super::groups::{ObservablesGroup1, RawObservablesGroup1};
+
+pub const WINDOWS: [Duration; 5] = [
+    Duration::from_secs(1),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+    Duration::from_secs(30),
+    Duration::from_secs(60),
+];
+const DEFAULT_WINDOW: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Channel {
+    Thrust,
+    Pressure,
+}
+
+pub const CHANNELS: [Channel; 2] = [Channel::Thrust, Channel::Pressure];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sample {
+    // Of the node, see uptime.rs
+    time: Duration,
+    raw: [i32; 2],
+    // Tared, in kN and hPa
+    converted: [f64; 2],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Statistics {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+// The ADC page
+#[derive(Debug)]
+pub struct AdcMonitor {
+    active: bool,
+    samples: VecDeque<Sample>,
+    selected: usize,
+}
+
+impl Default for AdcMonitor {
+    fn default() -> Self {
+        Self {
+            active: false,
+            samples: VecDeque::new(),
+            selected: DEFAULT_WINDOW,
+        }
+    }
+}
+
+impl Channel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Channel::Thrust => "Thrust",
+            Channel::Pressure => "Pressure",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Channel::Thrust => "kN",
+            Channel::Pressure => "hPa",
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Channel::Thrust => 0,
+            Channel::Pressure => 1,
+        }
+    }
+}
+
+impl Statistics {
+    fn of(values: impl Iterator<Item = f64>) -> Option<Self> {
+        let (count, min, max, sum) = values.fold(
+            (0, f64::INFINITY, f64::NEG_INFINITY, 0.0),
+            |(count, min, max, sum), value| {
+                (count + 1, min.min(value), max.max(value), sum + value)
+            },
+        );
+        (count > 0).then(|| Statistics {
+            min,
+            max,
+            mean: sum / count as f64,
+        })
+    }
+}
+
+// The 24 bits of the ADS1256, two's complement
+pub fn hex(raw: i32) -> String {
+    format!("0x{:06X}", raw as u32 & 0xFF_FFFF)
+}
+
+impl AdcMonitor {
+    pub fn start(&mut self) {
+        self.active = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn feed(&mut self, raw: &RawObservablesGroup1, obg1: &ObservablesGroup1) {
+        // Should the time go back, the window starts over
+        if self
+            .samples
+            .back()
+            .map_or(false, |last| obg1.time < last.time)
+        {
+            self.samples.clear();
+        }
+        self.samples.push_back(Sample {
+            time: obg1.time,
+            raw: [raw.thrust.0, raw.pressure.0],
+            converted: [
+                obg1.thrust.get::<kilonewton>(),
+                obg1.pressure.get::<hectopascal>(),
+            ],
+        });
+        let longest = WINDOWS[WINDOWS.len() - 1];
+        while self.samples.front().map_or(false, |first| {
+            obg1.time.saturating_sub(first.time) > longest
+        }) {
+            self.samples.pop_front();
+        }
+    }
+
+    // After taring, the readings before don't compare
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn window(&self) -> Duration {
+        WINDOWS[self.selected]
+    }
+
+    pub fn select(&mut self, steps: i64) {
+        self.selected = (self.selected as i64 + steps).clamp(0, WINDOWS.len() as i64 - 1) as usize;
+    }
+
+    fn in_window(&self) -> impl Iterator<Item = &Sample> {
+        let last = self.samples.back().map_or(Duration::ZERO, |last| last.time);
+        let window = self.window();
+        self.samples
+            .iter()
+            .filter(move |sample| last.saturating_sub(sample.time) <= window)
+    }
+
+    // Within the window
+    pub fn count(&self) -> usize {
+        self.in_window().count()
+    }
+
+    pub fn latest(&self, channel: Channel) -> Option<(i32, f64)> {
+        let last = self.samples.back()?;
+        Some((last.raw[channel.index()], last.converted[channel.index()]))
+    }
+
+    pub fn raw_statistics(&self, channel: Channel) -> Option<Statistics> {
+        Statistics::of(
+            self.in_window()
+                .map(|sample| sample.raw[channel.index()] as f64),
+        )
+    }
+
+    pub fn converted_statistics(&self, channel: Channel) -> Option<Statistics> {
+        Statistics::of(
+            self.in_window()
+                .map(|sample| sample.converted[channel.index()]),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observables::{Ads1256Reading, ClkFreq, Timestamp};
+    use uom::si::f64::{Force, Pressure};
+
+    fn feed(monitor: &mut AdcMonitor, secs: u64, thrust: i32) {
+        let raw = RawObservablesGroup1 {
+            clkfreq: ClkFreq(1),
+            uptime: Timestamp(secs),
+            thrust: Ads1256Reading(thrust),
+            pressure: Ads1256Reading(-1),
+        };
+        let obg1 = ObservablesGroup1 {
+            clkfreq: ClkFreq(1),
+            uptime: Duration::from_secs(secs),
+            time: Duration::from_secs(secs),
+            thrust: Force::new::<kilonewton>(thrust as f64 / 1000.0),
+            pressure: Pressure::new::<hectopascal>(0.0),
+        };
+        monitor.feed(&raw, &obg1);
+    }
+
+    #[test]
+    fn test_statistics_cover_the_selected_window() {
+        let mut monitor = AdcMonitor::default();
+        assert_eq!(monitor.window(), Duration::from_secs(5));
+        assert_eq!(monitor.raw_statistics(Channel::Thrust), None);
+        for secs in 0..=70 {
+            feed(&mut monitor, secs, secs as i32 * 100);
+        }
+        // 65s to 70s
+        assert_eq!(monitor.count(), 6);
+        assert_eq!(
+            monitor.raw_statistics(Channel::Thrust),
+            Some(Statistics {
+                min: 6500.0,
+                max: 7000.0,
+                mean: 6750.0
+            })
+        );
+        assert_eq!(
+            monitor.converted_statistics(Channel::Thrust).unwrap().max,
+            7.0
+        );
+        assert_eq!(monitor.latest(Channel::Pressure), Some((-1, 0.0)));
+        assert_eq!(hex(-1), "0xFFFFFF");
+        monitor.select(10);
+        assert_eq!(monitor.window(), Duration::from_secs(60));
+        assert_eq!(monitor.count(), 61);
+        feed(&mut monitor, 1, 0);
+        assert_eq!(monitor.count(), 1);
+        monitor.clear();
+        assert_eq!(monitor.count(), 0);
+        assert_eq!(monitor.latest(Channel::Thrust), None);
+    }
+}
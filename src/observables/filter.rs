@@ -0,0 +1,261 @@
+// Smoothing of the plotted thrust and pressure, the raw channels
+// are noisy at high gain. Only what is shown gets filtered, the
+// recording and the stored history keep the raw values. Which
+// filter a plot uses is chosen on the filter page and stored in
+// the config file.
+use serde::{Deserialize, Serialize};
+
+const MIN_WINDOW: usize = 3;
+const MAX_WINDOW: usize = 51;
+const CUTOFF_STEP: f64 = 0.5;
+const MAX_CUTOFF: f64 = 50.0;
+
+const PARAMETERS: [&str; 4] = ["Thrust", "Pressure", "Window", "Cutoff"];
+const KINDS: [FilterKind; 4] = [
+    FilterKind::Raw,
+    FilterKind::MovingAverage,
+    FilterKind::Median,
+    FilterKind::LowPass,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterKind {
+    Raw,
+    MovingAverage,
+    Median,
+    // Second order Butterworth
+    LowPass,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Filters {
+    pub thrust: FilterKind,
+    pub pressure: FilterKind,
+    // Samples, of the moving average and median
+    pub window: usize,
+    pub cutoff_hz: f64,
+}
+
+// The filter page
+#[derive(Debug, Default)]
+pub struct FilterTuner {
+    active: bool,
+    pub selected: usize,
+}
+
+impl FilterKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterKind::Raw => "Raw",
+            FilterKind::MovingAverage => "Moving average",
+            FilterKind::Median => "Median",
+            FilterKind::LowPass => "Low-pass",
+        }
+    }
+
+    fn step(&self, steps: i64) -> FilterKind {
+        let index = KINDS.iter().position(|kind| kind == self).unwrap() as i64;
+        KINDS[(index + steps).rem_euclid(KINDS.len() as i64) as usize]
+    }
+}
+
+impl Default for Filters {
+    fn default() -> Self {
+        Self {
+            thrust: FilterKind::Raw,
+            pressure: FilterKind::Raw,
+            window: 9,
+            cutoff_hz: 5.0,
+        }
+    }
+}
+
+impl Filters {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(MIN_WINDOW..=MAX_WINDOW).contains(&self.window) {
+            anyhow::bail!("filter window must be {}..={}", MIN_WINDOW, MAX_WINDOW);
+        }
+        // The median is a sample then
+        if self.window % 2 == 0 {
+            anyhow::bail!("filter window must be odd");
+        }
+        if !(CUTOFF_STEP..=MAX_CUTOFF).contains(&self.cutoff_hz) {
+            anyhow::bail!("filter cutoff must be {}..={}Hz", CUTOFF_STEP, MAX_CUTOFF);
+        }
+        Ok(())
+    }
+
+    // Points are [time in s, value]. The low-pass needs the rate of the
+    // raw samples, without it's taken from the points, which only works
+    // if they are all raw samples.
+    pub fn apply(
+        &self,
+        kind: FilterKind,
+        points: Vec<[f64; 2]>,
+        sample_rate: Option<f64>,
+    ) -> Vec<[f64; 2]> {
+        match kind {
+            FilterKind::Raw => points,
+            FilterKind::MovingAverage => trailing(points, self.window, |window| {
+                window.iter().sum::<f64>() / window.len() as f64
+            }),
+            FilterKind::Median => trailing(points, self.window, |window| {
+                let mut sorted = window.to_vec();
+                sorted.sort_by(f64::total_cmp);
+                sorted[sorted.len() / 2]
+            }),
+            FilterKind::LowPass => low_pass(points, self.cutoff_hz, sample_rate),
+        }
+    }
+}
+
+// Over the window ending at each sample
+fn trailing(points: Vec<[f64; 2]>, window: usize, f: impl Fn(&[f64]) -> f64) -> Vec<[f64; 2]> {
+    let values: Vec<f64> = points.iter().map(|p| p[1]).collect();
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, [time, _])| [*time, f(&values[(i + 1).saturating_sub(window)..=i])])
+        .collect()
+}
+
+// Biquad from the bilinear transform. Starts settled on the first value.
+fn low_pass(points: Vec<[f64; 2]>, cutoff_hz: f64, sample_rate: Option<f64>) -> Vec<[f64; 2]> {
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) if points.len() > 2 && last[0] > first[0] => (first, last),
+        _ => return points,
+    };
+    let sample_rate =
+        sample_rate.unwrap_or_else(|| (points.len() - 1) as f64 / (last[0] - first[0]));
+    if cutoff_hz >= sample_rate / 2.0 {
+        return points;
+    }
+    let k = (std::f64::consts::PI * cutoff_hz / sample_rate).tan();
+    let norm = 1.0 / (1.0 + std::f64::consts::SQRT_2 * k + k * k);
+    let b0 = k * k * norm;
+    let a1 = 2.0 * (k * k - 1.0) * norm;
+    let a2 = (1.0 - std::f64::consts::SQRT_2 * k + k * k) * norm;
+    let (mut x1, mut x2, mut y1, mut y2) = (first[1], first[1], first[1], first[1]);
+    points
+        .iter()
+        .map(|[time, x]| {
+            let y = b0 * (x + 2.0 * x1 + x2) - a1 * y1 - a2 * y2;
+            (x2, x1, y2, y1) = (x1, *x, y1, y);
+            [*time, y]
+        })
+        .collect()
+}
+
+impl FilterTuner {
+    pub fn start(&mut self) {
+        self.active = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % PARAMETERS.len();
+    }
+
+    // Windows stay odd, so the median is a sample
+    pub fn adjust(&self, filters: &mut Filters, steps: i64) {
+        match self.selected {
+            0 => filters.thrust = filters.thrust.step(steps),
+            1 => filters.pressure = filters.pressure.step(steps),
+            2 => {
+                let window = filters.window as i64 + steps * 2;
+                filters.window = window.clamp(MIN_WINDOW as i64, MAX_WINDOW as i64) as usize;
+            }
+            _ => {
+                let cutoff = filters.cutoff_hz + steps as f64 * CUTOFF_STEP;
+                filters.cutoff_hz = cutoff.clamp(CUTOFF_STEP, MAX_CUTOFF);
+            }
+        }
+    }
+
+    pub fn parameters(filters: &Filters) -> [(&'static str, String); 4] {
+        [
+            (PARAMETERS[0], filters.thrust.name().to_string()),
+            (PARAMETERS[1], filters.pressure.name().to_string()),
+            (PARAMETERS[2], format!("{} samples", filters.window)),
+            (PARAMETERS[3], format!("{:.1}Hz", filters.cutoff_hz)),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 100Hz of a slow sine with the given noise on top
+    fn noisy(noise: impl Fn(usize) -> f64) -> Vec<[f64; 2]> {
+        (0..500)
+            .map(|i| {
+                let time = i as f64 / 100.0;
+                [time, time.sin() + noise(i)]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_filters_remove_the_noise() {
+        let filters = Filters {
+            window: 5,
+            cutoff_hz: 2.0,
+            ..Default::default()
+        };
+        let vibration = noisy(|i| if i % 2 == 0 { 1.0 } else { -1.0 });
+        let spikes = noisy(|i| if i % 10 == 0 { 5.0 } else { 0.0 });
+        assert_eq!(filters.apply(FilterKind::Raw, spikes.clone(), None), spikes);
+        for (kind, raw) in [
+            (FilterKind::MovingAverage, &vibration),
+            (FilterKind::Median, &spikes),
+            (FilterKind::LowPass, &vibration),
+        ] {
+            let filtered = filters.apply(kind, raw.clone(), None);
+            assert_eq!(filtered.len(), raw.len());
+            // Settled after a second, and lagging the sine a bit
+            for [time, value] in &filtered[100..] {
+                assert!((value - time.sin()).abs() < 0.25, "{:?} at {}", kind, time);
+            }
+        }
+        // Given the rate, the coefficients don't follow the points
+        let sparse: Vec<[f64; 2]> = vibration.iter().step_by(10).copied().collect();
+        assert_ne!(
+            filters.apply(FilterKind::LowPass, sparse.clone(), Some(100.0)),
+            filters.apply(FilterKind::LowPass, sparse, None)
+        );
+    }
+
+    #[test]
+    fn test_tuning_stays_in_range() {
+        let mut filters = Filters::default();
+        let mut tuner = FilterTuner::default();
+        tuner.adjust(&mut filters, -1);
+        assert_eq!(filters.thrust, FilterKind::LowPass);
+        tuner.next();
+        tuner.next();
+        tuner.adjust(&mut filters, 100);
+        assert_eq!(filters.window, MAX_WINDOW);
+        tuner.next();
+        tuner.adjust(&mut filters, -100);
+        assert_eq!(filters.cutoff_hz, CUTOFF_STEP);
+        assert!(filters.validate().is_ok());
+        let parsed: Filters = serde_json::from_str(r#"{"thrust": "median", "window": 2}"#).unwrap();
+        assert_eq!(parsed.thrust, FilterKind::Median);
+        assert!(parsed.validate().is_err());
+        let even = Filters {
+            window: 4,
+            ..Default::default()
+        };
+        assert!(even.validate().is_err());
+    }
+}
@@ -0,0 +1,189 @@
+// The observables of both profiles. The test stand's RedQueen always
+// records and counts anomalies, the rocket's doesn't, older flight
+// firmware only sends battery and pyros. The Farduino on the launch
+// rail reports its breakwire as the third group.
+use std::time::Duration;
+
+use uom::si::f64::{Force, Pressure};
+
+use super::{
+    tare::TareOffset, AdcForceCalibration, AdcPressureCalibration, Ads1256Reading, ClkFreq,
+    Timestamp,
+};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct RawObservablesGroup1 {
+    pub clkfreq: ClkFreq,
+    pub uptime: Timestamp,
+    pub thrust: Ads1256Reading,
+    pub pressure: Ads1256Reading,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RawObservablesGroup2 {
+    pub recording: Option<RawRecording>,
+    pub anomalies: Option<u32>,
+    pub vbb_voltage: u16,
+    pub pyro_status: u8,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RawRecording {
+    pub state: u8,
+    pub filename_or_error: Vec<u8>,
+    pub records: u32,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct RawObservablesGroup3 {
+    pub clkfreq: ClkFreq,
+    pub uptime: Timestamp,
+    // I(ntact) or B(roken)
+    pub breakwire: u8,
+    pub rail_exit: Timestamp,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum RawObservablesGroup {
+    OG1(RawObservablesGroup1),
+    OG2(RawObservablesGroup2),
+    OG3(RawObservablesGroup3),
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ObservablesGroup1 {
+    pub clkfreq: ClkFreq,
+    pub uptime: Duration,
+    // The uptime continued across reboots, see uptime.rs
+    pub time: Duration,
+    pub thrust: Force,
+    pub pressure: Pressure,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum RecordingState {
+    Unknown,
+    Error(String),
+    Pause,
+    Recording(String),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum PyroStatus {
+    Unknown,
+    Open,
+    Closed,
+}
+
+#[derive(Clone, Debug)]
+pub struct ObservablesGroup2 {
+    pub recording_state: RecordingState,
+    // None if the firmware doesn't report them
+    pub anomalies: Option<u32>,
+    pub records: Option<u32>,
+    pub vbb_voltage: f32,
+    pub pyro12_status: PyroStatus,
+    pub pyro34_status: PyroStatus,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Breakwire {
+    Intact,
+    // How long ago the rocket left the rail
+    Broken(Duration),
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct LaunchDetect {
+    pub uptime: Duration,
+    pub breakwire: Breakwire,
+}
+
+pub struct SystemDefinition {
+    thrust_calibration: AdcForceCalibration,
+    pressure_calibration: AdcPressureCalibration,
+}
+
+impl Default for SystemDefinition {
+    fn default() -> Self {
+        let thrust_calibration = AdcForceCalibration {
+            m: 4.451e-5,
+            c: -0.049,
+        };
+        let pressure_calibration = AdcPressureCalibration {
+            m: 4.213e-5,
+            c: -0.927,
+        };
+
+        Self {
+            thrust_calibration,
+            pressure_calibration,
+        }
+    }
+}
+
+impl SystemDefinition {
+    pub fn transform_og1(
+        &self,
+        raw: &RawObservablesGroup1,
+        tare: Option<&TareOffset>,
+    ) -> ObservablesGroup1 {
+        let uptime = raw.uptime.duration(&raw.clkfreq);
+        let thrust = self
+            .thrust_calibration
+            .tared_force(raw.thrust.clone(), tare.map(|t| t.thrust));
+        let pressure = self
+            .pressure_calibration
+            .tared_pressure(raw.pressure.clone(), tare.map(|t| t.pressure));
+        ObservablesGroup1 {
+            clkfreq: raw.clkfreq,
+            uptime,
+            time: uptime,
+            thrust,
+            pressure,
+        }
+    }
+
+    pub fn transform_og2(&self, raw: &RawObservablesGroup2) -> ObservablesGroup2 {
+        fn pyro_status_from_bitfield(value: u8) -> PyroStatus {
+            match value {
+                0 => PyroStatus::Unknown,
+                2 => PyroStatus::Open,
+                3 => PyroStatus::Closed,
+                _ => unreachable!(),
+            }
+        }
+
+        fn text(bytes: &[u8]) -> String {
+            String::from_utf8_lossy(bytes).to_string()
+        }
+
+        let vbb_voltage = raw.vbb_voltage as f32 * 0.00125;
+        let recording_state = match &raw.recording {
+            Some(recording) => match recording.state {
+                b'P' => RecordingState::Pause,
+                b'E' => RecordingState::Error(text(&recording.filename_or_error)),
+                b'R' => RecordingState::Recording(text(&recording.filename_or_error)),
+                _ => RecordingState::Unknown,
+            },
+            None => RecordingState::Unknown,
+        };
+        ObservablesGroup2 {
+            recording_state,
+            anomalies: raw.anomalies,
+            records: raw.recording.as_ref().map(|r| r.records),
+            vbb_voltage,
+            pyro12_status: pyro_status_from_bitfield(raw.pyro_status & 0x03),
+            pyro34_status: pyro_status_from_bitfield(raw.pyro_status >> 4 & 0x03),
+        }
+    }
+
+    pub fn transform_og3(&self, raw: &RawObservablesGroup3) -> LaunchDetect {
+        let uptime = raw.uptime.duration(&raw.clkfreq);
+        let breakwire = match raw.breakwire {
+            b'B' => Breakwire::Broken(uptime.saturating_sub(raw.rail_exit.duration(&raw.clkfreq))),
+            _ => Breakwire::Intact,
+        };
+        LaunchDetect { uptime, breakwire }
+    }
+}
@@ -0,0 +1,313 @@
+// Tiered in-memory storage for OBG1 observables. Long static
+// fires at high poll rates would otherwise grow without bounds,
+// so we keep a full-rate window of recent samples and fold
+// everything older into min/max/mean buckets. The raw data
+// still ends up on disk through the Recorder.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use uom::si::{force::kilonewton, pressure::hectopascal};
+
+use super::groups::ObservablesGroup1;
+
+const RECENT_CAPACITY: usize = 2000;
+const BUCKET_SIZE: usize = 20;
+const HISTORY_CAPACITY: usize = 1000;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Observable {
+    Thrust,
+    Pressure,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Aggregate {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Bucket {
+    pub start: Duration,
+    pub end: Duration,
+    pub count: usize,
+    pub thrust: Aggregate,
+    pub pressure: Aggregate,
+}
+
+pub struct Obg1History {
+    recent: VecDeque<ObservablesGroup1>,
+    pending: Vec<ObservablesGroup1>,
+    history: Vec<Bucket>,
+    recent_capacity: usize,
+    bucket_size: usize,
+    history_capacity: usize,
+}
+
+impl Observable {
+    pub fn value(&self, obg1: &ObservablesGroup1) -> f64 {
+        match self {
+            Observable::Thrust => obg1.thrust.get::<kilonewton>(),
+            Observable::Pressure => obg1.pressure.get::<hectopascal>(),
+        }
+    }
+}
+
+impl Aggregate {
+    fn from_values(values: impl Iterator<Item = f64>) -> Self {
+        let (mut min, mut max, mut sum, mut count) = (f64::MAX, f64::MIN, 0.0, 0);
+        for v in values {
+            min = min.min(v);
+            max = max.max(v);
+            sum += v;
+            count += 1;
+        }
+        Self {
+            min,
+            max,
+            mean: sum / count as f64,
+        }
+    }
+
+    fn merge(&self, count: usize, other: &Aggregate, other_count: usize) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+            mean: (self.mean * count as f64 + other.mean * other_count as f64)
+                / (count + other_count) as f64,
+        }
+    }
+}
+
+impl Bucket {
+    fn from_samples(samples: &[ObservablesGroup1]) -> Self {
+        Self {
+            start: samples.first().unwrap().time,
+            end: samples.last().unwrap().time,
+            count: samples.len(),
+            thrust: Aggregate::from_values(samples.iter().map(|s| Observable::Thrust.value(s))),
+            pressure: Aggregate::from_values(samples.iter().map(|s| Observable::Pressure.value(s))),
+        }
+    }
+
+    fn merge(&self, other: &Bucket) -> Self {
+        Self {
+            start: self.start,
+            end: other.end,
+            count: self.count + other.count,
+            thrust: self.thrust.merge(self.count, &other.thrust, other.count),
+            pressure: self
+                .pressure
+                .merge(self.count, &other.pressure, other.count),
+        }
+    }
+
+    pub fn aggregate(&self, observable: Observable) -> &Aggregate {
+        match observable {
+            Observable::Thrust => &self.thrust,
+            Observable::Pressure => &self.pressure,
+        }
+    }
+
+    pub fn midpoint(&self) -> Duration {
+        self.start + (self.end - self.start) / 2
+    }
+}
+
+impl Default for Obg1History {
+    fn default() -> Self {
+        Self::new(RECENT_CAPACITY, BUCKET_SIZE, HISTORY_CAPACITY)
+    }
+}
+
+impl Obg1History {
+    pub fn new(recent_capacity: usize, bucket_size: usize, history_capacity: usize) -> Self {
+        Self {
+            recent: VecDeque::with_capacity(recent_capacity),
+            pending: Vec::with_capacity(bucket_size),
+            history: vec![],
+            recent_capacity,
+            bucket_size,
+            history_capacity,
+        }
+    }
+
+    pub fn push(&mut self, obg1: ObservablesGroup1) {
+        self.recent.push_back(obg1);
+        if self.recent.len() > self.recent_capacity {
+            let oldest = self.recent.pop_front().unwrap();
+            self.pending.push(oldest);
+            if self.pending.len() >= self.bucket_size {
+                self.history.push(Bucket::from_samples(&self.pending));
+                self.pending.clear();
+                self.compact();
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.recent.clear();
+        self.pending.clear();
+        self.history.clear();
+    }
+
+    pub fn last(&self) -> Option<&ObservablesGroup1> {
+        self.recent.back()
+    }
+
+    // Number of samples seen, including the downsampled ones
+    pub fn len(&self) -> usize {
+        self.history.iter().map(|b| b.count).sum::<usize>() + self.pending.len() + self.recent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn recent(&self) -> impl DoubleEndedIterator<Item = &ObservablesGroup1> {
+        self.recent.iter()
+    }
+
+    pub fn history(&self) -> &Vec<Bucket> {
+        &self.history
+    }
+
+    pub fn first_time(&self) -> Option<Duration> {
+        self.history
+            .first()
+            .map(|b| b.start)
+            .or_else(|| self.pending.first().map(|o| o.time))
+            .or_else(|| self.recent.front().map(|o| o.time))
+    }
+
+    // Plot points relative to the first sample. The downsampled
+    // history contributes its bucket means, followed seamlessly
+    // by the full-rate samples.
+    pub fn points(&self, observable: Observable) -> Vec<[f64; 2]> {
+        let start = match self.first_time() {
+            Some(start) => start,
+            None => return vec![],
+        };
+        let relative = |time: Duration| time.saturating_sub(start).as_secs_f64();
+        let mut res: Vec<[f64; 2]> = self
+            .history
+            .iter()
+            .map(|b| [relative(b.midpoint()), b.aggregate(observable).mean])
+            .collect();
+        res.extend(
+            self.pending
+                .iter()
+                .chain(self.recent.iter())
+                .map(|o| [relative(o.time), observable.value(o)]),
+        );
+        res
+    }
+
+    // Of the full-rate samples, the history is too coarse for it
+    pub fn sample_rate(&self) -> Option<f64> {
+        let first = self.recent.front()?.time;
+        let last = self.recent.back()?.time;
+        (last > first).then(|| (self.recent.len() - 1) as f64 / (last - first).as_secs_f64())
+    }
+
+    // The min/max envelope of the downsampled history, so
+    // peaks folded into buckets remain visible.
+    pub fn envelope(&self, observable: Observable) -> (Vec<[f64; 2]>, Vec<[f64; 2]>) {
+        let start = match self.first_time() {
+            Some(start) => start,
+            None => return (vec![], vec![]),
+        };
+        let relative = |time: Duration| time.saturating_sub(start).as_secs_f64();
+        self.history
+            .iter()
+            .map(|b| {
+                let a = b.aggregate(observable);
+                (
+                    [relative(b.midpoint()), a.min],
+                    [relative(b.midpoint()), a.max],
+                )
+            })
+            .unzip()
+    }
+
+    // Once the history exceeds its capacity, neighbouring buckets
+    // are merged, halving the resolution of the whole history.
+    fn compact(&mut self) {
+        if self.history.len() <= self.history_capacity {
+            return;
+        }
+        self.history = self
+            .history
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => a.merge(b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uom::si::f64::{Force, Pressure};
+    use uom::si::{force::kilonewton, pressure::hectopascal};
+
+    use super::super::ClkFreq;
+    use super::*;
+
+    fn sample(secs: u64, thrust: f64) -> ObservablesGroup1 {
+        ObservablesGroup1 {
+            clkfreq: ClkFreq(1),
+            uptime: Duration::from_secs(secs),
+            time: Duration::from_secs(secs),
+            thrust: Force::new::<kilonewton>(thrust),
+            pressure: Pressure::new::<hectopascal>(1000.0),
+        }
+    }
+
+    #[test]
+    fn test_recent_window_is_full_rate() {
+        let mut history = Obg1History::new(10, 2, 10);
+        for i in 0..5 {
+            history.push(sample(i, i as f64));
+        }
+        assert_eq!(history.len(), 5);
+        assert!(history.history().is_empty());
+        assert_eq!(history.points(Observable::Thrust).len(), 5);
+        assert_eq!(history.last().unwrap().uptime, Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_overflow_is_downsampled() {
+        let mut history = Obg1History::new(4, 2, 10);
+        for i in 0..8 {
+            history.push(sample(i, i as f64));
+        }
+        assert_eq!(history.len(), 8);
+        assert_eq!(history.history().len(), 2);
+        let bucket = history.history()[0];
+        assert_eq!(bucket.count, 2);
+        assert_eq!(bucket.thrust.min, 0.0);
+        assert_eq!(bucket.thrust.max, 1.0);
+        assert_eq!(bucket.thrust.mean, 0.5);
+        assert_eq!(history.first_time(), Some(Duration::from_secs(0)));
+        assert_eq!(history.points(Observable::Thrust).len(), 6);
+        assert_eq!(history.sample_rate(), Some(1.0));
+    }
+
+    #[test]
+    fn test_history_is_compacted() {
+        let mut history = Obg1History::new(1, 1, 4);
+        for i in 0..6 {
+            history.push(sample(i, i as f64));
+        }
+        assert_eq!(history.history().len(), 3);
+        assert_eq!(history.history()[0].count, 2);
+        assert_eq!(history.history()[0].thrust.mean, 0.5);
+        assert_eq!(history.len(), 6);
+        history.clear();
+        assert!(history.is_empty());
+    }
+}
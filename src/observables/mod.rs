@@ -1,9 +1,12 @@
 use clap::ArgEnum;
+use serde::Deserialize;
 use std::time::Duration;
 use uom::si::f64::*;
 use uom::si::force::kilonewton;
 use uom::si::pressure::bar;
 
+use crate::rqprotocol::Node;
+
 // Raw wire-values
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct ClkFreq(pub u32);
@@ -36,10 +39,38 @@ pub enum AdcGain {
     Gain64,
 }
 
-#[cfg(feature = "test-stand")]
-pub mod rqa;
-#[cfg(feature = "rocket")]
-pub mod rqb;
+// What we are connected to, the test stand or the rocket. Decides
+// how OBG2 is parsed and how the observables are shown, so one
+// binary serves both roles.
+#[derive(Clone, Copy, Debug, Default, ArgEnum, PartialEq, Deserialize)]
+#[clap(rename_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum Profile {
+    TestStand,
+    #[default]
+    Rocket,
+}
+
+impl Profile {
+    // The RedQueen we control
+    pub fn target(&self) -> Node {
+        match self {
+            Profile::TestStand => Node::RedQueen(b'A'),
+            Profile::Rocket => Node::RedQueen(b'B'),
+        }
+    }
+}
+
+pub mod adc;
+pub mod filter;
+pub mod groups;
+pub mod history;
+pub mod range;
+pub mod reference;
+pub mod tare;
+pub mod template;
+pub mod units;
+pub mod uptime;
 
 impl Timestamp {
     pub fn duration(&self, clkfreq: &ClkFreq) -> Duration {
@@ -62,6 +93,15 @@ impl AdcForceCalibration {
         let res = value.into() * self.m + self.c;
         Force::new::<kilonewton>(res)
     }
+
+    // A tared reading replaces the intercept, so the
+    // reading at tare time becomes zero.
+    pub fn tared_force(&self, value: impl Into<f64>, offset: Option<f64>) -> Force {
+        match offset {
+            Some(offset) => Force::new::<kilonewton>((value.into() - offset) * self.m),
+            None => self.force(value),
+        }
+    }
 }
 
 impl AdcPressureCalibration {
@@ -69,6 +109,13 @@ impl AdcPressureCalibration {
         let res = value.into() * self.m + self.c;
         Pressure::new::<bar>(res)
     }
+
+    pub fn tared_pressure(&self, value: impl Into<f64>, offset: Option<f64>) -> Pressure {
+        match offset {
+            Some(offset) => Pressure::new::<bar>((value.into() - offset) * self.m),
+            None => self.pressure(value),
+        }
+    }
 }
 
 impl Into<u8> for AdcGain {
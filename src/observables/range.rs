@@ -0,0 +1,176 @@
+// Vertical ranges of the thrust and pressure plots. Fitted by egui,
+// a single spike squeezes the burn into a flat line and the axis
+// jumps with every frame. A plot either has a fixed range from the
+// config file, or follows the data without its outermost samples,
+// rounded to whole ticks so the axis holds still. The smallest span
+// shrinks with the ADC gain, a higher gain resolves finer changes.
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use super::history::Observable;
+
+// Of the samples, ignored at either end
+const SPIKE_SHARE: f64 = 0.01;
+// Of the span, added above and below
+const MARGIN: f64 = 0.1;
+const TICKS: f64 = 5.0;
+const MAX_DECIMALS: usize = 6;
+
+// In the unit of the plot
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlotRange {
+    Auto,
+    Fixed { min: f64, max: f64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlotRanges {
+    pub thrust: PlotRange,
+    pub pressure: PlotRange,
+}
+
+impl Observable {
+    // Of the plotted values
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Observable::Thrust => "kN",
+            Observable::Pressure => "hPa",
+        }
+    }
+
+    // At gain 1
+    fn min_span(&self) -> f64 {
+        match self {
+            Observable::Thrust => 1.0,
+            Observable::Pressure => 100.0,
+        }
+    }
+}
+
+impl Default for PlotRanges {
+    fn default() -> Self {
+        Self {
+            thrust: PlotRange::Auto,
+            pressure: PlotRange::Auto,
+        }
+    }
+}
+
+impl PlotRanges {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (name, range) in [("thrust", self.thrust), ("pressure", self.pressure)] {
+            if let PlotRange::Fixed { min, max } = range {
+                if !(min.is_finite() && max.is_finite() && min < max) {
+                    anyhow::bail!("{} plot range needs min below max", name);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, observable: Observable) -> PlotRange {
+        match observable {
+            Observable::Thrust => self.thrust,
+            Observable::Pressure => self.pressure,
+        }
+    }
+
+    // None lets egui fit the plot, there's nothing to go by yet
+    pub fn y_range(&self, observable: Observable, values: &[f64], gain: u8) -> Option<(f64, f64)> {
+        match self.get(observable) {
+            PlotRange::Fixed { min, max } => Some((min, max)),
+            PlotRange::Auto => auto_range(values, observable.min_span() / gain.max(1) as f64),
+        }
+    }
+}
+
+// 1, 2 or 5 times a power of ten, at least the given step
+fn nice_step(step: f64) -> f64 {
+    let magnitude = 10f64.powf(step.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|factor| factor * magnitude)
+        .find(|nice| *nice >= step)
+        .unwrap_or(10.0 * magnitude)
+}
+
+fn auto_range(values: &[f64], min_span: f64) -> Option<(f64, f64)> {
+    let mut sorted: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if sorted.is_empty() {
+        return None;
+    }
+    sorted.sort_by(f64::total_cmp);
+    let ignored = (sorted.len() as f64 * SPIKE_SHARE) as usize;
+    let (low, high) = (sorted[ignored], sorted[sorted.len() - 1 - ignored]);
+    let span = (high - low).max(min_span);
+    let center = (low + high) / 2.0;
+    let half = span * (0.5 + MARGIN);
+    let step = nice_step(2.0 * half / TICKS);
+    Some((
+        ((center - half) / step).floor() * step,
+        ((center + half) / step).ceil() * step,
+    ))
+}
+
+// Tick labels with the unit, with a decimal more than the
+// spacing of the ticks needs
+pub fn format_tick(value: f64, range: &RangeInclusive<f64>, unit: &str) -> String {
+    let step = (range.end() - range.start()) / TICKS;
+    let decimals = if step > 0.0 {
+        (1.0 - step.log10().floor()).clamp(0.0, MAX_DECIMALS as f64) as usize
+    } else {
+        0
+    };
+    format!("{:.*} {}", decimals, value, unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_range_ignores_spikes() {
+        let mut values: Vec<f64> = (0..200).map(|i| 2.0 + (i % 10) as f64 * 0.5).collect();
+        values[50] = 40.0;
+        values[120] = -30.0;
+        let ranges = PlotRanges::default();
+        assert_eq!(
+            ranges.y_range(Observable::Thrust, &values, 1),
+            Some((0.0, 8.0))
+        );
+        // A flat line still gets a span, finer at higher gain
+        assert_eq!(
+            ranges.y_range(Observable::Thrust, &[1.0; 10], 1),
+            Some((0.0, 2.0))
+        );
+        let (low, high) = ranges.y_range(Observable::Thrust, &[1.0; 10], 10).unwrap();
+        assert!((high - low - 0.2).abs() < 1e-9 && low < 1.0 && high > 1.0);
+        assert_eq!(ranges.y_range(Observable::Pressure, &[], 1), None);
+    }
+
+    #[test]
+    fn test_fixed_range_and_ticks() {
+        let ranges = PlotRanges {
+            thrust: PlotRange::Fixed { min: 0.0, max: 5.0 },
+            ..Default::default()
+        };
+        assert_eq!(
+            ranges.y_range(Observable::Thrust, &[100.0], 1),
+            Some((0.0, 5.0))
+        );
+        assert!(ranges.validate().is_ok());
+        assert!(PlotRanges {
+            pressure: PlotRange::Fixed { min: 3.0, max: 3.0 },
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+        assert_eq!(format_tick(2.5, &(0.0..=5.0), "kN"), "2.5 kN");
+        assert_eq!(format_tick(1013.0, &(900.0..=1100.0), "hPa"), "1013 hPa");
+        assert_eq!(nice_step(0.3), 0.5);
+        assert_eq!(nice_step(7.0), 10.0);
+    }
+}
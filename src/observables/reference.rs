@@ -0,0 +1,236 @@
+// The thrust curve a motor is expected to deliver, to compare a
+// static fire against. Read from RASP .eng files as published for
+// most motors, or from CSV with time and thrust per line:
+//
+// ; comment
+// H128 29 194 0-5-10 0.0937 0.2 AT
+//    0.01 120.0
+//    ...
+//
+// time [s],thrust [N]
+// 0.01,120.0
+//
+// Thrust is given in Newtons and kept in kN like the observables.
+use std::path::Path;
+
+use anyhow::{anyhow, bail};
+
+// Measured thrust below this counts as burnt out
+const BURNOUT_FRACTION: f64 = 0.05;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferenceCurve {
+    pub name: String,
+    // Seconds after ignition and kN, ordered by time
+    points: Vec<(f64, f64)>,
+}
+
+// Measured against the reference, over the time after ignition
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deviation {
+    pub peak: (f64, f64),
+    pub impulse: (f64, f64),
+    pub burn_time: (f64, f64),
+    // Root mean square of the difference, in kN
+    pub rms: f64,
+}
+
+impl ReferenceCurve {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("eng") => Self::parse_eng(&contents),
+            _ => Self::parse_csv(&name, &contents),
+        }
+    }
+
+    pub fn parse_eng(contents: &str) -> anyhow::Result<Self> {
+        let mut lines = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with(';'));
+        let header = lines.next().ok_or(anyhow!("no motor header"))?;
+        let name = header.split_whitespace().next().unwrap_or_default();
+        let points = lines
+            .map(|line| {
+                let mut fields = line.split_whitespace();
+                match (fields.next(), fields.next()) {
+                    (Some(time), Some(thrust)) => point(time, thrust),
+                    _ => Err(anyhow!("expected time and thrust: {}", line)),
+                }
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Self::new(name, points)
+    }
+
+    // A header line is skipped
+    pub fn parse_csv(name: &str, contents: &str) -> anyhow::Result<Self> {
+        let mut points = vec![];
+        for (index, line) in contents.lines().map(str::trim).enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (time, thrust) = line
+                .split_once(',')
+                .ok_or(anyhow!("expected time,thrust: {}", line))?;
+            match point(time, thrust) {
+                Ok(point) => points.push(point),
+                Err(_) if index == 0 => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Self::new(name, points)
+    }
+
+    fn new(name: &str, mut points: Vec<(f64, f64)>) -> anyhow::Result<Self> {
+        // Equal times would divide by zero when interpolating
+        if points.windows(2).any(|w| w[1].0 <= w[0].0) {
+            bail!("thrust curve isn't strictly ordered by time");
+        }
+        // Curves start at ignition with no thrust
+        if points.first().map_or(true, |first| first.0 > 0.0) {
+            points.insert(0, (0.0, 0.0));
+        }
+        if points.len() < 2 {
+            bail!("thrust curve without data");
+        }
+        Ok(Self {
+            name: name.into(),
+            points,
+        })
+    }
+
+    pub fn burn_time(&self) -> f64 {
+        self.points.last().unwrap().0
+    }
+
+    // Interpolated, nothing outside the burn
+    pub fn thrust_at(&self, time: f64) -> f64 {
+        match self.points.iter().position(|(t, _)| *t >= time) {
+            Some(0) | None => 0.0,
+            Some(i) => {
+                let ((t0, f0), (t1, f1)) = (self.points[i - 1], self.points[i]);
+                f0 + (f1 - f0) * (time - t0) / (t1 - t0)
+            }
+        }
+    }
+
+    // On the timeline, with ignition at the given time
+    pub fn points(&self, ignition: f64) -> Vec<[f64; 2]> {
+        self.points
+            .iter()
+            .map(|(time, thrust)| [ignition + time, *thrust])
+            .collect()
+    }
+
+    // From the measured timeline points, once the burn is over
+    pub fn deviation(&self, measured: &[[f64; 2]], ignition: f64) -> Option<Deviation> {
+        let burn_time = self.burn_time();
+        let measured: Vec<(f64, f64)> = measured
+            .iter()
+            .map(|[time, thrust]| (time - ignition, *thrust))
+            .filter(|(time, _)| *time >= 0.0)
+            .collect();
+        if measured.last().map_or(true, |(time, _)| *time < burn_time) {
+            return None;
+        }
+        let peak = |points: &[(f64, f64)]| points.iter().map(|p| p.1).fold(0.0, f64::max);
+        let impulse = |points: &[(f64, f64)]| {
+            points
+                .windows(2)
+                .map(|w| (w[1].0 - w[0].0) * (w[0].1 + w[1].1) / 2.0)
+                .sum::<f64>()
+        };
+        let burnout = peak(&measured) * BURNOUT_FRACTION;
+        let measured_burn_time = measured
+            .iter()
+            .rev()
+            .find(|(_, thrust)| *thrust > burnout)
+            .map_or(0.0, |(time, _)| *time);
+        let during_burn: Vec<_> = measured
+            .iter()
+            .filter(|(time, _)| *time <= burn_time)
+            .map(|(time, thrust)| (thrust - self.thrust_at(*time)).powi(2))
+            .collect();
+        let rms = (during_burn.iter().sum::<f64>() / during_burn.len().max(1) as f64).sqrt();
+        Some(Deviation {
+            peak: (peak(&measured), peak(&self.points)),
+            impulse: (impulse(&measured), impulse(&self.points)),
+            burn_time: (measured_burn_time, burn_time),
+            rms,
+        })
+    }
+}
+
+// Newtons to kN
+fn point(time: &str, thrust: &str) -> anyhow::Result<(f64, f64)> {
+    Ok((time.trim().parse()?, thrust.trim().parse::<f64>()? / 1000.0))
+}
+
+impl Deviation {
+    pub fn summary(&self) -> String {
+        format!(
+            "Peak {:.2}kN (expected {:.2}kN), impulse {:.2}kNs (expected {:.2}kNs), burn time {:.2}s (expected {:.2}s), RMS deviation {:.3}kN",
+            self.peak.0,
+            self.peak.1,
+            self.impulse.0,
+            self.impulse.1,
+            self.burn_time.0,
+            self.burn_time.1,
+            self.rms
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENG: &str = "; A made up motor
+X1000 75 500 0 1.0 2.0 ARR
+   0.5 2000.0
+   1.0 1000.0
+   1.5 0.0
+";
+
+    #[test]
+    fn test_formats_agree() {
+        let eng = ReferenceCurve::parse_eng(ENG).unwrap();
+        let csv =
+            ReferenceCurve::parse_csv("X1000", "time [s],thrust [N]\n0.5,2000\n1.0,1000\n1.5,0\n")
+                .unwrap();
+        assert_eq!(eng, csv);
+        assert_eq!(eng.burn_time(), 1.5);
+        assert_eq!(eng.thrust_at(0.25), 1.0);
+        assert_eq!(eng.thrust_at(2.0), 0.0);
+        assert_eq!(eng.points(10.0)[1], [10.5, 2.0]);
+        assert!(ReferenceCurve::parse_csv("broken", "0.5,2000\n0.25,1000\n").is_err());
+        assert!(ReferenceCurve::parse_csv("broken", "0.5,2000\n0.5,1000\n").is_err());
+    }
+
+    #[test]
+    fn test_deviation_after_burn() {
+        let reference = ReferenceCurve::parse_eng(ENG).unwrap();
+        let ignition = 10.0;
+        // Samples of the reference at 10% more thrust
+        let mut measured: Vec<[f64; 2]> = (0..=15)
+            .map(|i| {
+                let time = i as f64 / 10.0;
+                [ignition + time, reference.thrust_at(time) * 1.1]
+            })
+            .collect();
+        assert_eq!(reference.deviation(&measured[..10], ignition), None);
+        measured.push([ignition + 2.0, 0.0]);
+        let deviation = reference.deviation(&measured, ignition).unwrap();
+        assert!((deviation.peak.0 - 2.2).abs() < 1e-9);
+        assert_eq!(deviation.peak.1, 2.0);
+        assert!((deviation.impulse.0 - deviation.impulse.1 * 1.1).abs() < 1e-9);
+        assert!((deviation.burn_time.0 - 1.4).abs() < 1e-9);
+        assert_eq!(deviation.burn_time.1, 1.5);
+        assert!(deviation.rms > 0.0 && deviation.rms < 0.2);
+    }
+}
@@ -0,0 +1,124 @@
+// Zeroing of the load cell and pressure sensor before a static
+// fire. We keep a short window of raw ADC readings, and taring
+// records their average as offset which the transform pipeline
+// then subtracts. The offset lives as long as the session.
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+
+use super::groups::RawObservablesGroup1;
+
+const TARE_WINDOW: usize = 50;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TareOffset {
+    pub thrust: f64,
+    pub pressure: f64,
+    pub taken_at: DateTime<Utc>,
+}
+
+pub struct Tare {
+    window: VecDeque<(f64, f64)>,
+    window_size: usize,
+    pub offset: Option<TareOffset>,
+}
+
+impl Default for Tare {
+    fn default() -> Self {
+        Self::new(TARE_WINDOW)
+    }
+}
+
+impl TareOffset {
+    // One line per tare, written next to the recording
+    // so exported data can be interpreted later.
+    pub fn annotation(&self) -> String {
+        format!(
+            "{},thrust={:.1},pressure={:.1}\n",
+            self.taken_at.to_rfc3339(),
+            self.thrust,
+            self.pressure
+        )
+    }
+}
+
+impl Tare {
+    pub fn new(window_size: usize) -> Self {
+        Self {
+            window: VecDeque::with_capacity(window_size),
+            window_size,
+            offset: None,
+        }
+    }
+
+    pub fn feed(&mut self, raw: &RawObservablesGroup1) {
+        if self.window.len() == self.window_size {
+            self.window.pop_front();
+        }
+        self.window
+            .push_back((raw.thrust.into(), raw.pressure.into()));
+    }
+
+    // Takes the averaged readings as new offset. Without
+    // any readings, the previous offset is kept.
+    pub fn tare(&mut self, now: DateTime<Utc>) -> Option<&TareOffset> {
+        if self.window.is_empty() {
+            return None;
+        }
+        let count = self.window.len() as f64;
+        let (thrust, pressure) = self
+            .window
+            .iter()
+            .fold((0.0, 0.0), |(t, p), (thrust, pressure)| {
+                (t + thrust, p + pressure)
+            });
+        self.offset = Some(TareOffset {
+            thrust: thrust / count,
+            pressure: pressure / count,
+            taken_at: now,
+        });
+        self.offset.as_ref()
+    }
+
+    pub fn clear(&mut self) {
+        self.offset = None;
+    }
+}
+
+pub fn annotate(recording: &Path, offset: &TareOffset) -> std::io::Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(recording.with_extension("tare"))?;
+    file.write_all(offset.annotation().as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Ads1256Reading, ClkFreq, Timestamp};
+    use super::*;
+
+    fn raw(thrust: i32, pressure: i32) -> RawObservablesGroup1 {
+        RawObservablesGroup1 {
+            clkfreq: ClkFreq(1),
+            uptime: Timestamp(0),
+            thrust: Ads1256Reading(thrust),
+            pressure: Ads1256Reading(pressure),
+        }
+    }
+
+    #[test]
+    fn test_tare_averages_window() {
+        let mut tare = Tare::new(2);
+        assert_eq!(tare.tare(Utc::now()), None);
+        tare.feed(&raw(100, 1000));
+        tare.feed(&raw(200, 2000));
+        tare.feed(&raw(300, 3000));
+        let offset = tare.tare(Utc::now()).unwrap();
+        assert_eq!(offset.thrust, 250.0);
+        assert_eq!(offset.pressure, 2500.0);
+    }
+}
@@ -0,0 +1,155 @@
+// Observable groups declared by their fields, instead of writing the
+// parser, the structs, the transform and the render by hand. Each
+// field has a name, its wire type, the scale to its unit and the
+// unit:
+//
+//     observable_group! {
+//         // Tank of the hybrid
+//         4, RawTankGroup => TankGroup {
+//             temperature: i32, 0.01, "°C";
+//             level: u16, 0.1, "%";
+//         }
+//     }
+//
+// parses `RQAOBG,042,LNC,4,FFFFFF9C,01F4` with RawTankGroup::parse
+// and transforms it to a TankGroup of -1°C and 50%, to be shown with
+// render_group_table. To onboard it, add a variant to
+// RawObservablesGroup and the parser to obg_parser of the profile.
+// Groups with text or flags still need writing by hand.
+
+// Of a declared group
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Field {
+    pub name: &'static str,
+    pub unit: &'static str,
+}
+
+pub trait GroupTable {
+    const FIELDS: &'static [Field];
+
+    // In the order of the fields
+    fn values(&self) -> Vec<f64>;
+
+    // Name and value with unit, for the default render
+    fn rows(&self) -> Vec<(String, String)> {
+        Self::FIELDS
+            .iter()
+            .zip(self.values())
+            .map(|(field, value)| {
+                (
+                    field.name.replace('_', " "),
+                    format!("{:.3} {}", value, field.unit)
+                        .trim_end()
+                        .to_string(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[macro_export]
+macro_rules! observable_group {
+    (
+        $(#[$meta:meta])*
+        $group:literal, $raw:ident => $name:ident {
+            $($field:ident: $wire:ty, $scale:expr, $unit:literal;)*
+        }
+    ) => {
+        #[derive(Copy, Clone, PartialEq, Debug)]
+        pub struct $raw {
+            $(pub $field: $wire,)*
+        }
+
+        $(#[$meta])*
+        #[derive(Copy, Clone, PartialEq, Debug)]
+        pub struct $name {
+            $(pub $field: f64,)*
+        }
+
+        impl $raw {
+            pub const GROUP: u8 = $group;
+
+            pub fn parse(
+                s: &[u8],
+            ) -> $crate::rqparser::ParseResult<'_,
+                ($crate::rqprotocol::Node, usize, $crate::rqprotocol::Node, Self),
+            > {
+                let (s, (source, command_id, recipient)) =
+                    $crate::rqparser::wire::obg_header_parser(s, $group)?;
+                $(
+                    let (s, _) = nom::bytes::complete::tag(b",")(s)?;
+                    let (s, $field) = <$wire as $crate::rqparser::wire::Wire>::parse(s)?;
+                )*
+                Ok((s, (source, command_id, recipient, Self { $($field,)* })))
+            }
+
+            pub fn transform(&self) -> $name {
+                $name {
+                    $($field: $crate::rqparser::wire::Wire::to_f64(self.$field) * $scale,)*
+                }
+            }
+        }
+
+        impl $crate::observables::template::GroupTable for $name {
+            const FIELDS: &'static [$crate::observables::template::Field] = &[
+                $($crate::observables::template::Field {
+                    name: stringify!($field),
+                    unit: $unit,
+                },)*
+            ];
+
+            fn values(&self) -> Vec<f64> {
+                vec![$(self.$field,)*]
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rqprotocol::Node;
+
+    observable_group! {
+        4, RawTankGroup => TankGroup {
+            temperature: i32, 0.01, "°C";
+            level: u16, 0.1, "%";
+            valve_cycles: u8, 1.0, "";
+        }
+    }
+
+    #[test]
+    fn test_declared_group() {
+        let (rest, (source, id, recipient, raw)) =
+            RawTankGroup::parse(b"RQAOBG,042,LNC,4,FFFFFF9C,01F4,03").unwrap();
+        assert_eq!(
+            (rest, source, id, recipient),
+            (
+                b"".as_slice(),
+                Node::RedQueen(b'A'),
+                42,
+                Node::LaunchControl
+            )
+        );
+        let tank = raw.transform();
+        assert_eq!(
+            tank,
+            TankGroup {
+                temperature: -1.0,
+                level: 50.0,
+                valve_cycles: 3.0,
+            }
+        );
+        assert_eq!(
+            tank.rows(),
+            vec![
+                ("temperature".into(), "-1.000 °C".into()),
+                ("level".into(), "50.000 %".into()),
+                ("valve cycles".into(), "3.000".into()),
+            ]
+        );
+        assert_eq!(RawTankGroup::GROUP, 4);
+        assert!(RawTankGroup::parse(b"RQAOBG,042,LNC,1,FFFFFF9C,01F4,03").is_err());
+        assert!(RawTankGroup::parse(b"RQAOBG,042,LNC,4,FFFFFF9C,01F4").is_err());
+    }
+}
@@ -0,0 +1,62 @@
+// What thrust and pressure are shown in. Everything is kept and
+// recorded in SI units, only the numbers on the screen follow.
+use std::str::FromStr;
+
+use serde::Deserialize;
+use uom::si::{
+    f64::{Force, Pressure},
+    force::{kilonewton, pound_force},
+    pressure::{bar, psi},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+pub enum Units {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+impl FromStr for Units {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Metric" => Ok(Units::Metric),
+            "Imperial" => Ok(Units::Imperial),
+            _ => Err("No valid value, use Metric, Imperial"),
+        }
+    }
+}
+
+impl Units {
+    pub fn thrust(&self, thrust: Force) -> (f64, &'static str) {
+        match self {
+            Units::Metric => (thrust.get::<kilonewton>(), "kN"),
+            Units::Imperial => (thrust.get::<pound_force>(), "lbf"),
+        }
+    }
+
+    pub fn pressure(&self, pressure: Pressure) -> (f64, &'static str) {
+        match self {
+            Units::Metric => (pressure.get::<bar>(), "bar"),
+            Units::Imperial => (pressure.get::<psi>(), "psi"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        let thrust = Force::new::<kilonewton>(1.0);
+        assert_eq!(Units::Metric.thrust(thrust), (1.0, "kN"));
+        let (lbf, unit) = Units::Imperial.thrust(thrust);
+        assert!((lbf - 224.809).abs() < 1e-3);
+        assert_eq!(unit, "lbf");
+        let (pressure, _) = Units::Imperial.pressure(Pressure::new::<bar>(1.0));
+        assert!((pressure - 14.5038).abs() < 1e-4);
+        assert_eq!("Imperial".parse(), Ok(Units::Imperial));
+    }
+}
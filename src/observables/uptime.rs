@@ -0,0 +1,54 @@
+// The x-axis of the observables is the node's uptime, as converted
+// with its clock frequency. The uptime restarts at zero when a node
+// reboots, so to keep the samples of a node on one axis the time after
+// a reboot continues where the last sample before it was, plus the
+// time that passed on the host in between.
+use std::time::Duration;
+
+#[derive(Debug, Default, Clone)]
+pub struct NodeClock {
+    offset: Duration,
+    // Uptime and host time of the last sample
+    last: Option<(Duration, Duration)>,
+    reboots: usize,
+}
+
+impl NodeClock {
+    pub fn is_reboot(&self, uptime: Duration) -> bool {
+        self.last.map_or(false, |(last, _)| uptime < last)
+    }
+
+    // Of a sample, continuous across reboots
+    pub fn time(&mut self, uptime: Duration, host_time: Duration) -> Duration {
+        if let Some((last, last_host_time)) = self.last {
+            if uptime < last {
+                self.reboots += 1;
+                self.offset += last + host_time.saturating_sub(last_host_time) - uptime;
+            }
+        }
+        self.last = Some((uptime, host_time));
+        self.offset + uptime
+    }
+
+    pub fn reboots(&self) -> usize {
+        self.reboots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_continues_across_reboots() {
+        let secs = Duration::from_secs;
+        let mut clock = NodeClock::default();
+        assert_eq!(clock.time(secs(100), secs(10)), secs(100));
+        assert_eq!(clock.time(secs(101), secs(11)), secs(101));
+        assert!(clock.is_reboot(secs(1)));
+        // Down for 4s, up for 1s
+        assert_eq!(clock.time(secs(1), secs(16)), secs(106));
+        assert_eq!(clock.time(secs(2), secs(17)), secs(107));
+        assert_eq!(clock.reboots(), 1);
+    }
+}
@@ -0,0 +1,137 @@
+// Starting and stopping the recording on the node from the
+// observables. The operator confirms first, and the change only
+// counts once OBG2 reports the new recording state, the ACK alone
+// just tells us the command arrived.
+use std::time::Duration;
+
+use crate::clock::Instant;
+use crate::rqprotocol::Command;
+
+// How long OBG2 has to reflect the change
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OnboardRecording {
+    #[default]
+    Idle,
+    // Asking whether to start (true) or stop the recording
+    Confirm(bool),
+    // Confirmed, goes out once the consort is free
+    Requested(bool),
+    // Waiting for OBG2
+    Sent {
+        start: bool,
+        at: Instant,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Started,
+    Stopped,
+    Unconfirmed,
+}
+
+impl OnboardRecording {
+    pub fn is_idle(&self) -> bool {
+        *self == OnboardRecording::Idle
+    }
+
+    pub fn request(&mut self, recording: bool) {
+        if self.is_idle() {
+            *self = OnboardRecording::Confirm(!recording);
+        }
+    }
+
+    pub fn confirm(&mut self) {
+        if let OnboardRecording::Confirm(start) = *self {
+            *self = OnboardRecording::Requested(start);
+        }
+    }
+
+    // Without asking, returns if nothing else was going on
+    pub fn automatic(&mut self, start: bool) -> bool {
+        let idle = self.is_idle();
+        if idle {
+            *self = OnboardRecording::Requested(start);
+        }
+        idle
+    }
+
+    pub fn cancel(&mut self) {
+        if let OnboardRecording::Confirm(_) = self {
+            *self = OnboardRecording::Idle;
+        }
+    }
+
+    // The command to send, if one is due
+    pub fn command(&self) -> Option<Command> {
+        match self {
+            OnboardRecording::Requested(true) => Some(Command::RecordStart),
+            OnboardRecording::Requested(false) => Some(Command::RecordStop),
+            _ => None,
+        }
+    }
+
+    pub fn sent(&mut self, now: Instant) {
+        if let OnboardRecording::Requested(start) = *self {
+            *self = OnboardRecording::Sent { start, at: now };
+        }
+    }
+
+    // The node refused, there's nothing to wait for
+    pub fn refused(&mut self) -> bool {
+        let sent = matches!(self, OnboardRecording::Sent { .. });
+        if sent {
+            *self = OnboardRecording::Idle;
+        }
+        sent
+    }
+
+    // With the recording state from the latest OBG2
+    pub fn observe(&mut self, recording: bool, now: Instant) -> Option<Outcome> {
+        let (start, at) = match *self {
+            OnboardRecording::Sent { start, at } => (start, at),
+            _ => return None,
+        };
+        let outcome = match (recording == start, start) {
+            (true, true) => Outcome::Started,
+            (true, false) => Outcome::Stopped,
+            _ if now.duration_since(at) > CONFIRMATION_TIMEOUT => Outcome::Unconfirmed,
+            _ => return None,
+        };
+        *self = OnboardRecording::Idle;
+        Some(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, SimulatedClock};
+
+    #[test]
+    fn test_change_counts_once_reported() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut control = OnboardRecording::default();
+        control.request(false);
+        assert_eq!(control.command(), None);
+        control.confirm();
+        assert_eq!(control.command(), Some(Command::RecordStart));
+        control.sent(clock.now());
+        assert_eq!(control.observe(false, clock.now()), None);
+        assert_eq!(control.observe(true, clock.now()), Some(Outcome::Started));
+        assert!(control.is_idle());
+        control.request(true);
+        control.cancel();
+        assert!(control.is_idle());
+        control.request(true);
+        control.confirm();
+        control.sent(clock.now());
+        clock.advance(CONFIRMATION_TIMEOUT * 2);
+        assert_eq!(
+            control.observe(true, clock.now()),
+            Some(Outcome::Unconfirmed)
+        );
+    }
+}
@@ -0,0 +1,275 @@
+// A handheld wireless pendant sending the input events, so the arming
+// can be done away from the screen. It's off unless a port and a key
+// are configured. The pendant sends a UDP datagram per button press:
+//
+//     PDT,<sequence>,<event>,<tag>
+//
+// - The event is ENTER, BACK, SEND, LEFT:<n> or RIGHT:<n>, or ALIVE
+//   without any, sent every few seconds.
+// - The sequence is a decimal u64 the pendant increases with every
+//   datagram. We drop those not above the last one.
+// - The tag is the ChaCha20-Poly1305 tag of nothing, with the datagram
+//   up to the last comma as associated data, in 32 hex digits. The
+//   nonce is `PDT`, a zero byte and the 8 sequence bytes big endian,
+//   as with the link encryption.
+//
+// The last sequence is kept on the machine, so a restart doesn't open
+// the door to replaying captured datagrams. Only without it, when
+// paired for the first time, the first authentic datagram just tells
+// us where the pendant is.
+use std::io::ErrorKind;
+use std::net::UdpSocket;
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+
+use crate::input::InputEvent;
+use crate::linkcrypto::{aead, Error, Key};
+
+const ID: &str = "PDT";
+const ALIVE: &str = "ALIVE";
+const MAX_DATAGRAM: usize = 128;
+const SEQUENCE_FILE: &str = "pendant";
+
+pub struct Pendant {
+    socket: UdpSocket,
+    key: Key,
+    // None until the first authentic datagram
+    last_sequence: Option<u64>,
+    sequence_path: Option<PathBuf>,
+}
+
+fn read_sequence(path: &Path) -> Option<u64> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn write_sequence(path: &Path, sequence: u64) -> std::io::Result<()> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    std::fs::write(path, sequence.to_string())
+}
+
+fn nonce(sequence: u64) -> [u8; aead::NONCE_SIZE] {
+    let mut nonce = [0; aead::NONCE_SIZE];
+    nonce[..ID.len()].copy_from_slice(ID.as_bytes());
+    nonce[ID.len() + 1..].copy_from_slice(&sequence.to_be_bytes());
+    nonce
+}
+
+fn tag(key: &Key, sequence: u64, aad: &str) -> [u8; aead::TAG_SIZE] {
+    aead::seal(key, &nonce(sequence), aad.as_bytes(), &mut [])
+}
+
+fn event_text(event: Option<InputEvent>) -> String {
+    match event {
+        None => ALIVE.into(),
+        Some(InputEvent::Enter) => "ENTER".into(),
+        Some(InputEvent::Back) => "BACK".into(),
+        Some(InputEvent::Send) => "SEND".into(),
+        Some(InputEvent::Left(n)) => format!("LEFT:{}", n),
+        Some(InputEvent::Right(n)) => format!("RIGHT:{}", n),
+    }
+}
+
+// None is ALIVE
+fn parse_event(text: &str) -> Result<Option<InputEvent>, Error> {
+    let magnitude = |n: &str| n.parse().map_err(|_| Error::Malformed);
+    match text.split_once(':') {
+        None if text == ALIVE => Ok(None),
+        None if text == "ENTER" => Ok(Some(InputEvent::Enter)),
+        None if text == "BACK" => Ok(Some(InputEvent::Back)),
+        None if text == "SEND" => Ok(Some(InputEvent::Send)),
+        Some(("LEFT", n)) => Ok(Some(InputEvent::Left(magnitude(n)?))),
+        Some(("RIGHT", n)) => Ok(Some(InputEvent::Right(magnitude(n)?))),
+        _ => Err(Error::Malformed),
+    }
+}
+
+fn parse_tag(hex: &str) -> Result<[u8; aead::TAG_SIZE], Error> {
+    if hex.len() != 2 * aead::TAG_SIZE || !hex.is_ascii() {
+        return Err(Error::Malformed);
+    }
+    let mut tag = [0; aead::TAG_SIZE];
+    for (i, byte) in tag.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| Error::Malformed)?;
+    }
+    Ok(tag)
+}
+
+// What the pendant sends, for the firmware and the tests
+pub fn datagram(key: &Key, sequence: u64, event: Option<InputEvent>) -> String {
+    let aad = format!("{},{},{}", ID, sequence, event_text(event));
+    let tag: String = tag(key, sequence, &aad)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("{},{}", aad, tag)
+}
+
+impl Pendant {
+    pub fn bind(port: u16, key: Key) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        socket.set_nonblocking(true)?;
+        info!("Listening for the pendant on port {}", port);
+        let sequence_path = crate::ports::settings_path(SEQUENCE_FILE);
+        if sequence_path.is_none() {
+            warn!("No settings directory, the pendant sequence isn't kept");
+        }
+        Ok(Self::with_sequence_path(socket, key, sequence_path))
+    }
+
+    fn with_sequence_path(socket: UdpSocket, key: Key, sequence_path: Option<PathBuf>) -> Self {
+        Self {
+            socket,
+            key,
+            last_sequence: sequence_path.as_deref().and_then(read_sequence),
+            sequence_path,
+        }
+    }
+
+    // Of all datagrams received since the last call
+    pub fn poll(&mut self) -> Vec<InputEvent> {
+        let mut events = vec![];
+        let mut buffer = [0; MAX_DATAGRAM];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((size, sender)) => match self.accept(&buffer[..size]) {
+                    Ok(event) => events.extend(event),
+                    Err(e) => warn!("Dropped pendant datagram from {}, {:?}", sender, e),
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    warn!("Receiving from the pendant failed, {}", e);
+                    break;
+                }
+            }
+        }
+        events
+    }
+
+    fn accept(&mut self, datagram: &[u8]) -> Result<Option<InputEvent>, Error> {
+        let datagram = std::str::from_utf8(datagram).map_err(|_| Error::Malformed)?;
+        let datagram = datagram.trim_end();
+        let (aad, tag) = datagram.rsplit_once(',').ok_or(Error::Malformed)?;
+        let (sequence, event) = match aad.split_once(',') {
+            Some((ID, rest)) => rest.split_once(',').ok_or(Error::Malformed)?,
+            _ => return Err(Error::Malformed),
+        };
+        let sequence: u64 = sequence.parse().map_err(|_| Error::Malformed)?;
+        let event = parse_event(event)?;
+        let expected = self::tag(&self.key, sequence, aad);
+        let difference = expected
+            .iter()
+            .zip(parse_tag(tag)?)
+            .fold(0, |difference, (a, b)| difference | (a ^ b));
+        if difference != 0 {
+            return Err(Error::Forged);
+        }
+        match self.last_sequence.replace(sequence) {
+            Some(last) if sequence <= last => {
+                self.last_sequence = Some(last);
+                Err(Error::Replayed)
+            }
+            synced => {
+                // Before the event is acted upon
+                if let Some(path) = &self.sequence_path {
+                    if let Err(e) = write_sequence(path, sequence) {
+                        warn!("Can't keep the pendant sequence, {}", e);
+                    }
+                }
+                Ok(synced.and(event))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: Key = [7; aead::KEY_SIZE];
+
+    fn pendant_keeping(sequence_path: Option<PathBuf>) -> Pendant {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.set_nonblocking(true).unwrap();
+        Pendant::with_sequence_path(socket, KEY, sequence_path)
+    }
+
+    fn pendant() -> Pendant {
+        pendant_keeping(None)
+    }
+
+    #[test]
+    fn test_accepts_authentic_datagrams_once() {
+        let mut pendant = pendant();
+        let enter = datagram(&KEY, 10, Some(InputEvent::Enter));
+        // Only syncs the sequence
+        assert_eq!(pendant.accept(enter.as_bytes()), Ok(None));
+        let left = datagram(&KEY, 11, Some(InputEvent::Left(3)));
+        assert!(left.starts_with("PDT,11,LEFT:3,"));
+        assert_eq!(
+            pendant.accept(left.as_bytes()),
+            Ok(Some(InputEvent::Left(3)))
+        );
+        assert_eq!(pendant.accept(left.as_bytes()), Err(Error::Replayed));
+        assert_eq!(pendant.accept(enter.as_bytes()), Err(Error::Replayed));
+        let alive = datagram(&KEY, 12, None);
+        assert_eq!(pendant.accept(alive.as_bytes()), Ok(None));
+        let send = datagram(&KEY, 20, Some(InputEvent::Send));
+        assert_eq!(
+            pendant.accept(format!("{}\n", send).as_bytes()),
+            Ok(Some(InputEvent::Send))
+        );
+    }
+
+    #[test]
+    fn test_no_replay_after_a_restart() {
+        let path = std::env::temp_dir().join(format!("lnc-pendant-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut pendant = pendant_keeping(Some(path.clone()));
+        let alive = datagram(&KEY, 10, None);
+        assert_eq!(pendant.accept(alive.as_bytes()), Ok(None));
+        let send = datagram(&KEY, 11, Some(InputEvent::Send));
+        assert_eq!(pendant.accept(send.as_bytes()), Ok(Some(InputEvent::Send)));
+        // A captured press doesn't even sync a restarted one
+        let mut restarted = pendant_keeping(Some(path.clone()));
+        assert_eq!(restarted.accept(send.as_bytes()), Err(Error::Replayed));
+        let back = datagram(&KEY, 12, Some(InputEvent::Back));
+        assert_eq!(
+            restarted.accept(back.as_bytes()),
+            Ok(Some(InputEvent::Back))
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_forged_and_malformed_datagrams() {
+        let mut pendant = pendant();
+        pendant.last_sequence = Some(1);
+        let forged = datagram(&[8; aead::KEY_SIZE], 2, Some(InputEvent::Send));
+        assert_eq!(pendant.accept(forged.as_bytes()), Err(Error::Forged));
+        // The event is authenticated
+        let altered = datagram(&KEY, 2, Some(InputEvent::Back)).replace("BACK", "SEND");
+        assert_eq!(pendant.accept(altered.as_bytes()), Err(Error::Forged));
+        for malformed in ["", "PDT,2,ENTER", "PDT,x,ENTER,00", "XYZ,2,ENTER,00"] {
+            assert_eq!(pendant.accept(malformed.as_bytes()), Err(Error::Malformed));
+        }
+        assert_eq!(pendant.last_sequence, Some(1));
+    }
+
+    #[test]
+    fn test_poll_over_udp() {
+        let mut pendant = pendant();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = pendant.socket.local_addr().unwrap();
+        for (sequence, event) in [(1, None), (2, Some(InputEvent::Back))] {
+            sender
+                .send_to(datagram(&KEY, sequence, event).as_bytes(), address)
+                .unwrap();
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert_eq!(pendant.poll(), vec![InputEvent::Back]);
+        assert!(pendant.poll().is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+// Picking the serial port of the E32. With several COM devices, on
+// Windows especially, neither the only port nor the device of the
+// novaview can be assumed. The picker lists the ports with what they
+// are, the FTDI serial number among it, and the choice is remembered
+// on the machine for the next start.
+use std::path::{Path, PathBuf};
+
+use serialport::SerialPortType;
+
+const CHOICE_FILE: &str = "port";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortInfo {
+    pub name: String,
+    pub description: String,
+}
+
+pub fn available() -> Vec<PortInfo> {
+    let mut ports: Vec<PortInfo> = serialport::available_ports()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|port| PortInfo {
+            description: describe(&port.port_type),
+            name: port.port_name,
+        })
+        .collect();
+    ports.sort_by(|a, b| a.name.cmp(&b.name));
+    ports
+}
+
+fn describe(port_type: &SerialPortType) -> String {
+    match port_type {
+        SerialPortType::UsbPort(usb) => {
+            let mut parts: Vec<String> = [&usb.manufacturer, &usb.product]
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect();
+            if parts.is_empty() {
+                parts.push(format!("USB {:04x}:{:04x}", usb.vid, usb.pid));
+            }
+            if let Some(serial) = &usb.serial_number {
+                parts.push(format!("serial {}", serial));
+            }
+            parts.join(", ")
+        }
+        SerialPortType::PciPort => "PCI".into(),
+        SerialPortType::BluetoothPort => "Bluetooth".into(),
+        SerialPortType::Unknown => "Unknown".into(),
+    }
+}
+
+// Per user, where the platform keeps settings, also of the pendant
+pub fn settings_path(name: &str) -> Option<PathBuf> {
+    let base = std::env::var_os("APPDATA")
+        .or_else(|| std::env::var_os("XDG_CONFIG_HOME"))
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("launch-control").join(name))
+}
+
+fn read_choice(path: &Path) -> Option<String> {
+    let port = std::fs::read_to_string(path).ok()?.trim().to_string();
+    (!port.is_empty()).then(|| port)
+}
+
+fn write_choice(path: &Path, port: &str) -> anyhow::Result<()> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    std::fs::write(path, port)?;
+    Ok(())
+}
+
+pub fn remembered() -> Option<String> {
+    read_choice(&settings_path(CHOICE_FILE)?)
+}
+
+pub fn remember(port: &str) -> anyhow::Result<()> {
+    let path =
+        settings_path(CHOICE_FILE).ok_or_else(|| anyhow::anyhow!("no settings directory"))?;
+    write_choice(&path, port)
+}
+
+// The remembered port if it's still there, or the only one
+pub fn preselect(ports: &[PortInfo], remembered: Option<&str>) -> Option<String> {
+    if let Some(port) = ports
+        .iter()
+        .find(|port| Some(port.name.as_str()) == remembered)
+    {
+        return Some(port.name.clone());
+    }
+    match ports {
+        [port] => Some(port.name.clone()),
+        _ => None,
+    }
+}
+
+// The port picker page
+#[derive(Debug, Default)]
+pub struct PortPicker {
+    active: bool,
+    ports: Vec<PortInfo>,
+    pub selected: usize,
+    // To be remembered
+    chosen: Option<String>,
+}
+
+impl PortPicker {
+    // On the port in use, if it's listed
+    pub fn start(&mut self, ports: Vec<PortInfo>, current: &str) {
+        self.selected = ports
+            .iter()
+            .position(|port| port.name == current)
+            .unwrap_or(0);
+        self.ports = ports;
+        self.active = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn ports(&self) -> &[PortInfo] {
+        &self.ports
+    }
+
+    pub fn select(&mut self, steps: i64) {
+        if !self.ports.is_empty() {
+            let count = self.ports.len() as i64;
+            self.selected = (self.selected as i64 + steps).rem_euclid(count) as usize;
+        }
+    }
+
+    // Leaves the page with the selected port
+    pub fn choose(&mut self) -> Option<String> {
+        let port = self.ports.get(self.selected)?.name.clone();
+        self.chosen = Some(port.clone());
+        self.active = false;
+        Some(port)
+    }
+
+    pub fn take_chosen(&mut self) -> Option<String> {
+        self.chosen.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serialport::UsbPortInfo;
+
+    fn port(name: &str) -> PortInfo {
+        PortInfo {
+            name: name.into(),
+            description: "".into(),
+        }
+    }
+
+    #[test]
+    fn test_ports_are_described_and_picked() {
+        let ftdi = SerialPortType::UsbPort(UsbPortInfo {
+            vid: 0x0403,
+            pid: 0x6001,
+            serial_number: Some("A50285BI".into()),
+            manufacturer: Some("FTDI".into()),
+            product: Some("FT232R USB UART".into()),
+        });
+        assert_eq!(describe(&ftdi), "FTDI, FT232R USB UART, serial A50285BI");
+
+        let ports = vec![port("COM3"), port("COM7")];
+        assert_eq!(preselect(&ports, Some("COM7")), Some("COM7".into()));
+        assert_eq!(preselect(&ports, Some("COM9")), None);
+        assert_eq!(preselect(&ports[..1], None), Some("COM3".into()));
+
+        let mut picker = PortPicker::default();
+        picker.start(ports, "COM7");
+        assert_eq!(picker.selected, 1);
+        picker.select(1);
+        assert_eq!(picker.choose(), Some("COM3".into()));
+        assert!(!picker.is_active());
+        assert_eq!(picker.take_chosen(), Some("COM3".into()));
+        assert_eq!(picker.take_chosen(), None);
+
+        let path = std::env::temp_dir()
+            .join(format!("lnc-ports-{}", std::process::id()))
+            .join(CHOICE_FILE);
+        assert_eq!(read_choice(&path), None);
+        write_choice(&path, "COM7").unwrap();
+        assert_eq!(read_choice(&path), Some("COM7".into()));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
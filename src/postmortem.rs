@@ -0,0 +1,194 @@
+// What the dev team needs to reproduce a failure from a field report,
+// bundled when the connection fails or on demand: the last raw
+// sentences, the tail of the journal, the configuration with its
+// secrets redacted, the link statistics and a backtrace. We have no
+// archive format at hand, so the bundle is a directory with a
+// timestamped name, to be zipped with whatever the laptop offers.
+// Resolving the backtrace and the writes take a while, so they are
+// done by a worker.
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use chrono::{DateTime, Utc};
+use crossbeam_channel::{bounded, Receiver, TryRecvError};
+use log::error;
+
+use crate::args::ProgramArgs;
+
+pub const BUNDLE_DIRECTORY: &str = "postmortem";
+pub const JOURNAL_TAIL: usize = 200;
+const SENTENCE_HISTORY: usize = 200;
+const BUNDLE_TIME_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+const REDACTED: &str = "<redacted>";
+
+pub struct Bundle {
+    pub sentences: Vec<String>,
+    pub journal: Vec<String>,
+    pub config: String,
+    pub link: Vec<String>,
+    // Captured where the bundle was asked for, resolved by the worker
+    pub backtrace: Backtrace,
+}
+
+#[derive(Default)]
+pub struct PostMortem {
+    // Oldest first, longer than the failure page shows
+    sentences: VecDeque<Vec<u8>>,
+    config: String,
+    writing: Option<Receiver<Result<PathBuf, String>>>,
+    // Where the last bundle went, or why it failed
+    pub written: Option<Result<PathBuf, String>>,
+}
+
+// Of the arguments after the config file, the keys only say they're set
+pub fn redacted_config(args: &ProgramArgs) -> String {
+    let mut args = args.clone();
+    for key in [
+        &mut args.relay_secret_key,
+        &mut args.relay_server_key,
+        &mut args.relay_client_secret_key,
+        &mut args.pendant_key,
+    ] {
+        if key.is_some() {
+            *key = Some(REDACTED.into());
+        }
+    }
+    for key in args.link_keys.values_mut() {
+        *key = REDACTED.into();
+    }
+    format!("{:#?}", args)
+}
+
+// Into a directory named after the time, below the target
+pub fn write(bundle: &Bundle, target: &Path, at: DateTime<Utc>) -> anyhow::Result<PathBuf> {
+    let directory = target.join(format!(
+        "{}-{}",
+        BUNDLE_DIRECTORY,
+        at.format(BUNDLE_TIME_FORMAT)
+    ));
+    fs::create_dir_all(&directory)?;
+    let lines = |lines: &[String]| lines.iter().map(|line| format!("{}\n", line)).collect();
+    let files: [(&str, String); 5] = [
+        ("sentences.txt", lines(&bundle.sentences)),
+        ("journal.txt", lines(&bundle.journal)),
+        ("config.txt", bundle.config.clone()),
+        ("link.txt", lines(&bundle.link)),
+        ("backtrace.txt", bundle.backtrace.to_string()),
+    ];
+    for (name, contents) in files {
+        fs::write(directory.join(name), contents)?;
+    }
+    Ok(directory)
+}
+
+impl PostMortem {
+    pub fn record_sentence(&mut self, sentence: &[u8]) {
+        if self.sentences.len() == SENTENCE_HISTORY {
+            self.sentences.pop_front();
+        }
+        self.sentences.push_back(sentence.into());
+    }
+
+    pub fn sentences(&self) -> Vec<String> {
+        self.sentences
+            .iter()
+            .map(|sentence| String::from_utf8_lossy(sentence).trim_end().to_string())
+            .collect()
+    }
+
+    pub fn set_config(&mut self, args: &ProgramArgs) {
+        self.config = redacted_config(args);
+    }
+
+    pub fn config(&self) -> &str {
+        &self.config
+    }
+
+    pub fn is_writing(&self) -> bool {
+        self.writing.is_some()
+    }
+
+    // False while the last one is still being written
+    pub fn start(&mut self, bundle: Bundle, target: PathBuf, at: DateTime<Utc>) -> bool {
+        if self.is_writing() {
+            return false;
+        }
+        let (sender, receiver) = bounded(1);
+        thread::spawn(move || {
+            let _ = sender.send(write(&bundle, &target, at).map_err(|err| err.to_string()));
+        });
+        self.writing = Some(receiver);
+        true
+    }
+
+    // The outcome once the worker is done, it's kept for the failure page
+    pub fn poll(&mut self) -> Option<Result<PathBuf, String>> {
+        let outcome = match self.writing.as_ref()?.try_recv() {
+            Ok(outcome) => outcome,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => {
+                error!("Diagnostic bundle worker died");
+                Err("worker died".into())
+            }
+        };
+        self.writing = None;
+        self.written = Some(outcome.clone());
+        Some(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rqprotocol::Node;
+
+    #[test]
+    fn test_bundles_hold_the_history_without_secrets() {
+        let mut args = ProgramArgs::default();
+        args.pendant_key = Some("pendant-secret".into());
+        args.link_keys
+            .insert(Node::RedQueen(b'A'), "link-secret".into());
+        let mut postmortem = PostMortem::default();
+        postmortem.set_config(&args);
+        assert!(!postmortem.config().contains("pendant-secret"));
+        assert!(!postmortem.config().contains("link-secret"));
+        assert!(postmortem.config().contains(REDACTED));
+        for _ in 0..SENTENCE_HISTORY {
+            postmortem.record_sentence(b"$RQAPNG,001*1F\r\n");
+        }
+        postmortem.record_sentence(b"$RQAACK,001,PNG*03\r\n");
+        let sentences = postmortem.sentences();
+        assert_eq!(sentences.len(), SENTENCE_HISTORY);
+        assert_eq!(sentences.last().unwrap(), "$RQAACK,001,PNG*03");
+
+        let target = std::env::temp_dir().join(format!("lnc-postmortem-{}", std::process::id()));
+        let bundle = Bundle {
+            sentences,
+            journal: vec!["2026-10-16T10:00:00+00:00 Session started".into()],
+            config: postmortem.config().into(),
+            link: vec!["Malformed frames: 0".into()],
+            backtrace: Backtrace::disabled(),
+        };
+        let at = DateTime::parse_from_rfc3339("2026-10-16T10:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert!(postmortem.start(bundle, target.clone(), at));
+        assert!(postmortem.is_writing());
+        let directory = loop {
+            match postmortem.poll() {
+                Some(outcome) => break outcome.unwrap(),
+                None => thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        };
+        assert!(!postmortem.is_writing());
+        assert_eq!(postmortem.written, Some(Ok(directory.clone())));
+        assert_eq!(directory, target.join("postmortem-2026-10-16_10-00-00"));
+        let journal = fs::read_to_string(directory.join("journal.txt")).unwrap();
+        assert_eq!(journal, "2026-10-16T10:00:00+00:00 Session started\n");
+        assert!(directory.join("backtrace.txt").exists());
+        fs::remove_dir_all(&target).unwrap();
+    }
+}
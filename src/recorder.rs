@@ -1,67 +1,479 @@
 use std::{
-    fs::File,
-    io::Write,
+    collections::VecDeque,
+    fmt::Display,
+    fs::{File, OpenOptions},
+    io::{BufWriter, ErrorKind, Write},
+    net::{TcpStream, ToSocketAddrs},
     path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
     thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime},
 };
 
+use anyhow::anyhow;
 use chrono::{DateTime, Utc};
-use crossbeam_channel::{unbounded, Receiver, Sender};
-use log::info;
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender, TryRecvError};
+use log::{error, info};
+use ringbuffer::{AllocRingBuffer, RingBuffer};
+use serde::Deserialize;
+
+use crate::capture::Capture;
+use crate::run::RunId;
+
+// Recordings are named after their start and run, e.g.
+// 2024-05-01_13-37-swift-falcon-rqa.log
+pub const FILE_TIME_FORMAT: &str = "%Y-%m-%d_%H-%M";
+const FLUSH_SIZE: usize = 1024;
+// A failed sink is reopened at most this often, so
+// a replugged USB stick or restarted server is picked up.
+const REOPEN_INTERVAL: Duration = Duration::from_secs(5);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+// Of a network sink, sent after reconnecting so a short
+// Wi-Fi drop doesn't leave a gap on the logging laptop.
+const TCP_BACKLOG: usize = 256 * 1024;
+
+// With when the byte came in or the data went out, the
+// worker gets to them later
 enum Commands {
-    Store(u8),
+    Store(u8, SystemTime),
+    // Only of interest for the capture
+    Sent(Vec<u8>, SystemTime),
+    Flush,
     Quit,
 }
 
+// Where a copy of the byte stream goes
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Sink {
+    File(PathBuf),
+    // host:port
+    Tcp(String),
+    Memory(MemorySink),
+}
+
+// How a sink is written. A sink that failed is closed
+// and opened again later by the recorder.
+pub trait RecorderSink: Send {
+    fn open(&mut self) -> anyhow::Result<()>;
+    // Only called when open, failing with how many bytes got out
+    fn write(&mut self, buffer: &[u8]) -> Result<(), (usize, std::io::Error)>;
+    fn close(&mut self);
+    // Bytes kept while down, written after opening again
+    fn backlog(&self) -> usize {
+        0
+    }
+    // Still opening in the background, asked again on every write
+    fn opening(&self) -> bool {
+        false
+    }
+}
+
+struct FileSink {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+// Connects on its own thread, so the recorder keeps writing
+// the other sinks while the server doesn't answer
+struct TcpSink {
+    address: String,
+    stream: Option<TcpStream>,
+    connecting: Option<Receiver<anyhow::Result<TcpStream>>>,
+}
+
+// The last bytes recorded, shared with whoever wants to look
+#[derive(Clone)]
+pub struct MemorySink {
+    ring: Arc<Mutex<AllocRingBuffer<u8>>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SinkHealth {
+    Healthy,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SinkStatus {
+    pub sink: Sink,
+    pub health: SinkHealth,
+    pub written: usize,
+}
+
+// Shared with the recording thread, read by the UI
+#[derive(Debug, Clone, Default)]
+pub struct RecorderStatus {
+    sinks: Arc<Mutex<Vec<SinkStatus>>>,
+}
+
 pub struct Recorder {
     worker: Option<JoinHandle<()>>,
     command_sender: Sender<Commands>,
     pub path: Option<PathBuf>,
+    pub status: RecorderStatus,
+}
+
+struct SinkWriter {
+    sink: Sink,
+    output: Box<dyn RecorderSink>,
+    open: bool,
+    last_open: Option<Instant>,
+    failure: Option<String>,
+    backlog: VecDeque<u8>,
+}
+
+impl FromStr for Sink {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(capacity) = s.strip_prefix("memory://") {
+            return Ok(Sink::Memory(MemorySink::new(capacity.parse()?)));
+        }
+        match s.strip_prefix("tcp://") {
+            Some("") => Err(anyhow!("tcp sink needs host:port")),
+            Some(address) => Ok(Sink::Tcp(address.into())),
+            None => Ok(Sink::File(s.into())),
+        }
+    }
+}
+
+impl TryFrom<String> for Sink {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl Display for Sink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sink::File(path) => write!(f, "{}", path.display()),
+            Sink::Tcp(address) => write!(f, "tcp://{}", address),
+            Sink::Memory(memory) => write!(f, "memory://{}", memory.capacity()),
+        }
+    }
+}
+
+impl Sink {
+    fn recorder_sink(&self) -> Box<dyn RecorderSink> {
+        match self {
+            Sink::File(path) => Box::new(FileSink {
+                path: path.clone(),
+                file: None,
+            }),
+            Sink::Tcp(address) => Box::new(TcpSink {
+                address: address.clone(),
+                stream: None,
+                connecting: None,
+            }),
+            Sink::Memory(memory) => Box::new(memory.clone()),
+        }
+    }
+}
+
+// Like write_all, but tells how far it got
+fn write_all(output: &mut impl Write, buffer: &[u8]) -> Result<(), (usize, std::io::Error)> {
+    let mut written = 0;
+    while written < buffer.len() {
+        match output.write(&buffer[written..]) {
+            Ok(0) => return Err((written, ErrorKind::WriteZero.into())),
+            Ok(count) => written += count,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err((written, e)),
+        }
+    }
+    output.flush().map_err(|e| (written, e))
+}
+
+impl RecorderSink for FileSink {
+    fn open(&mut self) -> anyhow::Result<()> {
+        self.file = Some(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?,
+        );
+        Ok(())
+    }
+
+    fn write(&mut self, buffer: &[u8]) -> Result<(), (usize, std::io::Error)> {
+        write_all(self.file.as_mut().unwrap(), buffer)
+    }
+
+    fn close(&mut self) {
+        self.file = None;
+    }
+}
+
+fn connect(address: &str) -> anyhow::Result<TcpStream> {
+    let resolved = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or(anyhow!("can't resolve {}", address))?;
+    let stream = TcpStream::connect_timeout(&resolved, CONNECT_TIMEOUT)?;
+    stream.set_write_timeout(Some(CONNECT_TIMEOUT))?;
+    Ok(stream)
+}
+
+impl RecorderSink for TcpSink {
+    fn open(&mut self) -> anyhow::Result<()> {
+        let connecting = self.connecting.get_or_insert_with(|| {
+            let (sender, receiver) = bounded(1);
+            let address = self.address.clone();
+            thread::spawn(move || sender.send(connect(&address)));
+            receiver
+        });
+        let result = match connecting.try_recv() {
+            Err(TryRecvError::Empty) => return Err(anyhow!("connecting to {}", self.address)),
+            Err(TryRecvError::Disconnected) => Err(anyhow!("can't connect to {}", self.address)),
+            Ok(result) => result,
+        };
+        self.connecting = None;
+        self.stream = Some(result?);
+        Ok(())
+    }
+
+    fn write(&mut self, buffer: &[u8]) -> Result<(), (usize, std::io::Error)> {
+        write_all(self.stream.as_mut().unwrap(), buffer)
+    }
+
+    fn close(&mut self) {
+        self.stream = None;
+    }
+
+    fn backlog(&self) -> usize {
+        TCP_BACKLOG
+    }
+
+    fn opening(&self) -> bool {
+        self.connecting.is_some()
+    }
+}
+
+impl MemorySink {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            ring: Arc::new(Mutex::new(AllocRingBuffer::new(capacity.max(1)))),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.ring.lock().unwrap().capacity()
+    }
+
+    pub fn contents(&self) -> Vec<u8> {
+        self.ring.lock().unwrap().to_vec()
+    }
+}
+
+impl RecorderSink for MemorySink {
+    fn open(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn write(&mut self, buffer: &[u8]) -> Result<(), (usize, std::io::Error)> {
+        self.ring.lock().unwrap().extend(buffer.iter().copied());
+        Ok(())
+    }
+
+    fn close(&mut self) {}
+}
+
+impl std::fmt::Debug for MemorySink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MemorySink({})", self.capacity())
+    }
+}
+
+// The same ring
+impl PartialEq for MemorySink {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.ring, &other.ring)
+    }
+}
+
+impl RecorderStatus {
+    pub fn sinks(&self) -> Vec<SinkStatus> {
+        self.sinks.lock().unwrap().clone()
+    }
+
+    fn update(&self, index: usize, health: SinkHealth, written: usize) {
+        let mut sinks = self.sinks.lock().unwrap();
+        sinks[index].health = health;
+        sinks[index].written += written;
+    }
+}
+
+impl SinkWriter {
+    fn new(sink: Sink) -> Self {
+        Self {
+            output: sink.recorder_sink(),
+            sink,
+            open: false,
+            last_open: None,
+            failure: None,
+            backlog: VecDeque::new(),
+        }
+    }
+
+    fn open(&mut self) -> anyhow::Result<()> {
+        self.last_open = Some(Instant::now());
+        self.output.open()?;
+        self.open = true;
+        Ok(())
+    }
+
+    // Only failing once a sink opening in the background gives up
+    fn try_open(&mut self) -> Result<(), String> {
+        match self.open() {
+            Ok(()) => {
+                info!("Recording to {}", self.sink);
+                self.failure = None;
+                Ok(())
+            }
+            Err(e) if self.output.opening() => {
+                Err(self.failure.get_or_insert(e.to_string()).clone())
+            }
+            Err(e) => self.fail(e.to_string()).map(|_| ()),
+        }
+    }
+
+    fn fail(&mut self, reason: String) -> Result<usize, String> {
+        error!("Recording to {} failed, {}", self.sink, reason);
+        self.output.close();
+        self.open = false;
+        self.failure = Some(reason.clone());
+        Err(reason)
+    }
+
+    // Oldest first out when full
+    fn keep(&mut self, buffer: &[u8]) {
+        self.backlog.extend(buffer);
+        let excess = self.backlog.len().saturating_sub(self.output.backlog());
+        self.backlog.drain(..excess);
+    }
+
+    // Data arriving while the sink is down is lost for it,
+    // beyond its backlog
+    fn write(&mut self, buffer: &[u8]) -> Result<usize, String> {
+        if !self.open {
+            let due = self.output.opening()
+                || self
+                    .last_open
+                    .map_or(true, |last_open| last_open.elapsed() > REOPEN_INTERVAL);
+            if !due {
+                self.keep(buffer);
+                return Err(self.failure.clone().unwrap_or_default());
+            }
+            if let Err(reason) = self.try_open() {
+                self.keep(buffer);
+                return Err(reason);
+            }
+        }
+        let mut pending: Vec<u8> = self.backlog.drain(..).collect();
+        pending.extend_from_slice(buffer);
+        match self.output.write(&pending) {
+            Ok(()) => Ok(pending.len()),
+            // What got out isn't sent again
+            Err((written, e)) => {
+                self.keep(&pending[written..]);
+                self.fail(e.to_string())
+            }
+        }
+    }
 }
 
 impl Recorder {
-    pub fn new(path: Option<PathBuf>) -> Self {
+    pub fn new(sinks: Vec<Sink>) -> Self {
+        Self::new_with_capture(sinks, None)
+    }
+
+    // The capture gets both directions, see capture.rs
+    pub fn new_with_capture(sinks: Vec<Sink>, capture: Option<PathBuf>) -> Self {
         let (command_sender, command_receiver) = unbounded::<Commands>();
-        let path_copy = path.clone();
+        let path = sinks.iter().find_map(|sink| match sink {
+            Sink::File(path) => Some(path.clone()),
+            _ => None,
+        });
+        let status = RecorderStatus {
+            sinks: Arc::new(Mutex::new(
+                sinks
+                    .iter()
+                    .map(|sink| SinkStatus {
+                        sink: sink.clone(),
+                        health: SinkHealth::Healthy,
+                        written: 0,
+                    })
+                    .collect(),
+            )),
+        };
+        let worker_status = status.clone();
         let handle = thread::spawn(move || {
-            if let Some(path) = path_copy {
-                work_for_real(path, command_receiver);
-            } else {
-                loop {
-                    match command_receiver.recv().unwrap() {
-                        Commands::Quit => {
-                            break;
-                        }
-                        _ => {}
-                    }
+            let capture = capture.and_then(|path| match Capture::create(&path) {
+                Ok(capture) => {
+                    info!("Capturing serial traffic to {}", path.display());
+                    Some(capture)
                 }
-            }
+                Err(e) => {
+                    error!("Can't capture to {}, {}", path.display(), e);
+                    None
+                }
+            });
+            let writers = sinks.into_iter().map(SinkWriter::new).collect();
+            work(writers, capture, command_receiver, worker_status);
         });
         Recorder {
             worker: Some(handle),
             command_sender,
             path,
+            status,
         }
     }
 
-    pub fn new_with_default_file() -> Self {
+    // The mirror is either a directory that gets a file of the
+    // same name, or a socket that gets the plain stream.
+    pub fn new_with_default_file(
+        run: &RunId,
+        mirror: Option<&Sink>,
+        capture: Option<PathBuf>,
+    ) -> Self {
         let current_utc: DateTime<Utc> = Utc::now();
-        let rfc_format: String = current_utc.format("%Y-%m-%d_%H-%M").to_string();
-        let mut path = PathBuf::new();
-        path.push(format!("{}-rqa.log", rfc_format));
-        info!("Recording data to {:?}", path);
-        Self::new(Some(path))
+        let rfc_format: String = current_utc.format(FILE_TIME_FORMAT).to_string();
+        let name = format!("{}-{}-rqa.log", rfc_format, run.slug());
+        let mut sinks = vec![Sink::File(name.clone().into())];
+        match mirror {
+            Some(Sink::File(directory)) => sinks.push(Sink::File(directory.join(&name))),
+            Some(sink) => sinks.push(sink.clone()),
+            None => {}
+        }
+        info!("Recording data to {:?}", sinks);
+        Self::new_with_capture(sinks, capture)
     }
 
-    pub fn store(&mut self, c: u8) {
-        self.command_sender.send(Commands::Store(c)).unwrap();
+    pub fn store(&mut self, c: u8, at: SystemTime) {
+        self.command_sender.send(Commands::Store(c, at)).unwrap();
     }
 
     pub fn write_buffer(&mut self, buffer: &Vec<u8>) {
+        let at = SystemTime::now();
         for c in buffer {
-            self.store(*c);
+            self.store(*c, at);
         }
     }
+
+    pub fn store_sent(&mut self, buffer: &[u8], at: SystemTime) {
+        self.command_sender
+            .send(Commands::Sent(buffer.to_vec(), at))
+            .unwrap();
+    }
+
+    // Of what's below the flush size, and the capture
+    pub fn flush(&mut self) {
+        self.command_sender.send(Commands::Flush).unwrap();
+    }
 }
 
 impl Drop for Recorder {
@@ -71,25 +483,216 @@ impl Drop for Recorder {
     }
 }
 
-fn work_for_real(path: PathBuf, receiver: Receiver<Commands>) {
-    let mut output_file = File::create(path).unwrap();
+// Each sink fails on its own, the others keep recording
+fn flush(writers: &mut Vec<SinkWriter>, buffer: &mut Vec<u8>, status: &RecorderStatus) {
+    for (index, writer) in writers.iter_mut().enumerate() {
+        match writer.write(buffer) {
+            Ok(written) => status.update(index, SinkHealth::Healthy, written),
+            Err(reason) => status.update(index, SinkHealth::Failed(reason), 0),
+        }
+    }
+    buffer.clear();
+}
+
+// A failing capture is given up, unlike the sinks
+fn with_capture(
+    capture: &mut Option<Capture<BufWriter<File>>>,
+    write: impl FnOnce(&mut Capture<BufWriter<File>>) -> std::io::Result<()>,
+) {
+    if let Some(c) = capture {
+        if let Err(e) = write(c) {
+            error!("Capturing serial traffic failed, {}", e);
+            *capture = None;
+        }
+    }
+}
+
+fn work(
+    mut writers: Vec<SinkWriter>,
+    mut capture: Option<Capture<BufWriter<File>>>,
+    receiver: Receiver<Commands>,
+    status: RecorderStatus,
+) {
+    for (index, writer) in writers.iter_mut().enumerate() {
+        if let Err(reason) = writer.try_open() {
+            status.update(index, SinkHealth::Failed(reason), 0);
+        }
+    }
     let mut buffer = vec![];
     loop {
         match receiver.recv().unwrap() {
-            Commands::Store(c) => {
+            Commands::Store(c, at) => {
+                with_capture(&mut capture, |capture| capture.received(c, at));
                 buffer.push(c);
-                if buffer.len() > 1024 {
-                    output_file.write_all(&buffer).unwrap();
-                    buffer.clear();
+                if buffer.len() > FLUSH_SIZE {
+                    flush(&mut writers, &mut buffer, &status);
                 }
             }
-            Commands::Quit => {
-                if buffer.len() > 1024 {
-                    output_file.write_all(&buffer).unwrap();
-                    buffer.clear();
+            Commands::Sent(data, at) => {
+                with_capture(&mut capture, |capture| capture.sent(&data, at));
+            }
+            command @ (Commands::Flush | Commands::Quit) => {
+                if !buffer.is_empty() {
+                    flush(&mut writers, &mut buffer, &status);
+                }
+                with_capture(&mut capture, |capture| capture.flush());
+                if let Commands::Quit = command {
+                    break;
                 }
-                break;
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sinks_fail_independently() {
+        let path = std::env::temp_dir().join(format!("lnc-recording-{}.log", std::process::id()));
+        let missing = std::env::temp_dir().join("lnc-no-such-dir").join("rqa.log");
+        let mut recorder = Recorder::new(vec![Sink::File(missing), Sink::File(path.clone())]);
+        let status = recorder.status.clone();
+        recorder.write_buffer(&b"$RQBACK,001,LNC*00\r\n".to_vec());
+        drop(recorder);
+        let sinks = status.sinks();
+        assert!(matches!(sinks[0].health, SinkHealth::Failed(_)));
+        assert_eq!(sinks[1].health, SinkHealth::Healthy);
+        assert_eq!(sinks[1].written, 20);
+        assert_eq!(std::fs::read(&path).unwrap(), b"$RQBACK,001,LNC*00\r\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    // Up or down as the test wants, keeping a backlog like TCP.
+    // Room is how many bytes get out before the connection breaks.
+    struct FlakySink {
+        up: Arc<Mutex<bool>>,
+        room: Arc<Mutex<usize>>,
+        memory: MemorySink,
+    }
+
+    impl RecorderSink for FlakySink {
+        fn open(&mut self) -> anyhow::Result<()> {
+            match *self.up.lock().unwrap() {
+                true => Ok(()),
+                false => Err(anyhow!("unreachable")),
+            }
+        }
+
+        fn write(&mut self, buffer: &[u8]) -> Result<(), (usize, std::io::Error)> {
+            if !*self.up.lock().unwrap() {
+                return Err((0, ErrorKind::BrokenPipe.into()));
+            }
+            let mut room = self.room.lock().unwrap();
+            let written = buffer.len().min(*room);
+            *room -= written;
+            self.memory.write(&buffer[..written])?;
+            match written == buffer.len() {
+                true => Ok(()),
+                false => Err((written, ErrorKind::BrokenPipe.into())),
+            }
+        }
+
+        fn close(&mut self) {}
+
+        fn backlog(&self) -> usize {
+            8
+        }
+    }
+
+    #[test]
+    fn test_backlog_is_sent_after_reconnecting() {
+        let memory = MemorySink::new(64);
+        let up = Arc::new(Mutex::new(true));
+        let mut writer = SinkWriter::new(Sink::Memory(memory.clone()));
+        writer.output = Box::new(FlakySink {
+            up: up.clone(),
+            room: Arc::new(Mutex::new(usize::MAX)),
+            memory: memory.clone(),
+        });
+        writer.open().unwrap();
+        assert_eq!(writer.write(b"abc"), Ok(3));
+        *up.lock().unwrap() = false;
+        assert!(writer.write(b"defgh").is_err());
+        assert!(writer.write(b"ijk").is_err());
+        *up.lock().unwrap() = true;
+        // Not before the reopen interval
+        assert!(writer.write(b"lm").is_err());
+        writer.last_open = None;
+        assert_eq!(writer.write(b"no"), Ok(10));
+        // Only the last 8 while down were kept
+        assert_eq!(memory.contents(), b"abcfghijklmno");
+    }
+
+    #[test]
+    fn test_partial_write_keeps_only_the_rest() {
+        let memory = MemorySink::new(64);
+        let room = Arc::new(Mutex::new(2));
+        let mut writer = SinkWriter::new(Sink::Memory(memory.clone()));
+        writer.output = Box::new(FlakySink {
+            up: Arc::new(Mutex::new(true)),
+            room: room.clone(),
+            memory: memory.clone(),
+        });
+        writer.open().unwrap();
+        assert!(writer.write(b"abcde").is_err());
+        *room.lock().unwrap() = usize::MAX;
+        writer.last_open = None;
+        assert_eq!(writer.write(b"f"), Ok(4));
+        assert_eq!(memory.contents(), b"abcdef");
+    }
+
+    #[test]
+    fn test_tcp_sink_connects_in_the_background() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let mut writer = SinkWriter::new(Sink::Tcp(address.clone()));
+        // Unless the thread is quicker than the write
+        if writer.write(b"abc").is_err() {
+            assert!(writer.output.opening());
+            assert_eq!(writer.failure, Some(format!("connecting to {}", address)));
+        }
+        let (mut server, _) = listener.accept().unwrap();
+        // Picked up on a later write, without waiting for the reopen interval
+        while writer.write(b"").is_err() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(writer.write(b"def"), Ok(3));
+        let mut received = [0; 6];
+        std::io::Read::read_exact(&mut server, &mut received).unwrap();
+        assert_eq!(&received, b"abcdef");
+    }
+
+    #[test]
+    fn test_memory_sink_keeps_the_last_bytes() {
+        let sink: Sink = "memory://8".parse().unwrap();
+        let mut recorder = Recorder::new(vec![sink.clone()]);
+        let status = recorder.status.clone();
+        recorder.write_buffer(&b"$RQBACK,001,LNC*00\r\n".to_vec());
+        drop(recorder);
+        assert_eq!(status.sinks()[0].written, 20);
+        match sink {
+            Sink::Memory(memory) => assert_eq!(memory.contents(), b"LNC*00\r\n"),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_sink_from_str() {
+        assert_eq!(
+            "tcp://novaview.local:2525".parse::<Sink>().unwrap(),
+            Sink::Tcp("novaview.local:2525".into())
+        );
+        assert_eq!(
+            "/media/usb".parse::<Sink>().unwrap(),
+            Sink::File("/media/usb".into())
+        );
+        assert!("tcp://".parse::<Sink>().is_err());
+        assert_eq!(
+            "memory://4096".parse::<Sink>().unwrap().to_string(),
+            "memory://4096"
+        );
+        assert!("memory://lots".parse::<Sink>().is_err());
+    }
+}
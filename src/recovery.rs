@@ -0,0 +1,207 @@
+// After the flight, finding the rocket is all that's left. This
+// keeps the last known telemetry of every node around, independent
+// of the retention of the telemetry store, and how long ago it was
+// heard of. The telemetry receiver doesn't need the E32 link, so
+// this keeps working when the command link is long gone. While the
+// page is active, packets ping the operator now and then, and
+// loudly when a node is heard again after a silence.
+//
+// There is no GPS packet yet, the altitude is derived from the
+// barometric pressure relative to the first reading.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{
+    rqprotocol::Node,
+    telemetry::parser::rq2::{FlightPhase, TelemetryData},
+};
+
+// Heard of again after this, pinged loudly
+pub const SILENCE: Duration = Duration::from_secs(10);
+// Between the soft pings of an ongoing reception
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+// Of node time, shorter intervals make the descent rate too noisy
+const RATE_INTERVAL: u32 = 1_000_000;
+
+#[derive(Debug, Clone, Default)]
+pub struct Track {
+    // Into the session
+    pub last_packet: Duration,
+    // Above the first reading, in m
+    pub altitude: Option<f32>,
+    // Positive when descending, in m/s
+    pub descent_rate: Option<f32>,
+    pub phase: Option<FlightPhase>,
+    pub battery_voltage: Option<f32>,
+    reference: Option<f32>,
+    // Node timestamp in µs and altitude
+    sample: Option<(u32, f32)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ping {
+    pub node: Node,
+    // After a silence, to be loud
+    pub resumed: bool,
+}
+
+#[derive(Default)]
+pub struct Recovery {
+    active: bool,
+    tracks: HashMap<Node, Track>,
+    last_ping: Option<Duration>,
+}
+
+// International standard atmosphere, good enough for the
+// difference to the launch site
+fn altitude(pressure: f32, reference: f32) -> f32 {
+    44330.0 * (1.0 - (pressure / reference).powf(1.0 / 5.255))
+}
+
+impl Track {
+    fn update(&mut self, timestamp: u32, data: &TelemetryData) {
+        match data {
+            TelemetryData::State(state) => {
+                self.phase = Some(state.phase.clone());
+                self.battery_voltage = Some(state.battery_voltage);
+            }
+            TelemetryData::IMU(imu) if imu.pressure > 0.0 => {
+                let reference = *self.reference.get_or_insert(imu.pressure);
+                let height = altitude(imu.pressure, reference);
+                self.altitude = Some(height);
+                match self.sample {
+                    Some((then, previous)) => {
+                        // The timestamp wraps after a bit more than an hour
+                        let interval = timestamp.wrapping_sub(then);
+                        if interval >= RATE_INTERVAL {
+                            self.descent_rate =
+                                Some((previous - height) / (interval as f32 / 1_000_000.0));
+                            self.sample = Some((timestamp, height));
+                        }
+                    }
+                    None => self.sample = Some((timestamp, height)),
+                }
+            }
+            TelemetryData::IMU(_) | TelemetryData::Config(_) => {}
+        }
+    }
+}
+
+impl Recovery {
+    pub fn start(&mut self) {
+        self.active = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    // Returns if the operator should be pinged for the packet
+    pub fn record(
+        &mut self,
+        node: Node,
+        elapsed: Duration,
+        timestamp: u32,
+        data: &TelemetryData,
+    ) -> Option<Ping> {
+        let heard_of = self.tracks.contains_key(&node);
+        let track = self.tracks.entry(node).or_default();
+        let resumed = heard_of && elapsed.saturating_sub(track.last_packet) >= SILENCE;
+        track.last_packet = elapsed;
+        track.update(timestamp, data);
+        if !self.active {
+            return None;
+        }
+        let due = self
+            .last_ping
+            .map_or(true, |last| elapsed.saturating_sub(last) >= PING_INTERVAL);
+        (resumed || due).then(|| {
+            self.last_ping = Some(elapsed);
+            Ping { node, resumed }
+        })
+    }
+
+    // Ordered by node name
+    pub fn tracks(&self) -> Vec<(Node, &Track)> {
+        let mut tracks: Vec<_> = self.tracks.iter().map(|(node, t)| (*node, t)).collect();
+        tracks.sort_by_key(|(node, _)| node.to_string());
+        tracks
+    }
+
+    pub fn silence(&self, track: &Track, elapsed: Duration) -> Duration {
+        elapsed.saturating_sub(track.last_packet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::parser::rq2::{IMUPacket, IMUReading, MagReading};
+
+    fn imu(pressure: f32) -> TelemetryData {
+        TelemetryData::IMU(IMUPacket {
+            imu: IMUReading {
+                acc_x: 0.0,
+                acc_y: 0.0,
+                acc_z: 9.81,
+                gyr_x: 0.0,
+                gyr_y: 0.0,
+                gyr_z: 0.0,
+            },
+            mag: MagReading {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            pressure,
+            temperature: 20.0,
+        })
+    }
+
+    #[test]
+    fn test_descent_rate_from_pressure() {
+        let node = Node::RedQueen(b'B');
+        let mut recovery = Recovery::default();
+        recovery.record(node, Duration::from_secs(1), 0, &imu(101325.0));
+        // About 100m up, then the node timestamp wraps on the way down
+        let up = 100121.0;
+        recovery.record(node, Duration::from_secs(2), u32::MAX - 999_999, &imu(up));
+        recovery.record(node, Duration::from_secs(3), 500_000, &imu(up + 120.0));
+        let track = &recovery.tracks()[0].1;
+        assert!((track.altitude.unwrap() - 90.0).abs() < 1.0);
+        // Over 1.5s, from about 100m
+        assert!((track.descent_rate.unwrap() - 6.7).abs() < 0.5);
+        assert_eq!(
+            recovery.silence(track, Duration::from_secs(5)),
+            Duration::from_secs(2)
+        );
+    }
+
+    #[test]
+    fn test_louder_ping_after_silence() {
+        let node = Node::RedQueen(b'A');
+        let mut recovery = Recovery::default();
+        let at = Duration::from_secs;
+        assert_eq!(recovery.record(node, at(1), 0, &imu(101325.0)), None);
+        recovery.start();
+        assert_eq!(
+            recovery.record(node, at(2), 0, &imu(101325.0)),
+            Some(Ping {
+                node,
+                resumed: false
+            })
+        );
+        assert_eq!(recovery.record(node, at(3), 0, &imu(101325.0)), None);
+        assert_eq!(
+            recovery.record(node, at(13), 0, &imu(101325.0)),
+            Some(Ping {
+                node,
+                resumed: true
+            })
+        );
+    }
+}
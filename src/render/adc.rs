@@ -0,0 +1,89 @@
+use egui::{RichText, Ui};
+
+use crate::{
+    i18n::{tr, Language},
+    layout::scaling::monospace,
+    observables::adc::{hex, AdcMonitor, Statistics, CHANNELS},
+};
+
+use super::text_color;
+
+fn label(ui: &mut Ui, text: impl Into<String>, highlight: bool) {
+    ui.label(
+        RichText::new(text.into())
+            .font(monospace(ui, 20.0))
+            .color(text_color(highlight)),
+    );
+}
+
+fn render_statistics(ui: &mut Ui, statistics: Option<Statistics>, precision: usize) {
+    match statistics {
+        Some(Statistics { min, max, mean }) => {
+            for value in [min, max, mean] {
+                label(ui, format!("{:.*}", precision, value), false);
+            }
+        }
+        None => {
+            for _ in 0..3 {
+                label(ui, "-", false);
+            }
+        }
+    }
+}
+
+pub fn render_adc(ui: &mut Ui, adc: &AdcMonitor, language: Language) {
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new(tr(language, "ADC readings"))
+                .font(monospace(ui, 48.0))
+                .color(text_color(false)),
+        );
+        egui::Grid::new("adc").striped(false).show(ui, |ui| {
+            for heading in ["", "Hex", "Raw", "Value", "Min", "Max", "Mean"] {
+                label(ui, tr(language, heading), false);
+            }
+            ui.end_row();
+            for channel in CHANNELS {
+                let unit = channel.unit();
+                label(ui, tr(language, channel.name()), true);
+                match adc.latest(channel) {
+                    Some((raw, value)) => {
+                        label(ui, hex(raw), true);
+                        label(ui, raw.to_string(), true);
+                        label(ui, format!("{:.3} {}", value, unit), true);
+                    }
+                    None => {
+                        for _ in 0..3 {
+                            label(ui, "-", true);
+                        }
+                    }
+                }
+                render_statistics(ui, adc.raw_statistics(channel), 1);
+                ui.end_row();
+                label(ui, "", false);
+                label(ui, "", false);
+                label(ui, "", false);
+                label(ui, unit, false);
+                render_statistics(ui, adc.converted_statistics(channel), 3);
+                ui.end_row();
+            }
+        });
+        ui.separator();
+        ui.label(
+            RichText::new(format!(
+                "{}: {}, {}: {}s",
+                tr(language, "Tared readings of the target"),
+                adc.count(),
+                tr(language, "window"),
+                adc.window().as_secs()
+            ))
+            .color(text_color(false))
+            .heading(),
+        );
+        ui.label(
+            RichText::new(tr(language, "Left/Right: window, Enter: tare, Back: leave"))
+                .color(text_color(true))
+                .heading(),
+        );
+    });
+}
@@ -0,0 +1,31 @@
+use egui::{RichText, Ui};
+
+use crate::{annotation::Annotator, layout::scaling::monospace};
+
+use super::text_color;
+
+pub fn render_annotation(ui: &mut Ui, annotator: &Annotator) {
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new(format!(
+                "Mark at {:.1}s",
+                annotator.pending().unwrap_or(0.0)
+            ))
+            .font(monospace(ui, 48.0))
+            .color(text_color(false)),
+        );
+        for (index, label) in annotator.labels().iter().enumerate() {
+            ui.label(
+                RichText::new(label)
+                    .color(text_color(Some(index) == annotator.selected()))
+                    .heading(),
+            );
+        }
+        ui.separator();
+        ui.label(
+            RichText::new("Back marks without a label")
+                .color(text_color(false))
+                .heading(),
+        );
+    });
+}
@@ -0,0 +1,88 @@
+use egui::{RichText, Ui};
+use epaint::Color32;
+
+use crate::{
+    broadcast::{Broadcast, NodeResult, COMMANDS},
+    i18n::{tr, Language},
+    layout::scaling::monospace,
+};
+
+use super::text_color;
+
+fn render_result(ui: &mut Ui, result: NodeResult, language: Language) {
+    let (text, color) = match result {
+        NodeResult::Pending => (tr(language, "pending"), Color32::DARK_GRAY),
+        NodeResult::Sent => (tr(language, "running"), Color32::YELLOW),
+        NodeResult::Acked => ("ACK", Color32::GREEN),
+        NodeResult::Naked => ("NAK", Color32::RED),
+        NodeResult::TimedOut => ("TIMEOUT", Color32::RED),
+        NodeResult::NotSent => (tr(language, "not sent"), Color32::RED),
+        NodeResult::Skipped => (tr(language, "skipped"), Color32::GRAY),
+    };
+    ui.label(RichText::new(text).color(color).heading());
+}
+
+pub fn render_broadcast(ui: &mut Ui, broadcast: &Broadcast, language: Language) {
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new(tr(language, "Broadcast"))
+                .font(monospace(ui, 48.0))
+                .color(text_color(false)),
+        );
+        if !broadcast.is_running() {
+            for (index, command) in COMMANDS.iter().enumerate() {
+                ui.label(
+                    RichText::new(command.name())
+                        .color(text_color(index == broadcast.selected))
+                        .heading(),
+                );
+            }
+            ui.separator();
+        }
+        match &broadcast.run {
+            Some(run) => {
+                ui.label(
+                    RichText::new(run.command.name())
+                        .color(text_color(false))
+                        .heading(),
+                );
+                egui::Grid::new("broadcast").striped(false).show(ui, |ui| {
+                    for (node, result) in run.results.iter() {
+                        ui.label(
+                            RichText::new(node.to_string())
+                                .color(text_color(false))
+                                .heading(),
+                        );
+                        render_result(ui, *result, language);
+                        ui.end_row();
+                    }
+                });
+                if run.is_finished() {
+                    ui.label(
+                        RichText::new(run.summary())
+                            .color(text_color(false))
+                            .heading(),
+                    );
+                }
+            }
+            None => {
+                let nodes: Vec<_> = broadcast.nodes().iter().map(|n| n.to_string()).collect();
+                ui.label(
+                    RichText::new(nodes.join(", "))
+                        .color(text_color(false))
+                        .heading(),
+                );
+            }
+        }
+        ui.separator();
+        let text = match broadcast.is_running() {
+            true => "Back to skip the remaining nodes",
+            false => "Enter to send to all nodes, Back to leave",
+        };
+        ui.label(
+            RichText::new(tr(language, text))
+                .color(text_color(true))
+                .heading(),
+        );
+    });
+}
@@ -0,0 +1,62 @@
+use egui::{RichText, Ui};
+use epaint::Color32;
+
+use crate::{
+    calibration::{ImuCalibration, Verification},
+    layout::scaling::monospace,
+};
+
+use super::text_color;
+
+fn render_verification(ui: &mut Ui, verification: Verification) {
+    let (text, color) = match verification {
+        Verification::Unreported => ("not reported".into(), Color32::DARK_GRAY),
+        Verification::Verified => ("verified".into(), Color32::GREEN),
+        Verification::Mismatch(reported) => (format!("reports {}", reported.text()), Color32::RED),
+    };
+    ui.label(RichText::new(text).color(color).heading());
+}
+
+pub fn render_calibration(ui: &mut Ui, calibration: &ImuCalibration) {
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new("IMU ranges")
+                .font(monospace(ui, 48.0))
+                .color(text_color(false)),
+        );
+        egui::Grid::new("calibration")
+            .striped(false)
+            .show(ui, |ui| {
+                for (index, node) in calibration.nodes().iter().enumerate() {
+                    let current = index == calibration.selected_node;
+                    let ranges = calibration.ranges(node);
+                    ui.label(
+                        RichText::new(node.to_string())
+                            .color(text_color(false))
+                            .heading(),
+                    );
+                    for (parameter, name) in
+                        [ranges.acc.name(), ranges.gyr.name()].iter().enumerate()
+                    {
+                        let selected = current && parameter == calibration.selected;
+                        ui.label(RichText::new(*name).color(text_color(selected)).heading());
+                    }
+                    render_verification(ui, calibration.verification(node));
+                    ui.end_row();
+                }
+            });
+        ui.separator();
+        ui.label(
+            RichText::new("Nodes report their ranges on boot, telemetry is scaled as configured")
+                .color(text_color(false))
+                .heading(),
+        );
+        ui.label(
+            RichText::new(
+                "Left/Right: change, Enter: next, Send: take reported, Back: leave and store",
+            )
+            .color(text_color(true))
+            .heading(),
+        );
+    });
+}
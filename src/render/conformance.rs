@@ -0,0 +1,37 @@
+use egui::{Color32, RichText, Ui};
+
+use crate::{conformance::Conformance, layout::scaling::monospace};
+
+use super::text_color;
+
+// Over everything until acknowledged, in strict mode only
+pub fn render_deviations(ui: &mut Ui, conformance: &Conformance) {
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new("PROTOCOL DEVIATION")
+                .font(monospace(ui, 48.0))
+                .color(Color32::RED),
+        );
+        for (deviation, sentence) in conformance.unacknowledged() {
+            ui.label(
+                RichText::new(deviation.describe())
+                    .color(Color32::YELLOW)
+                    .heading(),
+            );
+            ui.label(
+                RichText::new(sentence)
+                    .font(monospace(ui, 16.0))
+                    .color(Color32::WHITE),
+            );
+        }
+        ui.separator();
+        ui.label(
+            RichText::new(format!(
+                "{} since start, all journaled. Enter acknowledges",
+                conformance.total()
+            ))
+            .color(text_color(true))
+            .heading(),
+        );
+    });
+}
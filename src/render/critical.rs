@@ -0,0 +1,83 @@
+use egui::{RichText, Ui};
+use epaint::Color32;
+
+use crate::{
+    i18n::tr,
+    input::{inputs_for, Binding},
+    layout::{numeric::NumericDisplay, scaling::monospace},
+    model::{LaunchControlMode, Mode, StateProcessing},
+    observables::groups::PyroStatus,
+};
+
+use super::{color_frame, freshness, snapshot::RenderSnapshot};
+
+// Where a glance from the distance has to suffice
+pub fn is_critical(mode: &Mode) -> bool {
+    matches!(
+        mode,
+        Mode::LaunchControl(
+            LaunchControlMode::WaitForFire { .. }
+                | LaunchControlMode::Fire
+                | LaunchControlMode::WaitForPyroTimeout(_)
+        )
+    )
+}
+
+fn pyro_text(name: &str, status: Option<PyroStatus>) -> (String, Color32) {
+    match status {
+        Some(PyroStatus::Closed) => (format!("{} CONTINUITY", name), Color32::GREEN),
+        Some(PyroStatus::Open) => (format!("{} OPEN", name), Color32::RED),
+        _ => (format!("{} UNKNOWN", name), Color32::GRAY),
+    }
+}
+
+// White and pure colors on black instead of the mode colors
+pub fn render_critical(ui: &mut Ui, state: &RenderSnapshot) {
+    let scale = state.accessibility.critical_scale;
+    let large = monospace(ui, 54.0 * scale);
+    let medium = monospace(ui, 24.0 * scale);
+    let stale = state.stale;
+    let obg2 = state.obg2.as_ref().filter(|_| !stale.obg2);
+    color_frame(Color32::BLACK, 10.0).show(ui, |ui| {
+        ui.set_min_size(ui.available_size());
+        ui.vertical_centered(|ui| {
+            ui.label(
+                RichText::new(tr(state.language, state.mode.name()).to_uppercase())
+                    .font(large)
+                    .color(Color32::WHITE),
+            );
+            let met = match state.met_s {
+                Some(met_s) => NumericDisplay::new(format!("T+{:.1}", met_s), "s"),
+                None => NumericDisplay::new("T-0", ""),
+            };
+            met.color(Color32::YELLOW).show(ui, 54.0 * scale);
+            if let Mode::LaunchControl(LaunchControlMode::WaitForFire { .. }) = state.mode {
+                ui.label(
+                    RichText::new("ENTER TO FIRE")
+                        .font(medium.clone())
+                        .color(Color32::YELLOW),
+                );
+            }
+            let (text, color) = match state.safety_key.inhibits() {
+                true => ("SAFETY KEY INHIBITS", Color32::RED),
+                false => ("SAFETY KEY ARMED", Color32::GREEN),
+            };
+            ui.label(RichText::new(text).font(medium.clone()).color(color));
+            for (name, status) in [
+                ("PYRO 1/2", obg2.map(|obg2| obg2.pyro12_status.clone())),
+                ("PYRO 3/4", obg2.map(|obg2| obg2.pyro34_status.clone())),
+            ] {
+                let (text, color) = pyro_text(name, status);
+                ui.label(freshness(
+                    RichText::new(text).font(medium.clone()).color(color),
+                    stale.obg2,
+                ));
+            }
+            ui.label(
+                RichText::new(format!("ABORT: {}", inputs_for(&Binding::Abort).join(", ")))
+                    .font(medium)
+                    .color(Color32::RED),
+            );
+        });
+    });
+}
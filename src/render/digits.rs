@@ -0,0 +1,36 @@
+use egui::{Sense, Ui};
+use emath::Align2;
+use epaint::Color32;
+
+use crate::{args::Privacy, digits::DigitEntry, layout::scaling::monospace};
+
+use super::text_color;
+
+fn render_digit(ui: &mut Ui, digit: u8, active: bool, privacy: Privacy) {
+    let digit_font = monospace(ui, 54.0);
+    let painter = ui.painter();
+    let text = match char::from_digit(digit as u32, 16) {
+        _ if privacy == Privacy::Mask && !active => "*".into(),
+        Some(c) => c.to_ascii_uppercase().to_string(),
+        None => unreachable!(),
+    };
+
+    let galley = painter.layout_no_wrap(text.clone(), digit_font.clone(), Color32::RED);
+    let rect = galley.size();
+    let (response, painter) = ui.allocate_painter(rect.into(), Sense::hover());
+
+    painter.text(
+        response.rect.center(),
+        Align2::CENTER_CENTER,
+        text,
+        digit_font,
+        text_color(active),
+    );
+}
+
+// The digit being edited is highlighted while active
+pub fn render_digit_entry(ui: &mut Ui, entry: &DigitEntry, active: bool, privacy: Privacy) {
+    for (position, digit) in entry.digits().iter().enumerate() {
+        render_digit(ui, *digit, active && position == entry.position(), privacy);
+    }
+}
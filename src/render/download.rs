@@ -0,0 +1,117 @@
+use egui::{ProgressBar, RichText, Ui};
+use epaint::Color32;
+
+use crate::{
+    download::{DownloadState, Downloader, FileOperation, Outcome},
+    layout::scaling::monospace,
+};
+
+use super::text_color;
+
+fn render_outcome(ui: &mut Ui, outcome: &Outcome) {
+    let (text, color) = match outcome {
+        Outcome::Saved(path) => (format!("Saved to {}", path.display()), Color32::GREEN),
+        Outcome::Failed(reason) => (format!("FAILED: {}", reason), Color32::RED),
+    };
+    ui.label(RichText::new(text).color(color).heading());
+}
+
+// The node's storage, with the selected file highlighted
+fn render_files(ui: &mut Ui, downloader: &Downloader) {
+    egui::Grid::new("files").striped(false).show(ui, |ui| {
+        for (index, file) in downloader.files.iter().enumerate() {
+            let selected = downloader.selected == Some(index);
+            ui.label(
+                RichText::new(&file.name)
+                    .color(text_color(selected))
+                    .heading(),
+            );
+            ui.label(
+                RichText::new(format!("{} bytes", file.size))
+                    .color(text_color(selected))
+                    .heading(),
+            );
+            ui.end_row();
+        }
+    });
+    let status = match &downloader.operation {
+        Some(FileOperation::Listing { index, .. }) => {
+            Some((format!("Listing entry {}", index), Color32::YELLOW))
+        }
+        Some(FileOperation::Deleting { name, .. }) => {
+            Some((format!("Deleting {}", name), Color32::YELLOW))
+        }
+        None if downloader.confirm_delete => downloader
+            .selected_file()
+            .map(|file| (format!("Delete {}?", file.name), Color32::YELLOW)),
+        None if downloader.files.is_empty() => Some(("No files".into(), text_color(false))),
+        None => None,
+    };
+    if let Some((text, color)) = status {
+        ui.label(RichText::new(text).color(color).heading());
+    }
+    if let Some(failure) = &downloader.failure {
+        ui.label(RichText::new(failure).color(Color32::RED).heading());
+    }
+}
+
+// Without a file or support on the node there is nothing to start
+pub fn render_downloads(
+    ui: &mut Ui,
+    downloader: &Downloader,
+    onboard_file: Option<String>,
+    supported: bool,
+    file_manage: bool,
+) {
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new("Downloads")
+                .font(monospace(ui, 48.0))
+                .color(text_color(false)),
+        );
+        let onboard = match (supported, &onboard_file) {
+            (false, _) => "Node doesn't support file reads".into(),
+            (true, None) => "No onboard recording reported".into(),
+            (true, Some(name)) => format!("Onboard file: {}", name),
+        };
+        ui.label(RichText::new(onboard).color(text_color(false)).heading());
+        ui.separator();
+        if file_manage {
+            render_files(ui, downloader);
+            ui.separator();
+        }
+        if let Some(download) = &downloader.download {
+            ui.label(
+                RichText::new(format!("{}: {} bytes", download.name, download.received()))
+                    .color(text_color(false))
+                    .heading(),
+            );
+            ui.add(ProgressBar::new(download.progress()).show_percentage());
+            match &download.state {
+                DownloadState::Opening => {
+                    ui.label(RichText::new("Opening").color(Color32::YELLOW).heading());
+                }
+                DownloadState::Reading { size, .. } => {
+                    ui.label(
+                        RichText::new(format!("Reading {} bytes", size))
+                            .color(Color32::YELLOW)
+                            .heading(),
+                    );
+                }
+                DownloadState::Closing(outcome) | DownloadState::Finished(outcome) => {
+                    render_outcome(ui, outcome)
+                }
+            }
+            ui.separator();
+        }
+        let text = match (downloader.is_running(), downloader.confirm_delete) {
+            (true, _) => "Back to cancel",
+            (false, true) => "Enter to delete, Back to keep the file",
+            (false, false) if file_manage => {
+                "Left/Right to select, Enter to download, Send to delete, Back to leave"
+            }
+            (false, false) => "Enter to download, Back to leave",
+        };
+        ui.label(RichText::new(text).color(text_color(true)).heading());
+    });
+}
@@ -0,0 +1,98 @@
+use egui::{RichText, Ui};
+use epaint::Color32;
+
+use crate::{
+    diagnostics::Diagnostics,
+    failover::Failover,
+    i18n::{tr, Language},
+    layout::scaling::monospace,
+    postmortem::PostMortem,
+};
+
+use super::text_color;
+
+fn dark_heading(ui: &mut Ui, text: &str) {
+    ui.label(RichText::new(text).color(text_color(false)).heading());
+}
+
+pub fn render_failure(
+    ui: &mut Ui,
+    diagnostics: &Diagnostics,
+    postmortem: &PostMortem,
+    failover: &Failover,
+    language: Language,
+) {
+    let mono_font = monospace(ui, 16.0);
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new(tr(language, "Connection failure"))
+                .font(monospace(ui, 48.0))
+                .color(Color32::RED),
+        );
+        match &diagnostics.cause {
+            Some(cause) => {
+                dark_heading(ui, &cause.description());
+                dark_heading(ui, cause.hint());
+            }
+            None => dark_heading(
+                ui,
+                tr(language, "Cause unknown, waiting for automatic reset."),
+            ),
+        }
+        ui.separator();
+        dark_heading(ui, tr(language, "Last received sentences:"));
+        let mut any = false;
+        for sentence in diagnostics.raw_sentences() {
+            any = true;
+            ui.label(
+                RichText::new(sentence)
+                    .font(mono_font.clone())
+                    .color(Color32::WHITE),
+            );
+        }
+        if !any {
+            ui.label(RichText::new("--").font(mono_font).color(Color32::WHITE));
+        }
+        ui.separator();
+        match &postmortem.written {
+            _ if postmortem.is_writing() => {
+                dark_heading(ui, tr(language, "Writing the diagnostic bundle..."))
+            }
+            Some(Ok(path)) => dark_heading(
+                ui,
+                &format!(
+                    "{} {}",
+                    tr(language, "Diagnostic bundle written to"),
+                    path.display()
+                ),
+            ),
+            Some(Err(err)) => {
+                ui.label(
+                    RichText::new(format!(
+                        "{}: {}",
+                        tr(language, "Diagnostic bundle failed"),
+                        err
+                    ))
+                    .color(Color32::RED)
+                    .heading(),
+                );
+            }
+            None => dark_heading(ui, tr(language, "Press Z to write a diagnostic bundle")),
+        }
+        ui.label(
+            RichText::new(tr(language, "Press Enter to retry now"))
+                .color(text_color(true))
+                .heading(),
+        );
+        if failover.has_secondary() {
+            ui.label(
+                RichText::new(format!(
+                    "Press S to switch from the {} radio",
+                    failover.active().name()
+                ))
+                .color(text_color(true))
+                .heading(),
+            );
+        }
+    });
+}
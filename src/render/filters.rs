@@ -0,0 +1,37 @@
+use egui::{RichText, Ui};
+
+use crate::{
+    layout::scaling::monospace,
+    observables::filter::{FilterTuner, Filters},
+};
+
+use super::text_color;
+
+pub fn render_filters(ui: &mut Ui, tuner: &FilterTuner, filters: &Filters) {
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new("Plot filters")
+                .font(monospace(ui, 48.0))
+                .color(text_color(false)),
+        );
+        egui::Grid::new("filters").striped(false).show(ui, |ui| {
+            for (index, (name, value)) in FilterTuner::parameters(filters).iter().enumerate() {
+                let selected = index == tuner.selected;
+                ui.label(RichText::new(*name).color(text_color(selected)).heading());
+                ui.label(RichText::new(value).color(text_color(selected)).heading());
+                ui.end_row();
+            }
+        });
+        ui.separator();
+        ui.label(
+            RichText::new("Only the plots are filtered, the recording stays raw")
+                .color(text_color(false))
+                .heading(),
+        );
+        ui.label(
+            RichText::new("Left/Right: change, Enter: next, Back: leave and store")
+                .color(text_color(true))
+                .heading(),
+        );
+    });
+}
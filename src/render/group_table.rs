@@ -0,0 +1,21 @@
+use egui::{RichText, Ui};
+
+use crate::observables::template::GroupTable;
+
+use super::{freshness, text_color};
+
+// The default for groups declared with observable_group!
+pub fn render_group_table<G: GroupTable>(ui: &mut Ui, group: &G, stale: bool) {
+    egui::Grid::new(std::any::type_name::<G>())
+        .striped(false)
+        .show(ui, |ui| {
+            for (name, value) in group.rows() {
+                ui.label(RichText::new(name).color(text_color(false)).heading());
+                ui.label(freshness(
+                    RichText::new(value).color(text_color(false)).heading(),
+                    stale,
+                ));
+                ui.end_row();
+            }
+        });
+}
@@ -0,0 +1,57 @@
+use egui::{RichText, Ui};
+
+use crate::{
+    i18n::{tr, Language},
+    input::{inputs_for, Binding, InputEvent, KEY_BINDINGS},
+    layout::scaling::monospace,
+};
+
+use super::text_color;
+
+fn render_row(ui: &mut Ui, binding: &Binding, effect: &str) {
+    ui.label(
+        RichText::new(inputs_for(binding).join(", "))
+            .font(monospace(ui, 20.0))
+            .color(text_color(true)),
+    );
+    ui.label(RichText::new(effect).color(text_color(false)).heading());
+    ui.end_row();
+}
+
+// Both columns come from the binding tables and the
+// model, so the help can't drift from the actual inputs.
+pub fn render_help(
+    ui: &mut Ui,
+    mode: &str,
+    effects: &[(InputEvent, &'static str)],
+    language: Language,
+) {
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new(format!("{}: {}", tr(language, "Help"), tr(language, mode)))
+                .font(monospace(ui, 48.0))
+                .color(text_color(false)),
+        );
+        egui::Grid::new("help").striped(false).show(ui, |ui| {
+            for (event, effect) in effects {
+                render_row(ui, &Binding::Event(*event), effect);
+            }
+        });
+        ui.separator();
+        egui::Grid::new("help actions")
+            .striped(false)
+            .show(ui, |ui| {
+                for (_, binding) in KEY_BINDINGS.iter() {
+                    if let Some(description) = binding.description() {
+                        render_row(ui, binding, description);
+                    }
+                }
+            });
+        ui.separator();
+        ui.label(
+            RichText::new(tr(language, "Enter or Back to close"))
+                .color(text_color(true))
+                .heading(),
+        );
+    });
+}
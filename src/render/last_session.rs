@@ -0,0 +1,118 @@
+use egui::{Color32, RichText, Ui};
+
+use crate::{
+    layout::scaling::monospace,
+    session::{LastSession, SessionSummary},
+};
+
+use super::text_color;
+
+fn render_row(ui: &mut Ui, name: &str, value: RichText) {
+    ui.label(RichText::new(name).color(text_color(false)).heading());
+    ui.label(value.heading());
+    ui.end_row();
+}
+
+pub fn render_summary(ui: &mut Ui, summary: &SessionSummary) {
+    let minutes = summary.duration.as_secs() / 60;
+    egui::Grid::new("last session")
+        .striped(false)
+        .show(ui, |ui| {
+            render_row(
+                ui,
+                "Started",
+                RichText::new(summary.started.format("%Y-%m-%d %H:%M").to_string())
+                    .color(Color32::WHITE),
+            );
+            if let Some(run) = &summary.run {
+                render_row(ui, "Run", RichText::new(run).color(Color32::WHITE));
+            }
+            render_row(
+                ui,
+                "Duration",
+                RichText::new(format!("{}:{:02}", minutes / 60, minutes % 60))
+                    .color(Color32::WHITE),
+            );
+            render_row(
+                ui,
+                "Max thrust",
+                RichText::new(
+                    summary
+                        .max_thrust_kn
+                        .map_or("--".into(), |thrust| format!("{:.3}kN", thrust)),
+                )
+                .color(Color32::WHITE),
+            );
+            render_row(
+                ui,
+                "Anomalies",
+                RichText::new(summary.anomalies.to_string()).color(match summary.anomalies {
+                    0 => Color32::WHITE,
+                    _ => Color32::YELLOW,
+                }),
+            );
+            if summary.files.is_empty() {
+                render_row(ui, "Files", RichText::new("None").color(Color32::YELLOW));
+            }
+            for file in &summary.files {
+                let (text, color) = match file.bytes {
+                    Some(0) => (format!("{} is empty", file.path.display()), Color32::RED),
+                    Some(bytes) => (
+                        format!("{} {}kB", file.path.display(), bytes / 1024),
+                        Color32::WHITE,
+                    ),
+                    None => (format!("{} is missing", file.path.display()), Color32::RED),
+                };
+                render_row(ui, "File", RichText::new(text).color(color));
+            }
+            let (text, color) = match (summary.ended_normally, summary.abnormal_terminations) {
+                (true, _) => ("Normally".to_string(), Color32::WHITE),
+                (false, 1) => ("Abnormally".to_string(), Color32::RED),
+                (false, count) => (
+                    format!("Abnormally, {} sessions in a row", count),
+                    Color32::RED,
+                ),
+            };
+            render_row(ui, "Ended", RichText::new(text).color(color));
+        });
+}
+
+pub fn render_last_session(ui: &mut Ui, last_session: &LastSession) {
+    let Some(summary) = last_session.summary() else {
+        return;
+    };
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new("Last session")
+                .font(monospace(ui, 48.0))
+                .color(text_color(false)),
+        );
+        ui.label(
+            RichText::new(summary.journal.display().to_string())
+                .color(text_color(false))
+                .heading(),
+        );
+        ui.separator();
+        if last_session.showing_full_journal() {
+            egui::ScrollArea::vertical()
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for line in &summary.lines {
+                        ui.label(
+                            RichText::new(line)
+                                .font(monospace(ui, 16.0))
+                                .color(text_color(false)),
+                        );
+                    }
+                });
+        } else {
+            render_summary(ui, summary);
+            ui.separator();
+            ui.label(
+                RichText::new("Enter shows the full journal, back dismisses")
+                    .color(text_color(false))
+                    .heading(),
+            );
+        }
+    });
+}
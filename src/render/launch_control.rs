@@ -1,39 +1,32 @@
+use std::time::Duration;
+
 use egui::{RichText, Sense, Ui};
 use emath::Align2;
-use epaint::{Color32, FontId, Shadow};
+use epaint::{Color32, Shadow};
 
 use crate::{
-    layout::colors::{kind_color32, Intensity, Kind},
-    model::LaunchControlMode,
-    observables::rqb::ObservablesGroup2,
+    args::Privacy,
+    freshness::Stale,
+    i18n::{tr, Language},
+    input::{inputs_for, Binding},
+    layout::{
+        colors::{kind_color32, Intensity, Kind},
+        numeric::NumericDisplay,
+        scaling::monospace,
+    },
+    model::{LaunchControlMode, PreArmCheck, PRE_ARM_CHECKS},
+    observables::groups::{ObservablesGroup2, PyroStatus},
+    safety::SafetyKey,
+    window::WindowStatus,
 };
 
-use super::{clear_frame, render_progress, rq_render::render_pyro_state, text_color};
-
-fn render_digit(ui: &mut Ui, digit: u8, active: bool) {
-    let digit_font = FontId::new(54.0, egui::FontFamily::Monospace);
-    let painter = ui.painter();
-    let text = match digit {
-        0..10 => format!("{}", digit),
-        10..16 => format!("{}", std::str::from_utf8(&[55 + digit]).expect("")),
-        _ => unreachable!(),
-    };
-
-    let galley = painter.layout_no_wrap(text.clone(), digit_font.clone(), Color32::RED);
-    let rect = galley.size();
-    let (response, painter) = ui.allocate_painter(rect.into(), Sense::hover());
-
-    painter.text(
-        response.rect.center(),
-        Align2::CENTER_CENTER,
-        text,
-        digit_font,
-        text_color(active),
-    );
-}
+use super::{
+    clear_frame, digits::render_digit_entry, freshness, render_progress, rqb::render_pyro_state,
+    text_color,
+};
 
 fn render_fire(ui: &mut Ui, state: &LaunchControlMode) {
-    let digit_font = FontId::new(54.0, egui::FontFamily::Monospace);
+    let digit_font = monospace(ui, 54.0);
     let painter = ui.painter();
     let text = "Press Enter to Fire!";
     let galley = painter.layout_no_wrap(text.into(), digit_font.clone(), Color32::RED);
@@ -52,27 +45,53 @@ fn render_fire(ui: &mut Ui, state: &LaunchControlMode) {
     );
 }
 
-fn render_launch_control_interactions(ui: &mut Ui, state: &LaunchControlMode) {
-    let (hi_a, lo_a, hi_b, lo_b) = state.digits();
-    let (hi_a_hl, lo_a_hl, hi_b_hl, lo_b_hl) = state.highlights();
+fn render_key_entry_expiry(ui: &mut Ui, expires_in: Duration, language: Language) {
+    let secs = expires_in.as_secs_f32().ceil() as u64;
+    ui.label(
+        RichText::new(format!(
+            "{}: {}s",
+            tr(language, "Key entry expires in"),
+            secs
+        ))
+        .color(match secs {
+            0..=10 => Color32::RED,
+            _ => Color32::YELLOW,
+        })
+        .heading(),
+    );
+}
+
+fn render_launch_control_interactions(
+    ui: &mut Ui,
+    state: &LaunchControlMode,
+    privacy: Privacy,
+    key_entry_expires_in: Option<Duration>,
+    language: Language,
+) {
+    let [(key_a, key_a_active), (key_b, key_b_active)] = state.key_entries();
+    let show_keys = privacy != Privacy::Hide;
 
     ui.vertical(|ui| {
-        ui.horizontal(|ui| {
-            egui::SidePanel::left("key a left")
-                .resizable(false)
-                .show_separator_line(false)
-                .frame(clear_frame())
-                .exact_width(ui.available_width() / 3.0)
-                .show_inside(ui, |ui| {
-                    ui.label(
-                        RichText::new("Enter Key A")
-                            .color(text_color(hi_a_hl || lo_a_hl))
-                            .heading(),
-                    );
-                });
-            render_digit(ui, hi_a, hi_a_hl);
-            render_digit(ui, lo_a, lo_a_hl);
-        });
+        if show_keys {
+            ui.horizontal(|ui| {
+                egui::SidePanel::left("key a left")
+                    .resizable(false)
+                    .show_separator_line(false)
+                    .frame(clear_frame())
+                    .exact_width(ui.available_width() / 3.0)
+                    .show_inside(ui, |ui| {
+                        ui.label(
+                            RichText::new(tr(language, "Enter Key A"))
+                                .color(text_color(key_a_active))
+                                .heading(),
+                        );
+                    });
+                render_digit_entry(ui, &key_a, key_a_active, privacy);
+            });
+        }
+        if let (true, Some(expires_in)) = (key_a_active, key_entry_expires_in) {
+            render_key_entry_expiry(ui, expires_in, language);
+        }
         ui.label(
             RichText::new("Unlock Pyros")
                 .color(text_color(
@@ -85,22 +104,26 @@ fn render_launch_control_interactions(ui: &mut Ui, state: &LaunchControlMode) {
                 .heading(),
         );
         render_progress(ui, state, state.unlock_pyros_progress(), false);
-        ui.horizontal(|ui| {
-            egui::SidePanel::left("key b left")
-                .resizable(false)
-                .show_separator_line(false)
-                .frame(clear_frame())
-                .exact_width(ui.available_width() / 3.0)
-                .show_inside(ui, |ui| {
-                    ui.label(
-                        RichText::new("Enter Key B")
-                            .color(text_color(hi_b_hl || lo_b_hl))
-                            .heading(),
-                    );
-                });
-            render_digit(ui, hi_b, hi_b_hl);
-            render_digit(ui, lo_b, lo_b_hl);
-        });
+        if show_keys {
+            ui.horizontal(|ui| {
+                egui::SidePanel::left("key b left")
+                    .resizable(false)
+                    .show_separator_line(false)
+                    .frame(clear_frame())
+                    .exact_width(ui.available_width() / 3.0)
+                    .show_inside(ui, |ui| {
+                        ui.label(
+                            RichText::new(tr(language, "Enter Key B"))
+                                .color(text_color(key_b_active))
+                                .heading(),
+                        );
+                    });
+                render_digit_entry(ui, &key_b, key_b_active, privacy);
+            });
+        }
+        if let (true, Some(expires_in)) = (key_b_active, key_entry_expires_in) {
+            render_key_entry_expiry(ui, expires_in, language);
+        }
         ui.label(
             RichText::new("Arm Pyros")
                 .color(text_color(
@@ -117,8 +140,91 @@ fn render_launch_control_interactions(ui: &mut Ui, state: &LaunchControlMode) {
     });
 }
 
+fn pre_arm_result(
+    check: PreArmCheck,
+    obg2: &Option<ObservablesGroup2>,
+    language: Language,
+) -> (String, Color32) {
+    let continuity = |status: &PyroStatus| match status {
+        PyroStatus::Closed => (tr(language, "continuity").into(), Color32::DARK_GREEN),
+        PyroStatus::Open => ("OPEN".into(), Color32::RED),
+        PyroStatus::Unknown => (tr(language, "unknown").into(), Color32::DARK_GRAY),
+    };
+    match (check, obg2) {
+        (_, None) => (tr(language, "waiting").into(), Color32::DARK_GRAY),
+        (PreArmCheck::Battery, Some(obg2)) => {
+            (format!("{:03.2}V", obg2.vbb_voltage), Color32::BLACK)
+        }
+        (PreArmCheck::Pyro12, Some(obg2)) => continuity(&obg2.pyro12_status),
+        (PreArmCheck::Pyro34, Some(obg2)) => continuity(&obg2.pyro34_status),
+    }
+}
+
+fn render_pre_arm_checklist(
+    ui: &mut Ui,
+    acknowledged: usize,
+    obg2: &Option<ObservablesGroup2>,
+    stale: bool,
+    language: Language,
+) {
+    let font = monospace(ui, 32.0);
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new("Pre-Arm Checklist")
+                .color(text_color(true))
+                .heading(),
+        );
+        for (index, check) in PRE_ARM_CHECKS.iter().enumerate() {
+            let name = match check {
+                PreArmCheck::Battery => "VBB",
+                PreArmCheck::Pyro12 => "Pyro 1/2",
+                PreArmCheck::Pyro34 => "Pyro 3/4",
+            };
+            let (result, color) = pre_arm_result(*check, obg2, language);
+            let mark = if index < acknowledged { "[x]" } else { "[ ]" };
+            ui.horizontal(|ui| {
+                ui.label(
+                    RichText::new(format!("{} {:<9}", mark, name))
+                        .font(font.clone())
+                        .color(text_color(index == acknowledged)),
+                );
+                ui.label(freshness(
+                    RichText::new(result).font(font.clone()).color(color),
+                    stale,
+                ));
+            });
+        }
+        ui.label(
+            RichText::new(tr(
+                language,
+                if obg2.is_some() {
+                    "Enter to acknowledge, Back to abort"
+                } else {
+                    "Querying continuity..."
+                },
+            ))
+            .color(text_color(false)),
+        );
+    });
+}
+
+fn render_abort(ui: &mut Ui, state: &LaunchControlMode) {
+    let (text, hint) = match state {
+        LaunchControlMode::SafeAbort => ("Aborting", "Waiting for the pyros to be locked..."),
+        _ => ("Aborted", "Pyros locked, Enter or Back to return"),
+    };
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new(text)
+                .font(monospace(ui, 54.0))
+                .color(Color32::RED),
+        );
+        ui.label(RichText::new(hint).color(text_color(true)).heading());
+    });
+}
+
 fn render_rocket_screen(ui: &mut Ui) {
-    let giant_font = FontId::new(250.0, egui::FontFamily::Monospace);
+    let giant_font = monospace(ui, 250.0);
     let color = Color32::WHITE;
     let painter = ui.painter();
     let galley = painter.layout_no_wrap("🚀".into(), giant_font.clone(), color);
@@ -133,15 +239,15 @@ fn render_rocket_screen(ui: &mut Ui) {
     );
 }
 
-fn vbb_from_obg2(obg2: &Option<ObservablesGroup2>) -> String {
+fn vbb_from_obg2(obg2: &Option<ObservablesGroup2>) -> NumericDisplay {
     match obg2 {
-        Some(obg2) => format!("{:03.2}", obg2.vbb_voltage),
-        None => "--.--".into(),
+        Some(obg2) => NumericDisplay::new(format!("{:03.2}", obg2.vbb_voltage), "V"),
+        None => NumericDisplay::new("--.--", "V"),
     }
 }
 
-fn render_launch_control_powerstate(ui: &mut Ui, obg2: &Option<ObservablesGroup2>) {
-    let digit_font = FontId::new(54.0, egui::FontFamily::Monospace);
+fn render_launch_control_powerstate(ui: &mut Ui, obg2: &Option<ObservablesGroup2>, stale: bool) {
+    let digit_font = monospace(ui, 54.0);
     let painter = ui.painter();
     let galley = painter.layout_no_wrap("X".into(), digit_font.clone(), Color32::RED);
     let char_height = galley.rect.height();
@@ -152,11 +258,10 @@ fn render_launch_control_powerstate(ui: &mut Ui, obg2: &Option<ObservablesGroup2
                 .font(digit_font.clone())
                 .color(Color32::BLACK),
         );
-        ui.label(
-            RichText::new(vbb_from_obg2(obg2))
-                .font(digit_font.clone())
-                .color(Color32::BLACK),
-        );
+        vbb_from_obg2(obg2)
+            .stale(stale)
+            .color(Color32::BLACK)
+            .show(ui, 54.0);
         ui.label(
             RichText::new("Pyro 1/2")
                 .font(digit_font.clone())
@@ -164,7 +269,9 @@ fn render_launch_control_powerstate(ui: &mut Ui, obg2: &Option<ObservablesGroup2
         );
         render_pyro_state(
             ui,
-            obg2.clone().and_then(|obg2| Some(obg2.pyro12_status)),
+            obg2.clone()
+                .filter(|_| !stale)
+                .and_then(|obg2| Some(obg2.pyro12_status)),
             char_height,
         );
         ui.label(
@@ -174,7 +281,9 @@ fn render_launch_control_powerstate(ui: &mut Ui, obg2: &Option<ObservablesGroup2
         );
         render_pyro_state(
             ui,
-            obg2.clone().and_then(|obg2| Some(obg2.pyro34_status)),
+            obg2.clone()
+                .filter(|_| !stale)
+                .and_then(|obg2| Some(obg2.pyro34_status)),
             char_height,
         );
     });
@@ -184,7 +293,48 @@ pub fn render_launch_control(
     ui: &mut Ui,
     state: &LaunchControlMode,
     obg2: &Option<ObservablesGroup2>,
+    stale: Stale,
+    launched: bool,
+    privacy: Privacy,
+    safety_key: &SafetyKey,
+    launch_window: Option<&WindowStatus>,
+    key_entry_expires_in: Option<Duration>,
+    language: Language,
 ) {
+    if safety_key.inhibits() {
+        let text = match &safety_key.fault {
+            Some(fault) => format!("Inhibited by safety key ({})", fault),
+            None => "Inhibited by safety key".into(),
+        };
+        ui.label(
+            RichText::new(text)
+                .font(monospace(ui, 24.0))
+                .color(Color32::RED),
+        );
+    }
+    // Key AB and the ignition wait for it
+    if let Some(status) = launch_window.filter(|status| !status.allows_launch()) {
+        ui.label(
+            RichText::new(format!("Held by the launch window: {}", status.text()))
+                .font(monospace(ui, 24.0))
+                .color(Color32::RED),
+        );
+    }
+    // Above the launch control, which may still need an abort
+    if launched {
+        ui.label(
+            RichText::new("Left the rail without our ignition")
+                .font(monospace(ui, 24.0))
+                .color(Color32::RED),
+        );
+    }
+    if state.abortable() {
+        ui.label(
+            RichText::new(format!("Abort: {}", inputs_for(&Binding::Abort).join(", ")))
+                .color(Color32::RED)
+                .heading(),
+        );
+    }
     ui.horizontal(|ui| {
         let left_width = (ui.available_width() * 0.7).ceil();
         let right_width = ui.available_width() - left_width;
@@ -196,8 +346,20 @@ pub fn render_launch_control(
             .show_inside(ui, |ui| match state {
                 LaunchControlMode::WaitForPyroTimeout(_) => render_rocket_screen(ui),
                 LaunchControlMode::SwitchToObservables => render_rocket_screen(ui),
+                LaunchControlMode::SafeAbort | LaunchControlMode::Aborted => {
+                    render_abort(ui, state)
+                }
+                LaunchControlMode::PreArm { acknowledged } => {
+                    render_pre_arm_checklist(ui, *acknowledged, obg2, stale.obg2, language)
+                }
                 _ => {
-                    render_launch_control_interactions(ui, state);
+                    render_launch_control_interactions(
+                        ui,
+                        state,
+                        privacy,
+                        key_entry_expires_in,
+                        language,
+                    );
                 }
             });
         egui::SidePanel::right("powerstate")
@@ -212,6 +374,8 @@ pub fn render_launch_control(
                 shadow: Shadow::NONE,
             })
             .exact_width(right_width)
-            .show_inside(ui, |ui| render_launch_control_powerstate(ui, obg2));
+            .show_inside(ui, |ui| {
+                render_launch_control_powerstate(ui, obg2, stale.obg2)
+            });
     });
 }
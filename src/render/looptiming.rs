@@ -0,0 +1,65 @@
+use egui::{Align2, Frame, RichText, Ui};
+use epaint::Color32;
+
+use crate::{
+    layout::scaling::monospace,
+    looptiming::{LoopTiming, PHASES},
+};
+
+// Frames further apart than this are felt as a stutter
+const STUTTER_MS: f32 = 50.0;
+
+fn ms(duration: std::time::Duration) -> f32 {
+    duration.as_secs_f32() * 1000.0
+}
+
+// The text and whether it needs attention
+fn timing_lines(timing: &LoopTiming) -> Vec<(String, bool)> {
+    let mut lines = vec![];
+    match (timing.fps(), timing.worst_frame()) {
+        (Some(fps), Some(worst)) => lines.push((
+            format!("{:5.1} fps, worst frame {:.1}ms", fps, ms(worst)),
+            ms(worst) > STUTTER_MS,
+        )),
+        _ => lines.push(("-- fps".into(), false)),
+    }
+    for phase in PHASES {
+        if let Some(stats) = timing.phase(phase) {
+            lines.push((
+                format!(
+                    "{:<9} {:5.1}ms mean {:5.1}ms max",
+                    phase.name(),
+                    ms(stats.mean),
+                    ms(stats.max)
+                ),
+                ms(stats.max) > STUTTER_MS,
+            ));
+        }
+    }
+    lines.push((format!("Burst     {} packets", timing.burst()), false));
+    for (name, depth) in timing.queues.iter() {
+        lines.push((format!("{:<12} {}", name, depth), *depth > 0));
+    }
+    lines
+}
+
+// Over whatever is shown, in the corner the transitions don't use
+pub fn render_loop_timing(ui: &mut Ui, timing: &LoopTiming) {
+    let mono_font = monospace(ui, 14.0);
+    egui::Area::new("loop timing")
+        .anchor(Align2::LEFT_TOP, [8.0, 8.0])
+        .show(ui.ctx(), |ui| {
+            Frame::popup(ui.style()).show(ui, |ui| {
+                for (text, attention) in timing_lines(timing) {
+                    ui.label(
+                        RichText::new(text)
+                            .font(mono_font.clone())
+                            .color(match attention {
+                                true => Color32::YELLOW,
+                                false => Color32::WHITE,
+                            }),
+                    );
+                }
+            });
+        });
+}
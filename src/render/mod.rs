@@ -1,38 +1,81 @@
 use std::time::Duration;
 
 use egui::epaint::Shadow;
-use egui::{vec2, Align2, Color32, FontId, Frame, Id, ProgressBar, RichText, Sense, Stroke, Ui};
+use egui::{
+    vec2, Align, Align2, Color32, Frame, Id, Layout, ProgressBar, RichText, Sense, Stroke, Ui,
+};
 use emath::{pos2, Pos2};
 use palette::{Gradient, LinSrgb};
 
+use crate::config::ReloadOutcome;
 use crate::connection::Connection;
 use crate::ebyte::modem_baud_rate;
+use crate::i18n::{tr, Language};
 use crate::layout::colors::{color32, kind_color, kind_color32, Intensity, Kind};
+use crate::layout::scaling::monospace;
 use crate::model::{ControlArea, LaunchControlMode, Mode, Model, StateProcessing};
-use crate::observables::AdcGain;
+use crate::observables::Profile;
+use crate::onboard::OnboardRecording;
+use crate::recorder::{SinkHealth, SinkStatus};
+use crate::telemetry::ModuleStatus;
+use crate::window::WindowStatus;
 
-#[cfg(feature = "test-stand")]
-use crate::observables::rqa as rqobs;
-
-#[cfg(feature = "rocket")]
-use crate::observables::rqb as rqobs;
-
-#[cfg(feature = "test-stand")]
+pub mod group_table;
 pub mod rqa;
-#[cfg(feature = "rocket")]
 pub mod rqb;
-
+pub mod snapshot;
+
+use self::adc::render_adc;
+use self::annotation::render_annotation;
+use self::broadcast::render_broadcast;
+use self::calibration::render_calibration;
+use self::conformance::render_deviations;
+use self::critical::{is_critical, render_critical};
+use self::download::render_downloads;
+use self::failure::render_failure;
+use self::filters::render_filters;
+use self::help::render_help;
+use self::last_session::render_last_session;
 use self::launch_control::render_launch_control;
+use self::looptiming::render_loop_timing;
+use self::monitor::render_monitor;
+use self::ports::render_ports;
+use self::recovery::render_recovery;
 use self::rf_silence::render_rf_silence;
-#[cfg(feature = "test-stand")]
-use self::rqa as rq_render;
-#[cfg(feature = "rocket")]
-use self::rqb as rq_render;
-
+use self::roster::render_roster;
+use self::self_test::render_self_test;
+use self::sequences::render_sequences;
+use self::sessions::render_sessions;
+use self::snapshot::{RenderSnapshot, StatusSnapshot};
+use self::timeline::render_timeline;
+use self::transitions::render_transitions;
+use self::tuning::render_tuning;
+
+mod adc;
+mod annotation;
+mod broadcast;
+mod calibration;
+mod conformance;
+mod critical;
+mod digits;
+mod download;
+mod failure;
+mod filters;
+mod help;
+mod last_session;
 mod launch_control;
+mod looptiming;
+mod monitor;
+mod ports;
+mod recovery;
 mod rf_silence;
-
-use self::rq_render::render_observables;
+mod roster;
+mod self_test;
+mod sequences;
+mod sessions;
+mod timeline;
+mod transitions;
+mod tuning;
 
 // fn split_rect_horizontally_at(rect: &Rect, split: f32) -> (Rect, Rect) {
 //     let lt = rect.left_top();
@@ -46,7 +89,7 @@ use self::rq_render::render_observables;
 // }
 
 fn render_header_text(ui: &mut Ui, text: &str, color: Color32) {
-    let digit_font = FontId::new(32.0, egui::FontFamily::Monospace);
+    let digit_font = monospace(ui, 32.0);
     let painter = ui.painter();
     let galley = painter.layout_no_wrap(text.into(), digit_font.clone(), color);
     let rect = galley.size();
@@ -76,6 +119,15 @@ fn text_color(active: bool) -> Color32 {
     }
 }
 
+// Values that stopped coming in are greyed out and struck through
+fn freshness(text: RichText, stale: bool) -> RichText {
+    if stale {
+        text.color(Color32::GRAY).strikethrough()
+    } else {
+        text
+    }
+}
+
 fn kind_for_mode(mode: &Mode) -> Kind {
     match mode {
         Mode::Observables(_) => Kind::Observables,
@@ -84,22 +136,23 @@ fn kind_for_mode(mode: &Mode) -> Kind {
     }
 }
 
-fn render_header<C: Connection, Id: Iterator<Item = usize>>(ui: &mut Ui, model: &Model<C, Id>) {
-    let reset_ongoing = model.mode.reset_ongoing();
-    let is_observables = match model.mode() {
+fn render_header(ui: &mut Ui, snapshot: &RenderSnapshot) {
+    let reset_ongoing = snapshot.reset_ongoing;
+    let is_observables = match snapshot.mode {
         Mode::Observables(_) => true,
         _ => false,
     };
-    let is_launch_control = match model.mode() {
+    let is_launch_control = match snapshot.mode {
         Mode::LaunchControl(_) => true,
         _ => false,
     };
-    let is_rf_silence = match model.mode() {
+    let is_rf_silence = match snapshot.mode {
         Mode::RFSilence(_) => true,
         _ => false,
     };
 
-    let is_tabs = match model.control {
+    let language = snapshot.language;
+    let is_tabs = match snapshot.control {
         ControlArea::Tabs => true,
         ControlArea::Details => false,
     };
@@ -116,7 +169,7 @@ fn render_header<C: Connection, Id: Iterator<Item = usize>>(ui: &mut Ui, model:
             .show_inside(ui, |ui| {
                 render_header_text(
                     ui,
-                    "Observables",
+                    tr(language, "Observables"),
                     text_color(is_observables && is_tabs && !reset_ongoing),
                 );
             });
@@ -131,7 +184,7 @@ fn render_header<C: Connection, Id: Iterator<Item = usize>>(ui: &mut Ui, model:
             .show_inside(ui, |ui| {
                 render_header_text(
                     ui,
-                    "Launch Control",
+                    tr(language, "Launch Control"),
                     text_color(is_launch_control && is_tabs && !reset_ongoing),
                 );
             });
@@ -146,13 +199,33 @@ fn render_header<C: Connection, Id: Iterator<Item = usize>>(ui: &mut Ui, model:
             .show_inside(ui, |ui| {
                 render_header_text(
                     ui,
-                    "RF Silence",
+                    tr(language, "RF Silence"),
                     text_color(is_rf_silence && is_tabs && !reset_ongoing),
                 );
             });
     });
 }
 
+// Below the tabs, with where the time comes from
+fn render_launch_window(ui: &mut Ui, status: &WindowStatus, gps_fix: Option<bool>) {
+    let color = match status {
+        WindowStatus::Pending { .. } => Color32::WHITE,
+        WindowStatus::Open { closing: false, .. } => Color32::GREEN,
+        WindowStatus::Open { closing: true, .. } => Color32::YELLOW,
+        WindowStatus::Hold { .. } | WindowStatus::Closed => Color32::RED,
+    };
+    let source = match gps_fix {
+        Some(true) => " (GPS time)",
+        Some(false) => " (system time, no GPS fix)",
+        None => "",
+    };
+    ui.label(
+        RichText::new(format!("{}{}", status.text(), source))
+            .font(monospace(ui, 20.0))
+            .color(color),
+    );
+}
+
 fn render_progress(ui: &mut Ui, state: &LaunchControlMode, progress: f32, ignition: bool) {
     let gradient = Gradient::new(vec![
         LinSrgb::new(0.0, 1.0, 0.0),
@@ -181,15 +254,154 @@ fn render_progress(ui: &mut Ui, state: &LaunchControlMode, progress: f32, igniti
     ui.add(pbar);
 }
 
-fn render_body<C: Connection, Id: Iterator<Item = usize>>(ui: &mut Ui, state: &Model<C, Id>) {
-    let obg2 = state.obg2.clone();
+fn render_body(ui: &mut Ui, state: &RenderSnapshot) {
+    if state.help {
+        render_help(ui, state.mode.name(), &state.input_effects, state.language);
+        return;
+    }
+    if !state.conformance.unacknowledged().is_empty() {
+        render_deviations(ui, state.conformance);
+        return;
+    }
+    if state.annotator.is_picking() {
+        render_annotation(ui, state.annotator);
+        return;
+    }
+    if state.port_picker.is_active() {
+        render_ports(ui, state.port_picker, state.port);
+        return;
+    }
+    if state.last_session.is_active() {
+        render_last_session(ui, state.last_session);
+        return;
+    }
+    if state.self_test.is_active() {
+        render_self_test(ui, state.self_test, state.language);
+        return;
+    }
+    if state.monitor.is_active() {
+        render_monitor(
+            ui,
+            state.monitor,
+            state.invariants,
+            state.backoff,
+            state.status.holding,
+            state.now,
+        );
+        return;
+    }
+    // Also when the command link failed, it's not needed for this
+    if state.recovery.is_active() {
+        render_recovery(ui, state.recovery, state.elapsed);
+        return;
+    }
+    if state.roster_page.is_active() {
+        render_roster(
+            ui,
+            state.roster,
+            &state.status.nodes,
+            state.status.subscription.is_some(),
+        );
+        return;
+    }
+    if state.sessions.is_active() {
+        render_sessions(ui, state.sessions);
+        return;
+    }
+    if state.downloader.is_active() {
+        render_downloads(
+            ui,
+            state.downloader,
+            state.onboard_file.clone(),
+            state.file_read,
+            state.file_manage,
+        );
+        return;
+    }
+    if state.broadcast.is_active() {
+        render_broadcast(ui, state.broadcast, state.language);
+        return;
+    }
+    if state.sequencer.is_active() {
+        render_sequences(ui, state.sequencer, state.now, state.language);
+        return;
+    }
+    if state.tuner.is_active() {
+        render_tuning(ui, state.tuner, state.crank);
+        return;
+    }
+    if state.filter_tuner.is_active() {
+        render_filters(ui, state.filter_tuner, state.filters);
+        return;
+    }
+    if state.calibration.is_active() {
+        render_calibration(ui, state.calibration);
+        return;
+    }
+    if state.adc.is_active() {
+        render_adc(ui, state.adc, state.language);
+        return;
+    }
+    if state.failure {
+        render_failure(
+            ui,
+            state.diagnostics,
+            state.postmortem,
+            state.failover,
+            state.language,
+        );
+        return;
+    }
+    if state.accessibility.enabled && is_critical(&state.mode) {
+        render_critical(ui, state);
+        return;
+    }
+    let language = state.language;
     match state.mode {
-        Mode::Observables(_state) => render_observables(ui, state),
-        Mode::LaunchControl(state) => {
-            render_launch_control(ui, &state, &obg2);
+        // The details of the observables are the fused timeline
+        Mode::Observables(_) => match state.control {
+            ControlArea::Tabs => match state.profile {
+                Profile::TestStand => rqa::render_observables(
+                    ui,
+                    state.obg1,
+                    &state.obg2,
+                    state.stale,
+                    state.tare,
+                    state.filters,
+                    state.plot_ranges,
+                    state.adc_gain.clone().into(),
+                    state.units,
+                ),
+                Profile::Rocket => rqb::render_observables(ui, state),
+            },
+            ControlArea::Details => {
+                render_onboard_recording(ui, state.onboard_recording, language);
+                render_timeline(
+                    ui,
+                    state.timeline,
+                    state.journal,
+                    state.reference,
+                    state.deviation,
+                    state.filters,
+                );
+            }
+        },
+        Mode::LaunchControl(mode) => {
+            render_launch_control(
+                ui,
+                &mode,
+                &state.obg2,
+                state.stale,
+                state.launched,
+                state.privacy,
+                state.safety_key,
+                state.launch_window,
+                state.status.key_entry_expires_in,
+                language,
+            );
         }
-        Mode::RFSilence(state) => {
-            render_rf_silence(ui, state);
+        Mode::RFSilence(mode) => {
+            render_rf_silence(ui, mode, language);
         }
     }
 }
@@ -256,48 +468,238 @@ fn render_nrf_state(ui: &mut Ui, heard_of_since: Duration) {
     painter.circle_filled(center, rect.y * 0.8 * 0.5, color);
 }
 
-fn render_status<C: Connection, Id: Iterator<Item = usize>>(ui: &mut Ui, model: &Model<C, Id>) {
+// Above the timeline while starting or stopping
+fn render_onboard_recording(ui: &mut Ui, control: &OnboardRecording, language: Language) {
+    let text = match control {
+        OnboardRecording::Idle => return,
+        OnboardRecording::Confirm(true) => {
+            "Start onboard recording? Enter to confirm, Back to cancel"
+        }
+        OnboardRecording::Confirm(false) => {
+            "Stop onboard recording? Enter to confirm, Back to cancel"
+        }
+        OnboardRecording::Requested(_) | OnboardRecording::Sent { .. } => {
+            "Waiting for the node to report its recording state..."
+        }
+    };
+    ui.label(RichText::new(tr(language, text)).color(Color32::YELLOW));
+}
+
+type Label = (String, Option<Color32>);
+
+fn recording_labels(sinks: &[SinkStatus], language: Language) -> Vec<Label> {
+    if sinks.is_empty() {
+        return vec![(tr(language, "Not recording to file").into(), None)];
+    }
+    sinks
+        .iter()
+        .enumerate()
+        .map(|(index, status)| {
+            let kind = tr(language, if index == 0 { "Recording" } else { "Mirror" });
+            match &status.health {
+                SinkHealth::Healthy => (format!("{}: {}", kind, status.sink), None),
+                SinkHealth::Failed(reason) => (
+                    format!("{} FAILED: {} ({})", kind, status.sink, reason),
+                    Some(Color32::RED),
+                ),
+            }
+        })
+        .collect()
+}
+
+// Of the status bar between the mode and the nodes, None in the
+// default color
+fn status_labels(status: &StatusSnapshot) -> Vec<Label> {
+    let language = status.language;
+    let mut labels = vec![];
+    let mut notice = |text: String, color: Option<Color32>| labels.push((text, color));
+    if status.standby {
+        notice("STANDBY".into(), Some(Color32::LIGHT_BLUE));
+    }
+    if let Some((radio, port)) = &status.radio {
+        notice(format!("Radio: {} ({})", radio, port), None);
+    }
+    notice(format!("{}: {:?}", tr(language, "Gain"), status.gain), None);
+    notice(
+        format!(
+            "{}: {}",
+            tr(language, "Connected"),
+            status.uptime.map_or("--:--".to_string(), |duration| {
+                let seconds = duration.as_secs();
+                format!("{}:{:02}", seconds / 60, seconds % 60)
+            })
+        ),
+        None,
+    );
+    for (text, color) in recording_labels(&status.recording, language) {
+        notice(text, color);
+    }
+    if let Some(percentage) = status.ground_percentage {
+        let runtime = status
+            .ground_runtime
+            .map_or("--:--".to_string(), |runtime| {
+                let minutes = runtime.as_secs() / 60;
+                format!("{}:{:02}", minutes / 60, minutes % 60)
+            });
+        let text = format!("Ground: {:.0}% {}", percentage, runtime);
+        if status.ground_low {
+            notice(format!("{} LOW BATTERY", text), Some(Color32::RED));
+        } else {
+            notice(text, None);
+        }
+    }
+    if status.reconfigurations > 0 {
+        notice(
+            format!("E32 reconfigured {}x", status.reconfigurations),
+            Some(Color32::YELLOW),
+        );
+    }
+    if status.bursts > 0 || status.discarded_bytes > 0 {
+        notice(
+            format!(
+                "{} bursts, {} bytes discarded",
+                status.bursts, status.discarded_bytes
+            ),
+            (status.discarded_bytes > 0).then_some(Color32::YELLOW),
+        );
+    }
+    match status.subscription {
+        Some(health) if !health.connected => {
+            notice(
+                format!("Relay connecting ({})", health.attempts),
+                Some(Color32::YELLOW),
+            );
+        }
+        Some(health) if health.replaying => {
+            notice("Relay backfilling".into(), Some(Color32::YELLOW));
+        }
+        _ => {}
+    }
+    if let Some(duty_cycle) = status.duty_cycle {
+        let text = match duty_cycle.cap {
+            Some(cap) => format!(
+                "Duty cycle {:.1}/{:.0}%",
+                duty_cycle.usage * 100.0,
+                cap * 100.0
+            ),
+            None => format!("Duty cycle {:.1}%", duty_cycle.usage * 100.0),
+        };
+        if duty_cycle.throttled {
+            notice(format!("{} polls held", text), Some(Color32::YELLOW));
+        } else {
+            notice(text, None);
+        }
+    }
+    if status.auto_recording {
+        notice("Auto recording".into(), Some(Color32::LIGHT_GREEN));
+    }
+    if let Some(left) = status.holding {
+        notice(
+            format!("Backing off {:.1}s", left.as_secs_f32()),
+            Some(Color32::YELLOW),
+        );
+    }
+    if status.violations > 0 {
+        notice(
+            format!("{} protocol violations", status.violations),
+            Some(Color32::YELLOW),
+        );
+    }
+    if status.malformed_frames > 0 {
+        notice(
+            format!("{} malformed frames", status.malformed_frames),
+            Some(Color32::YELLOW),
+        );
+    }
+    if let Some(progress) = status.drain_progress {
+        notice(format!("Purging line: {}%", progress), None);
+    }
+    match &status.reload {
+        Some(ReloadOutcome::Applied(reload)) => {
+            if !reload.needs_reconnect.is_empty() {
+                notice(
+                    format!(
+                        "Config: reconnect for {}",
+                        reload.needs_reconnect.join(", ")
+                    ),
+                    Some(Color32::YELLOW),
+                );
+            }
+            if !reload.needs_restart.is_empty() {
+                notice(
+                    format!("Config: restart for {}", reload.needs_restart.join(", ")),
+                    Some(Color32::YELLOW),
+                );
+            }
+        }
+        Some(ReloadOutcome::Rejected(reason)) => {
+            notice(format!("Config rejected: {}", reason), Some(Color32::RED));
+        }
+        _ => {}
+    }
+    if !status.imu_mismatch.is_empty() {
+        let nodes: Vec<_> = status
+            .imu_mismatch
+            .iter()
+            .map(|node| node.to_string())
+            .collect();
+        notice(
+            format!("IMU ranges differ: {}", nodes.join(", ")),
+            Some(Color32::RED),
+        );
+    }
+    if !status.silent.is_empty() {
+        let nodes: Vec<_> = status.silent.iter().map(|node| node.to_string()).collect();
+        match status.silence_overridden {
+            true => notice(
+                format!("Radio silence overridden: {}", nodes.join(", ")),
+                Some(Color32::RED),
+            ),
+            false => notice(
+                format!("Radio silence: {}", nodes.join(", ")),
+                Some(Color32::LIGHT_BLUE),
+            ),
+        }
+    }
+    if status.overloaded {
+        notice("OVERLOAD".into(), Some(Color32::RED));
+    }
+    if status.faults {
+        notice("FAULTS".into(), Some(Color32::RED));
+    }
+    if let Some(reset_countdown) = status.auto_reset_in {
+        notice(
+            format!(
+                "{}: {}",
+                tr(language, "Automatic reset in"),
+                reset_countdown.as_secs()
+            ),
+            None,
+        );
+    }
+    labels
+}
+
+fn render_status(ui: &mut Ui, snapshot: &RenderSnapshot) {
     ui.horizontal(|ui| {
-        if model.mode.core_mode().is_failure() {
+        if snapshot.failure {
             ui.spinner();
         } else {
             render_alive(ui);
         };
-        ui.label(model.mode().name());
+        ui.label(tr(snapshot.language, snapshot.mode.name()));
+        if let Some(met_s) = snapshot.met_s {
+            ui.label(format!("T+{:.1}s", met_s));
+        }
         ui.label(format!("E32 baud rate: {:?}", modem_baud_rate()));
-        ui.label(format!(
-            "Gain: {:?}",
-            match model.adc_gain {
-                AdcGain::Gain1 => 1,
-                AdcGain::Gain2 => 2,
-                AdcGain::Gain4 => 4,
-                AdcGain::Gain8 => 8,
-                AdcGain::Gain16 => 16,
-                AdcGain::Gain32 => 32,
-                AdcGain::Gain64 => 64,
-            }
-        ));
-        ui.label(format!(
-            "Connected: {}",
-            model.uptime().map_or("--:--".to_string(), |duration| {
-                let seconds = duration.as_secs();
-                format!("{}:{:02}", seconds / 60, seconds % 60)
-            })
-        ));
-        ui.label(
-            model
-                .recorder_path
-                .clone()
-                .map_or("Not recording to file".to_string(), |path| {
-                    format!("Recording: {:?}", path)
-                }),
-        );
-        if let Some(reset_countdown) = model.auto_reset_in() {
-            ui.label(format!("Automatic reset in: {}", reset_countdown.as_secs()));
+        for (text, color) in status_labels(&snapshot.status) {
+            match color {
+                Some(color) => ui.label(RichText::new(text).color(color)),
+                None => ui.label(text),
+            };
         }
-        for node in model.registered_nodes() {
-            let heard_of_since = model.heard_from_since(&node);
-            let name = match node {
+        for (node, heard_of_since, module_status) in snapshot.status.nodes.iter() {
+            let name = match *node {
                 crate::rqprotocol::Node::RedQueen(id) => {
                     let buf = [b'R', b'Q', id];
                     unsafe { std::str::from_utf8_unchecked(&buf) }.to_string()
@@ -309,8 +711,18 @@ fn render_status<C: Connection, Id: Iterator<Item = usize>>(ui: &mut Ui, model:
                 crate::rqprotocol::Node::LaunchControl => "LNC".to_string(),
             };
             ui.label(name);
-            render_nrf_state(ui, heard_of_since);
+            match module_status {
+                Some(ModuleStatus::NoModule) => {
+                    ui.label(RichText::new("NO HW").color(Color32::GRAY));
+                }
+                _ => render_nrf_state(ui, *heard_of_since),
+            }
         }
+        // In the corner, to match the recordings of the run
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            ui.label(RichText::new(snapshot.run.slug()).monospace())
+                .on_hover_text(snapshot.run.to_string());
+        });
     });
 }
 
@@ -335,12 +747,11 @@ fn color_frame(color: Color32, padding: f32) -> Frame {
     }
 }
 
-fn status_background_frame<C: Connection, IdGenerator: Iterator<Item = usize>>(
-    ui: &mut Ui,
-    model: &Model<C, IdGenerator>,
-) -> Frame {
+fn status_background_frame(ui: &mut Ui, snapshot: &RenderSnapshot) -> Frame {
     let id = Id::new("status_background_frame");
-    let how_connected = ui.ctx().animate_bool_with_time(id, !model.connected(), 0.5);
+    let how_connected = ui
+        .ctx()
+        .animate_bool_with_time(id, !snapshot.connected, 0.5);
 
     let gradient = Gradient::new(vec![
         kind_color(Kind::Status, Intensity::Low),
@@ -360,7 +771,11 @@ fn status_background_frame<C: Connection, IdGenerator: Iterator<Item = usize>>(
 }
 
 pub fn render<C: Connection, Id: Iterator<Item = usize>>(ui: &mut Ui, model: &Model<C, Id>) {
-    let tabs_active = match model.control {
+    render_snapshot(ui, &RenderSnapshot::of(model));
+}
+
+pub fn render_snapshot(ui: &mut Ui, snapshot: &RenderSnapshot) {
+    let tabs_active = match snapshot.control {
         ControlArea::Tabs => true,
         ControlArea::Details => false,
     };
@@ -371,25 +786,84 @@ pub fn render<C: Connection, Id: Iterator<Item = usize>>(ui: &mut Ui, model: &Mo
         .min_height(ui.spacing().interact_size.y * 2.0)
         .show_inside(ui, |ui| {
             ui.vertical_centered(|ui| {
-                render_header(ui, model);
+                render_header(ui, snapshot);
+                if let Some(status) = snapshot.launch_window {
+                    render_launch_window(ui, status, snapshot.gps_fix);
+                }
             });
         });
     egui::TopBottomPanel::bottom("bottom_panel")
         .resizable(false)
         .show_separator_line(false)
         .min_height(ui.spacing().interact_size.y * 2.0)
-        .frame(status_background_frame(ui, model))
+        .frame(status_background_frame(ui, snapshot))
         .show_inside(ui, |ui| {
             ui.vertical_centered(|ui| {
-                render_status(ui, model);
+                render_status(ui, snapshot);
             });
         });
     egui::CentralPanel::default()
         .frame(color_frame(
-            kind_color32(kind_for_mode(model.mode()), intensity(!tabs_active)),
+            kind_color32(kind_for_mode(&snapshot.mode), intensity(!tabs_active)),
             0.0,
         ))
         .show_inside(ui, |ui| {
-            render_body(ui, model);
+            render_body(ui, snapshot);
         });
+    if snapshot.transitions.visible {
+        render_transitions(ui, snapshot.transitions, &snapshot.mode, snapshot.now);
+    }
+    if snapshot.loop_timing.visible {
+        render_loop_timing(ui, snapshot.loop_timing);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Reload;
+    use crate::rqprotocol::Node;
+
+    #[test]
+    fn test_status_labels_of_a_snapshot() {
+        let calm = StatusSnapshot::default();
+        assert_eq!(
+            status_labels(&calm),
+            vec![
+                ("Gain: 0".into(), None),
+                ("Connected: --:--".into(), None),
+                ("Not recording to file".into(), None),
+            ]
+        );
+        let troubled = StatusSnapshot {
+            uptime: Some(Duration::from_secs(125)),
+            ground_percentage: Some(12.0),
+            ground_low: true,
+            violations: 2,
+            reload: Some(ReloadOutcome::Applied(Reload {
+                applied: vec![],
+                needs_reconnect: vec!["port"],
+                needs_restart: vec!["relay_server_key"],
+            })),
+            silent: vec![Node::RedQueen(b'B')],
+            overloaded: true,
+            ..calm
+        };
+        let labels = status_labels(&troubled);
+        assert_eq!(labels[1], ("Connected: 2:05".into(), None));
+        assert_eq!(
+            labels[3..],
+            [
+                ("Ground: 12% --:-- LOW BATTERY".into(), Some(Color32::RED)),
+                ("2 protocol violations".into(), Some(Color32::YELLOW)),
+                ("Config: reconnect for port".into(), Some(Color32::YELLOW)),
+                (
+                    "Config: restart for relay_server_key".into(),
+                    Some(Color32::YELLOW)
+                ),
+                ("Radio silence: RQB".into(), Some(Color32::LIGHT_BLUE)),
+                ("OVERLOAD".into(), Some(Color32::RED)),
+            ]
+        );
+    }
 }
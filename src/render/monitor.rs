@@ -0,0 +1,130 @@
+use egui::{RichText, Ui};
+use epaint::Color32;
+
+use std::time::Duration;
+
+use crate::{
+    backoff::BackoffStats, clock::Instant, invariants::Invariants, layout::scaling::monospace,
+    monitor::Monitor,
+};
+
+use super::text_color;
+
+const MONITOR_ROWS: usize = 16;
+const VIOLATION_ROWS: usize = 4;
+
+fn filter_text<T: ToString>(filter: &Option<T>) -> String {
+    filter.as_ref().map_or("all".into(), |f| f.to_string())
+}
+
+// Of the command link, holding is what's left of the current backoff
+fn backoff_text(backoff: &BackoffStats, holding: Option<Duration>) -> String {
+    let current = match holding {
+        Some(left) => format!("holding {:.1}s", left.as_secs_f32()),
+        None => "not holding".into(),
+    };
+    format!(
+        "Backoff: {}, {} failed in a row, {} backoffs, {:.1}s held, longest {:.1}s",
+        current,
+        backoff.failures,
+        backoff.backoffs,
+        backoff.held.as_secs_f32(),
+        backoff.longest.as_secs_f32()
+    )
+}
+
+pub fn render_monitor(
+    ui: &mut Ui,
+    monitor: &Monitor,
+    invariants: &Invariants,
+    backoff: &BackoffStats,
+    holding: Option<Duration>,
+    now: Instant,
+) {
+    let mono_font = monospace(ui, 14.0);
+    ui.vertical(|ui| {
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new("Monitor")
+                    .font(monospace(ui, 32.0))
+                    .color(text_color(false)),
+            );
+            ui.label(
+                RichText::new(format!(
+                    "Talker: {}  Type: {}  Invalid: {}",
+                    filter_text(&monitor.talker),
+                    filter_text(&monitor.kind),
+                    monitor.invalid()
+                ))
+                .color(text_color(false))
+                .heading(),
+            );
+        });
+        ui.label(
+            RichText::new(backoff_text(backoff, holding))
+                .color(match holding {
+                    Some(_) => Color32::YELLOW,
+                    None => text_color(false),
+                })
+                .heading(),
+        );
+        ui.label(
+            RichText::new("Left/Right: talker, Enter: type, Back: leave")
+                .color(text_color(true))
+                .heading(),
+        );
+        ui.separator();
+        let traffic: Vec<_> = monitor.filtered().collect();
+        let skip = traffic.len().saturating_sub(MONITOR_ROWS);
+        egui::Grid::new("monitor").striped(true).show(ui, |ui| {
+            for traffic in traffic.into_iter().skip(skip) {
+                ui.label(
+                    RichText::new(traffic.at.format("%H:%M:%S%.3f").to_string())
+                        .font(mono_font.clone())
+                        .color(Color32::GRAY),
+                );
+                ui.label(
+                    RichText::new(traffic.talker.to_string())
+                        .font(mono_font.clone())
+                        .color(Color32::WHITE),
+                );
+                ui.label(
+                    RichText::new(&traffic.sentence)
+                        .font(mono_font.clone())
+                        .color(Color32::WHITE),
+                );
+                ui.end_row();
+                // Below the sentence it's about
+                if let Some(failure) = &traffic.failure {
+                    ui.label("");
+                    ui.label("");
+                    ui.label(
+                        RichText::new(format!("Parse error {}", failure))
+                            .font(mono_font.clone())
+                            .color(Color32::RED),
+                    );
+                    ui.end_row();
+                }
+            }
+        });
+        if invariants.count() > 0 {
+            ui.separator();
+            ui.label(
+                RichText::new(format!("Protocol violations: {}", invariants.count()))
+                    .color(Color32::RED)
+                    .heading(),
+            );
+            for logged in invariants.violations().take(VIOLATION_ROWS) {
+                ui.label(
+                    RichText::new(format!(
+                        "-{:5.1}s {}",
+                        now.duration_since(logged.at).as_secs_f32(),
+                        logged.violation.description()
+                    ))
+                    .font(mono_font.clone())
+                    .color(Color32::WHITE),
+                );
+            }
+        }
+    });
+}
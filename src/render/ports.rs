@@ -0,0 +1,49 @@
+use egui::{RichText, Ui};
+use epaint::Color32;
+
+use crate::{layout::scaling::monospace, ports::PortPicker};
+
+use super::text_color;
+
+// The port in use is marked
+pub fn render_ports(ui: &mut Ui, picker: &PortPicker, current: &str) {
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new("Radio port")
+                .font(monospace(ui, 48.0))
+                .color(text_color(false)),
+        );
+        if picker.ports().is_empty() {
+            ui.label(
+                RichText::new("No serial ports found, plug in the radio and open again")
+                    .color(Color32::RED)
+                    .heading(),
+            );
+        }
+        egui::Grid::new("ports").striped(false).show(ui, |ui| {
+            for (index, port) in picker.ports().iter().enumerate() {
+                let name = match port.name == current {
+                    true => format!("{} (in use)", port.name),
+                    false => port.name.clone(),
+                };
+                ui.label(
+                    RichText::new(name)
+                        .color(text_color(index == picker.selected))
+                        .heading(),
+                );
+                ui.label(
+                    RichText::new(&port.description)
+                        .color(text_color(false))
+                        .heading(),
+                );
+                ui.end_row();
+            }
+        });
+        ui.separator();
+        ui.label(
+            RichText::new("Enter to use the port, remembered on this machine")
+                .color(text_color(true))
+                .heading(),
+        );
+    });
+}
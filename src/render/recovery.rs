@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use egui::{RichText, Ui};
+
+use crate::{
+    layout::scaling::monospace,
+    recovery::{Recovery, SILENCE},
+};
+
+use super::{freshness, text_color};
+
+fn optional(value: Option<f32>, unit: &str) -> String {
+    value.map_or("-".into(), |value| format!("{:.1}{}", value, unit))
+}
+
+pub fn render_recovery(ui: &mut Ui, recovery: &Recovery, elapsed: Duration) {
+    let mono_font = monospace(ui, 24.0);
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new("Recovery")
+                .font(monospace(ui, 48.0))
+                .color(text_color(false)),
+        );
+        let tracks = recovery.tracks();
+        if tracks.is_empty() {
+            ui.label(
+                RichText::new("No telemetry received yet")
+                    .color(text_color(false))
+                    .heading(),
+            );
+        }
+        egui::Grid::new("recovery").striped(true).show(ui, |ui| {
+            for header in [
+                "Node",
+                "Last heard",
+                "Altitude",
+                "Descent",
+                "Phase",
+                "Battery",
+            ] {
+                ui.label(RichText::new(header).color(text_color(false)).heading());
+            }
+            ui.end_row();
+            for (node, track) in tracks {
+                let silence = recovery.silence(track, elapsed);
+                let stale = silence >= SILENCE;
+                let phase = track
+                    .phase
+                    .as_ref()
+                    .map_or("-".into(), |phase| format!("{:?}", phase));
+                for text in [
+                    node.to_string(),
+                    format!("{:.0}s ago", silence.as_secs_f32()),
+                    optional(track.altitude, "m"),
+                    optional(track.descent_rate, "m/s"),
+                    phase,
+                    optional(track.battery_voltage, "V"),
+                ] {
+                    ui.label(freshness(
+                        RichText::new(text)
+                            .font(mono_font.clone())
+                            .color(text_color(false)),
+                        stale,
+                    ));
+                }
+                ui.end_row();
+            }
+        });
+        ui.separator();
+        ui.label(
+            RichText::new("Altitude above the first reading, there is no GPS yet")
+                .color(text_color(false))
+                .heading(),
+        );
+        ui.label(
+            RichText::new("Back: leave")
+                .color(text_color(true))
+                .heading(),
+        );
+    });
+}
@@ -1,10 +1,14 @@
 use egui::{ProgressBar, Sense, Ui};
 use emath::Align2;
-use epaint::{Color32, FontId};
+use epaint::Color32;
 use palette::{Gradient, LinSrgb};
 
 use crate::{
-    layout::colors::{color32, kind_color32, Intensity, Kind},
+    i18n::{tr, Language},
+    layout::{
+        colors::{color32, kind_color32, Intensity, Kind},
+        scaling::monospace,
+    },
     model::RFSilenceMode,
 };
 
@@ -26,10 +30,10 @@ fn render_progress(ui: &mut Ui, state: &RFSilenceMode) {
     ui.add(pbar);
 }
 
-fn render_header_text(ui: &mut Ui, state: RFSilenceMode) {
-    let digit_font = FontId::new(48.0, egui::FontFamily::Monospace);
+fn render_header_text(ui: &mut Ui, state: RFSilenceMode, language: Language) {
+    let digit_font = monospace(ui, 48.0);
     let painter = ui.painter();
-    let text = "Press Enter to enter RF Silence!";
+    let text = tr(language, "Press Enter to enter RF Silence!");
     let galley = painter.layout_no_wrap(text.into(), digit_font.clone(), Color32::RED);
     let rect = galley.size();
     let (response, painter) = ui.allocate_painter(rect.into(), Sense::hover());
@@ -47,7 +51,7 @@ fn render_header_text(ui: &mut Ui, state: RFSilenceMode) {
     render_progress(ui, &state);
 }
 
-pub fn render_rf_silence(ui: &mut Ui, state: RFSilenceMode) {
+pub fn render_rf_silence(ui: &mut Ui, state: RFSilenceMode, language: Language) {
     ui.horizontal(|ui| {
         egui::SidePanel::left("rf_silence")
             .resizable(false)
@@ -56,7 +60,7 @@ pub fn render_rf_silence(ui: &mut Ui, state: RFSilenceMode) {
             .exact_width(ui.available_width())
             .show_inside(ui, |ui| {
                 ui.vertical(|ui| {
-                    render_header_text(ui, state);
+                    render_header_text(ui, state, language);
                 });
             })
     });
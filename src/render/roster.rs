@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use egui::{RichText, Ui};
+use epaint::Color32;
+
+use crate::{
+    layout::scaling::monospace,
+    rqprotocol::Node,
+    telemetry::{roster::ChannelConfig, ModuleStatus},
+};
+
+use super::text_color;
+
+// Heard from since and the module, of the registered nodes
+pub fn render_roster(
+    ui: &mut Ui,
+    roster: &[ChannelConfig],
+    nodes: &[(Node, Duration, Option<ModuleStatus>)],
+    relayed: bool,
+) {
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new("Telemetry roster")
+                .font(monospace(ui, 48.0))
+                .color(text_color(false)),
+        );
+        egui::Grid::new("roster").striped(false).show(ui, |ui| {
+            for entry in roster.iter() {
+                ui.label(
+                    RichText::new(entry.node.to_string())
+                        .color(text_color(false))
+                        .heading(),
+                );
+                ui.label(
+                    RichText::new(format!(
+                        "Channel {} ({}MHz)",
+                        entry.channel,
+                        2400 + entry.channel as u32
+                    ))
+                    .color(text_color(false))
+                    .heading(),
+                );
+                let (text, color) = match nodes.iter().find(|(node, ..)| *node == entry.node) {
+                    Some((_, since, Some(ModuleStatus::Receiving))) => (
+                        format!("heard {:.1}s ago", since.as_secs_f32()),
+                        Color32::GREEN,
+                    ),
+                    Some((_, _, Some(ModuleStatus::NoModule))) => {
+                        ("no module".into(), Color32::RED)
+                    }
+                    _ => ("not heard yet".into(), Color32::DARK_GRAY),
+                };
+                ui.label(RichText::new(text).color(color).heading());
+                ui.end_row();
+            }
+        });
+        ui.separator();
+        let text = match relayed {
+            true => "Relayed, the channels are those of the receiver",
+            false => "Set in the config file, the modules are set up again on reload",
+        };
+        ui.label(RichText::new(text).color(text_color(false)).heading());
+        ui.label(
+            RichText::new("Back to leave")
+                .color(text_color(true))
+                .heading(),
+        );
+    });
+}
@@ -1,81 +1,170 @@
 use epaint::Color32;
 use std::time::Duration;
-use uom::si::{
-    f64::{Force, Pressure},
-    force::kilonewton,
-    pressure::bar,
-};
+use uom::si::f64::{Force, Pressure};
 
 use egui::{
-    plot::{Legend, Line, Plot, PlotPoints},
+    plot::{Legend, Line, Plot, PlotBounds, PlotPoints, PlotUi},
     RichText, Ui,
 };
 
-use crate::observables::rqa::{ObservablesGroup1, ObservablesGroup2, RecordingState};
+use crate::freshness::Stale;
+use crate::layout::numeric::{NumericDisplay, Trend};
+use crate::observables::{
+    filter::{FilterKind, Filters},
+    groups::{ObservablesGroup2, RecordingState},
+    history::{Obg1History, Observable},
+    range::{format_tick, PlotRanges},
+    tare::Tare,
+    units::Units,
+};
+
+use super::{clear_frame, freshness, text_color, timeline::filtered_name};
 
-use super::{clear_frame, text_color};
+// Of the reference screen, about a heading
+const NUMERIC_HEIGHT: f32 = 28.0;
+const THRUST_DEADBAND_KN: f64 = 0.01;
+const PRESSURE_DEADBAND_HPA: f64 = 10.0;
 
-fn render_uptime(ui: &mut Ui, uptime: Duration) {
+fn render_uptime(ui: &mut Ui, uptime: Duration, stale: bool) {
     let secs = uptime.as_secs_f64();
-    ui.label(
+    ui.label(freshness(
         RichText::new(format!("{:.2}", secs))
             .color(text_color(false))
             .heading(),
-    );
+        stale,
+    ));
 }
 
-fn render_thrust(ui: &mut Ui, thrust: Force) {
-    ui.label(
-        RichText::new(format!(
-            "{:.8}kN",
-            thrust.get::<uom::si::force::kilonewton>()
-        ))
+// Of the last two samples, changes within the deadband are steady
+fn trend(obg1: &Obg1History, observable: Observable, deadband: f64) -> Option<Trend> {
+    let mut recent = obg1.recent().rev();
+    let current = observable.value(recent.next()?);
+    let previous = observable.value(recent.next()?);
+    Some(Trend::of(previous, current, deadband))
+}
+
+fn render_thrust(ui: &mut Ui, obg1: &Obg1History, thrust: Force, units: Units, stale: bool) {
+    let (thrust, unit) = units.thrust(thrust);
+    NumericDisplay::new(format!("{:.8}", thrust), unit)
+        .trend(trend(obg1, Observable::Thrust, THRUST_DEADBAND_KN))
+        .stale(stale)
         .color(text_color(false))
-        .heading(),
-    );
+        .show(ui, NUMERIC_HEIGHT);
 }
 
-fn render_pressure(ui: &mut Ui, pressure: Pressure) {
-    ui.label(
-        RichText::new(format!("{:.6}bar", pressure.get::<bar>()))
-            .color(text_color(false))
-            .heading(),
-    );
+fn render_pressure(ui: &mut Ui, obg1: &Obg1History, pressure: Pressure, units: Units, stale: bool) {
+    let (pressure, unit) = units.pressure(pressure);
+    NumericDisplay::new(format!("{:.6}", pressure), unit)
+        .trend(trend(obg1, Observable::Pressure, PRESSURE_DEADBAND_HPA))
+        .stale(stale)
+        .color(text_color(false))
+        .show(ui, NUMERIC_HEIGHT);
+}
+
+fn render_tare(ui: &mut Ui, tare: &Tare) {
+    let text = match &tare.offset {
+        Some(offset) => format!(
+            "{:.1}/{:.1} at {}",
+            offset.thrust,
+            offset.pressure,
+            offset.taken_at.format("%H:%M:%S")
+        ),
+        None => "Press Enter to tare".to_string(),
+    };
+    ui.label(RichText::new(text).heading().color(Color32::WHITE));
 }
 
-fn render_recording_state(ui: &mut Ui, recording_state: &RecordingState) {
+fn render_recording_state(ui: &mut Ui, recording_state: &RecordingState, stale: bool) {
     let (text, color) = match &recording_state {
         RecordingState::Unknown => ("Unknown".to_string(), Color32::DARK_GRAY),
         RecordingState::Error(text) => (text.clone(), Color32::RED),
         RecordingState::Pause => ("Pause".to_string(), Color32::DARK_GRAY),
         RecordingState::Recording(filename) => (filename.clone(), Color32::WHITE),
     };
-    ui.label(RichText::new(text).heading().color(color));
+    ui.label(freshness(RichText::new(text).heading().color(color), stale));
 }
 
-pub fn render_pyro_state(ui: &mut Ui, pyro_status: Option<PyroStatus>, height: f32) {
-    let rect = Vec2::new(ui.available_width(), height);
-    let (_response, painter) = ui.allocate_painter(rect.into(), Sense::hover());
-    let center = painter.clip_rect().center();
-    painter.circle_filled(center, height * 1.0 * 0.5, Color32::BLACK);
-    painter.circle_filled(
-        center,
-        height * 0.9 * 0.5,
-        match pyro_status {
-            Some(pyro_status) => match pyro_status {
-                PyroStatus::Unknown => Color32::DARK_GRAY,
-                PyroStatus::Open => Color32::RED,
-                PyroStatus::Closed => Color32::GREEN,
-            },
-            None => Color32::DARK_GRAY,
-        },
+// Renders the downsampled history as min/max envelope together
+// with the mean, continued by the full-rate recent samples.
+// Only the mean is filtered, the envelope shows the raw spread.
+fn plot_history(
+    plot_ui: &mut PlotUi,
+    obg1: &Obg1History,
+    observable: Observable,
+    name: &str,
+    kind: FilterKind,
+    filtered: Vec<[f64; 2]>,
+) {
+    if obg1.len() < 2 {
+        return;
+    }
+    let (min, max) = obg1.envelope(observable);
+    for envelope in [min, max] {
+        plot_ui.line(
+            Line::new(PlotPoints::from(envelope))
+                .color(Color32::from_rgb(50, 75, 125))
+                .style(egui::plot::LineStyle::Solid),
+        );
+    }
+    plot_ui.line(
+        Line::new(PlotPoints::from(filtered))
+            .color(Color32::from_rgb(100, 150, 250))
+            .style(egui::plot::LineStyle::Solid)
+            .name(filtered_name(name, kind)),
     );
 }
 
+// With the vertical range of the filtered values, if there's one to
+// keep, and the unit at the ticks
+fn observable_plot(
+    ui: &mut Ui,
+    obg1: Option<&Obg1History>,
+    observable: Observable,
+    name: &str,
+    filters: &Filters,
+    plot_ranges: &PlotRanges,
+    gain: u8,
+) {
+    let kind = match observable {
+        Observable::Thrust => filters.thrust,
+        Observable::Pressure => filters.pressure,
+    };
+    // Once a frame, for the line and its range
+    let filtered = obg1.map_or(vec![], |obg1| {
+        filters.apply(kind, obg1.points(observable), obg1.sample_rate())
+    });
+    let values: Vec<f64> = filtered.iter().map(|point| point[1]).collect();
+    let y_range = plot_ranges.y_range(observable, &values, gain);
+    let x_range = match (filtered.first(), filtered.last()) {
+        (Some(first), Some(last)) if first[0] < last[0] => Some((first[0], last[0])),
+        _ => None,
+    };
+    let unit = observable.unit();
+    let plot = Plot::new(format!("{}_plot", name.to_lowercase()))
+        .legend(Legend::default())
+        .y_axis_formatter(move |value, range| format_tick(value, range, unit));
+    plot.show(ui, |plot_ui| {
+        if let Some(obg1) = obg1 {
+            plot_history(plot_ui, obg1, observable, name, kind, filtered);
+        }
+        // Every frame, a drag or zoom doesn't stick
+        if let (Some((start, end)), Some((min, max))) = (x_range, y_range) {
+            plot_ui.set_plot_bounds(PlotBounds::from_min_max([start, min], [end, max]));
+        }
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn render_observables(
     ui: &mut Ui,
-    obg1: &Vec<ObservablesGroup1>,
+    obg1: Option<&Obg1History>,
     obg2: &Option<ObservablesGroup2>,
+    stale: Stale,
+    tare: &Tare,
+    filters: &Filters,
+    plot_ranges: &PlotRanges,
+    gain: u8,
+    units: Units,
 ) {
     ui.vertical(|ui| {
         ui.horizontal(|ui| {
@@ -92,8 +181,8 @@ pub fn render_observables(
                             .heading(),
                     );
                 });
-            if let Some(obg1) = obg1.last() {
-                render_uptime(ui, obg1.uptime);
+            if let Some(obg1) = obg1.and_then(Obg1History::last) {
+                render_uptime(ui, obg1.uptime, stale.obg1);
             }
         });
         ui.horizontal(|ui| {
@@ -106,8 +195,8 @@ pub fn render_observables(
                 .show_inside(ui, |ui| {
                     ui.label(RichText::new("Thrust").color(text_color(false)).heading());
                 });
-            if let Some(obg1) = obg1.last() {
-                render_thrust(ui, obg1.thrust);
+            if let Some((history, obg1)) = obg1.and_then(|h| Some((h, h.last()?))) {
+                render_thrust(ui, history, obg1.thrust, units, stale.obg1);
             }
         });
         ui.horizontal(|ui| {
@@ -120,10 +209,22 @@ pub fn render_observables(
                 .show_inside(ui, |ui| {
                     ui.label(RichText::new("Pressure").color(text_color(false)).heading());
                 });
-            if let Some(obg1) = obg1.last() {
-                render_pressure(ui, obg1.pressure);
+            if let Some((history, obg1)) = obg1.and_then(|h| Some((h, h.last()?))) {
+                render_pressure(ui, history, obg1.pressure, units, stale.obg1);
             }
         });
+        ui.horizontal(|ui| {
+            egui::SidePanel::left("tare")
+                .resizable(false)
+                .show_separator_line(false)
+                .frame(clear_frame())
+                .resizable(false)
+                .exact_width(ui.available_width() / 5.0)
+                .show_inside(ui, |ui| {
+                    ui.label(RichText::new("Tare").color(text_color(false)).heading());
+                });
+            render_tare(ui, tare);
+        });
         ui.horizontal(|ui| {
             egui::SidePanel::left("recording")
                 .resizable(false)
@@ -139,7 +240,7 @@ pub fn render_observables(
                     );
                 });
             if let Some(obg2) = obg2 {
-                render_recording_state(ui, &obg2.recording_state);
+                render_recording_state(ui, &obg2.recording_state, stale.obg2);
             }
         });
         ui.horizontal(|ui| {
@@ -156,14 +257,16 @@ pub fn render_observables(
                             .heading(),
                     );
                 });
-            ui.label(
+            ui.label(freshness(
                 RichText::new(
-                    obg2.clone()
-                        .map_or("--".to_string(), |obg2| format!("{}", obg2.records)),
+                    obg2.as_ref()
+                        .and_then(|obg2| obg2.records)
+                        .map_or("--".to_string(), |records| records.to_string()),
                 )
                 .heading()
                 .color(Color32::WHITE),
-            );
+                stale.obg2,
+            ));
         });
         ui.horizontal(|ui| {
             egui::SidePanel::left("anomalies")
@@ -179,14 +282,16 @@ pub fn render_observables(
                             .heading(),
                     );
                 });
-            ui.label(
+            ui.label(freshness(
                 RichText::new(
-                    obg2.clone()
-                        .map_or("--".to_string(), |obg2| format!("{}", obg2.anomalies)),
+                    obg2.as_ref()
+                        .and_then(|obg2| obg2.anomalies)
+                        .map_or("--".to_string(), |anomalies| anomalies.to_string()),
                 )
                 .heading()
                 .color(Color32::WHITE),
-            );
+                stale.obg2,
+            ));
         });
         egui::SidePanel::left("thrust_plot")
             .resizable(false)
@@ -195,30 +300,15 @@ pub fn render_observables(
             .resizable(false)
             .exact_width(ui.available_width() / 2.0)
             .show_inside(ui, |ui| {
-                let plot = Plot::new("thrust_plot").legend(Legend::default());
-                let mut plot_points = PlotPoints::default();
-                if obg1.len() >= 2 {
-                    let start = obg1.first().unwrap().uptime;
-                    let points: Vec<[f64; 2]> = obg1
-                        .iter()
-                        .map(|item| {
-                            [
-                                (item.uptime - start).as_secs_f64(),
-                                item.thrust.get::<kilonewton>(),
-                            ]
-                        })
-                        .collect();
-                    plot_points = points.into();
-                }
-                plot.show(ui, |plot_ui| {
-                    plot_ui.line(
-                        Line::new(plot_points)
-                            .color(Color32::from_rgb(100, 150, 250))
-                            .style(egui::plot::LineStyle::Solid)
-                            .name("Thrust"),
-                    );
-                })
-                .response
+                observable_plot(
+                    ui,
+                    obg1,
+                    Observable::Thrust,
+                    "Thrust",
+                    filters,
+                    plot_ranges,
+                    gain,
+                );
             });
         egui::SidePanel::left("pressure_plot")
             .resizable(false)
@@ -227,30 +317,15 @@ pub fn render_observables(
             .resizable(false)
             .exact_width(ui.available_width())
             .show_inside(ui, |ui| {
-                let plot = Plot::new("pressure_plot").legend(Legend::default());
-                let mut plot_points = PlotPoints::default();
-                if obg1.len() >= 2 {
-                    let start = obg1.first().unwrap().uptime;
-                    let points: Vec<[f64; 2]> = obg1
-                        .iter()
-                        .map(|item| {
-                            [
-                                (item.uptime - start).as_secs_f64(),
-                                item.pressure.get::<uom::si::pressure::hectopascal>(),
-                            ]
-                        })
-                        .collect();
-                    plot_points = points.into();
-                }
-                plot.show(ui, |plot_ui| {
-                    plot_ui.line(
-                        Line::new(plot_points)
-                            .color(Color32::from_rgb(100, 150, 250))
-                            .style(egui::plot::LineStyle::Solid)
-                            .name("Pressure"),
-                    );
-                })
-                .response
+                observable_plot(
+                    ui,
+                    obg1,
+                    Observable::Pressure,
+                    "Pressure",
+                    filters,
+                    plot_ranges,
+                    gain,
+                );
             });
     });
 }
@@ -1,17 +1,24 @@
+use std::collections::{HashMap, VecDeque};
+
 use emath::Vec2;
 use epaint::{Color32, Shadow};
 
 use egui::{Frame, Id, RichText, Sense, Ui};
 
 use crate::{
-    connection::Connection,
-    model::Model,
-    observables::rqb::PyroStatus,
+    freshness::Stale,
+    observables::{
+        groups::{Breakwire, LaunchDetect, ObservablesGroup2, PyroStatus, RecordingState},
+        history::Obg1History,
+    },
     rqprotocol::Node,
-    telemetry::parser::rq2::{IMUPacket, IgnitionSMState, TelemetryData},
+    telemetry::{
+        parser::rq2::{IMUPacket, StatePacket, TelemetryData},
+        retention::Stored,
+    },
 };
 
-use super::{clear_frame, text_color};
+use super::{clear_frame, freshness, snapshot::RenderSnapshot, text_color};
 
 pub fn render_pyro_state(ui: &mut Ui, pyro_status: Option<PyroStatus>, height: f32) {
     let rect = Vec2::new(ui.available_width(), height);
@@ -36,22 +43,42 @@ fn dark_label(ui: &mut Ui, text: &str) {
     ui.label(RichText::new(text).color(text_color(false)).heading());
 }
 
-fn flatten_data(data: Option<&Vec<TelemetryData>>) -> (Option<IMUPacket>, Option<IgnitionSMState>) {
+fn observable_label(ui: &mut Ui, text: &str, stale: bool) {
+    ui.label(freshness(
+        RichText::new(text).color(text_color(false)).heading(),
+        stale,
+    ));
+}
+
+fn flatten_data(data: Option<&VecDeque<Stored>>) -> (Option<IMUPacket>, Option<StatePacket>) {
     let mut imu = None;
-    let mut ism = None;
+    let mut state = None;
     if let Some(data) = data {
-        for packet in data {
-            match packet {
-                TelemetryData::Ignition(d) => {
-                    ism = Some(d.clone());
+        // The latest of each kind
+        for stored in data.iter().rev() {
+            match &stored.data {
+                TelemetryData::State(d) if state.is_none() => {
+                    state = Some(d.clone());
                 }
-                TelemetryData::IMU(d) => {
+                TelemetryData::IMU(d) if imu.is_none() => {
                     imu = Some(d.clone());
                 }
+                _ => {}
+            }
+            if imu.is_some() && state.is_some() {
+                break;
             }
         }
     }
-    (imu, ism)
+    (imu, state)
+}
+
+fn continuity(closed: bool) -> &'static str {
+    if closed {
+        "closed"
+    } else {
+        "open"
+    }
 }
 
 fn render_vector(ui: &mut Ui, id: Id, prefix: &str, v: (f32, f32, f32)) {
@@ -66,8 +93,8 @@ fn render_vector(ui: &mut Ui, id: Id, prefix: &str, v: (f32, f32, f32)) {
         });
 }
 
-fn render_redqueen(ui: &mut Ui, name: &str, node: Node, data: Option<&Vec<TelemetryData>>) {
-    let (imu_data, ignition_sm_state) = flatten_data(data);
+fn render_redqueen(ui: &mut Ui, name: &str, node: Node, data: Option<&VecDeque<Stored>>) {
+    let (imu_data, state_packet) = flatten_data(data);
     let base_id: Id = name.to_string().into();
 
     egui::Grid::new(base_id.with("outer grid"))
@@ -76,8 +103,26 @@ fn render_redqueen(ui: &mut Ui, name: &str, node: Node, data: Option<&Vec<Teleme
             dark_label(ui, name);
             ui.end_row();
             dark_label(ui, "State");
-            if let Some(state) = ignition_sm_state {
-                dark_label(ui, &format!("{:?}", state));
+            if let Some(state) = &state_packet {
+                dark_label(ui, &format!("{:?}", state.ignition));
+            }
+            ui.end_row();
+            dark_label(ui, "Phase");
+            if let Some(state) = &state_packet {
+                dark_label(ui, &format!("{:?}", state.phase));
+            }
+            ui.end_row();
+            dark_label(ui, "Batt");
+            if let Some(state) = &state_packet {
+                dark_label(ui, &format!("{:1.2}V", state.battery_voltage));
+            }
+            ui.end_row();
+            dark_label(ui, "Pyro");
+            if let Some(state) = &state_packet {
+                ui.horizontal(|ui| {
+                    dark_label(ui, &format!("12:{}", continuity(state.continuity.pyro12())));
+                    dark_label(ui, &format!("34:{}", continuity(state.continuity.pyro34())));
+                });
             }
             ui.end_row();
             dark_label(ui, "Acc");
@@ -110,6 +155,83 @@ fn render_redqueen(ui: &mut Ui, name: &str, node: Node, data: Option<&Vec<Teleme
         });
 }
 
+fn recording_label(ui: &mut Ui, recording_state: &RecordingState, stale: bool) {
+    let (text, color) = match recording_state {
+        RecordingState::Unknown => ("Unknown".to_string(), Color32::DARK_GRAY),
+        RecordingState::Error(text) => (text.clone(), Color32::RED),
+        RecordingState::Pause => ("Pause".to_string(), Color32::DARK_GRAY),
+        RecordingState::Recording(filename) => (filename.clone(), Color32::WHITE),
+    };
+    ui.label(freshness(RichText::new(text).heading().color(color), stale));
+}
+
+// What the target reports over the radio link, as
+// opposed to the NRF telemetry of all RedQueens.
+fn render_flight_observables(
+    ui: &mut Ui,
+    name: &str,
+    obg1: Option<&Obg1History>,
+    obg2: Option<&ObservablesGroup2>,
+    stale: Stale,
+) {
+    let height = ui.text_style_height(&egui::TextStyle::Heading);
+    // A stale pyro state is as good as unknown
+    let (pyro12, pyro34) = match obg2 {
+        Some(obg2) if !stale.obg2 => (
+            Some(obg2.pyro12_status.clone()),
+            Some(obg2.pyro34_status.clone()),
+        ),
+        _ => (None, None),
+    };
+    egui::Grid::new("flight observables")
+        .striped(false)
+        .show(ui, |ui| {
+            dark_label(ui, name);
+            ui.end_row();
+            dark_label(ui, "Uptime");
+            if let Some(obg1) = obg1.and_then(Obg1History::last) {
+                observable_label(ui, &format!("{:.2}", obg1.uptime.as_secs_f64()), stale.obg1);
+            }
+            ui.end_row();
+            dark_label(ui, "Batt");
+            if let Some(obg2) = obg2 {
+                observable_label(ui, &format!("{:1.2}V", obg2.vbb_voltage), stale.obg2);
+            }
+            ui.end_row();
+            dark_label(ui, "Pyro 12");
+            render_pyro_state(ui, pyro12, height);
+            ui.end_row();
+            dark_label(ui, "Pyro 34");
+            render_pyro_state(ui, pyro34, height);
+            ui.end_row();
+            dark_label(ui, "Recording");
+            if let Some(obg2) = obg2 {
+                recording_label(ui, &obg2.recording_state, stale.obg2);
+            }
+            ui.end_row();
+            dark_label(ui, "Records");
+            observable_label(
+                ui,
+                &obg2
+                    .and_then(|o| o.records)
+                    .map_or("--".to_string(), |records| records.to_string()),
+                stale.obg2,
+            );
+        });
+}
+
+fn render_launch_detect(ui: &mut Ui, launch_detect: &HashMap<Node, LaunchDetect>) {
+    let mut farduinos: Vec<_> = launch_detect.iter().collect();
+    farduinos.sort_by_key(|(node, _)| node.to_string());
+    for (node, detect) in farduinos {
+        let (text, color) = match detect.breakwire {
+            Breakwire::Intact => (format!("{} breakwire intact", node), text_color(false)),
+            Breakwire::Broken(_) => (format!("{} breakwire BROKEN", node), Color32::RED),
+        };
+        ui.label(RichText::new(text).color(color).heading());
+    }
+}
+
 const OVERVIEW_FRAME_OUTER_MARGIN: f32 = 2.0;
 const OVERVIEW_FRAME_INNER_MARGIN: f32 = 4.0;
 
@@ -124,11 +246,7 @@ fn lined_frame() -> Frame {
     }
 }
 
-pub fn render_observables<C, Id>(ui: &mut Ui, model: &Model<C, Id>)
-where
-    C: Connection,
-    Id: Iterator<Item = usize>,
-{
+pub fn render_observables(ui: &mut Ui, snapshot: &RenderSnapshot) {
     egui::SidePanel::left("RQs")
         .resizable(false)
         .show_separator_line(false)
@@ -136,21 +254,21 @@ where
         .resizable(false)
         .exact_width(ui.available_width() / 2.0)
         .show_inside(ui, |ui| {
-            let nodes = model.registered_nodes();
-            let mut rqs: Vec<_> = nodes
+            let mut rqs: Vec<_> = snapshot
+                .telemetry
                 .iter()
-                .filter(|n| match n {
+                .filter(|(n, _)| match n {
                     Node::RedQueen(_) => true,
                     _ => false,
                 })
                 .collect();
-            rqs.sort_by(|a, b| {
-                let a: u8 = (**a).into();
-                let b: u8 = (**b).into();
+            rqs.sort_by(|(a, _), (b, _)| {
+                let a: u8 = (*a).into();
+                let b: u8 = (*b).into();
                 a.cmp(&b)
             });
             let mut count = rqs.len();
-            for rq in rqs {
+            for (rq, data) in rqs {
                 let name = format!("RQ{}", unsafe {
                     std::str::from_utf8_unchecked(&[(*rq).into()])
                 });
@@ -166,7 +284,7 @@ where
                             - (OVERVIEW_FRAME_OUTER_MARGIN + OVERVIEW_FRAME_INNER_MARGIN) * 2.0,
                     )
                     .show_inside(ui, |ui| {
-                        render_redqueen(ui, &name, rq.clone(), model.telemetry_data_for_node(rq));
+                        render_redqueen(ui, &name, rq.clone(), *data);
                     });
                 count -= 1;
             }
@@ -178,6 +296,16 @@ where
         .resizable(false)
         .exact_width(ui.available_width())
         .show_inside(ui, |ui| {
+            lined_frame().show(ui, |ui| {
+                render_flight_observables(
+                    ui,
+                    &snapshot.target.to_string(),
+                    snapshot.obg1,
+                    snapshot.obg2.as_ref(),
+                    snapshot.stale,
+                );
+            });
             ui.label(RichText::new("FDB").color(text_color(false)).heading());
+            render_launch_detect(ui, snapshot.launch_detect);
         });
 }
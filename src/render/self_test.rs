@@ -0,0 +1,54 @@
+use egui::{RichText, Ui};
+use epaint::Color32;
+
+use crate::{
+    i18n::{tr, Language},
+    layout::scaling::monospace,
+    selftest::{SelfTest, Verdict},
+};
+
+use super::text_color;
+
+fn render_verdict(ui: &mut Ui, verdict: &Verdict, language: Language) {
+    let (text, color) = match verdict {
+        Verdict::Pending => (tr(language, "pending").to_string(), Color32::DARK_GRAY),
+        Verdict::Running => (tr(language, "running").to_string(), Color32::YELLOW),
+        Verdict::Passed => ("PASS".to_string(), Color32::GREEN),
+        Verdict::Failed(reason) => (format!("FAIL: {}", reason), Color32::RED),
+    };
+    ui.label(RichText::new(text).color(color).heading());
+}
+
+pub fn render_self_test(ui: &mut Ui, self_test: &SelfTest, language: Language) {
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new(tr(language, "Self-test"))
+                .font(monospace(ui, 48.0))
+                .color(text_color(false)),
+        );
+        egui::Grid::new("self test").striped(false).show(ui, |ui| {
+            for (check, verdict) in self_test.verdicts() {
+                ui.label(
+                    RichText::new(check.description())
+                        .color(text_color(false))
+                        .heading(),
+                );
+                render_verdict(ui, verdict, language);
+                ui.end_row();
+            }
+        });
+        ui.separator();
+        let text = if !self_test.finished() {
+            "Running checks, Back to restart"
+        } else if self_test.passed() {
+            "All checks passed, press Enter to continue"
+        } else {
+            "Launch Control locked. Enter to continue, Back to re-run"
+        };
+        ui.label(
+            RichText::new(tr(language, text))
+                .color(text_color(true))
+                .heading(),
+        );
+    });
+}
@@ -0,0 +1,114 @@
+use egui::{ProgressBar, RichText, Ui};
+use epaint::Color32;
+
+use crate::{
+    clock::Instant,
+    i18n::{tr, Language},
+    layout::scaling::monospace,
+    sequence::{Outcome, Run, Sequencer, Step},
+};
+
+use super::text_color;
+
+fn render_outcome(ui: &mut Ui, outcome: &Outcome, language: Language) {
+    let (text, color) = match outcome {
+        Outcome::Completed => (tr(language, "Completed").to_string(), Color32::GREEN),
+        Outcome::Aborted => (tr(language, "Aborted").to_string(), Color32::YELLOW),
+        Outcome::Failed(reason) => (format!("FAILED: {}", reason), Color32::RED),
+    };
+    ui.label(RichText::new(text).color(color).heading());
+}
+
+fn render_run(ui: &mut Ui, run: &Run, now: Instant, language: Language) {
+    ui.label(
+        RichText::new(&run.sequence.name)
+            .color(text_color(false))
+            .heading(),
+    );
+    ui.add(ProgressBar::new(run.progress()).show_percentage());
+    for (index, step) in run.sequence.steps.iter().enumerate() {
+        let color = match index {
+            _ if index < run.step => Color32::DARK_GRAY,
+            _ if index == run.step && !run.is_finished() => Color32::YELLOW,
+            _ => text_color(false),
+        };
+        ui.label(RichText::new(step.to_string()).color(color).heading());
+    }
+    ui.separator();
+    match (&run.outcome, run.current()) {
+        (Some(outcome), _) => render_outcome(ui, outcome, language),
+        (None, Some(Step::Prompt(text))) => {
+            ui.label(RichText::new(text).color(Color32::WHITE).heading());
+        }
+        (None, _) => {
+            ui.label(
+                RichText::new(format!("{:.0}s", run.step_elapsed(now).as_secs_f32()))
+                    .color(text_color(false))
+                    .heading(),
+            );
+        }
+    }
+}
+
+pub fn render_sequences(ui: &mut Ui, sequencer: &Sequencer, now: Instant, language: Language) {
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new(tr(language, "Sequences"))
+                .font(monospace(ui, 48.0))
+                .color(text_color(false)),
+        );
+        match &sequencer.run {
+            Some(run) if sequencer.is_running() => render_run(ui, run, now, language),
+            _ => {
+                if sequencer.directory.is_none() {
+                    ui.label(
+                        RichText::new(tr(language, "No sequence directory given"))
+                            .color(text_color(false))
+                            .heading(),
+                    );
+                }
+                for (index, sequence) in sequencer.sequences.iter().enumerate() {
+                    let text = format!(
+                        "{} ({} {})",
+                        sequence.name,
+                        sequence.steps.len(),
+                        tr(language, "steps")
+                    );
+                    ui.label(
+                        RichText::new(text)
+                            .color(text_color(index == sequencer.selected))
+                            .heading(),
+                    );
+                }
+                for error in &sequencer.errors {
+                    ui.label(RichText::new(error).color(Color32::RED));
+                }
+                if let Some(run) = &sequencer.run {
+                    ui.separator();
+                    ui.label(
+                        RichText::new(format!(
+                            "{}: {}",
+                            tr(language, "Last run"),
+                            run.sequence.name
+                        ))
+                        .color(text_color(false))
+                        .heading(),
+                    );
+                    if let Some(outcome) = &run.outcome {
+                        render_outcome(ui, outcome, language);
+                    }
+                }
+            }
+        }
+        ui.separator();
+        let text = match sequencer.is_running() {
+            true => "Enter to confirm prompts, Back to abort",
+            false => "Enter to run, Back to leave",
+        };
+        ui.label(
+            RichText::new(tr(language, text))
+                .color(text_color(true))
+                .heading(),
+        );
+    });
+}
@@ -0,0 +1,125 @@
+use egui::{Color32, RichText, Ui};
+
+use crate::{
+    layout::scaling::monospace,
+    session::SessionSummary,
+    sessions::{Replay, SessionBrowser},
+};
+
+use super::{last_session::render_summary, text_color};
+
+// Of the transcript, up to the position
+const REPLAY_LINES: usize = 16;
+
+fn render_replay(ui: &mut Ui, summary: &SessionSummary, replay: &Replay) {
+    let entries = &replay.transcript.entries;
+    let text = match (&replay.recording, entries.len()) {
+        (None, _) => "No recording left of this session".to_string(),
+        (Some(path), 0) => format!("{} holds no sentences", path.display()),
+        (Some(path), count) => format!(
+            "{}, sentence {} of {}",
+            path.display(),
+            replay.position + 1,
+            count
+        ),
+    };
+    ui.label(RichText::new(text).color(text_color(false)).heading());
+    ui.separator();
+    let first = (replay.position + 1).saturating_sub(REPLAY_LINES);
+    for (index, entry) in entries
+        .iter()
+        .enumerate()
+        .take(replay.position + 1)
+        .skip(first)
+    {
+        ui.label(
+            RichText::new(format!("#{:<5} {}", index, entry))
+                .font(monospace(ui, 16.0))
+                .color(match index == replay.position {
+                    true => Color32::WHITE,
+                    false => text_color(false),
+                }),
+        );
+    }
+    ui.separator();
+    egui::ScrollArea::vertical()
+        .stick_to_bottom(true)
+        .show(ui, |ui| {
+            for line in &summary.lines {
+                ui.label(
+                    RichText::new(line)
+                        .font(monospace(ui, 16.0))
+                        .color(text_color(false)),
+                );
+            }
+        });
+}
+
+pub fn render_sessions(ui: &mut Ui, browser: &SessionBrowser) {
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new("Sessions")
+                .font(monospace(ui, 48.0))
+                .color(text_color(false)),
+        );
+        let Some(summary) = browser.selected() else {
+            ui.label(
+                RichText::new("No previous sessions next to the recording")
+                    .color(Color32::YELLOW)
+                    .heading(),
+            );
+            ui.label(
+                RichText::new("Back to leave")
+                    .color(text_color(true))
+                    .heading(),
+            );
+            return;
+        };
+        ui.label(
+            RichText::new(format!(
+                "{} of {}: {}",
+                browser.selected_index() + 1,
+                browser.sessions().len(),
+                summary.journal.display()
+            ))
+            .color(text_color(false))
+            .heading(),
+        );
+        ui.separator();
+        if let Some(replay) = browser.replay() {
+            render_replay(ui, summary, replay);
+            return;
+        }
+        render_summary(ui, summary);
+        ui.separator();
+        if browser.is_working() {
+            ui.label(
+                RichText::new("Reading the recordings...")
+                    .color(Color32::YELLOW)
+                    .heading(),
+            );
+        }
+        match &browser.exported {
+            Some(Ok(path)) => {
+                ui.label(
+                    RichText::new(format!("Exported to {}", path.display()))
+                        .color(Color32::GREEN)
+                        .heading(),
+                );
+            }
+            Some(Err(err)) => {
+                ui.label(
+                    RichText::new(format!("Export failed: {}", err))
+                        .color(Color32::RED)
+                        .heading(),
+                );
+            }
+            None => {}
+        }
+        ui.label(
+            RichText::new("Enter replays, send exports, back leaves")
+                .color(text_color(true))
+                .heading(),
+        );
+    });
+}
@@ -0,0 +1,298 @@
+// What a frame shows, taken from the model once per frame, so the
+// render code doesn't reach into the model and its connection. Small
+// values are copied, the bigger parts like the timeline are borrowed
+// for the frame.
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use crate::airtime::DutyCycleStatus;
+use crate::annotation::Annotator;
+use crate::args::Privacy;
+use crate::backoff::BackoffStats;
+use crate::broadcast::Broadcast;
+use crate::calibration::ImuCalibration;
+use crate::clock::Instant;
+use crate::config::ReloadOutcome;
+use crate::conformance::Conformance;
+use crate::connection::Connection;
+use crate::crank::{Crank, CrankTuner};
+use crate::diagnostics::Diagnostics;
+use crate::download::Downloader;
+use crate::failover::Failover;
+use crate::freshness::Stale;
+use crate::fsm::TransitionLog;
+use crate::i18n::Language;
+use crate::input::InputEvent;
+use crate::invariants::Invariants;
+use crate::journal::Journal;
+use crate::layout::scaling::Accessibility;
+use crate::looptiming::LoopTiming;
+use crate::model::{ControlArea, Mode, Model, StateProcessing};
+use crate::monitor::Monitor;
+use crate::observables::adc::AdcMonitor;
+use crate::observables::filter::{FilterTuner, Filters};
+use crate::observables::groups::{LaunchDetect, ObservablesGroup2};
+use crate::observables::history::Obg1History;
+use crate::observables::range::PlotRanges;
+use crate::observables::reference::{Deviation, ReferenceCurve};
+use crate::observables::tare::Tare;
+use crate::observables::units::Units;
+use crate::observables::{AdcGain, Profile};
+use crate::onboard::OnboardRecording;
+use crate::ports::PortPicker;
+use crate::postmortem::PostMortem;
+use crate::recorder::SinkStatus;
+use crate::recovery::Recovery;
+use crate::rqprotocol::{Capabilities, Node};
+use crate::run::RunId;
+use crate::safety::SafetyKey;
+use crate::selftest::SelfTest;
+use crate::sequence::Sequencer;
+use crate::session::LastSession;
+use crate::sessions::SessionBrowser;
+use crate::telemetry::retention::Stored;
+use crate::telemetry::roster::{ChannelConfig, RosterPage};
+use crate::telemetry::{ModuleStatus, SubscriptionHealth};
+use crate::timeline::Timeline;
+use crate::window::WindowStatus;
+
+// Of the status bar
+#[derive(Debug, Clone, Default)]
+pub struct StatusSnapshot {
+    pub language: Language,
+    // Name and port, with a secondary radio
+    pub radio: Option<(&'static str, String)>,
+    pub gain: u8,
+    pub uptime: Option<Duration>,
+    pub recording: Vec<SinkStatus>,
+    pub ground_percentage: Option<f32>,
+    pub ground_runtime: Option<Duration>,
+    pub ground_low: bool,
+    pub reconfigurations: usize,
+    pub bursts: usize,
+    pub discarded_bytes: usize,
+    pub subscription: Option<SubscriptionHealth>,
+    pub duty_cycle: Option<DutyCycleStatus>,
+    pub auto_recording: bool,
+    pub holding: Option<Duration>,
+    pub violations: usize,
+    pub malformed_frames: usize,
+    pub drain_progress: Option<u8>,
+    pub reload: Option<ReloadOutcome>,
+    pub overloaded: bool,
+    pub faults: bool,
+    pub auto_reset_in: Option<Duration>,
+    pub key_entry_expires_in: Option<Duration>,
+    pub standby: bool,
+    // Reporting other IMU ranges than configured
+    pub imu_mismatch: Vec<Node>,
+    // Expecting radio silence, and whether commands go out anyway
+    pub silent: Vec<Node>,
+    pub silence_overridden: bool,
+    // Heard from since and the module
+    pub nodes: Vec<(Node, Duration, Option<ModuleStatus>)>,
+}
+
+pub struct RenderSnapshot<'a> {
+    pub mode: Mode,
+    pub control: ControlArea,
+    pub language: Language,
+    pub reset_ongoing: bool,
+    pub failure: bool,
+    pub connected: bool,
+    pub now: Instant,
+    pub elapsed: Duration,
+    // Since the anchor, launch or ignition
+    pub met_s: Option<f64>,
+    pub launch_window: Option<&'a WindowStatus>,
+    pub gps_fix: Option<bool>,
+    pub status: StatusSnapshot,
+    pub run: RunId,
+    pub transitions: &'a TransitionLog,
+    pub loop_timing: &'a LoopTiming,
+
+    // The pages over the mode
+    pub help: bool,
+    pub input_effects: Vec<(InputEvent, &'static str)>,
+    pub conformance: &'a Conformance,
+    pub annotator: &'a Annotator,
+    pub port_picker: &'a PortPicker,
+    // Of the active radio
+    pub port: &'a str,
+    pub last_session: &'a LastSession,
+    pub self_test: &'a SelfTest,
+    pub monitor: &'a Monitor,
+    pub invariants: &'a Invariants,
+    pub backoff: &'a BackoffStats,
+    pub recovery: &'a Recovery,
+    pub roster_page: &'a RosterPage,
+    pub roster: &'a [ChannelConfig],
+    pub sessions: &'a SessionBrowser,
+    pub downloader: &'a Downloader,
+    pub onboard_file: Option<String>,
+    pub file_read: bool,
+    pub file_manage: bool,
+    pub broadcast: &'a Broadcast,
+    pub sequencer: &'a Sequencer,
+    pub tuner: &'a CrankTuner,
+    pub crank: &'a Crank,
+    pub filter_tuner: &'a FilterTuner,
+    pub calibration: &'a ImuCalibration,
+    pub adc: &'a AdcMonitor,
+    pub diagnostics: &'a Diagnostics,
+    pub postmortem: &'a PostMortem,
+    pub failover: &'a Failover,
+    pub accessibility: Accessibility,
+
+    // Of the target node
+    pub target: Node,
+    pub obg1: Option<&'a Obg1History>,
+    pub obg2: Option<ObservablesGroup2>,
+    pub stale: Stale,
+    pub launched: bool,
+    pub privacy: Privacy,
+    pub safety_key: &'a SafetyKey,
+    pub profile: Profile,
+    pub tare: &'a Tare,
+    pub filters: &'a Filters,
+    pub plot_ranges: &'a PlotRanges,
+    pub units: Units,
+    pub adc_gain: &'a AdcGain,
+    pub onboard_recording: &'a OnboardRecording,
+    pub reference: Option<(&'a ReferenceCurve, f64)>,
+    pub deviation: Option<&'a Deviation>,
+    pub timeline: &'a Timeline,
+    pub journal: &'a Journal,
+    // Of the registered nodes
+    pub telemetry: Vec<(Node, Option<&'a VecDeque<Stored>>)>,
+    pub launch_detect: &'a HashMap<Node, LaunchDetect>,
+}
+
+impl<'a> RenderSnapshot<'a> {
+    pub fn of<C: Connection, Id: Iterator<Item = usize>>(model: &'a Model<C, Id>) -> Self {
+        let nodes = model.registered_nodes();
+        let status = StatusSnapshot {
+            language: model.language,
+            radio: model
+                .failover
+                .has_secondary()
+                .then(|| (model.failover.active().name(), model.failover.port().into())),
+            gain: match model.adc_gain {
+                AdcGain::Gain1 => 1,
+                AdcGain::Gain2 => 2,
+                AdcGain::Gain4 => 4,
+                AdcGain::Gain8 => 8,
+                AdcGain::Gain16 => 16,
+                AdcGain::Gain32 => 32,
+                AdcGain::Gain64 => 64,
+            },
+            uptime: model.uptime(),
+            recording: model.recording.sinks(),
+            ground_percentage: model.ground_power.percentage(),
+            ground_runtime: model.ground_power.runtime(),
+            ground_low: model.ground_power.low(),
+            reconfigurations: model.diagnostics.reconfigurations,
+            bursts: model.diagnostics.bursts,
+            discarded_bytes: model.diagnostics.discarded_bytes,
+            subscription: model.telemetry_subscription(),
+            duty_cycle: model.duty_cycle(),
+            auto_recording: model.auto_record.is_triggered(),
+            holding: model.consort.holding(),
+            violations: model.consort.invariants().count(),
+            malformed_frames: model.malformed_frames(),
+            drain_progress: model.drain_progress,
+            reload: model.reload.clone(),
+            overloaded: model.overloaded(),
+            faults: model.faults.borrow().enabled,
+            auto_reset_in: model.auto_reset_in(),
+            key_entry_expires_in: model.key_entry_expires_in(),
+            standby: model.idle.is_standby(),
+            imu_mismatch: model.calibration.mismatched(),
+            silent: model.silence.silent().to_vec(),
+            silence_overridden: model.silence.is_overridden(),
+            nodes: nodes
+                .iter()
+                .map(|node| {
+                    (
+                        *node,
+                        model.heard_from_since(node),
+                        model.module_status(node),
+                    )
+                })
+                .collect(),
+        };
+        Self {
+            mode: *model.mode(),
+            control: model.control,
+            language: model.language,
+            reset_ongoing: model.mode.reset_ongoing(),
+            failure: model.mode.core_mode().is_failure(),
+            connected: model.connected(),
+            now: model.now(),
+            elapsed: model.elapsed(),
+            met_s: model.t0().map(|t0| model.elapsed().as_secs_f64() - t0),
+            launch_window: model.launch_window(),
+            gps_fix: model.gps_fix(),
+            status,
+            run: model.run,
+            transitions: &model.transitions,
+            loop_timing: &model.loop_timing,
+            help: model.help,
+            input_effects: model.input_effects(),
+            conformance: &model.conformance,
+            annotator: &model.annotator,
+            port_picker: &model.port_picker,
+            port: model.failover.port(),
+            last_session: &model.last_session,
+            self_test: &model.self_test,
+            monitor: &model.monitor,
+            invariants: model.consort.invariants(),
+            backoff: model.consort.backoff_stats(),
+            recovery: &model.recovery,
+            roster_page: &model.roster_page,
+            sessions: &model.sessions,
+            roster: &model.roster,
+            downloader: &model.downloader,
+            onboard_file: model.onboard_file(),
+            file_read: model.capabilities().supports(Capabilities::FILE_READ),
+            file_manage: model.capabilities().supports(Capabilities::FILE_MANAGE),
+            broadcast: &model.broadcast,
+            sequencer: &model.sequencer,
+            tuner: &model.tuner,
+            crank: &model.crank,
+            filter_tuner: &model.filter_tuner,
+            calibration: &model.calibration,
+            adc: &model.adc,
+            diagnostics: &model.diagnostics,
+            postmortem: &model.postmortem,
+            failover: &model.failover,
+            accessibility: model.accessibility,
+            target: model.consort.dest(),
+            obg1: model.target_obg1(),
+            obg2: model.target_obg2().cloned(),
+            stale: model.target_stale(),
+            launched: model.uncommanded_launch(),
+            privacy: model.privacy,
+            safety_key: &model.safety_key,
+            profile: model.profile,
+            tare: &model.tare,
+            filters: &model.filters,
+            plot_ranges: &model.plot_ranges,
+            units: model.units,
+            adc_gain: &model.adc_gain,
+            onboard_recording: &model.onboard_recording,
+            reference: model.reference.as_ref().zip(model.ignition_at()),
+            deviation: model
+                .thrust_deviation
+                .as_ref()
+                .map(|(_, deviation)| deviation),
+            timeline: &model.timeline,
+            journal: &model.journal,
+            telemetry: nodes
+                .iter()
+                .map(|node| (*node, model.telemetry_data_for_node(node)))
+                .collect(),
+            launch_detect: &model.launch_detect,
+        }
+    }
+}
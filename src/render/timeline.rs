@@ -0,0 +1,129 @@
+use egui::{
+    plot::{Legend, Line, LineStyle, Plot, PlotPoint, PlotPoints, PlotUi, Text, VLine},
+    Align2, Color32, RichText, Ui,
+};
+
+use crate::{
+    journal::{Journal, Marker},
+    observables::{
+        filter::{FilterKind, Filters},
+        reference::{Deviation, ReferenceCurve},
+    },
+    timeline::{Series, Timeline},
+};
+
+use super::text_color;
+
+// Fraction of the visible time span a marker reacts to hovering
+const MARKER_HOVER_TOLERANCE: f64 = 0.01;
+
+const SERIES: [Series; 4] = [
+    Series::Thrust,
+    Series::Pressure,
+    Series::Acceleration,
+    Series::OnboardPressure,
+];
+
+fn marker_color(marker: Marker) -> Color32 {
+    match marker {
+        Marker::Reset => Color32::GRAY,
+        Marker::PyrosUnlocked => Color32::YELLOW,
+        Marker::Ignition => Color32::RED,
+        Marker::Anomaly => Color32::LIGHT_RED,
+        Marker::Abort => Color32::from_rgb(255, 165, 0),
+        Marker::RailExit => Color32::LIGHT_BLUE,
+        Marker::Mark => Color32::WHITE,
+    }
+}
+
+fn filter_kind(filters: &Filters, series: Series) -> FilterKind {
+    match series {
+        Series::Thrust => filters.thrust,
+        Series::Pressure => filters.pressure,
+        Series::Acceleration | Series::OnboardPressure => FilterKind::Raw,
+    }
+}
+
+// The legend tells which filter a series is shown with
+pub fn filtered_name(name: &str, kind: FilterKind) -> String {
+    match kind {
+        FilterKind::Raw => name.into(),
+        kind => format!("{} ({})", name, kind.name().to_lowercase()),
+    }
+}
+
+// Markers share their name per kind, so the legend toggles them together
+fn plot_markers(plot_ui: &mut PlotUi, journal: &Journal) {
+    for (marker, at, _) in journal.markers() {
+        plot_ui.vline(
+            VLine::new(at)
+                .color(marker_color(marker))
+                .name(marker.name()),
+        );
+    }
+    let pointer = match plot_ui.pointer_coordinate() {
+        Some(pointer) => pointer,
+        None => return,
+    };
+    let tolerance = plot_ui.plot_bounds().width() * MARKER_HOVER_TOLERANCE;
+    if let Some((marker, at, text)) = journal.marker_near(pointer.x, tolerance) {
+        plot_ui.text(
+            Text::new(PlotPoint::new(at, pointer.y), format!(" {}", text))
+                .color(marker_color(marker))
+                .anchor(Align2::LEFT_BOTTOM),
+        );
+    }
+}
+
+// The reference curve starts at the given ignition
+pub fn render_timeline(
+    ui: &mut Ui,
+    timeline: &Timeline,
+    journal: &Journal,
+    reference: Option<(&ReferenceCurve, f64)>,
+    deviation: Option<&Deviation>,
+    filters: &Filters,
+) {
+    ui.vertical(|ui| {
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new(format!("Onboard alignment: {:+.2}s", timeline.alignment))
+                    .color(text_color(false))
+                    .heading(),
+            );
+            ui.label(
+                RichText::new("Left/Right to align, Enter to tare")
+                    .color(text_color(true))
+                    .heading(),
+            );
+        });
+        if let Some(deviation) = deviation {
+            ui.label(
+                RichText::new(deviation.summary())
+                    .color(text_color(false))
+                    .heading(),
+            );
+        }
+        let plot = Plot::new("timeline").legend(Legend::default());
+        plot.show(ui, |plot_ui| {
+            for series in SERIES {
+                let kind = filter_kind(filters, series);
+                let points = filters.apply(kind, timeline.points(series), None);
+                if points.len() > 1 {
+                    plot_ui.line(
+                        Line::new(PlotPoints::from(points))
+                            .name(filtered_name(series.name(), kind)),
+                    );
+                }
+            }
+            if let Some((reference, ignition)) = reference {
+                plot_ui.line(
+                    Line::new(PlotPoints::from(reference.points(ignition)))
+                        .style(LineStyle::dashed_loose())
+                        .name(format!("Reference {} [kN]", reference.name)),
+                );
+            }
+            plot_markers(plot_ui, journal);
+        });
+    });
+}
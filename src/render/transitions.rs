@@ -0,0 +1,39 @@
+use egui::{Align2, Frame, RichText, Ui};
+use epaint::Color32;
+
+use crate::{
+    clock::Instant,
+    fsm::{mode_name, TransitionLog},
+    layout::scaling::monospace,
+    model::Mode,
+};
+
+// Over whatever is shown, so the state machine can be
+// followed while operating it
+pub fn render_transitions(ui: &mut Ui, log: &TransitionLog, mode: &Mode, now: Instant) {
+    let mono_font = monospace(ui, 14.0);
+    egui::Area::new("transitions")
+        .anchor(Align2::RIGHT_TOP, [-8.0, 8.0])
+        .show(ui.ctx(), |ui| {
+            Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label(
+                    RichText::new(mode_name(mode))
+                        .font(mono_font.clone())
+                        .color(Color32::YELLOW),
+                );
+                ui.separator();
+                for transition in log.entries() {
+                    ui.label(
+                        RichText::new(format!(
+                            "-{:5.1}s {} -> {}",
+                            now.duration_since(transition.at).as_secs_f32(),
+                            transition.from,
+                            transition.to
+                        ))
+                        .font(mono_font.clone())
+                        .color(Color32::WHITE),
+                    );
+                }
+            });
+        });
+}
@@ -0,0 +1,45 @@
+use egui::{RichText, Ui};
+
+use crate::{
+    crank::{Crank, CrankTuner},
+    layout::scaling::monospace,
+};
+
+use super::text_color;
+
+pub fn render_tuning(ui: &mut Ui, tuner: &CrankTuner, crank: &Crank) {
+    ui.vertical(|ui| {
+        ui.label(
+            RichText::new("Progress tuning")
+                .font(monospace(ui, 48.0))
+                .color(text_color(false)),
+        );
+        egui::Grid::new("tuning").striped(false).show(ui, |ui| {
+            for (index, (name, value)) in CrankTuner::parameters(crank).iter().enumerate() {
+                let selected = index == tuner.selected;
+                ui.label(RichText::new(*name).color(text_color(selected)).heading());
+                ui.label(RichText::new(value).color(text_color(selected)).heading());
+                ui.end_row();
+            }
+        });
+        ui.separator();
+        // To be pasted into the config file once it feels right
+        ui.label(
+            RichText::new(format!(
+                "\"crank_increment\": {}, \"crank_decay_interval_ms\": {}, \
+                 \"crank_decay_amount\": {}, \"crank_threshold\": {}",
+                crank.increment,
+                crank.decay_interval.as_millis(),
+                crank.decay_amount,
+                crank.threshold
+            ))
+            .font(monospace(ui, 16.0))
+            .color(text_color(false)),
+        );
+        ui.label(
+            RichText::new("Left/Right: adjust, Enter: next, Back: leave")
+                .color(text_color(true))
+                .heading(),
+        );
+    });
+}
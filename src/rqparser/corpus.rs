@@ -0,0 +1,165 @@
+// Runs the golden vectors in corpus/ through the parsers. Each vector
+// is a line naming its kind and input, followed by a line with the
+// expected outcome starting with "= ". A failing vector prints what
+// was actually parsed, ready to be pasted once checked. Commands are
+// also re-marshalled and have to come out byte for byte.
+use std::path::Path;
+
+use crate::observables::Profile;
+use crate::rqprotocol::{Node, Transaction};
+use crate::telemetry::parser::rq2::{packet_parser, ImuRanges};
+
+use super::{ack_parser, obg_parser, verify_nmea_format, NomError, MAX_BUFFER_SIZE};
+
+// Observables are parsed as the profile of the corpus has it
+const CORPORA: &[(Profile, &str)] = &[
+    (Profile::Rocket, "common.corpus"),
+    (Profile::Rocket, "rqb.corpus"),
+    (Profile::TestStand, "rqa.corpus"),
+];
+
+// As the default build has it, for the vectors to hold in both
+#[cfg(feature = "diagnostics")]
+fn plain(err: nom::Err<NomError>) -> nom::Err<nom::error::Error<&[u8]>> {
+    use nom::error::{Error, ErrorKind, VerboseErrorKind};
+    err.map(|e| match e.errors[0] {
+        (input, VerboseErrorKind::Nom(kind)) => Error::new(input, kind),
+        (input, _) => Error::new(input, ErrorKind::Char),
+    })
+}
+
+#[cfg(not(feature = "diagnostics"))]
+fn plain(err: nom::Err<NomError>) -> nom::Err<NomError> {
+    err
+}
+
+struct Vector {
+    origin: String,
+    kind: String,
+    input: String,
+    expected: String,
+}
+
+fn load(name: &str) -> Vec<Vector> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("corpus")
+        .join(name);
+    let contents = std::fs::read_to_string(&path).expect("corpus file missing");
+    let mut vectors = vec![];
+    let mut pending: Option<(usize, &str, &str)> = None;
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match (line.strip_prefix("= "), pending.take()) {
+            (Some(expected), Some((number, kind, input))) => vectors.push(Vector {
+                origin: format!("{}:{}", name, number),
+                kind: kind.into(),
+                input: input.into(),
+                expected: expected.into(),
+            }),
+            (None, None) => {
+                let (kind, input) = line.split_once(' ').expect("vector without input");
+                pending = Some((index + 1, kind, input));
+            }
+            _ => panic!(
+                "{}:{} isn't a vector followed by its outcome",
+                name,
+                index + 1
+            ),
+        }
+    }
+    assert!(pending.is_none(), "{} ends without an outcome", name);
+    vectors
+}
+
+fn sentence(input: &str) -> Vec<u8> {
+    format!("{}\r\n", input).into_bytes()
+}
+
+// The outcome, and whether a command survived re-marshalling
+fn evaluate(vector: &Vector, profile: Profile) -> (String, bool) {
+    let sentence = sentence(&vector.input);
+    let contents = match vector.kind.as_str() {
+        "telemetry" => vec![],
+        _ => match verify_nmea_format(&sentence) {
+            Ok(contents) => contents.to_vec(),
+            Err(err) => return (format!("Err({:?})", err), true),
+        },
+    };
+    match vector.kind.as_str() {
+        "nmea" => (format!("{:?}", String::from_utf8_lossy(&contents)), true),
+        "command" => match Transaction::from_sentence(&contents) {
+            Ok(transaction) => {
+                let mut buffer = [0; MAX_BUFFER_SIZE];
+                let roundtrip = transaction
+                    .commandeer(&mut buffer)
+                    .map_or(false, |marshalled| marshalled == sentence);
+                (format!("{:?}", transaction), roundtrip)
+            }
+            Err(err) => (format!("Err({:?})", err), true),
+        },
+        "ack" => match ack_parser(&contents) {
+            Ok((rest, ack)) => (
+                format!("{:?} {:?}", ack, String::from_utf8_lossy(rest)),
+                true,
+            ),
+            Err(err) => (
+                format!("Err({:?})", plain(err).map_input(String::from_utf8_lossy)),
+                true,
+            ),
+        },
+        "obg" => match obg_parser(profile, &contents) {
+            Ok((rest, obg)) if rest.is_empty() => (format!("{:?}", obg), true),
+            Ok((rest, _)) => (
+                format!("Err(trailing {:?})", String::from_utf8_lossy(rest)),
+                true,
+            ),
+            Err(err) => (
+                format!("Err({:?})", plain(err).map_input(String::from_utf8_lossy)),
+                true,
+            ),
+        },
+        "telemetry" => {
+            let (node, frame) = vector
+                .input
+                .split_once(' ')
+                .expect("telemetry without node");
+            let node: Node = node.parse().expect("telemetry for unknown node");
+            let frame: Vec<u8> = (0..frame.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&frame[i..i + 2], 16).expect("frame isn't hex"))
+                .collect();
+            // The vectors are of nodes with the default ranges
+            match packet_parser(node, &ImuRanges::default(), &frame) {
+                Ok((_, packet)) => (format!("{:?}", packet), true),
+                Err(err) => (format!("Err({:?})", err.map_input(|i| i.len())), true),
+            }
+        }
+        kind => panic!("{} has unknown kind {}", vector.origin, kind),
+    }
+}
+
+#[test]
+fn test_golden_corpus() {
+    let mut failures = vec![];
+    let mut count = 0;
+    for (profile, name) in CORPORA {
+        for vector in load(name) {
+            count += 1;
+            let (actual, roundtrip) = evaluate(&vector, *profile);
+            if actual != vector.expected {
+                failures.push(format!(
+                    "{} {}\n  expected {}\n  actual   {}",
+                    vector.origin, vector.input, vector.expected, actual
+                ));
+            }
+            if !roundtrip {
+                failures.push(format!("{} doesn't re-marshal", vector.origin));
+            }
+        }
+    }
+    assert!(count > 0);
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
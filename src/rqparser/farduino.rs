@@ -0,0 +1,74 @@
+// OBG3 of the Farduino on the launch rail, the breakwire. Both
+// timestamps are of the Farduino's clock, the rail exit is only
+// meaningful once the wire broke.
+use super::{command_id_parser, hex_u32_parser, hex_u64_parser, node_parser, ParseResult};
+use nom::{branch::alt, bytes::complete::tag, sequence::tuple};
+
+use crate::{
+    observables::{
+        groups::{RawObservablesGroup, RawObservablesGroup3},
+        ClkFreq, Timestamp,
+    },
+    rqprotocol::Node,
+};
+
+pub fn obg3_parser(s: &[u8]) -> ParseResult<'_, (Node, usize, Node, RawObservablesGroup)> {
+    // FDBOBG,123,LNC,3,0BEBC200,00000000AA894CC8,B,00000000AA000000
+    let (
+        rest,
+        (source, _, command_id, _, recipient, _, clkfreq, _, uptime, _, breakwire, _, rail_exit),
+    ) = tuple((
+        node_parser,
+        tag(b"OBG,"),
+        command_id_parser,
+        tag(b","),
+        node_parser,
+        tag(",3,"),
+        hex_u32_parser,
+        tag(","),
+        hex_u64_parser,
+        tag(","),
+        alt((tag("I"), tag("B"))),
+        tag(","),
+        hex_u64_parser,
+    ))(s)?;
+    Ok((
+        rest,
+        (
+            source,
+            command_id,
+            recipient,
+            RawObservablesGroup::OG3(RawObservablesGroup3 {
+                clkfreq: ClkFreq(clkfreq),
+                uptime: Timestamp(uptime),
+                breakwire: breakwire[0],
+                rail_exit: Timestamp(rail_exit),
+            }),
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_obg3_parser() {
+        let (rest, (source, id, _, obg3)) =
+            obg3_parser(b"FDBOBG,042,LNC,3,0BEBC200,00000000AA894CC8,B,00000000AA000000").unwrap();
+        assert_eq!(
+            (rest, source, id),
+            (b"".as_slice(), Node::Farduino(b'B'), 42)
+        );
+        assert_eq!(
+            obg3,
+            RawObservablesGroup::OG3(RawObservablesGroup3 {
+                clkfreq: ClkFreq(0x0BEBC200),
+                uptime: Timestamp(0xAA894CC8),
+                breakwire: b'B',
+                rail_exit: Timestamp(0xAA000000),
+            })
+        );
+        assert!(obg3_parser(b"FDBOBG,042,LNC,3,0BEBC200,00000000AA894CC8,X,0").is_err());
+    }
+}
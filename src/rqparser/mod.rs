@@ -1,22 +1,30 @@
 use crate::{
-    observables::AdcGain,
+    observables::{
+        groups::{RawObservablesGroup, RawObservablesGroup1},
+        AdcGain, Ads1256Reading, ClkFreq, Profile, Timestamp,
+    },
     rqprotocol::{AckHeader, Acknowledgement, Command, Node, RqTimestamp, Transaction},
 };
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_till, take_while_m_n},
     character::{is_alphabetic, is_digit, is_hex_digit},
-    multi::many1_count,
+    combinator::opt,
+    error::context,
+    multi::{many0, many1_count},
     sequence::{preceded, separated_pair, tuple},
     IResult,
 };
 use ringbuffer::{AllocRingBuffer, RingBuffer};
 use std::time::Duration;
 
-#[cfg(feature = "test-stand")]
+pub mod farduino;
 pub mod rqa;
-#[cfg(feature = "rocket")]
 pub mod rqb;
+pub mod wire;
+
+#[cfg(test)]
+mod corpus;
 
 const START_DELIMITER: u8 = b'$';
 const CHECKSUM_DELIMITER: u8 = b'*';
@@ -29,6 +37,138 @@ pub enum Error {
     OutputBufferOverflow,
 }
 
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for Error {}
+
+// The diagnostics build keeps the trail of a parse failure, to tell
+// firmware format mismatches apart. The default one only knows the
+// innermost failing combinator, but is cheaper on the hot path.
+#[cfg(feature = "diagnostics")]
+pub type NomError<'a> = nom::error::VerboseError<&'a [u8]>;
+#[cfg(not(feature = "diagnostics"))]
+pub type NomError<'a> = nom::error::Error<&'a [u8]>;
+
+pub type ParseResult<'a, T> = IResult<&'a [u8], T, NomError<'a>>;
+
+// Of a valid NMEA payload none of our parsers understood
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseFailure {
+    // Into the payload, after the $
+    pub offset: usize,
+    pub expected: String,
+    pub found: String,
+}
+
+impl std::fmt::Display for ParseFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "at byte {} expected {}, found \"{}\"",
+            self.offset, self.expected, self.found
+        )
+    }
+}
+
+// The remaining input and what was expected there
+#[cfg(feature = "diagnostics")]
+fn innermost<'a>(error: &NomError<'a>) -> Option<(&'a [u8], String)> {
+    use nom::error::VerboseErrorKind;
+    let (input, kind) = error.errors.first()?;
+    // The innermost context names it best
+    let expected = error
+        .errors
+        .iter()
+        .find_map(|(_, kind)| match kind {
+            VerboseErrorKind::Context(context) => Some(context.to_string()),
+            _ => None,
+        })
+        .unwrap_or_else(|| match kind {
+            VerboseErrorKind::Char(c) => format!("'{}'", c),
+            VerboseErrorKind::Nom(kind) => kind.description().to_lowercase(),
+            VerboseErrorKind::Context(context) => context.to_string(),
+        });
+    Some((input, expected))
+}
+
+#[cfg(not(feature = "diagnostics"))]
+fn innermost<'a>(error: &NomError<'a>) -> Option<(&'a [u8], String)> {
+    Some((error.input, error.code.description().to_lowercase()))
+}
+
+impl ParseFailure {
+    const FOUND: usize = 12;
+
+    fn new(payload: &[u8], error: nom::Err<NomError>) -> Option<Self> {
+        let (rest, expected) = match &error {
+            nom::Err::Error(e) | nom::Err::Failure(e) => innermost(e)?,
+            nom::Err::Incomplete(_) => (&payload[payload.len()..], "more".into()),
+        };
+        let found = &rest[..rest.len().min(Self::FOUND)];
+        Some(Self {
+            offset: payload.len() - rest.len(),
+            expected,
+            found: String::from_utf8_lossy(found).into(),
+        })
+    }
+}
+
+// The failure that got furthest, unless any of them parses. The
+// alternatives of alt only report the last one.
+fn furthest<T>(
+    payload: &[u8],
+    parsers: &[fn(&[u8]) -> ParseResult<'_, T>],
+) -> Option<ParseFailure> {
+    let mut failures = vec![];
+    for parser in parsers {
+        match parser(payload) {
+            Ok(_) => return None,
+            Err(e) => failures.extend(ParseFailure::new(payload, e)),
+        }
+    }
+    failures
+        .into_iter()
+        .rev()
+        .max_by_key(|failure| failure.offset)
+}
+
+// Why a valid NMEA payload of one of our sentence types, but from
+// someone else's firmware, didn't parse. None if it did, or isn't ours.
+pub fn diagnose(profile: Profile, payload: &[u8]) -> Option<ParseFailure> {
+    let obg2_parser: fn(&[u8]) -> ParseResult<'_, _> = match profile {
+        Profile::TestStand => rqa::obg2_parser,
+        Profile::Rocket => rqb::obg2_parser,
+    };
+    match payload.get(3..6)? {
+        b"CMD" => furthest(
+            payload,
+            &[
+                command_reset_parser,
+                command_ignition_parser,
+                command_unlock_pyros_parser,
+                command_secret_partial_parser,
+                command_secret_full_parser,
+                command_ping_parser,
+                command_obg_parser,
+                command_version_parser,
+                command_file_open_parser,
+                command_file_read_parser,
+                command_file_close_parser,
+                command_record_start_parser,
+                command_record_stop_parser,
+                command_abort_parser,
+            ],
+        ),
+        b"ACK" | b"NAK" => furthest(payload, &[ack_parser]),
+        b"OBG" => furthest(payload, &[obg1_parser, farduino::obg3_parser, obg2_parser]),
+        _ => None,
+    }
+}
+
 #[derive(Debug)]
 enum State {
     WaitForStart,
@@ -208,13 +348,13 @@ pub fn nibble_to_hex(nibble: u8) -> u8 {
 }
 
 #[allow(dead_code)]
-fn timestamp_unit(s: &[u8]) -> IResult<&[u8], u8> {
+fn timestamp_unit(s: &[u8]) -> ParseResult<'_, u8> {
     let (rest, out) = take_while_m_n(2, 2, is_digit)(s)?;
     Ok((rest, (out[0] - 48) * 10 + out[1] - 48))
 }
 
 #[allow(dead_code)]
-fn timestamp_prefix(s: &[u8]) -> IResult<&[u8], (Option<u8>, Option<u8>, u8)> {
+fn timestamp_prefix(s: &[u8]) -> ParseResult<'_, (Option<u8>, Option<u8>, u8)> {
     let (rest, count) = many1_count(timestamp_unit)(s)?;
     let prefix = &s[0..count * 2];
     let (mut hour, mut minute) = (None, None);
@@ -239,8 +379,8 @@ fn timestamp_prefix(s: &[u8]) -> IResult<&[u8], (Option<u8>, Option<u8>, u8)> {
     Ok((rest, (hour, minute, seconds)))
 }
 
-fn usize_parser(s: &[u8]) -> IResult<&[u8], usize> {
-    let (rest, out) = take_while_m_n(1, 8, is_digit)(s)?;
+fn usize_parser(s: &[u8]) -> ParseResult<'_, usize> {
+    let (rest, out) = context("number", take_while_m_n(1, 8, is_digit))(s)?;
     let mut accu: usize = 0;
     for c in out {
         accu *= 10;
@@ -249,7 +389,7 @@ fn usize_parser(s: &[u8]) -> IResult<&[u8], usize> {
     Ok((rest, accu))
 }
 
-fn timestamp_suffix(s: &[u8]) -> IResult<&[u8], Duration> {
+fn timestamp_suffix(s: &[u8]) -> ParseResult<'_, Duration> {
     let (rest, out) = take_while_m_n(1, 6, is_digit)(s)?;
     let mut accu: u64 = 0;
     for c in out {
@@ -264,7 +404,7 @@ fn timestamp_suffix(s: &[u8]) -> IResult<&[u8], Duration> {
     Ok((rest, Duration::from_micros(accu)))
 }
 
-fn timestamp_parser(s: &[u8]) -> IResult<&[u8], RqTimestamp> {
+fn timestamp_parser(s: &[u8]) -> ParseResult<'_, RqTimestamp> {
     let (rest, (prefix, fractional)) =
         separated_pair(timestamp_prefix, tag(b"."), timestamp_suffix)(s)?;
     Ok((
@@ -278,7 +418,7 @@ fn timestamp_parser(s: &[u8]) -> IResult<&[u8], RqTimestamp> {
     ))
 }
 
-pub fn ack_parser(s: &[u8]) -> IResult<&[u8], Acknowledgement> {
+pub fn ack_parser(s: &[u8]) -> ParseResult<'_, Acknowledgement> {
     let (rest, (source, acknowledgement, _, id, _, recipient)) = tuple((
         node_parser,
         alt((tag(b"ACK"), tag(b"NAK"))),
@@ -299,24 +439,71 @@ pub fn ack_parser(s: &[u8]) -> IResult<&[u8], Acknowledgement> {
     }
 }
 
-pub fn one_hex_return_value_parser(s: &[u8]) -> IResult<&[u8], u8> {
+// Who answers which transaction and how, for any kind of answer
+pub fn answer_header_parser(s: &[u8]) -> ParseResult<'_, (Node, &[u8], usize, Node)> {
+    let (rest, (source, verb, _, id, _, recipient)) = tuple((
+        node_parser,
+        alt((tag(b"ACK"), tag(b"NAK"), tag(b"OBG"))),
+        tag(b","),
+        command_id_parser,
+        tag(b","),
+        node_parser,
+    ))(s)?;
+    Ok((rest, (source, verb, id, recipient)))
+}
+
+pub fn one_hex_return_value_parser(s: &[u8]) -> ParseResult<'_, u8> {
     preceded(tag(b","), hex_byte)(s)
 }
 
-pub fn two_return_values_parser(s: &[u8]) -> IResult<&[u8], (u8, u8)> {
+pub fn two_return_values_parser(s: &[u8]) -> ParseResult<'_, (u8, u8)> {
     tuple((one_hex_return_value_parser, one_hex_return_value_parser))(s)
 }
 
-pub fn one_usize_return_value_parser(s: &[u8]) -> IResult<&[u8], usize> {
+pub fn one_usize_return_value_parser(s: &[u8]) -> ParseResult<'_, usize> {
     preceded(tag(b","), usize_parser)(s)
 }
 
-fn hex_byte(s: &[u8]) -> IResult<&[u8], u8> {
-    let (rest, out) = take_while_m_n(2, 2, is_hex_digit)(s)?;
+pub fn version_return_values_parser(s: &[u8]) -> ParseResult<'_, (usize, u32)> {
+    tuple((
+        one_usize_return_value_parser,
+        preceded(tag(b","), hex_u32_parser),
+    ))(s)
+}
+
+pub fn file_open_return_values_parser(s: &[u8]) -> ParseResult<'_, (usize, u32)> {
+    version_return_values_parser(s)
+}
+
+// The offset of the chunk followed by its hex encoded data
+pub fn file_chunk_return_values_parser(s: &[u8]) -> ParseResult<'_, (usize, Vec<u8>)> {
+    tuple((
+        one_usize_return_value_parser,
+        preceded(tag(b","), many0(hex_byte)),
+    ))(s)
+}
+
+// The index, the number of files and the size and name of the file
+// at the index, if there is one
+pub fn file_list_return_values_parser(
+    s: &[u8],
+) -> ParseResult<'_, (usize, usize, Option<(usize, Vec<u8>)>)> {
+    tuple((
+        one_usize_return_value_parser,
+        one_usize_return_value_parser,
+        opt(tuple((
+            one_usize_return_value_parser,
+            preceded(tag(b","), string_parser),
+        ))),
+    ))(s)
+}
+
+fn hex_byte(s: &[u8]) -> ParseResult<'_, u8> {
+    let (rest, out) = context("2 hex digits", take_while_m_n(2, 2, is_hex_digit))(s)?;
     Ok((rest, unhex(out[0]).unwrap() << 4 | unhex(out[1]).unwrap()))
 }
 
-fn avionics_parser(s: &[u8]) -> IResult<&[u8], Node> {
+fn avionics_parser(s: &[u8]) -> ParseResult<'_, Node> {
     let (rest, (praefix, identifier)) = tuple((
         alt((tag(b"RQ"), tag(b"FD"))),
         take_while_m_n(1, 1, is_alphabetic),
@@ -328,24 +515,24 @@ fn avionics_parser(s: &[u8]) -> IResult<&[u8], Node> {
     }
 }
 
-fn lnc_parser(s: &[u8]) -> IResult<&[u8], Node> {
+fn lnc_parser(s: &[u8]) -> ParseResult<'_, Node> {
     let (rest, _) = tag(b"LNC")(s)?;
     Ok((rest, Node::LaunchControl))
 }
 
-fn node_parser(s: &[u8]) -> IResult<&[u8], Node> {
-    alt((lnc_parser, avionics_parser))(s)
+pub fn node_parser(s: &[u8]) -> ParseResult<'_, Node> {
+    context("node", alt((lnc_parser, avionics_parser)))(s)
 }
 
-fn command_id_parser(s: &[u8]) -> IResult<&[u8], usize> {
-    let (rest, bytes) = take_while_m_n(3, 3, is_digit)(s)?;
+fn command_id_parser(s: &[u8]) -> ParseResult<'_, usize> {
+    let (rest, bytes) = context("command id", take_while_m_n(3, 3, is_digit))(s)?;
     let a = (bytes[0] - b'0') as usize;
     let b = (bytes[1] - b'0') as usize;
     let c = (bytes[2] - b'0') as usize;
     Ok((rest, (a * 100 + b * 10 + c)))
 }
 
-fn command_prefix_parser(s: &[u8]) -> IResult<&[u8], (Node, usize, Node)> {
+fn command_prefix_parser(s: &[u8]) -> ParseResult<'_, (Node, usize, Node)> {
     // LNCCMD,123,RQA
     let (rest, (source, _, command_id, _, recipient, _)) = tuple((
         node_parser,
@@ -358,7 +545,7 @@ fn command_prefix_parser(s: &[u8]) -> IResult<&[u8], (Node, usize, Node)> {
     Ok((rest, (source, command_id, recipient)))
 }
 
-fn gain_parser(s: &[u8]) -> IResult<&[u8], AdcGain> {
+fn gain_parser(s: &[u8]) -> ParseResult<'_, AdcGain> {
     let (rest, num) = hex_u8_parser(s)?;
     let gain = match num {
         1 => AdcGain::Gain1,
@@ -373,7 +560,7 @@ fn gain_parser(s: &[u8]) -> IResult<&[u8], AdcGain> {
     Ok((rest, gain))
 }
 
-fn command_reset_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
+fn command_reset_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
     // LNCCMD,123,RQA,RESET,40
     let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
     let (rest, (_, _, gain)) = tuple((tag(b"RESET"), tag(","), gain_parser))(rest)?;
@@ -381,7 +568,7 @@ fn command_reset_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
     Ok((rest, transaction))
 }
 
-fn command_ping_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
+fn command_ping_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
     // LNCCMD,123,RQA,PING
     let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
     let (rest, _) = tag(b"PING")(rest)?;
@@ -389,7 +576,7 @@ fn command_ping_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
     Ok((rest, transaction))
 }
 
-fn command_ignition_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
+fn command_ignition_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
     // LNCCMD,123,RQA,IGNITION
     let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
     let (rest, _) = tag(b"IGNITION")(rest)?;
@@ -397,7 +584,7 @@ fn command_ignition_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
     Ok((rest, transaction))
 }
 
-fn command_unlock_pyros_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
+fn command_unlock_pyros_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
     // LNCCMD,123,RQA,UNLOCK_PYROS
     let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
     let (rest, _) = tag(b"UNLOCK_PYROS")(rest)?;
@@ -405,7 +592,7 @@ fn command_unlock_pyros_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
     Ok((rest, transaction))
 }
 
-fn command_secret_partial_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
+fn command_secret_partial_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
     // LNCCMD,123,RQA,SECRET_A,3F
     let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
     let (rest, (_, secret)) = tuple((tag(b"SECRET_A,"), hex_byte))(rest)?;
@@ -418,7 +605,25 @@ fn command_secret_partial_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
     Ok((rest, transaction))
 }
 
-fn command_obg_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
+fn command_version_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
+    // LNCCMD,123,RQA,VERSION
+    let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
+    let (rest, _) = tag(b"VERSION")(rest)?;
+    let transaction = Transaction::new(source, recipient, command_id, Command::Version);
+    Ok((rest, transaction))
+}
+
+// Only sent, never received, but the specification's example is read back
+#[cfg(test)]
+pub fn command_rf_silence_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
+    // LNCCMD,123,RQB,RF_SILENCE
+    let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
+    let (rest, _) = tag(b"RF_SILENCE")(rest)?;
+    let transaction = Transaction::new(source, recipient, command_id, Command::EnterRFSilence);
+    Ok((rest, transaction))
+}
+
+fn command_obg_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
     // LNCCMD,123,RQA,OBG,01
     let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
     let (rest, (_, group)) = tuple((tag(b"OBG,"), usize_parser))(rest)?;
@@ -431,7 +636,7 @@ fn command_obg_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
     Ok((rest, transaction))
 }
 
-fn command_secret_full_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
+fn command_secret_full_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
     // LNCCMD,123,RQA,SECRET_AB,3F,AB
     let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
     let (rest, (_, secret_a, _, secret_b)) =
@@ -445,7 +650,87 @@ fn command_secret_full_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
     Ok((rest, transaction))
 }
 
-pub fn command_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
+fn command_file_open_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
+    // LNCCMD,123,RQB,FILE_OPEN,TEST.DAT
+    let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
+    let (rest, (_, name)) = tuple((tag(b"FILE_OPEN,"), string_parser))(rest)?;
+    let transaction = Transaction::new(
+        source,
+        recipient,
+        command_id,
+        Command::FileOpen(String::from_utf8_lossy(&name).into()),
+    );
+    Ok((rest, transaction))
+}
+
+fn command_file_read_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
+    // LNCCMD,123,RQB,FILE_READ,48,24
+    let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
+    let (rest, (_, offset, _, length)) =
+        tuple((tag(b"FILE_READ,"), usize_parser, tag(b","), usize_parser))(rest)?;
+    let transaction = Transaction::new(
+        source,
+        recipient,
+        command_id,
+        Command::FileRead(offset, length),
+    );
+    Ok((rest, transaction))
+}
+
+fn command_file_close_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
+    // LNCCMD,123,RQB,FILE_CLOSE
+    let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
+    let (rest, _) = tag(b"FILE_CLOSE")(rest)?;
+    let transaction = Transaction::new(source, recipient, command_id, Command::FileClose);
+    Ok((rest, transaction))
+}
+
+fn command_file_list_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
+    // LNCCMD,123,RQB,FILE_LIST,0
+    let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
+    let (rest, (_, index)) = tuple((tag(b"FILE_LIST,"), usize_parser))(rest)?;
+    let transaction = Transaction::new(source, recipient, command_id, Command::FileList(index));
+    Ok((rest, transaction))
+}
+
+fn command_file_delete_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
+    // LNCCMD,123,RQB,FILE_DELETE,TEST.DAT
+    let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
+    let (rest, (_, name)) = tuple((tag(b"FILE_DELETE,"), string_parser))(rest)?;
+    let transaction = Transaction::new(
+        source,
+        recipient,
+        command_id,
+        Command::FileDelete(String::from_utf8_lossy(&name).into()),
+    );
+    Ok((rest, transaction))
+}
+
+fn command_record_start_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
+    // LNCCMD,123,RQB,RECORD_START
+    let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
+    let (rest, _) = tag(b"RECORD_START")(rest)?;
+    let transaction = Transaction::new(source, recipient, command_id, Command::RecordStart);
+    Ok((rest, transaction))
+}
+
+fn command_record_stop_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
+    // LNCCMD,123,RQB,RECORD_STOP
+    let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
+    let (rest, _) = tag(b"RECORD_STOP")(rest)?;
+    let transaction = Transaction::new(source, recipient, command_id, Command::RecordStop);
+    Ok((rest, transaction))
+}
+
+fn command_abort_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
+    // LNCCMD,123,RQB,ABORT
+    let (rest, (source, command_id, recipient)) = command_prefix_parser(s)?;
+    let (rest, _) = tag(b"ABORT")(rest)?;
+    let transaction = Transaction::new(source, recipient, command_id, Command::Abort);
+    Ok((rest, transaction))
+}
+
+pub fn command_parser(s: &[u8]) -> ParseResult<'_, Transaction> {
     alt((
         command_reset_parser,
         command_ignition_parser,
@@ -454,11 +739,20 @@ pub fn command_parser(s: &[u8]) -> IResult<&[u8], Transaction> {
         command_secret_full_parser,
         command_ping_parser,
         command_obg_parser,
+        command_version_parser,
+        command_file_open_parser,
+        command_file_read_parser,
+        command_file_close_parser,
+        command_file_list_parser,
+        command_file_delete_parser,
+        command_record_start_parser,
+        command_record_stop_parser,
+        command_abort_parser,
     ))(s)
 }
 
-fn hex_u32_parser(s: &[u8]) -> IResult<&[u8], u32> {
-    let (rest, out) = take_while_m_n(8, 8, is_hex_digit)(s)?;
+fn hex_u32_parser(s: &[u8]) -> ParseResult<'_, u32> {
+    let (rest, out) = context("8 hex digits", take_while_m_n(8, 8, is_hex_digit))(s)?;
     let mut res: u32 = 0;
     for i in 0..8 {
         res <<= 4;
@@ -467,8 +761,8 @@ fn hex_u32_parser(s: &[u8]) -> IResult<&[u8], u32> {
     Ok((rest, res))
 }
 
-fn hex_u16_parser(s: &[u8]) -> IResult<&[u8], u16> {
-    let (rest, out) = take_while_m_n(4, 4, is_hex_digit)(s)?;
+fn hex_u16_parser(s: &[u8]) -> ParseResult<'_, u16> {
+    let (rest, out) = context("4 hex digits", take_while_m_n(4, 4, is_hex_digit))(s)?;
     let mut res: u16 = 0;
     for i in 0..4 {
         res <<= 4;
@@ -477,8 +771,8 @@ fn hex_u16_parser(s: &[u8]) -> IResult<&[u8], u16> {
     Ok((rest, res))
 }
 
-fn hex_u8_parser(s: &[u8]) -> IResult<&[u8], u8> {
-    let (rest, out) = take_while_m_n(2, 2, is_hex_digit)(s)?;
+fn hex_u8_parser(s: &[u8]) -> ParseResult<'_, u8> {
+    let (rest, out) = context("2 hex digits", take_while_m_n(2, 2, is_hex_digit))(s)?;
     let mut res: u8 = 0;
     for i in 0..2 {
         res <<= 4;
@@ -487,13 +781,13 @@ fn hex_u8_parser(s: &[u8]) -> IResult<&[u8], u8> {
     Ok((rest, res))
 }
 
-fn hex_i32_parser(s: &[u8]) -> IResult<&[u8], i32> {
+fn hex_i32_parser(s: &[u8]) -> ParseResult<'_, i32> {
     let (rest, num) = hex_u32_parser(s)?;
     Ok((rest, num as i32))
 }
 
-fn hex_u64_parser(s: &[u8]) -> IResult<&[u8], u64> {
-    let (rest, out) = take_while_m_n(16, 16, is_hex_digit)(s)?;
+fn hex_u64_parser(s: &[u8]) -> ParseResult<'_, u64> {
+    let (rest, out) = context("16 hex digits", take_while_m_n(16, 16, is_hex_digit))(s)?;
     let mut res: u64 = 0;
     for i in 0..16 {
         res <<= 4;
@@ -502,7 +796,467 @@ fn hex_u64_parser(s: &[u8]) -> IResult<&[u8], u64> {
     Ok((rest, res))
 }
 
-fn string_parser(s: &[u8]) -> IResult<&[u8], Vec<u8>> {
+fn string_parser(s: &[u8]) -> ParseResult<'_, Vec<u8>> {
     let (rest, string) = take_till(|c| c == b'*' || c == b',')(s)?;
     Ok((rest, string.into()))
 }
+
+fn obg1_parser(s: &[u8]) -> ParseResult<'_, (Node, usize, Node, RawObservablesGroup)> {
+    // RQAOBG,123,LNC,1,0BEBC200,00000000AA894CC8,000669E2
+    let (rest, (source, _, command_id, _, recipient, _, clkfreq, _, timestamp, _, adc0, _, adc1)) =
+        tuple((
+            node_parser,
+            tag(b"OBG,"),
+            command_id_parser,
+            tag(b","),
+            node_parser,
+            tag(",1,"),
+            hex_u32_parser,
+            tag(","),
+            hex_u64_parser,
+            tag(","),
+            hex_i32_parser,
+            tag(","),
+            hex_i32_parser,
+        ))(s)?;
+    Ok((
+        rest,
+        (
+            source,
+            command_id,
+            recipient,
+            RawObservablesGroup::OG1(RawObservablesGroup1 {
+                clkfreq: ClkFreq(clkfreq),
+                uptime: Timestamp(timestamp),
+                thrust: Ads1256Reading(adc0),
+                pressure: Ads1256Reading(adc1),
+            }),
+        ),
+    ))
+}
+
+// OBG1 is the same for all profiles
+pub fn obg_parser(
+    profile: Profile,
+    s: &[u8],
+) -> ParseResult<'_, (Node, usize, Node, RawObservablesGroup)> {
+    match profile {
+        Profile::TestStand => alt((obg1_parser, farduino::obg3_parser, rqa::obg2_parser))(s),
+        Profile::Rocket => alt((obg1_parser, farduino::obg3_parser, rqb::obg2_parser))(s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use super::*;
+    use crate::observables::groups::{RawObservablesGroup2, RawRecording};
+
+    #[test]
+    fn test_feeding_full_sentence() {
+        let sentence = b"$RQSTATE,013940.4184,DROGUE_OPEN*39\r\n";
+        let mut parser = SentenceParser::new();
+        let mut called = false;
+        parser
+            .feed(sentence, |output_sentence| {
+                called = true;
+                assert_eq!(output_sentence.len(), 37);
+                assert_eq!(&output_sentence[0..37], sentence);
+            })
+            .expect("");
+        assert!(called);
+    }
+
+    #[test]
+    fn test_too_small_output_buffer() {
+        let sentence = b"$RQSTATE,01234567890123456789012345678901234567890123456789012345678901234567890123456789013940.4184,DROGUE_OPEN*39\r\n";
+        let mut parser = SentenceParser::new();
+        assert_eq!(
+            Err(Error::OutputBufferOverflow),
+            parser.feed(sentence, |_| {})
+        );
+    }
+
+    #[test]
+    fn test_output_buffer_overvflow_recovers() {
+        let sentence = b"$RQSTATE,01234567890123456789012345678901234567890123456789012345678901234567890123456789013940.4184,DROGUE_OPEN*39\r\n";
+        let mut parser = SentenceParser::new();
+
+        assert_eq!(
+            Err(Error::OutputBufferOverflow),
+            parser.feed(sentence, |sentence| {
+                assert_eq!(sentence, b"$TEST\r\n");
+            })
+        );
+        assert_matches!(
+            parser.feed(b"$TEST\r\n", |sentence| {
+                assert_eq!(sentence, b"$TEST\r\n");
+            }),
+            Ok(_),
+        );
+    }
+
+    #[test]
+    fn test_leading_garbage_is_discarded() {
+        let sentence =
+            b"prentend-this-is-an-earlier-sentence\r\n$RQSTATE,013940.4184,DROGUE_OPEN*39\r\n";
+        let mut parser = SentenceParser::new();
+        let mut called = false;
+        parser
+            .feed(sentence, |output_sentence| {
+                called = true;
+                assert_eq!(output_sentence.len(), 37);
+            })
+            .expect("");
+        assert!(called);
+    }
+
+    #[test]
+    fn test_even_more_garbage_is_discarded() {
+        let sentence = b"$\rX\r\n$RQSTATE,013940.4184,DROGUE_OPEN*39\r\n";
+        let mut parser = SentenceParser::new();
+        let mut called = false;
+        parser
+            .feed(sentence, |output_sentence| {
+                called = true;
+                println!("{:?}", output_sentence);
+                assert_eq!(output_sentence.len(), 37);
+            })
+            .expect("should've worked");
+        assert!(called);
+    }
+
+    #[test]
+    fn test_nmea_format_verification() {
+        assert_eq!(Err(NMEAFormatError::FormatError), verify_nmea_format(b""));
+        assert_eq!(Err(NMEAFormatError::FormatError), verify_nmea_format(b""));
+        assert_eq!(
+            Ok(b"PFEC,GPint,RMC05".as_slice()),
+            verify_nmea_format(b"$PFEC,GPint,RMC05*2D\r\n")
+        );
+        assert_eq!(
+            Err(NMEAFormatError::NoChecksumError(b"".as_slice())),
+            verify_nmea_format(b"$\r\n")
+        );
+
+        assert_eq!(
+            Err(NMEAFormatError::ChecksumError),
+            verify_nmea_format(b"$PFEC,GPint,RMC05*2E\r\n")
+        );
+    }
+
+    #[test]
+    fn test_nmea_formatter() {
+        let mut formatter = NMEAFormatter::default();
+        formatter.format_sentence(b"PFEC,GPint,RMC05").unwrap();
+        assert_eq!(
+            Ok(b"$PFEC,GPint,RMC05*2D\r\n".as_slice()),
+            formatter.buffer()
+        );
+    }
+
+    #[test]
+    fn test_timestamp_parsing() {
+        assert_eq!(timestamp_unit(b"123456"), Ok((&b"3456"[..], 12)));
+        assert_eq!(
+            timestamp_prefix(b"123456"),
+            Ok((b"".as_slice(), (Some(12), Some(34), 56)))
+        );
+        assert_eq!(
+            timestamp_suffix(b"000001"),
+            Ok((b"".as_slice(), Duration::from_micros(1)))
+        );
+        assert_eq!(
+            timestamp_suffix(b"1"),
+            Ok((b"".as_slice(), Duration::from_micros(100000)))
+        );
+
+        assert_eq!(
+            timestamp_parser(b"123456.1"),
+            Ok((
+                b"".as_slice(),
+                (RqTimestamp {
+                    hour: Some(12),
+                    minute: Some(34),
+                    seconds: 56,
+                    fractional: Duration::from_micros(100000)
+                })
+            ))
+        );
+        assert_eq!(
+            timestamp_parser(b"3456.1"),
+            Ok((
+                b"".as_slice(),
+                (RqTimestamp {
+                    hour: None,
+                    minute: Some(34),
+                    seconds: 56,
+                    fractional: Duration::from_micros(100000)
+                })
+            ))
+        );
+        assert_eq!(
+            timestamp_parser(b"56.1"),
+            Ok((
+                b"".as_slice(),
+                (RqTimestamp {
+                    hour: None,
+                    minute: None,
+                    seconds: 56,
+                    fractional: Duration::from_micros(100000)
+                })
+            ))
+        );
+    }
+
+    #[test]
+    fn test_node_parsing() {
+        assert_eq!(
+            node_parser(b"RQA"),
+            Ok((b"".as_slice(), Node::RedQueen(b'A')),)
+        );
+        assert_eq!(
+            node_parser(b"RQB"),
+            Ok((b"".as_slice(), Node::RedQueen(b'B')),)
+        );
+        assert_eq!(
+            node_parser(b"FDC"),
+            Ok((b"".as_slice(), Node::Farduino(b'C')),)
+        );
+        assert_eq!(
+            node_parser(b"LNC"),
+            Ok((b"".as_slice(), Node::LaunchControl,))
+        );
+        assert_eq!(
+            node_parser(b"RQBFOO"),
+            Ok((b"FOO".as_slice(), Node::RedQueen(b'B')),)
+        );
+    }
+
+    #[test]
+    fn test_command_id_parser() {
+        assert_matches!(command_id_parser(b"123"), Ok((_, 123)));
+    }
+
+    #[test]
+    fn test_ack_parsing() {
+        let inner_sentence = b"RQEACK,123,LNC";
+        assert_matches!(
+            ack_parser(inner_sentence),
+            Ok((
+                _,
+                Acknowledgement::Ack(AckHeader {
+                    recipient: Node::LaunchControl,
+                    source: Node::RedQueen(b'E'),
+                    id: 123,
+                    ..
+                })
+            ))
+        );
+        let inner_sentence = b"RQENAK,123,LNC";
+        assert_matches!(ack_parser(inner_sentence), Ok((_, Acknowledgement::Nak(_))));
+    }
+
+    #[test]
+    fn test_return_argument_parsing() {
+        assert_matches!(one_hex_return_value_parser(b",3F"), Ok((b"", 0x3f)));
+        assert_matches!(two_return_values_parser(b",AB,CD"), Ok((b"", (0xab, 0xcd))));
+    }
+
+    #[test]
+    fn test_command_parsing() {
+        assert_matches!(
+            command_parser(b"LNCCMD,123,RQA,RESET,40"),
+            Ok((
+                b"",
+                Transaction {
+                    id: 123,
+                    source: Node::LaunchControl,
+                    recipient: Node::RedQueen(b'A'),
+                    command: Command::Reset(AdcGain::Gain64),
+                    ..
+                }
+            ))
+        );
+        assert_matches!(
+            command_parser(b"LNCCMD,123,RQA,IGNITION"),
+            Ok((
+                b"",
+                Transaction {
+                    id: 123,
+                    source: Node::LaunchControl,
+                    recipient: Node::RedQueen(b'A'),
+                    command: Command::Ignition,
+                    ..
+                }
+            ))
+        );
+        assert_matches!(
+            command_parser(b"LNCCMD,123,RQA,UNLOCK_PYROS"),
+            Ok((
+                b"",
+                Transaction {
+                    id: 123,
+                    source: Node::LaunchControl,
+                    recipient: Node::RedQueen(b'A'),
+                    command: Command::UnlockPyros,
+                    ..
+                }
+            ))
+        );
+        assert_matches!(
+            command_parser(b"LNCCMD,123,RQA,SECRET_A,AB"),
+            Ok((
+                b"",
+                Transaction {
+                    id: 123,
+                    source: Node::LaunchControl,
+                    recipient: Node::RedQueen(b'A'),
+                    command: Command::LaunchSecretPartial(0xab),
+                    ..
+                }
+            ))
+        );
+        assert_matches!(
+            command_parser(b"LNCCMD,123,RQA,OBG,01"),
+            Ok((
+                b"",
+                Transaction {
+                    id: 123,
+                    source: Node::LaunchControl,
+                    recipient: Node::RedQueen(b'A'),
+                    command: Command::ObservableGroup(0x01),
+                    ..
+                }
+            ))
+        );
+        assert_matches!(
+            command_parser(b"LNCCMD,123,RQA,SECRET_AB,AB,CD"),
+            Ok((
+                b"",
+                Transaction {
+                    id: 123,
+                    source: Node::LaunchControl,
+                    recipient: Node::RedQueen(b'A'),
+                    command: Command::LaunchSecretFull(0xab, 0xcd),
+                    ..
+                }
+            ))
+        );
+        assert_matches!(
+            command_parser(b"LNCCMD,123,RQA,PING"),
+            Ok((
+                b"",
+                Transaction {
+                    id: 123,
+                    source: Node::LaunchControl,
+                    recipient: Node::RedQueen(b'A'),
+                    command: Command::Ping,
+                    ..
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_actual_response() {
+        assert_matches!(ack_parser(b"RQAACK,001,LNC,RESET"), Ok(_));
+    }
+
+    #[test]
+    fn test_usize_parser() {
+        assert_matches!(usize_parser(b"1"), Ok((b"", 1)));
+    }
+
+    #[test]
+    fn test_obg1_parser() {
+        assert_matches!(
+            obg1_parser(b"RQAOBG,006,LNC,1,0BEBC200,000000003440E810,00069B00,FFFFFA7B"),
+            Ok(_)
+        );
+        //b'OBG,003,LNC,1,0BEBC200,000000059681E328,00069BB7,FFFFFA79'
+
+        assert_matches!(
+            obg1_parser(b"RQAOBG,123,LNC,1,0BEBC200,00000000AA894CC8,FFFFFFFF,00000000"),
+            Ok((
+                b"",
+                (
+                    Node::RedQueen(b'A'),
+                    123,
+                    Node::LaunchControl,
+                    RawObservablesGroup::OG1(RawObservablesGroup1 {
+                        clkfreq: ClkFreq(0x0BEBC200),
+                        uptime: Timestamp(0x00000000AA894CC8),
+                        thrust: Ads1256Reading(-1),
+                        pressure: Ads1256Reading(0),
+                    })
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_string() {
+        let (rest, contents) = string_parser(b"TEST.DAT").unwrap();
+        assert_eq!(contents.as_slice(), b"TEST.DAT");
+        assert_eq!(rest, b"");
+        let (rest, contents) = string_parser(b"TEST.DAT,").unwrap();
+        assert_eq!(contents.as_slice(), b"TEST.DAT");
+        assert_eq!(rest, b",");
+        let (rest, contents) = string_parser(b"TEST.DAT*").unwrap();
+        assert_eq!(contents.as_slice(), b"TEST.DAT");
+        assert_eq!(rest, b"*");
+    }
+
+    #[test]
+    fn test_obg2_parser() {
+        assert_matches!(
+            obg_parser(
+                Profile::TestStand,
+                b"RQAOBG,010,LNC,2,R,RQADS002.TXT,00000064,00000579,007D,00"
+            ),
+            Ok((
+                b"",
+                (
+                    Node::RedQueen(b'A'),
+                    10,
+                    Node::LaunchControl,
+                    RawObservablesGroup::OG2(RawObservablesGroup2 {
+                        recording: Some(RawRecording {
+                            state: b'R',
+                            records: 1401,
+                            ..
+                        }),
+                        anomalies: Some(100),
+                        vbb_voltage: 125,
+                        pyro_status: 0x00,
+                    })
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let failure = diagnose(Profile::TestStand, b"RQAACK,12,LNC").unwrap();
+        assert_eq!((failure.offset, failure.found.as_str()), (7, "12,LNC"));
+        #[cfg(feature = "diagnostics")]
+        assert_eq!(failure.expected, "command id");
+        // Of all commands, the OBG one gets furthest
+        let failure = diagnose(Profile::Rocket, b"LNCCMD,001,RQB,OBG,X").unwrap();
+        assert_eq!(failure.offset, 19);
+        #[cfg(feature = "diagnostics")]
+        assert_eq!(failure.expected, "number");
+        assert_eq!(
+            diagnose(Profile::Rocket, b"RQBOBG,001,LNC,1,0BEBC2,0,0,0")
+                .unwrap()
+                .to_string()
+                .split(" expected")
+                .next(),
+            Some("at byte 17")
+        );
+        assert_eq!(diagnose(Profile::Rocket, b"LNCCMD,001,RQB,PING"), None);
+        assert_eq!(diagnose(Profile::Rocket, b"RQSTATE,013940.4184"), None);
+    }
+}
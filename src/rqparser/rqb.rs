@@ -1,64 +1,56 @@
+// OBG2 of the rocket's RedQueen, with the recording only
+// sent by newer firmware
 use super::{
-    command_id_parser, hex_i32_parser, hex_u16_parser, hex_u32_parser, hex_u64_parser,
-    hex_u8_parser, node_parser,
+    command_id_parser, hex_u16_parser, hex_u32_parser, hex_u8_parser, node_parser, string_parser,
+    ParseResult,
+};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    combinator::opt,
+    sequence::{terminated, tuple},
 };
-use nom::{branch::alt, bytes::complete::tag, sequence::tuple, IResult};
 
 use crate::{
-    observables::{
-        rqb::{RawObservablesGroup, RawObservablesGroup1, RawObservablesGroup2},
-        Ads1256Reading, ClkFreq, Timestamp,
-    },
+    observables::groups::{RawObservablesGroup, RawObservablesGroup2, RawRecording},
     rqprotocol::Node,
 };
 
-fn obg1_parser(s: &[u8]) -> IResult<&[u8], (Node, usize, Node, RawObservablesGroup)> {
-    // RQAOBG,123,LNC,1,0BEBC200,00000000AA894CC8,000669E2
-    let (rest, (source, _, command_id, _, recipient, _, clkfreq, _, timestamp, _, adc0, _, adc1)) =
+fn recording_parser(s: &[u8]) -> ParseResult<'_, RawRecording> {
+    // R,FLIGHT01.BIN,000000FF
+    let (rest, (state, _, filename_or_error, _, records)) = tuple((
+        alt((tag("E"), tag("P"), tag("U"), tag("R"))),
+        tag(","),
+        string_parser,
+        tag(","),
+        hex_u32_parser,
+    ))(s)?;
+    Ok((
+        rest,
+        RawRecording {
+            state: state[0],
+            filename_or_error,
+            records,
+        },
+    ))
+}
+
+pub fn obg2_parser(s: &[u8]) -> ParseResult<'_, (Node, usize, Node, RawObservablesGroup)> {
+    // RQBOBG,123,LNC,2,R,FLIGHT01.BIN,000000FF,ABCD,22
+    // or without the recording, RQBOBG,123,LNC,2,ABCD,22
+    let (rest, (source, _, command_id, _, recipient, _, recording, vbb_voltage, _, pyro_status)) =
         tuple((
             node_parser,
             tag(b"OBG,"),
             command_id_parser,
             tag(b","),
             node_parser,
-            tag(",1,"),
-            hex_u32_parser,
-            tag(","),
-            hex_u64_parser,
-            tag(","),
-            hex_i32_parser,
+            tag(",2,"),
+            opt(terminated(recording_parser, tag(","))),
+            hex_u16_parser,
             tag(","),
-            hex_i32_parser,
+            hex_u8_parser,
         ))(s)?;
-    Ok((
-        rest,
-        (
-            source,
-            command_id,
-            recipient,
-            RawObservablesGroup::OG1(RawObservablesGroup1 {
-                clkfreq: ClkFreq(clkfreq),
-                uptime: Timestamp(timestamp),
-                thrust: Ads1256Reading(adc0),
-                pressure: Ads1256Reading(adc1),
-            }),
-        ),
-    ))
-}
-
-fn obg2_parser(s: &[u8]) -> IResult<&[u8], (Node, usize, Node, RawObservablesGroup)> {
-    // RQAOBG,123,LNC,2,ABCD,22
-    let (rest, (source, _, command_id, _, recipient, _, vbb_voltage, _, pyro_status)) = tuple((
-        node_parser,
-        tag(b"OBG,"),
-        command_id_parser,
-        tag(b","),
-        node_parser,
-        tag(",2,"),
-        hex_u16_parser,
-        tag(","),
-        hex_u8_parser,
-    ))(s)?;
     Ok((
         rest,
         (
@@ -66,6 +58,8 @@ fn obg2_parser(s: &[u8]) -> IResult<&[u8], (Node, usize, Node, RawObservablesGro
             command_id,
             recipient,
             RawObservablesGroup::OG2(RawObservablesGroup2 {
+                recording,
+                anomalies: None,
                 vbb_voltage,
                 pyro_status,
             }),
@@ -73,6 +67,36 @@ fn obg2_parser(s: &[u8]) -> IResult<&[u8], (Node, usize, Node, RawObservablesGro
     ))
 }
 
-pub fn obg_parser(s: &[u8]) -> IResult<&[u8], (Node, usize, Node, RawObservablesGroup)> {
-    Ok(alt((obg1_parser, obg2_parser))(s)?)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_obg2_with_and_without_recording() {
+        let (_, (_, _, _, obg2)) =
+            obg2_parser(b"RQBOBG,123,LNC,2,R,FLIGHT01.BIN,000000FF,ABCD,22").unwrap();
+        assert_eq!(
+            obg2,
+            RawObservablesGroup::OG2(RawObservablesGroup2 {
+                recording: Some(RawRecording {
+                    state: b'R',
+                    filename_or_error: b"FLIGHT01.BIN".to_vec(),
+                    records: 255,
+                }),
+                anomalies: None,
+                vbb_voltage: 0xABCD,
+                pyro_status: 0x22,
+            })
+        );
+        let (_, (_, _, _, obg2)) = obg2_parser(b"RQBOBG,123,LNC,2,ABCD,22").unwrap();
+        assert_eq!(
+            obg2,
+            RawObservablesGroup::OG2(RawObservablesGroup2 {
+                recording: None,
+                anomalies: None,
+                vbb_voltage: 0xABCD,
+                pyro_status: 0x22,
+            })
+        );
+    }
 }
@@ -0,0 +1,49 @@
+// Field types of observable groups as sent by the firmware, fixed
+// width hex. Used by the groups declared with observable_group!.
+use nom::{bytes::complete::tag, sequence::tuple};
+
+use super::{
+    command_id_parser, hex_i32_parser, hex_u16_parser, hex_u32_parser, hex_u64_parser,
+    hex_u8_parser, node_parser, ParseResult,
+};
+use crate::rqprotocol::Node;
+
+pub trait Wire: Copy {
+    fn parse(s: &[u8]) -> ParseResult<'_, Self>;
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! wire {
+    ($type:ty, $parser:ident) => {
+        impl Wire for $type {
+            fn parse(s: &[u8]) -> ParseResult<'_, Self> {
+                $parser(s)
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+        }
+    };
+}
+
+wire!(u8, hex_u8_parser);
+wire!(u16, hex_u16_parser);
+wire!(u32, hex_u32_parser);
+wire!(i32, hex_i32_parser);
+wire!(u64, hex_u64_parser);
+
+// RQAOBG,123,LNC,4 up to the fields
+pub fn obg_header_parser(s: &[u8], group: u8) -> ParseResult<'_, (Node, usize, Node)> {
+    let group = group.to_string();
+    let (rest, (source, _, command_id, _, recipient, _, _)) = tuple((
+        node_parser,
+        tag(b"OBG,"),
+        command_id_parser,
+        tag(b","),
+        node_parser,
+        tag(b","),
+        tag(group.as_bytes()),
+    ))(s)?;
+    Ok((rest, (source, command_id, recipient)))
+}
@@ -1,24 +1,16 @@
-use std::{fmt::Display, ops::Range, time::Duration};
+use std::{fmt::Display, ops::Range, str::FromStr, time::Duration};
 
 use log::error;
 use serde::{de::Visitor, Deserialize, Serialize};
 
-#[cfg(feature = "test-stand")]
-use crate::observables::rqa::RawObservablesGroup;
-#[cfg(feature = "test-stand")]
-use crate::rqparser::rqa::obg_parser;
-
-#[cfg(feature = "rocket")]
-use crate::observables::rqb::RawObservablesGroup;
-#[cfg(feature = "rocket")]
-use crate::rqparser::rqb::obg_parser;
-
 use crate::{
-    observables::AdcGain,
+    observables::{groups::RawObservablesGroup, AdcGain, Profile},
     rqparser::{
-        ack_parser, command_parser, nibble_to_hex, one_hex_return_value_parser,
-        one_usize_return_value_parser, two_return_values_parser, verify_nmea_format,
-        NMEAFormatError, NMEAFormatter, MAX_BUFFER_SIZE,
+        ack_parser, command_parser, file_chunk_return_values_parser,
+        file_list_return_values_parser, file_open_return_values_parser, nibble_to_hex, obg_parser,
+        one_hex_return_value_parser, one_usize_return_value_parser, two_return_values_parser,
+        verify_nmea_format, version_return_values_parser, NMEAFormatError, NMEAFormatter, NomError,
+        MAX_BUFFER_SIZE,
     },
 };
 
@@ -41,6 +33,69 @@ pub enum Error {
     InvalidAssociation(Node, Node, usize, usize),
 }
 
+// The protocol version we speak. Nodes predating the
+// VERSION command are considered to be version 0.
+pub const PROTOCOL_VERSION: usize = 1;
+
+// Bitfield of features a node reports to support
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capabilities(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProtocolVersion {
+    pub version: usize,
+    pub capabilities: Capabilities,
+}
+
+impl Capabilities {
+    pub const OBG1: u32 = 1 << 0;
+    pub const OBG2: u32 = 1 << 1;
+    pub const RF_SILENCE: u32 = 1 << 2;
+    pub const FILE_READ: u32 = 1 << 3;
+    pub const RECORDING: u32 = 1 << 4;
+    pub const ENCRYPTION: u32 = 1 << 5;
+    pub const FILE_MANAGE: u32 = 1 << 6;
+
+    pub const NAMES: [(&'static str, u32); 7] = [
+        ("OBG1", Self::OBG1),
+        ("OBG2", Self::OBG2),
+        ("RF_SILENCE", Self::RF_SILENCE),
+        ("FILE_READ", Self::FILE_READ),
+        ("RECORDING", Self::RECORDING),
+        ("ENCRYPTION", Self::ENCRYPTION),
+        ("FILE_MANAGE", Self::FILE_MANAGE),
+    ];
+
+    pub fn all() -> Self {
+        Self(Self::NAMES.iter().fold(0, |all, (_, bit)| all | bit))
+    }
+
+    pub fn name(capability: u32) -> Option<&'static str> {
+        Self::NAMES
+            .iter()
+            .find(|(_, bit)| *bit == capability)
+            .map(|(name, _)| *name)
+    }
+
+    // What firmware before the VERSION command implemented
+    pub fn legacy() -> Self {
+        Self(Self::OBG1 | Self::OBG2 | Self::RF_SILENCE)
+    }
+
+    pub fn supports(&self, capability: u32) -> bool {
+        self.0 & capability == capability
+    }
+}
+
+impl ProtocolVersion {
+    pub fn legacy() -> Self {
+        Self {
+            version: 0,
+            capabilities: Capabilities::legacy(),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct RqTimestamp {
     pub hour: Option<u8>,
@@ -84,6 +139,34 @@ impl std::fmt::Debug for Node {
     }
 }
 
+impl Display for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Node::RedQueen(id) => write!(f, "RQ{}", *id as char),
+            Node::Farduino(id) => write!(f, "FD{}", *id as char),
+            Node::LaunchControl => write!(f, "LNC"),
+        }
+    }
+}
+
+impl FromStr for Node {
+    type Err = String;
+
+    // Ids are a single letter or digit
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let valid_id = |id: u8| id.is_ascii_alphanumeric();
+        match s.len() {
+            3 if s.is_ascii() && valid_id(s.as_bytes()[2]) => match &s[0..2] {
+                "RQ" => Ok(Node::RedQueen(s.as_bytes()[2])),
+                "FD" => Ok(Node::Farduino(s.as_bytes()[2])),
+                _ if s == "LNC" => Ok(Node::LaunchControl),
+                _ => Err(format!("Unknown node identifier '{}'", s)),
+            },
+            _ => Err(format!("Unknown node identifier '{}'", s)),
+        }
+    }
+}
+
 impl Serialize for Node {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -116,20 +199,7 @@ impl<'de> Visitor<'de> for NodeVisitor {
     where
         E: serde::de::Error,
     {
-        match v.len() {
-            3 => match &v[0..2] {
-                "RQ" => Ok(Node::RedQueen(v.as_bytes()[2])),
-                "FD" => Ok(Node::Farduino(v.as_bytes()[2])),
-                _ => {
-                    if v == "LNC" {
-                        Ok(Node::LaunchControl)
-                    } else {
-                        Err(E::custom(format!("Unknown node identifier '{}'", v)))
-                    }
-                }
-            },
-            _ => Err(E::custom(format!("Unknown node identifier '{}'", v))),
-        }
+        v.parse().map_err(E::custom)
     }
 }
 
@@ -153,7 +223,7 @@ pub struct AckHeader {
 }
 
 /// All commands known to the RQ protocol
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Command {
     Reset(AdcGain),
     LaunchSecretPartial(u8),
@@ -163,6 +233,19 @@ pub enum Command {
     Ping,
     ObservableGroup(usize),
     EnterRFSilence,
+    Version,
+    FileOpen(String),
+    // Offset and length in bytes
+    FileRead(usize, usize),
+    FileClose,
+    // The entry at the index, one per command to fit a sentence
+    FileList(usize),
+    FileDelete(String),
+    // Onboard recording, reported back through OBG2
+    RecordStart,
+    RecordStop,
+    // Re-locks the pyros, sent ahead of anything queued
+    Abort,
 }
 
 impl Display for Error {
@@ -182,6 +265,88 @@ pub enum Response {
     ObservableGroup(RawObservablesGroup),
     ObservableGroupAck,
     RFSilenceAck,
+    VersionAck(ProtocolVersion),
+    FileOpenAck {
+        size: usize,
+        checksum: u32,
+    },
+    FileReadAck {
+        offset: usize,
+        data: Vec<u8>,
+    },
+    FileCloseAck,
+    // Past the last entry there is none
+    FileListAck {
+        index: usize,
+        count: usize,
+        entry: Option<FileEntry>,
+    },
+    FileDeleteAck,
+    RecordStartAck,
+    RecordStopAck,
+    AbortAck,
+}
+
+// How a parameter is put on the wire
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    // Two digits
+    Hex8,
+    // Eight digits
+    Hex32,
+    Decimal,
+    // Without commas
+    Text,
+    // Two digits per byte, without separators
+    HexBytes,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: &'static str,
+    pub encoding: Encoding,
+    // Only trailing ones
+    pub optional: bool,
+}
+
+// The value of a command parameter, marshalled by its encoding
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Argument<'a> {
+    Hex8(u8),
+    Decimal(usize),
+    Text(&'a str),
+}
+
+impl Argument<'_> {
+    pub fn encoding(&self) -> Encoding {
+        match self {
+            Argument::Hex8(_) => Encoding::Hex8,
+            Argument::Decimal(_) => Encoding::Decimal,
+            Argument::Text(_) => Encoding::Text,
+        }
+    }
+}
+
+fn parameter(name: &'static str, encoding: Encoding) -> Parameter {
+    Parameter {
+        name,
+        encoding,
+        optional: false,
+    }
+}
+
+fn optional(name: &'static str, encoding: Encoding) -> Parameter {
+    Parameter {
+        optional: true,
+        ..parameter(name, encoding)
+    }
+}
+
+// Of the node's storage
+#[derive(Debug, PartialEq, Clone)]
+pub struct FileEntry {
+    pub name: String,
+    pub size: usize,
 }
 
 // Represents the state waiting for the
@@ -196,10 +361,19 @@ enum CommandProcessor {
     PingAck,
     ObservableGroupAck(usize),
     RFSilenceAck,
+    VersionAck,
+    FileOpenAck,
+    FileReadAck(usize),
+    FileCloseAck,
+    FileListAck(usize),
+    FileDeleteAck,
+    RecordStartAck,
+    RecordStopAck,
+    AbortAck,
 }
 
 impl Command {
-    fn verb(&self) -> &'static [u8] {
+    pub fn verb(&self) -> &'static [u8] {
         match self {
             Command::Reset(_) => b"RESET",
             Command::LaunchSecretPartial(_) => b"SECRET_A",
@@ -209,6 +383,79 @@ impl Command {
             Command::Ping => b"PING",
             Command::ObservableGroup(_) => b"OBG",
             Command::EnterRFSilence => b"RF_SILENCE",
+            Command::Version => b"VERSION",
+            Command::FileOpen(_) => b"FILE_OPEN",
+            Command::FileRead(_, _) => b"FILE_READ",
+            Command::FileClose => b"FILE_CLOSE",
+            Command::FileList(_) => b"FILE_LIST",
+            Command::FileDelete(_) => b"FILE_DELETE",
+            Command::RecordStart => b"RECORD_START",
+            Command::RecordStop => b"RECORD_STOP",
+            Command::Abort => b"ABORT",
+        }
+    }
+
+    // In the order they follow the verb
+    pub fn arguments(&self) -> Vec<(&'static str, Argument<'_>)> {
+        match self {
+            Command::Reset(gain) => vec![("adc_gain", Argument::Hex8((*gain).clone().into()))],
+            Command::LaunchSecretPartial(a) => vec![("secret_a", Argument::Hex8(*a))],
+            Command::LaunchSecretFull(a, b) => vec![
+                ("secret_a", Argument::Hex8(*a)),
+                ("secret_b", Argument::Hex8(*b)),
+            ],
+            Command::ObservableGroup(group) => vec![("group", Argument::Decimal(*group))],
+            Command::FileOpen(name) | Command::FileDelete(name) => {
+                vec![("name", Argument::Text(name))]
+            }
+            Command::FileRead(offset, length) => vec![
+                ("offset", Argument::Decimal(*offset)),
+                ("length", Argument::Decimal(*length)),
+            ],
+            Command::FileList(index) => vec![("index", Argument::Decimal(*index))],
+            Command::UnlockPyros
+            | Command::Ignition
+            | Command::Ping
+            | Command::EnterRFSilence
+            | Command::Version
+            | Command::FileClose
+            | Command::RecordStart
+            | Command::RecordStop
+            | Command::Abort => vec![],
+        }
+    }
+
+    pub fn parameters(&self) -> Vec<Parameter> {
+        self.arguments()
+            .into_iter()
+            .map(|(name, argument)| parameter(name, argument.encoding()))
+            .collect()
+    }
+
+    // Of the ACK, after its header
+    pub fn acknowledged_with(&self) -> Vec<Parameter> {
+        self.processor().parameters()
+    }
+
+    // Nodes without it don't know the command
+    pub fn capability(&self) -> Option<u32> {
+        match self {
+            Command::EnterRFSilence => Some(Capabilities::RF_SILENCE),
+            Command::FileOpen(_)
+            | Command::FileRead(_, _)
+            | Command::FileClose
+            | Command::FileList(_) => Some(Capabilities::FILE_READ),
+            Command::FileDelete(_) => Some(Capabilities::FILE_MANAGE),
+            Command::RecordStart | Command::RecordStop => Some(Capabilities::RECORDING),
+            Command::Reset(_)
+            | Command::LaunchSecretPartial(_)
+            | Command::UnlockPyros
+            | Command::LaunchSecretFull(_, _)
+            | Command::Ignition
+            | Command::Ping
+            | Command::ObservableGroup(_)
+            | Command::Version
+            | Command::Abort => None,
         }
     }
 
@@ -222,6 +469,15 @@ impl Command {
             Command::Ping => CommandProcessor::PingAck,
             Command::ObservableGroup(g) => CommandProcessor::ObservableGroupAck(*g),
             Command::EnterRFSilence => CommandProcessor::RFSilenceAck,
+            Command::Version => CommandProcessor::VersionAck,
+            Command::FileOpen(_) => CommandProcessor::FileOpenAck,
+            Command::FileRead(offset, _) => CommandProcessor::FileReadAck(*offset),
+            Command::FileClose => CommandProcessor::FileCloseAck,
+            Command::FileList(index) => CommandProcessor::FileListAck(*index),
+            Command::FileDelete(_) => CommandProcessor::FileDeleteAck,
+            Command::RecordStart => CommandProcessor::RecordStartAck,
+            Command::RecordStop => CommandProcessor::RecordStopAck,
+            Command::Abort => CommandProcessor::AbortAck,
         }
     }
     fn process_response(
@@ -240,7 +496,7 @@ impl Command {
         transaction: &Transaction,
         contents: &[u8],
     ) -> Result<(TransactionState, Response), Error> {
-        match obg_parser(contents) {
+        match obg_parser(transaction.profile, contents) {
             Ok((_rest, (_source, _command_id, _sender, raw))) => {
                 // TODO: a lot of checking!
                 Ok((TransactionState::Alive, Response::ObservableGroup(raw)))
@@ -307,6 +563,8 @@ pub struct Transaction {
     pub id: usize,
     pub command: Command,
     state: TransactionState,
+    // How observables in the response are parsed
+    profile: Profile,
 }
 
 pub trait Marshal {
@@ -344,8 +602,8 @@ impl From<NMEAFormatError<'_>> for Error {
     }
 }
 
-impl From<nom::Err<nom::error::Error<&[u8]>>> for Error {
-    fn from(value: nom::Err<nom::error::Error<&[u8]>>) -> Self {
+impl From<nom::Err<NomError<'_>>> for Error {
+    fn from(value: nom::Err<NomError<'_>>) -> Self {
         error!("nom error: {:?}", value.to_string());
         Error::ParseError
     }
@@ -445,6 +703,20 @@ fn number_length(value: usize) -> usize {
     }
 }
 
+fn u32_parameter(
+    buffer: &mut [u8],
+    range: Range<usize>,
+    param: u32,
+) -> Result<Range<usize>, Error> {
+    range_check_buffer_for_length(&range, buffer, 9)?;
+    let mut data: [u8; 9] = [b','; 9];
+    for i in 0..8 {
+        data[8 - i] = nibble_to_hex((param >> (i * 4) & 0xf) as u8);
+    }
+    buffer[range.clone()][0..9].copy_from_slice(&data);
+    Ok(range.start + 9..range.end)
+}
+
 fn usize_parameter(
     buffer: &mut [u8],
     range: Range<usize>,
@@ -470,19 +742,18 @@ impl Marshal for Command {
         buffer: &'a mut [u8],
         range: Range<usize>,
     ) -> Result<Range<usize>, Error> {
-        match self {
-            Command::Reset(gain) => u8_parameter(buffer, range, (*gain).clone().into()),
-            Command::LaunchSecretPartial(a) => u8_parameter(buffer, range, *a),
-            Command::UnlockPyros => Ok(range),
-            Command::LaunchSecretFull(a, b) => {
-                let range = u8_parameter(buffer, range, *a)?;
-                u8_parameter(buffer, range, *b)
-            }
-            Command::Ignition => Ok(range),
-            Command::Ping => Ok(range),
-            Command::ObservableGroup(group) => usize_parameter(buffer, range, *group),
-            Command::EnterRFSilence => Ok(range),
+        let mut range = range;
+        for (_, argument) in self.arguments() {
+            range = match argument {
+                Argument::Hex8(value) => u8_parameter(buffer, range, value)?,
+                Argument::Decimal(value) => usize_parameter(buffer, range, value)?,
+                Argument::Text(text) => {
+                    let range = append_bytes(buffer, range, b",")?;
+                    append_bytes(buffer, range, text.as_bytes())?
+                }
+            };
         }
+        Ok(range)
     }
 
     fn to_acknowledgement<'a>(
@@ -490,7 +761,15 @@ impl Marshal for Command {
         buffer: &'a mut [u8],
         range: Range<usize>,
     ) -> Result<Range<usize>, Error> {
-        self.to_command(buffer, range)
+        match self {
+            // When acknowledging ourselves, e.g. in simulations,
+            // we report what this frontend knows.
+            Command::Version => {
+                let range = usize_parameter(buffer, range, PROTOCOL_VERSION)?;
+                u32_parameter(buffer, range, Capabilities::all().0)
+            }
+            _ => self.to_command(buffer, range),
+        }
     }
 }
 
@@ -555,9 +834,15 @@ impl Transaction {
             id,
             command,
             state: TransactionState::Alive,
+            profile: Profile::default(),
         }
     }
 
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        self.profile = profile;
+        self
+    }
+
     pub fn from_sentence(sentence: &[u8]) -> Result<Self, Error> {
         let (_rest, transaction) = command_parser(sentence)?;
         Ok(transaction)
@@ -598,6 +883,46 @@ impl Transaction {
 }
 
 impl CommandProcessor {
+    fn parameters(&self) -> Vec<Parameter> {
+        match self {
+            CommandProcessor::ResetAck => vec![parameter("adc_gain", Encoding::Hex8)],
+            CommandProcessor::LaunchSecretPartial(_) => vec![parameter("secret_a", Encoding::Hex8)],
+            CommandProcessor::LaunchSecretFull(_, _) => vec![
+                parameter("secret_a", Encoding::Hex8),
+                parameter("secret_b", Encoding::Hex8),
+            ],
+            CommandProcessor::ObservableGroupAck(_) => vec![parameter("group", Encoding::Decimal)],
+            CommandProcessor::VersionAck => vec![
+                parameter("protocol_version", Encoding::Decimal),
+                parameter("capabilities", Encoding::Hex32),
+            ],
+            CommandProcessor::FileOpenAck => vec![
+                parameter("size", Encoding::Decimal),
+                parameter("checksum", Encoding::Hex32),
+            ],
+            CommandProcessor::FileReadAck(_) => vec![
+                parameter("offset", Encoding::Decimal),
+                parameter("data", Encoding::HexBytes),
+            ],
+            // Past the last file without its entry
+            CommandProcessor::FileListAck(_) => vec![
+                parameter("index", Encoding::Decimal),
+                parameter("count", Encoding::Decimal),
+                optional("size", Encoding::Decimal),
+                optional("name", Encoding::Text),
+            ],
+            CommandProcessor::UnlockPyrosAck
+            | CommandProcessor::IgnitionAck
+            | CommandProcessor::PingAck
+            | CommandProcessor::RFSilenceAck
+            | CommandProcessor::FileCloseAck
+            | CommandProcessor::FileDeleteAck
+            | CommandProcessor::RecordStartAck
+            | CommandProcessor::RecordStopAck
+            | CommandProcessor::AbortAck => vec![],
+        }
+    }
+
     fn process_response<'a>(&self, params: &'a [u8]) -> Result<(&'a [u8], Response), Error> {
         match self {
             CommandProcessor::LaunchSecretPartial(a) => {
@@ -632,6 +957,52 @@ impl CommandProcessor {
                 }
             }
             CommandProcessor::RFSilenceAck => Ok((params, Response::RFSilenceAck)),
+            CommandProcessor::VersionAck => {
+                let (rest, (version, capabilities)) = version_return_values_parser(params)?;
+                Ok((
+                    rest,
+                    Response::VersionAck(ProtocolVersion {
+                        version,
+                        capabilities: Capabilities(capabilities),
+                    }),
+                ))
+            }
+            CommandProcessor::FileOpenAck => {
+                let (rest, (size, checksum)) = file_open_return_values_parser(params)?;
+                Ok((rest, Response::FileOpenAck { size, checksum }))
+            }
+            CommandProcessor::FileReadAck(requested) => {
+                let (rest, (offset, data)) = file_chunk_return_values_parser(params)?;
+                if offset == *requested {
+                    Ok((rest, Response::FileReadAck { offset, data }))
+                } else {
+                    Err(Error::ParseError)
+                }
+            }
+            CommandProcessor::FileCloseAck => Ok((params, Response::FileCloseAck)),
+            CommandProcessor::FileListAck(requested) => {
+                let (rest, (index, count, entry)) = file_list_return_values_parser(params)?;
+                if index == *requested {
+                    let entry = entry.map(|(size, name)| FileEntry {
+                        name: String::from_utf8_lossy(&name).into(),
+                        size,
+                    });
+                    Ok((
+                        rest,
+                        Response::FileListAck {
+                            index,
+                            count,
+                            entry,
+                        },
+                    ))
+                } else {
+                    Err(Error::ParseError)
+                }
+            }
+            CommandProcessor::FileDeleteAck => Ok((params, Response::FileDeleteAck)),
+            CommandProcessor::RecordStartAck => Ok((params, Response::RecordStartAck)),
+            CommandProcessor::RecordStopAck => Ok((params, Response::RecordStopAck)),
+            CommandProcessor::AbortAck => Ok((params, Response::AbortAck)),
         }
     }
 }
@@ -643,6 +1014,18 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_node_from_str() {
+        assert_eq!("RQB".parse(), Ok(Node::RedQueen(b'B')));
+        assert_eq!("FDT".parse(), Ok(Node::Farduino(b'T')));
+        assert_eq!("LNC".parse(), Ok(Node::LaunchControl));
+        assert!("RQ".parse::<Node>().is_err());
+        assert!("XYZ".parse::<Node>().is_err());
+        assert!("FD ".parse::<Node>().is_err());
+        assert!("xÄ".parse::<Node>().is_err());
+        assert_eq!("FD1".parse(), Ok(Node::Farduino(b'1')));
+    }
+
     #[test]
     fn test_launch_secret_partial() {
         let mut t = Transaction::from_sentence(b"LNCCMD,123,RQA,SECRET_A,3F").unwrap();
@@ -705,6 +1088,198 @@ mod tests {
         assert_eq!(t.state(), TransactionState::Dead);
     }
 
+    #[test]
+    fn test_record_start() {
+        let mut t = Transaction::from_sentence(b"LNCCMD,126,RQB,RECORD_START").unwrap();
+        let mut dest: [u8; MAX_BUFFER_SIZE] = [0; MAX_BUFFER_SIZE];
+        let result = t.commandeer(&mut dest).unwrap();
+        assert_eq!(result, b"$LNCCMD,126,RQB,RECORD_START*41\r\n".as_slice());
+        assert_eq!(
+            t.process_response(b"$RQBACK,126,LNC*7C\r\n"),
+            Ok(Response::RecordStartAck)
+        );
+        assert_eq!(t.state(), TransactionState::Dead);
+    }
+
+    #[test]
+    fn test_version() {
+        let mut t = Transaction::from_sentence(b"LNCCMD,123,RQA,VERSION").unwrap();
+        let mut dest: [u8; MAX_BUFFER_SIZE] = [0; MAX_BUFFER_SIZE];
+        let result = t.commandeer(&mut dest).unwrap();
+        assert_eq!(result, b"$LNCCMD,123,RQA,VERSION*0F\r\n".as_slice());
+        let mut formatter = NMEAFormatter::default();
+        formatter
+            .format_sentence(format!("RQAACK,123,LNC,1,{:08X}", Capabilities::all().0).as_bytes())
+            .unwrap();
+        assert_eq!(
+            t.acknowledge(&mut dest).unwrap(),
+            formatter.buffer().unwrap()
+        );
+        assert_eq!(
+            t.process_response(b"$RQAACK,123,LNC,2,00000003*4B\r\n"),
+            Ok(Response::VersionAck(ProtocolVersion {
+                version: 2,
+                capabilities: Capabilities(Capabilities::OBG1 | Capabilities::OBG2),
+            }))
+        );
+        assert_eq!(t.state(), TransactionState::Dead);
+    }
+
+    // What answering VERSION ourselves reports, e.g. in simulations
+    fn acknowledged_capabilities() -> Capabilities {
+        let mut t = Transaction::new(
+            Node::LaunchControl,
+            Node::RedQueen(b'A'),
+            1,
+            Command::Version,
+        );
+        let mut dest: [u8; MAX_BUFFER_SIZE] = [0; MAX_BUFFER_SIZE];
+        let ack = t.acknowledge(&mut dest).unwrap().to_vec();
+        match t.process_response(&ack) {
+            Ok(Response::VersionAck(version)) => version.capabilities,
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_file_read_capability() {
+        assert!(acknowledged_capabilities().supports(Capabilities::FILE_READ));
+        assert_eq!(
+            Command::FileRead(0, 32).capability(),
+            Some(Capabilities::FILE_READ)
+        );
+    }
+
+    #[test]
+    fn test_recording_capability() {
+        assert!(acknowledged_capabilities().supports(Capabilities::RECORDING));
+        assert_eq!(
+            Command::RecordStart.capability(),
+            Some(Capabilities::RECORDING)
+        );
+    }
+
+    #[test]
+    fn test_encryption_capability() {
+        assert!(acknowledged_capabilities().supports(Capabilities::ENCRYPTION));
+        assert!(!Capabilities::legacy().supports(Capabilities::ENCRYPTION));
+    }
+
+    #[test]
+    fn test_file_manage_capability() {
+        assert!(acknowledged_capabilities().supports(Capabilities::FILE_MANAGE));
+        assert_eq!(
+            Command::FileDelete("REC001.DAT".into()).capability(),
+            Some(Capabilities::FILE_MANAGE)
+        );
+    }
+
+    #[test]
+    fn test_file_read() {
+        let mut dest: [u8; MAX_BUFFER_SIZE] = [0; MAX_BUFFER_SIZE];
+        let mut t = Transaction::from_sentence(b"LNCCMD,123,RQB,FILE_OPEN,TEST.DAT").unwrap();
+        assert_eq!(t.command, Command::FileOpen("TEST.DAT".into()));
+        let mut formatter = NMEAFormatter::default();
+        formatter
+            .format_sentence(b"RQBACK,123,LNC,48,CBF43926")
+            .unwrap();
+        assert_eq!(
+            t.process_response(formatter.buffer().unwrap()),
+            Ok(Response::FileOpenAck {
+                size: 48,
+                checksum: 0xCBF43926
+            })
+        );
+
+        let mut t = Transaction::from_sentence(b"LNCCMD,124,RQB,FILE_READ,24,24").unwrap();
+        let result = t.commandeer(&mut dest).unwrap();
+        assert!(result.starts_with(b"$LNCCMD,124,RQB,FILE_READ,24,24*"));
+        formatter
+            .format_sentence(b"RQBACK,124,LNC,24,DEADBEEF")
+            .unwrap();
+        assert_eq!(
+            t.process_response(formatter.buffer().unwrap()),
+            Ok(Response::FileReadAck {
+                offset: 24,
+                data: vec![0xDE, 0xAD, 0xBE, 0xEF]
+            })
+        );
+        // The chunk has to be the one we asked for
+        let mut t = Transaction::from_sentence(b"LNCCMD,125,RQB,FILE_READ,24,24").unwrap();
+        formatter
+            .format_sentence(b"RQBACK,125,LNC,0,DEADBEEF")
+            .unwrap();
+        assert_eq!(
+            t.process_response(formatter.buffer().unwrap()),
+            Err(Error::ParseError)
+        );
+    }
+
+    #[test]
+    fn test_file_list_and_delete() {
+        let mut dest: [u8; MAX_BUFFER_SIZE] = [0; MAX_BUFFER_SIZE];
+        let mut t = Transaction::from_sentence(b"LNCCMD,126,RQB,FILE_LIST,1").unwrap();
+        assert_eq!(t.command, Command::FileList(1));
+        let result = t.commandeer(&mut dest).unwrap();
+        assert!(result.starts_with(b"$LNCCMD,126,RQB,FILE_LIST,1*"));
+        let mut formatter = NMEAFormatter::default();
+        formatter
+            .format_sentence(b"RQBACK,126,LNC,1,2,4096,REC002.DAT")
+            .unwrap();
+        assert_eq!(
+            t.process_response(formatter.buffer().unwrap()),
+            Ok(Response::FileListAck {
+                index: 1,
+                count: 2,
+                entry: Some(FileEntry {
+                    name: "REC002.DAT".into(),
+                    size: 4096
+                })
+            })
+        );
+        let mut t = Transaction::from_sentence(b"LNCCMD,127,RQB,FILE_LIST,2").unwrap();
+        formatter.format_sentence(b"RQBACK,127,LNC,2,2").unwrap();
+        assert_eq!(
+            t.process_response(formatter.buffer().unwrap()),
+            Ok(Response::FileListAck {
+                index: 2,
+                count: 2,
+                entry: None
+            })
+        );
+        // The entry has to be the one we asked for
+        let mut t = Transaction::from_sentence(b"LNCCMD,128,RQB,FILE_LIST,0").unwrap();
+        formatter.format_sentence(b"RQBACK,128,LNC,1,2").unwrap();
+        assert_eq!(
+            t.process_response(formatter.buffer().unwrap()),
+            Err(Error::ParseError)
+        );
+
+        let mut t = Transaction::from_sentence(b"LNCCMD,129,RQB,FILE_DELETE,REC001.DAT").unwrap();
+        assert_eq!(t.command, Command::FileDelete("REC001.DAT".into()));
+        let result = t.commandeer(&mut dest).unwrap();
+        assert!(result.starts_with(b"$LNCCMD,129,RQB,FILE_DELETE,REC001.DAT*"));
+        formatter.format_sentence(b"RQBACK,129,LNC").unwrap();
+        assert_eq!(
+            t.process_response(formatter.buffer().unwrap()),
+            Ok(Response::FileDeleteAck)
+        );
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let caps = Capabilities(Capabilities::OBG1);
+        assert!(caps.supports(Capabilities::OBG1));
+        assert!(!caps.supports(Capabilities::RF_SILENCE));
+        assert!(Capabilities::legacy().supports(Capabilities::RF_SILENCE));
+        // Everything named, more than the legacy firmware
+        for (name, bit) in Capabilities::NAMES {
+            assert!(Capabilities::all().supports(bit));
+            assert_eq!(Capabilities::name(bit), Some(name));
+        }
+        assert_ne!(Capabilities::all(), Capabilities::legacy());
+    }
+
     #[test]
     fn test_observable_group_immediate_ack() {
         let mut t = Transaction::from_sentence(b"LNCCMD,123,RQA,OBG,1").unwrap();
@@ -0,0 +1,468 @@
+// The RQ protocol as a specification for the firmware and third-party
+// tooling, one machine readable JSON file and one document. It's
+// generated from the protocol code: the parameters are matched per
+// command, so a new command doesn't compile without them, and the
+// example sentences are marshalled by the transactions themselves.
+// The RQ protocol as a specification for the firmware and third-party
+// tooling, a JSON Schema of the command and acknowledgement sentences
+// and a document. Verbs, parameters and capabilities are the ones of
+// rqprotocol.rs, the example sentences are marshalled by the
+// transactions themselves. The framing and observables layouts are
+// written down here, the tests read their examples back.
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+
+use crate::observables::AdcGain;
+use crate::rqparser::{NMEAFormatter, MAX_BUFFER_SIZE};
+use crate::rqprotocol::{
+    Capabilities, Command, Encoding, Node, Parameter, Transaction, PROTOCOL_VERSION,
+};
+
+const EXAMPLE_ID: usize = 123;
+const EXAMPLE_SOURCE: Node = Node::LaunchControl;
+const EXAMPLE_RECIPIENT: Node = Node::RedQueen(b'B');
+
+const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+// Of any node, see rqparser
+const NODE_PATTERN: &str = "(LNC|RQ[A-Za-z]|FD[A-Za-z])";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandSpec {
+    pub verb: String,
+    pub parameters: Vec<Parameter>,
+    // Of the ACK, after the header
+    pub response: Vec<Parameter>,
+    pub capability: Option<&'static str>,
+    pub example: String,
+    pub example_ack: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SentenceFormat {
+    pub name: &'static str,
+    pub layout: &'static str,
+    pub example: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtocolSpec {
+    pub protocol_version: usize,
+    pub frontend_version: &'static str,
+    // Including the framing
+    pub max_sentence_length: usize,
+    pub sentences: Vec<SentenceFormat>,
+    pub commands: Vec<CommandSpec>,
+}
+
+fn describe(encoding: Encoding) -> &'static str {
+    match encoding {
+        Encoding::Hex8 => "2 hex digits",
+        Encoding::Hex32 => "8 hex digits",
+        Encoding::Decimal => "decimal",
+        Encoding::Text => "text without commas",
+        Encoding::HexBytes => "2 hex digits per byte",
+    }
+}
+
+fn pattern(encoding: Encoding) -> &'static str {
+    match encoding {
+        Encoding::Hex8 => "[0-9A-F]{2}",
+        Encoding::Hex32 => "[0-9A-F]{8}",
+        Encoding::Decimal => "[0-9]+",
+        Encoding::Text => "[^,*]*",
+        Encoding::HexBytes => "([0-9A-F]{2})*",
+    }
+}
+
+// One of each command, to be listed
+fn commands() -> Vec<Command> {
+    vec![
+        Command::Reset(AdcGain::Gain1),
+        Command::LaunchSecretPartial(0x12),
+        Command::UnlockPyros,
+        Command::LaunchSecretFull(0x12, 0x34),
+        Command::Ignition,
+        Command::Ping,
+        Command::ObservableGroup(1),
+        Command::EnterRFSilence,
+        Command::Version,
+        Command::FileOpen("FLIGHT01.BIN".into()),
+        Command::FileRead(0, 32),
+        Command::FileClose,
+        Command::FileList(0),
+        Command::FileDelete("FLIGHT01.BIN".into()),
+        Command::RecordStart,
+        Command::RecordStop,
+        Command::Abort,
+    ]
+}
+
+// Where the node doesn't echo the command's parameters. A command
+// missing here fails the test reading the examples back.
+fn example_response(command: &Command) -> Option<&'static str> {
+    match command {
+        Command::FileOpen(_) => Some(",4096,1C291CA3"),
+        Command::FileRead(_, _) => Some(",0,DEADBEEF"),
+        Command::FileList(_) => Some(",0,1,4096,FLIGHT01.BIN"),
+        Command::FileDelete(_) => Some(""),
+        _ => None,
+    }
+}
+
+fn sentence(contents: &str) -> String {
+    let mut formatter = NMEAFormatter::default();
+    formatter.format_sentence(contents.as_bytes()).unwrap();
+    String::from_utf8_lossy(formatter.buffer().unwrap()).into()
+}
+
+fn command_spec(command: Command) -> CommandSpec {
+    let transaction = Transaction::new(EXAMPLE_SOURCE, EXAMPLE_RECIPIENT, EXAMPLE_ID, command);
+    let mut dest = [0; MAX_BUFFER_SIZE];
+    let example = String::from_utf8_lossy(transaction.commandeer(&mut dest).unwrap()).into();
+    let example_ack = match example_response(&transaction.command) {
+        Some(values) => sentence(&format!(
+            "{}ACK,{:03},{}{}",
+            EXAMPLE_RECIPIENT, EXAMPLE_ID, EXAMPLE_SOURCE, values
+        )),
+        None => String::from_utf8_lossy(transaction.acknowledge(&mut dest).unwrap()).into(),
+    };
+    let command = &transaction.command;
+    CommandSpec {
+        verb: String::from_utf8_lossy(command.verb()).into(),
+        parameters: command.parameters(),
+        response: command.acknowledged_with(),
+        capability: command.capability().and_then(Capabilities::name),
+        example,
+        example_ack,
+    }
+}
+
+// Optional parameters in brackets
+fn layout(head: &str, parameters: &[Parameter]) -> String {
+    let field = |parameter: &Parameter| format!(",<{}>", parameter.name);
+    let required: String = parameters
+        .iter()
+        .filter(|p| !p.optional)
+        .map(field)
+        .collect();
+    let optional: String = parameters
+        .iter()
+        .filter(|p| p.optional)
+        .map(field)
+        .collect();
+    match optional.is_empty() {
+        true => format!("{}{}", head, required),
+        false => format!("{}{}[{}]", head, required, optional),
+    }
+}
+
+// Of the whole sentence, framing included
+fn sentence_pattern(head: &str, parameters: &[Parameter]) -> String {
+    let field = |parameter: &Parameter| format!(",{}", pattern(parameter.encoding));
+    let required: String = parameters
+        .iter()
+        .filter(|p| !p.optional)
+        .map(field)
+        .collect();
+    let optional: String = parameters
+        .iter()
+        .filter(|p| p.optional)
+        .map(field)
+        .collect();
+    let optional = match optional.is_empty() {
+        true => optional,
+        false => format!("({})?", optional),
+    };
+    format!(
+        "^\\${}{}{}\\*[0-9A-F]{{2}}\\r\\n$",
+        head, required, optional
+    )
+}
+
+impl CommandSpec {
+    pub fn layout(&self) -> String {
+        layout(
+            &format!("<source>CMD,<3 digit id>,<recipient>,{}", self.verb),
+            &self.parameters,
+        )
+    }
+
+    pub fn ack_layout(&self) -> String {
+        layout(
+            "<recipient>ACK,<3 digit id of the command>,<source of the command>",
+            &self.response,
+        )
+    }
+
+    fn pattern(&self) -> String {
+        sentence_pattern(
+            &format!(
+                "{}CMD,[0-9]{{3}},{},{}",
+                NODE_PATTERN, NODE_PATTERN, self.verb
+            ),
+            &self.parameters,
+        )
+    }
+
+    fn ack_pattern(&self) -> String {
+        sentence_pattern(
+            &format!("{}ACK,[0-9]{{3}},{}", NODE_PATTERN, NODE_PATTERN),
+            &self.response,
+        )
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Parameters: {}. Capability: {}.",
+            describe_parameters(&self.parameters),
+            self.capability.unwrap_or("always")
+        )
+    }
+}
+
+fn sentences() -> Vec<SentenceFormat> {
+    vec![
+        SentenceFormat {
+            name: "Framing",
+            layout: "$<contents>*<2 hex digits XOR checksum of the contents>\\r\\n",
+            example: sentence("LNCCMD,123,RQB,PING"),
+        },
+        SentenceFormat {
+            name: "Refusal",
+            layout: "<node>NAK,<3 digit id of the command>,<source of the command>",
+            example: sentence("RQBNAK,123,LNC"),
+        },
+        SentenceFormat {
+            name: "Observables group 1",
+            layout: "<node>OBG,<id>,<source>,1,<clock frequency hex32>,<timestamp 16 hex digits>,<adc0 hex32>,<adc1 hex32>",
+            example: sentence("RQAOBG,123,LNC,1,0BEBC200,00000000AA894CC8,000669E2,00000001"),
+        },
+        SentenceFormat {
+            name: "Observables group 2, test stand",
+            layout: "<node>OBG,<id>,<source>,2,<recording state>,<file or error>,<anomalies hex32>,<records hex32>,<vbb hex16>,<pyro status hex8>",
+            example: sentence("RQAOBG,123,LNC,2,R,FOOBAR.TXT,000000FF,12345678,ABCD,22"),
+        },
+        SentenceFormat {
+            name: "Observables group 2, rocket",
+            layout: "<node>OBG,<id>,<source>,2,[<recording state>,<file or error>,<records hex32>,]<vbb hex16>,<pyro status hex8>",
+            example: sentence("RQBOBG,123,LNC,2,R,FLIGHT01.BIN,000000FF,ABCD,22"),
+        },
+        SentenceFormat {
+            name: "Observables group 3",
+            layout: "<node>OBG,<id>,<source>,3,<clock frequency hex32>,<uptime 16 hex digits>,<breakwire I or B>,<rail exit 16 hex digits>",
+            example: sentence("FDBOBG,123,LNC,3,0BEBC200,00000000AA894CC8,B,00000000AA000000"),
+        },
+    ]
+}
+
+pub fn specification() -> ProtocolSpec {
+    ProtocolSpec {
+        protocol_version: PROTOCOL_VERSION,
+        frontend_version: env!("CARGO_PKG_VERSION"),
+        max_sentence_length: MAX_BUFFER_SIZE,
+        sentences: sentences(),
+        commands: commands().into_iter().map(command_spec).collect(),
+    }
+}
+
+fn describe_parameters(parameters: &[Parameter]) -> String {
+    match parameters.is_empty() {
+        true => "none".into(),
+        false => parameters
+            .iter()
+            .map(|parameter| format!("{} ({})", parameter.name, describe(parameter.encoding)))
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+// Each sentence a string matching one of the definitions, named by
+// verb, their acknowledgements with an _ACK suffix
+pub fn to_schema(spec: &ProtocolSpec) -> Value {
+    let mut definitions = Map::new();
+    for command in &spec.commands {
+        definitions.insert(
+            command.verb.clone(),
+            json!({
+                "title": format!("{} command", command.verb),
+                "description": command.description(),
+                "type": "string",
+                "maxLength": spec.max_sentence_length,
+                "pattern": command.pattern(),
+                "examples": [command.example],
+            }),
+        );
+        definitions.insert(
+            format!("{}_ACK", command.verb),
+            json!({
+                "title": format!("Acknowledgement of {}", command.verb),
+                "description": format!("Values: {}.", describe_parameters(&command.response)),
+                "type": "string",
+                "maxLength": spec.max_sentence_length,
+                "pattern": command.ack_pattern(),
+                "examples": [command.example_ack],
+            }),
+        );
+    }
+    definitions.insert(
+        "NAK".into(),
+        json!({
+            "title": "Refusal of any command",
+            "type": "string",
+            "pattern": sentence_pattern(&format!("{}NAK,[0-9]{{3}},{}", NODE_PATTERN, NODE_PATTERN), &[]),
+            "examples": [sentence("RQBNAK,123,LNC")],
+        }),
+    );
+    let capabilities: Map<_, _> = Capabilities::NAMES
+        .iter()
+        .map(|(name, bit)| (name.to_string(), json!(bit)))
+        .collect();
+    let any_of: Vec<_> = definitions
+        .keys()
+        .map(|name| json!({ "$ref": format!("#/$defs/{}", name) }))
+        .collect();
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "$id": format!("rq-protocol-v{}.schema.json", spec.protocol_version),
+        "title": format!("RQ protocol version {}", spec.protocol_version),
+        "description": format!(
+            "Command and acknowledgement sentences, generated by launch control {}. Capabilities are reported as a bitfield in the VERSION acknowledgement.",
+            spec.frontend_version
+        ),
+        "x-capabilities": capabilities,
+        "anyOf": any_of,
+        "$defs": definitions,
+    })
+}
+
+pub fn to_markdown(spec: &ProtocolSpec) -> String {
+    let mut doc = format!(
+        "# RQ protocol version {}\n\nGenerated by launch control {}. Sentences are at most {} bytes.\n\n## Sentences\n\n",
+        spec.protocol_version, spec.frontend_version, spec.max_sentence_length
+    );
+    for format in &spec.sentences {
+        doc += &format!(
+            "### {}\n\n`{}`\n\n    {}\n\n",
+            format.name,
+            format.layout,
+            format.example.trim_end()
+        );
+    }
+    doc += "## Capabilities\n\nReported as a bitfield in the VERSION acknowledgement.\n\n| Capability | Bit |\n|---|---|\n";
+    for (name, bit) in Capabilities::NAMES {
+        doc += &format!("| {} | 0x{:08X} |\n", name, bit);
+    }
+    doc += "\n## Commands\n";
+    for command in &spec.commands {
+        doc += &format!(
+            "\n### {}\n\n`{}`\n\n`{}`\n\n- Parameters: {}\n- Acknowledged with: {}\n- Capability: {}\n\n    {}\n    {}\n",
+            command.verb,
+            command.layout(),
+            command.ack_layout(),
+            describe_parameters(&command.parameters),
+            describe_parameters(&command.response),
+            command.capability.unwrap_or("always"),
+            command.example.trim_end(),
+            command.example_ack.trim_end()
+        );
+    }
+    doc
+}
+
+// Into the directory, named by protocol version
+pub fn export(directory: &Path) -> anyhow::Result<()> {
+    let spec = specification();
+    std::fs::create_dir_all(directory)?;
+    let stem = format!("rq-protocol-v{}", spec.protocol_version);
+    std::fs::write(
+        directory.join(format!("{}.schema.json", stem)),
+        serde_json::to_string_pretty(&to_schema(&spec))?,
+    )?;
+    std::fs::write(directory.join(format!("{}.md", stem)), to_markdown(&spec))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observables::Profile;
+    use crate::rqparser::{
+        command_parser, command_rf_silence_parser, obg_parser, verify_nmea_format,
+    };
+    use nom::branch::alt;
+
+    // Of the sentence contents
+    fn fields(sentence: &str) -> usize {
+        verify_nmea_format(sentence.as_bytes())
+            .unwrap()
+            .split(|c| *c == b',')
+            .count()
+    }
+
+    #[test]
+    fn test_spec_examples_are_understood() {
+        let spec = specification();
+        assert_eq!(spec.commands.len(), commands().len());
+        for (command, entry) in commands().into_iter().zip(spec.commands.iter()) {
+            let contents = verify_nmea_format(entry.example.as_bytes()).unwrap();
+            let (_, parsed) = alt((command_parser, command_rf_silence_parser))(contents).unwrap();
+            assert_eq!(parsed.command, command);
+            assert_eq!(fields(&entry.example), 4 + entry.parameters.len());
+            let mut transaction =
+                Transaction::new(EXAMPLE_SOURCE, EXAMPLE_RECIPIENT, EXAMPLE_ID, command);
+            assert!(
+                transaction
+                    .process_response(entry.example_ack.as_bytes())
+                    .is_ok(),
+                "{}",
+                entry.example_ack
+            );
+            assert_eq!(fields(&entry.example_ack), 3 + entry.response.len());
+        }
+        let file_open = spec
+            .commands
+            .iter()
+            .find(|c| c.verb == "FILE_OPEN")
+            .unwrap();
+        assert_eq!(file_open.capability, Some("FILE_READ"));
+        assert_eq!(file_open.response[1].encoding, Encoding::Hex32);
+
+        for format in spec
+            .sentences
+            .iter()
+            .filter(|f| f.name.starts_with("Observables"))
+        {
+            let contents = verify_nmea_format(format.example.as_bytes()).unwrap();
+            let profile = match format.name.ends_with("test stand") {
+                true => Profile::TestStand,
+                false => Profile::Rocket,
+            };
+            assert!(obg_parser(profile, contents).is_ok(), "{}", format.name);
+        }
+        assert!(to_markdown(&spec)
+            .contains("`<source>CMD,<3 digit id>,<recipient>,SECRET_AB,<secret_a>,<secret_b>`"));
+    }
+
+    #[test]
+    fn test_schema_has_a_definition_per_sentence() {
+        let spec = specification();
+        let schema = to_schema(&spec);
+        assert_eq!(schema["$schema"], SCHEMA_DIALECT);
+        let definitions = schema["$defs"].as_object().unwrap();
+        assert_eq!(definitions.len(), 2 * spec.commands.len() + 1);
+        assert_eq!(schema["anyOf"].as_array().unwrap().len(), definitions.len());
+        assert_eq!(
+            definitions["RESET"]["pattern"],
+            "^\\$(LNC|RQ[A-Za-z]|FD[A-Za-z])CMD,[0-9]{3},(LNC|RQ[A-Za-z]|FD[A-Za-z]),RESET,[0-9A-F]{2}\\*[0-9A-F]{2}\\r\\n$"
+        );
+        assert_eq!(
+            definitions["FILE_LIST_ACK"]["pattern"],
+            "^\\$(LNC|RQ[A-Za-z]|FD[A-Za-z])ACK,[0-9]{3},(LNC|RQ[A-Za-z]|FD[A-Za-z]),[0-9]+,[0-9]+(,[0-9]+,[^,*]*)?\\*[0-9A-F]{2}\\r\\n$"
+        );
+        assert_eq!(
+            schema["x-capabilities"]["RECORDING"],
+            Capabilities::RECORDING
+        );
+    }
+}
@@ -0,0 +1,169 @@
+// Identifies the artifacts of one run of launch control, so on test
+// days with several runs the recording, journal, telemetry spill and
+// relayed frames can be told apart and put back together. The id is
+// a random UUID, the slug of two words derived from it is what the
+// crew reads off the screen and calls out.
+use std::collections::hash_map::RandomState;
+use std::fmt::Display;
+use std::hash::{BuildHasher, Hasher};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const ADJECTIVES: [&str; 32] = [
+    "amber", "bold", "brisk", "calm", "coral", "crisp", "dusty", "eager", "fair", "fierce",
+    "gentle", "golden", "hazy", "icy", "jolly", "keen", "lively", "lucky", "misty", "noble",
+    "olive", "proud", "quiet", "rapid", "rusty", "silver", "steady", "swift", "tidy", "vivid",
+    "warm", "zesty",
+];
+
+const NOUNS: [&str; 32] = [
+    "albatross",
+    "badger",
+    "beacon",
+    "comet",
+    "condor",
+    "falcon",
+    "ferret",
+    "gecko",
+    "glacier",
+    "harbor",
+    "heron",
+    "ibis",
+    "jackal",
+    "kestrel",
+    "lantern",
+    "lynx",
+    "meteor",
+    "nebula",
+    "otter",
+    "pelican",
+    "quasar",
+    "raven",
+    "sparrow",
+    "summit",
+    "thistle",
+    "tundra",
+    "urchin",
+    "vortex",
+    "walrus",
+    "willow",
+    "yak",
+    "zephyr",
+];
+
+// A version 4 UUID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RunId([u8; 16]);
+
+impl RunId {
+    pub fn generate() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos());
+        // Each RandomState is seeded with fresh randomness
+        let random = |salt: u64| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u64(salt);
+            hasher.write_u128(nanos);
+            hasher.write_u32(std::process::id());
+            hasher.finish()
+        };
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&random(0).to_be_bytes());
+        bytes[8..].copy_from_slice(&random(1).to_be_bytes());
+        bytes[6] = bytes[6] & 0x0F | 0x40;
+        bytes[8] = bytes[8] & 0x3F | 0x80;
+        Self(bytes)
+    }
+
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+
+    // E.g. swift-falcon, from the random bits of the id
+    pub fn slug(&self) -> String {
+        format!(
+            "{}-{}",
+            ADJECTIVES[self.0[0] as usize % ADJECTIVES.len()],
+            NOUNS[self.0[1] as usize % NOUNS.len()]
+        )
+    }
+}
+
+// Hyphenated and lower case
+impl Display for RunId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, byte) in self.0.iter().enumerate() {
+            if matches!(index, 4 | 6 | 8 | 10) {
+                write!(f, "-")?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RunId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits: String = s.chars().filter(|c| *c != '-').collect();
+        if !s.is_ascii() || digits.len() != 32 || s.len() != 36 {
+            return Err(format!("{} isn't a UUID", s));
+        }
+        let mut bytes = [0; 16];
+        for (index, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&digits[index * 2..index * 2 + 2], 16)
+                .map_err(|_| format!("{} isn't a UUID", s))?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl Serialize for RunId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for RunId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_ids() {
+        let run = RunId::generate();
+        assert_ne!(run, RunId::generate());
+        let text = run.to_string();
+        assert_eq!(text.len(), 36);
+        assert_eq!(&text[14..15], "4");
+        assert_eq!(text.parse(), Ok(run));
+        let known = RunId::from_bytes([
+            0x05, 0x1B, 0x3C, 0x4D, 0x5E, 0x6F, 0x40, 0x81, 0x92, 0xA3, 0xB4, 0xC5, 0xD6, 0xE7,
+            0xF8, 0x09,
+        ]);
+        assert_eq!(known.to_string(), "051b3c4d-5e6f-4081-92a3-b4c5d6e7f809");
+        assert_eq!(known.slug(), "crisp-vortex");
+        assert!("051b3c4d5e6f408192a3b4c5d6e7f809".parse::<RunId>().is_err());
+        assert!("051b3c4d-5e6f-4081-92a3-b4c5d6e7f8zz"
+            .parse::<RunId>()
+            .is_err());
+        let json = serde_json::to_string(&known).unwrap();
+        assert_eq!(json, "\"051b3c4d-5e6f-4081-92a3-b4c5d6e7f809\"");
+        assert_eq!(serde_json::from_str::<RunId>(&json).unwrap(), known);
+    }
+}
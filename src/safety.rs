@@ -0,0 +1,113 @@
+// The range safety officer's key. On the novaview box it closes a
+// spare GPIO to ground. Without the key the launch sequence can be
+// walked through, but neither key AB nor the ignition is sent.
+// Anything but a steady reading of the key counts as absent.
+
+// Consecutive reads of the key before we believe it
+const DEBOUNCE_READS: usize = 3;
+
+pub trait SafetyInput {
+    fn key_present(&mut self) -> anyhow::Result<bool>;
+}
+
+// Desktop builds have no key
+pub struct MockSafetyInput {
+    pub present: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct SafetyKey {
+    present_reads: usize,
+    // Why the key isn't usable, if we know
+    pub fault: Option<String>,
+}
+
+impl Default for MockSafetyInput {
+    fn default() -> Self {
+        Self { present: true }
+    }
+}
+
+impl SafetyInput for MockSafetyInput {
+    fn key_present(&mut self) -> anyhow::Result<bool> {
+        Ok(self.present)
+    }
+}
+
+impl SafetyKey {
+    pub fn feed(&mut self, reading: anyhow::Result<bool>) {
+        match reading {
+            Ok(true) => {
+                self.present_reads = (self.present_reads + 1).min(DEBOUNCE_READS);
+                self.fault = None;
+            }
+            Ok(false) => {
+                self.present_reads = 0;
+                self.fault = None;
+            }
+            Err(e) => {
+                self.present_reads = 0;
+                self.fault = Some(e.to_string());
+            }
+        }
+    }
+
+    pub fn inhibits(&self) -> bool {
+        self.present_reads < DEBOUNCE_READS
+    }
+}
+
+#[cfg(feature = "novaview")]
+pub mod gpio {
+    use linux_embedded_hal::gpio_cdev::{Chip, LineHandle, LineRequestFlags};
+
+    use super::SafetyInput;
+
+    pub struct GpioSafetyInput {
+        line: LineHandle,
+    }
+
+    impl GpioSafetyInput {
+        pub fn new(chip: &str, line: u32) -> anyhow::Result<Self> {
+            let line = Chip::new(chip)?.get_line(line)?.request(
+                LineRequestFlags::INPUT,
+                0,
+                "safety-key",
+            )?;
+            Ok(Self { line })
+        }
+    }
+
+    impl SafetyInput for GpioSafetyInput {
+        // The key pulls the line low
+        fn key_present(&mut self) -> anyhow::Result<bool> {
+            Ok(self.line.get_value()? == 0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_key_is_debounced_and_fails_safe() {
+        let mut key = SafetyKey::default();
+        assert!(key.inhibits());
+        let mut input = MockSafetyInput::default();
+        for _ in 0..DEBOUNCE_READS - 1 {
+            key.feed(input.key_present());
+            assert!(key.inhibits());
+        }
+        key.feed(input.key_present());
+        assert!(!key.inhibits());
+        key.feed(Err(anyhow!("line gone")));
+        assert!(key.inhibits());
+        assert_eq!(key.fault, Some("line gone".into()));
+        input.present = false;
+        key.feed(input.key_present());
+        assert!(key.inhibits());
+        assert_eq!(key.fault, None);
+    }
+}
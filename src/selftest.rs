@@ -0,0 +1,190 @@
+// Startup self-test. The checks are fed from what the Model
+// observes while bringing up the connection, so we exercise the
+// same code paths as during operation.
+use std::fs::OpenOptions;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Check {
+    SerialPort,
+    E32Parameters,
+    Ping,
+    NRFModules,
+    Recorder,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Pending,
+    Running,
+    Passed,
+    Failed(String),
+}
+
+pub struct SelfTest {
+    verdicts: Vec<(Check, Verdict)>,
+    acknowledged: bool,
+}
+
+const CHECKS: [Check; 5] = [
+    Check::SerialPort,
+    Check::E32Parameters,
+    Check::Ping,
+    Check::NRFModules,
+    Check::Recorder,
+];
+
+impl Check {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Check::SerialPort => "Serial port opens",
+            Check::E32Parameters => "E32 parameters read and set",
+            Check::Ping => "RQ answers Ping",
+            Check::NRFModules => "NRF modules enumerate",
+            Check::Recorder => "Recorder file writable",
+        }
+    }
+}
+
+impl Verdict {
+    pub fn is_done(&self) -> bool {
+        match self {
+            Verdict::Passed | Verdict::Failed(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Default for SelfTest {
+    fn default() -> Self {
+        Self {
+            verdicts: CHECKS.iter().map(|c| (*c, Verdict::Pending)).collect(),
+            acknowledged: false,
+        }
+    }
+}
+
+impl SelfTest {
+    pub fn restart(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn record(&mut self, check: Check, verdict: Verdict) {
+        for (c, v) in self.verdicts.iter_mut() {
+            if *c == check {
+                *v = verdict.clone();
+            }
+        }
+    }
+
+    pub fn verdict(&self, check: Check) -> &Verdict {
+        &self
+            .verdicts
+            .iter()
+            .find(|(c, _)| *c == check)
+            .expect("all checks are known")
+            .1
+    }
+
+    pub fn verdicts(&self) -> impl Iterator<Item = &(Check, Verdict)> {
+        self.verdicts.iter()
+    }
+
+    // Whatever didn't finish in time counts as failed
+    pub fn expire(&mut self) {
+        for (_, v) in self.verdicts.iter_mut() {
+            if !v.is_done() {
+                *v = Verdict::Failed("Timeout".into());
+            }
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.verdicts.iter().all(|(_, v)| v.is_done())
+    }
+
+    pub fn passed(&self) -> bool {
+        self.verdicts.iter().all(|(_, v)| *v == Verdict::Passed)
+    }
+
+    // The checklist is shown until the operator confirms it
+    pub fn is_active(&self) -> bool {
+        !self.acknowledged
+    }
+
+    pub fn acknowledge(&mut self) -> bool {
+        if self.finished() {
+            self.acknowledged = true;
+        }
+        self.acknowledged
+    }
+}
+
+// Without creating the recording, that's up to the recorder
+pub fn check_recorder(path: Option<&Path>) -> Verdict {
+    match path {
+        Some(path) if path.exists() => match OpenOptions::new().append(true).open(path) {
+            Ok(_) => Verdict::Passed,
+            Err(err) => Verdict::Failed(err.to_string()),
+        },
+        Some(path) => {
+            let directory = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            match std::fs::metadata(directory) {
+                Ok(metadata) if !metadata.is_dir() => {
+                    Verdict::Failed(format!("{} isn't a directory", directory.display()))
+                }
+                Ok(metadata) if metadata.permissions().readonly() => {
+                    Verdict::Failed(format!("{} is read-only", directory.display()))
+                }
+                Ok(_) => Verdict::Passed,
+                Err(err) => Verdict::Failed(err.to_string()),
+            }
+        }
+        // Not recording is a deliberate choice
+        None => Verdict::Passed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acknowledge_needs_all_checks_done() {
+        let mut self_test = SelfTest::default();
+        self_test.record(Check::SerialPort, Verdict::Passed);
+        assert!(!self_test.acknowledge());
+        self_test.expire();
+        assert!(self_test.finished());
+        assert!(!self_test.passed());
+        assert_eq!(
+            *self_test.verdict(Check::Ping),
+            Verdict::Failed("Timeout".into())
+        );
+        assert!(self_test.acknowledge());
+        assert!(!self_test.is_active());
+    }
+
+    #[test]
+    fn test_recorder_check_creates_nothing() {
+        let directory = std::env::temp_dir();
+        let path = directory.join(format!("lnc-selftest-{}.log", std::process::id()));
+        assert_eq!(check_recorder(Some(&path)), Verdict::Passed);
+        assert!(!path.exists());
+        let missing = directory.join("lnc-no-such-dir").join("rqa.log");
+        assert!(matches!(check_recorder(Some(&missing)), Verdict::Failed(_)));
+        assert_eq!(check_recorder(None), Verdict::Passed);
+    }
+
+    #[test]
+    fn test_all_passed() {
+        let mut self_test = SelfTest::default();
+        for check in CHECKS {
+            self_test.record(check, Verdict::Passed);
+        }
+        assert!(self_test.passed());
+    }
+}
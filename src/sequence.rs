@@ -0,0 +1,546 @@
+// Scripted procedures, e.g. the pre-flight checks or what is done
+// after a static fire. A script is a text file with one step per
+// line, blank lines and lines starting with # are skipped:
+//
+//   send PING
+//   send OBG 2
+//   wait VBB > 12.0 within 10
+//   prompt Pyro continuity checked?
+//   delay 5
+//
+// Commands go through the consort like any other, so a step only
+// passes once its command was acknowledged. Only commands without
+// any effect on the node can be scripted, nothing that arms or fires.
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{space0, space1},
+    combinator::{all_consuming, map, map_res, opt, rest, value},
+    number::complete::double,
+    sequence::{preceded, tuple},
+    IResult,
+};
+
+use crate::{
+    clock::Instant,
+    rqprotocol::{Command, Response},
+};
+
+pub const SEQUENCE_EXTENSION: &str = "seq";
+const DEFAULT_WAIT: Duration = Duration::from_secs(30);
+// Generous, the consort gives up on the command long before
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Quantity {
+    // Volts
+    Vbb,
+    // Kilonewton
+    Thrust,
+    // Bar
+    Pressure,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Above,
+    Below,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Condition {
+    pub quantity: Quantity,
+    pub comparison: Comparison,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    Send(Command),
+    Wait {
+        condition: Condition,
+        within: Duration,
+    },
+    // Passes when the operator presses Enter
+    Prompt(String),
+    Delay(Duration),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sequence {
+    pub name: String,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Completed,
+    Aborted,
+    Failed(String),
+}
+
+#[derive(Debug)]
+pub struct Run {
+    pub sequence: Sequence,
+    pub step: usize,
+    step_started: Instant,
+    sent: bool,
+    pub outcome: Option<Outcome>,
+    reported: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Sequencer {
+    active: bool,
+    // Where the scripts are, re-read whenever the screen is opened
+    pub directory: Option<PathBuf>,
+    pub sequences: Vec<Sequence>,
+    // Scripts that didn't parse
+    pub errors: Vec<String>,
+    pub selected: usize,
+    pub run: Option<Run>,
+}
+
+impl Quantity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Quantity::Vbb => "VBB",
+            Quantity::Thrust => "THRUST",
+            Quantity::Pressure => "PRESSURE",
+        }
+    }
+}
+
+impl Condition {
+    pub fn holds(&self, actual: f64) -> bool {
+        match self.comparison {
+            Comparison::Above => actual > self.value,
+            Comparison::Below => actual < self.value,
+        }
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let comparison = match self.comparison {
+            Comparison::Above => ">",
+            Comparison::Below => "<",
+        };
+        write!(f, "{} {} {}", self.quantity.name(), comparison, self.value)
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Step::Send(Command::ObservableGroup(group)) => write!(f, "send OBG {}", group),
+            Step::Send(command) => write!(f, "send {:?}", command),
+            Step::Wait { condition, within } => {
+                write!(f, "wait {} within {}", condition, within.as_secs_f64())
+            }
+            Step::Prompt(text) => write!(f, "prompt {}", text),
+            Step::Delay(duration) => write!(f, "delay {}", duration.as_secs_f64()),
+        }
+    }
+}
+
+// Infinite or huge durations don't parse
+fn seconds_parser(s: &str) -> IResult<&str, Duration> {
+    map_res(double, |secs| Duration::try_from_secs_f64(secs.max(0.0)))(s)
+}
+
+fn send_parser(s: &str) -> IResult<&str, Step> {
+    let (rest, command) = preceded(
+        tuple((tag("send"), space1)),
+        alt((
+            value(Command::Ping, tag("PING")),
+            value(Command::Version, tag("VERSION")),
+            value(Command::ObservableGroup(1), tag("OBG 1")),
+            value(Command::ObservableGroup(2), tag("OBG 2")),
+        )),
+    )(s)?;
+    Ok((rest, Step::Send(command)))
+}
+
+fn wait_parser(s: &str) -> IResult<&str, Step> {
+    let (rest, (_, quantity, _, comparison, _, value, within)) = tuple((
+        tuple((tag("wait"), space1)),
+        alt((
+            value(Quantity::Vbb, tag("VBB")),
+            value(Quantity::Thrust, tag("THRUST")),
+            value(Quantity::Pressure, tag("PRESSURE")),
+        )),
+        space1,
+        alt((
+            value(Comparison::Above, tag(">")),
+            value(Comparison::Below, tag("<")),
+        )),
+        space1,
+        double,
+        opt(preceded(
+            tuple((space1, tag("within"), space1)),
+            seconds_parser,
+        )),
+    ))(s)?;
+    Ok((
+        rest,
+        Step::Wait {
+            condition: Condition {
+                quantity,
+                comparison,
+                value,
+            },
+            within: within.unwrap_or(DEFAULT_WAIT),
+        },
+    ))
+}
+
+fn prompt_parser(s: &str) -> IResult<&str, Step> {
+    map(
+        preceded(tuple((tag("prompt"), space1)), rest),
+        |text: &str| Step::Prompt(text.trim().into()),
+    )(s)
+}
+
+fn delay_parser(s: &str) -> IResult<&str, Step> {
+    map(
+        preceded(tuple((tag("delay"), space1)), seconds_parser),
+        Step::Delay,
+    )(s)
+}
+
+fn step_parser(s: &str) -> IResult<&str, Step> {
+    all_consuming(map(
+        tuple((
+            alt((send_parser, wait_parser, prompt_parser, delay_parser)),
+            space0,
+        )),
+        |(step, _)| step,
+    ))(s)
+}
+
+impl Sequence {
+    pub fn parse(name: &str, script: &str) -> anyhow::Result<Self> {
+        let mut steps = vec![];
+        for (number, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (_, step) = step_parser(line)
+                .map_err(|_| anyhow!("{}:{}: can't parse '{}'", name, number + 1, line))?;
+            steps.push(step);
+        }
+        if steps.is_empty() {
+            return Err(anyhow!("{}: no steps", name));
+        }
+        Ok(Self {
+            name: name.into(),
+            steps,
+        })
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let name = path
+            .file_stem()
+            .map_or("sequence".into(), |n| n.to_string_lossy().to_string());
+        Self::parse(&name, &std::fs::read_to_string(path)?)
+    }
+}
+
+// Whether the response finishes the command's transaction
+fn completes(command: &Command, response: &Response) -> bool {
+    match (command, response) {
+        (Command::Ping, Response::PingAck) => true,
+        (Command::Version, Response::VersionAck(_)) => true,
+        (Command::ObservableGroup(_), Response::ObservableGroupAck) => true,
+        _ => false,
+    }
+}
+
+impl Run {
+    pub fn new(sequence: Sequence, now: Instant) -> Self {
+        Self {
+            sequence,
+            step: 0,
+            step_started: now,
+            sent: false,
+            outcome: None,
+            reported: false,
+        }
+    }
+
+    pub fn current(&self) -> Option<&Step> {
+        match self.outcome {
+            Some(_) => None,
+            None => self.sequence.steps.get(self.step),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.step as f32 / self.sequence.steps.len() as f32
+    }
+
+    pub fn step_elapsed(&self, now: Instant) -> Duration {
+        now.duration_since(self.step_started)
+    }
+
+    fn advance(&mut self, now: Instant) {
+        self.step += 1;
+        self.step_started = now;
+        self.sent = false;
+        if self.step == self.sequence.steps.len() {
+            self.outcome = Some(Outcome::Completed);
+        }
+    }
+
+    fn fail(&mut self, reason: String) {
+        self.outcome = Some(Outcome::Failed(reason));
+    }
+
+    // The command of the current step, once
+    pub fn next_command(&mut self) -> Option<Command> {
+        match self.current() {
+            Some(Step::Send(command)) if !self.sent => {
+                let command = command.clone();
+                self.sent = true;
+                Some(command)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn process_response(&mut self, response: &Response, now: Instant) {
+        if let Some(Step::Send(command)) = self.current() {
+            if self.sent && completes(command, response) {
+                self.advance(now);
+            }
+        }
+    }
+
+    // Value gives the latest reading of a quantity, if any
+    pub fn drive(&mut self, now: Instant, value: impl Fn(Quantity) -> Option<f64>) {
+        let elapsed = self.step_elapsed(now);
+        match self.current().cloned() {
+            Some(Step::Send(command)) if self.sent && elapsed > SEND_TIMEOUT => {
+                self.fail(format!("no answer to {:?}", command))
+            }
+            Some(Step::Wait { condition, within }) => {
+                if value(condition.quantity).map_or(false, |v| condition.holds(v)) {
+                    self.advance(now);
+                } else if elapsed > within {
+                    self.fail(format!(
+                        "{} not met within {}s",
+                        condition,
+                        within.as_secs_f64()
+                    ));
+                }
+            }
+            Some(Step::Delay(duration)) if elapsed >= duration => self.advance(now),
+            _ => {}
+        }
+    }
+
+    // The operator confirmed the prompt
+    pub fn acknowledge(&mut self, now: Instant) -> Option<String> {
+        match self.current().cloned() {
+            Some(Step::Prompt(text)) => {
+                self.advance(now);
+                Some(text)
+            }
+            _ => None,
+        }
+    }
+
+    // The outcome, only the first time it's asked for
+    pub fn report(&mut self) -> Option<Outcome> {
+        if self.reported {
+            return None;
+        }
+        self.reported = self.outcome.is_some();
+        self.outcome.clone()
+    }
+
+    pub fn abort(&mut self) {
+        if !self.is_finished() {
+            self.outcome = Some(Outcome::Aborted);
+        }
+    }
+}
+
+impl Sequencer {
+    pub fn new(directory: Option<PathBuf>) -> Self {
+        Self {
+            directory,
+            ..Default::default()
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.active = true;
+        if !self.is_running() {
+            self.load();
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    // All scripts in the directory, sorted by name
+    fn load(&mut self) {
+        self.sequences.clear();
+        self.errors.clear();
+        self.selected = 0;
+        let directory = match &self.directory {
+            Some(directory) => directory.clone(),
+            None => return,
+        };
+        let mut paths: Vec<PathBuf> = match std::fs::read_dir(&directory) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.extension().map_or(false, |e| e == SEQUENCE_EXTENSION))
+                .collect(),
+            Err(e) => {
+                self.errors
+                    .push(format!("can't read {}: {}", directory.display(), e));
+                return;
+            }
+        };
+        paths.sort();
+        for path in paths {
+            match Sequence::load(&path) {
+                Ok(sequence) => self.sequences.push(sequence),
+                Err(e) => self.errors.push(e.to_string()),
+            }
+        }
+    }
+
+    pub fn select(&mut self, steps: i64) {
+        if !self.sequences.is_empty() && !self.is_running() {
+            self.selected =
+                (self.selected as i64 + steps).rem_euclid(self.sequences.len() as i64) as usize;
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.run.as_ref().map_or(false, |r| !r.is_finished())
+    }
+
+    // Returns the name of the started sequence
+    pub fn run_selected(&mut self, now: Instant) -> Option<String> {
+        if self.is_running() {
+            return None;
+        }
+        let sequence = self.sequences.get(self.selected)?.clone();
+        let name = sequence.name.clone();
+        self.run = Some(Run::new(sequence, now));
+        Some(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "
+# Pre-flight
+send PING
+wait VBB > 12.0 within 10
+prompt Pyro continuity checked?
+delay 2.5
+send OBG 2
+";
+
+    #[test]
+    fn test_parse() {
+        let sequence = Sequence::parse("preflight", SCRIPT).unwrap();
+        assert_eq!(sequence.steps.len(), 5);
+        assert_eq!(
+            sequence.steps[1],
+            Step::Wait {
+                condition: Condition {
+                    quantity: Quantity::Vbb,
+                    comparison: Comparison::Above,
+                    value: 12.0,
+                },
+                within: Duration::from_secs(10),
+            }
+        );
+        assert_eq!(
+            sequence.steps[2],
+            Step::Prompt("Pyro continuity checked?".into())
+        );
+        assert_eq!(sequence.steps[4].to_string(), "send OBG 2");
+        let error = Sequence::parse("fire", "send PING\nsend IGNITION\n").unwrap_err();
+        assert_eq!(error.to_string(), "fire:2: can't parse 'send IGNITION'");
+        assert!(Sequence::parse("empty", "# nothing\n").is_err());
+        let error = Sequence::parse("long", "send PING\ndelay inf\n").unwrap_err();
+        assert_eq!(error.to_string(), "long:2: can't parse 'delay inf'");
+        let error = Sequence::parse("long", "wait VBB > 12 within 1e30\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "long:1: can't parse 'wait VBB > 12 within 1e30'"
+        );
+    }
+
+    #[test]
+    fn test_run_through() {
+        let sequence = Sequence::parse("preflight", SCRIPT).unwrap();
+        let mut now = Instant::now();
+        let mut run = Run::new(sequence, now);
+        assert_eq!(run.next_command(), Some(Command::Ping));
+        assert_eq!(run.next_command(), None);
+        run.process_response(&Response::ObservableGroupAck, now);
+        assert_eq!(run.step, 0);
+        run.process_response(&Response::PingAck, now);
+        run.drive(now, |_| Some(11.0));
+        assert_eq!(run.step, 1);
+        run.drive(now, |_| Some(12.5));
+        assert!(run.acknowledge(now).is_some());
+        run.drive(now, |_| None);
+        assert_eq!(run.step, 3);
+        now += Duration::from_secs(3);
+        run.drive(now, |_| None);
+        assert_eq!(run.next_command(), Some(Command::ObservableGroup(2)));
+        run.process_response(&Response::ObservableGroupAck, now);
+        assert_eq!(run.report(), Some(Outcome::Completed));
+        assert_eq!(run.report(), None);
+        assert_eq!(run.progress(), 1.0);
+    }
+
+    #[test]
+    fn test_run_fails_and_aborts() {
+        let sequence = Sequence::parse("preflight", SCRIPT).unwrap();
+        let now = Instant::now();
+        let mut run = Run::new(sequence.clone(), now);
+        run.next_command();
+        run.drive(now + SEND_TIMEOUT * 2, |_| None);
+        assert_eq!(
+            run.outcome,
+            Some(Outcome::Failed("no answer to Ping".into()))
+        );
+        let mut run = Run::new(sequence, now);
+        run.next_command();
+        run.process_response(&Response::PingAck, now);
+        run.drive(now + Duration::from_secs(11), |_| Some(11.0));
+        assert!(matches!(run.outcome, Some(Outcome::Failed(_))));
+        run.abort();
+        assert!(matches!(run.outcome, Some(Outcome::Failed(_))));
+    }
+}
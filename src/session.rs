@@ -0,0 +1,317 @@
+// What the previous session left on disk, shown on startup, so the
+// crew can confirm yesterday's data made it before today's test. It
+// is read back from the newest journal next to the recording. A
+// session ending normally journals that it did, a journal without it
+// is of a session that crashed or lost power. The profile is journaled
+// too, recordings of the test stand decode differently.
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::{observables::Profile, rqprotocol::Node, run::RunId};
+
+const STARTED: &str = "Session started";
+const ENDED: &str = "Session ended";
+const RECORDING_TO: &str = "recording to ";
+const RUN: &str = "Run ";
+const PROFILE: &str = "Profile ";
+const MAX_THRUST: &str = "max thrust ";
+const ANOMALIES: &str = " anomalies reported by ";
+const EXTENSION: &str = "journal";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WrittenFile {
+    pub path: PathBuf,
+    // None if it's gone
+    pub bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionSummary {
+    pub journal: PathBuf,
+    pub started: DateTime<Utc>,
+    // Up to the last entry
+    pub duration: Duration,
+    pub max_thrust_kn: Option<f64>,
+    pub anomalies: u32,
+    // Slug and id, of sessions journaling one
+    pub run: Option<String>,
+    // Of sessions journaling one
+    pub profile: Option<Profile>,
+    pub files: Vec<WrittenFile>,
+    pub ended_normally: bool,
+    // Of this and the sessions before, since the last normal end
+    pub abnormal_terminations: usize,
+    // The full journal
+    pub lines: Vec<String>,
+}
+
+// The startup card
+#[derive(Debug, Default)]
+pub struct LastSession {
+    summary: Option<SessionSummary>,
+    active: bool,
+    full_journal: bool,
+}
+
+pub fn started_text(recording: Option<&Path>) -> String {
+    match recording {
+        Some(path) => format!("{}, {}{}", STARTED, RECORDING_TO, path.display()),
+        None => format!("{} without recording", STARTED),
+    }
+}
+
+pub fn run_text(run: &RunId) -> String {
+    format!("{}{} ({})", RUN, run.slug(), run)
+}
+
+pub fn profile_text(profile: Profile) -> String {
+    format!("{}{}", PROFILE, profile_name(profile))
+}
+
+// As on the command line
+fn profile_name(profile: Profile) -> &'static str {
+    match profile {
+        Profile::TestStand => "test-stand",
+        Profile::Rocket => "rocket",
+    }
+}
+
+fn profile(text: &str) -> Option<Profile> {
+    let name = text.strip_prefix(PROFILE)?;
+    [Profile::TestStand, Profile::Rocket]
+        .into_iter()
+        .find(|profile| profile_name(*profile) == name)
+}
+
+pub fn ended_text(max_thrust_kn: Option<f64>) -> String {
+    match max_thrust_kn {
+        Some(thrust) => format!("{}, {}", ENDED, max_thrust_text(thrust)),
+        None => ENDED.into(),
+    }
+}
+
+pub fn max_thrust_text(thrust_kn: f64) -> String {
+    format!("{}{:.3}kN", MAX_THRUST, thrust_kn)
+}
+
+// Lines are the time as RFC 3339 and the text
+fn entry(line: &str) -> Option<(DateTime<Utc>, &str)> {
+    let (at, text) = line.split_once(' ')?;
+    Some((DateTime::parse_from_rfc3339(at).ok()?.into(), text))
+}
+
+// Of an entry like "Session ended, max thrust 1.234kN"
+fn max_thrust(text: &str) -> Option<f64> {
+    let (_, rest) = text.split_once(MAX_THRUST)?;
+    rest.split_once("kN")?.0.parse().ok()
+}
+
+// Of an entry like "5 anomalies reported by RQA", counted up by the node
+fn anomalies(text: &str, target: Node) -> Option<u32> {
+    let (count, node) = text.split_once(ANOMALIES)?;
+    if node != target.to_string() {
+        return None;
+    }
+    count.parse().ok()
+}
+
+impl SessionSummary {
+    // Recordings named relative are next to the journal. The
+    // anomalies are the target's, other nodes count their own.
+    pub fn parse(journal: &Path, contents: &str, target: Node) -> Option<Self> {
+        let entries: Vec<_> = contents.lines().filter_map(entry).collect();
+        let (started, _) = entries.first()?;
+        let (last, _) = entries.last()?;
+        let directory = journal.parent().unwrap_or(Path::new(""));
+        let texts = entries.iter().map(|(_, text)| *text);
+        let files = texts
+            .clone()
+            .filter(|text| text.starts_with(STARTED))
+            .filter_map(|text| text.split_once(RECORDING_TO))
+            .map(|(_, path)| {
+                let path = directory.join(path);
+                let bytes = std::fs::metadata(&path).ok().map(|m| m.len());
+                WrittenFile { path, bytes }
+            })
+            .collect();
+        let ended_normally = texts.clone().last().map_or(false, |t| t.starts_with(ENDED));
+        Some(Self {
+            journal: journal.into(),
+            started: *started,
+            duration: (*last - *started).to_std().unwrap_or_default(),
+            max_thrust_kn: texts.clone().filter_map(max_thrust).reduce(f64::max),
+            anomalies: texts
+                .clone()
+                .filter_map(|text| anomalies(text, target))
+                .max()
+                .unwrap_or(0),
+            run: texts
+                .clone()
+                .find_map(|text| text.strip_prefix(RUN))
+                .map(String::from),
+            profile: texts.clone().find_map(profile),
+            files,
+            ended_normally,
+            abnormal_terminations: usize::from(!ended_normally),
+            lines: contents.lines().map(String::from).collect(),
+        })
+    }
+}
+
+fn read(journal: &Path, target: Node) -> Option<SessionSummary> {
+    SessionSummary::parse(journal, &std::fs::read_to_string(journal).ok()?, target)
+}
+
+// Journals are kept next to the recording
+pub fn directory(recording: &Path) -> &Path {
+    match recording.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+// Newest first, they are named after their start
+fn journals(directory: &Path, current: Option<&Path>) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return vec![];
+    };
+    let mut journals: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map_or(false, |e| e == EXTENSION))
+        .filter(|path| current.map_or(true, |current| path.file_name() != current.file_name()))
+        .collect();
+    journals.sort();
+    journals.reverse();
+    journals
+}
+
+// Of all journals in the directory but ours, newest first
+pub fn sessions(directory: &Path, current: Option<&Path>, target: Node) -> Vec<SessionSummary> {
+    journals(directory, current)
+        .iter()
+        .filter_map(|path| read(path, target))
+        .collect()
+}
+
+// Of the newest journal in the directory, but ours
+pub fn previous_session(
+    directory: &Path,
+    current: Option<&Path>,
+    target: Node,
+) -> Option<SessionSummary> {
+    let journals = journals(directory, current);
+    let mut summaries = journals.iter().filter_map(|path| read(path, target));
+    let mut summary = summaries.next()?;
+    if !summary.ended_normally {
+        summary.abnormal_terminations += summaries
+            .take_while(|previous| !previous.ended_normally)
+            .count();
+    }
+    Some(summary)
+}
+
+impl LastSession {
+    pub fn new(summary: Option<SessionSummary>) -> Self {
+        Self {
+            active: summary.is_some(),
+            summary,
+            full_journal: false,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn dismiss(&mut self) {
+        self.active = false;
+    }
+
+    pub fn toggle_full_journal(&mut self) {
+        self.full_journal = !self.full_journal;
+    }
+
+    pub fn showing_full_journal(&self) -> bool {
+        self.full_journal
+    }
+
+    pub fn summary(&self) -> Option<&SessionSummary> {
+        self.summary.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JOURNAL: &str = "\
+2026-10-15T09:00:00+00:00 Session started, recording to 20261015T090000-rqa.log
+2026-10-15T09:00:00+00:00 Run crisp-vortex (051b3c4d-5e6f-4081-92a3-b4c5d6e7f809)
+2026-10-15T09:00:00+00:00 Profile test-stand
+2026-10-15T09:10:00+00:00 2 anomalies reported by RQA
+2026-10-15T09:12:00+00:00 Onboard recording stopped automatically, max thrust 2.500kN
+2026-10-15T09:15:00+00:00 5 anomalies reported by RQA
+2026-10-15T09:16:00+00:00 7 anomalies reported by RQB
+2026-10-15T10:30:00+00:00 Session ended, max thrust 2.750kN
+";
+
+    #[test]
+    fn test_summary_of_a_journal() {
+        let rqa = Node::RedQueen(b'A');
+        let summary = SessionSummary::parse(Path::new("logs/a.journal"), JOURNAL, rqa).unwrap();
+        assert_eq!(summary.duration, Duration::from_secs(90 * 60));
+        assert_eq!(summary.max_thrust_kn, Some(2.75));
+        assert_eq!(summary.anomalies, 5);
+        assert!(summary.ended_normally);
+        assert_eq!(
+            summary.files,
+            vec![WrittenFile {
+                path: "logs/20261015T090000-rqa.log".into(),
+                bytes: None,
+            }]
+        );
+        assert_eq!(summary.lines.len(), 8);
+        assert_eq!(summary.profile, Some(Profile::TestStand));
+        assert_eq!(
+            summary.run.as_deref(),
+            Some("crisp-vortex (051b3c4d-5e6f-4081-92a3-b4c5d6e7f809)")
+        );
+        assert_eq!(
+            SessionSummary::parse(Path::new("a.journal"), "garbage", rqa),
+            None
+        );
+    }
+
+    #[test]
+    fn test_previous_session_counts_abnormal_terminations() {
+        let directory = std::env::temp_dir().join(format!("lnc-sessions-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let crashed = "2026-10-15T11:00:00+00:00 Session started without recording\n";
+        std::fs::write(directory.join("1.journal"), JOURNAL).unwrap();
+        std::fs::write(directory.join("2.journal"), crashed).unwrap();
+        std::fs::write(directory.join("3.journal"), crashed).unwrap();
+        std::fs::write(directory.join("4.journal"), crashed).unwrap();
+        let rqa = Node::RedQueen(b'A');
+        let summary = previous_session(&directory, Some(Path::new("4.journal")), rqa).unwrap();
+        assert_eq!(summary.journal, directory.join("3.journal"));
+        let listed: Vec<_> = sessions(&directory, Some(Path::new("4.journal")), rqa)
+            .into_iter()
+            .map(|summary| summary.journal)
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                directory.join("3.journal"),
+                directory.join("2.journal"),
+                directory.join("1.journal")
+            ]
+        );
+        assert!(!summary.ended_normally);
+        assert_eq!(summary.abnormal_terminations, 2);
+        assert!(summary.files.is_empty());
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(previous_session(&directory, None, rqa), None);
+    }
+}
@@ -0,0 +1,318 @@
+// The sessions before this one, to look back at without leaving the
+// ground station. A session is opened for replay, stepping through the
+// decoded recording next to its journal, or exported: the journal, the
+// recordings and their transcripts are copied to a directory of their
+// own on a stick, never onto the card they are recorded on. Both read
+// whole recordings, they are done by a worker. Recordings are decoded
+// with the profile of their session, if it journaled one.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use crossbeam_channel::{bounded, Receiver, TryRecvError};
+use log::error;
+
+use crate::observables::Profile;
+use crate::session::SessionSummary;
+use crate::transcript::Transcript;
+
+pub struct Replay {
+    // The first recording of the session still there
+    pub recording: Option<PathBuf>,
+    pub transcript: Transcript,
+    // Entries up to it are shown
+    pub position: usize,
+}
+
+// Of the worker
+enum Done {
+    Replay(Replay),
+    Exported(Result<PathBuf, String>),
+}
+
+#[derive(Default)]
+pub struct SessionBrowser {
+    active: bool,
+    // Newest first
+    sessions: Vec<SessionSummary>,
+    selected: usize,
+    replay: Option<Replay>,
+    // Where the last export went, or why it failed
+    pub exported: Option<Result<PathBuf, String>>,
+    // Of the replay or export being worked on
+    working: Option<Receiver<Done>>,
+}
+
+impl Replay {
+    pub fn open(summary: &SessionSummary, profile: Profile) -> Self {
+        let recording = summary
+            .files
+            .iter()
+            .find(|file| file.bytes.map_or(false, |bytes| bytes > 0))
+            .map(|file| file.path.clone());
+        let transcript = recording
+            .as_deref()
+            .and_then(|path| fs::read(path).ok())
+            .map(|data| Transcript::decode(&data, profile))
+            .unwrap_or_default();
+        Self {
+            recording,
+            transcript,
+            position: 0,
+        }
+    }
+
+    pub fn step(&mut self, steps: i64) {
+        let last = self.transcript.entries.len().saturating_sub(1) as i64;
+        self.position = (self.position as i64 + steps).clamp(0, last) as usize;
+    }
+}
+
+#[cfg(unix)]
+fn same_device(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_device(a: &Path, b: &Path) -> bool {
+    // Drive letters, e.g. "D:"
+    a.components().next() == b.components().next()
+}
+
+// Into a directory named after the journal, below the target
+pub fn export(
+    summary: &SessionSummary,
+    profile: Profile,
+    target: &Path,
+) -> anyhow::Result<PathBuf> {
+    if !target.is_dir() {
+        anyhow::bail!("{} isn't there, is the stick mounted?", target.display());
+    }
+    if same_device(target, &summary.journal) {
+        anyhow::bail!("{} is on the card of the recordings", target.display());
+    }
+    copy(summary, profile, target)
+}
+
+fn copy(summary: &SessionSummary, profile: Profile, target: &Path) -> anyhow::Result<PathBuf> {
+    let stem = summary
+        .journal
+        .file_stem()
+        .ok_or_else(|| anyhow::anyhow!("{} has no name", summary.journal.display()))?;
+    let directory = target.join(stem);
+    fs::create_dir_all(&directory)?;
+    for path in std::iter::once(&summary.journal).chain(
+        summary
+            .files
+            .iter()
+            .filter(|file| file.bytes.is_some())
+            .map(|file| &file.path),
+    ) {
+        let name = path
+            .file_name()
+            .ok_or_else(|| anyhow::anyhow!("{} has no name", path.display()))?;
+        fs::copy(path, directory.join(name))?;
+        if *path == summary.journal {
+            continue;
+        }
+        let transcript = Transcript::decode(&fs::read(path)?, profile);
+        let mut text = vec![];
+        transcript.write_text(&mut text, path)?;
+        let mut name = name.to_os_string();
+        name.push(".txt");
+        fs::write(directory.join(name), text)?;
+    }
+    Ok(directory)
+}
+
+impl SessionBrowser {
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    // A worker still busy finishes, its export is reported
+    pub fn start(&mut self, sessions: Vec<SessionSummary>) {
+        self.active = true;
+        self.sessions = sessions;
+        self.selected = 0;
+        self.replay = None;
+        self.exported = None;
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+        self.replay = None;
+    }
+
+    pub fn sessions(&self) -> &[SessionSummary] {
+        &self.sessions
+    }
+
+    pub fn selected(&self) -> Option<&SessionSummary> {
+        self.sessions.get(self.selected)
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, steps: i64) {
+        if !self.sessions.is_empty() && !self.is_working() {
+            self.selected =
+                (self.selected as i64 + steps).rem_euclid(self.sessions.len() as i64) as usize;
+            self.exported = None;
+        }
+    }
+
+    pub fn replay(&self) -> Option<&Replay> {
+        self.replay.as_ref()
+    }
+
+    pub fn is_working(&self) -> bool {
+        self.working.is_some()
+    }
+
+    // False if there's nothing to work on, or the worker is busy
+    fn spawn(&mut self, work: impl FnOnce(SessionSummary) -> Done + Send + 'static) -> bool {
+        if self.is_working() {
+            return false;
+        }
+        let Some(summary) = self.selected().cloned() else {
+            return false;
+        };
+        let (sender, receiver) = bounded(1);
+        thread::spawn(move || {
+            let _ = sender.send(work(summary));
+        });
+        self.working = Some(receiver);
+        true
+    }
+
+    // Of the selected session, the profile is the fallback
+    pub fn open(&mut self, profile: Profile) -> bool {
+        self.spawn(move |summary| {
+            Done::Replay(Replay::open(&summary, summary.profile.unwrap_or(profile)))
+        })
+    }
+
+    pub fn close(&mut self) {
+        self.replay = None;
+    }
+
+    pub fn step(&mut self, steps: i64) {
+        if let Some(replay) = &mut self.replay {
+            replay.step(steps);
+        }
+    }
+
+    // Of the selected session, the profile is the fallback
+    pub fn export_selected(&mut self, profile: Profile, target: PathBuf) -> bool {
+        self.spawn(move |summary| {
+            let profile = summary.profile.unwrap_or(profile);
+            Done::Exported(export(&summary, profile, &target).map_err(|err| err.to_string()))
+        })
+    }
+
+    // The outcome of an export once the worker is done, it's kept for
+    // the page. A replay is opened instead, if still on the page.
+    pub fn poll(&mut self) -> Option<Result<PathBuf, String>> {
+        let done = match self.working.as_ref()?.try_recv() {
+            Ok(done) => done,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => {
+                error!("Session worker died");
+                self.working = None;
+                return None;
+            }
+        };
+        self.working = None;
+        match done {
+            Done::Replay(replay) => {
+                if self.active {
+                    self.replay = Some(replay);
+                }
+                None
+            }
+            Done::Exported(outcome) => {
+                self.exported = Some(outcome.clone());
+                Some(outcome)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rqprotocol::Node, session::WrittenFile};
+
+    fn finish(browser: &mut SessionBrowser) -> Option<Result<PathBuf, String>> {
+        loop {
+            let outcome = browser.poll();
+            if !browser.is_working() {
+                return outcome;
+            }
+            thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_sessions_are_replayed_and_exported() {
+        let directory =
+            std::env::temp_dir().join(format!("lnc-session-browser-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let journal = directory.join("2026-10-15T10-00-00.journal");
+        let recording = directory.join("2026-10-15T10-00-00.log");
+        fs::write(&journal, "2026-10-15T10:00:00+00:00 Session started\n").unwrap();
+        fs::write(&recording, b"$RQAPNG,001*1F\r\n$RQAACK,001,PNG*03\r\n").unwrap();
+        let contents = fs::read_to_string(&journal).unwrap();
+        let summary = SessionSummary::parse(&journal, &contents, Node::RedQueen(b'A'))
+            .map(|summary| SessionSummary {
+                files: vec![
+                    WrittenFile {
+                        path: recording.clone(),
+                        bytes: Some(36),
+                    },
+                    WrittenFile {
+                        path: directory.join("gone.log"),
+                        bytes: None,
+                    },
+                ],
+                ..summary
+            })
+            .unwrap();
+        let mut browser = SessionBrowser::default();
+        browser.start(vec![summary.clone()]);
+        assert!(browser.open(Profile::default()));
+        assert!(!browser.open(Profile::default()));
+        assert_eq!(finish(&mut browser), None);
+        let replay = browser.replay().unwrap();
+        assert_eq!(replay.recording.as_ref(), Some(&recording));
+        assert_eq!(replay.transcript.entries.len(), 2);
+        browser.step(5);
+        assert_eq!(browser.replay().unwrap().position, 1);
+        browser.close();
+
+        // Not onto the card they are on
+        let target = directory.join("export");
+        assert!(browser.export_selected(Profile::default(), target.clone()));
+        assert!(finish(&mut browser).unwrap().is_err());
+        fs::create_dir_all(&target).unwrap();
+        assert!(browser.export_selected(Profile::default(), target.clone()));
+        let refused = finish(&mut browser).unwrap().unwrap_err();
+        assert!(refused.contains("on the card"), "{}", refused);
+        assert_eq!(browser.exported, Some(Err(refused)));
+
+        let exported = copy(&summary, Profile::default(), &target).unwrap();
+        assert_eq!(exported, target.join("2026-10-15T10-00-00"));
+        assert!(exported.join("2026-10-15T10-00-00.journal").exists());
+        assert!(exported.join("2026-10-15T10-00-00.log").exists());
+        let text = fs::read_to_string(exported.join("2026-10-15T10-00-00.log.txt")).unwrap();
+        assert!(text.contains("2 sentences"), "{}", text);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
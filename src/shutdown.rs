@@ -0,0 +1,149 @@
+// Exit in a fixed order instead of the Drop impls racing each other.
+// Polling stops first so nothing new comes in, the recording is
+// flushed while the workers still run, the nodes are told to lock
+// their pyros, the journal is closed with what that went like, and
+// only then the workers are joined. They all
+// get the same timeout, one that doesn't stop in time is named in
+// the log and left behind, the process exits anyway.
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use log::{info, warn};
+
+pub const JOIN_TIMEOUT: Duration = Duration::from_secs(2);
+const JOIN_POLL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    StopPollers,
+    FlushRecording,
+    SafeState,
+    FlushJournal,
+    JoinWorkers,
+}
+
+// A component with a thread of its own. Once asked to stop, its
+// Drop impl doesn't wait for the thread anymore.
+pub trait Stoppable {
+    fn name(&self) -> &'static str;
+    // Without waiting for the thread
+    fn stop(&mut self);
+    // True once the thread is joined
+    fn try_join(&mut self) -> bool;
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ShutdownReport {
+    pub stages: Vec<Stage>,
+    // Of the workers left behind
+    pub stuck: Vec<&'static str>,
+}
+
+impl Stage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::StopPollers => "stopping pollers",
+            Stage::FlushRecording => "flushing the recording",
+            Stage::SafeState => "sending the safe state",
+            Stage::FlushJournal => "flushing the journal",
+            Stage::JoinWorkers => "joining workers",
+        }
+    }
+}
+
+impl ShutdownReport {
+    pub fn enter(&mut self, stage: Stage) {
+        info!("Shutdown: {}", stage.name());
+        self.stages.push(stage);
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.stuck.is_empty()
+    }
+}
+
+// Joins only a finished thread, a stuck one can't block the caller
+pub fn try_join(worker: &mut Option<JoinHandle<()>>) -> bool {
+    match worker {
+        Some(handle) if !handle.is_finished() => false,
+        _ => {
+            worker.take().map(JoinHandle::join);
+            true
+        }
+    }
+}
+
+// All are asked to stop first, so they wind down in parallel
+pub fn join_all(mut workers: Vec<&mut dyn Stoppable>, timeout: Duration) -> Vec<&'static str> {
+    for worker in workers.iter_mut() {
+        worker.stop();
+    }
+    let mut waited = Duration::ZERO;
+    loop {
+        workers.retain_mut(|worker| !worker.try_join());
+        if workers.is_empty() || waited >= timeout {
+            break;
+        }
+        thread::sleep(JOIN_POLL);
+        waited += JOIN_POLL;
+    }
+    for worker in workers.iter() {
+        warn!("{} didn't stop within {:?}", worker.name(), timeout);
+    }
+    workers.iter().map(|worker| worker.name()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam_channel::{unbounded, Sender};
+
+    struct Thread {
+        name: &'static str,
+        // Dropping it stops the thread
+        stop: Option<Sender<()>>,
+        worker: Option<JoinHandle<()>>,
+    }
+
+    impl Thread {
+        fn spawn(name: &'static str, stuck: bool) -> Self {
+            let (stop, receiver) = unbounded::<()>();
+            let worker = thread::spawn(move || {
+                let _ = receiver.recv();
+                if stuck {
+                    thread::sleep(Duration::from_secs(1));
+                }
+            });
+            Self {
+                name,
+                stop: Some(stop),
+                worker: Some(worker),
+            }
+        }
+    }
+
+    impl Stoppable for Thread {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn stop(&mut self) {
+            self.stop = None;
+        }
+
+        fn try_join(&mut self) -> bool {
+            try_join(&mut self.worker)
+        }
+    }
+
+    #[test]
+    fn test_stuck_workers_are_left_behind() {
+        let mut e32 = Thread::spawn("E32", false);
+        let mut telemetry = Thread::spawn("Telemetry", true);
+        let stuck = join_all(vec![&mut e32, &mut telemetry], Duration::from_millis(100));
+        assert_eq!(stuck, vec!["Telemetry"]);
+        assert!(e32.worker.is_none());
+        assert!(telemetry.worker.is_some());
+        assert!(join_all(vec![&mut e32], JOIN_TIMEOUT).is_empty());
+    }
+}
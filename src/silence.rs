@@ -0,0 +1,97 @@
+// A node reports in its telemetry when its ignition state machine
+// went into radio silence, and expects us to stay quiet then. The
+// Consort holds back commands to it until it reports otherwise, and
+// the target isn't polled meanwhile. The operator can override that,
+// e.g. to command it out of the silence. The override lasts until no
+// node is silent anymore, so it's never left on by accident.
+use crate::rqprotocol::Node;
+use crate::telemetry::parser::rq2::IgnitionSMState;
+
+#[derive(Debug, Default)]
+pub struct SilenceCompliance {
+    // In the order they went silent
+    silent: Vec<Node>,
+    overridden: bool,
+}
+
+impl SilenceCompliance {
+    // Of a state packet, the text to journal if the node changed
+    pub fn record(&mut self, node: Node, ignition: &IgnitionSMState) -> Option<String> {
+        let silent = matches!(ignition, IgnitionSMState::RadioSilence);
+        match (silent, self.expects_silence(&node)) {
+            (true, false) => {
+                self.silent.push(node);
+                Some(format!(
+                    "{} expects radio silence, commands to it held back",
+                    node
+                ))
+            }
+            (false, true) => {
+                self.silent.retain(|silent| *silent != node);
+                if self.silent.is_empty() {
+                    self.overridden = false;
+                }
+                Some(format!("{} left radio silence", node))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn expects_silence(&self, node: &Node) -> bool {
+        self.silent.contains(node)
+    }
+
+    pub fn silent(&self) -> &[Node] {
+        &self.silent
+    }
+
+    pub fn is_overridden(&self) -> bool {
+        self.overridden
+    }
+
+    // What the Consort holds back from
+    pub fn inhibited(&self) -> Vec<Node> {
+        match self.overridden {
+            true => vec![],
+            false => self.silent.clone(),
+        }
+    }
+
+    // Only while a node is silent, the new state otherwise
+    pub fn toggle_override(&mut self) -> Option<bool> {
+        if self.silent.is_empty() {
+            return None;
+        }
+        self.overridden = !self.overridden;
+        Some(self.overridden)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_ends_with_the_silence() {
+        let mut compliance = SilenceCompliance::default();
+        let rqb = Node::RedQueen(b'B');
+        assert_eq!(compliance.toggle_override(), None);
+        assert_eq!(compliance.record(rqb, &IgnitionSMState::Reset), None);
+        assert_eq!(
+            compliance.record(rqb, &IgnitionSMState::RadioSilence),
+            Some("RQB expects radio silence, commands to it held back".into())
+        );
+        assert_eq!(compliance.record(rqb, &IgnitionSMState::RadioSilence), None);
+        assert_eq!(compliance.inhibited(), vec![rqb]);
+        assert_eq!(compliance.toggle_override(), Some(true));
+        assert!(compliance.inhibited().is_empty());
+        assert!(compliance.expects_silence(&rqb));
+        assert_eq!(
+            compliance.record(rqb, &IgnitionSMState::Reset),
+            Some("RQB left radio silence".into())
+        );
+        assert!(!compliance.is_overridden());
+        compliance.record(rqb, &IgnitionSMState::RadioSilence);
+        assert_eq!(compliance.inhibited(), vec![rqb]);
+    }
+}
@@ -0,0 +1,264 @@
+// The HIL bench exposes the UART of the RedQueen through a TCP serial
+// bridge (ser2net). Over it the connection speaks the protocol as it
+// would through the E32, without the radio: --transport tcp:HOST:PORT
+// makes HOST:PORT the port to open. A bridge that went away, e.g. as
+// ser2net restarted, is reconnected with the next write or read. For
+// the timing of the radio link a latency with jitter is added each way.
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use log::{info, warn};
+
+use crate::connection::{Line, ANSWER_TIMEOUT};
+use crate::rqparser::MAX_BUFFER_SIZE;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+// Reads reconnect at most this often, in monitor or purge
+// mode nothing is written for a long time
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Transport {
+    #[default]
+    Serial,
+    Tcp(String),
+}
+
+// Of each direction, the jitter is added up to its amount
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Latency {
+    pub delay: Duration,
+    pub jitter: Duration,
+}
+
+pub struct TcpConnection {
+    address: String,
+    // Gone after the bridge closed it, until reconnected
+    stream: Option<TcpStream>,
+    connected_at: Instant,
+    received: VecDeque<u8>,
+    latency: Latency,
+    rng: u32,
+}
+
+impl FromStr for Transport {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "serial" => Ok(Transport::Serial),
+            Some(("tcp", address)) if address.contains(':') => Ok(Transport::Tcp(address.into())),
+            _ => Err(anyhow!(
+                "Transport {} is neither serial nor tcp:HOST:PORT",
+                s
+            )),
+        }
+    }
+}
+
+fn connect(address: &str) -> std::io::Result<TcpStream> {
+    let mut last_err = std::io::Error::new(ErrorKind::NotFound, "no address to connect to");
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(stream) => {
+                stream.set_nodelay(true)?;
+                stream.set_read_timeout(Some(ANSWER_TIMEOUT))?;
+                return Ok(stream);
+            }
+            Err(err) => last_err = err,
+        }
+    }
+    Err(last_err)
+}
+
+// HOST:PORT, unlike a serial port such as /dev/ttyUSB0 or COM3
+pub fn is_address(port: &str) -> bool {
+    port.rsplit_once(':').map_or(false, |(host, port)| {
+        !host.is_empty() && !host.contains(['/', '\\']) && port.parse::<u16>().is_ok()
+    })
+}
+
+impl TcpConnection {
+    pub fn connect(address: &str, latency: Latency) -> anyhow::Result<Self> {
+        let stream = connect(address)?;
+        info!("Connected to the bridge at {}", address);
+        Ok(Self {
+            address: address.into(),
+            stream: Some(stream),
+            connected_at: Instant::now(),
+            received: VecDeque::new(),
+            latency,
+            rng: 0x2545f491,
+        })
+    }
+
+    // xorshift, as for the simulated telemetry
+    fn random(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng as f32 / u32::MAX as f32
+    }
+
+    fn emulate_latency(&mut self) {
+        let latency = self.latency.delay + self.latency.jitter.mul_f32(self.random());
+        if !latency.is_zero() {
+            std::thread::sleep(latency);
+        }
+    }
+
+    fn stream(&mut self) -> std::io::Result<&mut TcpStream> {
+        if self.stream.is_none() {
+            warn!("Reconnecting to the bridge at {}", self.address);
+            self.connected_at = Instant::now();
+            self.stream = Some(connect(&self.address)?);
+        }
+        Ok(self.stream.as_mut().unwrap())
+    }
+
+    // Once more after reconnecting, the bridge may have restarted
+    fn send(&mut self, data: &[u8]) -> std::io::Result<()> {
+        if let Err(err) = self.stream()?.write_all(data) {
+            warn!("Writing to the bridge failed: {}", err);
+            self.stream = None;
+            self.stream()?.write_all(data)?;
+        }
+        Ok(())
+    }
+}
+
+impl Line for TcpConnection {
+    fn write_buffer(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        self.emulate_latency();
+        Ok(self.send(data)?)
+    }
+
+    fn read(&mut self) -> nb::Result<u8, std::io::Error> {
+        if let Some(b) = self.received.pop_front() {
+            return Ok(b);
+        }
+        if self.stream.is_none() && self.connected_at.elapsed() < RECONNECT_INTERVAL {
+            return Err(nb::Error::Other(ErrorKind::NotConnected.into()));
+        }
+        let stream = self.stream().map_err(nb::Error::Other)?;
+        let mut buffer = [0; MAX_BUFFER_SIZE];
+        match stream.read(&mut buffer) {
+            Ok(0) => {
+                warn!("The bridge at {} closed the connection", self.address);
+                self.stream = None;
+                Err(nb::Error::Other(ErrorKind::ConnectionAborted.into()))
+            }
+            Ok(count) => {
+                self.emulate_latency();
+                self.received.extend(&buffer[..count]);
+                Ok(self.received.pop_front().unwrap())
+            }
+            // Which one depends on the platform
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                Err(nb::Error::Other(ErrorKind::TimedOut.into()))
+            }
+            Err(err) => {
+                self.stream = None;
+                Err(nb::Error::Other(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    fn read_line(connection: &mut TcpConnection) -> Vec<u8> {
+        let mut line = vec![];
+        while line.last() != Some(&b'\n') {
+            match connection.read() {
+                Ok(b) => line.push(b),
+                Err(nb::Error::Other(err)) if err.kind() == ErrorKind::TimedOut => {}
+                Err(err) => panic!("{:?}", err),
+            }
+        }
+        line
+    }
+
+    #[test]
+    fn test_transports_are_parsed() {
+        assert_eq!("serial".parse::<Transport>().unwrap(), Transport::Serial);
+        assert_eq!(
+            "tcp:bench:2001".parse::<Transport>().unwrap(),
+            Transport::Tcp("bench:2001".into())
+        );
+        assert!("tcp:bench".parse::<Transport>().is_err());
+        assert!("usb".parse::<Transport>().is_err());
+    }
+
+    #[test]
+    fn test_addresses_are_told_from_serial_ports() {
+        assert!(is_address("bench:2001"));
+        assert!(is_address("127.0.0.1:2001"));
+        assert!(!is_address("/dev/ttyUSB0"));
+        assert!(!is_address("COM3"));
+        assert!(!is_address(
+            "/dev/serial/by-path/pci-0000:00:14.0-usb-0:1:1.0"
+        ));
+        assert!(!is_address(""));
+    }
+
+    #[test]
+    fn test_reads_reconnect_without_a_write() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let bridge = std::thread::spawn(move || {
+            drop(listener.accept().unwrap());
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"$RQAMON*00\r\n").unwrap();
+        });
+        let mut connection = TcpConnection::connect(&address, Latency::default()).unwrap();
+        while connection.stream.is_some() {
+            let _ = connection.read();
+        }
+        // Not right away
+        assert!(matches!(
+            connection.read(),
+            Err(nb::Error::Other(err)) if err.kind() == ErrorKind::NotConnected
+        ));
+        connection.connected_at -= RECONNECT_INTERVAL;
+        assert_eq!(read_line(&mut connection), b"$RQAMON*00\r\n");
+        bridge.join().unwrap();
+    }
+
+    #[test]
+    fn test_the_bridge_is_reconnected_after_it_closed() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        // Answers one sentence per connection, like a restarting bridge
+        let bridge = std::thread::spawn(move || {
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buffer = [0; MAX_BUFFER_SIZE];
+                let count = stream.read(&mut buffer).unwrap();
+                assert_eq!(&buffer[..count], b"$RQAPNG,001*1F\r\n");
+                stream.write_all(b"$RQAACK,001,PNG*03\r\n").unwrap();
+            }
+        });
+        let latency = Latency {
+            delay: Duration::from_millis(1),
+            jitter: Duration::from_millis(1),
+        };
+        let mut connection = TcpConnection::connect(&address, latency).unwrap();
+        for _ in 0..2 {
+            connection.write_buffer(b"$RQAPNG,001*1F\r\n").unwrap();
+            assert_eq!(read_line(&mut connection), b"$RQAACK,001,PNG*03\r\n");
+            // Until the bridge closed it
+            while connection.stream.is_some() {
+                let _ = connection.read();
+            }
+        }
+        bridge.join().unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+// NRF payloads are meant to be whole 32 byte frames, but a frame can
+// arrive in shorter pieces, or two frames merged into one payload.
+// The assembler keeps the bytes of a node until a frame is complete
+// and splits merged payloads. A piece that isn't completed in time is
+// dropped and reported as malformed, so the next frame starts clean.
+//
+// Frames start with the sequence and the packet type, see
+// parser/rq2.rs. Bytes not starting a known packet type are skipped
+// until one does, so a lost piece doesn't shift all frames after it.
+// A whole frame arriving on top of a piece means the piece was short.
+use std::time::Duration;
+
+use crate::clock::Instant;
+
+pub const FRAME_SIZE: usize = 32;
+// The pieces of a frame are sent back to back
+const PIECE_TIMEOUT: Duration = Duration::from_millis(100);
+// State, IMU set A and B, config
+const PACKET_TYPES: u8 = 4;
+
+#[derive(Debug, Default)]
+pub struct FrameAssembler {
+    pending: Vec<u8>,
+    // Of the first byte pending
+    since: Option<Instant>,
+}
+
+// Unknown until the packet type is in
+fn starts_frame(bytes: &[u8]) -> bool {
+    bytes
+        .get(1)
+        .map_or(true, |packet_type| *packet_type < PACKET_TYPES)
+}
+
+impl FrameAssembler {
+    // The frames completed by the payload, in order, or why bytes
+    // were dropped
+    pub fn feed(&mut self, now: Instant, payload: &[u8]) -> Vec<Result<Vec<u8>, String>> {
+        let mut frames = vec![];
+        if !self.pending.is_empty() && payload.len() == FRAME_SIZE && starts_frame(payload) {
+            frames.push(Err(format!(
+                "short frame of {} bytes dropped",
+                self.pending.len()
+            )));
+            self.pending.clear();
+        }
+        if self.pending.is_empty() {
+            self.since = Some(now);
+        }
+        self.pending.extend_from_slice(payload);
+        let mut skipped = 0;
+        while !self.pending.is_empty() {
+            if !starts_frame(&self.pending) {
+                self.pending.remove(0);
+                skipped += 1;
+                continue;
+            }
+            if self.pending.len() < FRAME_SIZE {
+                break;
+            }
+            if skipped > 0 {
+                frames.push(Err(format!("{} bytes skipped to resync", skipped)));
+                skipped = 0;
+            }
+            frames.push(Ok(self.pending.drain(..FRAME_SIZE).collect()));
+            self.since = Some(now);
+        }
+        if skipped > 0 {
+            frames.push(Err(format!("{} bytes skipped to resync", skipped)));
+        }
+        if self.pending.is_empty() {
+            self.since = None;
+        }
+        frames
+    }
+
+    // Why an incomplete piece was dropped, if one was
+    pub fn expire(&mut self, now: Instant) -> Option<String> {
+        let since = self.since?;
+        if now.duration_since(since) < PIECE_TIMEOUT {
+            return None;
+        }
+        let dropped = std::mem::take(&mut self.pending);
+        self.since = None;
+        Some(format!(
+            "incomplete frame of {} bytes dropped",
+            dropped.len()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Of a state packet, padded with what is no packet type
+    fn frame(seq: u8) -> Vec<u8> {
+        let mut frame = vec![seq, 0, 1, 2, 3, 4, 5, 6];
+        frame.resize(FRAME_SIZE, 0xAA);
+        frame
+    }
+
+    #[test]
+    fn test_frames_are_reassembled() {
+        let start = Instant::now();
+        let mut assembler = FrameAssembler::default();
+        let (a, b) = (frame(0x10), frame(0x20));
+        assert_eq!(assembler.feed(start, &a), vec![Ok(a.clone())]);
+        // In pieces
+        assert!(assembler.feed(start, &b[..10]).is_empty());
+        assert_eq!(assembler.expire(start + PIECE_TIMEOUT / 2), None);
+        assert_eq!(assembler.feed(start, &b[10..]), vec![Ok(b.clone())]);
+        // Merged, with the start of the next one
+        let merged = [&a[..], &b[..], &a[..5]].concat();
+        assert_eq!(
+            assembler.feed(start, &merged),
+            vec![Ok(a.clone()), Ok(b.clone())]
+        );
+        assert_eq!(
+            assembler.expire(start + PIECE_TIMEOUT),
+            Some("incomplete frame of 5 bytes dropped".into())
+        );
+        assert_eq!(assembler.expire(start + PIECE_TIMEOUT * 2), None);
+        assert_eq!(assembler.feed(start, &a), vec![Ok(a.clone())]);
+    }
+
+    #[test]
+    fn test_lost_pieces_are_resynced() {
+        let start = Instant::now();
+        let mut assembler = FrameAssembler::default();
+        let (a, b) = (frame(0x10), frame(0x20));
+        // The start of a frame lost, the rest is skipped
+        let shifted = [&a[10..], &b[..]].concat();
+        assert_eq!(
+            assembler.feed(start, &shifted),
+            vec![Err("22 bytes skipped to resync".into()), Ok(b.clone())]
+        );
+        // The end of one lost, the next whole one replaces it
+        assert!(assembler.feed(start, &a[..10]).is_empty());
+        assert_eq!(
+            assembler.feed(start, &b),
+            vec![Err("short frame of 10 bytes dropped".into()), Ok(b.clone())]
+        );
+        assert_eq!(assembler.expire(start + PIECE_TIMEOUT), None);
+    }
+}
@@ -1,101 +1,289 @@
-use crate::rqprotocol::Node;
+use crate::error::{Error, ErrorReporter};
+use crate::{
+    clock::{Instant, SharedClock},
+    rqprotocol::Node,
+    run::RunId,
+    shutdown::Stoppable,
+};
 use ::zmq::{Context, Socket};
 use log::error;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::Duration;
 use std::{cell::RefCell, rc::Rc};
 
-use self::parser::rq2::{packet_parser, TelemetryPacket};
+use self::framing::FRAME_SIZE;
+use self::parser::rq2::{packet_parser, ImuRanges, TelemetryPacket};
+use self::relay::{Backlog, CurveClient, RelayFormat, Stamper};
+use self::roster::ChannelConfig;
+use self::sim::{SimConfig, SimulatedNRFConnector};
+use self::summary::{Aggregator, SUMMARY_TOPIC};
 
 #[cfg(feature = "novaview")]
 pub mod nrf;
 #[cfg(not(feature = "novaview"))]
 pub mod zmq;
 
+pub mod framing;
 pub mod parser;
+pub mod relay;
+pub mod retention;
+pub mod roster;
+pub mod sim;
+pub mod summary;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Message {
     pub node: Node,
     pub data: [u8; 32],
+    // When it was relayed, in µs since the epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at: Option<u64>,
+    // Of the publisher
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<RunId>,
 }
 
 #[derive(Clone)]
 pub enum RawTelemetryPacket {
     Frame(Node, Vec<u8>),
+    // Replayed by the publisher, relayed that long ago
+    Backfilled(Node, Vec<u8>, Duration),
+    // Periodically for nodes without a working module
     NoModule(Node),
+    // What couldn't be framed, and why
+    Malformed(Node, String),
+}
+
+// Nodes we haven't got any packet for are unknown
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModuleStatus {
+    Receiving,
+    NoModule,
+}
+
+// Of connectors getting the telemetry relayed over the network
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SubscriptionHealth {
+    pub connected: bool,
+    // Failed connection attempts
+    pub attempts: usize,
+    // Since the last frame relayed, of any node
+    pub silence: Option<Duration>,
+    pub replaying: bool,
+    // Frames got from the replays
+    pub backfilled: usize,
+    // Of the publisher, if it stamps one
+    pub relay_run: Option<RunId>,
 }
 
 pub trait NRFConnector {
     fn registered_nodes(&self) -> &Vec<Node>;
     fn heard_from_since(&self, node: &Node) -> Duration;
     fn drive(&mut self) -> Vec<RawTelemetryPacket>;
+
+    // None if the receiver is attached to us
+    fn subscription(&self) -> Option<SubscriptionHealth> {
+        None
+    }
+
+    // Of the nodes and their channels, once changed
+    fn set_roster(&mut self, _roster: &[ChannelConfig]) {}
+
+    // Joined on exit, see shutdown.rs
+    fn worker(&mut self) -> Option<&mut dyn Stoppable> {
+        None
+    }
 }
 
 #[cfg(not(feature = "novaview"))]
-pub fn create() -> Rc<RefCell<dyn NRFConnector>> {
-    Rc::new(RefCell::new(
-        zmq::ZMQSubscriberNRFConnector::new("tcp://novaview.local:2424").unwrap(),
-    ))
+pub fn create(
+    clock: SharedClock,
+    client: Option<CurveClient>,
+    replay_uri: &str,
+    roster: &[ChannelConfig],
+) -> Rc<RefCell<dyn NRFConnector>> {
+    let mut subscriber =
+        zmq::ZMQSubscriberNRFConnector::new("tcp://novaview.local:2424", replay_uri, clock, client)
+            .unwrap();
+    subscriber.set_roster(roster);
+    Rc::new(RefCell::new(subscriber))
 }
 
 #[cfg(feature = "novaview")]
-pub fn create() -> Rc<RefCell<dyn NRFConnector>> {
-    let telemetry = nrf::TelemetryFrontend::new(nrf::DEFAULT_CONFIGURATION.into_iter()).unwrap();
+pub fn create(
+    clock: SharedClock,
+    _client: Option<CurveClient>,
+    _replay_uri: &str,
+    roster: &[ChannelConfig],
+) -> Rc<RefCell<dyn NRFConnector>> {
+    let telemetry = nrf::TelemetryFrontend::new(roster, clock).unwrap();
     Rc::new(RefCell::new(telemetry))
 }
 
+pub fn create_simulated(config: SimConfig, clock: SharedClock) -> Rc<RefCell<dyn NRFConnector>> {
+    Rc::new(RefCell::new(SimulatedNRFConnector::new(config, clock)))
+}
+
 pub struct ZMQPublisher {
-    #[allow(dead_code)]
     context: Context,
     socket: Socket,
+    format: RelayFormat,
+    // Frames per message in the binary format
+    batch: usize,
+    secret_key: Option<String>,
+    stamper: Stamper,
+    run: Option<RunId>,
+    // Answering replay requests, with what's kept for them
+    replay: Option<(Socket, Backlog)>,
+    // Parsed and aggregated for lightweight consumers, see summary.rs
+    summaries: Option<Aggregator>,
     pub count: usize,
 }
 
 impl ZMQPublisher {
-    pub fn new(uri: &str) -> anyhow::Result<Self> {
+    // Without a secret key anyone on the network can subscribe,
+    // with one only the allowed clients
+    pub fn new(
+        uri: &str,
+        format: RelayFormat,
+        batch: usize,
+        secret_key: Option<&str>,
+        allowed_clients: &[String],
+    ) -> anyhow::Result<Self> {
         let context = Context::new();
         let socket = context.socket(::zmq::PUB)?;
+        if let Some(secret_key) = secret_key {
+            relay::allow_clients(&context, allowed_clients)?;
+            relay::secure_server(&socket, secret_key)?;
+        }
         socket.bind(uri)?;
         Ok(Self {
             context,
             socket,
+            format,
+            batch,
+            secret_key: secret_key.map(Into::into),
+            stamper: Stamper::default(),
+            run: None,
+            replay: None,
+            summaries: None,
             count: 0,
         })
     }
 
+    // Stamped into the frames relayed from now on
+    pub fn set_run(&mut self, run: RunId) {
+        self.run = Some(run);
+    }
+
+    // Keeps the frames of the backlog for subscribers to request
+    pub fn serve_replay(&mut self, uri: &str, backlog: Duration) -> anyhow::Result<()> {
+        let socket = self.context.socket(::zmq::REP)?;
+        if let Some(secret_key) = &self.secret_key {
+            relay::secure_server(&socket, secret_key)?;
+        }
+        socket.bind(uri)?;
+        self.replay = Some((socket, Backlog::new(backlog)));
+        Ok(())
+    }
+
+    // Also published on the summary topic, each interval
+    pub fn publish_summaries(&mut self, interval: Duration, imu_ranges: HashMap<Node, ImuRanges>) {
+        let mut aggregator = Aggregator::new(interval);
+        aggregator.set_imu_ranges(imu_ranges);
+        self.summaries = Some(aggregator);
+    }
+
+    // Only frames of the same call are batched, so
+    // relaying doesn't add latency.
     pub fn publish_telemetry_data(&mut self, messages: &Vec<RawTelemetryPacket>) {
+        let mut frames = vec![];
         for data in messages.into_iter() {
             match data {
-                RawTelemetryPacket::Frame(node, data) => {
-                    self.count += data.len();
-                    let message = Message {
-                        node: *node,
-                        data: (*data).clone().try_into().unwrap(),
-                    };
-
-                    let j = serde_json::to_string(&message).unwrap();
-                    let _ = self.socket.send(&j.as_bytes(), 0);
-                }
-                RawTelemetryPacket::NoModule(_) => {}
+                RawTelemetryPacket::Frame(node, data) => match data.as_slice().try_into() {
+                    Ok(data) => {
+                        self.count += FRAME_SIZE;
+                        frames.push(Message {
+                            node: *node,
+                            data,
+                            at: Some(self.stamper.stamp()),
+                            run: self.run,
+                        });
+                    }
+                    Err(_) => error!("Not relaying a {} byte frame of {}", data.len(), node),
+                },
+                // Relayed before, with the time they were
+                RawTelemetryPacket::Backfilled(..)
+                | RawTelemetryPacket::NoModule(_)
+                | RawTelemetryPacket::Malformed(..) => {}
+            }
+        }
+        for message in relay::encode(self.format, &frames, self.batch) {
+            let _ = self.socket.send(&message, 0);
+        }
+        if let Some(aggregator) = &mut self.summaries {
+            for frame in frames.iter() {
+                aggregator.push(frame.node, &frame.data);
+            }
+            if let Some(summary) = aggregator.poll(Instant::now(), self.run) {
+                let _ = self
+                    .socket
+                    .send_multipart([SUMMARY_TOPIC.to_vec(), summary.to_json()], 0);
+            }
+        }
+        if let Some((socket, backlog)) = &mut self.replay {
+            for frame in frames {
+                backlog.push(frame);
+            }
+            // Each request is answered right away, so there's
+            // never more than one waiting per subscriber
+            while let Ok(request) = socket.recv_bytes(::zmq::DONTWAIT) {
+                let reply = match relay::parse_replay_request(&request) {
+                    Ok(since) => relay::replay_reply(&backlog.since(since)),
+                    Err(err) => {
+                        error!("Bad replay request: {:?}", err);
+                        relay::replay_reply(&[])
+                    }
+                };
+                let _ = socket.send_multipart(reply, 0);
             }
         }
     }
 }
 
-pub fn process_raw_telemetry_data(raw: &Vec<RawTelemetryPacket>) -> Vec<TelemetryPacket> {
+// Nodes without configured IMU ranges use the defaults. Each packet
+// comes with how long ago it was received, none unless backfilled.
+pub fn process_raw_telemetry_data(
+    raw: &Vec<RawTelemetryPacket>,
+    imu_ranges: &HashMap<Node, ImuRanges>,
+    reporter: &ErrorReporter,
+) -> Vec<(TelemetryPacket, Duration)> {
     let mut res = vec![];
     for packet in raw.into_iter() {
-        match packet {
-            RawTelemetryPacket::Frame(node, data) => match packet_parser(*node, data) {
-                Ok((_, packet)) => {
-                    res.push(packet);
-                }
-                Err(err) => {
-                    error!("telemetry packet error: {:?}", err);
-                }
-            },
-            RawTelemetryPacket::NoModule(_) => todo!(),
+        let (node, data, age) = match packet {
+            RawTelemetryPacket::Frame(node, data) => (node, data, Duration::ZERO),
+            RawTelemetryPacket::Backfilled(node, data, age) => (node, data, *age),
+            RawTelemetryPacket::Malformed(node, reason) => {
+                reporter.report(Error::Telemetry {
+                    node: *node,
+                    reason: reason.clone(),
+                });
+                continue;
+            }
+            // Tracked by the model as module status
+            RawTelemetryPacket::NoModule(_) => continue,
+        };
+        match packet_parser(
+            *node,
+            &imu_ranges.get(node).copied().unwrap_or_default(),
+            data,
+        ) {
+            Ok((_, packet)) => res.push((packet, age)),
+            Err(err) => reporter.report(Error::Telemetry {
+                node: *node,
+                reason: format!("{:?}", err.map_input(|i| i.len())),
+            }),
         }
     }
     res
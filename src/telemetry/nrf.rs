@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use anyhow::anyhow;
 use crossbeam_channel::{unbounded, Receiver, Sender};
@@ -16,10 +16,16 @@ use linux_embedded_hal::{
     spidev::{SpiModeFlags, Spidev, SpidevOptions},
     CdevPin, CdevPinError,
 };
-use log::{info, warn};
-
-use super::{Message, NRFConnector, TelemetryData};
-use crate::rqprotocol::Node;
+use log::{error, info, warn};
+
+use super::framing::FrameAssembler;
+use super::roster::{self, ChannelConfig};
+use super::{NRFConnector, RawTelemetryPacket};
+use crate::{
+    clock::{Instant, SharedClock},
+    rqprotocol::Node,
+    shutdown::{self, Stoppable},
+};
 
 type SpiError = embedded_nrf24l01::Error<std::io::Error>;
 type NRFStandby = StandbyMode<NRF24L01<CdevPinError, CEPin, NullPin, SpiWrapper>>;
@@ -27,25 +33,6 @@ type NRFRx = RxMode<NRF24L01<CdevPinError, CEPin, NullPin, SpiWrapper>>;
 
 const PIPE_ADDRESS: &[u8] = b"FARAF";
 
-pub const DEFAULT_CONFIGURATION: [Config; 4] = [
-    Config {
-        node: Node::RedQueen(b'B'),
-        channel: 0,
-    },
-    Config {
-        node: Node::RedQueen(b'T'),
-        channel: 125,
-    },
-    Config {
-        node: Node::Farduino(b'T'),
-        channel: 32,
-    },
-    Config {
-        node: Node::Farduino(b'B'),
-        channel: 64,
-    },
-];
-
 struct NullPin {}
 impl OutputPin for NullPin {
     type Error = CdevPinError;
@@ -157,12 +144,6 @@ fn enumerate_nrf_modules(chip: &mut Chip) -> impl Iterator<Item = NRFEntry> {
     nrfs.into_iter()
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct Config {
-    pub node: Node,
-    pub channel: u8,
-}
-
 #[derive(Debug)]
 enum NRFOrDummy {
     Working(NRFRx),
@@ -170,23 +151,33 @@ enum NRFOrDummy {
 }
 
 impl NRFOrDummy {
-    fn read(&mut self, res: &mut Vec<TelemetryData>, node: Node) {
+    fn read(
+        &mut self,
+        res: &mut Vec<RawTelemetryPacket>,
+        node: Node,
+        assembler: &mut FrameAssembler,
+        now: Instant,
+    ) {
         match self {
             NRFOrDummy::Working(nrf) => {
+                if let Some(reason) = assembler.expire(now) {
+                    res.push(RawTelemetryPacket::Malformed(node, reason));
+                }
                 if let Some(_) = nrf.can_read().unwrap() {
                     let payload = nrf.read().unwrap();
-                    let data: &[u8] = &payload;
-                    if data.len() > 0 {
-                        let data = TelemetryData::Frame(node, data.into());
-                        res.push(data);
+                    for frame in assembler.feed(now, &payload) {
+                        res.push(match frame {
+                            Ok(frame) => RawTelemetryPacket::Frame(node, frame),
+                            Err(reason) => RawTelemetryPacket::Malformed(node, reason),
+                        });
                     }
                 }
             }
             NRFOrDummy::Dummy(last_timestamp) => {
-                let elapsed = Instant::now() - *last_timestamp;
+                let elapsed = now - *last_timestamp;
                 if elapsed.as_secs() > 5 {
-                    *last_timestamp = Instant::now();
-                    res.push(TelemetryData::NoModule(node));
+                    *last_timestamp = now;
+                    res.push(RawTelemetryPacket::NoModule(node));
                 }
             }
         }
@@ -197,51 +188,56 @@ impl NRFOrDummy {
 struct TelemetryConnection {
     nrf: NRFOrDummy,
     node: Node,
+    assembler: FrameAssembler,
 }
 
 impl TelemetryConnection {
-    fn new(config: Config, nrf: NRFEntry) -> Self {
+    fn new(config: ChannelConfig, nrf: NRFEntry, now: Instant) -> Self {
         let nrf = match nrf {
             NRFEntry::Working(mut nrf) => match nrf.set_frequency(config.channel) {
                 Ok(_) => match nrf.rx() {
                     Ok(rx_nrf) => NRFOrDummy::Working(rx_nrf),
                     Err(_) => {
                         warn!("Can't get module into RX mode");
-                        NRFOrDummy::Dummy(Instant::now())
+                        NRFOrDummy::Dummy(now)
                     }
                 },
                 Err(_) => {
                     warn!("Can't set frequency for {:?}", config);
-                    NRFOrDummy::Dummy(Instant::now())
+                    NRFOrDummy::Dummy(now)
                 }
             },
-            NRFEntry::Unavailable => NRFOrDummy::Dummy(Instant::now()),
+            NRFEntry::Unavailable => NRFOrDummy::Dummy(now),
         };
         Self {
             node: config.node,
             nrf,
+            assembler: FrameAssembler::default(),
         }
     }
 
-    fn read(&mut self) -> Vec<TelemetryData> {
+    fn read(&mut self, now: Instant) -> Vec<RawTelemetryPacket> {
         let mut res = vec![];
-        self.nrf.read(&mut res, self.node);
+        self.nrf.read(&mut res, self.node, &mut self.assembler, now);
         res
     }
 }
 
 pub struct TelemetryEndpoint {
     worker: Option<JoinHandle<()>>,
-    command_receiver: Receiver<TelemetryData>,
+    command_receiver: Receiver<RawTelemetryPacket>,
     running: Arc<Mutex<bool>>,
     start: Instant,
     last_comms: HashMap<Node, Instant>,
     registered_nodes: Vec<Node>,
+    clock: SharedClock,
+    // Asked to stop by the shutdown
+    stopping: bool,
 }
 
 impl TelemetryEndpoint {
     pub fn heard_from_since(&self, node: &Node) -> Duration {
-        Instant::now()
+        self.clock.now()
             - if self.last_comms.contains_key(node) {
                 self.last_comms[node]
             } else {
@@ -262,35 +258,57 @@ impl TelemetryEndpoint {
         self.worker.take().map(JoinHandle::join);
     }
 
-    fn drive(&mut self) -> Vec<TelemetryData> {
+    fn drive(&mut self) -> Vec<RawTelemetryPacket> {
         let mut res = vec![];
         for data in self.command_receiver.try_iter() {
             match data {
-                TelemetryData::Frame(node, _) => {
-                    self.last_comms.insert(node, Instant::now());
+                RawTelemetryPacket::Frame(node, _)
+                | RawTelemetryPacket::Backfilled(node, ..)
+                | RawTelemetryPacket::Malformed(node, _) => {
+                    self.last_comms.insert(node, self.clock.now());
                     res.push(data.clone());
                 }
-                TelemetryData::NoModule(_) => {}
+                // Passed on so the frontend can tell it apart from silence
+                RawTelemetryPacket::NoModule(_) => res.push(data.clone()),
             }
         }
         res
     }
 }
 
+impl Stoppable for TelemetryEndpoint {
+    fn name(&self) -> &'static str {
+        "Telemetry worker"
+    }
+
+    fn stop(&mut self) {
+        self.stopping = true;
+        *self.running.lock().unwrap() = false;
+    }
+
+    fn try_join(&mut self) -> bool {
+        shutdown::try_join(&mut self.worker)
+    }
+}
+
 impl Drop for TelemetryEndpoint {
     fn drop(&mut self) {
-        self.quit();
+        // Left behind by the shutdown otherwise
+        if !self.stopping {
+            self.quit();
+        }
     }
 }
 fn work(
-    sender: Sender<TelemetryData>,
+    sender: Sender<RawTelemetryPacket>,
     mut connections: Vec<TelemetryConnection>,
     running: Arc<Mutex<bool>>,
+    clock: SharedClock,
 ) {
     loop {
         let mut sent = false;
         for conn in connections.iter_mut() {
-            for data in conn.read() {
+            for data in conn.read(clock.now()) {
                 sent = true;
                 sender.send(data).expect("crossbeam not working");
             }
@@ -307,11 +325,14 @@ fn work(
     }
 }
 
-pub fn setup_telemetry(configs: impl Iterator<Item = Config>) -> anyhow::Result<TelemetryEndpoint> {
+pub fn setup_telemetry(
+    configs: impl Iterator<Item = ChannelConfig>,
+    clock: SharedClock,
+) -> anyhow::Result<TelemetryEndpoint> {
     let mut chip = Chip::new::<PathBuf>("/dev/gpiochip0".into())?;
     let mut registered_nodes = vec![];
     let nrf_modules = enumerate_nrf_modules(&mut chip).collect::<Vec<NRFEntry>>();
-    let configs = configs.collect::<Vec<Config>>();
+    let configs = configs.collect::<Vec<ChannelConfig>>();
     if nrf_modules.len() < configs.len() {
         warn!(
             "Not enough modules for {} configurations, only configuring the first {}",
@@ -322,28 +343,32 @@ pub fn setup_telemetry(configs: impl Iterator<Item = Config>) -> anyhow::Result<
     let mut connections = vec![];
     for (config, nrf) in configs.into_iter().zip(nrf_modules.into_iter()) {
         registered_nodes.push(config.node.clone());
-        let conn = TelemetryConnection::new(config, nrf);
+        let conn = TelemetryConnection::new(config, nrf, clock.now());
         connections.push(conn);
     }
     let running = Arc::new(Mutex::new(true));
     let worker_running = running.clone();
-    let (command_sender, command_receiver) = unbounded::<TelemetryData>();
+    let (command_sender, command_receiver) = unbounded::<RawTelemetryPacket>();
+    let worker_clock = clock.clone();
     let handle = thread::spawn(move || {
-        work(command_sender, connections, worker_running);
+        work(command_sender, connections, worker_running, worker_clock);
     });
 
     Ok(TelemetryEndpoint {
         command_receiver,
         worker: Some(handle),
         running,
-        start: Instant::now(),
+        start: clock.now(),
         last_comms: HashMap::new(),
         registered_nodes,
+        clock,
+        stopping: false,
     })
 }
 
 pub struct TelemetryFrontend {
     endpoint: TelemetryEndpoint,
+    roster: Vec<ChannelConfig>,
 }
 
 impl NRFConnector for TelemetryFrontend {
@@ -355,49 +380,50 @@ impl NRFConnector for TelemetryFrontend {
         self.endpoint.heard_from_since(node)
     }
 
-    fn drive(&mut self) -> Vec<TelemetryData> {
+    fn drive(&mut self) -> Vec<RawTelemetryPacket> {
         self.endpoint.drive()
     }
-}
 
-impl TelemetryFrontend {
-    pub fn new(configs: impl Iterator<Item = Config>) -> anyhow::Result<Self> {
-        let endpoint = setup_telemetry(configs)?;
-        Ok(Self { endpoint })
+    fn worker(&mut self) -> Option<&mut dyn Stoppable> {
+        Some(&mut self.endpoint)
     }
-}
-
-#[cfg(test)]
-mod tests {
 
-    struct MessageParser {
-        buffer: Vec<u8>,
-    }
-
-    impl Default for MessageParser {
-        fn default() -> Self {
-            Self {
-                buffer: Default::default(),
+    // The new endpoint is set up before the old one is dropped. Should
+    // the modules still be held by it, they are released and set up again,
+    // falling back to the old roster if that fails too.
+    fn set_roster(&mut self, roster: &[ChannelConfig]) {
+        if self.roster == roster {
+            return;
+        }
+        let clock = self.endpoint.clock.clone();
+        let endpoint = setup_telemetry(roster.iter().copied(), clock.clone()).or_else(|err| {
+            warn!("Can't set up telemetry next to the old one, {:?}", err);
+            self.endpoint.quit();
+            setup_telemetry(roster.iter().copied(), clock.clone())
+        });
+        match endpoint {
+            Ok(endpoint) => {
+                info!("Telemetry set up for {:?}", roster::nodes(roster));
+                self.endpoint = endpoint;
+                self.roster = roster.into();
+            }
+            Err(err) => {
+                error!("Can't set up telemetry again, {:?}", err);
+                match setup_telemetry(self.roster.iter().copied(), clock) {
+                    Ok(endpoint) => self.endpoint = endpoint,
+                    Err(err) => error!("Can't restore telemetry, {:?}", err),
+                }
             }
         }
     }
+}
 
-    impl MessageParser {
-        fn feed(&mut self, data: &[u8], callback: impl FnMut(&[u8])) {}
-    }
-
-    #[test]
-    fn test_message_parser() {
-        // let mut parser = MessageParser::default();
-        // let mut result = vec![];
-        // let a = b"$1,0BEBC200,0000010495916A20,000";
-        // let b = b"70EE1\x13\x10\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
-        // assert_eq!(a.len(), 32);
-        // assert_eq!(b.len(), 32);
-        // parser.feed(a, |message| {
-        //     let message: Vec<u8> = message.into();
-        //     result.push(message);
-        // });
-        // assert_eq!(result.len(), 1);
+impl TelemetryFrontend {
+    pub fn new(roster: &[ChannelConfig], clock: SharedClock) -> anyhow::Result<Self> {
+        let endpoint = setup_telemetry(roster.iter().copied(), clock)?;
+        Ok(Self {
+            endpoint,
+            roster: roster.into(),
+        })
     }
 }
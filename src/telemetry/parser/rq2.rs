@@ -1,4 +1,5 @@
 use nom::{bytes::complete::take, combinator::fail, sequence::tuple, IResult};
+use serde::{Deserialize, Serialize};
 
 use crate::rqprotocol::Node;
 
@@ -10,30 +11,137 @@ enum PacketType {
     StatePacket = 0,
     ImuSetAPacket = 1,
     ImuSetBPacket = 2,
+    // The IMU ranges the node configured, sent on boot
+    ConfigPacket = 3,
 }
 
 #[derive(Debug, Clone)]
 pub struct Preamble {
     seq: isize,
     packet_type: PacketType,
-    timestamp: u32,
+    // Node uptime in microseconds
+    pub timestamp: u32,
 }
 
-enum BMI088AccRange {
+// In the order of their register values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BMI088AccRange {
+    #[serde(rename = "3g")]
     AccRange3g,
+    #[serde(rename = "6g")]
     AccRange6g,
+    #[serde(rename = "12g")]
     AccRange12g,
+    #[serde(rename = "24g")]
     AccRange24g,
 }
 
-enum BMI088GyrRange {
+pub const ACC_RANGES: [BMI088AccRange; 4] = [
+    BMI088AccRange::AccRange3g,
+    BMI088AccRange::AccRange6g,
+    BMI088AccRange::AccRange12g,
+    BMI088AccRange::AccRange24g,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BMI088GyrRange {
+    #[serde(rename = "2000dps")]
     GyrRange2000s,
+    #[serde(rename = "1000dps")]
     GyrRange1000s,
+    #[serde(rename = "500dps")]
     GyrRange500s,
+    #[serde(rename = "250dps")]
     GyrRange250s,
+    #[serde(rename = "125dps")]
     GyrRange125s,
 }
 
+pub const GYR_RANGES: [BMI088GyrRange; 5] = [
+    BMI088GyrRange::GyrRange2000s,
+    BMI088GyrRange::GyrRange1000s,
+    BMI088GyrRange::GyrRange500s,
+    BMI088GyrRange::GyrRange250s,
+    BMI088GyrRange::GyrRange125s,
+];
+
+// What the raw IMU values of a node are scaled with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImuRanges {
+    pub acc: BMI088AccRange,
+    pub gyr: BMI088GyrRange,
+}
+
+impl BMI088AccRange {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BMI088AccRange::AccRange3g => "3g",
+            BMI088AccRange::AccRange6g => "6g",
+            BMI088AccRange::AccRange12g => "12g",
+            BMI088AccRange::AccRange24g => "24g",
+        }
+    }
+
+    // In g per LSB
+    fn factor(&self) -> f32 {
+        match self {
+            BMI088AccRange::AccRange3g => 2.0_f32.powf(0.0 + 1.0) * 1.5 / 32768.0,
+            BMI088AccRange::AccRange6g => 2.0_f32.powf(1.0 + 1.0) * 1.5 / 32768.0,
+            BMI088AccRange::AccRange12g => 2.0_f32.powf(2.0 + 1.0) * 1.5 / 32768.0,
+            BMI088AccRange::AccRange24g => 2.0_f32.powf(3.0 + 1.0) * 1.5 / 32768.0,
+        }
+    }
+
+    pub fn step(&self, steps: i64) -> Self {
+        let index = ACC_RANGES.iter().position(|range| range == self).unwrap() as i64;
+        ACC_RANGES[(index + steps).rem_euclid(ACC_RANGES.len() as i64) as usize]
+    }
+}
+
+impl BMI088GyrRange {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BMI088GyrRange::GyrRange2000s => "2000dps",
+            BMI088GyrRange::GyrRange1000s => "1000dps",
+            BMI088GyrRange::GyrRange500s => "500dps",
+            BMI088GyrRange::GyrRange250s => "250dps",
+            BMI088GyrRange::GyrRange125s => "125dps",
+        }
+    }
+
+    // In degrees per second per LSB
+    fn factor(&self) -> f32 {
+        match self {
+            BMI088GyrRange::GyrRange2000s => 1.0 / 32768.0 * 2000.0,
+            BMI088GyrRange::GyrRange1000s => 1.0 / 32768.0 * 1000.0,
+            BMI088GyrRange::GyrRange500s => 1.0 / 32768.0 * 500.0,
+            BMI088GyrRange::GyrRange250s => 1.0 / 32768.0 * 250.0,
+            BMI088GyrRange::GyrRange125s => 1.0 / 32768.0 * 125.0,
+        }
+    }
+
+    pub fn step(&self, steps: i64) -> Self {
+        let index = GYR_RANGES.iter().position(|range| range == self).unwrap() as i64;
+        GYR_RANGES[(index + steps).rem_euclid(GYR_RANGES.len() as i64) as usize]
+    }
+}
+
+impl Default for ImuRanges {
+    fn default() -> Self {
+        Self {
+            acc: DEFAULT_ACC_RANGE,
+            gyr: DEFAULT_GYR_RANGE,
+        }
+    }
+}
+
+impl ImuRanges {
+    pub fn text(&self) -> String {
+        format!("{}/{}", self.acc.name(), self.gyr.name())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct IMUReading {
     pub acc_x: f32,
@@ -59,6 +167,12 @@ pub struct IMUPacket {
     pub temperature: f32,
 }
 
+impl IMUReading {
+    pub fn acc_magnitude(&self) -> f32 {
+        (self.acc_x * self.acc_x + self.acc_y * self.acc_y + self.acc_z * self.acc_z).sqrt()
+    }
+}
+
 // This needs to be in sync with
 // ignition-sm.h!
 #[derive(Debug, Clone)]
@@ -71,10 +185,39 @@ pub enum IgnitionSMState {
     RadioSilence,
 }
 
+// This needs to be in sync with
+// flight-sm.h!
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlightPhase {
+    Ground,
+    Armed,
+    Boost,
+    Coast,
+    Descent,
+    Landed,
+}
+
+// Pyro continuity as bitfield, a set bit
+// means the igniter circuit is closed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContinuityFlags(pub u8);
+
+// The state packet layout after the preamble:
+// ignition state (u8), flight phase (u8),
+// battery voltage in mV (u16), continuity (u8)
+#[derive(Debug, Clone)]
+pub struct StatePacket {
+    pub ignition: IgnitionSMState,
+    pub phase: FlightPhase,
+    pub battery_voltage: f32,
+    pub continuity: ContinuityFlags,
+}
+
 #[derive(Debug, Clone)]
 pub enum TelemetryData {
-    Ignition(IgnitionSMState),
+    State(StatePacket),
     IMU(IMUPacket),
+    Config(ImuRanges),
 }
 
 #[derive(Debug, Clone)]
@@ -98,6 +241,51 @@ fn ignition_state_parser(s: &[u8]) -> IResult<&[u8], IgnitionSMState> {
     Ok((rest, state))
 }
 
+fn flight_phase_parser(s: &[u8]) -> IResult<&[u8], FlightPhase> {
+    let (rest, c) = take(1 as usize)(s)?;
+    let phase = match c[0] {
+        0 => FlightPhase::Ground,
+        1 => FlightPhase::Armed,
+        2 => FlightPhase::Boost,
+        3 => FlightPhase::Coast,
+        4 => FlightPhase::Descent,
+        5 => FlightPhase::Landed,
+        _ => return fail(s),
+    };
+    Ok((rest, phase))
+}
+
+impl ContinuityFlags {
+    pub const PYRO12: u8 = 1 << 0;
+    pub const PYRO34: u8 = 1 << 1;
+
+    pub fn pyro12(&self) -> bool {
+        self.0 & Self::PYRO12 != 0
+    }
+
+    pub fn pyro34(&self) -> bool {
+        self.0 & Self::PYRO34 != 0
+    }
+}
+
+fn state_packet_parser(s: &[u8]) -> IResult<&[u8], StatePacket> {
+    let (rest, (ignition, phase, battery_voltage, continuity)) = tuple((
+        ignition_state_parser,
+        flight_phase_parser,
+        u16_parser,
+        take(1 as usize),
+    ))(s)?;
+    Ok((
+        rest,
+        StatePacket {
+            ignition,
+            phase,
+            battery_voltage: battery_voltage as f32 / 1000.0,
+            continuity: ContinuityFlags(continuity[0]),
+        },
+    ))
+}
+
 fn sequence_parser(s: &[u8]) -> IResult<&[u8], isize> {
     let (rest, c) = take(1 as usize)(s)?;
     Ok((rest, c[0] as isize))
@@ -109,6 +297,7 @@ fn packet_type_parser(s: &[u8]) -> IResult<&[u8], PacketType> {
         0 => PacketType::StatePacket,
         1 => PacketType::ImuSetAPacket,
         2 => PacketType::ImuSetBPacket,
+        3 => PacketType::ConfigPacket,
         _ => return fail(s),
     };
     Ok((rest, res))
@@ -123,36 +312,26 @@ fn u32_parser(s: &[u8]) -> IResult<&[u8], u32> {
     Ok((rest, res))
 }
 
-fn i16_parser(s: &[u8]) -> IResult<&[u8], i16> {
+fn u16_parser(s: &[u8]) -> IResult<&[u8], u16> {
     let (rest, prefix) = take(2 as usize)(s)?;
     let mut res: u16 = 0;
     for i in 0..2 {
         res |= (prefix[i] as u16) << (i * 8);
     }
+    Ok((rest, res))
+}
+
+fn i16_parser(s: &[u8]) -> IResult<&[u8], i16> {
+    let (rest, res) = u16_parser(s)?;
     Ok((rest, res as i16))
 }
 
-fn bmi088_parser(
-    acc_range: BMI088AccRange,
-    gyr_range: BMI088GyrRange,
-    s: &[u8],
-) -> IResult<&[u8], IMUReading> {
+fn bmi088_parser<'a>(ranges: &ImuRanges, s: &'a [u8]) -> IResult<&'a [u8], IMUReading> {
     let (rest, (ax, ay, az, gx, gy, gz)) = tuple((
         i16_parser, i16_parser, i16_parser, i16_parser, i16_parser, i16_parser,
     ))(s)?;
-    let af = match acc_range {
-        BMI088AccRange::AccRange3g => 2.0_f32.powf(0.0 + 1.0) * 1.5 / 32768.0,
-        BMI088AccRange::AccRange6g => 2.0_f32.powf(1.0 + 1.0) * 1.5 / 32768.0,
-        BMI088AccRange::AccRange12g => 2.0_f32.powf(2.0 + 1.0) * 1.5 / 32768.0,
-        BMI088AccRange::AccRange24g => 2.0_f32.powf(3.0 + 1.0) * 1.5 / 32768.0,
-    };
-    let gf = match gyr_range {
-        BMI088GyrRange::GyrRange2000s => 1.0 / 32768.0 * 2000.0,
-        BMI088GyrRange::GyrRange1000s => 1.0 / 32768.0 * 1000.0,
-        BMI088GyrRange::GyrRange500s => 1.0 / 32768.0 * 500.0,
-        BMI088GyrRange::GyrRange250s => 1.0 / 32768.0 * 250.0,
-        BMI088GyrRange::GyrRange125s => 1.0 / 32768.0 * 125.0,
-    };
+    let af = ranges.acc.factor();
+    let gf = ranges.gyr.factor();
     Ok((
         rest,
         IMUReading {
@@ -199,12 +378,8 @@ fn preamble_parser(s: &[u8]) -> IResult<&[u8], Preamble> {
     ))
 }
 
-fn imu_packet_parser(
-    acc_range: BMI088AccRange,
-    gyr_range: BMI088GyrRange,
-    s: &[u8],
-) -> IResult<&[u8], IMUPacket> {
-    let (rest, imu) = bmi088_parser(acc_range, gyr_range, s)?;
+fn imu_packet_parser<'a>(ranges: &ImuRanges, s: &'a [u8]) -> IResult<&'a [u8], IMUPacket> {
+    let (rest, imu) = bmi088_parser(ranges, s)?;
     let (rest, mag) = mag_parser(rest)?;
     let (rest, (pressure, temperature)) = tuple((f32_parser, f32_parser))(rest)?;
     Ok((
@@ -218,21 +393,48 @@ fn imu_packet_parser(
     ))
 }
 
-pub fn packet_parser(node: Node, s: &[u8]) -> IResult<&[u8], TelemetryPacket> {
+// Register values of the BMI088 range settings
+fn config_packet_parser(s: &[u8]) -> IResult<&[u8], ImuRanges> {
+    let (rest, codes) = take(2 as usize)(s)?;
+    match (
+        ACC_RANGES.get(codes[0] as usize),
+        GYR_RANGES.get(codes[1] as usize),
+    ) {
+        (Some(acc), Some(gyr)) => Ok((
+            rest,
+            ImuRanges {
+                acc: *acc,
+                gyr: *gyr,
+            },
+        )),
+        _ => fail(s),
+    }
+}
+
+// The IMU values are scaled with the ranges configured for the node
+pub fn packet_parser<'a>(
+    node: Node,
+    ranges: &ImuRanges,
+    s: &'a [u8],
+) -> IResult<&'a [u8], TelemetryPacket> {
     let (rest, preamble) = preamble_parser(s)?;
     let (rest, data) = match preamble.packet_type {
         PacketType::StatePacket => {
-            let (rest, state) = ignition_state_parser(rest)?;
-            (rest, TelemetryData::Ignition(state))
+            let (rest, state) = state_packet_parser(rest)?;
+            (rest, TelemetryData::State(state))
         }
         PacketType::ImuSetAPacket => {
-            let (rest, packet) = imu_packet_parser(DEFAULT_ACC_RANGE, DEFAULT_GYR_RANGE, rest)?;
+            let (rest, packet) = imu_packet_parser(ranges, rest)?;
             (rest, TelemetryData::IMU(packet))
         }
         PacketType::ImuSetBPacket => {
-            let (rest, packet) = imu_packet_parser(DEFAULT_ACC_RANGE, DEFAULT_GYR_RANGE, rest)?;
+            let (rest, packet) = imu_packet_parser(ranges, rest)?;
             (rest, TelemetryData::IMU(packet))
         }
+        PacketType::ConfigPacket => {
+            let (rest, ranges) = config_packet_parser(rest)?;
+            (rest, TelemetryData::Config(ranges))
+        }
     };
     Ok((
         rest,
@@ -288,7 +490,8 @@ mod tests {
     #[test]
     fn test_imu_packet_parsing() {
         let sentence = b"\x00\x02\xfe\xb7\xdd\x81\xfd\xff\n\x00S\x05\x00\x00\xf9\xff\xfd\xff\x9b\x02^\xf7K\xf7\x8f\x8b{D\x00\x00\x00\x00";
-        let (_rest, packet) = packet_parser(Node::RedQueen(b'B'), sentence).unwrap();
+        let (_rest, packet) =
+            packet_parser(Node::RedQueen(b'B'), &ImuRanges::default(), sentence).unwrap();
         assert_matches!(
             packet,
             TelemetryPacket {
@@ -297,16 +500,87 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_imu_ranges() {
+        let sentence = b"\x00\x02\xfe\xb7\xdd\x81\xfd\xff\n\x00S\x05\x00\x00\xf9\xff\xfd\xff\x9b\x02^\xf7K\xf7\x8f\x8b{D\x00\x00\x00\x00";
+        let imu = |ranges: &ImuRanges| match packet_parser(Node::RedQueen(b'B'), ranges, sentence)
+            .unwrap()
+            .1
+            .data
+        {
+            TelemetryData::IMU(packet) => packet.imu,
+            _ => panic!("expected an IMU packet"),
+        };
+        let default = imu(&ImuRanges::default());
+        let narrow = imu(&ImuRanges {
+            acc: BMI088AccRange::AccRange3g,
+            gyr: BMI088GyrRange::GyrRange250s,
+        });
+        assert_eq!(default.acc_x, narrow.acc_x * 8.0);
+        assert_eq!(default.gyr_z, narrow.gyr_z * 8.0);
+
+        let sentence = b"\x07\x03\x00\x00\x00\x00\x01\x03";
+        let (_rest, packet) =
+            packet_parser(Node::RedQueen(b'B'), &ImuRanges::default(), sentence).unwrap();
+        assert_matches!(
+            packet.data,
+            TelemetryData::Config(ImuRanges {
+                acc: BMI088AccRange::AccRange6g,
+                gyr: BMI088GyrRange::GyrRange250s,
+            })
+        );
+        assert!(packet_parser(
+            Node::RedQueen(b'B'),
+            &ImuRanges::default(),
+            b"\x07\x03\x00\x00\x00\x00\x04\x00"
+        )
+        .is_err());
+        assert_eq!(
+            serde_json::from_str::<ImuRanges>(r#"{"acc": "12g"}"#).unwrap(),
+            ImuRanges {
+                acc: BMI088AccRange::AccRange12g,
+                gyr: BMI088GyrRange::GyrRange2000s,
+            }
+        );
+        assert_eq!(
+            BMI088GyrRange::GyrRange125s.step(1),
+            BMI088GyrRange::GyrRange2000s
+        );
+    }
+
     #[test]
     fn test_ignition_state_packet_parsing() {
         let sentence = b"A\x00~\xdcvV\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
-        let (_rest, packet) = packet_parser(Node::RedQueen(b'B'), sentence).unwrap();
+        let (_rest, packet) =
+            packet_parser(Node::RedQueen(b'B'), &ImuRanges::default(), sentence).unwrap();
         assert_matches!(
             packet,
             TelemetryPacket {
-                data: TelemetryData::Ignition(IgnitionSMState::Reset),
+                data: TelemetryData::State(StatePacket {
+                    ignition: IgnitionSMState::Reset,
+                    phase: FlightPhase::Ground,
+                    ..
+                }),
                 ..
             }
         );
     }
+
+    #[test]
+    fn test_state_packet_parsing() {
+        let sentence = b"B\x00~\xdcvV\x03\x02\x2c\x10\x02\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+        let (_rest, packet) =
+            packet_parser(Node::RedQueen(b'B'), &ImuRanges::default(), sentence).unwrap();
+        match packet.data {
+            TelemetryData::State(state) => {
+                assert_matches!(state.ignition, IgnitionSMState::SecretAB);
+                assert_eq!(state.phase, FlightPhase::Boost);
+                assert_eq!(state.battery_voltage, 4.140);
+                assert!(!state.continuity.pyro12());
+                assert!(state.continuity.pyro34());
+            }
+            _ => panic!("expected a state packet"),
+        }
+    }
 }
@@ -0,0 +1,466 @@
+// Framing of the telemetry relayed over ZMQ. Originally each frame
+// was sent as a JSON object, which more than triples its size. The
+// binary envelope starts with a magic byte JSON can't start with and
+// a version, followed by a batch of frames, each as the three bytes
+// naming the node, the time it was relayed at and the raw frame.
+// Since the third version the header is followed by the run of the
+// publisher, all zero without one, so relayed frames can be matched
+// to the recordings of the same run. Subscribers accept both, and
+// the earlier versions of the envelope without the time or run.
+//
+// The publisher keeps the last minutes of frames, and answers replay
+// requests for everything relayed after a time with binary envelopes.
+// Subscribers joining late or after an outage use it to backfill.
+//
+// With keys configured the relay is authenticated and encrypted
+// using ZMQ's CURVE. The publisher holds the secret key and only lets
+// in subscribers whose public keys it was given, answering the ZAP
+// requests of its sockets. Subscribers need its public key and a pair
+// of their own. Keys are Z85 encoded as printed by libzmq's curve_keygen.
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use serde::Deserialize;
+
+use crate::rqprotocol::Node;
+use crate::run::RunId;
+
+use super::Message;
+
+pub const ENVELOPE_MAGIC: u8 = 0xA5;
+pub const ENVELOPE_VERSION: u8 = 3;
+// Without the run
+const ENVELOPE_VERSION_TIMED: u8 = 2;
+// Without the time per frame
+const ENVELOPE_VERSION_UNTIMED: u8 = 1;
+// Frames per envelope, the count is a single byte
+pub const DEFAULT_BATCH: usize = 8;
+const HEADER_SIZE: usize = 3;
+const NODE_SIZE: usize = 3;
+const TIME_SIZE: usize = 8;
+const RUN_SIZE: usize = 16;
+const FRAME_SIZE: usize = 32;
+const KEY_SIZE: usize = 32;
+// Where libzmq sends the authentication requests of a context
+const ZAP_ENDPOINT: &str = "inproc://zeromq.zap.01";
+// Of the replay, by default
+pub const DEFAULT_BACKLOG: Duration = Duration::from_secs(300);
+// Where the publisher answers replay requests, and where they go to
+pub const DEFAULT_REPLAY_BIND: &str = "tcp://0.0.0.0:2425";
+pub const DEFAULT_REPLAY_URI: &str = "tcp://novaview.local:2425";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+pub enum RelayFormat {
+    // Understood by subscribers predating the binary envelope
+    #[default]
+    Json,
+    Binary,
+}
+
+impl FromStr for RelayFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Json" => Ok(RelayFormat::Json),
+            "Binary" => Ok(RelayFormat::Binary),
+            _ => Err("No valid value, use Json, Binary"),
+        }
+    }
+}
+
+// One ZMQ message per returned buffer
+pub fn encode(format: RelayFormat, messages: &[Message], batch: usize) -> Vec<Vec<u8>> {
+    match format {
+        RelayFormat::Json => messages
+            .iter()
+            .map(|m| serde_json::to_vec(m).unwrap())
+            .collect(),
+        RelayFormat::Binary => messages
+            .chunks(batch.clamp(1, u8::MAX as usize))
+            .map(encode_envelope)
+            .collect(),
+    }
+}
+
+// The frames of a publisher are all of its run
+fn encode_envelope(messages: &[Message]) -> Vec<u8> {
+    let entry = NODE_SIZE + TIME_SIZE + FRAME_SIZE;
+    let mut res = Vec::with_capacity(HEADER_SIZE + RUN_SIZE + messages.len() * entry);
+    res.extend([ENVELOPE_MAGIC, ENVELOPE_VERSION, messages.len() as u8]);
+    match messages.first().and_then(|message| message.run) {
+        Some(run) => res.extend(run.as_bytes()),
+        None => res.extend([0; RUN_SIZE]),
+    }
+    for message in messages {
+        res.extend(message.node.to_string().as_bytes());
+        res.extend(message.at.unwrap_or(0).to_be_bytes());
+        res.extend(message.data);
+    }
+    res
+}
+
+pub fn decode(bytes: &[u8]) -> anyhow::Result<Vec<Message>> {
+    match bytes {
+        [ENVELOPE_MAGIC, version @ (ENVELOPE_VERSION_UNTIMED | ENVELOPE_VERSION_TIMED | ENVELOPE_VERSION), count, rest @ ..] =>
+        {
+            let time_size = match *version {
+                ENVELOPE_VERSION_UNTIMED => 0,
+                _ => TIME_SIZE,
+            };
+            let (run, frames) = match *version {
+                ENVELOPE_VERSION if rest.len() >= RUN_SIZE => {
+                    let (run, frames) = rest.split_at(RUN_SIZE);
+                    let run: [u8; RUN_SIZE] = run.try_into().unwrap();
+                    (
+                        (run != [0; RUN_SIZE]).then(|| RunId::from_bytes(run)),
+                        frames,
+                    )
+                }
+                ENVELOPE_VERSION => return Err(anyhow!("envelope without its run")),
+                _ => (None, rest),
+            };
+            let entry = NODE_SIZE + time_size + FRAME_SIZE;
+            if frames.len() != *count as usize * entry {
+                return Err(anyhow!(
+                    "envelope of {} frames has {} bytes",
+                    count,
+                    frames.len()
+                ));
+            }
+            frames
+                .chunks(entry)
+                .map(|frame| {
+                    let node = std::str::from_utf8(&frame[0..NODE_SIZE])?
+                        .parse()
+                        .map_err(|e: String| anyhow!(e))?;
+                    let (time, data) = frame[NODE_SIZE..].split_at(time_size);
+                    Ok(Message {
+                        node,
+                        data: data.try_into()?,
+                        at: (time_size > 0).then(|| u64::from_be_bytes(time.try_into().unwrap())),
+                        run,
+                    })
+                })
+                .collect()
+        }
+        [ENVELOPE_MAGIC, version, ..] => Err(anyhow!("unknown envelope version {}", version)),
+        _ => Ok(vec![serde_json::from_slice::<Message>(bytes)?]),
+    }
+}
+
+// Everything relayed after the time, 0 for the whole backlog
+pub fn replay_request(since: u64) -> [u8; TIME_SIZE] {
+    since.to_be_bytes()
+}
+
+pub fn parse_replay_request(bytes: &[u8]) -> anyhow::Result<u64> {
+    Ok(u64::from_be_bytes(bytes.try_into().map_err(|_| {
+        anyhow!("replay request has {} bytes", bytes.len())
+    })?))
+}
+
+// Always at least one envelope, as a multipart ZMQ message
+pub fn replay_reply(messages: &[Message]) -> Vec<Vec<u8>> {
+    match messages.is_empty() {
+        true => vec![encode_envelope(&[])],
+        false => encode(RelayFormat::Binary, messages, u8::MAX as usize),
+    }
+}
+
+// In µs since the epoch
+fn micros_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_micros() as u64)
+}
+
+// Of a frame stamped by the publisher, as far as the clocks agree
+pub fn age(at: u64) -> Duration {
+    Duration::from_micros(micros_now().saturating_sub(at))
+}
+
+// In µs since the epoch, strictly increasing with every frame
+// relayed, so subscribers can tell what they've already seen.
+#[derive(Debug, Default)]
+pub struct Stamper {
+    last: u64,
+}
+
+impl Stamper {
+    pub fn stamp(&mut self) -> u64 {
+        self.last = micros_now().max(self.last + 1);
+        self.last
+    }
+}
+
+// The frames of the last minutes, by their time
+#[derive(Debug)]
+pub struct Backlog {
+    window: Duration,
+    messages: VecDeque<Message>,
+}
+
+impl Backlog {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            messages: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, message: Message) {
+        let Some(at) = message.at else {
+            return;
+        };
+        let window = self.window.as_micros() as u64;
+        while let Some(oldest) = self.messages.front().and_then(|m| m.at) {
+            if at.saturating_sub(oldest) <= window {
+                break;
+            }
+            self.messages.pop_front();
+        }
+        self.messages.push_back(message);
+    }
+
+    pub fn since(&self, since: u64) -> Vec<Message> {
+        let start = self
+            .messages
+            .partition_point(|message| message.at.map_or(true, |at| at <= since));
+        self.messages.range(start..).cloned().collect()
+    }
+}
+
+pub fn decode_key(z85: &str) -> anyhow::Result<Vec<u8>> {
+    let key = zmq::z85_decode(z85).map_err(|e| anyhow!("key isn't Z85: {}", e))?;
+    if key.len() != KEY_SIZE {
+        return Err(anyhow!(
+            "key has {} bytes instead of {}",
+            key.len(),
+            KEY_SIZE
+        ));
+    }
+    Ok(key)
+}
+
+fn ensure_curve() -> anyhow::Result<()> {
+    match zmq::has("curve") {
+        Some(true) => Ok(()),
+        _ => Err(anyhow!("libzmq was built without CURVE")),
+    }
+}
+
+// Of a subscriber. Without a pair of its own a fresh one is
+// made, which publishers only letting in known keys turn away.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurveClient {
+    pub server_key: String,
+    // Public and secret
+    pub keys: Option<(String, String)>,
+}
+
+// For the bound side, publisher or command bridge alike
+pub fn secure_server(socket: &zmq::Socket, secret_key: &str) -> anyhow::Result<()> {
+    ensure_curve()?;
+    socket.set_curve_server(true)?;
+    socket.set_curve_secretkey(&decode_key(secret_key)?)?;
+    Ok(())
+}
+
+// Answers for all CURVE sockets of the context, so it must be called
+// once before binding them. The handler lives as long as the context.
+pub fn allow_clients(context: &zmq::Context, public_keys: &[String]) -> anyhow::Result<()> {
+    if public_keys.is_empty() {
+        return Err(anyhow!("no relay clients are allowed"));
+    }
+    let allowed = public_keys
+        .iter()
+        .map(|key| decode_key(key))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let handler = context.socket(zmq::REP)?;
+    handler.bind(ZAP_ENDPOINT)?;
+    thread::spawn(move || {
+        while let Ok(request) = handler.recv_multipart(0) {
+            if handler
+                .send_multipart(zap_reply(&request, &allowed), 0)
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+    Ok(())
+}
+
+// The request is the version, its id, the domain, the address, the
+// identity, the mechanism and, for CURVE, the public key of the client
+fn zap_reply(request: &[Vec<u8>], allowed: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let id = request.get(1).cloned().unwrap_or_default();
+    let known = request
+        .get(5)
+        .map_or(false, |mechanism| mechanism == b"CURVE")
+        && request.get(6).map_or(false, |key| allowed.contains(key));
+    let (status, text): (&[u8], &[u8]) = if known {
+        (b"200", b"OK")
+    } else {
+        (b"400", b"Unknown client key")
+    };
+    vec![
+        b"1.0".to_vec(),
+        id,
+        status.to_vec(),
+        text.to_vec(),
+        vec![],
+        vec![],
+    ]
+}
+
+// Must be called before connecting
+pub fn secure_client(socket: &zmq::Socket, client: &CurveClient) -> anyhow::Result<()> {
+    ensure_curve()?;
+    socket.set_curve_serverkey(&decode_key(&client.server_key)?)?;
+    match &client.keys {
+        Some((public_key, secret_key)) => {
+            socket.set_curve_publickey(&decode_key(public_key)?)?;
+            socket.set_curve_secretkey(&decode_key(secret_key)?)?;
+        }
+        None => {
+            let ours = zmq::CurveKeyPair::new()?;
+            socket.set_curve_publickey(&ours.public_key)?;
+            socket.set_curve_secretkey(&ours.secret_key)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages() -> Vec<Message> {
+        (0..5)
+            .map(|i| Message {
+                node: if i % 2 == 0 {
+                    Node::RedQueen(b'B')
+                } else {
+                    Node::Farduino(b'B')
+                },
+                data: [i; 32],
+                at: Some(1_000_000 + i as u64),
+                run: Some(RunId::from_bytes([9; RUN_SIZE])),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_binary_batches_roundtrip() {
+        let encoded = encode(RelayFormat::Binary, &messages(), 2);
+        assert_eq!(encoded.len(), 3);
+        assert_eq!(
+            encoded[0].len(),
+            HEADER_SIZE + RUN_SIZE + 2 * (NODE_SIZE + TIME_SIZE + FRAME_SIZE)
+        );
+        let decoded: Vec<Message> = encoded.iter().flat_map(|b| decode(b).unwrap()).collect();
+        assert_eq!(decoded, messages());
+    }
+
+    #[test]
+    fn test_json_still_understood() {
+        let encoded = encode(RelayFormat::Json, &messages(), DEFAULT_BATCH);
+        assert_eq!(encoded.len(), 5);
+        assert_eq!(decode(&encoded[1]).unwrap(), vec![messages()[1].clone()]);
+    }
+
+    #[test]
+    fn test_broken_envelopes_are_rejected() {
+        let mut encoded = encode(RelayFormat::Binary, &messages(), DEFAULT_BATCH).remove(0);
+        encoded.pop();
+        assert!(decode(&encoded).is_err());
+        assert!(decode(&[ENVELOPE_MAGIC, ENVELOPE_VERSION + 1, 0]).is_err());
+        assert!(decode(&[ENVELOPE_MAGIC, ENVELOPE_VERSION, 0]).is_err());
+        let mut empty = vec![ENVELOPE_MAGIC, ENVELOPE_VERSION, 0];
+        empty.extend([0; RUN_SIZE]);
+        assert!(decode(&empty).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_untimed_envelopes_still_understood() {
+        let mut envelope = vec![ENVELOPE_MAGIC, ENVELOPE_VERSION_UNTIMED, 1];
+        envelope.extend(b"RQB");
+        envelope.extend([3; FRAME_SIZE]);
+        let decoded = decode(&envelope).unwrap();
+        assert_eq!(decoded[0].node, Node::RedQueen(b'B'));
+        assert_eq!(decoded[0].at, None);
+        let mut envelope = vec![ENVELOPE_MAGIC, ENVELOPE_VERSION_TIMED, 1];
+        envelope.extend(b"RQB");
+        envelope.extend(7u64.to_be_bytes());
+        envelope.extend([3; FRAME_SIZE]);
+        let decoded = decode(&envelope).unwrap();
+        assert_eq!((decoded[0].at, decoded[0].run), (Some(7), None));
+    }
+
+    #[test]
+    fn test_backlog_replays_what_is_recent() {
+        let mut backlog = Backlog::new(Duration::from_secs(60));
+        for (i, at) in [0, 30, 50, 70, 100].into_iter().enumerate() {
+            backlog.push(Message {
+                node: Node::RedQueen(b'B'),
+                data: [i as u8; 32],
+                at: Some(at * 1_000_000),
+                run: None,
+            });
+        }
+        let times = |messages: Vec<Message>| -> Vec<u64> {
+            messages.iter().map(|m| m.at.unwrap() / 1_000_000).collect()
+        };
+        assert_eq!(times(backlog.since(0)), vec![50, 70, 100]);
+        assert_eq!(times(backlog.since(70_000_000)), vec![100]);
+        let reply = replay_reply(&backlog.since(0));
+        assert_eq!(reply.len(), 1);
+        assert_eq!(times(decode(&reply[0]).unwrap()), vec![50, 70, 100]);
+        assert!(decode(&replay_reply(&[])[0]).unwrap().is_empty());
+        let request = replay_request(70_000_000);
+        assert_eq!(parse_replay_request(&request).unwrap(), 70_000_000);
+        let mut stamper = Stamper::default();
+        assert!(stamper.stamp() < stamper.stamp());
+        // A publisher ahead of us relayed it just now
+        assert_eq!(age(u64::MAX), Duration::ZERO);
+        assert!(age(0) > DEFAULT_BACKLOG);
+    }
+
+    #[test]
+    fn test_keys_are_checked() {
+        let key = zmq::z85_encode(&[7; KEY_SIZE]).unwrap();
+        assert_eq!(decode_key(&key).unwrap(), vec![7; KEY_SIZE]);
+        assert!(decode_key(&key[..35]).is_err());
+        assert!(decode_key("not a key").is_err());
+    }
+
+    #[test]
+    fn test_only_allowed_clients_get_in() {
+        let allowed = vec![vec![1; KEY_SIZE]];
+        let request = |key: Vec<u8>| {
+            vec![
+                b"1.0".to_vec(),
+                b"7".to_vec(),
+                vec![],
+                b"10.0.0.2".to_vec(),
+                vec![],
+                b"CURVE".to_vec(),
+                key,
+            ]
+        };
+        let reply = zap_reply(&request(vec![1; KEY_SIZE]), &allowed);
+        assert_eq!(reply[1], b"7");
+        assert_eq!(reply[2], b"200");
+        assert_eq!(zap_reply(&request(vec![2; KEY_SIZE]), &allowed)[2], b"400");
+        assert_eq!(
+            zap_reply(&request(vec![1; KEY_SIZE])[..6], &allowed)[2],
+            b"400"
+        );
+        assert!(allow_clients(&zmq::Context::new(), &[]).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+// Bounds the telemetry kept in memory per node, so hours of waiting
+// on the pad don't exhaust the Pi. What falls out of the window can
+// be spilled to a CSV file named like the recording, one line per
+// packet with the node and the seconds into the session first,
+// after a comment naming the run of the session:
+//
+// # run 051b3c4d-5e6f-4081-92a3-b4c5d6e7f809 crisp-vortex
+// RQB,12.345,state,<ignition>,<phase>,<battery V>,<continuity bits>
+// RQB,12.365,imu,<acc xyz>,<gyr xyz>,<mag xyz>,<pressure>,<temperature>
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use log::error;
+
+use crate::recorder::FILE_TIME_FORMAT;
+use crate::rqprotocol::Node;
+use crate::run::RunId;
+
+use super::parser::rq2::TelemetryData;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetentionPolicy {
+    pub max_entries: usize,
+    pub max_age: Duration,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        // About six minutes of IMU data at 50Hz
+        Self {
+            max_entries: 20_000,
+            max_age: Duration::from_secs(600),
+        }
+    }
+}
+
+pub struct Stored {
+    // Into the session
+    pub received: Duration,
+    pub data: TelemetryData,
+}
+
+struct Spill {
+    path: PathBuf,
+    run: Option<RunId>,
+    writer: Option<BufWriter<File>>,
+}
+
+// E.g. 2024-05-01_13-37-swift-falcon-telemetry.csv
+pub fn spill_path(directory: &Path, run: &RunId) -> PathBuf {
+    let started: DateTime<Utc> = Utc::now();
+    directory.join(format!(
+        "{}-{}-telemetry.csv",
+        started.format(FILE_TIME_FORMAT),
+        run.slug()
+    ))
+}
+
+pub struct TelemetryStore {
+    policy: RetentionPolicy,
+    nodes: HashMap<Node, VecDeque<Stored>>,
+    spill: Option<Spill>,
+}
+
+impl TelemetryStore {
+    pub fn new(policy: RetentionPolicy, spill: Option<&Path>, run: Option<RunId>) -> Self {
+        Self {
+            policy,
+            nodes: HashMap::new(),
+            spill: spill.map(|path| Spill {
+                path: path.to_path_buf(),
+                run,
+                writer: None,
+            }),
+        }
+    }
+
+    // Tightening the limits takes effect with the next packet
+    pub fn set_policy(&mut self, policy: RetentionPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn push(&mut self, node: Node, received: Duration, data: TelemetryData) {
+        let stored = self.nodes.entry(node).or_default();
+        stored.push_back(Stored { received, data });
+        while let Some(oldest) = stored.front() {
+            let too_old = received.saturating_sub(oldest.received) > self.policy.max_age;
+            if stored.len() <= self.policy.max_entries && !too_old {
+                break;
+            }
+            let evicted = stored.pop_front().unwrap();
+            if let Some(spill) = &mut self.spill {
+                if let Err(err) = spill.write(&node, &evicted) {
+                    error!(
+                        "Can't spill telemetry to {:?}, dropping it: {}",
+                        spill.path, err
+                    );
+                    self.spill = None;
+                }
+            }
+        }
+    }
+
+    pub fn for_node(&self, node: &Node) -> Option<&VecDeque<Stored>> {
+        self.nodes.get(node)
+    }
+}
+
+impl Default for TelemetryStore {
+    fn default() -> Self {
+        Self::new(RetentionPolicy::default(), None, None)
+    }
+}
+
+impl Spill {
+    fn write(&mut self, node: &Node, stored: &Stored) -> std::io::Result<()> {
+        if self.writer.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            let empty = file.metadata()?.len() == 0;
+            let mut writer = BufWriter::new(file);
+            if let (true, Some(run)) = (empty, self.run) {
+                writeln!(writer, "# run {} {}", run, run.slug())?;
+            }
+            self.writer = Some(writer);
+        }
+        let writer = self.writer.as_mut().unwrap();
+        write!(writer, "{},{:.3},", node, stored.received.as_secs_f64())?;
+        match &stored.data {
+            TelemetryData::State(state) => writeln!(
+                writer,
+                "state,{:?},{:?},{:.2},{}",
+                state.ignition, state.phase, state.battery_voltage, state.continuity.0
+            ),
+            TelemetryData::IMU(imu) => writeln!(
+                writer,
+                "imu,{},{},{},{},{},{},{},{},{},{},{}",
+                imu.imu.acc_x,
+                imu.imu.acc_y,
+                imu.imu.acc_z,
+                imu.imu.gyr_x,
+                imu.imu.gyr_y,
+                imu.imu.gyr_z,
+                imu.mag.x,
+                imu.mag.y,
+                imu.mag.z,
+                imu.pressure,
+                imu.temperature
+            ),
+            TelemetryData::Config(ranges) => {
+                writeln!(writer, "config,{},{}", ranges.acc.name(), ranges.gyr.name())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::parser::rq2::{
+        ContinuityFlags, FlightPhase, IgnitionSMState, StatePacket,
+    };
+
+    fn state(battery_voltage: f32) -> TelemetryData {
+        TelemetryData::State(StatePacket {
+            ignition: IgnitionSMState::Reset,
+            phase: FlightPhase::Ground,
+            battery_voltage,
+            continuity: ContinuityFlags(3),
+        })
+    }
+
+    #[test]
+    fn test_evicted_packets_are_spilled() {
+        let path = std::env::temp_dir().join(format!("lnc-telemetry-{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let rqb = Node::RedQueen(b'B');
+        let mut store = TelemetryStore::new(
+            RetentionPolicy {
+                max_entries: 2,
+                max_age: Duration::from_secs(10),
+            },
+            Some(&path),
+            Some(RunId::from_bytes([0x05; 16])),
+        );
+        for second in 0..3 {
+            store.push(rqb, Duration::from_secs(second), state(8.0));
+        }
+        assert_eq!(store.for_node(&rqb).unwrap().len(), 2);
+        // Ages out the two before
+        store.push(rqb, Duration::from_secs(13), state(7.5));
+        let kept = store.for_node(&rqb).unwrap();
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].received, Duration::from_secs(13));
+        drop(store);
+        let spilled = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            spilled.lines().collect::<Vec<_>>(),
+            vec![
+                "# run 05050505-0505-0505-0505-050505050505 crisp-falcon",
+                "RQB,0.000,state,Reset,Ground,8.00,3",
+                "RQB,1.000,state,Reset,Ground,8.00,3",
+                "RQB,2.000,state,Reset,Ground,8.00,3",
+            ]
+        );
+    }
+}
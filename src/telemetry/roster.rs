@@ -0,0 +1,137 @@
+// Which node sends its telemetry on which NRF channel. The receiver
+// has a module per entry, in the order of the roster. The roster is
+// part of the config file, so a node swapped on the pad only needs
+// an edit there, the modules are set up again on reload.
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::rqprotocol::Node;
+
+// Of the NRF24L01, 2400 to 2525MHz
+pub const MAX_CHANNEL: u8 = 125;
+// Modules on the receiver
+pub const MAX_ENTRIES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChannelConfig {
+    pub node: Node,
+    pub channel: u8,
+}
+
+pub const DEFAULT_ROSTER: [ChannelConfig; 4] = [
+    ChannelConfig {
+        node: Node::RedQueen(b'B'),
+        channel: 0,
+    },
+    ChannelConfig {
+        node: Node::RedQueen(b'T'),
+        channel: 125,
+    },
+    ChannelConfig {
+        node: Node::Farduino(b'T'),
+        channel: 32,
+    },
+    ChannelConfig {
+        node: Node::Farduino(b'B'),
+        channel: 64,
+    },
+];
+
+pub fn validate(roster: &[ChannelConfig]) -> anyhow::Result<()> {
+    if roster.is_empty() || roster.len() > MAX_ENTRIES {
+        return Err(anyhow!(
+            "telemetry roster needs 1..={} entries",
+            MAX_ENTRIES
+        ));
+    }
+    for (index, entry) in roster.iter().enumerate() {
+        if entry.node == Node::LaunchControl {
+            return Err(anyhow!("LNC doesn't send telemetry"));
+        }
+        if entry.channel > MAX_CHANNEL {
+            return Err(anyhow!(
+                "{} has channel {}, the highest is {}",
+                entry.node,
+                entry.channel,
+                MAX_CHANNEL
+            ));
+        }
+        for other in roster[..index].iter() {
+            if other.node == entry.node {
+                return Err(anyhow!("{} is in the telemetry roster twice", entry.node));
+            }
+            if other.channel == entry.channel {
+                return Err(anyhow!(
+                    "{} and {} share channel {}",
+                    other.node,
+                    entry.node,
+                    entry.channel
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn nodes(roster: &[ChannelConfig]) -> Vec<Node> {
+    roster.iter().map(|entry| entry.node).collect()
+}
+
+// The roster page
+#[derive(Debug, Default)]
+pub struct RosterPage {
+    active: bool,
+}
+
+impl RosterPage {
+    pub fn start(&mut self) {
+        self.active = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> anyhow::Result<Vec<ChannelConfig>> {
+        let roster: Vec<ChannelConfig> = serde_json::from_str(text)?;
+        validate(&roster)?;
+        Ok(roster)
+    }
+
+    #[test]
+    fn test_roster_validation() {
+        assert!(validate(&DEFAULT_ROSTER).is_ok());
+        let roster =
+            parse(r#"[{"node": "FDC", "channel": 12}, {"node": "RQB", "channel": 0}]"#).unwrap();
+        assert_eq!(
+            nodes(&roster),
+            vec![Node::Farduino(b'C'), Node::RedQueen(b'B')]
+        );
+        assert!(parse("[]").is_err());
+        assert!(parse(r#"[{"node": "FDB", "channel": 126}]"#).is_err());
+        assert!(parse(r#"[{"node": "LNC", "channel": 1}]"#).is_err());
+        assert!(parse(r#"[{"node": "FD", "channel": 1}]"#).is_err());
+        assert_eq!(
+            parse(r#"[{"node": "FDB", "channel": 1}, {"node": "FDB", "channel": 2}]"#)
+                .unwrap_err()
+                .to_string(),
+            "FDB is in the telemetry roster twice"
+        );
+        assert_eq!(
+            parse(r#"[{"node": "FDB", "channel": 1}, {"node": "RQB", "channel": 1}]"#)
+                .unwrap_err()
+                .to_string(),
+            "FDB and RQB share channel 1"
+        );
+    }
+}
@@ -0,0 +1,360 @@
+// Synthetic RQ2 telemetry for development without NRF hardware.
+// Each node runs through a scripted launch: the ignition state
+// machine is walked through, then boost, coast and descent with
+// matching IMU and pressure values. Frames are encoded exactly
+// like the nodes do, so they go through the regular parser.
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::anyhow;
+
+use crate::{
+    clock::{Instant, SharedClock},
+    rqprotocol::Node,
+};
+
+use super::{NRFConnector, RawTelemetryPacket};
+
+const FRAME_SIZE: usize = 32;
+const STATE_PACKET: u8 = 0;
+const IMU_SET_A_PACKET: u8 = 1;
+// Scaling of the BMI088 at the default ranges
+const ACC_LSB_PER_G: f32 = 32768.0 / 24.0;
+const GYR_LSB_PER_DPS: f32 = 32768.0 / 2000.0;
+const GROUND_PRESSURE: f32 = 1013.25;
+const DROPOUT_DURATION: Duration = Duration::from_secs(2);
+// Packets per second, more than the modules manage
+const MAX_RATE: f32 = 1000.0;
+
+// (seconds into the script, ignition state, flight phase)
+const SCRIPT: [(f32, u8, u8); 9] = [
+    (0.0, 0, 0),
+    (5.0, 1, 0),
+    (10.0, 2, 0),
+    (15.0, 3, 1),
+    (20.0, 4, 2),
+    (23.0, 4, 3),
+    (33.0, 4, 4),
+    (63.0, 4, 5),
+    (70.0, 0, 0),
+];
+
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    // Packets per second and node
+    pub imu_rate: f32,
+    pub state_rate: f32,
+    // Probability of losing a packet, a tenth of
+    // which start a longer dropout of the node
+    pub dropout: f32,
+}
+
+struct SimNode {
+    node: Node,
+    seq: u8,
+    next_imu: Instant,
+    next_state: Instant,
+    silent_until: Option<Instant>,
+}
+
+pub struct SimulatedNRFConnector {
+    nodes: Vec<Node>,
+    sims: Vec<SimNode>,
+    config: SimConfig,
+    last_comms: HashMap<Node, Instant>,
+    start: Instant,
+    clock: SharedClock,
+    rng: u32,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            imu_rate: 50.0,
+            state_rate: 2.0,
+            dropout: 0.02,
+        }
+    }
+}
+
+impl SimConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (name, rate) in [("imu", self.imu_rate), ("state", self.state_rate)] {
+            if !(rate > 0.0 && rate <= MAX_RATE) {
+                return Err(anyhow!(
+                    "simulated {} rate {} out of 0..{}",
+                    name,
+                    rate,
+                    MAX_RATE
+                ));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.dropout) {
+            return Err(anyhow!("simulated dropout {} out of 0..1", self.dropout));
+        }
+        Ok(())
+    }
+}
+
+// Ignition state and flight phase at the given time
+fn script_state(t: f32) -> (u8, u8) {
+    let t = t % SCRIPT[SCRIPT.len() - 1].0;
+    SCRIPT
+        .iter()
+        .rev()
+        .find(|(start, _, _)| *start <= t)
+        .map(|(_, ignition, phase)| (*ignition, *phase))
+        .unwrap_or((0, 0))
+}
+
+// Acceleration in g along the rocket axis and altitude in m
+fn script_flight(t: f32) -> (f32, f32) {
+    let t = t % SCRIPT[SCRIPT.len() - 1].0;
+    match script_state(t).1 {
+        2 => {
+            let burn = t - 20.0;
+            (1.0 + 8.0 * (burn / 3.0).min(1.0), 0.5 * 80.0 * burn * burn)
+        }
+        3 => {
+            let coast = t - 23.0;
+            (0.0, 360.0 + 240.0 * coast - 4.9 * coast * coast)
+        }
+        4 => {
+            let descent = t - 33.0;
+            (0.8, (2850.0 - 95.0 * descent).max(0.0))
+        }
+        _ => (1.0, 0.0),
+    }
+}
+
+fn put_i16(frame: &mut Vec<u8>, value: f32) {
+    frame.extend_from_slice(&(value.round() as i16).to_le_bytes());
+}
+
+impl SimNode {
+    fn preamble(&mut self, frame: &mut Vec<u8>, packet_type: u8, timestamp: u32) {
+        frame.push(self.seq);
+        frame.push(packet_type);
+        frame.extend_from_slice(&timestamp.to_le_bytes());
+        self.seq = self.seq.wrapping_add(1);
+    }
+
+    fn state_frame(&mut self, t: f32, timestamp: u32) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(FRAME_SIZE);
+        self.preamble(&mut frame, STATE_PACKET, timestamp);
+        let (ignition, phase) = script_state(t);
+        frame.push(ignition);
+        frame.push(phase);
+        // Battery sags slowly over the script
+        let millivolts = 4150.0 - 2.0 * (t % SCRIPT[SCRIPT.len() - 1].0);
+        frame.extend_from_slice(&(millivolts as u16).to_le_bytes());
+        frame.push(0b11);
+        frame.resize(FRAME_SIZE, 0);
+        frame
+    }
+
+    fn imu_frame(&mut self, t: f32, timestamp: u32, noise: f32) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(FRAME_SIZE);
+        self.preamble(&mut frame, IMU_SET_A_PACKET, timestamp);
+        let (acc, altitude) = script_flight(t);
+        put_i16(&mut frame, noise * 0.05 * ACC_LSB_PER_G);
+        put_i16(&mut frame, -noise * 0.05 * ACC_LSB_PER_G);
+        put_i16(&mut frame, (acc + noise * 0.02) * ACC_LSB_PER_G);
+        put_i16(&mut frame, noise * GYR_LSB_PER_DPS);
+        put_i16(&mut frame, -noise * GYR_LSB_PER_DPS);
+        put_i16(&mut frame, 10.0 * (t * 0.5).sin() * GYR_LSB_PER_DPS);
+        for mag in [200.0, -40.0, 410.0] {
+            put_i16(&mut frame, mag + noise * 5.0);
+        }
+        let pressure = GROUND_PRESSURE * (1.0 - altitude / 44330.0).powf(5.255);
+        frame.extend_from_slice(&pressure.to_le_bytes());
+        let temperature: f32 = 21.5 - altitude * 0.0065;
+        frame.extend_from_slice(&temperature.to_le_bytes());
+        frame
+    }
+}
+
+impl SimulatedNRFConnector {
+    pub fn new(config: SimConfig, clock: SharedClock) -> Self {
+        let start = clock.now();
+        let nodes = vec![
+            Node::RedQueen(b'B'),
+            Node::Farduino(b'B'),
+            Node::RedQueen(b'T'),
+            Node::Farduino(b'T'),
+        ];
+        let sims = nodes
+            .iter()
+            .map(|node| SimNode {
+                node: *node,
+                seq: 0,
+                next_imu: start,
+                next_state: start,
+                silent_until: None,
+            })
+            .collect();
+        Self {
+            nodes,
+            sims,
+            config,
+            last_comms: HashMap::new(),
+            start,
+            clock,
+            rng: 0x2545f491,
+        }
+    }
+
+    // xorshift, good enough for noise and dropouts
+    fn random(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng as f32 / u32::MAX as f32
+    }
+
+    fn dropped(&mut self, index: usize, now: Instant) -> bool {
+        if let Some(silent_until) = self.sims[index].silent_until {
+            if now < silent_until {
+                return true;
+            }
+            self.sims[index].silent_until = None;
+        }
+        let r = self.random();
+        if r < self.config.dropout / 10.0 {
+            self.sims[index].silent_until = Some(now + DROPOUT_DURATION);
+        }
+        r < self.config.dropout
+    }
+
+    fn generate(&mut self, now: Instant) -> Vec<RawTelemetryPacket> {
+        let mut res = vec![];
+        let imu_interval = Duration::from_secs_f32(1.0 / self.config.imu_rate);
+        let state_interval = Duration::from_secs_f32(1.0 / self.config.state_rate);
+        for index in 0..self.sims.len() {
+            loop {
+                let sim = &self.sims[index];
+                let (due, is_state) = if sim.next_state <= sim.next_imu {
+                    (sim.next_state, true)
+                } else {
+                    (sim.next_imu, false)
+                };
+                if due > now {
+                    break;
+                }
+                let elapsed = due - self.start;
+                let t = elapsed.as_secs_f32();
+                let timestamp = elapsed.as_micros() as u32;
+                let noise = self.random() - 0.5;
+                let sim = &mut self.sims[index];
+                let frame = if is_state {
+                    sim.next_state += state_interval;
+                    sim.state_frame(t, timestamp)
+                } else {
+                    sim.next_imu += imu_interval;
+                    sim.imu_frame(t, timestamp, noise)
+                };
+                let node = sim.node;
+                if !self.dropped(index, due) {
+                    self.last_comms.insert(node, due);
+                    res.push(RawTelemetryPacket::Frame(node, frame));
+                }
+            }
+        }
+        res
+    }
+}
+
+impl NRFConnector for SimulatedNRFConnector {
+    fn registered_nodes(&self) -> &Vec<Node> {
+        &self.nodes
+    }
+
+    fn heard_from_since(&self, node: &Node) -> Duration {
+        self.clock.now() - *self.last_comms.get(node).unwrap_or(&self.start)
+    }
+
+    fn drive(&mut self) -> Vec<RawTelemetryPacket> {
+        self.generate(self.clock.now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clock::SimulatedClock,
+        telemetry::parser::rq2::{packet_parser, FlightPhase, ImuRanges, TelemetryData},
+    };
+
+    #[test]
+    fn test_generated_frames_parse() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut sim = SimulatedNRFConnector::new(
+            SimConfig {
+                dropout: 0.0,
+                ..Default::default()
+            },
+            clock.shared(),
+        );
+        clock.advance(Duration::from_millis(21010));
+        let packets = sim.drive();
+        // Both packet types for all nodes, one more
+        // each for the initial packet at t=0
+        let per_node = 21 * 50 + 21 * 2 + 2;
+        assert_eq!(packets.len(), 4 * per_node);
+        let mut boost = false;
+        for packet in packets {
+            match packet {
+                RawTelemetryPacket::Frame(node, data) => {
+                    assert_eq!(data.len(), FRAME_SIZE);
+                    let (_, packet) = packet_parser(node, &ImuRanges::default(), &data).unwrap();
+                    if let TelemetryData::State(state) = packet.data {
+                        boost |= state.phase == FlightPhase::Boost;
+                    }
+                }
+                RawTelemetryPacket::NoModule(_) => panic!("simulated nodes are present"),
+                RawTelemetryPacket::Malformed(..) => panic!("simulated frames are whole"),
+                RawTelemetryPacket::Backfilled(..) => panic!("simulated frames are live"),
+            }
+        }
+        assert!(boost);
+    }
+
+    #[test]
+    fn test_dropouts() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut sim = SimulatedNRFConnector::new(
+            SimConfig {
+                dropout: 1.0,
+                ..Default::default()
+            },
+            clock.shared(),
+        );
+        clock.advance(Duration::from_secs(1));
+        assert!(sim.drive().is_empty());
+        assert_eq!(
+            sim.heard_from_since(&Node::RedQueen(b'B')),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn test_rates_are_validated() {
+        assert!(SimConfig::default().validate().is_ok());
+        for config in [
+            SimConfig {
+                imu_rate: 0.0,
+                ..Default::default()
+            },
+            SimConfig {
+                state_rate: f32::NAN,
+                ..Default::default()
+            },
+            SimConfig {
+                dropout: 1.5,
+                ..Default::default()
+            },
+        ] {
+            assert!(config.validate().is_err());
+        }
+    }
+}
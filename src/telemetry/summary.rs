@@ -0,0 +1,216 @@
+// Parsed telemetry aggregated per node, for consumers of the relay
+// that don't bring the parser stack, like dashboards or a spreadsheet
+// fed by a script. Besides the raw frames the publisher then sends a
+// JSON summary per interval as a two part message, the topic and the
+// summary, so they subscribe to the topic alone. Subscribers of the
+// raw frames skip it.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::clock::Instant;
+use crate::rqprotocol::Node;
+use crate::run::RunId;
+
+use super::parser::rq2::{packet_parser, ImuRanges, TelemetryData, TelemetryPacket};
+
+pub const SUMMARY_TOPIC: &[u8] = b"summary";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeSummary {
+    pub node: Node,
+    pub frames: usize,
+    // Of the last state packet
+    pub ignition: Option<String>,
+    pub phase: Option<String>,
+    pub battery_voltage: Option<f32>,
+    pub continuity: Option<u8>,
+    // Over the IMU packets
+    pub acc_mean: Option<f32>,
+    pub acc_max: Option<f32>,
+    pub pressure_mean: Option<f32>,
+    pub temperature_mean: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Summary {
+    // Of the publisher, like the frames
+    pub run: Option<RunId>,
+    pub interval_ms: u64,
+    // Only the nodes heard from in the interval
+    pub nodes: Vec<NodeSummary>,
+}
+
+#[derive(Debug, Default)]
+struct Imu {
+    count: usize,
+    acc_sum: f32,
+    acc_max: f32,
+    pressure_sum: f32,
+    temperature_sum: f32,
+}
+
+#[derive(Debug)]
+pub struct Aggregator {
+    interval: Duration,
+    // Nodes without use the defaults, as the frontend does
+    imu_ranges: HashMap<Node, ImuRanges>,
+    started: Option<Instant>,
+    // In the order first heard from
+    nodes: Vec<(NodeSummary, Imu)>,
+}
+
+impl Imu {
+    fn push(&mut self, acc: f32, pressure: f32, temperature: f32) {
+        self.count += 1;
+        self.acc_sum += acc;
+        self.acc_max = self.acc_max.max(acc);
+        self.pressure_sum += pressure;
+        self.temperature_sum += temperature;
+    }
+
+    fn mean(&self, sum: f32) -> Option<f32> {
+        (self.count > 0).then(|| sum / self.count as f32)
+    }
+}
+
+impl NodeSummary {
+    fn new(node: Node) -> Self {
+        Self {
+            node,
+            frames: 0,
+            ignition: None,
+            phase: None,
+            battery_voltage: None,
+            continuity: None,
+            acc_mean: None,
+            acc_max: None,
+            pressure_mean: None,
+            temperature_mean: None,
+        }
+    }
+}
+
+impl Summary {
+    pub fn to_json(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap()
+    }
+}
+
+impl Aggregator {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            imu_ranges: HashMap::new(),
+            started: None,
+            nodes: vec![],
+        }
+    }
+
+    pub fn set_imu_ranges(&mut self, imu_ranges: HashMap<Node, ImuRanges>) {
+        self.imu_ranges = imu_ranges;
+    }
+
+    // Frames that don't parse are counted, but not summarized
+    pub fn push(&mut self, node: Node, frame: &[u8]) {
+        let ranges = self.imu_ranges.get(&node).copied().unwrap_or_default();
+        let index = match self
+            .nodes
+            .iter()
+            .position(|(summary, _)| summary.node == node)
+        {
+            Some(index) => index,
+            None => {
+                self.nodes.push((NodeSummary::new(node), Imu::default()));
+                self.nodes.len() - 1
+            }
+        };
+        let (summary, imu) = &mut self.nodes[index];
+        summary.frames += 1;
+        if let Ok((_, TelemetryPacket { data, .. })) = packet_parser(node, &ranges, frame) {
+            match data {
+                TelemetryData::State(state) => {
+                    summary.ignition = Some(format!("{:?}", state.ignition));
+                    summary.phase = Some(format!("{:?}", state.phase));
+                    summary.battery_voltage = Some(state.battery_voltage);
+                    summary.continuity = Some(state.continuity.0);
+                }
+                TelemetryData::IMU(packet) => imu.push(
+                    packet.imu.acc_magnitude(),
+                    packet.pressure,
+                    packet.temperature,
+                ),
+                TelemetryData::Config(_) => {}
+            }
+        }
+    }
+
+    // Once the interval is over, also without any frames
+    pub fn poll(&mut self, now: Instant, run: Option<RunId>) -> Option<Summary> {
+        let started = *self.started.get_or_insert(now);
+        if now.duration_since(started) < self.interval {
+            return None;
+        }
+        self.started = Some(now);
+        let nodes = self
+            .nodes
+            .drain(..)
+            .map(|(summary, imu)| NodeSummary {
+                acc_mean: imu.mean(imu.acc_sum),
+                acc_max: (imu.count > 0).then_some(imu.acc_max),
+                pressure_mean: imu.mean(imu.pressure_sum),
+                temperature_mean: imu.mean(imu.temperature_sum),
+                ..summary
+            })
+            .collect();
+        Some(Summary {
+            run,
+            interval_ms: self.interval.as_millis() as u64,
+            nodes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATE: &[u8; 32] = b"B\x00~\xdcvV\x03\x02\x2c\x10\x02\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+
+    // 10 Hz
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn test_frames_are_summarized_per_interval() {
+        let rqb = Node::RedQueen(b'B');
+        let mut aggregator = Aggregator::new(INTERVAL);
+        let start = Instant::now();
+        assert_eq!(aggregator.poll(start, None), None);
+        aggregator.push(rqb, STATE);
+        aggregator.push(rqb, &[0xff; 3]);
+        assert_eq!(
+            aggregator.poll(start + Duration::from_millis(50), None),
+            None
+        );
+        let summary = aggregator.poll(start + INTERVAL, None).unwrap();
+        assert_eq!(summary.interval_ms, 100);
+        assert_eq!(
+            summary.nodes,
+            vec![NodeSummary {
+                node: rqb,
+                frames: 2,
+                ignition: Some("SecretAB".into()),
+                phase: Some("Boost".into()),
+                battery_voltage: Some(4.140),
+                continuity: Some(2),
+                ..NodeSummary::new(rqb)
+            }]
+        );
+        let json = String::from_utf8(summary.to_json()).unwrap();
+        assert!(json.contains(r#""ignition":"SecretAB""#), "{}", json);
+        // Nothing heard of since
+        let summary = aggregator.poll(start + INTERVAL * 2, None).unwrap();
+        assert!(summary.nodes.is_empty());
+    }
+}
@@ -1,21 +1,57 @@
-use std::{
-    collections::HashMap,
-    time::{Duration, Instant},
-};
+// Telemetry relayed by the launch control. The publisher may not be
+// resolvable yet when we start, so connecting is retried in the
+// background until it works. Once connected, and whenever the relay
+// comes back after a silence, the backlog of the publisher is
+// requested, so a late subscriber still gets the recent telemetry.
+// Live frames wait for the replay, and replayed frames seen before
+// are dropped by their time.
+use std::{collections::HashMap, thread, time::Duration};
 
+use crossbeam_channel::{unbounded, Receiver};
 use log::error;
 
-use crate::rqprotocol::Node;
+use crate::{
+    clock::{Instant, SharedClock},
+    rqprotocol::Node,
+    run::RunId,
+};
+
+use super::relay::{self, CurveClient};
+use super::roster::{self, ChannelConfig, DEFAULT_ROSTER};
+use super::summary::SUMMARY_TOPIC;
+use super::{Message, NRFConnector, RawTelemetryPacket, SubscriptionHealth};
 
-use super::{Message, NRFConnector, RawTelemetryPacket};
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(2);
+const REPLAY_TIMEOUT: Duration = Duration::from_secs(2);
+// Of the whole relay, after this we might have missed frames
+const SILENCE: Duration = Duration::from_secs(5);
+
+struct Replay {
+    socket: ::zmq::Socket,
+    started: Instant,
+    // Live frames arriving meanwhile
+    held: Vec<Message>,
+}
 
 pub struct ZMQSubscriberNRFConnector {
     nodes: Vec<Node>,
-    #[allow(dead_code)]
     context: ::zmq::Context,
-    socket: ::zmq::Socket,
+    replay_uri: String,
+    client: Option<CurveClient>,
+    socket: Option<::zmq::Socket>,
+    // From the connecting thread, an error per failed attempt
+    connecting: Receiver<Result<::zmq::Socket, String>>,
+    attempts: usize,
+    replay: Option<Replay>,
+    backfilled: usize,
+    // Time of the newest frame, as stamped by the publisher
+    newest: Option<u64>,
+    last_frame: Option<Instant>,
+    // Of the publisher, with the last frame stamped with one
+    run: Option<RunId>,
     last_comms: HashMap<Node, Instant>,
     start: Instant,
+    clock: SharedClock,
 }
 
 impl NRFConnector for ZMQSubscriberNRFConnector {
@@ -24,7 +60,7 @@ impl NRFConnector for ZMQSubscriberNRFConnector {
     }
 
     fn heard_from_since(&self, node: &Node) -> Duration {
-        Instant::now()
+        self.clock.now()
             - if self.last_comms.contains_key(node) {
                 self.last_comms[node]
             } else {
@@ -33,21 +69,25 @@ impl NRFConnector for ZMQSubscriberNRFConnector {
     }
 
     fn drive(&mut self) -> Vec<super::RawTelemetryPacket> {
-        let mut res = vec![];
+        self.poll_connection();
+        let mut live = vec![];
+        let Some(socket) = &self.socket else {
+            return vec![];
+        };
         loop {
-            match self.socket.recv_bytes(::zmq::DONTWAIT) {
-                Ok(bytes) => {
-                    let s = unsafe { std::str::from_utf8_unchecked(&bytes) };
-                    match serde_json::from_str::<Message>(&s) {
-                        Ok(message) => {
-                            self.last_comms.insert(message.node, Instant::now());
-                            res.push(RawTelemetryPacket::Frame(message.node, message.data.into()));
-                        }
-                        Err(err) => {
-                            error!("ZMQ deserialization error: {:?}", err);
-                        }
+            match socket.recv_bytes(::zmq::DONTWAIT) {
+                // Not for us, with the summary still to come
+                Ok(bytes) if bytes == SUMMARY_TOPIC => {
+                    while socket.get_rcvmore().unwrap_or(false) {
+                        let _ = socket.recv_bytes(0);
                     }
                 }
+                Ok(bytes) => match relay::decode(&bytes) {
+                    Ok(messages) => live.extend(messages),
+                    Err(err) => {
+                        error!("ZMQ deserialization error: {:?}", err);
+                    }
+                },
                 Err(err) => match err {
                     zmq::Error::EAGAIN => {
                         break;
@@ -58,27 +98,233 @@ impl NRFConnector for ZMQSubscriberNRFConnector {
                 },
             }
         }
+        let now = self.clock.now();
+        let resumed = self
+            .last_frame
+            .map_or(false, |last| now.duration_since(last) >= SILENCE);
+        if !live.is_empty() {
+            if resumed && self.replay.is_none() {
+                self.request_replay();
+            }
+            self.last_frame = Some(now);
+        }
+        let mut res = vec![];
+        match &mut self.replay {
+            Some(replay) => {
+                replay.held.append(&mut live);
+                let replayed = match replay.socket.recv_multipart(::zmq::DONTWAIT) {
+                    Ok(parts) => parts
+                        .iter()
+                        .filter_map(|part| relay::decode(part).ok())
+                        .flatten()
+                        .collect(),
+                    Err(zmq::Error::EAGAIN)
+                        if now.duration_since(replay.started) < REPLAY_TIMEOUT =>
+                    {
+                        return res;
+                    }
+                    Err(err) => {
+                        error!("Telemetry replay failed: {:?}", err);
+                        vec![]
+                    }
+                };
+                let held = std::mem::take(&mut replay.held);
+                self.replay = None;
+                for message in replayed {
+                    if self.accept(&message, true) {
+                        self.backfilled += 1;
+                        res.push(match message.at {
+                            Some(at) => RawTelemetryPacket::Backfilled(
+                                message.node,
+                                message.data.into(),
+                                relay::age(at),
+                            ),
+                            None => RawTelemetryPacket::Frame(message.node, message.data.into()),
+                        });
+                    }
+                }
+                self.relay(held, &mut res);
+            }
+            None => self.relay(live, &mut res),
+        }
         res
     }
+
+    // The receiver has its own roster, only the nodes are taken
+    fn set_roster(&mut self, roster: &[ChannelConfig]) {
+        self.nodes = roster::nodes(roster);
+    }
+
+    fn subscription(&self) -> Option<SubscriptionHealth> {
+        Some(SubscriptionHealth {
+            connected: self.socket.is_some(),
+            attempts: self.attempts,
+            silence: self
+                .last_frame
+                .map(|last| self.clock.now().duration_since(last)),
+            replaying: self.replay.is_some(),
+            backfilled: self.backfilled,
+            relay_run: self.run,
+        })
+    }
 }
 
 impl ZMQSubscriberNRFConnector {
-    pub fn new(uri: &str) -> anyhow::Result<Self> {
+    pub fn new(
+        uri: &str,
+        replay_uri: &str,
+        clock: SharedClock,
+        client: Option<CurveClient>,
+    ) -> anyhow::Result<Self> {
         let context = ::zmq::Context::new();
-        let socket = context.socket(::zmq::SUB)?;
-        socket.set_subscribe(b"")?;
-        socket.connect(uri)?;
+        let (sender, connecting) = unbounded();
+        {
+            let context = context.clone();
+            let uri = uri.to_string();
+            let client = client.clone();
+            thread::spawn(move || loop {
+                let attempt =
+                    subscribe(&context, &uri, client.as_ref()).map_err(|err| err.to_string());
+                let connected = attempt.is_ok();
+                if sender.send(attempt).is_err() || connected {
+                    return;
+                }
+                thread::sleep(RECONNECT_INTERVAL);
+            });
+        }
         Ok(Self {
             context,
-            socket,
-            nodes: vec![
-                Node::RedQueen(b'B'),
-                Node::Farduino(b'B'),
-                Node::RedQueen(b'T'),
-                Node::Farduino(b'T'),
-            ],
+            replay_uri: replay_uri.into(),
+            client,
+            socket: None,
+            connecting,
+            attempts: 0,
+            replay: None,
+            backfilled: 0,
+            newest: None,
+            last_frame: None,
+            run: None,
+            nodes: roster::nodes(&DEFAULT_ROSTER),
             last_comms: HashMap::new(),
-            start: Instant::now(),
+            start: clock.now(),
+            clock,
         })
     }
+
+    fn poll_connection(&mut self) {
+        while let Ok(attempt) = self.connecting.try_recv() {
+            match attempt {
+                Ok(socket) => {
+                    self.socket = Some(socket);
+                    self.request_replay();
+                }
+                Err(err) => {
+                    self.attempts += 1;
+                    error!("Can't subscribe to the telemetry relay: {}", err);
+                }
+            }
+        }
+    }
+
+    // Without a publisher serving replays this just times out
+    fn request_replay(&mut self) {
+        let request = || -> anyhow::Result<::zmq::Socket> {
+            let socket = self.context.socket(::zmq::REQ)?;
+            socket.set_linger(0)?;
+            if let Some(client) = &self.client {
+                relay::secure_client(&socket, client)?;
+            }
+            socket.connect(&self.replay_uri)?;
+            socket.send(&relay::replay_request(self.newest.unwrap_or(0))[..], 0)?;
+            Ok(socket)
+        };
+        match request() {
+            Ok(socket) => {
+                self.replay = Some(Replay {
+                    socket,
+                    started: self.clock.now(),
+                    held: vec![],
+                })
+            }
+            Err(err) => error!("Can't request the telemetry replay: {:?}", err),
+        }
+    }
+
+    // Only replayed frames can be ones seen before, live ones are always
+    // new. Frames of publishers predating the time stamps are as well.
+    fn accept(&mut self, message: &Message, replayed: bool) -> bool {
+        // A restarted relay stamps with its own clock
+        if message.run.is_some() && message.run != self.run {
+            self.run = message.run;
+            self.newest = None;
+        }
+        match (message.at, self.newest) {
+            (Some(at), Some(newest)) if replayed && at <= newest => false,
+            // Live ones also follow the clock of the relay back
+            (Some(at), _) => {
+                self.newest = Some(at);
+                true
+            }
+            (None, _) => true,
+        }
+    }
+
+    fn relay(&mut self, messages: Vec<Message>, res: &mut Vec<RawTelemetryPacket>) {
+        for message in messages {
+            if self.accept(&message, false) {
+                self.last_comms.insert(message.node, self.clock.now());
+                res.push(RawTelemetryPacket::Frame(message.node, message.data.into()));
+            }
+        }
+    }
+}
+
+fn subscribe(
+    context: &::zmq::Context,
+    uri: &str,
+    client: Option<&CurveClient>,
+) -> anyhow::Result<::zmq::Socket> {
+    let socket = context.socket(::zmq::SUB)?;
+    if let Some(client) = client {
+        relay::secure_client(&socket, client)?;
+    }
+    socket.set_subscribe(b"")?;
+    socket.connect(uri)?;
+    Ok(socket)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(at: u64, run: RunId) -> Message {
+        Message {
+            node: Node::RedQueen(b'A'),
+            data: [0; 32],
+            at: Some(at),
+            run: Some(run),
+        }
+    }
+
+    #[test]
+    fn test_only_replayed_frames_are_dropped() {
+        let mut connector = ZMQSubscriberNRFConnector::new(
+            "tcp://127.0.0.1:1",
+            "tcp://127.0.0.1:1",
+            crate::clock::system(),
+            None,
+        )
+        .unwrap();
+        let run = RunId::generate();
+        assert!(connector.accept(&message(100, run), true));
+        assert!(!connector.accept(&message(100, run), true));
+        // The clock of the relay went back
+        assert!(connector.accept(&message(50, run), false));
+        assert!(connector.accept(&message(60, run), true));
+        assert!(!connector.accept(&message(60, run), true));
+        // A restarted relay
+        let restarted = RunId::generate();
+        assert!(connector.accept(&message(10, restarted), true));
+        assert_eq!(connector.run, Some(restarted));
+    }
 }
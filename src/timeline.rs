@@ -0,0 +1,241 @@
+// Fuses ground observables and onboard telemetry onto one time
+// axis. Both come with their own clock, so each source is mapped
+// onto the host clock using the smallest observed difference between
+// arrival and source time, i.e. the least delayed sample. Samples keep
+// their source time, so a better estimate moves all of them. What
+// remains (e.g. different radio latencies) is corrected by manual
+// alignment. The onboard timestamps are µs in a u32, which wraps after
+// about 71 minutes, so they are unwrapped per node first.
+use std::collections::{HashMap, VecDeque};
+
+use crate::rqprotocol::Node;
+
+const TIMELINE_CAPACITY: usize = 5000;
+const TIMESTAMP_RANGE: u64 = 1 << 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Series {
+    Thrust,
+    Pressure,
+    Acceleration,
+    OnboardPressure,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Source {
+    Ground,
+    Onboard(Node),
+}
+
+#[derive(Default)]
+struct ClockSync {
+    offset: Option<f64>,
+    // Of the onboard counter, the last one and how often it wrapped
+    last_timestamp: Option<u32>,
+    wraps: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    source: Source,
+    time: f64,
+    value: f64,
+}
+
+pub struct Timeline {
+    clocks: HashMap<Source, ClockSync>,
+    series: HashMap<Series, VecDeque<Sample>>,
+    capacity: usize,
+    // Seconds the onboard data is shifted relative to the ground
+    pub alignment: f64,
+}
+
+impl Series {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Series::Thrust => "Thrust [kN]",
+            Series::Pressure => "Pressure [bar]",
+            Series::Acceleration => "Acceleration [g]",
+            Series::OnboardPressure => "Onboard pressure [hPa]",
+        }
+    }
+
+    pub fn is_onboard(&self) -> bool {
+        match self {
+            Series::Acceleration | Series::OnboardPressure => true,
+            _ => false,
+        }
+    }
+}
+
+impl ClockSync {
+    // A step back by more than half the range is a wrap,
+    // anything less a sample arriving late
+    fn unwrap(&mut self, timestamp: u32) -> f64 {
+        let last = self.last_timestamp.unwrap_or(timestamp);
+        let wraps = if timestamp > last && timestamp - last > u32::MAX / 2 {
+            // Late from before the wrap
+            self.wraps.saturating_sub(1)
+        } else {
+            if timestamp < last && last - timestamp > u32::MAX / 2 {
+                self.wraps += 1;
+            }
+            self.last_timestamp = Some(timestamp);
+            self.wraps
+        };
+        (wraps * TIMESTAMP_RANGE + timestamp as u64) as f64 / 1_000_000.0
+    }
+
+    fn observe(&mut self, source_time: f64, host_time: f64) {
+        let offset = host_time - source_time;
+        self.offset = Some(match self.offset {
+            Some(known) => known.min(offset),
+            None => offset,
+        });
+    }
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new(TIMELINE_CAPACITY)
+    }
+}
+
+impl Timeline {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            clocks: HashMap::new(),
+            series: HashMap::new(),
+            capacity,
+            alignment: 0.0,
+        }
+    }
+
+    // Ground observables, with the RQ uptime as source time
+    pub fn push_ground(&mut self, uptime: f64, host_time: f64, thrust: f64, pressure: f64) {
+        let source = Source::Ground;
+        self.synchronize(source, uptime, host_time);
+        self.push(Series::Thrust, source, uptime, thrust);
+        self.push(Series::Pressure, source, uptime, pressure);
+    }
+
+    // Onboard telemetry, with the node timestamp in µs as source time
+    pub fn push_onboard(
+        &mut self,
+        node: Node,
+        timestamp: u32,
+        host_time: f64,
+        acceleration: f64,
+        pressure: f64,
+    ) {
+        let source = Source::Onboard(node);
+        let timestamp = self.clocks.entry(source).or_default().unwrap(timestamp);
+        self.synchronize(source, timestamp, host_time);
+        self.push(Series::Acceleration, source, timestamp, acceleration);
+        self.push(Series::OnboardPressure, source, timestamp, pressure);
+    }
+
+    pub fn align(&mut self, delta: f64) {
+        self.alignment += delta;
+    }
+
+    pub fn clear(&mut self) {
+        self.clocks.clear();
+        self.series.clear();
+    }
+
+    pub fn points(&self, series: Series) -> Vec<[f64; 2]> {
+        let shift = if series.is_onboard() {
+            self.alignment
+        } else {
+            0.0
+        };
+        let offset = |source: &Source| {
+            self.clocks
+                .get(source)
+                .and_then(|clock| clock.offset)
+                .unwrap_or_default()
+        };
+        self.series
+            .get(&series)
+            .map(|samples| {
+                samples
+                    .iter()
+                    .map(|s| [s.time + offset(&s.source) + shift, s.value])
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn synchronize(&mut self, source: Source, source_time: f64, host_time: f64) {
+        self.clocks
+            .entry(source)
+            .or_default()
+            .observe(source_time, host_time)
+    }
+
+    fn push(&mut self, series: Series, source: Source, time: f64, value: f64) {
+        let samples = self
+            .series
+            .entry(series)
+            .or_insert_with(|| VecDeque::with_capacity(self.capacity));
+        if samples.len() == self.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(Sample {
+            source,
+            time,
+            value,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_sync_uses_least_delayed_sample() {
+        let mut timeline = Timeline::new(10);
+        // Sent at source time 1.0 and 2.0, the first one delayed
+        timeline.push_ground(1.0, 10.5, 1.0, 1.0);
+        timeline.push_ground(2.0, 11.1, 2.0, 1.0);
+        timeline.push_onboard(Node::RedQueen(b'B'), 100_000_000, 10.5, 1.0, 1000.0);
+        let thrust = timeline.points(Series::Thrust);
+        assert!((thrust[0][0] - 10.1).abs() < 1e-9);
+        assert!((thrust[1][0] - 11.1).abs() < 1e-9);
+        assert_eq!(timeline.points(Series::Acceleration), vec![[10.5, 1.0]]);
+    }
+
+    #[test]
+    fn test_alignment_shifts_onboard_only() {
+        let mut timeline = Timeline::new(1);
+        timeline.push_ground(1.0, 1.0, 1.0, 1.0);
+        timeline.push_ground(2.0, 2.0, 2.0, 1.0);
+        timeline.push_onboard(Node::RedQueen(b'B'), 0, 1.0, 1.0, 1000.0);
+        timeline.align(-0.25);
+        assert_eq!(timeline.points(Series::Thrust), vec![[2.0, 2.0]]);
+        assert_eq!(
+            timeline.points(Series::OnboardPressure),
+            vec![[0.75, 1000.0]]
+        );
+    }
+
+    #[test]
+    fn test_onboard_timestamps_are_unwrapped() {
+        let mut timeline = Timeline::new(10);
+        let node = Node::RedQueen(b'B');
+        // Shortly before the wrap, shortly after and one arriving late
+        timeline.push_onboard(node, u32::MAX - 999_999, 100.0, 1.0, 1000.0);
+        timeline.push_onboard(node, 1_000_000, 102.0, 1.0, 1000.0);
+        timeline.push_onboard(node, u32::MAX, 103.0, 1.0, 1000.0);
+        let times: Vec<f64> = timeline
+            .points(Series::Acceleration)
+            .iter()
+            .map(|point| point[0])
+            .collect();
+        assert!((times[0] - 100.0).abs() < 1e-6);
+        assert!((times[1] - 102.0).abs() < 1e-6);
+        assert!((times[2] - 101.0).abs() < 1e-6);
+    }
+}
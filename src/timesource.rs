@@ -0,0 +1,77 @@
+// Which time is used for what. Timeouts, plots and markers run on the
+// monotonic time of the clock, what's written for people (the journal,
+// file names) is stamped with the wall time. The wall time of the Pi
+// steps when it gets NTP at the field or the RTC is read late, so the
+// two are compared on each frame: a step is journaled with its size,
+// so stamps on either side of it can be put right afterwards.
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::clock::Instant;
+
+// Less is jitter of reading both times
+const STEP_THRESHOLD: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockStep {
+    // Negative when the wall clock went back
+    pub by: chrono::Duration,
+    pub wall: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+pub struct StepDetector {
+    last: Option<(Instant, DateTime<Utc>)>,
+}
+
+impl ClockStep {
+    pub fn text(&self) -> String {
+        format!(
+            "Wall clock stepped by {:+.1}s, journal times before are off by that",
+            self.by.num_milliseconds() as f64 / 1000.0
+        )
+    }
+}
+
+impl StepDetector {
+    pub fn check(&mut self, now: Instant, wall: DateTime<Utc>) -> Option<ClockStep> {
+        let (last_now, last_wall) = self.last.replace((now, wall))?;
+        let monotonic = chrono::Duration::from_std(now.duration_since(last_now)).ok()?;
+        let by = (wall - last_wall) - monotonic;
+        let threshold = chrono::Duration::from_std(STEP_THRESHOLD).unwrap();
+        if by.abs() < threshold {
+            return None;
+        }
+        Some(ClockStep { by, wall })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, SimulatedClock};
+
+    #[test]
+    fn test_wall_clock_steps_are_detected() {
+        let clock = SimulatedClock::new(Instant::now());
+        let mut detector = StepDetector::default();
+        let mut check = || detector.check(clock.now(), clock.wall());
+        assert_eq!(check(), None);
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(check(), None);
+        clock.step_wall(chrono::Duration::milliseconds(300));
+        assert_eq!(check(), None);
+        clock.advance(Duration::from_millis(16));
+        clock.step_wall(chrono::Duration::seconds(-3600));
+        let step = check().unwrap();
+        assert_eq!(step.by, chrono::Duration::seconds(-3600));
+        assert_eq!(step.wall, clock.wall());
+        assert_eq!(
+            step.text(),
+            "Wall clock stepped by -3600.0s, journal times before are off by that"
+        );
+        clock.advance(Duration::from_millis(16));
+        assert_eq!(check(), None);
+    }
+}
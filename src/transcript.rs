@@ -0,0 +1,321 @@
+// Offline decoding of Recorder files. The recorder stores the raw
+// bytes as they come off the radio, without host time, so the only
+// clock available is the RQ uptime carried by OBG1 responses. Each
+// entry is stamped with the most recent uptime seen up to it.
+use std::{collections::HashMap, fmt::Display, io::Write, path::Path};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+
+use crate::{
+    observables::{groups::RawObservablesGroup, Profile},
+    recorder::FILE_TIME_FORMAT,
+    rqparser::{
+        ack_parser, command_parser, obg_parser, verify_nmea_format, NMEAFormatError, SentenceParser,
+    },
+    rqprotocol::{Acknowledgement, Node},
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Decoded {
+    Command { command: String },
+    Ack,
+    Nak,
+    Observables { group: String },
+    // Valid NMEA, but none of our parsers understood it
+    Unknown,
+    ChecksumFailure,
+    Malformed { reason: String },
+    // The sentence parser discarded a too long sentence
+    Overflow,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Entry {
+    // Byte offset of the sentence start in the recording
+    pub offset: usize,
+    // RQ uptime in seconds
+    pub uptime: Option<f64>,
+    pub sentence: String,
+    pub source: Option<Node>,
+    pub recipient: Option<Node>,
+    pub id: Option<usize>,
+    #[serde(flatten)]
+    pub decoded: Decoded,
+    // Index of the matching command or response entry
+    pub paired_with: Option<usize>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    pub sentences: usize,
+    pub checksum_failures: usize,
+    pub malformed: usize,
+    pub unknown: usize,
+    pub paired: usize,
+    pub unpaired_commands: usize,
+    pub unpaired_responses: usize,
+}
+
+#[derive(Default)]
+pub struct Transcript {
+    pub entries: Vec<Entry>,
+}
+
+// The recorder names its files after the time recording started
+pub fn recording_start(path: &Path) -> Option<DateTime<Utc>> {
+    let name = path.file_name()?.to_str()?;
+    let stamp = name.get(0..16)?;
+    NaiveDateTime::parse_from_str(stamp, FILE_TIME_FORMAT)
+        .ok()
+        .map(|naive| naive.and_utc())
+}
+
+fn decode_payload(
+    payload: &[u8],
+    profile: Profile,
+) -> (Option<(Node, Node, usize)>, Decoded, Option<f64>) {
+    if let Ok((_, transaction)) = command_parser(payload) {
+        return (
+            Some((transaction.source, transaction.recipient, transaction.id)),
+            Decoded::Command {
+                command: format!("{:?}", transaction.command),
+            },
+            None,
+        );
+    }
+    if let Ok((_, (source, id, recipient, raw))) = obg_parser(profile, payload) {
+        let uptime = match &raw {
+            RawObservablesGroup::OG1(obg1) => {
+                Some(obg1.uptime.duration(&obg1.clkfreq).as_secs_f64())
+            }
+            _ => None,
+        };
+        return (
+            Some((source, recipient, id)),
+            Decoded::Observables {
+                group: format!("{:?}", raw),
+            },
+            uptime,
+        );
+    }
+    match ack_parser(payload) {
+        Ok((_, Acknowledgement::Ack(header))) => (
+            Some((header.source, header.recipient, header.id)),
+            Decoded::Ack,
+            None,
+        ),
+        Ok((_, Acknowledgement::Nak(header))) => (
+            Some((header.source, header.recipient, header.id)),
+            Decoded::Nak,
+            None,
+        ),
+        Err(_) => (None, Decoded::Unknown, None),
+    }
+}
+
+impl Transcript {
+    pub fn decode(data: &[u8], profile: Profile) -> Self {
+        let mut transcript = Self::default();
+        let mut parser = SentenceParser::new();
+        let mut uptime = None;
+        let mut start = 0;
+        for (offset, byte) in data.iter().enumerate() {
+            if *byte == b'$' {
+                start = offset;
+            }
+            let mut sentence: Option<Vec<u8>> = None;
+            if parser
+                .feed(&[*byte], |s| sentence = Some(s.to_vec()))
+                .is_err()
+            {
+                transcript.push(start, uptime, "", None, Decoded::Overflow);
+            }
+            if let Some(sentence) = sentence {
+                let text = String::from_utf8_lossy(&sentence).trim_end().to_string();
+                let (address, decoded) = match verify_nmea_format(&sentence) {
+                    Ok(payload) => {
+                        let (address, decoded, obg_uptime) = decode_payload(payload, profile);
+                        uptime = obg_uptime.or(uptime);
+                        (address, decoded)
+                    }
+                    Err(NMEAFormatError::ChecksumError) => (None, Decoded::ChecksumFailure),
+                    Err(err) => (
+                        None,
+                        Decoded::Malformed {
+                            reason: format!("{:?}", err),
+                        },
+                    ),
+                };
+                transcript.push(start, uptime, &text, address, decoded);
+            }
+        }
+        transcript.pair();
+        transcript
+    }
+
+    fn push(
+        &mut self,
+        offset: usize,
+        uptime: Option<f64>,
+        sentence: &str,
+        address: Option<(Node, Node, usize)>,
+        decoded: Decoded,
+    ) {
+        self.entries.push(Entry {
+            offset,
+            uptime,
+            sentence: sentence.into(),
+            source: address.map(|a| a.0),
+            recipient: address.map(|a| a.1),
+            id: address.map(|a| a.2),
+            decoded,
+            paired_with: None,
+        });
+    }
+
+    // Responses cross over source and recipient of the command.
+    // Ids get reused, so only the most recent command is pending.
+    fn pair(&mut self) {
+        let mut pending: HashMap<(Node, Node, usize), usize> = HashMap::new();
+        for index in 0..self.entries.len() {
+            let entry = &self.entries[index];
+            let (source, recipient, id) = match (entry.source, entry.recipient, entry.id) {
+                (Some(source), Some(recipient), Some(id)) => (source, recipient, id),
+                _ => continue,
+            };
+            match entry.decoded {
+                Decoded::Command { .. } => {
+                    pending.insert((source, recipient, id), index);
+                }
+                Decoded::Ack | Decoded::Nak | Decoded::Observables { .. } => {
+                    if let Some(command) = pending.remove(&(recipient, source, id)) {
+                        self.entries[command].paired_with = Some(index);
+                        self.entries[index].paired_with = Some(command);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // As rqdecode prints it
+    pub fn write_text(&self, out: &mut impl Write, recording: &Path) -> std::io::Result<()> {
+        if let Some(start) = recording_start(recording) {
+            writeln!(out, "Recording started {}", start)?;
+        }
+        for (index, entry) in self.entries.iter().enumerate() {
+            writeln!(out, "#{:<5} {}", index, entry)?;
+        }
+        writeln!(out, "{}", self.summary())
+    }
+
+    pub fn summary(&self) -> Summary {
+        let mut summary = Summary::default();
+        for entry in self.entries.iter() {
+            summary.sentences += 1;
+            match entry.decoded {
+                Decoded::ChecksumFailure => summary.checksum_failures += 1,
+                Decoded::Malformed { .. } | Decoded::Overflow => summary.malformed += 1,
+                Decoded::Unknown => summary.unknown += 1,
+                Decoded::Command { .. } if entry.paired_with.is_none() => {
+                    summary.unpaired_commands += 1
+                }
+                Decoded::Ack | Decoded::Nak | Decoded::Observables { .. }
+                    if entry.paired_with.is_none() =>
+                {
+                    summary.unpaired_responses += 1
+                }
+                _ => summary.paired += 1,
+            }
+        }
+        summary
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.uptime {
+            Some(uptime) => write!(f, "{:>8} {:>10.3}s ", self.offset, uptime)?,
+            None => write!(f, "{:>8} {:>11} ", self.offset, "-")?,
+        }
+        let decoded = match &self.decoded {
+            Decoded::Command { command } => format!("CMD {}", command),
+            Decoded::Ack => "ACK".into(),
+            Decoded::Nak => "NAK".into(),
+            Decoded::Observables { group } => format!("OBG {}", group),
+            Decoded::Unknown => "UNKNOWN".into(),
+            Decoded::ChecksumFailure => "CHECKSUM FAILURE".into(),
+            Decoded::Malformed { reason } => format!("MALFORMED {}", reason),
+            Decoded::Overflow => "OVERFLOW".into(),
+        };
+        write!(f, "{:<48} {}", self.sentence, decoded)?;
+        if let Some(paired) = self.paired_with {
+            write!(f, " <-> #{}", paired)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} sentences, {} checksum failures, {} malformed, {} unknown, {} paired, {} unpaired commands, {} unpaired responses",
+            self.sentences,
+            self.checksum_failures,
+            self.malformed,
+            self.unknown,
+            self.paired,
+            self.unpaired_commands,
+            self.unpaired_responses
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rqparser::NMEAFormatter;
+
+    fn sentence(payload: &[u8]) -> Vec<u8> {
+        let mut formatter = NMEAFormatter::default();
+        formatter.format_sentence(payload).unwrap();
+        formatter.buffer().unwrap().to_vec()
+    }
+
+    #[test]
+    fn test_decode_and_pair() {
+        let mut recording = b"garbage".to_vec();
+        recording.extend(sentence(b"LNCCMD,001,RQB,PING"));
+        recording.extend(sentence(b"RQBACK,001,LNC"));
+        recording.extend(b"$RQBACK,002,LNC*00\r\n");
+        recording.extend(sentence(b"RQBACK,003,LNC"));
+        recording.extend(sentence(b"RQBFOO"));
+        let transcript = Transcript::decode(&recording, Profile::Rocket);
+        let entries = &transcript.entries;
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[0].offset, 7);
+        assert_eq!(entries[0].paired_with, Some(1));
+        assert_eq!(entries[1].decoded, Decoded::Ack);
+        assert_eq!(entries[1].paired_with, Some(0));
+        assert_eq!(entries[2].decoded, Decoded::ChecksumFailure);
+        assert_eq!(entries[4].decoded, Decoded::Unknown);
+        let summary = transcript.summary();
+        assert_eq!(summary.paired, 2);
+        assert_eq!(summary.checksum_failures, 1);
+        assert_eq!(summary.unpaired_responses, 1);
+    }
+
+    #[test]
+    fn test_recording_start() {
+        assert_eq!(
+            recording_start(Path::new("/tmp/2024-05-01_13-37-rqa.log"))
+                .unwrap()
+                .to_string(),
+            "2024-05-01 13:37:00 UTC"
+        );
+        assert!(recording_start(Path::new("capture.log")).is_none());
+    }
+}
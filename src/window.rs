@@ -0,0 +1,309 @@
+// The launch window the range gave us, with the holds planned in it,
+// e.g. for an aircraft passing. Key AB and the ignition are only sent
+// while the window is open and we aren't holding, once it closes we
+// hold for good. When it's about to close, the crew is warned. Times
+// are of the wall clock, disciplined by GPS if gpsd is configured.
+use std::time::Duration;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer};
+
+const DEFAULT_WARN_BEFORE_CLOSE_S: u64 = 300;
+
+fn rfc3339<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&text)
+        .map(|at| at.into())
+        .map_err(serde::de::Error::custom)
+}
+
+fn default_warn_before_close_s() -> u64 {
+    DEFAULT_WARN_BEFORE_CLOSE_S
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HoldPoint {
+    #[serde(deserialize_with = "rfc3339")]
+    pub at: DateTime<Utc>,
+    pub duration_s: u64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LaunchWindowConfig {
+    #[serde(deserialize_with = "rfc3339")]
+    pub open: DateTime<Utc>,
+    #[serde(deserialize_with = "rfc3339")]
+    pub close: DateTime<Utc>,
+    #[serde(default)]
+    pub holds: Vec<HoldPoint>,
+    #[serde(default = "default_warn_before_close_s")]
+    pub warn_before_close_s: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowStatus {
+    Pending {
+        opens_in: Duration,
+    },
+    Open {
+        closes_in: Duration,
+        closing: bool,
+    },
+    Hold {
+        reason: String,
+        resumes_in: Duration,
+    },
+    Closed,
+}
+
+#[derive(Debug)]
+pub struct LaunchWindow {
+    config: LaunchWindowConfig,
+    status: Option<WindowStatus>,
+    warned: bool,
+}
+
+// As 01:02:03
+pub fn countdown_text(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn until(now: DateTime<Utc>, then: DateTime<Utc>) -> Duration {
+    (then - now).to_std().unwrap_or_default()
+}
+
+impl HoldPoint {
+    fn end(&self) -> DateTime<Utc> {
+        self.at + chrono::Duration::seconds(self.duration_s as i64)
+    }
+}
+
+impl LaunchWindowConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.open >= self.close {
+            return Err(anyhow!("launch window closes before it opens"));
+        }
+        for hold in self.holds.iter() {
+            if hold.duration_s == 0 || hold.reason.trim().is_empty() {
+                return Err(anyhow!("hold at {} needs a duration and reason", hold.at));
+            }
+            if hold.at < self.open || hold.end() > self.close {
+                return Err(anyhow!("hold at {} is outside the window", hold.at));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn status(&self, now: DateTime<Utc>) -> WindowStatus {
+        if now < self.open {
+            return WindowStatus::Pending {
+                opens_in: until(now, self.open),
+            };
+        }
+        if now >= self.close {
+            return WindowStatus::Closed;
+        }
+        if let Some(hold) = self
+            .holds
+            .iter()
+            .find(|hold| hold.at <= now && now < hold.end())
+        {
+            return WindowStatus::Hold {
+                reason: hold.reason.clone(),
+                resumes_in: until(now, hold.end()),
+            };
+        }
+        let closes_in = until(now, self.close);
+        WindowStatus::Open {
+            closes_in,
+            closing: closes_in <= Duration::from_secs(self.warn_before_close_s),
+        }
+    }
+}
+
+impl WindowStatus {
+    pub fn allows_launch(&self) -> bool {
+        matches!(self, WindowStatus::Open { .. })
+    }
+
+    pub fn text(&self) -> String {
+        match self {
+            WindowStatus::Pending { opens_in } => {
+                format!("Window opens in {}", countdown_text(*opens_in))
+            }
+            WindowStatus::Open { closes_in, .. } => {
+                format!("Window open, closes in {}", countdown_text(*closes_in))
+            }
+            WindowStatus::Hold { reason, resumes_in } => {
+                format!(
+                    "HOLD {}, resumes in {}",
+                    reason,
+                    countdown_text(*resumes_in)
+                )
+            }
+            WindowStatus::Closed => "Window closed, HOLD".into(),
+        }
+    }
+
+    fn same_phase(&self, other: &WindowStatus) -> bool {
+        match (self, other) {
+            (WindowStatus::Hold { reason: a, .. }, WindowStatus::Hold { reason: b, .. }) => a == b,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl LaunchWindow {
+    pub fn new(config: LaunchWindowConfig) -> Self {
+        Self {
+            config,
+            status: None,
+            warned: false,
+        }
+    }
+
+    pub fn config(&self) -> &LaunchWindowConfig {
+        &self.config
+    }
+
+    // What changed since the last update, for the journal
+    pub fn update(&mut self, now: DateTime<Utc>) -> Vec<String> {
+        let status = self.config.status(now);
+        let mut events = vec![];
+        let changed = self
+            .status
+            .as_ref()
+            .map_or(true, |last| !last.same_phase(&status));
+        if changed {
+            events.push(match &status {
+                WindowStatus::Pending { .. } => status.text(),
+                WindowStatus::Open { .. } => "Launch window open".into(),
+                WindowStatus::Hold { reason, resumes_in } => {
+                    format!("Hold for {}s: {}", resumes_in.as_secs(), reason)
+                }
+                WindowStatus::Closed => "Launch window closed, holding".into(),
+            });
+        }
+        if let WindowStatus::Open {
+            closes_in,
+            closing: true,
+        } = status
+        {
+            if !self.warned {
+                self.warned = true;
+                events.push(format!(
+                    "Launch window closes in {}",
+                    countdown_text(closes_in)
+                ));
+            }
+        }
+        self.status = Some(status);
+        events
+    }
+
+    // Before the first update nothing is allowed
+    pub fn status(&self) -> Option<&WindowStatus> {
+        self.status.as_ref()
+    }
+
+    pub fn allows_launch(&self) -> bool {
+        self.status
+            .as_ref()
+            .map_or(false, WindowStatus::allows_launch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().into()
+    }
+
+    const CONFIG: &str = r#"{
+        "open": "2026-10-17T09:00:00Z",
+        "close": "2026-10-17T11:00:00Z",
+        "holds": [{"at": "2026-10-17T09:30:00Z", "duration_s": 600, "reason": "Aircraft"}]
+    }"#;
+
+    #[test]
+    fn test_window_status() {
+        let config: LaunchWindowConfig = serde_json::from_str(CONFIG).unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.status(at("2026-10-17T08:58:30Z")),
+            WindowStatus::Pending {
+                opens_in: Duration::from_secs(90)
+            }
+        );
+        assert!(config.status(at("2026-10-17T09:00:00Z")).allows_launch());
+        let hold = config.status(at("2026-10-17T09:35:00Z"));
+        assert_eq!(hold.text(), "HOLD Aircraft, resumes in 00:05:00");
+        assert!(!hold.allows_launch());
+        assert_eq!(
+            config.status(at("2026-10-17T10:56:00Z")),
+            WindowStatus::Open {
+                closes_in: Duration::from_secs(240),
+                closing: true
+            }
+        );
+        assert_eq!(
+            config.status(at("2026-10-17T11:00:00Z")),
+            WindowStatus::Closed
+        );
+        let mut invalid = config.clone();
+        invalid.holds[0].duration_s = 2 * 3600;
+        assert!(invalid.validate().is_err());
+        assert!(serde_json::from_str::<LaunchWindowConfig>(
+            r#"{"open": "tomorrow", "close": "2026-10-17T11:00:00Z"}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_transitions_are_journaled_once() {
+        let config: LaunchWindowConfig = serde_json::from_str(CONFIG).unwrap();
+        let mut window = LaunchWindow::new(config);
+        assert!(!window.allows_launch());
+        assert_eq!(
+            window.update(at("2026-10-17T08:59:00Z")),
+            vec!["Window opens in 00:01:00"]
+        );
+        assert!(window.update(at("2026-10-17T08:59:30Z")).is_empty());
+        assert_eq!(
+            window.update(at("2026-10-17T09:00:01Z")),
+            vec!["Launch window open"]
+        );
+        assert!(window.allows_launch());
+        assert_eq!(
+            window.update(at("2026-10-17T09:30:00Z")),
+            vec!["Hold for 600s: Aircraft"]
+        );
+        assert!(!window.allows_launch());
+        assert_eq!(
+            window.update(at("2026-10-17T09:40:00Z")),
+            vec!["Launch window open"]
+        );
+        assert_eq!(
+            window.update(at("2026-10-17T10:55:00Z")),
+            vec!["Launch window closes in 00:05:00"]
+        );
+        assert!(window.update(at("2026-10-17T10:56:00Z")).is_empty());
+        assert_eq!(
+            window.update(at("2026-10-17T11:00:00Z")),
+            vec!["Launch window closed, holding"]
+        );
+        assert!(!window.allows_launch());
+    }
+}